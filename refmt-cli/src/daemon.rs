@@ -0,0 +1,623 @@
+//! Long-running daemon speaking JSON-RPC 2.0 over stdio or a Unix socket
+//!
+//! The daemon keeps compiled transformers and the refmt.toml they were built from warm
+//! between requests, so editor integrations don't pay process startup and regex
+//! compilation on every save, and get the results the CLI would. Requests and
+//! responses are newline-delimited JSON objects.
+//!
+//! Supported methods:
+//! - `transform`: transform a content buffer and return the result
+//! - `process`: transform a file or directory on disk
+//! - `planRenames`: compute file renames without applying them
+//! - `shutdown`: stop the daemon after responding
+
+use log::{debug, info, warn};
+use refmt_core::vfs::{self, Fs};
+use refmt_core::walker::WalkOptions;
+use refmt_core::{
+    CaseConverter, CaseFormat, CaseTransform, Config, EmojiOptions, EmojiTransformer,
+    ExtensionSet, FileRenamer, FilterOptions, ParseCaseFormatError, RenameOptions, SpaceReplace,
+    WhitespaceCleaner, WhitespaceOptions,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+/// A JSON-RPC error object
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_params(message: impl Into<String>) -> Self {
+        RpcError::new(INVALID_PARAMS, message)
+    }
+
+    /// Whether the request was valid but the daemon failed to carry it out
+    #[cfg(feature = "server")]
+    pub fn is_server_error(&self) -> bool {
+        self.code == SERVER_ERROR
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(e: anyhow::Error) -> Self {
+        RpcError::new(SERVER_ERROR, e.to_string())
+    }
+}
+
+impl From<refmt_core::Error> for RpcError {
+    fn from(e: refmt_core::Error) -> Self {
+        match e.root() {
+            refmt_core::Error::Invalid(_) => RpcError::invalid_params(e.to_string()),
+            _ => RpcError::new(SERVER_ERROR, e.to_string()),
+        }
+    }
+}
+
+/// Content transformations that can be requested
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Operation {
+    Emojis,
+    Clean,
+    Convert,
+}
+
+fn default_operations() -> Vec<Operation> {
+    vec![Operation::Emojis, Operation::Clean]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct TransformParams {
+    content: String,
+    /// The file the buffer belongs to, so it's cleaned like that file (Markdown keeps
+    /// its hard line breaks)
+    path: Option<PathBuf>,
+    #[serde(default = "default_operations")]
+    operations: Vec<Operation>,
+    from: Option<String>,
+    to: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ProcessParams {
+    path: PathBuf,
+    #[serde(default = "default_operations")]
+    operations: Vec<Operation>,
+    from: Option<String>,
+    to: Option<String>,
+    #[serde(default = "default_true")]
+    recursive: bool,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize)]
+struct PlanRenamesParams {
+    path: PathBuf,
+    case: Option<String>,
+    separator: Option<String>,
+    add_prefix: Option<String>,
+    remove_prefix: Option<String>,
+    add_suffix: Option<String>,
+    remove_suffix: Option<String>,
+    #[serde(default = "default_true")]
+    recursive: bool,
+}
+
+/// Per-buffer transformation counts
+#[derive(Debug, Default)]
+struct ContentStats {
+    emoji_changes: usize,
+    whitespace_lines: usize,
+    converted: bool,
+}
+
+impl ContentStats {
+    fn to_json(&self) -> Value {
+        json!({
+            "emoji_changes": self.emoji_changes,
+            "whitespace_lines": self.whitespace_lines,
+            "converted": self.converted,
+        })
+    }
+}
+
+/// Resolves the `from`/`to` parameters when a conversion is requested
+fn conversion_formats(
+    operations: &[Operation],
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<Option<(CaseFormat, CaseFormat)>, RpcError> {
+    if !operations.contains(&Operation::Convert) {
+        return Ok(None);
+    }
+
+    let (from, to) = match (from, to) {
+        (Some(from), Some(to)) => (from, to),
+        _ => {
+            return Err(RpcError::invalid_params(
+                "'convert' requires both 'from' and 'to' formats",
+            ))
+        }
+    };
+
+    let from_format: CaseFormat = from
+        .parse()
+        .map_err(|e: ParseCaseFormatError| RpcError::invalid_params(e.to_string()))?;
+    let to_format: CaseFormat = to
+        .parse()
+        .map_err(|e: ParseCaseFormatError| RpcError::invalid_params(e.to_string()))?;
+
+    Ok(Some((from_format, to_format)))
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::invalid_params(e.to_string()))
+}
+
+/// Daemon state: transformers are built once and reused across requests
+pub struct Daemon {
+    emoji: EmojiTransformer,
+    cleaner: WhitespaceCleaner,
+    converters: HashMap<(CaseFormat, CaseFormat), CaseConverter>,
+    fs: Arc<dyn Fs>,
+    walk: WalkOptions,
+    /// Extensions, emoji and rename settings and abbreviations from refmt.toml
+    config: Config,
+    /// The excludes every transformer applies
+    filter: FilterOptions,
+}
+
+impl Daemon {
+    /// Creates a daemon with default transformer options
+    pub fn new() -> Self {
+        Self::with_fs(vfs::real())
+    }
+
+    /// Creates a daemon that processes the files of `fs`
+    pub fn with_fs(fs: Arc<dyn Fs>) -> Self {
+        Self::with_config(fs, Config::default(), Vec::new())
+    }
+
+    /// Creates a daemon that processes the files of `fs` with the settings of
+    /// refmt.toml, leaving out the files `exclude` matches
+    pub fn with_config(fs: Arc<dyn Fs>, config: Config, exclude: Vec<glob::Pattern>) -> Self {
+        let filter = FilterOptions {
+            exclude,
+            ..FilterOptions::default()
+        };
+        let mut emoji = EmojiOptions {
+            fs: fs.clone(),
+            filter: filter.clone(),
+            file_extensions: config.extensions(ExtensionSet::Emojis),
+            mappings: config.emoji_mappings(),
+            ..EmojiOptions::default()
+        };
+        if let Some(replace_task) = config.emojis.replace_task {
+            emoji.replace_task_emojis = replace_task;
+        }
+        if let Some(remove_other) = config.emojis.remove_other {
+            emoji.remove_other_emojis = remove_other;
+        }
+        let cleaner = WhitespaceOptions {
+            fs: fs.clone(),
+            filter: filter.clone(),
+            file_extensions: config.extensions(ExtensionSet::Whitespace),
+            ..WhitespaceOptions::default()
+        };
+
+        Daemon {
+            emoji: EmojiTransformer::new(emoji),
+            cleaner: WhitespaceCleaner::new(cleaner),
+            converters: HashMap::new(),
+            fs,
+            walk: WalkOptions::default(),
+            config,
+            filter,
+        }
+    }
+
+    /// Limits the recursive walks of `process` and `planRenames` requests
+    pub fn with_walk(mut self, walk: WalkOptions) -> Self {
+        self.walk = walk;
+        self
+    }
+
+    /// Returns a cached converter for the given formats, compiling it on first use
+    fn converter(&mut self, from: CaseFormat, to: CaseFormat) -> anyhow::Result<&CaseConverter> {
+        use std::collections::hash_map::Entry;
+
+        match self.converters.entry((from, to)) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                debug!("Compiling converter {:?} -> {:?}", from, to);
+                let converter = CaseConverter::builder(from, to)
+                    .extensions(self.config.extensions(ExtensionSet::Convert))
+                    .abbreviations(self.config.abbreviations())
+                    .filter(self.filter.clone())
+                    .fs(self.fs.clone())
+                    .build()?;
+                Ok(entry.insert(converter))
+            }
+        }
+    }
+
+    /// Serves newline-delimited requests until EOF or a `shutdown` request.
+    ///
+    /// Returns `true` if the client asked the daemon to shut down.
+    pub fn serve<R: BufRead, W: Write>(&mut self, reader: R, mut writer: W) -> anyhow::Result<bool> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (response, shutdown) = self.handle_line(&line);
+            if let Some(response) = response {
+                writeln!(writer, "{}", response)?;
+                writer.flush()?;
+            }
+            if shutdown {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Handles one request line, returning the response (if any) and whether to shut down
+    fn handle_line(&mut self, line: &str) -> (Option<Value>, bool) {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => {
+                return (
+                    Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string()))),
+                    false,
+                )
+            }
+        };
+
+        // Requests without an id are notifications and get no response
+        let id = request.get("id").cloned();
+        let method = request.get("method").and_then(Value::as_str);
+
+        let method = match (request.get("jsonrpc").and_then(Value::as_str), method) {
+            (Some("2.0"), Some(method)) => method.to_string(),
+            _ => {
+                let error = RpcError::new(INVALID_REQUEST, "Expected a JSON-RPC 2.0 request");
+                return (Some(error_response(id.unwrap_or(Value::Null), error)), false);
+            }
+        };
+
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+        debug!("Daemon request: {}", method);
+
+        let shutdown = method == "shutdown";
+        let result = self.dispatch(&method, params);
+
+        let response = id.map(|id| match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => error_response(id, error),
+        });
+
+        (response, shutdown)
+    }
+
+    /// Invokes a method directly, bypassing JSON-RPC framing
+    pub fn dispatch(&mut self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "transform" => self.transform(parse_params(params)?),
+            "process" => self.process(parse_params(params)?),
+            "planRenames" => self.plan_renames(parse_params(params)?),
+            "shutdown" => Ok(Value::Null),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method '{}'", method),
+            )),
+        }
+    }
+
+    /// Runs the requested operations over a buffer, the content of `path` if known
+    fn apply(
+        &mut self,
+        path: Option<&Path>,
+        content: &str,
+        operations: &[Operation],
+        formats: Option<(CaseFormat, CaseFormat)>,
+    ) -> anyhow::Result<(Option<String>, ContentStats)> {
+        let mut stats = ContentStats::default();
+        // Stays `None` until an operation actually changes the buffer
+        let mut modified: Option<String> = None;
+
+        for operation in operations {
+            let current = modified.as_deref().unwrap_or(content);
+            let result = match operation {
+                Operation::Emojis => {
+                    let (transformed, changes) = self.emoji.transform_str(current);
+                    stats.emoji_changes += changes;
+                    transformed
+                }
+                Operation::Clean => {
+                    let (cleaned, lines) = match path {
+                        Some(path) => self.cleaner.clean_path_str(path, current),
+                        None => self.cleaner.clean_str(current),
+                    };
+                    stats.whitespace_lines += lines;
+                    cleaned
+                }
+                Operation::Convert => match formats {
+                    Some((from, to)) => {
+                        let converted = self.converter(from, to)?.convert_text(current);
+                        stats.converted |= matches!(converted, Cow::Owned(_));
+                        converted
+                    }
+                    None => Cow::Borrowed(current),
+                },
+            };
+
+            if let Cow::Owned(result) = result {
+                modified = Some(result);
+            }
+        }
+
+        Ok((modified, stats))
+    }
+
+    fn transform(&mut self, params: TransformParams) -> Result<Value, RpcError> {
+        let formats =
+            conversion_formats(&params.operations, params.from.as_deref(), params.to.as_deref())?;
+        // A buffer of an excluded file is left alone, as the CLI would leave the file
+        let excluded = params
+            .path
+            .as_deref()
+            .is_some_and(|path| self.filter.skip_reason(path).is_some());
+        let operations = if excluded { &[][..] } else { &params.operations[..] };
+        let (modified, stats) =
+            self.apply(params.path.as_deref(), &params.content, operations, formats)?;
+        let changed = modified.is_some();
+
+        Ok(json!({
+            "content": modified.unwrap_or(params.content),
+            "changed": changed,
+            "stats": stats.to_json(),
+        }))
+    }
+
+    fn process(&mut self, params: ProcessParams) -> Result<Value, RpcError> {
+        let formats =
+            conversion_formats(&params.operations, params.from.as_deref(), params.to.as_deref())?;
+        let (files, base) =
+            collect_files(self.fs.as_ref(), &params.path, params.recursive, self.walk)?;
+
+        let mut results = Vec::new();
+        for file in files {
+            // Only run the operations whose filters accept this file
+            let operations: Vec<Operation> = params
+                .operations
+                .iter()
+                .copied()
+                .filter(|op| match op {
+                    Operation::Emojis => self.emoji.should_process(&file),
+                    Operation::Clean => self.cleaner.should_process(&file),
+                    Operation::Convert => match formats {
+                        Some((from, to)) => self
+                            .converter(from, to)
+                            .map(|c| c.should_process(&file, &base))
+                            .unwrap_or(false),
+                        None => false,
+                    },
+                })
+                .collect();
+
+            if operations.is_empty() {
+                continue;
+            }
+
+            let content = self.fs.read(&file).map_err(|e| e.to_string());
+            let content = content.and_then(|b| String::from_utf8(b).map_err(|e| e.to_string()));
+            let content = match content {
+                Ok(content) => content,
+                Err(e) => {
+                    results.push(json!({ "path": file, "error": e }));
+                    continue;
+                }
+            };
+
+            let (transformed, stats) = self.apply(Some(&file), &content, &operations, formats)?;
+            let Some(transformed) = transformed else {
+                continue;
+            };
+
+            // A file that can't be written fails on its own, not the whole request
+            if !params.dry_run {
+                if let Err(e) = self.fs.write(&file, transformed.as_bytes()) {
+                    results.push(json!({ "path": file, "error": e.to_string() }));
+                    continue;
+                }
+            }
+            results.push(json!({ "path": file, "changed": true, "stats": stats.to_json() }));
+        }
+
+        Ok(json!({
+            "dry_run": params.dry_run,
+            "files_changed": results.iter().filter(|r| r.get("changed").is_some()).count(),
+            "files": results,
+        }))
+    }
+
+    fn plan_renames(&mut self, params: PlanRenamesParams) -> Result<Value, RpcError> {
+        let mut options = RenameOptions {
+            recursive: params.recursive,
+            walk: self.walk,
+            dry_run: true,
+            case_transform: match params.case.as_deref() {
+                None => self.config.rename.case.unwrap_or(CaseTransform::None),
+                Some("lowercase") => CaseTransform::Lowercase,
+                Some("uppercase") => CaseTransform::Uppercase,
+                Some("capitalize") => CaseTransform::Capitalize,
+                Some(other) => {
+                    return Err(RpcError::invalid_params(format!("Unknown case '{}'", other)))
+                }
+            },
+            space_replace: match params.separator.as_deref() {
+                None => self.config.rename.spaces.unwrap_or(SpaceReplace::None),
+                Some("underscore") => SpaceReplace::Underscore,
+                Some("hyphen") => SpaceReplace::Hyphen,
+                Some(other) => {
+                    return Err(RpcError::invalid_params(format!(
+                        "Unknown separator '{}'",
+                        other
+                    )))
+                }
+            },
+            add_prefix: params.add_prefix,
+            remove_prefix: params.remove_prefix,
+            add_suffix: params.add_suffix,
+            remove_suffix: params.remove_suffix,
+            file_extensions: self.config.extensions.rename.clone(),
+            filter: self.filter.clone(),
+            ..RenameOptions::default()
+        };
+
+        options.fs = self.fs.clone();
+
+        if !self.fs.exists(&params.path) {
+            return Err(RpcError::invalid_params(format!(
+                "Path '{}' does not exist",
+                params.path.display()
+            )));
+        }
+
+        let renamer = FileRenamer::new(options);
+        let plan = renamer.plan(&params.path)?;
+        for e in renamer.take_walk_errors() {
+            warn!("Could not read {}", e);
+        }
+
+        Ok(json!({ "renames": plan }))
+    }
+}
+
+impl Default for Daemon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+/// Lists the files under `path`, returning them with the base directory used for glob matching
+fn collect_files(
+    fs: &dyn Fs,
+    path: &Path,
+    recursive: bool,
+    walk: WalkOptions,
+) -> Result<(Vec<PathBuf>, PathBuf), RpcError> {
+    if fs.is_file(path) {
+        let base = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        return Ok((vec![path.to_path_buf()], base));
+    }
+
+    if !fs.is_dir(path) {
+        return Err(RpcError::invalid_params(format!(
+            "Path '{}' does not exist",
+            path.display()
+        )));
+    }
+
+    let found = fs.walk(path, recursive, walk)?;
+    for e in found.errors {
+        warn!("Could not read {}", e);
+    }
+
+    Ok((found.files, path.to_path_buf()))
+}
+
+/// Runs `daemon` on stdin/stdout
+pub fn serve_stdio(mut daemon: Daemon) -> anyhow::Result<()> {
+    info!("refmt daemon listening on stdio");
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    daemon.serve(stdin.lock(), stdout.lock())?;
+    Ok(())
+}
+
+/// Runs `daemon` on a Unix domain socket, serving one connection at a time
+#[cfg(unix)]
+pub fn serve_socket(path: &Path, mut daemon: Daemon) -> anyhow::Result<()> {
+    use std::io::BufReader;
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixListener;
+
+    // Remove a stale socket from a previous run, but never a regular file
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if metadata.file_type().is_socket() {
+            fs::remove_file(path)?;
+        } else {
+            anyhow::bail!("'{}' exists and is not a socket", path.display());
+        }
+    }
+
+    let listener = UnixListener::bind(path)?;
+    info!("refmt daemon listening on {}", path.display());
+
+    // A failing connection is logged and dropped; the daemon keeps accepting others
+    for stream in listener.incoming() {
+        let stream = match stream.and_then(|s| Ok((s.try_clone()?, s))) {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Failed to accept a connection: {}", e);
+                continue;
+            }
+        };
+        let (reader, writer) = stream;
+        match daemon.serve(BufReader::new(reader), writer) {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(e) => warn!("Connection failed: {}", e),
+        }
+    }
+
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+/// Unix sockets are not available on this platform
+#[cfg(not(unix))]
+pub fn serve_socket(path: &Path, _daemon: Daemon) -> anyhow::Result<()> {
+    anyhow::bail!(
+        "Socket mode is not supported on this platform ('{}'); use stdio instead",
+        path.display()
+    )
+}
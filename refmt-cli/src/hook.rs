@@ -0,0 +1,74 @@
+//! `refmt install-hook`: a git pre-commit hook checking the staged files
+//!
+//! The hook pipes the staged content of each staged file (`git show :FILE`, not the
+//! working tree copy) to `refmt clean --check -` and `refmt emojis --check -`, and
+//! fails the commit if either would change it. Teams using the pre-commit framework,
+//! which stashes unstaged changes itself, get the same checks as a
+//! `.pre-commit-config.yaml` snippet instead.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use refmt_core::git;
+
+/// Marks hooks written by refmt, which may be replaced without `--force`
+const MARKER: &str = "# Installed by `refmt install-hook`";
+
+/// The pre-commit hook; checks the staged content of each staged file
+pub const PRE_COMMIT_HOOK: &str = r#"#!/bin/sh
+# Installed by `refmt install-hook`
+# Fails the commit when the staged content of a file has trailing whitespace or emojis;
+# fix them with `refmt clean <file>` and `refmt emojis <file>`, then stage them again.
+status=0
+while IFS= read -r file; do
+    # Submodules have no content to check
+    [ -d "$file" ] && continue
+    git show ":$file" | refmt clean --check --stdin-filename "$file" - || status=1
+    git show ":$file" | refmt emojis --check --stdin-filename "$file" - || status=1
+done <<EOF
+$(git diff --cached --name-only --diff-filter=ACMR)
+EOF
+exit $status
+"#;
+
+/// Hooks for the pre-commit framework's `.pre-commit-config.yaml`
+pub const PRE_COMMIT_CONFIG: &str = r#"repos:
+  - repo: local
+    hooks:
+      - id: refmt-clean
+        name: refmt clean
+        entry: sh -c 'status=0; for f in "$@"; do refmt clean --check "$f" || status=1; done; exit $status' --
+        language: system
+        types: [text]
+      - id: refmt-emojis
+        name: refmt emojis
+        entry: sh -c 'status=0; for f in "$@"; do refmt emojis --check "$f" || status=1; done; exit $status' --
+        language: system
+        types: [text]
+"#;
+
+/// Writes the pre-commit hook of the repository containing `path`, returning its path
+///
+/// An existing hook is only replaced if refmt wrote it or `force` is set.
+pub fn install(path: &Path, force: bool) -> anyhow::Result<PathBuf> {
+    let hooks = git::hooks_dir(path)?;
+    let hook = hooks.join("pre-commit");
+
+    if let Ok(existing) = fs::read_to_string(&hook) {
+        if !existing.contains(MARKER) && !force {
+            anyhow::bail!(
+                "'{}' already exists; pass --force to replace it",
+                hook.display()
+            );
+        }
+    }
+
+    fs::create_dir_all(&hooks)?;
+    fs::write(&hook, PRE_COMMIT_HOOK)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(hook)
+}
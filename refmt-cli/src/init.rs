@@ -0,0 +1,201 @@
+//! `refmt init`: a starter `refmt.toml`
+//!
+//! Every setting is listed, commented out where the built-in default applies, so the
+//! file documents what can be configured. The extension sets are filled in for the
+//! kinds of project found in the directory (a `Cargo.toml`, a `pyproject.toml`, a
+//! `package.json`, ...).
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use refmt_core::config::CONFIG_FILE_NAME;
+use refmt_core::ExtensionSet;
+
+/// A kind of project `init` recognizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectKind {
+    Rust,
+    Python,
+    JavaScript,
+}
+
+impl ProjectKind {
+    const ALL: [ProjectKind; 3] = [
+        ProjectKind::Rust,
+        ProjectKind::Python,
+        ProjectKind::JavaScript,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProjectKind::Rust => "Rust",
+            ProjectKind::Python => "Python",
+            ProjectKind::JavaScript => "JavaScript",
+        }
+    }
+
+    /// Files whose presence identifies the project kind
+    fn markers(self) -> &'static [&'static str] {
+        match self {
+            ProjectKind::Rust => &["Cargo.toml"],
+            ProjectKind::Python => &[
+                "pyproject.toml",
+                "setup.py",
+                "setup.cfg",
+                "requirements.txt",
+            ],
+            ProjectKind::JavaScript => &["package.json", "tsconfig.json"],
+        }
+    }
+
+    /// Extensions for `clean`, `emojis` and `convert`
+    fn extensions(self) -> [&'static [&'static str]; 3] {
+        match self {
+            ProjectKind::Rust => [&[".rs", ".toml", ".md"], &[".md", ".rs"], &[".rs"]],
+            ProjectKind::Python => [
+                &[".py", ".pyi", ".toml", ".cfg", ".md", ".rst", ".txt"],
+                &[".md", ".rst", ".py"],
+                &[".py", ".pyi"],
+            ],
+            ProjectKind::JavaScript => [
+                &[
+                    ".js", ".jsx", ".mjs", ".cjs", ".ts", ".tsx", ".json", ".css", ".html", ".md",
+                ],
+                &[".md", ".js", ".jsx", ".ts", ".tsx"],
+                &[".js", ".jsx", ".mjs", ".ts", ".tsx"],
+            ],
+        }
+    }
+
+    /// The `from` and `to` of the language's usual identifier conversion
+    fn conversion(self) -> (&'static str, &'static str) {
+        match self {
+            ProjectKind::Rust | ProjectKind::Python => ("camel", "snake"),
+            ProjectKind::JavaScript => ("snake", "camel"),
+        }
+    }
+}
+
+/// The kinds of project `dir` holds, from the files at its top level
+pub fn detect(dir: &Path) -> Vec<ProjectKind> {
+    ProjectKind::ALL
+        .into_iter()
+        .filter(|kind| {
+            kind.markers()
+                .iter()
+                .any(|marker| dir.join(marker).is_file())
+        })
+        .collect()
+}
+
+/// A commented `refmt.toml` for a project of the given kinds
+pub fn starter_config(kinds: &[ProjectKind]) -> String {
+    let names: Vec<&str> = kinds.iter().map(|kind| kind.name()).collect();
+    let mut config = String::from(
+        "# Settings for refmt in this project. Command-line flags override them, and\n\
+         # ~/.config/refmt/config.toml holds defaults for all projects.\n",
+    );
+    if !names.is_empty() {
+        let _ = writeln!(config, "# Generated for a {} project.", names.join(" and "));
+    }
+    config.push_str(
+        "\n\
+         # Refuse to modify files while the git repository has uncommitted changes\n\
+         # require_clean_git = true\n\
+         \n\
+         # Words `convert` keeps as written in capitalized formats\n\
+         # abbreviations = [\"ID\", \"URL\", \"HTTP\"]\n\
+         \n\
+         # Files to leave out, on top of --exclude and .refmtignore\n\
+         # exclude = [\"**/generated/**\"]\n\
+         \n\
+         # What `refmt <path>` and `refmt watch` run, in order\n\
+         # steps = [\"rename\", \"emojis\", \"clean\"]\n\
+         # Or a built-in pipeline: markdown-cleanup, code-hygiene, snake-everything\n\
+         # preset = \"code-hygiene\"\n\
+         \n\
+         # The files each subcommand processes\n\
+         [extensions]\n",
+    );
+
+    let mut sets: [Vec<String>; 3] = Default::default();
+    for kind in kinds {
+        for (set, extensions) in sets.iter_mut().zip(kind.extensions()) {
+            for ext in extensions {
+                if !set.iter().any(|e| e == ext) {
+                    set.push(ext.to_string());
+                }
+            }
+        }
+    }
+    // Without a project type, show the built-in sets
+    let comment = if kinds.is_empty() {
+        config.push_str("# No project type detected, so the built-in sets apply\n");
+        sets = [
+            ExtensionSet::Whitespace,
+            ExtensionSet::Emojis,
+            ExtensionSet::Convert,
+        ]
+        .map(ExtensionSet::defaults);
+        "# "
+    } else {
+        ""
+    };
+    for (name, set) in ["clean", "emojis", "convert"].into_iter().zip(&sets) {
+        let quoted: Vec<String> = set.iter().map(|ext| format!("\"{}\"", ext)).collect();
+        let _ = writeln!(config, "{}{} = [{}]", comment, name, quoted.join(", "));
+    }
+    config.push_str("# Files `rename_files` and the rename step touch; all of them when unset\n");
+    config.push_str("# rename = [\".txt\"]\n");
+
+    let (from, to) = kinds
+        .first()
+        .map_or(("camel", "snake"), |kind| kind.conversion());
+    let _ = write!(
+        config,
+        "\n\
+         # Defaults for `refmt convert` without --from and --to\n\
+         [convert]\n\
+         # from = [\"{}\"]\n\
+         # to = \"{}\"\n\
+         # exclude_words = [\"iOS\", \"macOS\"]\n\
+         \n\
+         # Defaults for `refmt emojis`\n\
+         [emojis]\n\
+         # replace_task = true\n\
+         # remove_other = true\n\
+         # mappings = {{ \"🚀\" = \"[launch]\" }}\n\
+         \n\
+         # Defaults for `refmt rename_files` without case or separator flags\n\
+         [rename]\n\
+         # case = \"lowercase\"\n\
+         # spaces = \"underscore\"\n\
+         \n\
+         # Settings applied on top of the others with --profile docs\n\
+         # [profile.docs]\n\
+         # extensions = {{ clean = [\".md\"], emojis = [\".md\"] }}\n",
+        from, to
+    );
+    config
+}
+
+/// Writes a starter `refmt.toml` in `dir`, returning its path and the detected kinds
+///
+/// An existing file is only replaced if `force` is set.
+pub fn write(dir: &Path, force: bool) -> anyhow::Result<(PathBuf, Vec<ProjectKind>)> {
+    if !dir.is_dir() {
+        anyhow::bail!("'{}' is not a directory", dir.display());
+    }
+    let file = dir.join(CONFIG_FILE_NAME);
+    if file.exists() && !force {
+        anyhow::bail!(
+            "'{}' already exists; pass --force to replace it",
+            file.display()
+        );
+    }
+
+    let kinds = detect(dir);
+    fs::write(&file, starter_config(&kinds))?;
+    Ok((file, kinds))
+}
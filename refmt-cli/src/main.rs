@@ -1,19 +1,50 @@
-use clap::{Parser, Subcommand};
+mod daemon;
+mod hook;
+mod init;
+mod output;
+mod pager;
+mod run_report;
+#[cfg(feature = "server")]
+mod server;
+mod watch;
+
+use clap::{Args, Parser, Subcommand};
 use refmt_core::{
-    CaseConverter, CaseFormat, CaseTransform, CombinedOptions, CombinedProcessor, EmojiOptions,
-    EmojiTransformer, FileRenamer, RenameOptions, SpaceReplace, TimestampFormat,
-    WhitespaceCleaner, WhitespaceOptions,
+    config, git, header, journal, memory, report, vfs, walker, AsciiChecker, AsciiOptions, CaseConverter,
+    CaseFormat, CaseTransform, ChangedLines, CharCategory, ClassNameConverter, ClassNameOptions,
+    CombinedOptions, CombinedProcessor, CombinedStep, CommentConverter, CommentOptions, CommentStyle, Config,
+    ConflictResolution, ContentTransform, DigitBoundary, DotenvNormalizer, DotenvOptions,
+    EmojiOptions, EmojiTransformer, EolConverter, EolOptions, ExtensionSet, FailedFile, FieldRenamer,
+    FileRenamer, FilenameChecker, FilenameFix, FilenameOptions, FilterOptions, GuardOptions,
+    GuardRenamer, HardBreaks, HeaderAction, HeaderManager, HeaderOptions, ImportOptions, ImportSorter,
+    IndentOptions, IndentStyle, KeyConverter, KeyFormat, KeyOptions, KeywordCase, LineEnding,
+    NamingChecker, NamingLanguage, NamingOptions, NumberFormatter, NumberOptions, Progress,
+    ProgressEvent, PunctuationNormalizer, PunctuationOptions, QuoteOptions, QuoteStyle,
+    QuoteTransformer, RenameOptions, SchemaOptions, ShebangNormalizer, ShebangOptions, SpaceReplace,
+    SqlFormatter, SqlOptions, StatsOptions, StringQuote, StringQuoteConverter, StringQuoteOptions,
+    TimestampFormat, WhitespaceCleaner, WhitespaceOptions, Abbreviations, CaseCounts, CaseScanner,
+    ConvertStepOptions, FileReport,
 };
 use indicatif::{ProgressBar, ProgressStyle};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use logging_timer::time;
 use simplelog::*;
-use std::path::PathBuf;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Prints a summary line like `println!`, or keeps it for `--output json`
+macro_rules! summary {
+    ($ctx:expr, $($arg:tt)*) => {
+        $ctx.output.message(format!($($arg)*))
+    };
+}
 
 #[derive(Parser)]
 #[command(
     name = "refmt",
-    version = "0.2.0",
+    version,
     about = "Code transformation tool for case conversion and cleaning",
     long_about = "A modular code transformation framework.\n\n\
                   Usage:\n\
@@ -22,8 +53,35 @@ use std::path::PathBuf;
                   Commands:\n\
                   - convert: Convert between case formats\n\
                   - clean: Remove trailing whitespace\n\
+                  - indent: Re-indent files (tabs/spaces, indentation width)\n\
                   - emojis: Remove or replace emojis with text alternatives\n\
-                  - rename_files: Rename files with various transformations"
+                  - rename_files: Rename files with various transformations\n\
+                  - eol: Convert line endings between LF and CRLF\n\
+                  - header: Insert, update or remove license/copyright headers\n\
+                  - guards: Rename C/C++ include guards after their file names\n\
+                  - comments: Convert comment styles and normalize comment banners\n\
+                  - quotes: Straighten or curl typographic quotes in text files\n\
+                  - punctuation: Replace Unicode dashes, ellipses and fullwidth punctuation\n\
+                  - ascii-check: List non-ASCII characters with their positions and categories\n\
+                  - stats: Count identifiers per case format, extension and directory\n\
+                  - numbers: Insert digit separators into long numeric literals\n\
+                  - convert-keys: Convert the case of JSON, YAML and TOML mapping keys\n\
+                  - schema-fields: Rename protobuf fields and JSON Schema properties\n\
+                  - dotenv: Uppercase .env keys and report duplicates\n\
+                  - sql: Normalize the case of SQL keywords\n\
+                  - class-names: Convert CSS class and id names in stylesheets and markup\n\
+                  - string-quotes: Convert JS/TS and Python strings to single or double quotes\n\
+                  - shebang: Normalize shebang lines and the executable bit\n\
+                  - sort-imports: Sort and group #include/import/use lines\n\
+                  - check-names: Check that file names match the identifiers they declare\n\
+                  - naming: Lint or fix naming conventions (--lang rust)\n\
+                  - undo: Revert an earlier run recorded in .refmt/journal\n\
+                  - list-formats: List the case formats and lossy conversions\n\
+                  - watch: Re-run clean, emojis or convert on files as they change\n\
+                  - install-hook: Check staged files with a git pre-commit hook\n\
+                  - init: Write a starter refmt.toml for the detected project type\n\
+                  - daemon: Serve JSON-RPC requests over stdio or a socket\n\
+                  - serve: Serve an HTTP API (requires the `server` feature)"
 )]
 struct Cli {
     #[command(subcommand)]
@@ -41,6 +99,37 @@ struct Cli {
     #[arg(short = 'd', long = "dry-run", requires = "path")]
     dry_run: bool,
 
+    /// Report the files that would change and fail if there are any (when no
+    /// subcommand is specified)
+    #[arg(long, requires = "path", conflicts_with = "dry_run")]
+    check: bool,
+
+    /// Don't ask for confirmation before changing many files (convert, rename_files and
+    /// the default command)
+    #[arg(short = 'y', long, global = true)]
+    yes: bool,
+
+    /// Ask for confirmation before changing at least N files [default: 50, or
+    /// confirm_threshold in refmt.toml]
+    #[arg(long, global = true, value_name = "N")]
+    confirm_threshold: Option<usize>,
+
+    /// Show a unified diff of each changed file, over all steps (when no subcommand is
+    /// specified)
+    #[arg(long, requires = "path")]
+    diff: bool,
+
+    /// Transformations to run, in order (when no subcommand is specified) [default:
+    /// steps from refmt.toml, or rename,emojis,clean]
+    #[arg(long, value_delimiter = ',', value_parser = config::STEPS, requires = "path")]
+    steps: Option<Vec<String>>,
+
+    /// A built-in pipeline (when no subcommand is specified): markdown-cleanup
+    /// (emojis,clean), code-hygiene (clean) or snake-everything (convert camel and pascal
+    /// identifiers to snake, then rename files to lowercase with underscores)
+    #[arg(long, value_parser = config::PRESETS.map(|(name, _)| name), requires = "path", conflicts_with = "steps")]
+    preset: Option<String>,
+
     /// Enable verbose output (can be used multiple times: -v, -vv, -vvv)
     #[arg(short = 'v', long = "verbose", global = true, action = clap::ArgAction::Count)]
     verbose: u8,
@@ -52,64 +141,358 @@ struct Cli {
     /// Write logs to file
     #[arg(long = "log-file", global = true)]
     log_file: Option<PathBuf>,
+
+    /// Don't pipe long dry-run diffs through $PAGER
+    #[arg(long = "no-pager", global = true)]
+    no_pager: bool,
+
+    /// Refuse to modify files while the git repository has uncommitted changes
+    #[arg(long = "require-clean-git", global = true)]
+    require_clean_git: bool,
+
+    /// Modify files even if refmt.toml sets require_clean_git
+    #[arg(long = "allow-dirty", global = true, conflicts_with = "require_clean_git")]
+    allow_dirty: bool,
+
+    /// When to use colors; `auto` honors NO_COLOR and TERM=dumb
+    #[arg(long, global = true, value_parser = ["auto", "always", "never"], default_value = "auto")]
+    color: String,
+
+    /// Whether unreadable directories and files that can't be read or written are
+    /// reported after the run or abort it
+    #[arg(long = "walk-errors", global = true, value_parser = ["warn", "fail"], default_value = "warn")]
+    walk_errors: String,
+
+    /// Retry failed reads, writes and renames up to N times (locked files, network
+    /// shares)
+    #[arg(long, global = true, value_name = "N", default_value_t = 0)]
+    retries: u32,
+
+    /// Milliseconds to wait before the first retry, doubled before each further one
+    #[arg(long = "retry-backoff", global = true, value_name = "MS", default_value_t = 100)]
+    retry_backoff: u64,
+
+    /// Keep the modification time of rewritten files, so build systems don't rebuild
+    /// what they depend on
+    #[arg(long = "preserve-mtime", global = true)]
+    preserve_mtime: bool,
+
+    /// Leave rewritten files with the permissions a fresh write gives them instead of
+    /// restoring their original mode
+    #[arg(long = "no-preserve-permissions", global = true)]
+    no_preserve_permissions: bool,
+
+    /// Don't record the run's changes in .refmt/journal (see `refmt undo`)
+    #[arg(long = "no-journal", global = true)]
+    no_journal: bool,
+
+    /// Runs to keep in .refmt/journal; older ones are deleted when a run is saved
+    #[arg(long = "journal-keep", global = true, value_name = "N", default_value_t = journal::DEFAULT_KEEP)]
+    journal_keep: usize,
+
+    /// Print per-file results and summaries as text, or one JSON summary of the run
+    /// (files changed, per-file change counts, renames, errors) on stdout
+    #[arg(long, global = true, value_parser = ["text", "json"], default_value = "text")]
+    output: String,
+
+    /// Stream one JSON event per processed file on stdout as the run goes (changed,
+    /// renamed, failed), then the summary lines and a final `finished` event
+    #[arg(long, global = true, conflicts_with = "output")]
+    events: bool,
+
+    /// Write a JSON record of the run (per-file actions, warnings and errors, timings,
+    /// configuration and version) to this file
+    #[arg(long = "report-file", global = true, value_name = "FILE")]
+    report_file: Option<PathBuf>,
+
+    /// Check in memory that a second pass would change nothing before writing, and fail
+    /// otherwise (convert, clean, emojis and the default command)
+    #[arg(long = "verify-idempotent", global = true)]
+    verify_idempotent: bool,
+
+    /// Skip files whose name or path matches GLOB, e.g. '**/migrations/**' (repeatable;
+    /// convert, clean, emojis, rename_files and the default command)
+    #[arg(long, global = true, value_name = "GLOB", value_parser = glob::Pattern::new)]
+    exclude: Vec<glob::Pattern>,
+
+    /// Apply the settings of [profile.NAME] in refmt.toml on top of the others
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Descend at most N directory levels in recursive runs (1 = only the files in
+    /// the given directory)
+    #[arg(long = "max-depth", global = true, value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// Follow symbolic links to directories and files in recursive runs; links that
+    /// loop are reported and skipped
+    #[arg(long = "follow-symlinks", global = true)]
+    follow_symlinks: bool,
 }
 
-#[derive(Subcommand)]
-enum Commands {
-    /// Convert between case formats
-    #[command(group(clap::ArgGroup::new("from").required(true).multiple(false)))]
-    #[command(group(clap::ArgGroup::new("to").required(true).multiple(false)))]
-    Convert {
-        /// Convert FROM camelCase
-        #[arg(long = "from-camel", group = "from")]
-        from_camel: bool,
+/// File filter flags shared by the text transformers
+#[derive(Args, Debug, Clone)]
+struct FilterArgs {
+    /// Also process translation files (.po, .xliff, locales/*.json, ...), which are
+    /// skipped by default
+    #[arg(long = "include-localization")]
+    include_localization: bool,
+
+    /// Also descend into hidden directories such as .github and .config (.git is
+    /// always skipped)
+    #[arg(long = "include-hidden-dirs")]
+    include_hidden_dirs: bool,
 
-        /// Convert FROM PascalCase
-        #[arg(long = "from-pascal", group = "from")]
-        from_pascal: bool,
+    /// Also process hidden files such as .editorconfig
+    #[arg(long = "include-hidden-files")]
+    include_hidden_files: bool,
 
-        /// Convert FROM snake_case
-        #[arg(long = "from-snake", group = "from")]
-        from_snake: bool,
+    /// Extensions to remove from the default (or -e) set, e.g. .md,.rst
+    #[arg(long = "exclude-extensions", value_delimiter = ',', value_name = "EXTS")]
+    exclude_extensions: Vec<String>,
 
-        /// Convert FROM SCREAMING_SNAKE_CASE
-        #[arg(long = "from-screaming-snake", group = "from")]
-        from_screaming_snake: bool,
+    /// Start from an empty extension set instead of the defaults, built-in or from
+    /// refmt.toml (use with -e)
+    #[arg(long = "no-default-extensions")]
+    no_default_extensions: bool,
 
-        /// Convert FROM kebab-case
-        #[arg(long = "from-kebab", group = "from")]
-        from_kebab: bool,
+    /// Print the effective extension set and exit
+    #[arg(long = "show-extensions")]
+    show_extensions: bool,
 
-        /// Convert FROM SCREAMING-KEBAB-CASE
-        #[arg(long = "from-screaming-kebab", group = "from")]
-        from_screaming_kebab: bool,
+    /// Also skip directories with this name (repeatable), on top of build, target,
+    /// node_modules, __pycache__, venv and .venv
+    #[arg(long = "skip-dir", value_name = "DIR")]
+    skip_dirs: Vec<String>,
+
+    /// Don't skip the default build and dependency directories (.git is always skipped)
+    #[arg(long = "no-default-skip-dirs")]
+    no_default_skip_dirs: bool,
+}
 
-        /// Convert TO camelCase
-        #[arg(long = "to-camel", group = "to")]
-        to_camel: bool,
+impl FilterArgs {
+    /// The extensions to process: `-e`, or the defaults of `set` from the config file
+    /// or the built-in table, minus exclusions
+    fn extensions(
+        &self,
+        set: ExtensionSet,
+        extensions: Option<Vec<String>>,
+        config: &Config,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut extensions = match extensions {
+            Some(exts) => exts,
+            None if self.no_default_extensions => Vec::new(),
+            None => config.extensions(set),
+        };
+        let filter = self.options(&[]);
+        extensions.retain(|ext| filter.allows_extension(ext));
 
-        /// Convert TO PascalCase
-        #[arg(long = "to-pascal", group = "to")]
-        to_pascal: bool,
+        if extensions.is_empty() && !self.show_extensions {
+            anyhow::bail!("No file extensions to process; add some with -e");
+        }
+        Ok(extensions)
+    }
 
-        /// Convert TO snake_case
-        #[arg(long = "to-snake", group = "to")]
-        to_snake: bool,
+    /// Prints the effective extension set if requested, returning whether it did
+    fn show(&self, extensions: &[String]) -> bool {
+        if self.show_extensions {
+            for ext in extensions {
+                println!("{}", ext);
+            }
+        }
+        self.show_extensions
+    }
 
-        /// Convert TO SCREAMING_SNAKE_CASE
-        #[arg(long = "to-screaming-snake", group = "to")]
-        to_screaming_snake: bool,
+    fn options(&self, exclude: &[glob::Pattern]) -> FilterOptions {
+        let mut options = FilterOptions {
+            protect_localization: !self.include_localization,
+            exclude_extensions: self.exclude_extensions.clone(),
+            include_hidden_dirs: self.include_hidden_dirs,
+            include_hidden_files: self.include_hidden_files,
+            exclude: exclude.to_vec(),
+            ..FilterOptions::default()
+        };
+        if self.no_default_skip_dirs {
+            options.skip_dirs.clear();
+        }
+        options.skip_dirs.extend(self.skip_dirs.iter().cloned());
+        // At -vv, say why each file was left alone
+        options.report_all_skips = log::log_enabled!(log::Level::Debug);
+        options
+    }
+}
 
-        /// Convert TO kebab-case
-        #[arg(long = "to-kebab", group = "to")]
-        to_kebab: bool,
+/// The pre-`--from`/`--to` format flags of `convert`, kept (hidden) so existing
+/// scripts still work
+#[derive(Args, Debug, Clone)]
+struct LegacyFormatFlags {
+    /// Same as --from camel
+    #[arg(long = "from-camel", group = "source", hide = true)]
+    from_camel: bool,
 
-        /// Convert TO SCREAMING-KEBAB-CASE
-        #[arg(long = "to-screaming-kebab", group = "to")]
-        to_screaming_kebab: bool,
+    /// Same as --from pascal
+    #[arg(long = "from-pascal", group = "source", hide = true)]
+    from_pascal: bool,
 
-        /// The directory or file to convert
-        path: PathBuf,
+    /// Same as --from snake
+    #[arg(long = "from-snake", group = "source", hide = true)]
+    from_snake: bool,
+
+    /// Same as --from screaming-snake
+    #[arg(long = "from-screaming-snake", group = "source", hide = true)]
+    from_screaming_snake: bool,
+
+    /// Same as --from kebab
+    #[arg(long = "from-kebab", group = "source", hide = true)]
+    from_kebab: bool,
+
+    /// Same as --from screaming-kebab
+    #[arg(long = "from-screaming-kebab", group = "source", hide = true)]
+    from_screaming_kebab: bool,
+
+    /// Same as --from train
+    #[arg(long = "from-train", group = "source", hide = true)]
+    from_train: bool,
+
+    /// Same as --from ada
+    #[arg(long = "from-ada", group = "source", hide = true)]
+    from_ada: bool,
+
+    /// Same as --from dot
+    #[arg(long = "from-dot", group = "source", hide = true)]
+    from_dot: bool,
+
+    /// Same as --from path
+    #[arg(long = "from-path", group = "source", hide = true)]
+    from_path: bool,
+
+    /// Same as --to camel
+    #[arg(long = "to-camel", group = "target", hide = true)]
+    to_camel: bool,
+
+    /// Same as --to pascal
+    #[arg(long = "to-pascal", group = "target", hide = true)]
+    to_pascal: bool,
+
+    /// Same as --to snake
+    #[arg(long = "to-snake", group = "target", hide = true)]
+    to_snake: bool,
+
+    /// Same as --to screaming-snake
+    #[arg(long = "to-screaming-snake", group = "target", hide = true)]
+    to_screaming_snake: bool,
+
+    /// Same as --to kebab
+    #[arg(long = "to-kebab", group = "target", hide = true)]
+    to_kebab: bool,
+
+    /// Same as --to screaming-kebab
+    #[arg(long = "to-screaming-kebab", group = "target", hide = true)]
+    to_screaming_kebab: bool,
+
+    /// Same as --to train
+    #[arg(long = "to-train", group = "target", hide = true)]
+    to_train: bool,
+
+    /// Same as --to ada
+    #[arg(long = "to-ada", group = "target", hide = true)]
+    to_ada: bool,
+
+    /// Same as --to dot
+    #[arg(long = "to-dot", group = "target", hide = true)]
+    to_dot: bool,
+
+    /// Same as --to path
+    #[arg(long = "to-path", group = "target", hide = true)]
+    to_path: bool,
+}
+
+impl LegacyFormatFlags {
+    /// Formats selected with --from-* flags
+    fn sources(&self) -> Vec<CaseFormat> {
+        selected_formats([
+            self.from_camel,
+            self.from_pascal,
+            self.from_snake,
+            self.from_screaming_snake,
+            self.from_kebab,
+            self.from_screaming_kebab,
+            self.from_train,
+            self.from_ada,
+            self.from_dot,
+            self.from_path,
+        ])
+    }
+
+    /// The format selected with a --to-* flag, if any
+    fn target(&self) -> Option<CaseFormat> {
+        selected_formats([
+            self.to_camel,
+            self.to_pascal,
+            self.to_snake,
+            self.to_screaming_snake,
+            self.to_kebab,
+            self.to_screaming_kebab,
+            self.to_train,
+            self.to_ada,
+            self.to_dot,
+            self.to_path,
+        ])
+        .first()
+        .copied()
+    }
+}
+
+/// Loads the user config and the refmt.toml that apply to `path`, or the defaults if
+/// there are none, with the `--profile` applied
+fn load_config(path: &Path, profile: Option<&str>) -> anyhow::Result<Config> {
+    let files = config::config_files(path);
+    for file in &files {
+        debug!("Using config file: {}", file.display());
+    }
+    let config = Config::load_all(&files)?;
+    match profile {
+        Some(name) => {
+            debug!("Using profile: {}", name);
+            Ok(config.with_profile(name)?)
+        }
+        None => Ok(config),
+    }
+}
+
+#[derive(Subcommand)]
+// Parsed once at startup; boxing the large variants would only obscure the fields
+#[allow(clippy::large_enum_variant)]
+enum Commands {
+    /// Convert between case formats
+    // --from and --to may come from refmt.toml instead
+    #[command(group(clap::ArgGroup::new("source").multiple(true)))]
+    #[command(group(clap::ArgGroup::new("target").multiple(false)))]
+    // Keep the hidden --from-*/--to-* flags out of the usage line
+    #[command(override_usage = "refmt convert [OPTIONS] <--from <FORMAT>|--from-auto> --to <FORMAT> <PATH|--string <IDENTIFIER>>")]
+    Convert {
+        /// Format(s) to convert from; repeat to convert several in one pass
+        #[arg(long, value_enum, value_name = "FORMAT", group = "source")]
+        from: Vec<CaseFormat>,
+
+        /// Format to convert to
+        #[arg(long, value_enum, value_name = "FORMAT", group = "target")]
+        to: Option<CaseFormat>,
+
+        #[command(flatten)]
+        legacy: Box<LegacyFormatFlags>,
+
+        /// Convert FROM any detected format (all but dot.case and path/case)
+        #[arg(long = "from-auto", group = "source")]
+        from_auto: bool,
+
+        /// The directory or file to convert, or - to convert stdin to stdout
+        #[arg(required_unless_present = "string")]
+        path: Option<PathBuf>,
+
+        /// Print the conversion of an identifier and exit; repeat for several
+        #[arg(long, value_name = "IDENTIFIER", conflicts_with = "path")]
+        string: Vec<String>,
 
         /// Convert files recursively
         #[arg(short = 'r', long)]
@@ -119,8 +502,17 @@ enum Commands {
         #[arg(short = 'd', long = "dry-run")]
         dry_run: bool,
 
+        /// Report the files that would change, without changing them, and fail if
+        /// there are any
+        #[arg(long, conflicts_with = "dry_run")]
+        check: bool,
+
+        /// Show a unified diff of each change
+        #[arg(long)]
+        diff: bool,
+
         /// File extensions to process
-        #[arg(short = 'e', long = "extensions")]
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
         extensions: Option<Vec<String>>,
 
         /// Prefix to add to all converted words
@@ -155,20 +547,68 @@ enum Commands {
         #[arg(long = "replace-suffix-to", requires = "replace_suffix_from")]
         replace_suffix_to: Option<String>,
 
-        /// Glob pattern to filter files
-        #[arg(long)]
-        glob: Option<String>,
+        /// Only convert files matching this glob pattern (repeatable; a file matching
+        /// any of them is converted)
+        #[arg(long, value_name = "GLOB")]
+        glob: Vec<String>,
 
         /// Regex pattern to filter which words get converted
         #[arg(long = "word-filter")]
         word_filter: Option<String>,
+
+        /// Regex pattern of words not to convert, applied after --word-filter
+        #[arg(long = "word-exclude", value_name = "REGEX")]
+        word_exclude: Option<String>,
+
+        /// Identifiers never to convert, e.g. iPhone,macOS
+        #[arg(long = "exclude-words", value_delimiter = ',', value_name = "WORDS")]
+        exclude_words: Vec<String>,
+
+        /// File listing identifiers never to convert, one per line (# starts a comment)
+        #[arg(long = "exclude-words-file", value_name = "FILE")]
+        exclude_words_file: Option<PathBuf>,
+
+        /// Also match identifiers with non-ASCII letters (größeZahl, naïveValue)
+        #[arg(long)]
+        unicode: bool,
+
+        /// How digits split words: `attach` (sha256_hash), `separate` (sha_256_hash) or
+        /// `split` (a digit after a letter starts a word); unset keeps the source's words
+        #[arg(long, value_name = "MODE", value_parser = parse_digit_boundary)]
+        digits: Option<DigitBoundary>,
+
+        /// TOML file with `abbreviations = ["ID", "URL", ...]` to spell as given in
+        /// capitalized formats (userID); defaults to the list in refmt.toml
+        #[arg(long, value_name = "FILE")]
+        abbreviations: Option<PathBuf>,
+
+        /// In JSON, YAML and TOML files, convert only mapping keys, keeping values,
+        /// comments and layout (add the extensions with -e)
+        #[arg(long = "keys-only")]
+        keys_only: bool,
+
+        /// Only convert the names each file declares (variables, functions, parameters,
+        /// types) and their uses there, not names from libraries it calls
+        #[arg(long = "declared-only")]
+        declared_only: bool,
+
+        /// Don't rewrite Markdown links to headings changed by the conversion
+        #[arg(long = "no-update-anchors")]
+        no_update_anchors: bool,
+
+        #[command(flatten)]
+        filter: FilterArgs,
     },
 
     /// Remove trailing whitespace from files
     Clean {
-        /// The directory or file to clean
+        /// The directory or file to clean, or - to clean stdin to stdout
         path: PathBuf,
 
+        /// With - as the path, the file stdin holds, for its extension and the filters
+        #[arg(long, value_name = "PATH")]
+        stdin_filename: Option<PathBuf>,
+
         /// Process files recursively
         #[arg(short = 'r', long, default_value_t = true)]
         recursive: bool,
@@ -177,16 +617,94 @@ enum Commands {
         #[arg(short = 'd', long = "dry-run")]
         dry_run: bool,
 
+        /// Report the files that would change, without changing them, and fail if
+        /// there are any
+        #[arg(long, conflicts_with = "dry_run")]
+        check: bool,
+
+        /// Show a unified diff of each change
+        #[arg(long)]
+        diff: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+
+        /// Only clean lines that git reports as added or modified
+        #[arg(long)]
+        changed_lines_only: bool,
+
+        /// Compare against this revision instead of the index
+        #[arg(long, value_name = "REV", requires = "changed_lines_only")]
+        since: Option<String>,
+
+        /// Only consider staged changes
+        #[arg(long, requires = "changed_lines_only")]
+        staged: bool,
+
+        /// Also rewrite tabs in the indentation as WIDTH spaces each
+        #[arg(long, value_name = "WIDTH")]
+        tabs_to_spaces: Option<usize>,
+
+        /// Also rewrite each WIDTH spaces of indentation as a tab
+        #[arg(long, value_name = "WIDTH", conflicts_with = "tabs_to_spaces")]
+        spaces_to_tabs: Option<usize>,
+
+        /// Add a line ending to a last line without one
+        #[arg(long)]
+        ensure_final_newline: bool,
+
+        /// Remove the blank lines at the end of each file
+        #[arg(long)]
+        trim_trailing_blank_lines: bool,
+
+        /// What trailing spaces making a hard line break in Markdown files become
+        #[arg(long, value_parser = ["keep", "backslash", "remove"], default_value = "keep")]
+        markdown_breaks: String,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// Re-indent files with tabs or spaces and a new indentation width
+    Indent {
+        /// The directory or file to process
+        path: PathBuf,
+
+        /// Character to indent with
+        #[arg(long, value_parser = ["spaces", "tabs"], default_value = "spaces")]
+        style: String,
+
+        /// Spaces per indentation level in the output
+        #[arg(long, default_value_t = 4)]
+        width: usize,
+
+        /// Spaces per indentation level in the input [default: detected per file]
+        #[arg(long = "from-width")]
+        from_width: Option<usize>,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
         /// File extensions to process
-        #[arg(short = 'e', long = "extensions")]
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
         extensions: Option<Vec<String>>,
     },
 
     /// Remove or replace emojis with text alternatives
     Emojis {
-        /// The directory or file to process
+        /// The directory or file to process, or - to process stdin to stdout
         path: PathBuf,
 
+        /// With - as the path, the file stdin holds, for its extension and the filters
+        #[arg(long, value_name = "PATH")]
+        stdin_filename: Option<PathBuf>,
+
         /// Process files recursively [default: true]
         #[arg(short = 'r', long, default_value_t = true)]
         recursive: bool,
@@ -195,8 +713,17 @@ enum Commands {
         #[arg(short = 'd', long = "dry-run")]
         dry_run: bool,
 
+        /// Report the files that would change, without changing them, and fail if
+        /// there are any
+        #[arg(long, conflicts_with = "dry_run")]
+        check: bool,
+
+        /// Show a unified diff of each change
+        #[arg(long)]
+        diff: bool,
+
         /// File extensions to process (default: .md, .txt, and common source files)
-        #[arg(short = 'e', long = "extensions")]
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
         extensions: Option<Vec<String>>,
 
         /// Replace task completion emojis with text (e.g., ✅ -> [x]) [default: true]
@@ -206,6 +733,13 @@ enum Commands {
         /// Remove all other emojis [default: true]
         #[arg(long = "remove-other", default_value_t = true)]
         remove_other: bool,
+
+        /// Don't rewrite Markdown links to headings changed by emoji removal
+        #[arg(long = "no-update-anchors")]
+        no_update_anchors: bool,
+
+        #[command(flatten)]
+        filter: FilterArgs,
     },
 
     /// Rename files with various transformations
@@ -265,455 +799,3640 @@ enum Commands {
         /// Add timestamp prefix in YYMMDD format (e.g., 250915_)
         #[arg(long = "timestamp-short")]
         timestamp_short: bool,
+
+        /// What to do when the new name already exists; `prompt` asks per conflict
+        #[arg(
+            long = "on-conflict",
+            value_parser = ["error", "skip", "overwrite", "number", "prompt"],
+            default_value = "error"
+        )]
+        on_conflict: String,
     },
-}
 
-/// Initialize logging based on verbosity level
-fn init_logging(verbose: u8, quiet: bool, log_file: Option<PathBuf>) -> anyhow::Result<()> {
-    let log_level = if quiet {
-        LevelFilter::Error
-    } else {
-        match verbose {
-            0 => LevelFilter::Warn,
-            1 => LevelFilter::Info,
-            2 => LevelFilter::Debug,
-            _ => LevelFilter::Trace,
-        }
-    };
+    /// Convert line endings (LF/CRLF)
+    Eol {
+        /// The directory or file to process
+        path: PathBuf,
 
-    let config = ConfigBuilder::new()
-        .set_time_format_rfc3339()
-        .set_thread_level(LevelFilter::Off)
-        .set_target_level(LevelFilter::Off)
-        .build();
+        /// Line ending to convert to
+        #[arg(long, value_parser = ["lf", "crlf"])]
+        to: String,
 
-    let mut loggers: Vec<Box<dyn SharedLogger>> = vec![TermLogger::new(
-        log_level,
-        config.clone(),
-        TerminalMode::Mixed,
-        ColorChoice::Auto,
-    )];
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
 
-    if let Some(log_path) = log_file {
-        let file = std::fs::File::create(&log_path)?;
-        loggers.push(WriteLogger::new(LevelFilter::Debug, config, file));
-        eprintln!("Logging to file: {}", log_path.display());
-    }
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
 
-    CombinedLogger::init(loggers)?;
+        /// File extensions to process (default: all text files)
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
 
-    debug!("Logging initialized with level: {:?}", log_level);
-    Ok(())
-}
+        /// Ignore .gitattributes (binary, -text and eol attributes)
+        #[arg(long = "no-gitattributes")]
+        no_gitattributes: bool,
+    },
 
-/// Create a progress spinner
-fn create_spinner(message: &str) -> ProgressBar {
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.green} {msg}")
-            .unwrap()
-            .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]),
-    );
-    spinner.set_message(message.to_string());
-    spinner.enable_steady_tick(std::time::Duration::from_millis(80));
-    spinner
-}
+    /// Insert, update or remove license/copyright headers
+    Header {
+        /// The directory or file to process
+        path: PathBuf,
 
-fn determine_case_format(
-    from_camel: bool,
-    from_pascal: bool,
-    from_snake: bool,
-    from_screaming_snake: bool,
-    from_kebab: bool,
-    _from_screaming_kebab: bool,
-) -> CaseFormat {
-    if from_camel {
-        CaseFormat::CamelCase
-    } else if from_pascal {
-        CaseFormat::PascalCase
-    } else if from_snake {
-        CaseFormat::SnakeCase
-    } else if from_screaming_snake {
-        CaseFormat::ScreamingSnakeCase
-    } else if from_kebab {
-        CaseFormat::KebabCase
-    } else {
-        CaseFormat::ScreamingKebabCase
-    }
-}
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
 
-#[time("info")]
-fn run_convert(
-    from_camel: bool,
-    from_pascal: bool,
-    from_snake: bool,
-    from_screaming_snake: bool,
-    from_kebab: bool,
-    from_screaming_kebab: bool,
-    to_camel: bool,
-    to_pascal: bool,
-    to_snake: bool,
-    to_screaming_snake: bool,
-    to_kebab: bool,
-    to_screaming_kebab: bool,
-    path: PathBuf,
-    recursive: bool,
-    dry_run: bool,
-    extensions: Option<Vec<String>>,
-    prefix: String,
-    suffix: String,
-    strip_prefix: Option<String>,
-    strip_suffix: Option<String>,
-    replace_prefix_from: Option<String>,
-    replace_prefix_to: Option<String>,
-    replace_suffix_from: Option<String>,
-    replace_suffix_to: Option<String>,
-    glob: Option<String>,
-    word_filter: Option<String>,
-) -> anyhow::Result<()> {
-    let from_format = determine_case_format(
-        from_camel,
-        from_pascal,
-        from_snake,
-        from_screaming_snake,
-        from_kebab,
-        from_screaming_kebab,
-    );
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
 
-    let to_format = determine_case_format(
-        to_camel,
-        to_pascal,
-        to_snake,
-        to_screaming_snake,
-        to_kebab,
-        to_screaming_kebab,
-    );
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
 
-    info!(
-        "Converting from {:?} to {:?}",
-        from_format, to_format
-    );
-    info!("Target path: {}", path.display());
-    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+        /// Header template; {year}, {holder} and {license} are substituted
+        #[arg(long, conflicts_with = "template_file")]
+        template: Option<String>,
 
-    if let Some(ref exts) = extensions {
-        debug!("File extensions: {:?}", exts);
-    }
-    if !prefix.is_empty() {
-        debug!("Prefix: '{}'", prefix);
-    }
+        /// Read the header template from a file
+        #[arg(long = "template-file")]
+        template_file: Option<PathBuf>,
+
+        /// Copyright holder for {holder}
+        #[arg(long)]
+        holder: Option<String>,
+
+        /// License identifier for {license} (e.g., MIT)
+        #[arg(long)]
+        license: Option<String>,
+
+        /// Year for {year} [default: current year]
+        #[arg(long)]
+        year: Option<u64>,
+
+        /// Remove existing headers instead of adding them
+        #[arg(long)]
+        remove: bool,
+
+        /// Only ensure an SPDX-License-Identifier line for --license
+        #[arg(long, requires = "license", conflicts_with_all = ["remove", "template", "template_file"])]
+        spdx: bool,
+
+        /// Report files with missing or outdated headers and fail if there are any
+        #[arg(long, conflicts_with = "dry_run")]
+        check: bool,
+    },
+
+    /// Rewrite C/C++ include guards to match their (possibly renamed) file names
+    Guards {
+        /// The directory or file to process
+        path: PathBuf,
+
+        /// Case format of the guards: screaming-snake, snake, camel or pascal
+        #[arg(long, value_enum, value_name = "FORMAT", default_value_t = CaseFormat::ScreamingSnakeCase)]
+        to: CaseFormat,
+
+        /// Prefix for every guard (e.g., the project name)
+        #[arg(long, default_value = "")]
+        prefix: String,
+
+        /// Suffix for every guard (e.g., _INCLUDED)
+        #[arg(long, default_value = "")]
+        suffix: String,
+
+        /// Include the header's directories (relative to PATH) in the guard
+        #[arg(long = "path-components")]
+        path_components: bool,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+    },
+
+    /// Convert between comment styles and normalize comment banners
+    Comments {
+        /// The directory or file to process
+        path: PathBuf,
+
+        /// Convert C-family comments to `//` lines or `/* */` blocks
+        #[arg(long, value_parser = ["line", "block"])]
+        to: Option<String>,
+
+        /// Normalize `#` comment banners and decorated headings
+        #[arg(long)]
+        banners: bool,
+
+        /// Width of normalized banner rules
+        #[arg(long, default_value_t = 79)]
+        width: usize,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+
+        /// Show a unified diff of each change
+        #[arg(long)]
+        diff: bool,
+    },
+
+    /// Convert curly quotes to straight ASCII quotes, or the reverse
+    Quotes {
+        /// The directory or file to process
+        path: PathBuf,
+
+        /// Convert curly quotes and apostrophes to ASCII
+        #[arg(long, conflicts_with = "curl")]
+        straighten: bool,
+
+        /// Convert ASCII quotes and apostrophes to curly ones
+        #[arg(long)]
+        curl: bool,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+    },
+
+    /// Replace Unicode dashes, ellipses and fullwidth punctuation with ASCII
+    Punctuation {
+        /// The directory or file to process
+        path: PathBuf,
+
+        /// Replacement for em dashes
+        #[arg(long = "em-dash", default_value = "--")]
+        em_dash: String,
+
+        /// Replacement for en dashes and minus signs
+        #[arg(long = "en-dash", default_value = "-")]
+        en_dash: String,
+
+        /// Replacement for the ellipsis character
+        #[arg(long, default_value = "...")]
+        ellipsis: String,
+
+        /// Leave fullwidth punctuation alone (e.g. in CJK text)
+        #[arg(long = "keep-fullwidth")]
+        keep_fullwidth: bool,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+    },
+
+    /// List every non-ASCII character with its position and category
+    AsciiCheck {
+        /// The directory or file to scan
+        path: PathBuf,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+    },
+
+    /// Count the identifiers of each case format per extension and directory, to see
+    /// how far a migration has come (--output json for scripts)
+    Stats {
+        /// The directory or file to scan
+        path: PathBuf,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+
+        /// Also count dot.case and path/case, which member accesses and paths match too
+        #[arg(long)]
+        dot_path: bool,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// Insert digit separators into long numeric literals (1000000 -> 1_000_000)
+    Numbers {
+        /// The directory or file to process
+        path: PathBuf,
+
+        /// Minimum number of digits before a literal is grouped
+        #[arg(long = "min-digits", default_value_t = 5)]
+        min_digits: usize,
+
+        /// Also group hexadecimal, octal and binary literals, in fours
+        #[arg(long)]
+        radix: bool,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+    },
+
+    /// Convert the case of mapping keys in JSON, YAML and TOML documents
+    ConvertKeys {
+        /// The directory or file to process
+        path: PathBuf,
+
+        /// Case format to convert keys to
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        to: CaseFormat,
+
+        /// Document format [default: detected from the file extension]
+        #[arg(long, value_parser = ["json", "yaml", "toml"])]
+        format: Option<String>,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+    },
+
+    /// Rename protobuf message fields and JSON Schema properties to a case format
+    SchemaFields {
+        /// The directory or file to process
+        path: PathBuf,
+
+        /// Case format to rename fields to
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        to: CaseFormat,
+
+        /// Write the old -> new field mapping to this JSON file ("-" for stdout)
+        #[arg(long, value_name = "FILE")]
+        mapping: Option<PathBuf>,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+    },
+
+    /// Uppercase .env keys, normalize spacing around `=` and report duplicate keys
+    Dotenv {
+        /// The directory or .env file to process
+        path: PathBuf,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+    },
+
+    /// Normalize the case of SQL keywords, leaving strings, identifiers and comments alone
+    Sql {
+        /// The directory or file to process
+        path: PathBuf,
+
+        /// Case to write keywords in
+        #[arg(long, value_parser = ["upper", "lower"], default_value = "upper")]
+        keywords: String,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+    },
+
+    /// Convert CSS class and id names in stylesheets and HTML/JSX markup together
+    ClassNames {
+        /// The directory or file to process
+        path: PathBuf,
+
+        /// Case format to convert names to: kebab or camel
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        to: CaseFormat,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+    },
+
+    /// Convert JS/TS and Python string literals to single or double quotes
+    StringQuotes {
+        /// The directory or file to process
+        path: PathBuf,
+
+        /// Quote character to use
+        #[arg(long, value_parser = ["single", "double"])]
+        to: String,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+    },
+
+    /// Normalize shebang lines and make the executable bit match them
+    Shebang {
+        /// The directory or file to process
+        path: PathBuf,
+
+        /// Keep absolute interpreter paths instead of using /usr/bin/env
+        #[arg(long = "no-env")]
+        no_env: bool,
+
+        /// Leave file permissions alone
+        #[arg(long = "no-permissions")]
+        no_permissions: bool,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
+        /// File extensions to process (default: all text files)
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+    },
+
+    /// Sort and group consecutive #include/import/use lines
+    SortImports {
+        /// The directory or file to process
+        path: PathBuf,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+
+        /// Report files with unsorted imports and fail if there are any
+        #[arg(long, conflicts_with = "dry_run")]
+        check: bool,
+    },
+
+    /// Check that file names match the class or function they declare
+    #[command(group(clap::ArgGroup::new("fix").multiple(false)))]
+    CheckNames {
+        /// The directory or file to check
+        path: PathBuf,
+
+        /// Case format of file names [default: per language]
+        #[arg(long = "file-case", value_parser = ["snake", "kebab", "camel", "pascal"])]
+        file_case: Option<String>,
+
+        /// Rename mismatched files after the identifier they declare
+        #[arg(long = "fix-filename", group = "fix")]
+        fix_filename: bool,
+
+        /// Rename mismatched identifiers (within the file) after the file name
+        #[arg(long = "fix-identifier", group = "fix")]
+        fix_identifier: bool,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
+        /// File extensions to process
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+    },
+
+    /// Lint or fix a language's naming conventions
+    Naming {
+        /// The directory or file to check
+        path: PathBuf,
+
+        /// Language whose conventions apply
+        #[arg(long, value_parser = ["rust"], default_value = "rust")]
+        lang: String,
+
+        /// Rename violating identifiers in every processed file
+        #[arg(long)]
+        fix: bool,
+
+        /// Process files recursively [default: true]
+        #[arg(short = 'r', long, default_value_t = true)]
+        recursive: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+
+        /// File extensions to process [default: the language's]
+        #[arg(short = 'e', long = "extensions", value_delimiter = ',')]
+        extensions: Option<Vec<String>>,
+    },
+
+    /// Revert the rewrites and renames of an earlier run, as recorded in .refmt/journal
+    Undo {
+        /// A directory or file in the project
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Undo the latest run (the default)
+        #[arg(long, conflicts_with = "id")]
+        last: bool,
+
+        /// Undo the run with this id (see --list)
+        #[arg(long)]
+        id: Option<String>,
+
+        /// List the recorded runs, oldest first, instead of undoing one
+        #[arg(long, conflicts_with_all = ["last", "id"])]
+        list: bool,
+
+        /// Dry run (don't modify files)
+        #[arg(short = 'd', long = "dry-run")]
+        dry_run: bool,
+    },
+
+    /// List the case formats with an example, the pattern identifiers are matched with
+    /// and the conversions that can lose information (--output json for scripts)
+    ListFormats,
+
+    /// Watch a directory and re-run clean, emojis and/or convert on files as they change
+    Watch {
+        /// The directory to watch
+        path: PathBuf,
+
+        /// Also watch subdirectories
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Transformations to run on each changed file, in order [default: steps from
+        /// refmt.toml, or clean,emojis]
+        #[arg(long, value_delimiter = ',', value_parser = ["clean", "emojis", "convert"])]
+        run: Option<Vec<String>>,
+
+        /// Format to convert from (with --run convert)
+        #[arg(long)]
+        from: Option<CaseFormat>,
+
+        /// Format to convert to (with --run convert)
+        #[arg(long)]
+        to: Option<CaseFormat>,
+
+        /// Milliseconds to wait for changes to settle before processing them
+        #[arg(long, value_name = "MS", default_value_t = 300)]
+        debounce: u64,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// Install a git pre-commit hook running `clean --check` and `emojis --check` on
+    /// the staged files
+    InstallHook {
+        /// A directory in the repository
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Print hooks for the pre-commit framework's .pre-commit-config.yaml instead
+        #[arg(long = "pre-commit")]
+        pre_commit: bool,
+
+        /// Replace an existing pre-commit hook that refmt didn't write
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Write a commented starter refmt.toml, with extensions for the detected project
+    /// type (Rust, Python, JavaScript)
+    Init {
+        /// The project directory
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Also install the git pre-commit hook (see install-hook)
+        #[arg(long = "with-hooks")]
+        with_hooks: bool,
+
+        /// Replace an existing refmt.toml, and pre-commit hook with --with-hooks
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Run a long-lived JSON-RPC server for editor integrations
+    Daemon {
+        /// Listen on a Unix domain socket instead of stdio
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+
+    /// Serve the transformations as an HTTP API
+    #[cfg(feature = "server")]
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        /// Address to bind to
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+}
+
+impl Commands {
+    /// Whether the command writes its result to stdout instead of files (`convert ... -`,
+    /// `convert --string`, `clean -`, `emojis -`)
+    fn is_filter(&self) -> bool {
+        matches!(self, Commands::Convert { path, .. } if path.as_ref().is_none_or(|p| p.as_os_str() == "-"))
+            || matches!(
+                self,
+                Commands::Clean { path, .. } | Commands::Emojis { path, .. }
+                    if path.as_os_str() == "-"
+            )
+    }
+
+    /// The path this command processes, and whether it modifies it (not for dry runs,
+    /// checks and reports); `None` for servers and filters
+    fn target(&self) -> Option<(&Path, bool)> {
+        if self.is_filter() {
+            return None;
+        }
+        match self {
+            Commands::Header {
+                path,
+                dry_run,
+                check,
+                ..
+            }
+            | Commands::SortImports {
+                path,
+                dry_run,
+                check,
+                ..
+            } => Some((path, !dry_run && !check)),
+            Commands::CheckNames {
+                path,
+                dry_run,
+                fix_filename,
+                fix_identifier,
+                ..
+            } => Some((path, !dry_run && (*fix_filename || *fix_identifier))),
+            Commands::Naming {
+                path, dry_run, fix, ..
+            } => Some((path, !dry_run && *fix)),
+            Commands::Convert {
+                path,
+                dry_run,
+                check,
+                ..
+            } => path.as_deref().map(|path| (path, !dry_run && !check)),
+            Commands::Clean {
+                path,
+                dry_run,
+                check,
+                ..
+            }
+            | Commands::Emojis {
+                path,
+                dry_run,
+                check,
+                ..
+            } => Some((path, !dry_run && !check)),
+            Commands::Indent { path, dry_run, .. }
+            | Commands::RenameFiles { path, dry_run, .. }
+            | Commands::Eol { path, dry_run, .. }
+            | Commands::Guards { path, dry_run, .. }
+            | Commands::Comments { path, dry_run, .. }
+            | Commands::Quotes { path, dry_run, .. }
+            | Commands::Punctuation { path, dry_run, .. }
+            | Commands::Numbers { path, dry_run, .. }
+            | Commands::ConvertKeys { path, dry_run, .. }
+            | Commands::SchemaFields { path, dry_run, .. }
+            | Commands::Dotenv { path, dry_run, .. }
+            | Commands::Sql { path, dry_run, .. }
+            | Commands::ClassNames { path, dry_run, .. }
+            | Commands::StringQuotes { path, dry_run, .. }
+            | Commands::Shebang { path, dry_run, .. } => Some((path, !dry_run)),
+            Commands::AsciiCheck { path, .. } | Commands::Stats { path, .. } => {
+                Some((path, false))
+            }
+            // Restores what refmt changed, which is what a clean tree check would refuse
+            Commands::Undo { .. } => None,
+            // Runs until interrupted, and journals each pass itself
+            Commands::Watch { .. } => None,
+            Commands::InstallHook { .. } | Commands::Init { .. } => None,
+            Commands::ListFormats => None,
+            Commands::Daemon { .. } => None,
+            #[cfg(feature = "server")]
+            Commands::Serve { .. } => None,
+        }
+    }
+}
+
+/// Refuses to continue when `path` is in a git repository with uncommitted changes
+///
+/// Required by `--require-clean-git` or `require_clean_git` in refmt.toml, unless
+/// `--allow-dirty` is given.
+fn check_clean_git(
+    path: &Path,
+    profile: Option<&str>,
+    flag: bool,
+    allow_dirty: bool,
+) -> anyhow::Result<()> {
+    if allow_dirty || !(flag || load_config(path, profile)?.require_clean_git) {
+        return Ok(());
+    }
+
+    match git::uncommitted_changes(path)? {
+        None => {
+            warn!(
+                "'{}' is not in a git repository; skipping the clean tree check",
+                path.display()
+            );
+            Ok(())
+        }
+        Some(changes) if changes.is_empty() => Ok(()),
+        Some(changes) => {
+            for change in &changes {
+                info!("Uncommitted: {}", change);
+            }
+            anyhow::bail!(
+                "Refusing to modify files: the git repository has {} uncommitted change(s); \
+                 commit or stash them first, or pass --allow-dirty",
+                changes.len()
+            )
+        }
+    }
+}
+
+/// What every command shares, built once in `main` from the global options
+struct RunContext {
+    /// The filesystem every transformer reads and writes through
+    fs: Arc<dyn vfs::Fs>,
+    /// The `--max-depth`, `--follow-symlinks` and `--walk-errors` options of recursive
+    /// walks
+    walk: walker::WalkOptions,
+    /// The `--exclude` globs, with the excludes of refmt.toml
+    exclude: Vec<glob::Pattern>,
+    /// The `--profile`
+    profile: Option<String>,
+    /// Whether `--verify-idempotent` was given
+    verify_idempotent: bool,
+    /// Whether `--yes` was given
+    yes: bool,
+    /// The `--confirm-threshold`
+    confirm_threshold: Option<usize>,
+    /// Whether spinners and diffs may be drawn in color
+    color: bool,
+    /// Where summary lines and per-file events go
+    output: output::Output,
+    /// Records the run for `--report-file`
+    recorder: Option<Arc<run_report::Recorder>>,
+}
+
+impl RunContext {
+    /// The configuration that applies to `path`, with the `--profile` applied
+    fn load_config(&self, path: &Path) -> anyhow::Result<Config> {
+        load_config(path, self.profile.as_deref())
+    }
+
+    /// Whether a run may stop to ask before a large change: it writes, `--yes` wasn't
+    /// given and someone is at the terminal to answer
+    fn can_confirm(&self, dry_run: bool) -> bool {
+        !dry_run && !self.yes && std::io::stdin().is_terminal()
+    }
+
+    /// Asks whether to go on with a run changing `files` files, once they reach the
+    /// `--confirm-threshold` (or `confirm_threshold` in refmt.toml); `Ok(false)` when
+    /// declined
+    fn confirm_large_run(
+        &self,
+        config: &Config,
+        files: usize,
+        question: &str,
+    ) -> anyhow::Result<bool> {
+        let threshold = self
+            .confirm_threshold
+            .or(config.confirm_threshold)
+            .unwrap_or(CONFIRM_FILES);
+        if files < threshold {
+            return Ok(true);
+        }
+        confirm(question)
+    }
+
+    /// With `--verify-idempotent`, fails unless `transforms` are idempotent on every
+    /// file under `path`
+    ///
+    /// Runs before anything is written, so a failure leaves the files untouched.
+    fn verify_idempotent(
+        &self,
+        path: &Path,
+        recursive: bool,
+        transforms: &[&dyn ContentTransform],
+    ) -> anyhow::Result<()> {
+        if !self.verify_idempotent {
+            return Ok(());
+        }
+
+        let unstable =
+            memory::unstable_files(self.fs.as_ref(), path, recursive, self.walk, transforms)?;
+        if unstable.is_empty() {
+            info!("Verified that a second pass changes nothing");
+            return Ok(());
+        }
+        for file in &unstable {
+            error!("A second pass would change '{}' again", file.display());
+        }
+        anyhow::bail!(
+            "The transformation is not idempotent on {} file(s); no files were changed",
+            unstable.len()
+        )
+    }
+
+    /// Lists the files that couldn't be processed, and fails the run if there were any
+    fn report_failed_files(&self, failed: &[FailedFile]) -> anyhow::Result<()> {
+        if failed.is_empty() {
+            return Ok(());
+        }
+
+        error!("{} file(s) could not be processed:", failed.len());
+        for file in failed {
+            error!("  {}", file);
+        }
+        if let Some(recorder) = &self.recorder {
+            recorder.record_failed_files(failed);
+        }
+        anyhow::bail!("{} file(s) could not be processed", failed.len())
+    }
+}
+
+/// The local filesystem, with the retries and preserved file attributes the global
+/// options ask for, recording its changes in `journal`
+fn open_filesystem(cli: &Cli, journal: Option<Arc<journal::Journal>>) -> Arc<dyn vfs::Fs> {
+    let mut real = vfs::RealFs::new(vfs::PreservePolicy {
+        permissions: !cli.no_preserve_permissions,
+        mtime: cli.preserve_mtime,
+    });
+    real.journal = journal;
+    let real = Arc::new(real);
+    let retry = vfs::RetryPolicy {
+        attempts: cli.retries.saturating_add(1),
+        backoff: Duration::from_millis(cli.retry_backoff),
+    };
+    if retry.attempts > 1 {
+        Arc::new(vfs::RetryFs::new(real, retry))
+    } else {
+        real
+    }
+}
+
+/// Resolves `--color` against the environment
+///
+/// `auto` turns colors off when the logs don't go to a terminal, NO_COLOR is set to a
+/// non-empty value, or the terminal is dumb.
+fn color_choice(color: &str, terminal_mode: TerminalMode) -> ColorChoice {
+    match color {
+        "always" => ColorChoice::Always,
+        "never" => ColorChoice::Never,
+        _ => {
+            let is_terminal = match terminal_mode {
+                TerminalMode::Stderr => std::io::stderr().is_terminal(),
+                _ => std::io::stdout().is_terminal(),
+            };
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+            let dumb = std::env::var("TERM").is_ok_and(|term| term == "dumb");
+            if !is_terminal || no_color || dumb {
+                ColorChoice::Never
+            } else {
+                ColorChoice::Auto
+            }
+        }
+    }
+}
+
+/// Initialize logging based on verbosity level
+fn init_logging(
+    verbose: u8,
+    quiet: bool,
+    log_file: Option<PathBuf>,
+    terminal_mode: TerminalMode,
+    color: ColorChoice,
+    recorder: Option<&Arc<run_report::Recorder>>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let log_level = if quiet {
+        LevelFilter::Error
+    } else {
+        match verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    };
+
+    let mut builder = ConfigBuilder::new();
+    builder
+        .set_time_format_rfc3339()
+        .set_thread_level(LevelFilter::Off)
+        .set_target_level(LevelFilter::Off);
+    let config = builder.build();
+    let term_config = builder.add_filter_ignore_str(report::LOG_TARGET).build();
+
+    // Per-file messages from the transformers are the normal output, printed bare on
+    // stdout; the daemon owns stdout, so it drops them
+    let report_config = ConfigBuilder::new()
+        .add_filter_allow_str(report::LOG_TARGET)
+        .set_max_level(LevelFilter::Off)
+        .set_time_level(LevelFilter::Off)
+        .set_thread_level(LevelFilter::Off)
+        .set_target_level(LevelFilter::Off)
+        .set_location_level(LevelFilter::Off)
+        .build();
+    let report_level = if quiet || json || matches!(terminal_mode, TerminalMode::Stderr) {
+        LevelFilter::Off
+    } else {
+        LevelFilter::Info
+    };
+
+    let mut loggers: Vec<Box<dyn SharedLogger>> = vec![
+        TermLogger::new(log_level, term_config, terminal_mode, color),
+        WriteLogger::new(report_level, report_config, std::io::stdout()),
+    ];
+
+    if let Some(log_path) = log_file {
+        let file = std::fs::File::create(&log_path)?;
+        loggers.push(WriteLogger::new(LevelFilter::Debug, config, file));
+        eprintln!("Logging to file: {}", log_path.display());
+    }
+
+    if let Some(recorder) = recorder {
+        loggers.push(recorder.logger());
+    }
+
+    CombinedLogger::init(loggers)?;
+
+    debug!("Logging initialized with level: {:?}", log_level);
+    Ok(())
+}
+
+/// Create a progress spinner
+///
+/// The spinner is hidden when stdout isn't a terminal (or the terminal is dumb), so
+/// piped output stays clean.
+fn create_spinner(message: &str, color: bool) -> ProgressBar {
+    let dumb = std::env::var("TERM").is_ok_and(|term| term == "dumb");
+    if !std::io::stdout().is_terminal() || dumb {
+        return ProgressBar::hidden();
+    }
+
+    let template = if color {
+        "{spinner:.green} {msg}"
+    } else {
+        "{spinner} {msg}"
+    };
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::default_spinner()
+            .template(template)
+            .unwrap()
+            .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]),
+    );
+    spinner.set_message(message.to_string());
+    spinner.enable_steady_tick(std::time::Duration::from_millis(80));
+    spinner
+}
+
+/// Turns `spinner` into a progress bar with rate and ETA once the transformer has
+/// counted the files it will process
+fn track_progress(spinner: &ProgressBar, color: bool) -> Progress {
+    let bar = spinner.clone();
+    Progress::new(move |event| match event {
+        ProgressEvent::Started { total } => {
+            let template = if color {
+                "{spinner:.green} {msg} [{wide_bar:.cyan/blue}] {pos}/{len} ({per_sec}, ETA {eta})"
+            } else {
+                "{spinner} {msg} [{wide_bar}] {pos}/{len} ({per_sec}, ETA {eta})"
+            };
+            bar.set_length(total as u64);
+            bar.set_style(
+                ProgressStyle::default_bar()
+                    .template(template)
+                    .unwrap()
+                    .progress_chars("=> ")
+                    .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]),
+            );
+        }
+        ProgressEvent::FileDone { .. } => bar.inc(1),
+    })
+}
+
+/// Parses the `--digits` mode
+fn parse_digit_boundary(mode: &str) -> Result<DigitBoundary, String> {
+    match mode {
+        "attach" => Ok(DigitBoundary::Attach),
+        "separate" => Ok(DigitBoundary::Separate),
+        "split" => Ok(DigitBoundary::Split),
+        _ => Err("expected attach, separate or split".to_string()),
+    }
+}
+
+/// Reads a list of words, one per line, skipping blank lines and `#` comments
+fn read_word_list(path: &Path) -> anyhow::Result<Vec<String>> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        anyhow::anyhow!("Failed to read word list '{}': {}", path.display(), e)
+    })?;
+    Ok(content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// The formats whose flags are set, given in [`CaseFormat::ALL`] order
+fn selected_formats(flags: [bool; 10]) -> Vec<CaseFormat> {
+    CaseFormat::ALL
+        .into_iter()
+        .zip(flags)
+        .filter_map(|(format, selected)| selected.then_some(format))
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+#[time("info")]
+fn run_convert(
+    ctx: &RunContext,
+    mut from_formats: Vec<CaseFormat>,
+    from_auto: bool,
+    to_format: Option<CaseFormat>,
+    path: Option<PathBuf>,
+    strings: Vec<String>,
+    recursive: bool,
+    dry_run: bool,
+    check: bool,
+    diff: bool,
+    extensions: Option<Vec<String>>,
+    prefix: String,
+    suffix: String,
+    strip_prefix: Option<String>,
+    strip_suffix: Option<String>,
+    replace_prefix_from: Option<String>,
+    replace_prefix_to: Option<String>,
+    replace_suffix_from: Option<String>,
+    replace_suffix_to: Option<String>,
+    glob: Vec<String>,
+    word_filter: Option<String>,
+    word_exclude: Option<String>,
+    mut exclude_words: Vec<String>,
+    exclude_words_file: Option<PathBuf>,
+    unicode: bool,
+    digits: Option<DigitBoundary>,
+    abbreviations: Option<PathBuf>,
+    keys_only: bool,
+    declared_only: bool,
+    no_update_anchors: bool,
+    filter: FilterArgs,
+    no_pager: bool,
+) -> anyhow::Result<()> {
+    if from_auto && !from_formats.is_empty() {
+        anyhow::bail!("--from-auto can't be combined with --from");
+    }
+    // --string has no path; refmt.toml is looked up from the current directory
+    let path = path.unwrap_or_else(|| PathBuf::from("."));
+    let config = ctx.load_config(&path)?;
+
+    if !from_auto && from_formats.is_empty() {
+        from_formats = config.convert.from.clone();
+    }
+    if !from_auto && from_formats.is_empty() {
+        anyhow::bail!(
+            "No format to convert from: pass --from or --from-auto, or set convert.from in refmt.toml"
+        );
+    }
+    let Some(to_format) = to_format.or(config.convert.to) else {
+        anyhow::bail!("No format to convert to: pass --to, or set convert.to in refmt.toml");
+    };
+
+    if from_auto {
+        info!("Converting from any detected format to {}", to_format);
+    } else {
+        let names: Vec<String> = from_formats.iter().map(|f| f.to_string()).collect();
+        info!(
+            "Converting from {} to {}",
+            names.join(", "), to_format
+        );
+    }
+    info!("Target path: {}", path.display());
+    info!("Recursive: {}, Dry run: {}, Check: {}", recursive, dry_run, check);
+
+    let extensions = filter.extensions(ExtensionSet::Convert, extensions, &config)?;
+    debug!("File extensions: {:?}", extensions);
+    if filter.show(&extensions) {
+        return Ok(());
+    }
+    let abbreviations = match abbreviations {
+        Some(file) => Abbreviations::load(&file)?,
+        None => config.abbreviations(),
+    };
+    if !prefix.is_empty() {
+        debug!("Prefix: '{}'", prefix);
+    }
     if !suffix.is_empty() {
         debug!("Suffix: '{}'", suffix);
     }
-    if let Some(ref pattern) = glob {
-        debug!("Glob pattern: '{}'", pattern);
+    if !glob.is_empty() {
+        debug!("Glob patterns: {:?}", glob);
+    }
+    if let Some(ref filter) = word_filter {
+        debug!("Word filter: '{}'", filter);
+    }
+    if let Some(ref filter) = word_exclude {
+        debug!("Word exclude filter: '{}'", filter);
+    }
+    if let Some(file) = exclude_words_file {
+        exclude_words.extend(read_word_list(&file)?);
+    }
+    exclude_words.extend(config.convert.exclude_words.iter().cloned());
+    if !exclude_words.is_empty() {
+        debug!("Excluded words: {:?}", exclude_words);
+    }
+
+    let mut converter = CaseConverter::new(
+        from_formats.first().copied().unwrap_or(to_format),
+        to_format,
+        Some(extensions),
+        recursive,
+        dry_run || check,
+        prefix,
+        suffix,
+        strip_prefix,
+        strip_suffix,
+        replace_prefix_from,
+        replace_prefix_to,
+        replace_suffix_from,
+        replace_suffix_to,
+        None,
+        word_filter,
+    )?;
+    converter.set_globs(&glob)?;
+    if from_auto {
+        converter.set_auto_detect(true);
+    } else {
+        converter.set_source_formats(from_formats)?;
+    }
+    converter.set_word_exclude_filter(word_exclude)?;
+    converter.set_excluded_words(exclude_words);
+    converter.set_unicode(unicode || config.convert.unicode);
+    converter.set_digit_boundary(digits);
+    converter.set_abbreviations(abbreviations);
+    converter.set_structured_keys(keys_only);
+    #[cfg(feature = "declared-only")]
+    converter.set_declared_only(declared_only);
+    #[cfg(not(feature = "declared-only"))]
+    if declared_only {
+        anyhow::bail!("--declared-only needs refmt built with the `declared-only` feature");
+    }
+    converter.set_update_anchors(!no_update_anchors);
+    converter.set_show_diff(diff);
+    converter.set_color_diff(ctx.color);
+    converter.set_filter(filter.options(&ctx.exclude));
+    converter.set_fs(ctx.fs.clone());
+    converter.set_events(ctx.output.events());
+    converter.set_walk(ctx.walk);
+
+    if !strings.is_empty() {
+        for string in &strings {
+            println!("{}", converter.convert_identifier(string));
+        }
+        return Ok(());
+    }
+
+    // As a filter: stdin to stdout, without touching the filesystem
+    if path.as_os_str() == "-" {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        std::io::stdout().write_all(converter.convert_text(&input).as_bytes())?;
+        return Ok(());
+    }
+
+    ctx.verify_idempotent(&path, recursive, &[&converter])?;
+
+    // Large runs are previewed and confirmed, unless --yes or nobody can answer
+    if ctx.can_confirm(dry_run || check) {
+        let changed =
+            memory::changed_files(ctx.fs.as_ref(), &path, recursive, ctx.walk, &[&converter])?;
+        let question = format!("{} files will be modified. Continue?", changed.len());
+        if !ctx.confirm_large_run(&config, changed.len(), &question)? {
+            summary!(ctx, "Aborted; no files were changed");
+            return Ok(());
+        }
+    }
+
+    let _pager = start_diff_pager(ctx, dry_run || check, diff, no_pager);
+    let spinner = create_spinner("Processing files...", ctx.color);
+    converter.set_progress(track_progress(&spinner, ctx.color));
+
+    let result = converter.process_directory(&path);
+
+    spinner.finish_and_clear();
+
+    match result {
+        Ok(report) => {
+            report_walk_errors(&report.unreadable);
+            let prefix = if dry_run || check { "[DRY-RUN] " } else { "" };
+            info!(
+                "{}Converted {} identifiers in {} of {} file(s)",
+                prefix,
+                report.identifiers_converted(),
+                report.files_changed(),
+                report.files_scanned
+            );
+            ctx.report_failed_files(&report.failed)?;
+            if check && report.files_changed() > 0 {
+                anyhow::bail!("{} file(s) need case conversion", report.files_changed());
+            }
+            Ok(())
+        }
+        Err(e) => {
+            error!("Conversion failed: {}", e);
+            Err(e.into())
+        }
+    }
+}
+
+/// The file stdin holds when `path` is `-`, from `--stdin-filename`
+fn stdin_name(path: &Path, stdin_filename: Option<PathBuf>) -> anyhow::Result<Option<PathBuf>> {
+    match (path.as_os_str() == "-", stdin_filename) {
+        (true, Some(name)) => Ok(Some(name)),
+        (true, None) => anyhow::bail!("Reading stdin needs --stdin-filename"),
+        (false, Some(_)) => anyhow::bail!("--stdin-filename only applies to - as the path"),
+        (false, None) => Ok(None),
+    }
+}
+
+/// Transforms stdin as the content of `name`: prints the result, or with `check` fails
+/// if anything would change
+///
+/// Content the filters leave out, or that isn't UTF-8, passes through unchanged.
+fn filter_stdin(
+    transform: &dyn ContentTransform,
+    name: &Path,
+    check: bool,
+    what: &str,
+) -> anyhow::Result<()> {
+    let mut input = Vec::new();
+    std::io::stdin().read_to_end(&mut input)?;
+    let text = match std::str::from_utf8(&input) {
+        Ok(text) if transform.accepts(name) => text,
+        _ => {
+            debug!("Leaving '{}' as it is", name.display());
+            if !check {
+                std::io::stdout().write_all(&input)?;
+            }
+            return Ok(());
+        }
+    };
+
+    let (output, changes) = transform.transform(name, text);
+    if check {
+        if changes > 0 {
+            anyhow::bail!("'{}' needs {}", name.display(), what);
+        }
+        return Ok(());
+    }
+    std::io::stdout().write_all(output.as_bytes())?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+#[time("info")]
+fn run_clean(
+    ctx: &RunContext,
+    path: PathBuf,
+    stdin_filename: Option<PathBuf>,
+    recursive: bool,
+    dry_run: bool,
+    check: bool,
+    diff: bool,
+    extensions: Option<Vec<String>>,
+    changed_lines_only: bool,
+    since: Option<String>,
+    staged: bool,
+    tabs_to_spaces: Option<usize>,
+    spaces_to_tabs: Option<usize>,
+    ensure_final_newline: bool,
+    trim_trailing_blank_lines: bool,
+    markdown_breaks: String,
+    filter: FilterArgs,
+    no_pager: bool,
+) -> anyhow::Result<()> {
+    info!("Cleaning whitespace from: {}", path.display());
+    info!("Recursive: {}, Dry run: {}, Check: {}", recursive, dry_run, check);
+
+    let stdin_name = stdin_name(&path, stdin_filename)?;
+    let config = ctx.load_config(stdin_name.as_deref().unwrap_or(&path))?;
+    let extensions = filter.extensions(ExtensionSet::Whitespace, extensions, &config)?;
+    debug!("File extensions: {:?}", extensions);
+    if filter.show(&extensions) {
+        return Ok(());
+    }
+
+    let mut options = WhitespaceOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        recursive,
+        dry_run: dry_run || check,
+        show_diff: diff,
+        color_diff: ctx.color,
+        ..WhitespaceOptions::default()
+    };
+    options.filter = filter.options(&ctx.exclude);
+    options.file_extensions = extensions;
+    if let Some(width) = tabs_to_spaces {
+        options.indent = Some(IndentOptions::expand_tabs(width));
+    } else if let Some(width) = spaces_to_tabs {
+        options.indent = Some(IndentOptions::unexpand(width));
+    }
+    options.ensure_final_newline = ensure_final_newline;
+    options.trim_trailing_blank_lines = trim_trailing_blank_lines;
+    options.hard_breaks = match markdown_breaks.as_str() {
+        "backslash" => HardBreaks::Backslash,
+        "remove" => HardBreaks::Remove,
+        _ => HardBreaks::Keep,
+    };
+
+    if changed_lines_only {
+        debug!("Restricting to lines changed since {:?} (staged: {})", since, staged);
+        options.changed_lines = Some(ChangedLines::from_git(&path, since.as_deref(), staged)?);
+    }
+
+    let spinner = create_spinner("Cleaning files...", ctx.color);
+    options.progress = track_progress(&spinner, ctx.color);
+
+    let cleaner = WhitespaceCleaner::new(options);
+    if let Some(name) = stdin_name {
+        spinner.finish_and_clear();
+        return filter_stdin(&cleaner, &name, check, "whitespace cleaning");
+    }
+    ctx.verify_idempotent(&path, recursive, &[&cleaner])?;
+    let _pager = start_diff_pager(ctx, dry_run || check, diff, no_pager);
+    let (files, lines) = cleaner.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&cleaner.take_walk_errors());
+
+    if files > 0 {
+        let prefix = if dry_run || check { "[DRY-RUN] " } else { "" };
+        info!(
+            "{}Cleaned {} lines in {} file(s)",
+            prefix, lines, files
+        );
+        summary!(
+            ctx,
+            "{}Cleaned {} lines in {} file(s)",
+            prefix, lines, files
+        );
+    } else {
+        info!("No files needed cleaning");
+        summary!(ctx, "No files needed cleaning");
+    }
+
+    ctx.report_failed_files(&cleaner.take_failed_files())?;
+
+    if check && files > 0 {
+        anyhow::bail!("{} file(s) need whitespace cleaning", files);
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+#[time("info")]
+fn run_indent(
+    ctx: &RunContext,
+    path: PathBuf,
+    style: String,
+    width: usize,
+    from_width: Option<usize>,
+    recursive: bool,
+    dry_run: bool,
+    extensions: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    if width == 0 || from_width == Some(0) {
+        anyhow::bail!("Indentation width must be at least 1");
+    }
+
+    info!("Re-indenting with {} (width {}) in: {}", style, width, path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+
+    let mut options = WhitespaceOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        remove_trailing: false,
+        indent: Some(IndentOptions {
+            style: if style == "tabs" {
+                IndentStyle::Tabs
+            } else {
+                IndentStyle::Spaces
+            },
+            width,
+            source_width: from_width,
+        }),
+        recursive,
+        dry_run,
+        ..WhitespaceOptions::default()
+    };
+
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
+    }
+
+    let spinner = create_spinner("Re-indenting files...", ctx.color);
+    options.progress = track_progress(&spinner, ctx.color);
+
+    let cleaner = WhitespaceCleaner::new(options);
+    let (files, lines) = cleaner.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&cleaner.take_walk_errors());
+
+    if files > 0 {
+        let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+        info!("{}Re-indented {} lines in {} file(s)", prefix, lines, files);
+        summary!(ctx, "{}Re-indented {} lines in {} file(s)", prefix, lines, files);
+    } else {
+        info!("No files needed re-indenting");
+        summary!(ctx, "No files needed re-indenting");
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+#[time("info")]
+fn run_emojis(
+    ctx: &RunContext,
+    path: PathBuf,
+    stdin_filename: Option<PathBuf>,
+    recursive: bool,
+    dry_run: bool,
+    check: bool,
+    diff: bool,
+    extensions: Option<Vec<String>>,
+    replace_task: bool,
+    remove_other: bool,
+    no_update_anchors: bool,
+    filter: FilterArgs,
+    no_pager: bool,
+) -> anyhow::Result<()> {
+    let stdin_name = stdin_name(&path, stdin_filename)?;
+    let config = ctx.load_config(stdin_name.as_deref().unwrap_or(&path))?;
+    // Both flags are on by default, so only refmt.toml can turn them off
+    let replace_task = config.emojis.replace_task.unwrap_or(replace_task);
+    let remove_other = config.emojis.remove_other.unwrap_or(remove_other);
+    info!("Processing emojis from: {}", path.display());
+    info!("Recursive: {}, Dry run: {}, Check: {}", recursive, dry_run, check);
+    info!(
+        "Replace task emojis: {}, Remove other emojis: {}",
+        replace_task, remove_other
+    );
+
+    let extensions = filter.extensions(ExtensionSet::Emojis, extensions, &config)?;
+    debug!("File extensions: {:?}", extensions);
+    if filter.show(&extensions) {
+        return Ok(());
+    }
+
+    let mut options = EmojiOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        recursive,
+        dry_run: dry_run || check,
+        replace_task_emojis: replace_task,
+        remove_other_emojis: remove_other,
+        mappings: config.emoji_mappings(),
+        update_anchors: !no_update_anchors,
+        show_diff: diff,
+        color_diff: ctx.color,
+        ..EmojiOptions::default()
+    };
+    options.filter = filter.options(&ctx.exclude);
+    options.file_extensions = extensions;
+
+    let spinner = create_spinner("Transforming emojis...", ctx.color);
+    options.progress = track_progress(&spinner, ctx.color);
+
+    let transformer = EmojiTransformer::new(options);
+    if let Some(name) = stdin_name {
+        spinner.finish_and_clear();
+        return filter_stdin(&transformer, &name, check, "emoji transformation");
+    }
+    ctx.verify_idempotent(&path, recursive, &[&transformer])?;
+    let _pager = start_diff_pager(ctx, dry_run || check, diff, no_pager);
+    let (files, changes) = transformer.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&transformer.take_walk_errors());
+
+    if files > 0 {
+        let prefix = if dry_run || check { "[DRY-RUN] " } else { "" };
+        info!(
+            "{}Transformed emojis in {} file(s) ({} changes)",
+            prefix, files, changes
+        );
+        summary!(
+            ctx,
+            "{}Transformed emojis in {} file(s) ({} changes)",
+            prefix, files, changes
+        );
+    } else {
+        info!("No files contained emojis to transform");
+        summary!(ctx, "No files contained emojis to transform");
+    }
+
+    ctx.report_failed_files(&transformer.take_failed_files())?;
+
+    if check && files > 0 {
+        anyhow::bail!("{} file(s) contain emojis to transform", files);
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+#[time("info")]
+fn run_rename(
+    ctx: &RunContext,
+    path: PathBuf,
+    recursive: bool,
+    dry_run: bool,
+    to_lowercase: bool,
+    to_uppercase: bool,
+    to_capitalize: bool,
+    underscored: bool,
+    hyphenated: bool,
+    add_prefix: Option<String>,
+    rm_prefix: Option<String>,
+    add_suffix: Option<String>,
+    rm_suffix: Option<String>,
+    timestamp_long: bool,
+    timestamp_short: bool,
+    on_conflict: String,
+) -> anyhow::Result<()> {
+    info!("Renaming files in: {}", path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+
+    let mut options = RenameOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        recursive,
+        dry_run,
+        ..RenameOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
+
+    // refmt.toml decides when no flag does
+    let config = ctx.load_config(&path)?;
+    options.file_extensions = config.extensions.rename.clone();
+    if let Some(case) = config.rename.case {
+        options.case_transform = case;
+    }
+    if let Some(spaces) = config.rename.spaces {
+        options.space_replace = spaces;
+    }
+
+    // Set case transform (only one should be selected)
+    if to_lowercase {
+        options.case_transform = CaseTransform::Lowercase;
+        debug!("Case transform: Lowercase");
+    } else if to_uppercase {
+        options.case_transform = CaseTransform::Uppercase;
+        debug!("Case transform: Uppercase");
+    } else if to_capitalize {
+        options.case_transform = CaseTransform::Capitalize;
+        debug!("Case transform: Capitalize");
+    }
+
+    // Set separator replacement (only one should be selected)
+    if underscored {
+        options.space_replace = SpaceReplace::Underscore;
+        debug!("Separator replacement: Underscore");
+    } else if hyphenated {
+        options.space_replace = SpaceReplace::Hyphen;
+        debug!("Separator replacement: Hyphen");
+    }
+
+    // Set prefix/suffix options
+    options.add_prefix = add_prefix.clone();
+    options.remove_prefix = rm_prefix.clone();
+    options.add_suffix = add_suffix.clone();
+    options.remove_suffix = rm_suffix.clone();
+
+    // Set timestamp format (only one should be selected)
+    if timestamp_long {
+        options.timestamp_format = TimestampFormat::Long;
+        debug!("Timestamp format: Long (YYYYMMDD)");
+    } else if timestamp_short {
+        options.timestamp_format = TimestampFormat::Short;
+        debug!("Timestamp format: Short (YYMMDD)");
+    }
+
+    if let Some(ref prefix) = add_prefix {
+        debug!("Add prefix: '{}'", prefix);
+    }
+    if let Some(ref prefix) = rm_prefix {
+        debug!("Remove prefix: '{}'", prefix);
+    }
+    if let Some(ref suffix) = add_suffix {
+        debug!("Add suffix: '{}'", suffix);
+    }
+    if let Some(ref suffix) = rm_suffix {
+        debug!("Remove suffix: '{}'", suffix);
+    }
+
+    debug!("On conflict: {}", on_conflict);
+
+    let mut renamer = FileRenamer::new(options);
+
+    // Large runs are previewed and confirmed, unless --yes or nobody can answer
+    if ctx.can_confirm(dry_run) {
+        let plan = renamer.plan(&path)?;
+        // Renaming walks the same tree, and reports what it can't read
+        renamer.take_walk_errors();
+        let question = format!("{} files will be renamed. Continue?", plan.len());
+        if !ctx.confirm_large_run(&config, plan.len(), &question)? {
+            summary!(ctx, "Aborted; no files were renamed");
+            return Ok(());
+        }
+    }
+
+    // Prompts would be drawn over by the spinner
+    let spinner = if on_conflict == "prompt" {
+        ProgressBar::hidden()
+    } else {
+        create_spinner("Renaming files...", ctx.color)
+    };
+
+    match on_conflict.as_str() {
+        "skip" => renamer.set_conflict_handler(|_, _| ConflictResolution::Skip),
+        "overwrite" => renamer.set_conflict_handler(|_, _| ConflictResolution::Overwrite),
+        "number" => renamer.set_conflict_handler(|_, _| ConflictResolution::AutoNumber),
+        "prompt" => renamer.set_conflict_handler(prompt_conflict),
+        _ => {}
+    }
+    let count = renamer.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&renamer.take_walk_errors());
+
+    if count > 0 {
+        let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+        info!("{}Renamed {} file(s)", prefix, count);
+        summary!(ctx, "{}Renamed {} file(s)", prefix, count);
+    } else {
+        info!("No files needed renaming");
+        summary!(ctx, "No files needed renaming");
+    }
+
+    ctx.report_failed_files(&renamer.take_failed_files())
+}
+
+/// Asks how to resolve a rename conflict; skips when stdin is closed
+fn prompt_conflict(path: &Path, target: &Path) -> ConflictResolution {
+    let read_answer = |question: &str| -> Option<String> {
+        print!("{}", question);
+        std::io::Write::flush(&mut std::io::stdout()).ok()?;
+        let mut answer = String::new();
+        match std::io::stdin().read_line(&mut answer) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(answer.trim().to_string()),
+        }
+    };
+
+    let question = format!(
+        "'{}' already exists (renaming '{}'). [s]kip, [o]verwrite, auto-[n]umber, [r]ename? ",
+        target.display(),
+        path.display()
+    );
+    loop {
+        let Some(answer) = read_answer(&question) else {
+            return ConflictResolution::Skip;
+        };
+        match answer.to_lowercase().as_str() {
+            "s" | "skip" => return ConflictResolution::Skip,
+            "o" | "overwrite" => return ConflictResolution::Overwrite,
+            "n" | "number" => return ConflictResolution::AutoNumber,
+            "r" | "rename" => match read_answer("New name: ") {
+                Some(name) if !name.is_empty() => return ConflictResolution::Rename(name.into()),
+                Some(_) => continue,
+                None => return ConflictResolution::Skip,
+            },
+            _ => continue,
+        }
+    }
+}
+
+/// Runs touching at least this many files ask for confirmation first, unless
+/// `--confirm-threshold` or refmt.toml says otherwise
+const CONFIRM_FILES: usize = 50;
+
+/// Asks a yes/no question on the terminal; anything but `y`/`yes` declines
+fn confirm(question: &str) -> anyhow::Result<bool> {
+    print!("{} [y/N] ", question);
+    std::io::Write::flush(&mut std::io::stdout())?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Prints a table of the files the steps change, with a column per step: the new name
+/// for a rename, the number of changes for the others
+fn print_step_table(ctx: &RunContext, steps: &[&str], files: &[FileReport]) {
+    let mut rows: Vec<Vec<String>> = vec![std::iter::once("FILE")
+        .chain(steps.iter().copied())
+        .map(str::to_uppercase)
+        .collect()];
+    for file in files {
+        let mut row = vec![file.path.display().to_string()];
+        for &step in steps {
+            row.push(match (step, file.changes(step), &file.renamed_to) {
+                (_, None, _) => "-".to_string(),
+                ("rename", Some(_), Some(to)) => to.file_name().map_or_else(
+                    || to.display().to_string(),
+                    |n| n.to_string_lossy().into_owned(),
+                ),
+                (_, Some(changes), _) => changes.to_string(),
+            });
+        }
+        rows.push(row);
+    }
+
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    summary!(ctx, "");
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        summary!(ctx, "{}", cells.join("  ").trim_end());
+    }
+}
+
+/// The combined pipeline for the named steps, with their settings from refmt.toml
+fn combined_steps(
+    ctx: &RunContext,
+    names: &[String],
+    config: &Config,
+) -> anyhow::Result<Vec<CombinedStep>> {
+    let mut steps = Vec::new();
+    for name in names {
+        let step = match name.as_str() {
+            "rename" => {
+                let options = RenameOptions {
+                    fs: ctx.fs.clone(),
+                    events: ctx.output.events(),
+                    walk: ctx.walk,
+                    case_transform: config.rename.case.unwrap_or(CaseTransform::Lowercase),
+                    space_replace: config.rename.spaces.unwrap_or(SpaceReplace::None),
+                    file_extensions: config.extensions.rename.clone(),
+                    ..RenameOptions::default()
+                };
+                CombinedStep::Rename(options)
+            }
+            "emojis" => {
+                let mut options = EmojiOptions {
+                    fs: ctx.fs.clone(),
+                    events: ctx.output.events(),
+                    walk: ctx.walk,
+                    ..EmojiOptions::default()
+                };
+                options.replace_task_emojis =
+                    config.emojis.replace_task.unwrap_or(options.replace_task_emojis);
+                options.remove_other_emojis =
+                    config.emojis.remove_other.unwrap_or(options.remove_other_emojis);
+                options.mappings = config.emoji_mappings();
+                options.file_extensions = config.extensions(ExtensionSet::Emojis);
+                CombinedStep::Emojis(options)
+            }
+            "clean" => {
+                let options = WhitespaceOptions {
+                    fs: ctx.fs.clone(),
+                    events: ctx.output.events(),
+                    walk: ctx.walk,
+                    file_extensions: config.extensions(ExtensionSet::Whitespace),
+                    ..WhitespaceOptions::default()
+                };
+                CombinedStep::Clean(options)
+            }
+            _ => {
+                let Some(to) = config.convert.to.filter(|_| !config.convert.from.is_empty())
+                else {
+                    anyhow::bail!(
+                        "The convert step needs convert.from and convert.to in refmt.toml"
+                    );
+                };
+                let mut options = ConvertStepOptions::new(config.convert.from.clone(), to);
+                options.file_extensions = config.extensions(ExtensionSet::Convert);
+                options.excluded_words = config.convert.exclude_words.clone();
+                options.abbreviations = config.abbreviations();
+                options.unicode = config.convert.unicode;
+                CombinedStep::Convert(options)
+            }
+        };
+        steps.push(step);
+    }
+    Ok(steps)
+}
+
+#[allow(clippy::too_many_arguments)]
+#[time("info")]
+fn run_combined(
+    ctx: &RunContext,
+    path: PathBuf,
+    recursive: bool,
+    dry_run: bool,
+    check: bool,
+    steps: Option<Vec<String>>,
+    preset: Option<String>,
+    diff: bool,
+    no_pager: bool,
+) -> anyhow::Result<()> {
+    let mut config = ctx.load_config(&path)?;
+    if let Some(preset) = preset.or_else(|| config.preset.clone()) {
+        info!("Preset: {}", preset);
+        config = config.with_preset(&preset)?;
+    }
+    info!("Running combined transformations on: {}", path.display());
+    info!("Recursive: {}, Dry run: {}, Check: {}", recursive, dry_run, check);
+
+    let dry_run = dry_run || check;
+    let mut options = CombinedOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        recursive,
+        dry_run,
+        exclude: ctx.exclude.clone(),
+        show_diff: diff,
+        color_diff: ctx.color,
+        ..CombinedOptions::default()
+    };
+    let steps = steps.or_else(|| config.steps.clone()).unwrap_or_else(|| {
+        CombinedStep::defaults()
+            .iter()
+            .map(|step| step.name().to_string())
+            .collect()
+    });
+    options.steps = combined_steps(ctx, &steps, &config)?;
+
+    let processor = CombinedProcessor::new(options);
+    let step_names = processor.step_names();
+    info!("Steps: {}", step_names.join(", "));
+    ctx.verify_idempotent(&path, recursive, &processor.transforms())?;
+
+    // Large runs are previewed and confirmed, unless --yes or nobody can answer
+    if ctx.can_confirm(dry_run) {
+        let plan = processor.plan(&path)?;
+        // Processing walks the same tree, and reports what it can't read
+        processor.take_walk_errors();
+        debug!("Planned changes: {:?}", plan);
+        let question = format!(
+            "{} files will be modified, {} renamed. Continue?",
+            plan.files_modified, plan.files_renamed
+        );
+        let files = plan.files_modified + plan.files_renamed;
+        if !ctx.confirm_large_run(&config, files, &question)? {
+            summary!(ctx, "Aborted; no files were changed");
+            return Ok(());
+        }
+    }
+
+    let _pager = start_diff_pager(ctx, dry_run, diff, no_pager);
+    let spinner = create_spinner(
+        &format!("Processing files ({})...", step_names.join(", ")),
+        ctx.color,
+    );
+
+    let stats = processor.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&processor.take_walk_errors());
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+
+    // Print summary
+    if stats.files_renamed > 0 || stats.files_modified > 0 {
+        info!(
+            "{}Combined processing complete: {} renamed, {} emoji-transformed ({} changes), {} whitespace-cleaned ({} lines), {} converted ({} identifiers)",
+            prefix, stats.files_renamed, stats.files_emoji_transformed, stats.emoji_changes,
+            stats.files_whitespace_cleaned, stats.whitespace_lines_cleaned,
+            stats.files_converted, stats.identifiers_converted
+        );
+        summary!(
+            ctx,
+            "{}Processed files:",
+            prefix
+        );
+        if stats.files_renamed > 0 {
+            summary!(ctx, "  - Renamed: {} file(s)", stats.files_renamed);
+        }
+        if stats.files_emoji_transformed > 0 {
+            summary!(
+                ctx,
+                "  - Emoji transformations: {} file(s) ({} changes)",
+                stats.files_emoji_transformed, stats.emoji_changes
+            );
+        }
+        if stats.files_whitespace_cleaned > 0 {
+            summary!(
+                ctx,
+                "  - Whitespace cleaned: {} file(s) ({} lines)",
+                stats.files_whitespace_cleaned, stats.whitespace_lines_cleaned
+            );
+        }
+        if stats.files_converted > 0 {
+            summary!(
+                ctx,
+                "  - Identifiers converted: {} file(s) ({} identifiers)",
+                stats.files_converted, stats.identifiers_converted
+            );
+        }
+    } else {
+        info!("No files needed processing");
+        summary!(ctx, "No files needed processing");
+    }
+
+    // What each step would do to each file, for review
+    if dry_run && !stats.files.is_empty() {
+        if ctx.output.is_json() {
+            ctx.output.report(serde_json::to_value(&stats.files)?);
+        } else {
+            print_step_table(ctx, &step_names, &stats.files);
+        }
+    }
+
+    ctx.report_failed_files(&processor.take_failed_files())?;
+
+    if check && stats.files_renamed + stats.files_modified > 0 {
+        anyhow::bail!(
+            "{} file(s) need renaming, {} need changes",
+            stats.files_renamed,
+            stats.files_modified
+        );
+    }
+
+    Ok(())
+}
+
+#[time("info")]
+fn run_eol(
+    ctx: &RunContext,
+    path: PathBuf,
+    to: String,
+    recursive: bool,
+    dry_run: bool,
+    extensions: Option<Vec<String>>,
+    no_gitattributes: bool,
+) -> anyhow::Result<()> {
+    info!("Converting line endings to {} in: {}", to, path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+
+    let mut options = EolOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        target: if to == "crlf" {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        },
+        recursive,
+        dry_run,
+        ..EolOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
+    options.respect_gitattributes = !no_gitattributes;
+
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
+    }
+
+    let spinner = create_spinner("Converting line endings...", ctx.color);
+
+    let converter = EolConverter::new(options);
+    let stats = converter.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&converter.take_walk_errors());
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if stats.files_converted > 0 {
+        info!(
+            "{}Converted {} line endings in {} file(s)",
+            prefix, stats.lines_converted, stats.files_converted
+        );
+        summary!(
+            ctx,
+            "{}Converted {} line endings in {} file(s)",
+            prefix, stats.lines_converted, stats.files_converted
+        );
+    } else {
+        info!("No files needed line ending conversion");
+        summary!(ctx, "No files needed line ending conversion");
+    }
+    if stats.binary_skipped > 0 {
+        summary!(ctx, "  - Skipped {} binary file(s)", stats.binary_skipped);
+    }
+    if stats.attributes_skipped > 0 {
+        summary!(
+            ctx,
+            "  - Skipped {} file(s) marked binary or -text in .gitattributes",
+            stats.attributes_skipped
+        );
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+#[time("info")]
+fn run_header(
+    ctx: &RunContext,
+    path: PathBuf,
+    recursive: bool,
+    dry_run: bool,
+    extensions: Option<Vec<String>>,
+    template: Option<String>,
+    template_file: Option<PathBuf>,
+    holder: Option<String>,
+    license: Option<String>,
+    year: Option<u64>,
+    remove: bool,
+    spdx: bool,
+    check: bool,
+) -> anyhow::Result<()> {
+    info!("Managing headers in: {}", path.display());
+    info!("Recursive: {}, Dry run: {}, Check: {}", recursive, dry_run, check);
+
+    let mut options = HeaderOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        recursive,
+        dry_run: dry_run || check,
+        ..HeaderOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
+    options.action = if remove {
+        HeaderAction::Remove
+    } else if spdx {
+        HeaderAction::Spdx
+    } else {
+        HeaderAction::Apply
+    };
+
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
+    }
+
+    options.template = match (template, template_file) {
+        (Some(template), _) => template,
+        (None, Some(file)) => std::fs::read_to_string(&file).map_err(|e| {
+            anyhow::anyhow!("Failed to read template '{}': {}", file.display(), e)
+        })?,
+        // The default template gains an SPDX line when a license is given
+        (None, None) if license.is_some() => {
+            format!("{}\nSPDX-License-Identifier: {{license}}", header::DEFAULT_TEMPLATE)
+        }
+        (None, None) => header::DEFAULT_TEMPLATE.to_string(),
+    };
+    debug!("Header template: {:?}", options.template);
+
+    if !remove && !spdx {
+        if options.template.contains("{holder}") && holder.is_none() {
+            anyhow::bail!("The header template uses {{holder}}; pass --holder");
+        }
+        if options.template.contains("{license}") && license.is_none() {
+            anyhow::bail!("The header template uses {{license}}; pass --license");
+        }
+    }
+
+    options.holder = holder.unwrap_or_default();
+    options.license = license.unwrap_or_default();
+    if let Some(year) = year {
+        options.year = year;
+    }
+
+    let spinner = create_spinner("Processing headers...", ctx.color);
+
+    let manager = HeaderManager::new(options);
+    let stats = manager.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&manager.take_walk_errors());
+
+    let prefix = if dry_run || check { "[DRY-RUN] " } else { "" };
+    if stats.total() > 0 {
+        info!(
+            "{}Headers: {} added, {} updated, {} removed",
+            prefix, stats.inserted, stats.updated, stats.removed
+        );
+        summary!(
+            ctx,
+            "{}Headers: {} added, {} updated, {} removed",
+            prefix, stats.inserted, stats.updated, stats.removed
+        );
+    } else {
+        info!("All headers are up to date");
+        summary!(ctx, "All headers are up to date");
+    }
+
+    if check && stats.total() > 0 {
+        anyhow::bail!("{} file(s) need header changes", stats.total());
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+#[time("info")]
+fn run_guards(
+    ctx: &RunContext,
+    path: PathBuf,
+    format: CaseFormat,
+    prefix: String,
+    suffix: String,
+    path_components: bool,
+    recursive: bool,
+    dry_run: bool,
+    extensions: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    // Guards are C identifiers
+    check_format(
+        "guards",
+        format,
+        &[
+            CaseFormat::ScreamingSnakeCase,
+            CaseFormat::SnakeCase,
+            CaseFormat::CamelCase,
+            CaseFormat::PascalCase,
+        ],
+    )?;
+    info!("Renaming include guards in: {}", path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+
+    let mut options = GuardOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        format,
+        prefix,
+        suffix,
+        path_components,
+        recursive,
+        dry_run,
+        ..GuardOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
+
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
+    }
+
+    let renamer = GuardRenamer::new(options);
+    let renamed = renamer.process(&path)?;
+    report_walk_errors(&renamer.take_walk_errors());
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if renamed > 0 {
+        info!("{}Renamed {} include guard(s)", prefix, renamed);
+        summary!(ctx, "{}Renamed {} include guard(s)", prefix, renamed);
+    } else {
+        info!("All include guards are up to date");
+        summary!(ctx, "All include guards are up to date");
+    }
+
+    Ok(())
+}
+
+/// The formats keys and schema fields can be renamed to
+const KEY_FORMATS: [CaseFormat; 6] = [
+    CaseFormat::CamelCase,
+    CaseFormat::PascalCase,
+    CaseFormat::SnakeCase,
+    CaseFormat::ScreamingSnakeCase,
+    CaseFormat::KebabCase,
+    CaseFormat::ScreamingKebabCase,
+];
+
+/// Fails unless `command` can write names in `format`
+fn check_format(
+    command: &str,
+    format: CaseFormat,
+    supported: &[CaseFormat],
+) -> anyhow::Result<()> {
+    if supported.contains(&format) {
+        return Ok(());
+    }
+    let names: Vec<&str> = supported.iter().map(CaseFormat::name).collect();
+    anyhow::bail!(
+        "{} can't write {} names; use one of: {}",
+        command,
+        format,
+        names.join(", ")
+    )
+}
+
+/// Pages dry-run diffs, which can run long, the way git does
+fn start_diff_pager(
+    ctx: &RunContext,
+    dry_run: bool,
+    diff: bool,
+    no_pager: bool,
+) -> Option<pager::Pager> {
+    if dry_run && diff && !no_pager && !ctx.output.is_json() {
+        pager::start()
+    } else {
+        None
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[time("info")]
+fn run_comments(
+    ctx: &RunContext,
+    path: PathBuf,
+    to: Option<String>,
+    banners: bool,
+    width: usize,
+    recursive: bool,
+    dry_run: bool,
+    extensions: Option<Vec<String>>,
+    diff: bool,
+    no_pager: bool,
+) -> anyhow::Result<()> {
+    if to.is_none() && !banners {
+        anyhow::bail!("Nothing to do; pass --to line|block and/or --banners");
+    }
+
+    info!("Converting comments in: {}", path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+
+    let mut options = CommentOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        target: to.map(|to| {
+            if to == "block" {
+                CommentStyle::Block
+            } else {
+                CommentStyle::Line
+            }
+        }),
+        normalize_banners: banners,
+        banner_width: width,
+        show_diff: diff,
+        color_diff: ctx.color,
+        recursive,
+        dry_run,
+        ..CommentOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
+
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
+    }
+
+    let _pager = start_diff_pager(ctx, dry_run, diff, no_pager);
+
+    let converter = CommentConverter::new(options);
+    let (files, comments) = converter.process(&path)?;
+    report_walk_errors(&converter.take_walk_errors());
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if files > 0 {
+        info!("{}Converted {} comments in {} file(s)", prefix, comments, files);
+        summary!(ctx, "{}Converted {} comments in {} file(s)", prefix, comments, files);
+    } else {
+        info!("No comments needed conversion");
+        summary!(ctx, "No comments needed conversion");
+    }
+
+    Ok(())
+}
+
+#[time("info")]
+fn run_quotes(
+    ctx: &RunContext,
+    path: PathBuf,
+    straighten: bool,
+    curl: bool,
+    recursive: bool,
+    dry_run: bool,
+    extensions: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    if !straighten && !curl {
+        anyhow::bail!("Nothing to do; pass --straighten or --curl");
+    }
+
+    info!("Normalizing quotes in: {}", path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+
+    let mut options = QuoteOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        target: if curl {
+            QuoteStyle::Curly
+        } else {
+            QuoteStyle::Straight
+        },
+        recursive,
+        dry_run,
+        ..QuoteOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
+
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
+    }
+
+    let spinner = create_spinner("Normalizing quotes...", ctx.color);
+
+    let transformer = QuoteTransformer::new(options);
+    let (files, quotes) = transformer.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&transformer.take_walk_errors());
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if files > 0 {
+        info!("{}Normalized {} quotes in {} file(s)", prefix, quotes, files);
+        summary!(ctx, "{}Normalized {} quotes in {} file(s)", prefix, quotes, files);
+    } else {
+        info!("No files needed quote normalization");
+        summary!(ctx, "No files needed quote normalization");
     }
-    if let Some(ref filter) = word_filter {
-        debug!("Word filter: '{}'", filter);
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+#[time("info")]
+fn run_punctuation(
+    ctx: &RunContext,
+    path: PathBuf,
+    em_dash: String,
+    en_dash: String,
+    ellipsis: String,
+    keep_fullwidth: bool,
+    recursive: bool,
+    dry_run: bool,
+    extensions: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    info!("Normalizing punctuation in: {}", path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+
+    let mut options = PunctuationOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        em_dash,
+        en_dash,
+        ellipsis,
+        fullwidth: !keep_fullwidth,
+        recursive,
+        dry_run,
+        ..PunctuationOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
+
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
+    }
+
+    let spinner = create_spinner("Normalizing punctuation...", ctx.color);
+
+    let normalizer = PunctuationNormalizer::new(options);
+    let (files, replaced) = normalizer.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&normalizer.take_walk_errors());
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if files > 0 {
+        info!(
+            "{}Replaced {} punctuation characters in {} file(s)",
+            prefix, replaced, files
+        );
+        summary!(
+            ctx,
+            "{}Replaced {} punctuation characters in {} file(s)",
+            prefix, replaced, files
+        );
+    } else {
+        info!("No files needed punctuation normalization");
+        summary!(ctx, "No files needed punctuation normalization");
+    }
+
+    Ok(())
+}
+
+#[time("info")]
+fn run_numbers(
+    ctx: &RunContext,
+    path: PathBuf,
+    min_digits: usize,
+    radix: bool,
+    recursive: bool,
+    dry_run: bool,
+    extensions: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    if min_digits < 2 {
+        anyhow::bail!("--min-digits must be at least 2");
+    }
+
+    info!("Formatting numeric literals in: {}", path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+
+    let mut options = NumberOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        min_digits,
+        radix_literals: radix,
+        recursive,
+        dry_run,
+        ..NumberOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
+
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
+    }
+
+    let spinner = create_spinner("Formatting numeric literals...", ctx.color);
+
+    let formatter = NumberFormatter::new(options);
+    let (files, literals) = formatter.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&formatter.take_walk_errors());
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if files > 0 {
+        info!("{}Formatted {} numeric literals in {} file(s)", prefix, literals, files);
+        summary!(ctx, "{}Formatted {} numeric literals in {} file(s)", prefix, literals, files);
+    } else {
+        info!("No numeric literals needed digit separators");
+        summary!(ctx, "No numeric literals needed digit separators");
+    }
+
+    Ok(())
+}
+
+#[time("info")]
+fn run_convert_keys(
+    ctx: &RunContext,
+    path: PathBuf,
+    to: CaseFormat,
+    format: Option<String>,
+    recursive: bool,
+    dry_run: bool,
+    extensions: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    check_format("convert-keys", to, &KEY_FORMATS)?;
+    info!("Converting keys to {} in: {}", to, path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+
+    let mut options = KeyOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        target: to,
+        format: format.as_deref().and_then(KeyFormat::from_name),
+        recursive,
+        dry_run,
+        ..KeyOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
+
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
+    } else if let Some(format) = &format {
+        // Only the requested format's files
+        options.file_extensions.retain(|ext| KeyFormat::from_name(&ext[1..]) == options.format);
+        debug!("File extensions for {}: {:?}", format, options.file_extensions);
+    }
+
+    let spinner = create_spinner("Converting keys...", ctx.color);
+
+    let converter = KeyConverter::new(options);
+    let (files, keys) = converter.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&converter.take_walk_errors());
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if files > 0 {
+        info!("{}Renamed {} keys in {} file(s)", prefix, keys, files);
+        summary!(ctx, "{}Renamed {} keys in {} file(s)", prefix, keys, files);
+    } else {
+        info!("No keys needed converting");
+        summary!(ctx, "No keys needed converting");
+    }
+
+    Ok(())
+}
+
+#[time("info")]
+fn run_schema_fields(
+    ctx: &RunContext,
+    path: PathBuf,
+    to: CaseFormat,
+    mapping: Option<PathBuf>,
+    recursive: bool,
+    dry_run: bool,
+    extensions: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    check_format("schema-fields", to, &KEY_FORMATS)?;
+    info!("Renaming schema fields to {} in: {}", to, path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+
+    let mut options = SchemaOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        target: to,
+        recursive,
+        dry_run,
+        ..SchemaOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
+
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
+    }
+
+    let spinner = create_spinner("Renaming schema fields...", ctx.color);
+
+    let renamer = FieldRenamer::new(options);
+    let stats = renamer.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&renamer.take_walk_errors());
+
+    // The mapping is written even in dry-run mode, so it can be reviewed first
+    if let Some(mapping) = mapping {
+        let json = stats.mapping_json()?;
+        if mapping.as_os_str() == "-" {
+            print!("{}", json);
+        } else {
+            std::fs::write(&mapping, json)?;
+            info!("Wrote field mapping to {}", mapping.display());
+        }
+    }
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if stats.files_changed > 0 {
+        let message = format!(
+            "{}Renamed {} fields in {} file(s)",
+            prefix,
+            stats.renames.len(),
+            stats.files_changed
+        );
+        info!("{}", message);
+        summary!(ctx, "{}", message);
+    } else {
+        info!("No schema fields needed renaming");
+        summary!(ctx, "No schema fields needed renaming");
+    }
+
+    Ok(())
+}
+
+#[time("info")]
+fn run_dotenv(
+    ctx: &RunContext,
+    path: PathBuf,
+    recursive: bool,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    info!("Normalizing .env files in: {}", path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+
+    let options = DotenvOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        recursive,
+        dry_run,
+    };
+
+    let spinner = create_spinner("Normalizing .env files...", ctx.color);
+
+    let normalizer = DotenvNormalizer::new(options);
+    let stats = normalizer.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&normalizer.take_walk_errors());
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if stats.files_changed > 0 {
+        info!(
+            "{}Normalized {} lines in {} file(s)",
+            prefix, stats.lines_changed, stats.files_changed
+        );
+        summary!(
+            ctx,
+            "{}Normalized {} lines in {} file(s)",
+            prefix, stats.lines_changed, stats.files_changed
+        );
+    } else {
+        info!("No .env files needed normalizing");
+        summary!(ctx, "No .env files needed normalizing");
+    }
+    if !stats.duplicates.is_empty() {
+        summary!(ctx, "  - Duplicate keys: {}", stats.duplicates.len());
+        ctx.output.report(serde_json::to_value(&stats.duplicates)?);
+    }
+
+    Ok(())
+}
+
+#[time("info")]
+fn run_sql(
+    ctx: &RunContext,
+    path: PathBuf,
+    keywords: String,
+    recursive: bool,
+    dry_run: bool,
+    extensions: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    info!("Converting SQL keywords to {}case in: {}", keywords, path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+
+    let mut options = SqlOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        keywords: if keywords == "lower" {
+            KeywordCase::Lower
+        } else {
+            KeywordCase::Upper
+        },
+        recursive,
+        dry_run,
+        ..SqlOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
+
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
+    }
+
+    let spinner = create_spinner("Normalizing SQL keywords...", ctx.color);
+
+    let formatter = SqlFormatter::new(options);
+    let (files, changes) = formatter.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&formatter.take_walk_errors());
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if files > 0 {
+        info!("{}Recased {} keywords in {} file(s)", prefix, changes, files);
+        summary!(ctx, "{}Recased {} keywords in {} file(s)", prefix, changes, files);
+    } else {
+        info!("No SQL keywords needed recasing");
+        summary!(ctx, "No SQL keywords needed recasing");
+    }
+
+    Ok(())
+}
+
+#[time("info")]
+fn run_class_names(
+    ctx: &RunContext,
+    path: PathBuf,
+    to: CaseFormat,
+    recursive: bool,
+    dry_run: bool,
+    extensions: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    // Other separators aren't valid in CSS names, and other capitalizations are unusual
+    check_format("class-names", to, &[CaseFormat::KebabCase, CaseFormat::CamelCase])?;
+    info!("Converting class names to {} case in: {}", to, path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+
+    let mut options = ClassNameOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        target: to,
+        recursive,
+        dry_run,
+        ..ClassNameOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
+
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
+    }
+
+    let spinner = create_spinner("Converting class names...", ctx.color);
+
+    let converter = ClassNameConverter::new(options);
+    let stats = converter.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&converter.take_walk_errors());
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if stats.files_changed > 0 {
+        let message = format!(
+            "{}Renamed {} class/id names ({} references in {} file(s))",
+            prefix, stats.names_renamed, stats.references_updated, stats.files_changed
+        );
+        info!("{}", message);
+        summary!(ctx, "{}", message);
+    } else {
+        info!("No class names needed converting");
+        summary!(ctx, "No class names needed converting");
+    }
+
+    Ok(())
+}
+
+#[time("info")]
+fn run_string_quotes(
+    ctx: &RunContext,
+    path: PathBuf,
+    to: String,
+    recursive: bool,
+    dry_run: bool,
+    extensions: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    info!("Converting string literals to {} quotes in: {}", to, path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+
+    let mut options = StringQuoteOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        target: if to == "single" {
+            StringQuote::Single
+        } else {
+            StringQuote::Double
+        },
+        recursive,
+        dry_run,
+        ..StringQuoteOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
+
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
+    }
+
+    let spinner = create_spinner("Converting string literals...", ctx.color);
+
+    let converter = StringQuoteConverter::new(options);
+    let (files, literals) = converter.process(&path)?;
+
+    spinner.finish_and_clear();
+    report_walk_errors(&converter.take_walk_errors());
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if files > 0 {
+        info!("{}Converted {} string literals in {} file(s)", prefix, literals, files);
+        summary!(ctx, "{}Converted {} string literals in {} file(s)", prefix, literals, files);
+    } else {
+        info!("No string literals needed converting");
+        summary!(ctx, "No string literals needed converting");
     }
 
-    let spinner = create_spinner("Processing files...");
+    Ok(())
+}
+
+#[time("info")]
+fn run_shebang(
+    ctx: &RunContext,
+    path: PathBuf,
+    no_env: bool,
+    no_permissions: bool,
+    recursive: bool,
+    dry_run: bool,
+    extensions: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    info!("Normalizing shebangs in: {}", path.display());
+    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
 
-    let converter = CaseConverter::new(
-        from_format,
-        to_format,
-        extensions,
+    let mut options = ShebangOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        use_env: !no_env,
+        fix_permissions: !no_permissions,
         recursive,
         dry_run,
-        prefix,
-        suffix,
-        strip_prefix,
-        strip_suffix,
-        replace_prefix_from,
-        replace_prefix_to,
-        replace_suffix_from,
-        replace_suffix_to,
-        glob,
-        word_filter,
-    )?;
+        ..ShebangOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
 
-    let result = converter.process_directory(&path);
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
+    }
+
+    let spinner = create_spinner("Normalizing shebangs...", ctx.color);
+
+    let normalizer = ShebangNormalizer::new(options);
+    let stats = normalizer.process(&path)?;
 
     spinner.finish_and_clear();
+    report_walk_errors(&normalizer.take_walk_errors());
 
-    match result {
-        Ok(_) => {
-            info!("Conversion completed successfully");
-            Ok(())
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if stats.files_changed > 0 {
+        info!("{}Fixed {} file(s)", prefix, stats.files_changed);
+        summary!(ctx, "{}Fixed {} file(s)", prefix, stats.files_changed);
+        if stats.shebangs_rewritten > 0 {
+            summary!(ctx, "  - Shebangs rewritten: {}", stats.shebangs_rewritten);
         }
-        Err(e) => {
-            error!("Conversion failed: {}", e);
-            Err(e)
+        if stats.line_endings_fixed > 0 {
+            summary!(ctx, "  - CRLF after shebang: {}", stats.line_endings_fixed);
         }
+        if stats.permissions_fixed > 0 {
+            summary!(ctx, "  - Executable bits fixed: {}", stats.permissions_fixed);
+        }
+    } else {
+        info!("No shebangs needed fixing");
+        summary!(ctx, "No shebangs needed fixing");
     }
+
+    Ok(())
 }
 
 #[time("info")]
-fn run_clean(
+fn run_sort_imports(
+    ctx: &RunContext,
     path: PathBuf,
     recursive: bool,
     dry_run: bool,
     extensions: Option<Vec<String>>,
+    check: bool,
 ) -> anyhow::Result<()> {
-    info!("Cleaning whitespace from: {}", path.display());
-    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
-
-    if let Some(ref exts) = extensions {
-        debug!("File extensions: {:?}", exts);
-    }
+    info!("Sorting imports in: {}", path.display());
+    info!("Recursive: {}, Dry run: {}, Check: {}", recursive, dry_run, check);
 
-    let mut options = WhitespaceOptions::default();
-    options.recursive = recursive;
-    options.dry_run = dry_run;
+    let mut options = ImportOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        recursive,
+        dry_run: dry_run || check,
+        ..ImportOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
 
     if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
         options.file_extensions = exts;
     }
 
-    let spinner = create_spinner("Cleaning files...");
+    let spinner = create_spinner("Sorting imports...", ctx.color);
 
-    let cleaner = WhitespaceCleaner::new(options);
-    let (files, lines) = cleaner.process(&path)?;
+    let sorter = ImportSorter::new(options);
+    let (files, blocks) = sorter.process(&path)?;
 
     spinner.finish_and_clear();
+    report_walk_errors(&sorter.take_walk_errors());
 
+    let prefix = if dry_run || check { "[DRY-RUN] " } else { "" };
     if files > 0 {
-        let prefix = if dry_run { "[DRY-RUN] " } else { "" };
-        info!(
-            "{}Cleaned {} lines in {} file(s)",
-            prefix, lines, files
-        );
-        println!(
-            "{}Cleaned {} lines in {} file(s)",
-            prefix, lines, files
-        );
+        info!("{}Sorted {} import blocks in {} file(s)", prefix, blocks, files);
+        summary!(ctx, "{}Sorted {} import blocks in {} file(s)", prefix, blocks, files);
     } else {
-        info!("No files needed cleaning");
-        println!("No files needed cleaning");
+        info!("All imports are sorted");
+        summary!(ctx, "All imports are sorted");
+    }
+
+    if check && files > 0 {
+        anyhow::bail!("{} file(s) have unsorted imports", files);
     }
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 #[time("info")]
-fn run_emojis(
+fn run_check_names(
+    ctx: &RunContext,
     path: PathBuf,
+    file_case: Option<String>,
+    fix_filename: bool,
+    fix_identifier: bool,
     recursive: bool,
     dry_run: bool,
     extensions: Option<Vec<String>>,
-    replace_task: bool,
-    remove_other: bool,
 ) -> anyhow::Result<()> {
-    info!("Processing emojis from: {}", path.display());
+    info!("Checking file names in: {}", path.display());
     info!("Recursive: {}, Dry run: {}", recursive, dry_run);
-    info!(
-        "Replace task emojis: {}, Remove other emojis: {}",
-        replace_task, remove_other
-    );
-
-    if let Some(ref exts) = extensions {
-        debug!("File extensions: {:?}", exts);
-    }
 
-    let mut options = EmojiOptions::default();
-    options.recursive = recursive;
-    options.dry_run = dry_run;
-    options.replace_task_emojis = replace_task;
-    options.remove_other_emojis = remove_other;
+    let mut options = FilenameOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        file_format: file_case.map(|case| match case.as_str() {
+            "snake" => CaseFormat::SnakeCase,
+            "kebab" => CaseFormat::KebabCase,
+            "camel" => CaseFormat::CamelCase,
+            _ => CaseFormat::PascalCase,
+        }),
+        fix: if fix_filename {
+            FilenameFix::RenameFile
+        } else if fix_identifier {
+            FilenameFix::RenameIdentifier
+        } else {
+            FilenameFix::None
+        },
+        recursive,
+        dry_run,
+        ..FilenameOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
 
     if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
         options.file_extensions = exts;
     }
 
-    let spinner = create_spinner("Transforming emojis...");
+    let checker = FilenameChecker::new(options);
+    let mismatches = checker.process(&path)?;
+    report_walk_errors(&checker.take_walk_errors());
 
-    let transformer = EmojiTransformer::new(options);
-    let (files, changes) = transformer.process(&path)?;
-
-    spinner.finish_and_clear();
+    if mismatches.is_empty() {
+        info!("All file names match their identifiers");
+        summary!(ctx, "All file names match their identifiers");
+        return Ok(());
+    }
 
-    if files > 0 {
-        let prefix = if dry_run { "[DRY-RUN] " } else { "" };
-        info!(
-            "{}Transformed emojis in {} file(s) ({} changes)",
-            prefix, files, changes
-        );
-        println!(
-            "{}Transformed emojis in {} file(s) ({} changes)",
-            prefix, files, changes
+    let (fixed, remaining): (Vec<_>, Vec<_>) =
+        mismatches.iter().partition(|mismatch| mismatch.fixed);
+    for mismatch in &remaining {
+        let hint = match mismatch.expected_identifier {
+            Some(ref identifier) => format!(" (or rename it to {})", identifier),
+            None => String::new(),
+        };
+        summary!(
+            ctx,
+            "{}: {} belongs in '{}'{}",
+            mismatch.path.display(),
+            mismatch.identifier,
+            mismatch.expected_file_name,
+            hint
         );
-    } else {
-        info!("No files contained emojis to transform");
-        println!("No files contained emojis to transform");
+    }
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if !fixed.is_empty() {
+        info!("{}Fixed {} file name mismatch(es)", prefix, fixed.len());
+        summary!(ctx, "{}Fixed {} file name mismatch(es)", prefix, fixed.len());
+    }
+    if !remaining.is_empty() {
+        info!("Found {} file name mismatch(es)", remaining.len());
+        summary!(ctx, "Found {} file name mismatch(es)", remaining.len());
+        anyhow::bail!("{} file name mismatch(es)", remaining.len());
     }
 
     Ok(())
 }
 
 #[time("info")]
-fn run_rename(
+fn run_naming(
+    ctx: &RunContext,
     path: PathBuf,
+    lang: String,
+    fix: bool,
     recursive: bool,
     dry_run: bool,
-    to_lowercase: bool,
-    to_uppercase: bool,
-    to_capitalize: bool,
-    underscored: bool,
-    hyphenated: bool,
-    add_prefix: Option<String>,
-    rm_prefix: Option<String>,
-    add_suffix: Option<String>,
-    rm_suffix: Option<String>,
-    timestamp_long: bool,
-    timestamp_short: bool,
+    extensions: Option<Vec<String>>,
 ) -> anyhow::Result<()> {
-    info!("Renaming files in: {}", path.display());
-    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
+    info!("Checking {} naming conventions in: {}", lang, path.display());
+    info!("Recursive: {}, Dry run: {}, Fix: {}", recursive, dry_run, fix);
 
-    let mut options = RenameOptions::default();
-    options.recursive = recursive;
-    options.dry_run = dry_run;
+    let language = NamingLanguage::from_name(&lang)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported language: {}", lang))?;
 
-    // Set case transform (only one should be selected)
-    if to_lowercase {
-        options.case_transform = CaseTransform::Lowercase;
-        debug!("Case transform: Lowercase");
-    } else if to_uppercase {
-        options.case_transform = CaseTransform::Uppercase;
-        debug!("Case transform: Uppercase");
-    } else if to_capitalize {
-        options.case_transform = CaseTransform::Capitalize;
-        debug!("Case transform: Capitalize");
-    }
+    let mut options = NamingOptions {
+        fs: ctx.fs.clone(),
+        events: ctx.output.events(),
+        walk: ctx.walk,
+        language,
+        file_extensions: language.file_extensions(),
+        fix,
+        recursive,
+        dry_run,
+        ..NamingOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
 
-    // Set separator replacement (only one should be selected)
-    if underscored {
-        options.space_replace = SpaceReplace::Underscore;
-        debug!("Separator replacement: Underscore");
-    } else if hyphenated {
-        options.space_replace = SpaceReplace::Hyphen;
-        debug!("Separator replacement: Hyphen");
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
     }
 
-    // Set prefix/suffix options
-    options.add_prefix = add_prefix.clone();
-    options.remove_prefix = rm_prefix.clone();
-    options.add_suffix = add_suffix.clone();
-    options.remove_suffix = rm_suffix.clone();
+    let checker = NamingChecker::new(options);
+    let violations = checker.process(&path)?;
+    report_walk_errors(&checker.take_walk_errors());
 
-    // Set timestamp format (only one should be selected)
-    if timestamp_long {
-        options.timestamp_format = TimestampFormat::Long;
-        debug!("Timestamp format: Long (YYYYMMDD)");
-    } else if timestamp_short {
-        options.timestamp_format = TimestampFormat::Short;
-        debug!("Timestamp format: Short (YYMMDD)");
+    if violations.is_empty() {
+        info!("No naming violations found");
+        summary!(ctx, "No naming violations found");
+        return Ok(());
     }
 
-    if let Some(ref prefix) = add_prefix {
-        debug!("Add prefix: '{}'", prefix);
-    }
-    if let Some(ref prefix) = rm_prefix {
-        debug!("Remove prefix: '{}'", prefix);
+    if ctx.output.is_json() {
+        ctx.output.report(serde_json::to_value(&violations)?);
+    } else if !fix {
+        for violation in &violations {
+            println!(
+                "{}:{}:{}: {} `{}` should be `{}` ({})",
+                violation.path.display(),
+                violation.line,
+                violation.column,
+                violation.kind,
+                violation.name,
+                violation.expected,
+                violation.kind.lint()
+            );
+        }
     }
-    if let Some(ref suffix) = add_suffix {
-        debug!("Add suffix: '{}'", suffix);
+
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    info!("{}Found {} naming violation(s)", prefix, violations.len());
+    summary!(ctx, "{}Found {} naming violation(s)", prefix, violations.len());
+
+    if !fix {
+        anyhow::bail!("{} naming violation(s)", violations.len());
     }
-    if let Some(ref suffix) = rm_suffix {
-        debug!("Remove suffix: '{}'", suffix);
+
+    Ok(())
+}
+
+#[time("info")]
+fn run_ascii_check(
+    ctx: &RunContext,
+    path: PathBuf,
+    recursive: bool,
+    extensions: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    info!("Scanning for non-ASCII characters in: {}", path.display());
+    info!("Recursive: {}", recursive);
+
+    let mut options = AsciiOptions {
+        fs: ctx.fs.clone(),
+        walk: ctx.walk,
+        recursive,
+        ..AsciiOptions::default()
+    };
+    options.filter.exclude = ctx.exclude.clone();
+
+    if let Some(exts) = extensions {
+        debug!("File extensions: {:?}", exts);
+        options.file_extensions = exts;
     }
 
-    let spinner = create_spinner("Renaming files...");
+    let checker = AsciiChecker::new(options);
+    let findings = checker.process(&path)?;
+    report_walk_errors(&checker.take_walk_errors());
 
-    let renamer = FileRenamer::new(options);
-    let count = renamer.process(&path)?;
+    let mut by_category = std::collections::BTreeMap::new();
+    for file in &findings {
+        for found in &file.chars {
+            *by_category.entry(found.category).or_insert(0usize) += 1;
+            if ctx.output.is_json() {
+                continue;
+            }
+            println!(
+                "{}:{}:{}: U+{:04X} '{}' ({})",
+                file.path.display(),
+                found.line,
+                found.column,
+                found.ch as u32,
+                found.display_char(),
+                found.category
+            );
+        }
+    }
 
-    spinner.finish_and_clear();
+    if findings.is_empty() {
+        info!("No non-ASCII characters found");
+        summary!(ctx, "No non-ASCII characters found");
+        return Ok(());
+    }
 
-    if count > 0 {
-        let prefix = if dry_run { "[DRY-RUN] " } else { "" };
-        info!("{}Renamed {} file(s)", prefix, count);
-        println!("{}Renamed {} file(s)", prefix, count);
+    if ctx.output.is_json() {
+        ctx.output.report(serde_json::to_value(&findings)?);
     } else {
-        info!("No files needed renaming");
-        println!("No files needed renaming");
+        println!();
+    }
+    let total: usize = by_category.values().sum();
+    summary!(
+        ctx,
+        "Found {} non-ASCII character(s) in {} file(s)",
+        total,
+        findings.len()
+    );
+    for (category, count) in by_category {
+        let hint = match category {
+            CharCategory::Emoji => " (see `refmt emojis`)",
+            CharCategory::Punctuation => " (see `refmt quotes` and `refmt punctuation`)",
+            _ => "",
+        };
+        summary!(ctx, "  - {}: {}{}", category, count, hint);
     }
 
     Ok(())
 }
 
 #[time("info")]
-fn run_combined(path: PathBuf, recursive: bool, dry_run: bool) -> anyhow::Result<()> {
-    info!("Running combined transformations on: {}", path.display());
-    info!("Recursive: {}, Dry run: {}", recursive, dry_run);
-
-    let mut options = CombinedOptions::default();
-    options.recursive = recursive;
-    options.dry_run = dry_run;
+fn run_stats(
+    ctx: &RunContext,
+    path: PathBuf,
+    recursive: bool,
+    extensions: Option<Vec<String>>,
+    dot_path: bool,
+    filter: FilterArgs,
+    json: bool,
+) -> anyhow::Result<()> {
+    info!("Counting case formats in: {}", path.display());
+    info!("Recursive: {}", recursive);
 
-    let spinner = create_spinner("Processing files (rename, emojis, clean)...");
+    let config = ctx.load_config(&path)?;
+    let extensions = filter.extensions(ExtensionSet::Convert, extensions, &config)?;
+    debug!("File extensions: {:?}", extensions);
+    if filter.show(&extensions) {
+        return Ok(());
+    }
 
-    let processor = CombinedProcessor::new(options);
-    let stats = processor.process(&path)?;
+    let mut options = StatsOptions {
+        fs: ctx.fs.clone(),
+        walk: ctx.walk,
+        recursive,
+        file_extensions: extensions,
+        formats: if dot_path {
+            CaseFormat::ALL.to_vec()
+        } else {
+            StatsOptions::default_formats()
+        },
+        ..StatsOptions::default()
+    };
+    options.filter = filter.options(&ctx.exclude);
 
+    let spinner = create_spinner("Scanning files...", ctx.color);
+    let scanner = CaseScanner::new(options);
+    let stats = scanner.process(&path)?;
     spinner.finish_and_clear();
+    report_walk_errors(&scanner.take_walk_errors());
 
-    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+    if stats.total.is_empty() {
+        println!("No identifiers found in {} file(s)", stats.files_scanned);
+        return Ok(());
+    }
 
-    // Print summary
-    if stats.files_renamed > 0
-        || stats.files_emoji_transformed > 0
-        || stats.files_whitespace_cleaned > 0
-    {
-        info!(
-            "{}Combined processing complete: {} renamed, {} emoji-transformed ({} changes), {} whitespace-cleaned ({} lines)",
-            prefix, stats.files_renamed, stats.files_emoji_transformed, stats.emoji_changes,
-            stats.files_whitespace_cleaned, stats.whitespace_lines_cleaned
-        );
+    let total: usize = stats.total.values().sum();
+    println!("{} identifier(s) in {} file(s):", total, stats.files_scanned);
+    for (&format, count) in &stats.total {
         println!(
-            "{}Processed files:",
-            prefix
+            "  {:<17} {:>8}  {:>5.1}%",
+            format.name(),
+            count,
+            stats.share(format) * 100.0
         );
-        if stats.files_renamed > 0 {
-            println!("  - Renamed: {} file(s)", stats.files_renamed);
+    }
+
+    // One column per format found anywhere
+    let formats: Vec<CaseFormat> = stats.total.keys().copied().collect();
+    let print_table = |title: &str, rows: Vec<(String, &CaseCounts)>| {
+        let width = rows
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0)
+            .max(title.len());
+        let mut header = format!("{:<width$}", title.to_uppercase(), width = width);
+        for format in &formats {
+            header.push_str(&format!("  {:>8}", format.name()));
         }
-        if stats.files_emoji_transformed > 0 {
-            println!(
-                "  - Emoji transformations: {} file(s) ({} changes)",
-                stats.files_emoji_transformed, stats.emoji_changes
+        let column = |format: &CaseFormat| format.name().len().max(8);
+        println!();
+        println!("{}", header.trim_end());
+        for (name, counts) in rows {
+            let mut line = format!("{:<width$}", name, width = width);
+            for format in &formats {
+                let count = counts.get(format).copied().unwrap_or(0);
+                line.push_str(&format!("  {:>width$}", count, width = column(format)));
+            }
+            println!("{}", line);
+        }
+    };
+    print_table(
+        "Extension",
+        stats
+            .by_extension
+            .iter()
+            .map(|(ext, counts)| {
+                let name = if ext.is_empty() { "(none)" } else { ext };
+                (name.to_string(), counts)
+            })
+            .collect(),
+    );
+    print_table(
+        "Directory",
+        stats
+            .by_directory
+            .iter()
+            .map(|(dir, counts)| (dir.display().to_string(), counts))
+            .collect(),
+    );
+    Ok(())
+}
+
+fn run_undo(
+    ctx: &RunContext,
+    path: PathBuf,
+    id: Option<String>,
+    list: bool,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let root = journal::find_root(&path);
+
+    if list {
+        let entries = journal::entries(&root)?;
+        if entries.is_empty() {
+            summary!(ctx, "No recorded runs in '{}'", root.display());
+        }
+        for entry in entries {
+            let arguments: Vec<&str> = entry.command.iter().skip(1).map(String::as_str).collect();
+            summary!(
+                ctx,
+                "{}  {} change(s)  refmt {}",
+                entry.id,
+                entry.changes.len(),
+                arguments.join(" ")
             );
         }
-        if stats.files_whitespace_cleaned > 0 {
-            println!(
-                "  - Whitespace cleaned: {} file(s) ({} lines)",
-                stats.files_whitespace_cleaned, stats.whitespace_lines_cleaned
-            );
+        return Ok(());
+    }
+
+    let report = journal::undo(&root, id.as_deref(), dry_run, &ctx.output.events())?;
+    for skipped in &report.skipped {
+        warn!(
+            "'{}' has changed since run {}; left alone",
+            skipped.display(),
+            report.id
+        );
+    }
+    let prefix = if dry_run { "[DRY-RUN] " } else { "" };
+    info!(
+        "{}Restored {} file(s) and renamed back {} file(s) from run {}",
+        prefix, report.restored, report.renamed, report.id
+    );
+
+    if !report.skipped.is_empty() {
+        anyhow::bail!(
+            "{} file(s) were not restored; revert them by hand, then run `refmt undo --id {}` again",
+            report.skipped.len(),
+            report.id
+        );
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_watch(
+    ctx: &RunContext,
+    path: PathBuf,
+    recursive: bool,
+    run: Option<Vec<String>>,
+    from: Option<CaseFormat>,
+    to: Option<CaseFormat>,
+    debounce: u64,
+    filter: FilterArgs,
+    journal: Option<Arc<journal::Journal>>,
+) -> anyhow::Result<()> {
+    if !path.is_dir() {
+        anyhow::bail!("'{}' is not a directory", path.display());
+    }
+    let config = ctx.load_config(&path)?;
+    let run = match run {
+        Some(run) => run,
+        // Renaming a file while it is being watched would only trigger another pass
+        None => config
+            .steps
+            .clone()
+            .map(|steps| steps.into_iter().filter(|s| s != "rename").collect())
+            .unwrap_or_else(|| vec!["clean".to_string(), "emojis".to_string()]),
+    };
+    info!("Watching: {}", path.display());
+    info!("Recursive: {}, Steps: {}", recursive, run.join(", "));
+
+    let mut steps = Vec::new();
+    for step in &run {
+        match step.as_str() {
+            "clean" => {
+                let mut options = WhitespaceOptions {
+                    fs: ctx.fs.clone(),
+                    events: ctx.output.events(),
+                    walk: ctx.walk,
+                    ..WhitespaceOptions::default()
+                };
+                options.filter = filter.options(&ctx.exclude);
+                options.file_extensions =
+                    filter.extensions(ExtensionSet::Whitespace, None, &config)?;
+                steps.push(watch::Step::Clean(WhitespaceCleaner::new(options)));
+            }
+            "emojis" => {
+                let mut options = EmojiOptions {
+                    fs: ctx.fs.clone(),
+                    events: ctx.output.events(),
+                    walk: ctx.walk,
+                    ..EmojiOptions::default()
+                };
+                options.filter = filter.options(&ctx.exclude);
+                options.file_extensions = filter.extensions(ExtensionSet::Emojis, None, &config)?;
+                steps.push(watch::Step::Emojis(EmojiTransformer::new(options)));
+            }
+            _ => {
+                let from = match from {
+                    Some(from) => vec![from],
+                    None => config.convert.from.clone(),
+                };
+                let (Some(&first), Some(to)) = (from.first(), to.or(config.convert.to)) else {
+                    anyhow::bail!(
+                        "--run convert needs --from and --to, or convert.from and convert.to in refmt.toml"
+                    );
+                };
+                let converter = CaseConverter::builder(first, to)
+                    .extensions(filter.extensions(ExtensionSet::Convert, None, &config)?)
+                    .recursive(false)
+                    .source_formats(from)
+                    .excluded_words(config.convert.exclude_words.clone())
+                    .unicode(config.convert.unicode)
+                    .filter(filter.options(&ctx.exclude))
+                    .fs(ctx.fs.clone())
+                    .events(ctx.output.events())
+                    .walk(ctx.walk)
+                    .abbreviations(config.abbreviations())
+                    .build()?;
+                steps.push(watch::Step::Convert(Box::new(converter)));
+            }
+        }
+    }
+
+    watch::run(
+        &path,
+        recursive,
+        ctx.walk,
+        &ctx.output,
+        Duration::from_millis(debounce),
+        &steps,
+        journal,
+    )
+}
+
+fn run_install_hook(
+    ctx: &RunContext,
+    path: PathBuf,
+    pre_commit: bool,
+    force: bool,
+) -> anyhow::Result<()> {
+    if pre_commit {
+        print!("{}", hook::PRE_COMMIT_CONFIG);
+        return Ok(());
+    }
+
+    let hook = hook::install(&path, force)?;
+    info!("Wrote {}", hook.display());
+    summary!(ctx, "Installed the refmt pre-commit hook in '{}'", hook.display());
+    Ok(())
+}
+
+/// Prints the case formats; `json` prints them as a JSON array
+fn run_list_formats(json: bool) -> anyhow::Result<()> {
+    let lossy = |from: CaseFormat| {
+        CaseFormat::ALL
+            .into_iter()
+            .filter_map(move |to| from.lossy_reason(to).map(|reason| (to, reason)))
+    };
+
+    if json {
+        let formats: Vec<serde_json::Value> = CaseFormat::ALL
+            .into_iter()
+            .map(|format| {
+                let lossy: Vec<serde_json::Value> = lossy(format)
+                    .map(|(to, reason)| serde_json::json!({"to": to, "reason": reason}))
+                    .collect();
+                serde_json::json!({
+                    "name": format,
+                    "example": format.example(),
+                    "pattern": format.pattern(),
+                    "lossy": lossy,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&formats)?);
+        return Ok(());
+    }
+
+    println!("{:<17} {:<17} PATTERN", "FORMAT", "EXAMPLE");
+    for format in CaseFormat::ALL {
+        println!("{:<17} {:<17} {}", format.name(), format.example(), format.pattern());
+    }
+    println!();
+    println!("Lossy conversions:");
+    for from in CaseFormat::ALL {
+        // One line per reason, listing the formats it applies to
+        let mut reasons: Vec<(&str, Vec<&str>)> = Vec::new();
+        for (to, reason) in lossy(from) {
+            match reasons.iter_mut().find(|(r, _)| *r == reason) {
+                Some((_, targets)) => targets.push(to.name()),
+                None => reasons.push((reason, vec![to.name()])),
+            }
+        }
+        for (reason, targets) in reasons {
+            println!("  {} -> {}: {}", from, targets.join(", "), reason);
         }
+    }
+    Ok(())
+}
+
+fn run_init(ctx: &RunContext, path: PathBuf, with_hooks: bool, force: bool) -> anyhow::Result<()> {
+    let (file, kinds) = init::write(&path, force)?;
+    let names: Vec<&str> = kinds.iter().map(|kind| kind.name()).collect();
+    if names.is_empty() {
+        summary!(ctx, "Wrote '{}' (no project type detected)", file.display());
     } else {
-        info!("No files needed processing");
-        println!("No files needed processing");
+        summary!(ctx, "Wrote '{}' for {}", file.display(), names.join(", "));
     }
 
+    if with_hooks {
+        let hook = hook::install(&path, force)?;
+        summary!(ctx, "Installed the refmt pre-commit hook in '{}'", hook.display());
+    }
     Ok(())
 }
 
+fn run_daemon(ctx: &RunContext, socket: Option<PathBuf>) -> anyhow::Result<()> {
+    let config = ctx.load_config(Path::new("."))?;
+    let daemon = daemon::Daemon::with_config(ctx.fs.clone(), config, ctx.exclude.clone())
+        .with_walk(ctx.walk);
+    match socket {
+        Some(path) => daemon::serve_socket(&path, daemon),
+        None => daemon::serve_stdio(daemon),
+    }
+}
+
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
+    // The daemon owns stdout for protocol messages, and filters, list-formats and stats
+    // for their output, so their logs go to stderr
+    let owns_stdout = cli.command.as_ref().is_some_and(|command| {
+        matches!(
+            command,
+            Commands::Daemon { .. } | Commands::ListFormats | Commands::Stats { .. }
+        ) || command.is_filter()
+    });
+    let terminal_mode = if owns_stdout {
+        TerminalMode::Stderr
+    } else {
+        TerminalMode::Mixed
+    };
+
+    let color = color_choice(&cli.color, terminal_mode);
+
+    // The JSON summary and the event stream replace the text output, except where
+    // stdout carries data
+    let json = (cli.output == "json" || cli.events) && !owns_stdout;
+    let output = if json && cli.events {
+        output::Output::event_stream()
+    } else if json {
+        output::Output::json()
+    } else {
+        output::Output::default()
+    };
+    let recorder = cli.report_file.clone().map(run_report::Recorder::start);
+
     // Initialize logging
-    if let Err(e) = init_logging(cli.verbose, cli.quiet, cli.log_file.clone()) {
+    if let Err(e) = init_logging(
+        cli.verbose,
+        cli.quiet,
+        cli.log_file.clone(),
+        terminal_mode,
+        color,
+        recorder.as_ref(),
+        json,
+    ) {
         eprintln!("Warning: Failed to initialize logging: {}", e);
     }
 
     debug!("CLI arguments parsed successfully");
 
+    let walk = walker::WalkOptions {
+        max_depth: cli.max_depth,
+        follow_symlinks: cli.follow_symlinks,
+        errors: if cli.walk_errors == "fail" {
+            walker::ErrorPolicy::Fail
+        } else {
+            walker::ErrorPolicy::Warn
+        },
+    };
+
+    let target = match &cli.command {
+        Some(command) => command.target(),
+        None => cli.path.as_deref().map(|path| (path, !cli.dry_run && !cli.check)),
+    };
+
+    let profile = cli.profile.as_deref();
+    // The excludes of refmt.toml add to --exclude; an invalid config file is reported
+    // by the command when it loads it
+    let mut exclude = cli.exclude.clone();
+    let config_root = match &cli.command {
+        Some(Commands::Watch { path, .. }) => Some(path.as_path()),
+        _ => target.map(|(path, _)| path),
+    };
+    if let Ok(config) = load_config(config_root.unwrap_or(Path::new(".")), profile) {
+        exclude.extend(config.exclude_patterns().unwrap_or_default());
+    }
+    if let (Some((path, _)), Some(recorder)) = (target, &recorder) {
+        match load_config(path, profile) {
+            Ok(config) => recorder.snapshot_config(path, config),
+            Err(e) => warn!("Could not record the configuration: {:#}", e),
+        }
+    }
+    if let Some((path, true)) = target {
+        if let Err(e) = check_clean_git(path, profile, cli.require_clean_git, cli.allow_dirty) {
+            error!("{:#}", e);
+            let result = Err(e);
+            write_report(recorder.as_deref(), &result);
+            output.finish(&result);
+            std::process::exit(1);
+        }
+    }
+    // Watch saves each pass as a run of its own
+    let journal_path = match &cli.command {
+        Some(Commands::Watch { path, .. }) => Some(path.as_path()),
+        _ => target.and_then(|(path, writes)| writes.then_some(path)),
+    };
+    let journal = journal_path.filter(|_| !cli.no_journal).map(|path| {
+        Arc::new(journal::Journal::new(&journal::find_root(path)).keep(cli.journal_keep))
+    });
+    let ctx = RunContext {
+        fs: open_filesystem(&cli, journal.clone()),
+        walk,
+        exclude,
+        profile: cli.profile.clone(),
+        verify_idempotent: cli.verify_idempotent,
+        yes: cli.yes,
+        confirm_threshold: cli.confirm_threshold,
+        color: color != ColorChoice::Never,
+        output,
+        recorder,
+    };
+
     let result = match cli.command {
         None => {
             // Default command: run combined processing
             if let Some(path) = cli.path {
                 debug!("Running combined processing (default command)");
-                run_combined(path, cli.recursive, cli.dry_run)
+                run_combined(
+                    &ctx,
+                    path,
+                    cli.recursive,
+                    cli.dry_run,
+                    cli.check,
+                    cli.steps,
+                    cli.preset,
+                    cli.diff,
+                    cli.no_pager,
+                )
             } else {
                 // Neither command nor path specified - print help
                 error!("No command or path specified. Use --help for usage information.");
@@ -723,21 +4442,16 @@ fn main() -> anyhow::Result<()> {
 
         Some(cmd) => match cmd {
             Commands::Convert {
-                from_camel,
-                from_pascal,
-                from_snake,
-                from_screaming_snake,
-                from_kebab,
-                from_screaming_kebab,
-                to_camel,
-                to_pascal,
-                to_snake,
-                to_screaming_snake,
-                to_kebab,
-                to_screaming_kebab,
+                from,
+                to,
+                legacy,
+                from_auto,
                 path,
+                string,
                 recursive,
                 dry_run,
+                check,
+                diff,
                 extensions,
                 prefix,
                 suffix,
@@ -749,24 +4463,37 @@ fn main() -> anyhow::Result<()> {
                 replace_suffix_to,
                 glob,
                 word_filter,
+                word_exclude,
+                exclude_words,
+                exclude_words_file,
+                unicode,
+                digits,
+                abbreviations,
+                keys_only,
+                declared_only,
+                no_update_anchors,
+                filter,
             } => {
                 debug!("Running convert subcommand");
+                // The legacy --from-*/--to-* flags add to --from/--to
+                let mut from_formats = from;
+                for format in legacy.sources() {
+                    if !from_formats.contains(&format) {
+                        from_formats.push(format);
+                    }
+                }
+                let to_format = to.or_else(|| legacy.target());
                 run_convert(
-                    from_camel,
-                    from_pascal,
-                    from_snake,
-                    from_screaming_snake,
-                    from_kebab,
-                    from_screaming_kebab,
-                    to_camel,
-                    to_pascal,
-                    to_snake,
-                    to_screaming_snake,
-                    to_kebab,
-                    to_screaming_kebab,
+                    &ctx,
+                    from_formats,
+                    from_auto,
+                    to_format,
                     path,
+                    string,
                     recursive,
                     dry_run,
+                    check,
+                    diff,
                     extensions,
                     prefix,
                     suffix,
@@ -778,29 +4505,103 @@ fn main() -> anyhow::Result<()> {
                     replace_suffix_to,
                     glob,
                     word_filter,
+                    word_exclude,
+                    exclude_words,
+                    exclude_words_file,
+                    unicode,
+                    digits,
+                    abbreviations,
+                    keys_only,
+                    declared_only,
+                    no_update_anchors,
+                    filter,
+                    cli.no_pager,
                 )
             }
 
             Commands::Clean {
                 path,
+                stdin_filename,
                 recursive,
                 dry_run,
+                check,
+                diff,
                 extensions,
+                changed_lines_only,
+                since,
+                staged,
+                tabs_to_spaces,
+                spaces_to_tabs,
+                ensure_final_newline,
+                trim_trailing_blank_lines,
+                markdown_breaks,
+                filter,
             } => {
                 debug!("Running clean subcommand");
-                run_clean(path, recursive, dry_run, extensions)
+                run_clean(
+                    &ctx,
+                    path,
+                    stdin_filename,
+                    recursive,
+                    dry_run,
+                    check,
+                    diff,
+                    extensions,
+                    changed_lines_only,
+                    since,
+                    staged,
+                    tabs_to_spaces,
+                    spaces_to_tabs,
+                    ensure_final_newline,
+                    trim_trailing_blank_lines,
+                    markdown_breaks,
+                    filter,
+                    cli.no_pager,
+                )
+            }
+
+            Commands::Indent {
+                path,
+                style,
+                width,
+                from_width,
+                recursive,
+                dry_run,
+                extensions,
+            } => {
+                debug!("Running indent subcommand");
+                run_indent(&ctx, path, style, width, from_width, recursive, dry_run, extensions)
             }
 
             Commands::Emojis {
                 path,
+                stdin_filename,
                 recursive,
                 dry_run,
+                check,
+                diff,
                 extensions,
                 replace_task,
                 remove_other,
+                no_update_anchors,
+                filter,
             } => {
                 debug!("Running emojis subcommand");
-                run_emojis(path, recursive, dry_run, extensions, replace_task, remove_other)
+                run_emojis(
+                    &ctx,
+                    path,
+                    stdin_filename,
+                    recursive,
+                    dry_run,
+                    check,
+                    diff,
+                    extensions,
+                    replace_task,
+                    remove_other,
+                    no_update_anchors,
+                    filter,
+                    cli.no_pager,
+                )
             }
 
             Commands::RenameFiles {
@@ -818,9 +4619,11 @@ fn main() -> anyhow::Result<()> {
                 rm_suffix,
                 timestamp_long,
                 timestamp_short,
+                on_conflict,
             } => {
                 debug!("Running rename subcommand");
                 run_rename(
+                    &ctx,
                     path,
                     recursive,
                     dry_run,
@@ -835,16 +4638,394 @@ fn main() -> anyhow::Result<()> {
                     rm_suffix,
                     timestamp_long,
                     timestamp_short,
+                    on_conflict,
+                )
+            }
+
+            Commands::Eol {
+                path,
+                to,
+                recursive,
+                dry_run,
+                extensions,
+                no_gitattributes,
+            } => {
+                debug!("Running eol subcommand");
+                run_eol(&ctx, path, to, recursive, dry_run, extensions, no_gitattributes)
+            }
+
+            Commands::Header {
+                path,
+                recursive,
+                dry_run,
+                extensions,
+                template,
+                template_file,
+                holder,
+                license,
+                year,
+                remove,
+                spdx,
+                check,
+            } => {
+                debug!("Running header subcommand");
+                run_header(
+                    &ctx,
+                    path,
+                    recursive,
+                    dry_run,
+                    extensions,
+                    template,
+                    template_file,
+                    holder,
+                    license,
+                    year,
+                    remove,
+                    spdx,
+                    check,
+                )
+            }
+
+            Commands::Guards {
+                path,
+                to,
+                prefix,
+                suffix,
+                path_components,
+                recursive,
+                dry_run,
+                extensions,
+            } => {
+                debug!("Running guards subcommand");
+                run_guards(
+                    &ctx,
+                    path,
+                    to,
+                    prefix,
+                    suffix,
+                    path_components,
+                    recursive,
+                    dry_run,
+                    extensions,
+                )
+            }
+
+            Commands::Comments {
+                path,
+                to,
+                banners,
+                width,
+                recursive,
+                dry_run,
+                extensions,
+                diff,
+            } => {
+                debug!("Running comments subcommand");
+                run_comments(
+                    &ctx,
+                    path, to, banners, width, recursive, dry_run, extensions, diff, cli.no_pager,
+                )
+            }
+
+            Commands::Quotes {
+                path,
+                straighten,
+                curl,
+                recursive,
+                dry_run,
+                extensions,
+            } => {
+                debug!("Running quotes subcommand");
+                run_quotes(&ctx, path, straighten, curl, recursive, dry_run, extensions)
+            }
+
+            Commands::Punctuation {
+                path,
+                em_dash,
+                en_dash,
+                ellipsis,
+                keep_fullwidth,
+                recursive,
+                dry_run,
+                extensions,
+            } => {
+                debug!("Running punctuation subcommand");
+                run_punctuation(
+                    &ctx,
+                    path,
+                    em_dash,
+                    en_dash,
+                    ellipsis,
+                    keep_fullwidth,
+                    recursive,
+                    dry_run,
+                    extensions,
+                )
+            }
+
+            Commands::Numbers {
+                path,
+                min_digits,
+                radix,
+                recursive,
+                dry_run,
+                extensions,
+            } => {
+                debug!("Running numbers subcommand");
+                run_numbers(&ctx, path, min_digits, radix, recursive, dry_run, extensions)
+            }
+
+            Commands::ConvertKeys {
+                path,
+                to,
+                format,
+                recursive,
+                dry_run,
+                extensions,
+            } => {
+                debug!("Running convert-keys subcommand");
+                run_convert_keys(&ctx, path, to, format, recursive, dry_run, extensions)
+            }
+
+            Commands::SchemaFields {
+                path,
+                to,
+                mapping,
+                recursive,
+                dry_run,
+                extensions,
+            } => {
+                debug!("Running schema-fields subcommand");
+                run_schema_fields(&ctx, path, to, mapping, recursive, dry_run, extensions)
+            }
+
+            Commands::Dotenv {
+                path,
+                recursive,
+                dry_run,
+            } => {
+                debug!("Running dotenv subcommand");
+                run_dotenv(&ctx, path, recursive, dry_run)
+            }
+
+            Commands::Sql {
+                path,
+                keywords,
+                recursive,
+                dry_run,
+                extensions,
+            } => {
+                debug!("Running sql subcommand");
+                run_sql(&ctx, path, keywords, recursive, dry_run, extensions)
+            }
+
+            Commands::ClassNames {
+                path,
+                to,
+                recursive,
+                dry_run,
+                extensions,
+            } => {
+                debug!("Running class-names subcommand");
+                run_class_names(&ctx, path, to, recursive, dry_run, extensions)
+            }
+
+            Commands::StringQuotes {
+                path,
+                to,
+                recursive,
+                dry_run,
+                extensions,
+            } => {
+                debug!("Running string-quotes subcommand");
+                run_string_quotes(&ctx, path, to, recursive, dry_run, extensions)
+            }
+
+            Commands::Shebang {
+                path,
+                no_env,
+                no_permissions,
+                recursive,
+                dry_run,
+                extensions,
+            } => {
+                debug!("Running shebang subcommand");
+                run_shebang(&ctx, path, no_env, no_permissions, recursive, dry_run, extensions)
+            }
+
+            Commands::SortImports {
+                path,
+                recursive,
+                dry_run,
+                extensions,
+                check,
+            } => {
+                debug!("Running sort-imports subcommand");
+                run_sort_imports(&ctx, path, recursive, dry_run, extensions, check)
+            }
+
+            Commands::CheckNames {
+                path,
+                file_case,
+                fix_filename,
+                fix_identifier,
+                recursive,
+                dry_run,
+                extensions,
+            } => {
+                debug!("Running check-names subcommand");
+                run_check_names(
+                    &ctx,
+                    path,
+                    file_case,
+                    fix_filename,
+                    fix_identifier,
+                    recursive,
+                    dry_run,
+                    extensions,
                 )
             }
+
+            Commands::Naming {
+                path,
+                lang,
+                fix,
+                recursive,
+                dry_run,
+                extensions,
+            } => {
+                debug!("Running naming subcommand");
+                run_naming(&ctx, path, lang, fix, recursive, dry_run, extensions)
+            }
+
+            Commands::AsciiCheck {
+                path,
+                recursive,
+                extensions,
+            } => {
+                debug!("Running ascii-check subcommand");
+                run_ascii_check(&ctx, path, recursive, extensions)
+            }
+
+            Commands::Stats {
+                path,
+                recursive,
+                extensions,
+                dot_path,
+                filter,
+            } => {
+                debug!("Running stats subcommand");
+                let json = cli.output == "json";
+                run_stats(&ctx, path, recursive, extensions, dot_path, filter, json)
+            }
+
+            Commands::Undo {
+                path,
+                last: _,
+                id,
+                list,
+                dry_run,
+            } => {
+                debug!("Running undo subcommand");
+                run_undo(&ctx, path, id, list, dry_run)
+            }
+
+            Commands::Watch {
+                path,
+                recursive,
+                run,
+                from,
+                to,
+                debounce,
+                filter,
+            } => {
+                debug!("Running watch subcommand");
+                let journal = journal.clone();
+                run_watch(&ctx, path, recursive, run, from, to, debounce, filter, journal)
+            }
+
+            Commands::InstallHook {
+                path,
+                pre_commit,
+                force,
+            } => {
+                debug!("Running install-hook subcommand");
+                run_install_hook(&ctx, path, pre_commit, force)
+            }
+
+            Commands::ListFormats => {
+                debug!("Running list-formats subcommand");
+                run_list_formats(cli.output == "json")
+            }
+
+            Commands::Init {
+                path,
+                with_hooks,
+                force,
+            } => {
+                debug!("Running init subcommand");
+                run_init(&ctx, path, with_hooks, force)
+            }
+
+            Commands::Daemon { socket } => {
+                debug!("Running daemon subcommand");
+                run_daemon(&ctx, socket)
+            }
+
+            #[cfg(feature = "server")]
+            Commands::Serve { port, host } => {
+                debug!("Running serve subcommand");
+                server::serve(&host, port)
+            }
         }
     };
 
+    match journal.map(|journal| journal.finish(std::env::args().collect())) {
+        Some(Ok(Some(entry))) => debug!(
+            "Recorded {} change(s) as run {}; `refmt undo` reverts them",
+            entry.changes.len(),
+            entry.id
+        ),
+        Some(Ok(None)) | None => {}
+        Some(Err(e)) => warn!("Could not save the undo journal: {:#}", e),
+    }
+
     if let Err(ref e) = result {
         error!("Operation failed: {}", e);
     } else {
         debug!("Operation completed successfully");
     }
+    write_report(ctx.recorder.as_deref(), &result);
+    ctx.output.finish(&result);
 
     result
 }
+
+/// Writes the `--report-file`, if one was requested
+fn write_report(recorder: Option<&run_report::Recorder>, result: &anyhow::Result<()>) {
+    if let Some(recorder) = recorder {
+        if let Err(e) = recorder.write(result) {
+            error!(
+                "Failed to write report file '{}': {:#}",
+                recorder.path().display(),
+                e
+            );
+        }
+    }
+}
+
+/// Warns about the directories that couldn't be read, so users know part of the tree
+/// wasn't processed
+fn report_walk_errors(errors: &[walker::TraversalError]) {
+    if errors.is_empty() {
+        return;
+    }
+
+    warn!(
+        "{} path(s) could not be read and were not processed:",
+        errors.len()
+    );
+    for e in errors {
+        warn!("  {}", e);
+    }
+}
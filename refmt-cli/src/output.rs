@@ -0,0 +1,223 @@
+//! Machine-readable output on stdout
+//!
+//! - `--output json`: while the command runs, the per-file events of the transformers
+//!   are collected and the summary lines it would print are kept as messages; the whole
+//!   summary is printed as a single JSON document when the run ends, whether it
+//!   succeeded or not.
+//! - `--events`: each per-file event is printed as one line of JSON as soon as it
+//!   happens (`{"event":"changed","path":...,"changes":N,...}`), followed by the
+//!   summary lines as `message` events and a final `finished` event.
+//!
+//! Commands can add details to either with [`Output::report`], such as the per-file steps of a
+//! combined dry run.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use refmt_core::{EventSink, FileAction, FileEvent};
+use serde::Serialize;
+
+/// Changes to one file, summed over the transformations applied to it
+#[derive(Debug, Serialize)]
+struct FileSummary {
+    path: PathBuf,
+    changes: usize,
+    dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Rename {
+    from: PathBuf,
+    to: PathBuf,
+    dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Failure {
+    path: PathBuf,
+    message: String,
+}
+
+#[derive(Debug, Default)]
+struct Collected {
+    files: Vec<FileSummary>,
+    /// Position of each path in `files`
+    file_index: HashMap<PathBuf, usize>,
+    renames: Vec<Rename>,
+    failed_files: Vec<Failure>,
+    messages: Vec<String>,
+    report: Option<serde_json::Value>,
+}
+
+/// The printed document
+#[derive(Debug, Serialize)]
+struct Summary<'a> {
+    success: bool,
+    error: Option<String>,
+    files_changed: usize,
+    changes: usize,
+    files: &'a [FileSummary],
+    renames: &'a [Rename],
+    failed_files: &'a [Failure],
+    /// The summary lines text output would have printed
+    messages: &'a [String],
+    /// Details some commands add, such as the per-file steps of a dry run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report: Option<&'a serde_json::Value>,
+}
+
+/// What the run prints on stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Json,
+    Events,
+}
+
+/// Where the run's summary goes: printed as text by default, or collected for
+/// `--output json`, or streamed for `--events`
+///
+/// Clones share what has been collected, so event sinks made from one add to the
+/// summary [`finish`](Self::finish) prints.
+#[derive(Debug, Clone, Default)]
+pub struct Output {
+    mode: Option<Mode>,
+    collected: Arc<Mutex<Collected>>,
+}
+
+impl Output {
+    /// JSON output: summary lines are collected instead of printed
+    pub fn json() -> Self {
+        Output {
+            mode: Some(Mode::Json),
+            ..Output::default()
+        }
+    }
+
+    /// The event stream: every per-file event is printed as it happens
+    pub fn event_stream() -> Self {
+        Output {
+            mode: Some(Mode::Events),
+            ..Output::default()
+        }
+    }
+
+    fn collected(&self) -> MutexGuard<'_, Collected> {
+        lock(&self.collected)
+    }
+
+    /// The receiver of the transformers' per-file events, for their options: collected
+    /// for the JSON summary, printed as the event stream, or ignored with text output
+    pub fn events(&self) -> EventSink {
+        match self.mode {
+            Some(Mode::Json) => {
+                let collected = self.collected.clone();
+                EventSink::new(move |event: &FileEvent| record(&mut lock(&collected), event))
+            }
+            Some(Mode::Events) => EventSink::new(|event: &FileEvent| print_event(event)),
+            None => EventSink::default(),
+        }
+    }
+
+    /// Whether stdout carries JSON rather than text
+    pub fn is_json(&self) -> bool {
+        self.mode.is_some()
+    }
+
+    /// Prints a summary line, or keeps it for the JSON summary
+    pub fn message(&self, text: String) {
+        match self.mode {
+            Some(Mode::Json) => self.collected().messages.push(text),
+            Some(Mode::Events) => {
+                print_event(&serde_json::json!({"event": "message", "text": text}))
+            }
+            None => println!("{}", text),
+        }
+    }
+
+    /// Adds details to the JSON summary, or prints them as a `report` event
+    pub fn report(&self, details: serde_json::Value) {
+        match self.mode {
+            Some(Mode::Json) => self.collected().report = Some(details),
+            Some(Mode::Events) => {
+                print_event(&serde_json::json!({"event": "report", "report": details}))
+            }
+            None => {}
+        }
+    }
+
+    /// Prints the JSON summary or the `finished` event of the run, if either is on
+    pub fn finish(&self, result: &anyhow::Result<()>) {
+        let error = result.as_ref().err().map(|e| format!("{:#}", e));
+        match self.mode {
+            Some(Mode::Json) => {}
+            Some(Mode::Events) => {
+                print_event(&serde_json::json!({
+                    "event": "finished",
+                    "success": result.is_ok(),
+                    "error": error,
+                }));
+                return;
+            }
+            None => return,
+        }
+
+        let collected = self.collected();
+        let summary = Summary {
+            success: result.is_ok(),
+            error,
+            files_changed: collected.files.len(),
+            changes: collected.files.iter().map(|f| f.changes).sum(),
+            files: &collected.files,
+            renames: &collected.renames,
+            failed_files: &collected.failed_files,
+            messages: &collected.messages,
+            report: collected.report.as_ref(),
+        };
+        match serde_json::to_string_pretty(&summary) {
+            Ok(json) => println!("{}", json),
+            Err(e) => log::error!("Failed to write the JSON summary: {}", e),
+        }
+    }
+}
+
+fn lock(collected: &Mutex<Collected>) -> MutexGuard<'_, Collected> {
+    collected.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Prints one line of the event stream and flushes it, so readers see it right away
+fn print_event<T: Serialize>(event: &T) {
+    let Ok(line) = serde_json::to_string(event) else {
+        return;
+    };
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{}", line).and_then(|_| stdout.flush());
+}
+
+fn record(collected: &mut Collected, event: &FileEvent) {
+    match event.action {
+        FileAction::Changed => {
+            match collected.file_index.get(&event.path) {
+                Some(&n) => collected.files[n].changes += event.changes,
+                None => {
+                    collected.file_index.insert(event.path.clone(), collected.files.len());
+                    collected.files.push(FileSummary {
+                        path: event.path.clone(),
+                        changes: event.changes,
+                        dry_run: event.dry_run,
+                    });
+                }
+            }
+        }
+        FileAction::Renamed => collected.renames.push(Rename {
+            from: event.path.clone(),
+            to: event.to.clone().unwrap_or_default(),
+            dry_run: event.dry_run,
+        }),
+        FileAction::Failed => collected.failed_files.push(Failure {
+            path: event.path.clone(),
+            message: event.message.clone().unwrap_or_default(),
+        }),
+    }
+}
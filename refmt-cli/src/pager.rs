@@ -0,0 +1,109 @@
+//! Paging of long output through `$PAGER`, the way git does it
+//!
+//! While a [`Pager`] is alive, the process's stdout is redirected into the pager's
+//! stdin. The default pager is `less` with `LESS=FRX`, which exits straight away
+//! when the output fits on one screen, so short diffs print as usual.
+
+use std::env;
+use std::io::IsTerminal;
+
+/// A running pager; dropping it restores stdout and waits for the pager to exit
+pub struct Pager {
+    // Only held for its `Drop`
+    #[cfg(unix)]
+    _redirect: unix::Redirect,
+}
+
+/// Starts the pager when stdout is a terminal
+///
+/// Returns `None` when stdout is redirected, `$PAGER` is empty or `cat`, or the
+/// pager can't be started; output then goes to stdout directly.
+pub fn start() -> Option<Pager> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+
+    let command = env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+    let command = command.trim();
+    if command.is_empty() || command == "cat" {
+        return None;
+    }
+
+    #[cfg(unix)]
+    {
+        unix::Redirect::spawn(command).map(|redirect| Pager {
+            _redirect: redirect,
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use log::debug;
+    use std::env;
+    use std::io::{self, Write};
+    use std::os::fd::{AsRawFd, RawFd};
+    use std::process::{Child, Command, Stdio};
+
+    const STDOUT: RawFd = 1;
+
+    /// Stdout pointed at a pager's stdin, with the original kept to restore it
+    pub struct Redirect {
+        child: Child,
+        saved_stdout: RawFd,
+    }
+
+    impl Redirect {
+        pub fn spawn(command: &str) -> Option<Self> {
+            let mut pager = Command::new("sh");
+            pager.arg("-c").arg(command).stdin(Stdio::piped());
+            if env::var_os("LESS").is_none() {
+                pager.env("LESS", "FRX");
+            }
+
+            let mut child = match pager.spawn() {
+                Ok(child) => child,
+                Err(e) => {
+                    debug!("Failed to start pager '{}': {}", command, e);
+                    return None;
+                }
+            };
+            let stdin = child.stdin.take()?;
+
+            let _ = io::stdout().flush();
+            // SAFETY: plain descriptor calls; the pipe stays open through fd 1 once
+            // `stdin` is dropped, and `saved_stdout` is only closed in `drop`
+            let saved_stdout = unsafe { libc::dup(STDOUT) };
+            if saved_stdout < 0 || unsafe { libc::dup2(stdin.as_raw_fd(), STDOUT) } < 0 {
+                if saved_stdout >= 0 {
+                    unsafe { libc::close(saved_stdout) };
+                }
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+
+            Some(Redirect {
+                child,
+                saved_stdout,
+            })
+        }
+    }
+
+    impl Drop for Redirect {
+        fn drop(&mut self) {
+            let _ = io::stdout().flush();
+            // SAFETY: restoring fd 1 closes the last write end of the pipe, so the
+            // pager sees end of input
+            unsafe {
+                libc::dup2(self.saved_stdout, STDOUT);
+                libc::close(self.saved_stdout);
+            }
+            let _ = self.child.wait();
+        }
+    }
+}
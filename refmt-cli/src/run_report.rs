@@ -0,0 +1,188 @@
+//! `--report-file`: a JSON record of a run for audits and bots
+//!
+//! The recorder is installed as one more logger, so it sees the per-file messages of
+//! the transformers and every warning and error, whatever the console shows.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use refmt_core::{config, report, Config, FailedFile};
+use serde::Serialize;
+use simplelog::SharedLogger;
+
+/// A per-file message, such as `Cleaned 2 lines in 'src/main.rs'`
+#[derive(Debug, Clone, Serialize)]
+struct Action {
+    /// Milliseconds since the run started
+    elapsed_ms: u128,
+    message: String,
+}
+
+/// A file that couldn't be read or written, even after retrying
+#[derive(Debug, Clone, Serialize)]
+struct Failure {
+    path: PathBuf,
+    message: String,
+}
+
+/// A warning or error logged during the run
+#[derive(Debug, Clone, Serialize)]
+struct Problem {
+    elapsed_ms: u128,
+    level: String,
+    message: String,
+}
+
+#[derive(Debug, Default)]
+struct Records {
+    config_file: Option<PathBuf>,
+    config: Config,
+    actions: Vec<Action>,
+    problems: Vec<Problem>,
+    failed_files: Vec<Failure>,
+}
+
+/// The report file's content
+#[derive(Debug, Serialize)]
+struct RunReport<'a> {
+    tool: &'static str,
+    version: &'static str,
+    arguments: Vec<String>,
+    /// The `refmt.toml` in effect, if any, and the settings the run used
+    config_file: Option<&'a Path>,
+    config: &'a Config,
+    /// Seconds since the Unix epoch
+    started_at: u64,
+    duration_ms: u128,
+    success: bool,
+    error: Option<String>,
+    actions: &'a [Action],
+    problems: &'a [Problem],
+    failed_files: &'a [Failure],
+}
+
+/// Records a run for `--report-file`; one handle is installed as a logger and one is
+/// kept to write the report when the run ends
+pub struct Recorder {
+    path: PathBuf,
+    started: Instant,
+    started_at: SystemTime,
+    records: Mutex<Records>,
+}
+
+impl Recorder {
+    /// Starts recording a run whose report will be written to `path`
+    pub fn start(path: PathBuf) -> Arc<Recorder> {
+        Arc::new(Recorder {
+            path,
+            started: Instant::now(),
+            started_at: SystemTime::now(),
+            records: Mutex::default(),
+        })
+    }
+
+    fn records(&self) -> MutexGuard<'_, Records> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The logger that records the run; it must be installed with the others
+    pub fn logger(self: &Arc<Self>) -> Box<dyn SharedLogger> {
+        Box::new(RecordingLogger(self.clone()))
+    }
+
+    /// Where the report is written
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records `config`, the configuration that applies to `path`
+    pub fn snapshot_config(&self, path: &Path, config: Config) {
+        let mut records = self.records();
+        // The project's file; the user config and the profile may add to it
+        records.config_file = config::find_config(path);
+        records.config = config;
+    }
+
+    /// Records the files that couldn't be processed
+    pub fn record_failed_files(&self, failed: &[FailedFile]) {
+        self.records()
+            .failed_files
+            .extend(failed.iter().map(|file| Failure {
+                path: file.path.clone(),
+                message: file.message.clone(),
+            }));
+    }
+
+    /// Writes everything recorded so far, with the outcome of the run, to the report file
+    pub fn write(&self, result: &anyhow::Result<()>) -> anyhow::Result<()> {
+        let records = self.records();
+        let report = RunReport {
+            tool: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            arguments: std::env::args().collect(),
+            config_file: records.config_file.as_deref(),
+            config: &records.config,
+            started_at: self
+                .started_at
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            duration_ms: self.started.elapsed().as_millis(),
+            success: result.is_ok(),
+            error: result.as_ref().err().map(|e| format!("{:#}", e)),
+            actions: &records.actions,
+            problems: &records.problems,
+            failed_files: &records.failed_files,
+        };
+        let json = serde_json::to_string_pretty(&report)?;
+        std::fs::write(&self.path, json + "\n")?;
+        Ok(())
+    }
+}
+
+struct RecordingLogger(Arc<Recorder>);
+
+impl Log for RecordingLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target() == report::LOG_TARGET || metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let elapsed_ms = self.0.started.elapsed().as_millis();
+        let message = record.args().to_string();
+        let mut records = self.0.records();
+        if record.target() == report::LOG_TARGET {
+            records.actions.push(Action {
+                elapsed_ms,
+                message,
+            });
+        } else {
+            records.problems.push(Problem {
+                elapsed_ms,
+                level: record.level().to_string(),
+                message,
+            });
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for RecordingLogger {
+    fn level(&self) -> LevelFilter {
+        LevelFilter::Info
+    }
+
+    fn config(&self) -> Option<&simplelog::Config> {
+        None
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
@@ -0,0 +1,145 @@
+//! Small HTTP API over the content transformations (`server` feature)
+//!
+//! Endpoints:
+//! - `GET /health`: liveness check with the tool version
+//! - `POST /transform`: transform a buffer. A JSON body takes the same fields as the
+//!   daemon's `transform` method (`content`, `operations`, `from`, `to`, `path`); any
+//!   other body is treated as raw content, with operations taken from the query string
+//!   (`?operations=emojis,clean&from=camel&to=snake&path=README.md`, percent-encoded).
+//!
+//! Invalid requests get a 400 response, failures of the server itself a 500.
+//!
+//! Only in-memory transformations are exposed; the server never touches the filesystem.
+
+use crate::daemon::Daemon;
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+use std::io::Read;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Largest request body accepted, in bytes
+const MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+/// Serves the HTTP API until the process is terminated
+pub fn serve(host: &str, port: u16) -> anyhow::Result<()> {
+    let address = format!("{}:{}", host, port);
+    let server = Server::http(&address).map_err(|e| anyhow::anyhow!(e))?;
+    info!("refmt HTTP API listening on http://{}", address);
+
+    let mut daemon = Daemon::new();
+    for request in server.incoming_requests() {
+        debug!("{} {}", request.method(), request.url());
+        if let Err(e) = handle(&mut daemon, request) {
+            warn!("Failed to send response: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+fn handle(daemon: &mut Daemon, mut request: Request) -> std::io::Result<()> {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((url.as_str(), ""));
+
+    match (request.method(), path) {
+        (Method::Get, "/health") => respond(
+            request,
+            200,
+            json!({ "status": "ok", "version": env!("CARGO_PKG_VERSION") }),
+        ),
+        (Method::Post, "/transform") => {
+            let params = match read_params(&mut request, query) {
+                Ok(params) => params,
+                Err(message) => return respond(request, 400, json!({ "error": message })),
+            };
+
+            match daemon.dispatch("transform", params) {
+                Ok(result) => respond(request, 200, result),
+                Err(error) => {
+                    let status = if error.is_server_error() { 500 } else { 400 };
+                    respond(request, status, json!({ "error": error.message }))
+                }
+            }
+        }
+        (_, "/health") | (_, "/transform") => {
+            respond(request, 405, json!({ "error": "Method not allowed" }))
+        }
+        _ => respond(request, 404, json!({ "error": "Not found" })),
+    }
+}
+
+/// Builds `transform` parameters from the request body and query string
+fn read_params(request: &mut Request, query: &str) -> Result<Value, String> {
+    if request.body_length().unwrap_or(0) > MAX_BODY_SIZE {
+        return Err(format!("Request body exceeds {} bytes", MAX_BODY_SIZE));
+    }
+
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_SIZE as u64 + 1)
+        .read_to_string(&mut body)
+        .map_err(|e| format!("Invalid request body: {}", e))?;
+    if body.len() > MAX_BODY_SIZE {
+        return Err(format!("Request body exceeds {} bytes", MAX_BODY_SIZE));
+    }
+
+    let is_json = request.headers().iter().any(|h| {
+        h.field.equiv("Content-Type") && h.value.as_str().starts_with("application/json")
+    });
+    if is_json {
+        return serde_json::from_str(&body).map_err(|e| format!("Invalid JSON: {}", e));
+    }
+
+    // Raw body: options come from the query string
+    let mut params = json!({ "content": body });
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let (key, value) = (percent_decode(key)?, percent_decode(value)?);
+        match key.as_str() {
+            "operations" => {
+                params["operations"] = value.split(',').filter(|o| !o.is_empty()).collect();
+            }
+            "from" | "to" | "path" => params[key.as_str()] = json!(value),
+            _ => return Err(format!("Unknown query parameter '{}'", key)),
+        }
+    }
+
+    Ok(params)
+}
+
+/// Decodes a query string component: `%XX` escapes and `+` for a space
+fn percent_decode(component: &str) -> Result<String, String> {
+    let invalid = || format!("Invalid percent-encoding in '{}'", component);
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3).ok_or_else(invalid)?;
+                let hex = std::str::from_utf8(hex).map_err(|_| invalid())?;
+                decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| invalid())
+}
+
+fn respond(request: Request, status: u16, body: Value) -> std::io::Result<()> {
+    let header = Header::from_bytes("Content-Type", "application/json")
+        .expect("static header is valid");
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    request.respond(response)
+}
@@ -0,0 +1,129 @@
+//! `refmt watch`: re-applies transformations to files as they change
+//!
+//! File system events are debounced, so an editor's save (often a write, a rename and
+//! a metadata change) or a checkout touching many files triggers one pass over the
+//! files involved. Merely opening a file is an event too, so files whose modification
+//! time is the one refmt left them with are passed over; that includes refmt's own
+//! writes, and its reads.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, SystemTime};
+
+use log::{debug, warn};
+use notify_debouncer_mini::notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
+use refmt_core::ignore::IGNORE_FILE;
+use refmt_core::journal::Journal;
+use refmt_core::walker::WalkOptions;
+use refmt_core::{walker, CaseConverter, EmojiTransformer, IgnoreRules, WhitespaceCleaner};
+
+use crate::output::Output;
+
+/// One transformation of the watch pipeline
+pub enum Step {
+    Clean(WhitespaceCleaner),
+    Emojis(EmojiTransformer),
+    Convert(Box<CaseConverter>),
+}
+
+impl Step {
+    fn apply(&self, path: &Path, root: &Path) -> refmt_core::Result<()> {
+        match self {
+            Step::Clean(cleaner) => cleaner.process(path).map(|_| ()),
+            Step::Emojis(transformer) => transformer.process(path).map(|_| ()),
+            Step::Convert(converter) => converter.process_file(path, root).map(|_| ()),
+        }
+    }
+}
+
+/// Watches `root` and runs `steps`, in order, on every file that changes, until the
+/// process is interrupted; `walk` limits the search for `.refmtignore` files, and
+/// `output` receives the summary lines
+pub fn run(
+    root: &Path,
+    recursive: bool,
+    walk: WalkOptions,
+    output: &Output,
+    debounce: Duration,
+    steps: &[Step],
+    journal: Option<Arc<Journal>>,
+) -> anyhow::Result<()> {
+    // Events name files under the watched directory as the OS sees it
+    let watched = std::fs::canonicalize(root)?;
+    let mode = if recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+
+    let (sender, receiver) = mpsc::channel::<DebounceEventResult>();
+    let mut debouncer = new_debouncer(debounce, sender)?;
+    debouncer.watcher().watch(&watched, mode)?;
+
+    let mut ignore = load_ignore_rules(&watched, recursive, walk);
+    // Modification time of each file after its last pass
+    let mut processed: HashMap<PathBuf, SystemTime> = HashMap::new();
+    output.message(format!(
+        "Watching '{}' for changes (Ctrl-C to stop)",
+        root.display()
+    ));
+
+    for events in receiver {
+        let events = match events {
+            Ok(events) => events,
+            Err(e) => {
+                warn!("Watch error: {}", e);
+                continue;
+            }
+        };
+
+        let mut paths: Vec<PathBuf> = events.into_iter().map(|event| event.path).collect();
+        paths.sort();
+        paths.dedup();
+        if paths.iter().any(|p| p.file_name().is_some_and(|n| n == IGNORE_FILE)) {
+            debug!("Reloading {} files", IGNORE_FILE);
+            ignore = load_ignore_rules(&watched, recursive, walk);
+        }
+        paths.retain(|p| p.is_file() && !ignore.is_ignored(p));
+        paths.retain(|p| modified(p).is_none_or(|time| processed.get(p) != Some(&time)));
+        if paths.is_empty() {
+            continue;
+        }
+
+        for path in paths {
+            // Relative to the given root, so hidden and skipped directories are only
+            // looked for inside the watched tree
+            let relative = root.join(path.strip_prefix(&watched).unwrap_or(&path));
+            debug!("Changed: {}", relative.display());
+            for step in steps {
+                if let Err(e) = step.apply(&relative, root) {
+                    warn!("Failed to process '{}': {:#}", relative.display(), e);
+                }
+            }
+            if let Some(time) = modified(&path) {
+                processed.insert(path, time);
+            }
+        }
+        if let Some(journal) = &journal {
+            if let Err(e) = journal.finish(std::env::args().collect()) {
+                warn!("Could not save the undo journal: {:#}", e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    path.metadata().and_then(|m| m.modified()).ok()
+}
+
+/// Loads the `.refmtignore` files that apply to the watched tree
+fn load_ignore_rules(watched: &Path, recursive: bool, walk: WalkOptions) -> IgnoreRules {
+    let files = walker::collect_files(watched, recursive, walk)
+        .map(|walk| walk.files)
+        .unwrap_or_default();
+    IgnoreRules::load(watched, &files)
+}
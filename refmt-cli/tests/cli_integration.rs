@@ -19,7 +19,7 @@ fn get_binary_path() -> std::path::PathBuf {
     if !path.exists() {
         // Fallback: try to use cargo to build and get the path
         let _output = Command::new("cargo")
-            .args(&["build", "-p", "refmt", "--message-format=json"])
+            .args(["build", "-p", "refmt", "--message-format=json"])
             .output()
             .expect("Failed to build refmt");
 
@@ -41,7 +41,7 @@ fn test_cli_version() {
 
     assert!(output.status.success());
     let stdout = String::from_utf8_lossy(&output.stdout);
-    assert!(stdout.contains("0.2.0"));
+    assert_eq!(stdout.trim(), format!("refmt {}", env!("CARGO_PKG_VERSION")));
 }
 
 #[test]
@@ -68,7 +68,7 @@ fn test_cli_basic_conversion() {
     fs::write(&test_file, "myVariable = 'test'").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["convert", "--from-camel", "--to-snake"])
+        .args(["convert", "--from-camel", "--to-snake"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt");
@@ -91,7 +91,7 @@ fn test_cli_dry_run() {
     fs::write(&test_file, original).unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["convert", "--from-camel", "--to-snake", "--dry-run"])
+        .args(["convert", "--from-camel", "--to-snake", "--dry-run"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt");
@@ -124,7 +124,7 @@ fn test_cli_recursive() {
     fs::write(&file2, "nestedVar = 2").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["convert", "--from-camel", "--to-snake", "-r"])
+        .args(["convert", "--from-camel", "--to-snake", "-r"])
         .arg(&test_dir)
         .output()
         .expect("Failed to execute refmt");
@@ -149,7 +149,7 @@ fn test_cli_with_prefix() {
     fs::write(&test_file, "myVariable = 'test'").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["convert", "--from-camel", "--to-snake", "--prefix", "old_"])
+        .args(["convert", "--from-camel", "--to-snake", "--prefix", "old_"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt");
@@ -171,7 +171,7 @@ fn test_cli_with_suffix() {
     fs::write(&test_file, "myVariable = 'test'").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["convert", "--from-camel", "--to-snake", "--suffix", "_new"])
+        .args(["convert", "--from-camel", "--to-snake", "--suffix", "_new"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt");
@@ -193,7 +193,7 @@ fn test_cli_word_filter() {
     fs::write(&test_file, "getUserName = 'alice'\nmyVariable = 123").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["convert", "--from-camel", "--to-snake", "--word-filter", "^get.*"])
+        .args(["convert", "--from-camel", "--to-snake", "--word-filter", "^get.*"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt");
@@ -221,7 +221,7 @@ fn test_cli_multiple_extensions() {
     fs::write(&txt_file, "myVariable = 3").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["convert", "--from-camel", "--to-snake", "-e", ".py", "-e", ".js"])
+        .args(["convert", "--from-camel", "--to-snake", "-e", ".py", "-e", ".js"])
         .arg(&test_dir)
         .output()
         .expect("Failed to execute refmt");
@@ -242,7 +242,7 @@ fn test_cli_multiple_extensions() {
 #[test]
 fn test_cli_error_missing_from() {
     let output = Command::new(get_binary_path())
-        .args(&["convert", "--to-snake", "dummy.py"])
+        .args(["convert", "--to-snake", "dummy.py"])
         .output()
         .expect("Failed to execute refmt");
 
@@ -254,7 +254,7 @@ fn test_cli_error_missing_from() {
 #[test]
 fn test_cli_error_missing_to() {
     let output = Command::new(get_binary_path())
-        .args(&["convert", "--from-camel", "dummy.py"])
+        .args(["convert", "--from-camel", "dummy.py"])
         .output()
         .expect("Failed to execute refmt");
 
@@ -264,9 +264,9 @@ fn test_cli_error_missing_to() {
 }
 
 #[test]
-fn test_cli_error_conflicting_from() {
+fn test_cli_error_conflicting_to() {
     let output = Command::new(get_binary_path())
-        .args(&["convert", "--from-camel", "--from-snake", "--to-kebab", "dummy.py"])
+        .args(["convert", "--from-camel", "--to-snake", "--to-kebab", "dummy.py"])
         .output()
         .expect("Failed to execute refmt");
 
@@ -277,12 +277,18 @@ fn test_cli_error_conflicting_from() {
 
 #[test]
 fn test_cli_all_format_combinations() {
-    let test_cases = vec![
+    let test_cases = [
         ("--from-camel", "--to-pascal", "myName", "MyName"),
         ("--from-pascal", "--to-snake", "MyName", "my_name"),
         ("--from-snake", "--to-kebab", "my_name", "my-name"),
         ("--from-kebab", "--to-screaming-snake", "my-name", "MY_NAME"),
         ("--from-screaming-snake", "--to-camel", "MY_NAME", "myName"),
+        ("--from-ada", "--to-train", "My_Name", "My-Name"),
+        ("--from-train", "--to-snake", "Content-Type", "content_type"),
+        ("--from-dot", "--to-path", "config.value.name", "config/value/name"),
+        ("--from-camel", "--to-snake", "sha256Hash", "sha256_hash"),
+        ("--from-path", "--to-camel", "user/list", "userList"),
+        ("--from-auto", "--to-snake", "myName MAX_SIZE Content-Type", "my_name max_size content_type"),
     ];
 
     for (idx, (from_arg, to_arg, input, expected)) in test_cases.iter().enumerate() {
@@ -293,7 +299,7 @@ fn test_cli_all_format_combinations() {
         fs::write(&test_file, input).unwrap();
 
         let output = Command::new(get_binary_path())
-            .args(&["convert", from_arg, to_arg, "-e", ".txt"])
+            .args(["convert", from_arg, to_arg, "-e", ".txt"])
             .arg(&test_file)
             .output()
             .expect("Failed to execute refmt");
@@ -318,7 +324,7 @@ fn test_cli_clean_basic() {
     fs::write(&test_file, "line1   \nline2\t\nline3\n").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["clean"])
+        .args(["clean"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt clean");
@@ -344,7 +350,7 @@ fn test_cli_clean_dry_run() {
     fs::write(&test_file, original).unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["clean", "--dry-run"])
+        .args(["clean", "--dry-run"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt clean");
@@ -376,7 +382,7 @@ fn test_cli_clean_recursive() {
     fs::write(&file2, "line2\t\n").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["clean", "-r"])
+        .args(["clean", "-r"])
         .arg(&test_dir)
         .output()
         .expect("Failed to execute refmt clean");
@@ -404,7 +410,7 @@ fn test_cli_clean_extension_filtering() {
     fs::write(&txt_file, "line1   \n").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["clean", "-e", ".py"])
+        .args(["clean", "-e", ".py"])
         .arg(&test_dir)
         .output()
         .expect("Failed to execute refmt clean");
@@ -429,7 +435,7 @@ fn test_cli_clean_no_changes_needed() {
     fs::write(&test_file, "line1\nline2\nline3\n").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["clean"])
+        .args(["clean"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt clean");
@@ -445,7 +451,7 @@ fn test_cli_clean_no_changes_needed() {
 #[test]
 fn test_cli_clean_help() {
     let output = Command::new(get_binary_path())
-        .args(&["clean", "--help"])
+        .args(["clean", "--help"])
         .output()
         .expect("Failed to execute refmt clean --help");
 
@@ -463,7 +469,7 @@ fn test_cli_convert_subcommand() {
     fs::write(&test_file, "myVariable = 'test'").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["convert", "--from-camel", "--to-snake"])
+        .args(["convert", "--from-camel", "--to-snake"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt convert");
@@ -486,8 +492,9 @@ fn test_cli_rename_lowercase() {
     let test_file = test_dir.join("TestFile.txt");
     fs::write(&test_file, "content").unwrap();
 
+    // Without a terminal to answer, the confirmation is skipped at any threshold
     let output = Command::new(get_binary_path())
-        .args(&["rename_files", "--to-lowercase"])
+        .args(["rename_files", "--to-lowercase", "--confirm-threshold", "1"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt rename");
@@ -514,7 +521,7 @@ fn test_cli_rename_uppercase() {
     fs::write(&test_file, "content").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["rename_files", "--to-uppercase"])
+        .args(["rename_files", "--to-uppercase"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt rename");
@@ -541,7 +548,7 @@ fn test_cli_rename_capitalize() {
     fs::write(&test_file, "content").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["rename_files", "--to-capitalize"])
+        .args(["rename_files", "--to-capitalize"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt rename");
@@ -568,7 +575,7 @@ fn test_cli_rename_to_underscore() {
     fs::write(&test_file, "content").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["rename_files", "--underscored"])
+        .args(["rename_files", "--underscored"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt rename");
@@ -589,7 +596,7 @@ fn test_cli_rename_to_hyphen() {
     fs::write(&test_file, "content").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["rename_files", "--hyphenated"])
+        .args(["rename_files", "--hyphenated"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt rename");
@@ -610,7 +617,7 @@ fn test_cli_rename_add_prefix() {
     fs::write(&test_file, "content").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["rename_files", "--add-prefix", "new_"])
+        .args(["rename_files", "--add-prefix", "new_"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt rename");
@@ -631,7 +638,7 @@ fn test_cli_rename_rm_prefix() {
     fs::write(&test_file, "content").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["rename_files", "--rm-prefix", "old_"])
+        .args(["rename_files", "--rm-prefix", "old_"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt rename");
@@ -652,7 +659,7 @@ fn test_cli_rename_add_suffix() {
     fs::write(&test_file, "content").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["rename_files", "--add-suffix", "_backup"])
+        .args(["rename_files", "--add-suffix", "_backup"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt rename");
@@ -673,7 +680,7 @@ fn test_cli_rename_rm_suffix() {
     fs::write(&test_file, "content").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["rename_files", "--rm-suffix", "_old"])
+        .args(["rename_files", "--rm-suffix", "_old"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt rename");
@@ -694,7 +701,7 @@ fn test_cli_rename_combined() {
     fs::write(&test_file, "content").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&[
+        .args([
             "rename_files",
             "--rm-prefix",
             "old_",
@@ -724,7 +731,7 @@ fn test_cli_rename_dry_run() {
     fs::write(&test_file, original_content).unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["rename_files", "--to-lowercase", "--dry-run"])
+        .args(["rename_files", "--to-lowercase", "--dry-run"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt rename");
@@ -757,7 +764,7 @@ fn test_cli_rename_recursive() {
     fs::write(&file2, "content2").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["rename_files", "--to-lowercase", "-r"])
+        .args(["rename_files", "--to-lowercase", "-r"])
         .arg(&test_dir)
         .output()
         .expect("Failed to execute refmt rename");
@@ -772,7 +779,7 @@ fn test_cli_rename_recursive() {
 #[test]
 fn test_cli_rename_help() {
     let output = Command::new(get_binary_path())
-        .args(&["rename_files", "--help"])
+        .args(["rename_files", "--help"])
         .output()
         .expect("Failed to execute refmt rename --help");
 
@@ -805,7 +812,11 @@ fn test_cli_combined_default() {
 
     // On case-insensitive filesystems (like macOS default), TestFile.txt and testfile.txt
     // refer to the same file. Check that the actual filename on disk is lowercase.
-    let entries: Vec<_> = fs::read_dir(&test_dir).unwrap().collect();
+    // The undo journal lives in .refmt
+    let entries: Vec<_> = fs::read_dir(&test_dir)
+        .unwrap()
+        .filter(|e| e.as_ref().unwrap().file_name() != ".refmt")
+        .collect();
     assert_eq!(entries.len(), 1, "Should have exactly one file");
     let actual_name = entries[0].as_ref().unwrap().file_name();
     assert_eq!(actual_name.to_str().unwrap(), "testfile.txt", "Filename should be lowercase");
@@ -840,7 +851,7 @@ fn test_cli_combined_recursive() {
     fs::write(&file2, "More text\t\n☐ Todo\n").unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["-r"])
+        .args(["-r"])
         .arg(&test_dir)
         .output()
         .expect("Failed to execute refmt -r");
@@ -852,13 +863,13 @@ fn test_cli_combined_recursive() {
     assert!(sub_dir.join("file2.md").exists());
 
     // Check content transformations for file1
-    let content1 = fs::read_to_string(&test_dir.join("file1.txt")).unwrap();
+    let content1 = fs::read_to_string(test_dir.join("file1.txt")).unwrap();
     assert!(content1.contains("[x]"));
     assert!(!content1.contains("✅"));
     assert!(!content1.contains("   \n"));
 
     // Check content transformations for file2
-    let content2 = fs::read_to_string(&sub_dir.join("file2.md")).unwrap();
+    let content2 = fs::read_to_string(sub_dir.join("file2.md")).unwrap();
     assert!(content2.contains("[ ]"));
     assert!(!content2.contains("☐"));
     assert!(!content2.contains("\t\n"));
@@ -877,7 +888,7 @@ fn test_cli_combined_dry_run() {
     fs::write(&test_file, original_content).unwrap();
 
     let output = Command::new(get_binary_path())
-        .args(&["--dry-run"])
+        .args(["--dry-run"])
         .arg(&test_file)
         .output()
         .expect("Failed to execute refmt --dry-run");
@@ -923,3 +934,2964 @@ fn test_cli_combined_no_changes_needed() {
 
     fs::remove_dir_all(&test_dir).unwrap();
 }
+
+#[test]
+fn test_cli_eol() {
+    let test_dir = std::env::temp_dir().join("refmt_test_eol");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let text_file = test_dir.join("notes.txt");
+    let binary_file = test_dir.join("blob.bin");
+    fs::write(&text_file, "one\ntwo\n").unwrap();
+    fs::write(&binary_file, b"\x00\x01\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["eol", "--to", "crlf"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt eol");
+    assert!(output.status.success());
+
+    assert_eq!(fs::read_to_string(&text_file).unwrap(), "one\r\ntwo\r\n");
+    assert_eq!(fs::read(&binary_file).unwrap(), b"\x00\x01\n");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Converted 2 line endings in 1 file(s)"));
+    assert!(stdout.contains("Skipped 1 binary file(s)"));
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_header_insert_and_check() {
+    let test_dir = std::env::temp_dir().join("refmt_test_header");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let test_file = test_dir.join("main.rs");
+    fs::write(&test_file, "fn main() {}\n").unwrap();
+
+    // Check mode fails while the header is missing and leaves the file alone
+    let output = Command::new(get_binary_path())
+        .args(["header", "--check", "--holder", "Acme", "--year", "2025"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt header");
+    assert!(!output.status.success());
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "fn main() {}\n");
+
+    let output = Command::new(get_binary_path())
+        .args(["header", "--holder", "Acme", "--license", "MIT", "--year", "2025"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt header");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "// Copyright (c) 2025 Acme\n// SPDX-License-Identifier: MIT\n\nfn main() {}\n"
+    );
+
+    let output = Command::new(get_binary_path())
+        .args(["header", "--check", "--holder", "Acme", "--license", "MIT", "--year", "2025"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt header");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("All headers are up to date"));
+
+    let output = Command::new(get_binary_path())
+        .args(["header", "--remove"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt header");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "fn main() {}\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_indent() {
+    let test_dir = std::env::temp_dir().join("refmt_test_indent");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let test_file = test_dir.join("main.py");
+    fs::write(&test_file, "def f():\n  if x:\n    return 1\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["indent", "--width", "4"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt indent");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "def f():\n    if x:\n        return 1\n"
+    );
+
+    let output = Command::new(get_binary_path())
+        .args(["indent", "--style", "tabs"])
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute refmt indent");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "def f():\n\tif x:\n\t\treturn 1\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_quotes_straighten() {
+    let test_dir = std::env::temp_dir().join("refmt_test_quotes");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let test_file = test_dir.join("notes.md");
+    fs::write(
+        &test_file,
+        "\u{201C}It\u{2019}s fine\u{201D}\n```\nkeep \u{2018}this\u{2019}\n```\n",
+    )
+    .unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["quotes", "--straighten"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt quotes");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "\"It's fine\"\n```\nkeep \u{2018}this\u{2019}\n```\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_punctuation() {
+    let test_dir = std::env::temp_dir().join("refmt_test_punctuation");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let test_file = test_dir.join("notes.txt");
+    fs::write(&test_file, "Wait\u{2026} 1\u{2013}2 \u{2014} ok\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["punctuation", "--em-dash", "-"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt punctuation");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "Wait... 1-2 - ok\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_ascii_check() {
+    let test_dir = std::env::temp_dir().join("refmt_test_ascii_check");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let test_file = test_dir.join("main.py");
+    fs::write(&test_file, "# caf\u{e9}\nx = 1\u{200B}\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .arg("ascii-check")
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt ascii-check");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("main.py:1:6: U+00E9 '\u{e9}' (accented letter)"));
+    assert!(stdout.contains("main.py:2:6: U+200B '\\u{200b}' (invisible)"));
+    assert!(stdout.contains("Found 2 non-ASCII character(s) in 1 file(s)"));
+
+    let output = Command::new(get_binary_path())
+        .args(["--output", "json", "ascii-check"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt ascii-check");
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let chars = &summary["report"][0]["chars"];
+    assert_eq!(chars[1]["line"], 2);
+    assert_eq!(chars[1]["column"], 6);
+    assert_eq!(chars[1]["category"], "invisible");
+    assert_eq!(summary["messages"][0], "Found 2 non-ASCII character(s) in 1 file(s)");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_guards() {
+    let test_dir = std::env::temp_dir().join("refmt_test_guards");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let header = test_dir.join("string_buffer.h");
+    fs::write(&header, "#ifndef STRBUF_H\n#define STRBUF_H\n#endif /* STRBUF_H */\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["guards", "--prefix", "ACME_"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt guards");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&header).unwrap(),
+        "#ifndef ACME_STRING_BUFFER_H\n#define ACME_STRING_BUFFER_H\n#endif /* ACME_STRING_BUFFER_H */\n"
+    );
+
+    let output = Command::new(get_binary_path())
+        .args(["guards", "--to", "snake"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt guards");
+    assert!(output.status.success());
+    assert!(fs::read_to_string(&header).unwrap().starts_with("#ifndef string_buffer_h\n"));
+
+    // Hyphens aren't valid in a guard
+    let output = Command::new(get_binary_path())
+        .args(["guards", "--to", "kebab"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt guards");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("guards can't write kebab names"));
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_convert_updates_anchor_links() {
+    let test_dir = std::env::temp_dir().join("refmt_test_anchors");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let test_file = test_dir.join("api.md");
+    fs::write(&test_file, "## userName\n\nSee [userName](#username).\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from-camel", "--to-snake", "-e", ".md"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt convert");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "## user_name\n\nSee [user_name](#user_name).\n"
+    );
+
+    // Opting out leaves the links alone
+    fs::write(&test_file, "## userName\n\nSee [userName](#username).\n").unwrap();
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from-camel", "--to-snake", "-e", ".md", "--no-update-anchors"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt convert");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "## user_name\n\nSee [user_name](#username).\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_numbers() {
+    let test_dir = std::env::temp_dir().join("refmt_test_numbers");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let rust_file = test_dir.join("main.rs");
+    fs::write(&rust_file, "const N: u64 = 1000000; // 1000000\n").unwrap();
+    let cpp_file = test_dir.join("main.cpp");
+    fs::write(&cpp_file, "long n = 25000000L;\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .arg("numbers")
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt numbers");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Formatted 2 numeric literals in 2 file(s)"));
+    assert_eq!(
+        fs::read_to_string(&rust_file).unwrap(),
+        "const N: u64 = 1_000_000; // 1000000\n"
+    );
+    assert_eq!(fs::read_to_string(&cpp_file).unwrap(), "long n = 25'000'000L;\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_sort_imports_check() {
+    let test_dir = std::env::temp_dir().join("refmt_test_sort_imports");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let test_file = test_dir.join("app.py");
+    fs::write(&test_file, "import sys\nimport os\n\nprint(os, sys)\n").unwrap();
+
+    // Check mode fails without touching the file
+    let output = Command::new(get_binary_path())
+        .args(["sort-imports", "--check"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt sort-imports");
+    assert!(!output.status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "import sys\nimport os\n\nprint(os, sys)\n"
+    );
+
+    let output = Command::new(get_binary_path())
+        .arg("sort-imports")
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt sort-imports");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "import os\nimport sys\n\nprint(os, sys)\n"
+    );
+
+    let output = Command::new(get_binary_path())
+        .args(["sort-imports", "--check"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt sort-imports");
+    assert!(output.status.success());
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_check_names() {
+    let test_dir = std::env::temp_dir().join("refmt_test_check_names");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    fs::write(test_dir.join("user_service.py"), "class AccountService:\n    pass\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .arg("check-names")
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt check-names");
+    assert!(!output.status.success(), "mismatches fail the check");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("AccountService belongs in 'account_service.py' (or rename it to UserService)"));
+    assert!(stdout.contains("Found 1 file name mismatch(es)"));
+
+    let output = Command::new(get_binary_path())
+        .args(["check-names", "--fix-filename"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt check-names");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Fixed 1 file name mismatch(es)"));
+    assert!(!stdout.contains("Found"));
+    assert!(test_dir.join("account_service.py").exists());
+    assert!(!test_dir.join("user_service.py").exists());
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_naming_rust() {
+    let test_dir = std::env::temp_dir().join("refmt_test_naming");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let test_file = test_dir.join("lib.rs");
+    fs::write(&test_file, "const maxSize: usize = 8;\nfn fillBuf() -> usize { maxSize }\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["naming", "--lang", "rust"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt naming");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("lib.rs:1:7: constant `maxSize` should be `MAX_SIZE` (non_upper_case_globals)"));
+    assert!(stdout.contains("Found 2 naming violation(s)"));
+
+    let output = Command::new(get_binary_path())
+        .args(["--output", "json", "naming", "--lang", "rust"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt naming");
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(summary["success"], false);
+    assert_eq!(summary["report"][0]["kind"], "constant");
+    assert_eq!(summary["report"][0]["expected"], "MAX_SIZE");
+    assert_eq!(summary["messages"].as_array().unwrap().len(), 1);
+
+    let output = Command::new(get_binary_path())
+        .args(["naming", "--lang", "rust", "--fix"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt naming");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "const MAX_SIZE: usize = 8;\nfn fill_buf() -> usize { MAX_SIZE }\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_clean_changed_lines_only() {
+    let test_dir = std::env::temp_dir().join("refmt_test_changed_lines");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(&test_dir)
+            .output()
+            .expect("Failed to execute git")
+            .status;
+        assert!(status.success());
+    };
+
+    let test_file = test_dir.join("legacy.py");
+    fs::write(&test_file, "a = 1  \nb = 2  \nc = 3  \n").unwrap();
+    git(&["init", "-q"]);
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "initial"]);
+
+    fs::write(&test_file, "a = 1  \nb = 20  \nc = 3  \n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--changed-lines-only"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt clean");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "a = 1  \nb = 20\nc = 3  \n");
+
+    // Nothing is staged
+    fs::write(&test_file, "a = 1  \nb = 20  \nc = 3  \n").unwrap();
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--changed-lines-only", "--staged"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt clean");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No files needed cleaning"));
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_string_quotes() {
+    let test_dir = std::env::temp_dir().join("refmt_test_string_quotes");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let py_file = test_dir.join("app.py");
+    fs::write(&py_file, "def f():\n    '''Doc.'''\n    return 'a' + 'it\\'s'\n").unwrap();
+    let js_file = test_dir.join("app.js");
+    fs::write(&js_file, "const a = \"x\" + `${\"y\"}`;\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["string-quotes", "--to", "double"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt string-quotes");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&py_file).unwrap(),
+        "def f():\n    '''Doc.'''\n    return \"a\" + \"it's\"\n"
+    );
+
+    let output = Command::new(get_binary_path())
+        .args(["string-quotes", "--to", "single"])
+        .arg(&js_file)
+        .output()
+        .expect("Failed to execute refmt string-quotes");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&js_file).unwrap(), "const a = 'x' + `${\"y\"}`;\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_shebang() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let test_dir = std::env::temp_dir().join("refmt_test_shebang");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let script = test_dir.join("deploy");
+    fs::write(&script, "#!/usr/local/bin/python\r\nprint('ok')\n").unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o644)).unwrap();
+
+    let output = Command::new(get_binary_path())
+        .arg("shebang")
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt shebang");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("#!/usr/local/bin/python -> #!/usr/bin/env python3"));
+    assert!(stdout.contains("Fixed CRLF after shebang"));
+    assert!(stdout.contains("executable"));
+
+    assert_eq!(fs::read_to_string(&script).unwrap(), "#!/usr/bin/env python3\nprint('ok')\n");
+    assert_eq!(fs::metadata(&script).unwrap().permissions().mode() & 0o111, 0o111);
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_header_spdx_check() {
+    let test_dir = std::env::temp_dir().join("refmt_test_header_spdx");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let test_file = test_dir.join("main.rs");
+    fs::write(&test_file, "// Copyright Acme\nfn main() {}\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["header", "--spdx", "--license", "Apache-2.0", "--check"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt header");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Would add SPDX identifier to"));
+
+    let output = Command::new(get_binary_path())
+        .args(["header", "--spdx", "--license", "Apache-2.0"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt header");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "// SPDX-License-Identifier: Apache-2.0\n// Copyright Acme\nfn main() {}\n"
+    );
+
+    let output = Command::new(get_binary_path())
+        .args(["header", "--spdx", "--license", "Apache-2.0", "--check"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt header");
+    assert!(output.status.success());
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_convert_keys() {
+    let test_dir = std::env::temp_dir().join("refmt_test_convert_keys");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let yaml_file = test_dir.join("config.yaml");
+    fs::write(&yaml_file, "logLevel: debugMode\nretryPolicy:\n  maxAttempts: 3\n").unwrap();
+    let json_file = test_dir.join("data.json");
+    fs::write(&json_file, "{\"userId\": 1}\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["convert-keys", "--format", "yaml", "--to", "snake"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt convert-keys");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Renamed 3 keys in 1 file(s)"));
+
+    assert_eq!(
+        fs::read_to_string(&yaml_file).unwrap(),
+        "log_level: debugMode\nretry_policy:\n  max_attempts: 3\n"
+    );
+    // Only YAML files were asked for
+    assert_eq!(fs::read_to_string(&json_file).unwrap(), "{\"userId\": 1}\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_dotenv() {
+    let test_dir = std::env::temp_dir().join("refmt_test_dotenv");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let env_file = test_dir.join(".env");
+    fs::write(&env_file, "db_host = localhost\nDB_HOST=db\napi-token=\"a = b\"\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["--output", "json", "dotenv", "--dry-run"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt dotenv");
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(summary["report"][0]["key"], "DB_HOST");
+    assert_eq!(summary["report"][0]["line"], 2);
+    assert_eq!(summary["report"][0]["first_line"], 1);
+
+    let output = Command::new(get_binary_path())
+        .arg("dotenv")
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt dotenv");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(":2: duplicate key 'DB_HOST' (first defined on line 1)"));
+    assert!(stdout.contains("Normalized 2 lines in 1 file(s)"));
+
+    assert_eq!(
+        fs::read_to_string(&env_file).unwrap(),
+        "DB_HOST=localhost\nDB_HOST=db\nAPI_TOKEN=\"a = b\"\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_sql() {
+    let test_dir = std::env::temp_dir().join("refmt_test_sql");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let sql_file = test_dir.join("query.sql");
+    fs::write(&sql_file, "select id from users where note = 'select me'; -- from here\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .arg("sql")
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt sql");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Recased 3 keywords in 1 file(s)"));
+    assert_eq!(
+        fs::read_to_string(&sql_file).unwrap(),
+        "SELECT id FROM users WHERE note = 'select me'; -- from here\n"
+    );
+
+    let output = Command::new(get_binary_path())
+        .args(["sql", "--keywords", "lower"])
+        .arg(&sql_file)
+        .output()
+        .expect("Failed to execute refmt sql");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&sql_file).unwrap(),
+        "select id from users where note = 'select me'; -- from here\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_class_names() {
+    let test_dir = std::env::temp_dir().join("refmt_test_class_names");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let css_file = test_dir.join("app.css");
+    let html_file = test_dir.join("index.html");
+    let jsx_file = test_dir.join("Nav.jsx");
+    fs::write(&css_file, ".nav-bar a, #main-content { color: #fff; }\n").unwrap();
+    fs::write(&html_file, "<div id=\"main-content\" class=\"nav-bar row\"></div>\n").unwrap();
+    fs::write(&jsx_file, "const Nav = () => <nav className=\"nav-bar\" />;\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["class-names", "--to", "camel"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt class-names");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Renamed 2 class/id names (5 references in 3 file(s))"));
+
+    assert_eq!(
+        fs::read_to_string(&css_file).unwrap(),
+        ".navBar a, #mainContent { color: #fff; }\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&html_file).unwrap(),
+        "<div id=\"mainContent\" class=\"navBar row\"></div>\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&jsx_file).unwrap(),
+        "const Nav = () => <nav className=\"navBar\" />;\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_schema_fields() {
+    let test_dir = std::env::temp_dir().join("refmt_test_schema_fields");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join("proto")).unwrap();
+
+    let proto_file = test_dir.join("proto").join("user.proto");
+    let package_file = test_dir.join("proto").join("package.json");
+    let mapping_file = test_dir.join("mapping.json");
+    fs::write(&proto_file, "message User {\n  string firstName = 1;\n  int32 age = 2;\n}\n").unwrap();
+    fs::write(&package_file, "{\"properties\": {\"fooBar\": 1}}\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["schema-fields", "--to", "snake", "--mapping"])
+        .arg(&mapping_file)
+        .arg(test_dir.join("proto"))
+        .output()
+        .expect("Failed to execute refmt schema-fields");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Renamed 1 fields in 1 file(s)"));
+
+    assert_eq!(
+        fs::read_to_string(&proto_file).unwrap(),
+        "message User {\n  string first_name = 1;\n  int32 age = 2;\n}\n"
+    );
+    // Plain JSON files aren't schemas
+    assert_eq!(
+        fs::read_to_string(&package_file).unwrap(),
+        "{\"properties\": {\"fooBar\": 1}}\n"
+    );
+
+    let mapping: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&mapping_file).unwrap()).unwrap();
+    assert_eq!(mapping[0]["scope"], "User");
+    assert_eq!(mapping[0]["old"], "firstName");
+    assert_eq!(mapping[0]["new"], "first_name");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_skips_localization_files() {
+    let test_dir = std::env::temp_dir().join("refmt_test_localization");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let md_file = test_dir.join("notes.md");
+    let po_file = test_dir.join("de.po");
+    fs::write(&md_file, "Done \u{2705}\n").unwrap();
+    fs::write(&po_file, "msgid \"Done\"\nmsgstr \"Fertig \u{2705}\"\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["emojis", "-e", ".md", "-e", ".po"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt emojis");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Skipped localization file"));
+    assert_eq!(fs::read_to_string(&md_file).unwrap(), "Done [x]\n");
+    assert!(fs::read_to_string(&po_file).unwrap().contains('\u{2705}'));
+
+    let output = Command::new(get_binary_path())
+        .args(["emojis", "-e", ".po", "--include-localization"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt emojis");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&po_file).unwrap(),
+        "msgid \"Done\"\nmsgstr \"Fertig [x]\"\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_exclude_extensions() {
+    let test_dir = std::env::temp_dir().join("refmt_test_exclude_extensions");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let py_file = test_dir.join("main.py");
+    let md_file = test_dir.join("notes.md");
+    let txt_file = test_dir.join("todo.txt");
+    for file in [&py_file, &md_file, &txt_file] {
+        fs::write(file, "text   \n").unwrap();
+    }
+
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--exclude-extensions", ".md,txt"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt clean");
+    assert!(output.status.success());
+
+    assert_eq!(fs::read_to_string(&py_file).unwrap(), "text\n");
+    assert_eq!(fs::read_to_string(&md_file).unwrap(), "text   \n");
+    assert_eq!(fs::read_to_string(&txt_file).unwrap(), "text   \n");
+
+    // -e splits on commas like --exclude-extensions
+    let output = Command::new(get_binary_path())
+        .args(["clean", "-e", ".md,.txt"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt clean");
+    assert!(output.status.success());
+
+    assert_eq!(fs::read_to_string(&md_file).unwrap(), "text\n");
+    assert_eq!(fs::read_to_string(&txt_file).unwrap(), "text\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_show_extensions() {
+    let output = Command::new(get_binary_path())
+        .args(["emojis", "--show-extensions", "--exclude-extensions", ".md", "."])
+        .output()
+        .expect("Failed to execute refmt emojis");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let extensions: Vec<&str> = stdout.lines().collect();
+    assert!(extensions.contains(&".txt"));
+    assert!(!extensions.contains(&".md"));
+
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--no-default-extensions", "-e", ".txt", "--show-extensions", "."])
+        .output()
+        .expect("Failed to execute refmt clean");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), ".txt\n");
+
+    // Nothing left to process
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--no-default-extensions", "."])
+        .output()
+        .expect("Failed to execute refmt clean");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No file extensions to process"));
+}
+
+#[test]
+fn test_cli_pager_skipped_when_piped() {
+    let test_dir = std::env::temp_dir().join("refmt_test_pager");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(test_dir.join("main.c"), "/* setup */\nint x;\n").unwrap();
+
+    // Stdout isn't a terminal here, so the diff is printed directly
+    for extra in [&[][..], &["--no-pager"][..]] {
+        let output = Command::new(get_binary_path())
+            .env("PAGER", "sed s/^/PAGED:/")
+            .args(["comments", "--to", "line", "--dry-run", "--diff"])
+            .args(extra)
+            .arg(&test_dir)
+            .output()
+            .expect("Failed to execute refmt comments");
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("+// setup"));
+        assert!(!stdout.contains("PAGED:"));
+    }
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_config_extensions() {
+    let test_dir = std::env::temp_dir().join("refmt_test_config_extensions");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join("docs")).unwrap();
+    fs::write(
+        test_dir.join("refmt.toml"),
+        "[extensions]\nclean = [\"txt\", \".md\"]\n",
+    )
+    .unwrap();
+
+    // The config applies to subdirectories and replaces the built-in set
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--show-extensions"])
+        .arg(test_dir.join("docs"))
+        .output()
+        .expect("Failed to execute refmt clean");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), ".txt\n.md\n");
+
+    // Commands without an entry keep their defaults; -e still wins
+    let output = Command::new(get_binary_path())
+        .args(["emojis", "--show-extensions"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt emojis");
+    assert!(String::from_utf8_lossy(&output.stdout).contains(".rst\n"));
+
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--show-extensions", "-e", ".py"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt clean");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), ".py\n");
+
+    // Unknown keys are rejected rather than silently ignored
+    fs::write(test_dir.join("refmt.toml"), "[extensions]\ncleen = []\n").unwrap();
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--show-extensions"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt clean");
+    assert!(!output.status.success());
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_color_flag() {
+    let test_dir = std::env::temp_dir().join("refmt_test_color");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(test_dir.join("notes.txt"), "trailing  \n").unwrap();
+
+    let run = |color: Option<&str>| {
+        let mut command = Command::new(get_binary_path());
+        command.env_remove("NO_COLOR").arg("-v");
+        if let Some(color) = color {
+            command.args(["--color", color]);
+        }
+        let output = command
+            .args(["clean", "--dry-run", "-e", ".txt"])
+            .arg(&test_dir)
+            .output()
+            .expect("Failed to execute refmt clean");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    assert!(run(Some("always")).contains("\x1b["));
+    assert!(!run(Some("never")).contains("\x1b["));
+    // Piped output has no colors or spinner by default
+    let stdout = run(None);
+    assert!(!stdout.contains("\x1b["));
+    assert!(!stdout.contains("Cleaning files..."));
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_verbose_skip_reasons() {
+    let test_dir = std::env::temp_dir().join("refmt_test_skip_reasons");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join(".cache")).unwrap();
+    fs::create_dir_all(test_dir.join("node_modules")).unwrap();
+    fs::write(test_dir.join("notes.txt"), "trailing  \n").unwrap();
+    fs::write(test_dir.join(".cache/notes.txt"), "trailing  \n").unwrap();
+    fs::write(test_dir.join("node_modules/notes.txt"), "trailing  \n").unwrap();
+    fs::write(test_dir.join("data.txt"), b"bin\0ary  \n").unwrap();
+    fs::write(test_dir.join("image.png"), "png").unwrap();
+
+    // Relative paths starting with `.` aren't mistaken for hidden ones
+    let output = Command::new(get_binary_path())
+        .current_dir(&test_dir)
+        .args(["-vv", "clean", "--dry-run", "-e", ".txt", "."])
+        .output()
+        .expect("Failed to execute refmt clean");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Would clean 1 lines in './notes.txt'"));
+    assert!(stdout.contains("Skipped hidden file './.cache/notes.txt'"));
+    assert!(stdout.contains("Skipped file in skipped directory './node_modules/notes.txt'"));
+    assert!(stdout.contains("Skipped binary file './data.txt'"));
+    assert!(stdout.contains("Skipped file with unselected extension './image.png'"));
+
+    // Without -vv, only the summary is printed
+    let output = Command::new(get_binary_path())
+        .current_dir(&test_dir)
+        .args(["clean", "--dry-run", "-e", ".txt", "."])
+        .output()
+        .expect("Failed to execute refmt clean");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("Skipped"));
+    assert!(stdout.contains("Cleaned 1 lines in 1 file(s)"));
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_default_command_confirmation() {
+    let test_dir = std::env::temp_dir().join("refmt_test_confirm");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    for i in 0..60 {
+        fs::write(test_dir.join(format!("f{}.txt", i)), "trailing  \n").unwrap();
+    }
+
+    // Without a terminal to answer on, large runs proceed without prompting
+    let output = Command::new(get_binary_path())
+        .arg(&test_dir)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("Continue?"));
+    assert_eq!(fs::read_to_string(test_dir.join("f0.txt")).unwrap(), "trailing\n");
+
+    let output = Command::new(get_binary_path())
+        .arg("--yes")
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_require_clean_git() {
+    let test_dir = std::env::temp_dir().join("refmt_test_clean_git");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(["-c", "user.name=refmt", "-c", "user.email=refmt@example.com"])
+            .args(args)
+            .current_dir(&test_dir)
+            .output()
+            .map(|output| output.status.success());
+        status.unwrap_or(false)
+    };
+    if !git(&["init", "-q"]) {
+        // git isn't available
+        fs::remove_dir_all(&test_dir).unwrap();
+        return;
+    }
+    fs::write(test_dir.join("a.py"), "x = 1\n").unwrap();
+    assert!(git(&["add", "."]));
+    assert!(git(&["commit", "-q", "-m", "init"]));
+
+    let run = |args: &[&str]| {
+        Command::new(get_binary_path())
+            .args(args)
+            .arg(&test_dir)
+            .output()
+            .expect("Failed to execute refmt")
+    };
+
+    // A clean tree may be modified
+    assert!(run(&["clean", "--require-clean-git"]).status.success());
+
+    fs::write(test_dir.join("a.py"), "x = 1   \n").unwrap();
+    let output = run(&["clean", "--require-clean-git"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 uncommitted change(s)"), "stderr: {}", stderr);
+    assert_eq!(fs::read_to_string(test_dir.join("a.py")).unwrap(), "x = 1   \n");
+
+    // Dry runs don't modify anything, so they're always allowed
+    assert!(run(&["clean", "--require-clean-git", "--dry-run"]).status.success());
+
+    // refmt.toml makes the check the default; --allow-dirty overrides it
+    fs::write(test_dir.join("refmt.toml"), "require_clean_git = true\n").unwrap();
+    assert!(!run(&["clean"]).status.success());
+    assert!(run(&["clean", "--allow-dirty"]).status.success());
+    assert_eq!(fs::read_to_string(test_dir.join("a.py")).unwrap(), "x = 1\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_rename_conflicts() {
+    use std::io::Write;
+
+    let test_dir = std::env::temp_dir().join("refmt_test_rename_conflicts");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(test_dir.join("Notes.txt"), "upper").unwrap();
+    fs::write(test_dir.join("notes.txt"), "lower").unwrap();
+
+    let rename = |on_conflict: &str, input: &str| {
+        let mut child = Command::new(get_binary_path())
+            .args(["rename_files", "--to-lowercase", "--on-conflict", on_conflict])
+            .arg(&test_dir)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .expect("Failed to execute refmt");
+        child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    // By default a conflict is an error
+    assert!(!rename("error", "").status.success());
+
+    let output = rename("skip", "");
+    assert!(output.status.success());
+    assert!(test_dir.join("Notes.txt").exists());
+
+    // Unknown answers are asked again; `r` then takes a new name
+    let output = rename("prompt", "x\nr\nnotes-old.txt\n");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[s]kip, [o]verwrite, auto-[n]umber, [r]ename?"));
+    assert_eq!(fs::read_to_string(test_dir.join("notes-old.txt")).unwrap(), "upper");
+    assert_eq!(fs::read_to_string(test_dir.join("notes.txt")).unwrap(), "lower");
+
+    fs::write(test_dir.join("Notes.txt"), "upper").unwrap();
+    assert!(rename("number", "").status.success());
+    assert_eq!(fs::read_to_string(test_dir.join("notes-2.txt")).unwrap(), "upper");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_walk_errors() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let test_dir = std::env::temp_dir().join("refmt_test_walk_errors");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join("locked")).unwrap();
+    fs::write(test_dir.join("locked/a.py"), "x = 1  \n").unwrap();
+    fs::write(test_dir.join("b.py"), "y = 2  \n").unwrap();
+    fs::set_permissions(test_dir.join("locked"), fs::Permissions::from_mode(0o000)).unwrap();
+    // Root can read the directory anyway
+    let unreadable = fs::read_dir(test_dir.join("locked")).is_err();
+
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--dry-run"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.contains("could not be read"), unreadable, "stderr: {}", stderr);
+
+    let output = Command::new(get_binary_path())
+        .args(["--walk-errors", "fail", "clean", "--dry-run"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert_eq!(output.status.success(), !unreadable);
+
+    fs::set_permissions(test_dir.join("locked"), fs::Permissions::from_mode(0o755)).unwrap();
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_quiet_hides_file_messages() {
+    let test_dir = std::env::temp_dir().join("refmt_test_quiet_messages");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let file = test_dir.join("notes.txt");
+
+    fs::write(&file, "trailing   \n").unwrap();
+    let output = Command::new(get_binary_path())
+        .arg("clean")
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Cleaned 1 lines in '"), "{}", stdout);
+
+    fs::write(&file, "trailing   \n").unwrap();
+    let output = Command::new(get_binary_path())
+        .args(["--quiet", "clean"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("lines in '"));
+    assert_eq!(fs::read_to_string(&file).unwrap(), "trailing\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_verify_idempotent() {
+    let test_dir = std::env::temp_dir().join("refmt_test_verify_idempotent");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let file = test_dir.join("notes.md");
+    fs::write(&file, "- \u{2705} done \n\tuserName = 1\t\n").unwrap();
+
+    for args in [&["emojis"][..], &["clean"], &["convert", "--from-camel", "--to-snake"]] {
+        let output = Command::new(get_binary_path())
+            .arg("--verify-idempotent")
+            .args(args)
+            .arg(&test_dir)
+            .output()
+            .expect("Failed to execute refmt");
+        assert!(
+            output.status.success(),
+            "{:?}: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    assert_eq!(fs::read_to_string(&file).unwrap(), "- [x] done\n\tuser_name = 1\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_include_hidden() {
+    let test_dir = std::env::temp_dir().join("refmt_test_include_hidden");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join(".github/workflows")).unwrap();
+    fs::create_dir_all(test_dir.join(".git")).unwrap();
+    let workflow = test_dir.join(".github/workflows/README.md");
+    let dotfile = test_dir.join(".notes.md");
+    let git_file = test_dir.join(".git/description.md");
+    for file in [&workflow, &dotfile, &git_file] {
+        fs::write(file, "text  \n").unwrap();
+    }
+
+    let clean = |flags: &[&str]| {
+        let output = Command::new(get_binary_path())
+            .arg("clean")
+            .args(flags)
+            .arg(&test_dir)
+            .output()
+            .expect("Failed to execute refmt");
+        assert!(output.status.success());
+    };
+
+    clean(&[]);
+    assert_eq!(fs::read_to_string(&workflow).unwrap(), "text  \n");
+
+    clean(&["--include-hidden-dirs"]);
+    assert_eq!(fs::read_to_string(&workflow).unwrap(), "text\n");
+    assert_eq!(fs::read_to_string(&dotfile).unwrap(), "text  \n");
+
+    clean(&["--include-hidden-files"]);
+    assert_eq!(fs::read_to_string(&dotfile).unwrap(), "text\n");
+    assert_eq!(fs::read_to_string(&git_file).unwrap(), "text  \n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_report_file() {
+    let test_dir = std::env::temp_dir().join("refmt_test_report_file");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join("src")).unwrap();
+    fs::write(test_dir.join("refmt.toml"), "[extensions]\nclean = [\"md\"]\n").unwrap();
+    fs::write(test_dir.join("src/notes.md"), "text  \n").unwrap();
+    let report_file = test_dir.join("report.json");
+
+    let output = Command::new(get_binary_path())
+        .args(["--quiet", "--report-file"])
+        .arg(&report_file)
+        .arg("clean")
+        .arg(test_dir.join("src"))
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&report_file).unwrap()).unwrap();
+    assert_eq!(report["tool"], "refmt");
+    assert_eq!(report["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(report["success"], true);
+    assert_eq!(report["config"]["extensions"]["clean"][0], ".md");
+    let actions = report["actions"].as_array().unwrap();
+    assert_eq!(actions.len(), 1);
+    assert!(actions[0]["message"].as_str().unwrap().starts_with("Cleaned 1 lines in"));
+
+    // Failures are recorded too
+    let output = Command::new(get_binary_path())
+        .arg("--report-file")
+        .arg(&report_file)
+        .args(["indent", "--width", "0"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(!output.status.success());
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&report_file).unwrap()).unwrap();
+    assert_eq!(report["success"], false);
+    assert!(report["error"].as_str().unwrap().contains("at least 1"));
+    assert!(!report["problems"].as_array().unwrap().is_empty());
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_failed_files() {
+    let test_dir = std::env::temp_dir().join("refmt_test_failed_files");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    // Not UTF-8, but no NUL byte either, so it isn't skipped as binary
+    fs::write(test_dir.join("a_latin1.md"), b"caf\xe9  \n").unwrap();
+    fs::write(test_dir.join("b_notes.md"), "text  \n").unwrap();
+    let report_file = test_dir.join("report.json");
+
+    let output = Command::new(get_binary_path())
+        .args(["--retries", "2", "--retry-backoff", "1", "--report-file"])
+        .arg(&report_file)
+        .arg("clean")
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 file(s) could not be processed"));
+    assert!(stderr.contains("a_latin1.md"));
+
+    // The rest of the tree was still processed
+    assert_eq!(fs::read_to_string(test_dir.join("b_notes.md")).unwrap(), "text\n");
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&report_file).unwrap()).unwrap();
+    let failed = report["failed_files"].as_array().unwrap();
+    assert_eq!(failed.len(), 1);
+    assert!(failed[0]["path"].as_str().unwrap().ends_with("a_latin1.md"));
+
+    // With --walk-errors fail, the first failure aborts the run
+    let output = Command::new(get_binary_path())
+        .args(["--walk-errors", "fail", "clean"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(!output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("could not be processed"));
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_convert_multiple_from() {
+    let test_dir = std::env::temp_dir().join("refmt_test_convert_multiple_from");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let test_file = test_dir.join("test.txt");
+    fs::write(&test_file, "userName UserId MAX_SIZE\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from-camel", "--from-pascal", "--to-snake", "-e", ".txt"])
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "user_name user_id MAX_SIZE\n");
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from-auto", "--from-camel", "--to-snake"])
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(!output.status.success());
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_convert_unicode() {
+    let test_dir = std::env::temp_dir().join("refmt_test_convert_unicode");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let test_file = test_dir.join("test.txt");
+    fs::write(&test_file, "größeZahl naïveValue userName\n").unwrap();
+
+    let convert = |extra: &[&str]| {
+        Command::new(get_binary_path())
+            .args(["convert", "--from-camel", "--to-snake", "-e", ".txt"])
+            .args(extra)
+            .arg(&test_file)
+            .output()
+            .expect("Failed to execute refmt")
+    };
+
+    // Non-ASCII identifiers are left alone by default
+    assert!(convert(&[]).status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "größeZahl naïveValue user_name\n"
+    );
+
+    assert!(convert(&["--unicode"]).status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "größe_zahl naïve_value user_name\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_convert_digits() {
+    let test_dir = std::env::temp_dir().join("refmt_test_convert_digits");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let test_file = test_dir.join("test.txt");
+    fs::write(&test_file, "sha256Hash user2Name\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from-camel", "--to-snake", "--digits", "separate", "-e", ".txt"])
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "sha_256_hash user_2_name\n");
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from-snake", "--to-camel", "--digits", "attach", "-e", ".txt"])
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "sha256Hash user2Name\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_convert_from_to_names() {
+    let test_dir = std::env::temp_dir().join("refmt_test_convert_from_to_names");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let test_file = test_dir.join("test.txt");
+    fs::write(&test_file, "userName UserId MAX_SIZE\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from", "camel", "--from", "pascal", "--to", "snake", "-e", ".txt"])
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "user_name user_id MAX_SIZE\n");
+
+    // The old flags still work alongside the new ones
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from", "snake", "--from-screaming-snake", "--to-kebab", "-e", ".txt"])
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "user-name user-id max-size\n");
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from", "hungarian", "--to", "snake"])
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("possible values"));
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_convert_abbreviations() {
+    let test_dir = std::env::temp_dir().join("refmt_test_convert_abbreviations");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let test_file = test_dir.join("test.txt");
+    fs::write(&test_file, "user_id db_url\n").unwrap();
+    fs::write(test_dir.join("refmt.toml"), "abbreviations = [\"ID\"]\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from", "snake", "--to", "camel", "-e", ".txt"])
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "userID dbUrl\n");
+
+    // --abbreviations replaces the list from refmt.toml
+    let dictionary = test_dir.join("abbreviations.toml");
+    fs::write(&dictionary, "abbreviations = [\"DB\", \"URL\"]\n").unwrap();
+    fs::write(&test_file, "user_id db_url\n").unwrap();
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from", "snake", "--to", "pascal", "-e", ".txt", "--abbreviations"])
+        .arg(&dictionary)
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "UserId DBURL\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_convert_keys_only() {
+    let test_dir = std::env::temp_dir().join("refmt_test_convert_keys_only");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let json_file = test_dir.join("config.json");
+    fs::write(&json_file, "{ \"userName\": \"fooBar\", \"homeUrl\": \"http://x/myPage\" }\n").unwrap();
+    let yaml_file = test_dir.join("config.yaml");
+    fs::write(&yaml_file, "# maxSize: in bytes\nmaxSize: 10  # keep\nlabel: someValue\n").unwrap();
+    let flow_file = test_dir.join("flow.yaml");
+    fs::write(&flow_file, "server: {hostName: web}\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from", "camel", "--to", "snake", "--keys-only", "-e", ".json", "-e", ".yaml"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(!output.status.success());
+    assert_eq!(
+        fs::read_to_string(&json_file).unwrap(),
+        "{ \"user_name\": \"fooBar\", \"home_url\": \"http://x/myPage\" }\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&yaml_file).unwrap(),
+        "# maxSize: in bytes\nmax_size: 10  # keep\nlabel: someValue\n"
+    );
+    // A flow mapping can't be renamed in place, so that file fails and is left alone
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("flow.yaml"), "stderr: {}", stderr);
+    assert_eq!(fs::read_to_string(&flow_file).unwrap(), "server: {hostName: web}\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+#[cfg(feature = "declared-only")]
+fn test_cli_convert_declared_only() {
+    let test_dir = std::env::temp_dir().join("refmt_test_convert_declared_only");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let test_file = test_dir.join("app.py");
+    fs::write(&test_file, "def loadUser(userId):\n    return api.fetchById(userId)\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from", "camel", "--to", "snake", "--declared-only"])
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "def load_user(user_id):\n    return api.fetchById(user_id)\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_convert_exclude_words() {
+    let test_dir = std::env::temp_dir().join("refmt_test_convert_exclude_words");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let test_file = test_dir.join("test.txt");
+    fs::write(&test_file, "iPhone macOS getElementById userName\n").unwrap();
+    let word_list = test_dir.join("words.txt");
+    fs::write(&word_list, "# External APIs\ngetElementById\n\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from", "camel", "--to", "snake", "-e", ".txt"])
+        .args(["--exclude-words", "iPhone,macOS", "--exclude-words-file"])
+        .arg(&word_list)
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "iPhone macOS getElementById user_name\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_convert_word_exclude() {
+    let test_dir = std::env::temp_dir().join("refmt_test_convert_word_exclude");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let test_file = test_dir.join("test.txt");
+    fs::write(&test_file, "testUserName getUserName userName\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from", "camel", "--to", "snake", "-e", ".txt"])
+        .args(["--word-filter", "Name$", "--word-exclude", "^test"])
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&test_file).unwrap(),
+        "testUserName get_user_name user_name\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_convert_stdin() {
+    use std::io::Write;
+
+    let mut child = Command::new(get_binary_path())
+        .args(["-v", "convert", "--from", "camel", "--to", "snake", "-"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to execute refmt");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"let userName = getUser();\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    // Logs stay on stderr, so stdout is just the converted text
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "let user_name = get_user();\n");
+}
+
+#[test]
+fn test_cli_clean_and_emojis_stdin() {
+    use std::io::Write;
+
+    let run = |args: &[&str], input: &str| {
+        let mut child = Command::new(get_binary_path())
+            .args(args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .expect("Failed to execute refmt");
+        child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+        let output = child.wait_with_output().unwrap();
+        (output.status.success(), String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+
+    let clean = ["clean", "--stdin-filename", "src/app.py", "-"];
+    assert_eq!(run(&clean, "x = 1  \n"), (true, "x = 1\n".to_string()));
+    let check = ["clean", "--check", "--stdin-filename", "src/app.py", "-"];
+    assert_eq!(run(&check, "x = 1  \n"), (false, String::new()));
+    assert_eq!(run(&check, "x = 1\n"), (true, String::new()));
+
+    let emojis = ["emojis", "--check", "--stdin-filename", "README.md", "-"];
+    assert!(!run(&emojis, "Done ✅\n").0);
+    // Files the filters leave out pass
+    let image = ["emojis", "--check", "--stdin-filename", "logo.png", "-"];
+    assert!(run(&image, "Done ✅\n").0);
+
+    assert!(!run(&["clean", "-"], "x\n").0, "stdin needs a file name");
+}
+
+#[test]
+fn test_cli_convert_string() {
+    let output = Command::new(get_binary_path())
+        .args(["-v", "convert", "--from", "camel", "--to", "snake"])
+        .args(["--string", "getUserName", "--string", "already_snake"])
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "get_user_name\nalready_snake\n");
+
+    // A path or --string is required, not both
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from", "camel", "--to", "snake", "--string", "x", "."])
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_cli_convert_refmtignore() {
+    let test_dir = std::env::temp_dir().join("refmt_test_convert_refmtignore");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join("third_party")).unwrap();
+    fs::write(test_dir.join(".refmtignore"), "third_party/\n*.gen.py\n").unwrap();
+    fs::write(test_dir.join("main.py"), "userName = 1\n").unwrap();
+    fs::write(test_dir.join("api.gen.py"), "userName = 1\n").unwrap();
+    fs::write(test_dir.join("third_party/lib.py"), "userName = 1\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from", "camel", "--to", "snake", "-r"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(test_dir.join("main.py")).unwrap(), "user_name = 1\n");
+    assert_eq!(fs::read_to_string(test_dir.join("api.gen.py")).unwrap(), "userName = 1\n");
+    assert_eq!(
+        fs::read_to_string(test_dir.join("third_party/lib.py")).unwrap(),
+        "userName = 1\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_exclude_globs() {
+    let test_dir = std::env::temp_dir().join("refmt_test_exclude_globs");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join("app/migrations")).unwrap();
+    fs::write(test_dir.join("app/models.py"), "userName = 1   \n").unwrap();
+    fs::write(test_dir.join("app/migrations/0001.py"), "userName = 1   \n").unwrap();
+    fs::write(test_dir.join("app/migrations/Init.txt"), "x\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from", "camel", "--to", "snake", "-r"])
+        .args(["--exclude", "**/migrations/**"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(test_dir.join("app/models.py")).unwrap(),
+        "user_name = 1   \n"
+    );
+    assert_eq!(
+        fs::read_to_string(test_dir.join("app/migrations/0001.py")).unwrap(),
+        "userName = 1   \n"
+    );
+
+    // Global, so it also applies to the default command
+    let output = Command::new(get_binary_path())
+        .args(["-r", "--exclude", "**/migrations/**"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(test_dir.join("app/models.py")).unwrap(), "user_name = 1\n");
+    assert!(test_dir.join("app/migrations/Init.txt").exists());
+    assert_eq!(
+        fs::read_to_string(test_dir.join("app/migrations/0001.py")).unwrap(),
+        "userName = 1   \n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_convert_multiple_globs() {
+    let test_dir = std::env::temp_dir().join("refmt_test_convert_multiple_globs");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join("src")).unwrap();
+    for file in ["src/app.py", "src/app.js", "notes.md"] {
+        fs::write(test_dir.join(file), "userName\n").unwrap();
+    }
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from", "camel", "--to", "snake", "-r"])
+        .args(["--glob", "*.py", "--glob", "*.md"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(test_dir.join("src/app.py")).unwrap(), "user_name\n");
+    assert_eq!(fs::read_to_string(test_dir.join("notes.md")).unwrap(), "user_name\n");
+    assert_eq!(fs::read_to_string(test_dir.join("src/app.js")).unwrap(), "userName\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_skip_dirs() {
+    let test_dir = std::env::temp_dir().join("refmt_test_skip_dirs");
+    let _ = fs::remove_dir_all(&test_dir);
+    for dir in ["src", "node_modules/pkg", "vendor"] {
+        fs::create_dir_all(test_dir.join(dir)).unwrap();
+        fs::write(test_dir.join(dir).join("a.py"), "userName = 1   \n").unwrap();
+    }
+    let read = |dir: &str| fs::read_to_string(test_dir.join(dir).join("a.py")).unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["convert", "--from", "camel", "--to", "snake", "-r", "--skip-dir", "vendor"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(read("src"), "user_name = 1   \n");
+    assert_eq!(read("node_modules/pkg"), "userName = 1   \n");
+    assert_eq!(read("vendor"), "userName = 1   \n");
+
+    let output = Command::new(get_binary_path())
+        .args(["clean", "-r", "--no-default-skip-dirs"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(read("node_modules/pkg"), "userName = 1\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_max_depth() {
+    let test_dir = std::env::temp_dir().join("refmt_test_max_depth");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join("a/b")).unwrap();
+    for file in ["top.py", "a/mid.py", "a/b/deep.py"] {
+        fs::write(test_dir.join(file), "x = 1   \n").unwrap();
+    }
+
+    let output = Command::new(get_binary_path())
+        .args(["clean", "-r", "--max-depth", "2"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(test_dir.join("top.py")).unwrap(), "x = 1\n");
+    assert_eq!(fs::read_to_string(test_dir.join("a/mid.py")).unwrap(), "x = 1\n");
+    assert_eq!(fs::read_to_string(test_dir.join("a/b/deep.py")).unwrap(), "x = 1   \n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_preserve_mtime() {
+    let test_dir = std::env::temp_dir().join("refmt_test_preserve_mtime");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let test_file = test_dir.join("notes.txt");
+    fs::write(&test_file, "Done ✅   \n").unwrap();
+    let old = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+    fs::File::options().write(true).open(&test_file).unwrap().set_modified(old).unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["--preserve-mtime", "clean"])
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "Done ✅\n");
+    assert_eq!(fs::metadata(&test_file).unwrap().modified().unwrap(), old);
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_undo() {
+    let test_dir = std::env::temp_dir().join("refmt_test_undo");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(test_dir.join("refmt.toml"), "").unwrap();
+    fs::write(test_dir.join("notes.txt"), "Done ✅   \n").unwrap();
+    fs::write(test_dir.join("My File.txt"), "plain\n").unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(get_binary_path())
+            .args(args)
+            .arg(&test_dir)
+            .output()
+            .expect("Failed to execute refmt")
+    };
+
+    assert!(run(&["clean"]).status.success());
+    assert!(run(&["rename_files", "--to-lowercase", "--underscored"]).status.success());
+    assert_eq!(fs::read_to_string(test_dir.join("notes.txt")).unwrap(), "Done ✅\n");
+    assert!(test_dir.join("my_file.txt").exists());
+
+    let output = run(&["undo", "--list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().count(), 2, "{}", stdout);
+    assert!(stdout.contains("refmt clean"));
+
+    // Latest run first: the rename, then the clean
+    assert!(run(&["undo"]).status.success());
+    assert!(test_dir.join("My File.txt").exists());
+    assert!(!test_dir.join("my_file.txt").exists());
+    assert!(run(&["undo", "--last"]).status.success());
+    assert_eq!(fs::read_to_string(test_dir.join("notes.txt")).unwrap(), "Done ✅   \n");
+
+    let output = run(&["undo"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Nothing to undo"));
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_check_mode() {
+    let test_dir = std::env::temp_dir().join("refmt_test_check_mode");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(test_dir.join("notes.txt"), "Done ✅   \n").unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(get_binary_path())
+            .args(args)
+            .arg(&test_dir)
+            .output()
+            .expect("Failed to execute refmt")
+    };
+
+    for args in [&["clean", "--check"][..], &["emojis", "--check"], &["--check"]] {
+        let output = run(args);
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        assert!(String::from_utf8_lossy(&output.stdout).contains("notes.txt"));
+    }
+    assert_eq!(fs::read_to_string(test_dir.join("notes.txt")).unwrap(), "Done ✅   \n");
+    assert!(!test_dir.join(".refmt").exists());
+
+    assert!(run(&["--yes"]).status.success());
+    for args in [&["clean", "--check"][..], &["emojis", "--check"], &["--check"]] {
+        assert_eq!(run(args).status.code(), Some(0), "{:?}", args);
+    }
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_diff_preview() {
+    let test_dir = std::env::temp_dir().join("refmt_test_diff_preview");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(test_dir.join("app.py"), "user_name = 1  \nprint(user_name)\n").unwrap();
+    fs::write(test_dir.join("notes.md"), "Done ✅\n").unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::new(get_binary_path())
+            .args(args)
+            .arg(&test_dir)
+            .output()
+            .expect("Failed to execute refmt");
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let stdout = run(&["clean", "--dry-run", "--diff"]);
+    assert!(stdout.contains("Would clean 1 lines in"));
+    assert!(stdout.contains("-user_name = 1  \n+user_name = 1\n"), "{}", stdout);
+
+    let stdout = run(&["emojis", "--dry-run", "--diff"]);
+    assert!(stdout.contains("-Done ✅\n+Done [x]\n"), "{}", stdout);
+
+    let stdout = run(&["convert", "--from", "snake", "--to", "camel", "-e", ".py", "--check", "--diff"]);
+    assert!(stdout.contains("+userName = 1  \n+print(userName)\n"), "{}", stdout);
+    assert!(!stdout.contains("\x1b["), "colors only on a terminal");
+
+    let stdout = run(&["--color", "always", "clean", "--dry-run", "--diff"]);
+    assert!(stdout.contains("\x1b[32m+user_name = 1\x1b[0m"), "{}", stdout);
+
+    assert_eq!(
+        fs::read_to_string(test_dir.join("app.py")).unwrap(),
+        "user_name = 1  \nprint(user_name)\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_output_json() {
+    let test_dir = std::env::temp_dir().join("refmt_test_output_json");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(test_dir.join("app.py"), "a = 1  \nb = 2  \n").unwrap();
+    fs::write(test_dir.join("Notes.txt"), "ok\n").unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::new(get_binary_path())
+            .args(["--output", "json"])
+            .args(args)
+            .arg(&test_dir)
+            .output()
+            .expect("Failed to execute refmt");
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        let summary: serde_json::Value =
+            serde_json::from_str(&stdout).unwrap_or_else(|e| panic!("{}: {}", e, stdout));
+        (output.status.success(), summary)
+    };
+
+    let (success, summary) = run(&["clean"]);
+    assert!(success);
+    assert_eq!(summary["success"], true);
+    assert_eq!(summary["files_changed"], 1);
+    assert_eq!(summary["changes"], 2);
+    assert!(summary["files"][0]["path"].as_str().unwrap().ends_with("app.py"));
+    assert_eq!(summary["files"][0]["dry_run"], false);
+    assert_eq!(summary["messages"][0], "Cleaned 2 lines in 1 file(s)");
+
+    let (_, summary) = run(&["rename_files", "--to-lowercase", "--dry-run"]);
+    let renames = summary["renames"].as_array().unwrap();
+    assert_eq!(renames.len(), 1);
+    assert!(renames[0]["to"].as_str().unwrap().ends_with("notes.txt"));
+    assert_eq!(renames[0]["dry_run"], true);
+
+    fs::write(test_dir.join("app.py"), "a = 1  \n").unwrap();
+    let (success, summary) = run(&["clean", "--check"]);
+    assert!(!success);
+    assert_eq!(summary["success"], false);
+    assert_eq!(summary["error"], "1 file(s) need whitespace cleaning");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_events_stream() {
+    let test_dir = std::env::temp_dir().join("refmt_test_events_stream");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(test_dir.join("a.py"), "x = 1  \n").unwrap();
+    fs::write(test_dir.join("b.py"), "y = 2\t\nz = 3 \n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["--events", "clean"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+
+    let events: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|e| panic!("{}: {}", e, line)))
+        .collect();
+    assert_eq!(events.len(), 4, "{:?}", events);
+    assert_eq!(events[0]["event"], "changed");
+    assert!(events[0]["path"].as_str().unwrap().ends_with("a.py"));
+    assert_eq!(events[0]["changes"], 1);
+    assert_eq!(events[1]["changes"], 2);
+    assert_eq!(events[2]["event"], "message");
+    assert_eq!(events[2]["text"], "Cleaned 3 lines in 2 file(s)");
+    assert_eq!(events[3]["event"], "finished");
+    assert_eq!(events[3]["success"], true);
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_watch() {
+    let test_dir = std::env::temp_dir().join("refmt_test_watch");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join("vendor")).unwrap();
+    fs::write(test_dir.join(".refmtignore"), "vendor/\n").unwrap();
+
+    let mut child = Command::new(get_binary_path())
+        .args(["watch", "-r", "--debounce", "50"])
+        .arg(&test_dir)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .expect("Failed to execute refmt");
+
+    // Keep touching the files until the watcher is up and has cleaned one of them
+    let file = test_dir.join("notes.md");
+    let vendored = test_dir.join("vendor").join("lib.md");
+    let mut cleaned = false;
+    for _ in 0..100 {
+        fs::write(&vendored, "Done ✅  \n").unwrap();
+        if fs::read_to_string(&file).is_ok_and(|content| content == "Done [x]\n") {
+            cleaned = true;
+            break;
+        }
+        fs::write(&file, "Done ✅  \n").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert!(cleaned, "the watcher never cleaned {}", file.display());
+    assert_eq!(fs::read_to_string(&vendored).unwrap(), "Done ✅  \n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_install_hook() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let test_dir = std::env::temp_dir().join("refmt_test_install_hook");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    // The hook finds refmt on PATH
+    let binary = get_binary_path();
+    let path = format!(
+        "{}:{}",
+        binary.parent().unwrap().display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(&test_dir)
+            .env("PATH", &path)
+            .output()
+            .expect("Failed to execute git")
+            .status
+            .success()
+    };
+    let install = |args: &[&str]| {
+        Command::new(&binary)
+            .arg("install-hook")
+            .args(args)
+            .arg(&test_dir)
+            .output()
+            .expect("Failed to execute refmt")
+    };
+    assert!(git(&["init", "-q"]));
+
+    let hook = test_dir.join(".git").join("hooks").join("pre-commit");
+    fs::write(&hook, "#!/bin/sh\nexit 0\n").unwrap();
+    assert!(!install(&[]).status.success(), "an existing hook is kept");
+    assert!(install(&["--force"]).status.success());
+    assert!(install(&[]).status.success(), "refmt's own hook is replaced");
+    assert!(fs::read_to_string(&hook).unwrap().contains("refmt clean --check"));
+    assert_eq!(fs::metadata(&hook).unwrap().permissions().mode() & 0o111, 0o111);
+
+    fs::write(test_dir.join("app.py"), "x = 1  \n").unwrap();
+    assert!(git(&["add", "app.py"]));
+    assert!(!git(&["commit", "-q", "-m", "whitespace"]), "the hook rejects the commit");
+    // Fixing only the working tree copy doesn't fix what would be committed
+    fs::write(test_dir.join("app.py"), "x = 1\n").unwrap();
+    assert!(!git(&["commit", "-q", "-m", "whitespace"]), "the staged content is checked");
+    assert!(git(&["add", "app.py"]));
+    // Unstaged dirt doesn't block a clean staged file
+    fs::write(test_dir.join("app.py"), "x = 1\ny = 2  \n").unwrap();
+    assert!(git(&["commit", "-q", "-m", "clean"]));
+
+    let output = install(&["--pre-commit"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("id: refmt-clean"), "{}", stdout);
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_config_defaults() {
+    let test_dir = std::env::temp_dir().join("refmt_test_config_defaults");
+    let _ = fs::remove_dir_all(&test_dir);
+    let user_dir = test_dir.join("xdg/refmt");
+    let project = test_dir.join("project");
+    fs::create_dir_all(&user_dir).unwrap();
+    fs::create_dir_all(project.join("gen")).unwrap();
+    fs::write(
+        user_dir.join("config.toml"),
+        "[convert]\nfrom = [\"camel\"]\nto = \"kebab\"\n\n[emojis]\nmappings = { \"🚀\" = \"[launch]\" }\n",
+    )
+    .unwrap();
+    fs::write(
+        project.join("refmt.toml"),
+        "exclude = [\"**/gen/**\"]\n\n[convert]\nto = \"snake\"\n",
+    )
+    .unwrap();
+    fs::write(project.join("app.py"), "userName = 1\n").unwrap();
+    fs::write(project.join("gen/out.py"), "userName = 1\n").unwrap();
+    fs::write(project.join("NOTES.md"), "🚀 Go\n").unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(get_binary_path())
+            .env("XDG_CONFIG_HOME", test_dir.join("xdg"))
+            .args(args)
+            .arg(&project)
+            .output()
+            .expect("Failed to execute refmt")
+    };
+
+    // --from comes from the user config, --to from the project's, which wins
+    let output = run(&["convert", "-r"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(project.join("app.py")).unwrap(), "user_name = 1\n");
+    assert_eq!(fs::read_to_string(project.join("gen/out.py")).unwrap(), "userName = 1\n");
+
+    // Flags override the config
+    let output = run(&["convert", "--from", "snake", "--to", "pascal", "-r"]);
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(project.join("app.py")).unwrap(), "UserName = 1\n");
+
+    assert!(run(&["emojis"]).status.success());
+    assert_eq!(fs::read_to_string(project.join("NOTES.md")).unwrap(), "[launch] Go\n");
+
+    fs::write(project.join("refmt.toml"), "[rename]\ncase = \"lowercase\"\n").unwrap();
+    assert!(run(&["rename_files"]).status.success());
+    assert!(project.join("notes.md").exists());
+
+    fs::remove_file(user_dir.join("config.toml")).unwrap();
+    let output = run(&["convert"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No format to convert from"));
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_profiles() {
+    let test_dir = std::env::temp_dir().join("refmt_test_profiles");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(
+        test_dir.join("refmt.toml"),
+        "[extensions]\nclean = [\".rs\"]\n\n[profile.docs.extensions]\nclean = [\".md\"]\n",
+    )
+    .unwrap();
+    fs::write(test_dir.join("lib.rs"), "fn main() {}  \n").unwrap();
+    fs::write(test_dir.join("guide.md"), "# Guide  \n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--profile", "docs"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(test_dir.join("guide.md")).unwrap(), "# Guide\n");
+    assert_eq!(fs::read_to_string(test_dir.join("lib.rs")).unwrap(), "fn main() {}  \n");
+
+    let output = Command::new(get_binary_path())
+        .args(["--profile", "cpp", "clean"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown profile 'cpp'"));
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_init() {
+    let test_dir = std::env::temp_dir().join("refmt_test_init");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(test_dir.join("pyproject.toml"), "[project]\nname = \"demo\"\n").unwrap();
+    fs::write(test_dir.join("app.py"), "x = 1  \n").unwrap();
+    fs::write(test_dir.join("notes.txt"), "note  \n").unwrap();
+
+    let init = |args: &[&str]| {
+        Command::new(get_binary_path())
+            .arg("init")
+            .args(args)
+            .arg(&test_dir)
+            .output()
+            .expect("Failed to execute refmt")
+    };
+
+    let output = init(&[]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("for Python"));
+    let config = fs::read_to_string(test_dir.join("refmt.toml")).unwrap();
+    assert!(config.contains("convert = [\".py\", \".pyi\"]"), "{}", config);
+
+    // The written file is a valid config: clean uses its extensions
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--show-extensions"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(".pyi"));
+
+    let output = init(&[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--force"));
+
+    // --with-hooks needs a git repository
+    let output = init(&["--force", "--with-hooks"]);
+    assert!(!output.status.success());
+    Command::new("git")
+        .args(["init", "-q"])
+        .current_dir(&test_dir)
+        .status()
+        .expect("Failed to run git");
+    let output = init(&["--force", "--with-hooks"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(test_dir.join(".git/hooks/pre-commit").exists());
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_list_formats() {
+    let output = Command::new(get_binary_path())
+        .arg("list-formats")
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("screaming-snake   USER_ACCOUNT_ID"), "{}", stdout);
+    assert!(stdout.contains("screaming-snake -> camel, pascal, train, ada: acronym casing"));
+
+    let output = Command::new(get_binary_path())
+        .args(["--output", "json", "list-formats"])
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    let formats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let formats = formats.as_array().unwrap();
+    assert_eq!(formats.len(), 10);
+    assert_eq!(formats[0]["name"], "camel");
+    assert_eq!(formats[0]["example"], "userAccountId");
+    assert_eq!(formats[0]["lossy"][0]["to"], "snake");
+    assert_eq!(formats[2]["lossy"][0]["to"], "camel");
+}
+
+#[test]
+fn test_cli_stats() {
+    let test_dir = std::env::temp_dir().join("refmt_test_stats");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join("lib")).unwrap();
+    fs::write(test_dir.join("app.py"), "user_name = get_user()\nuserId = 1\n").unwrap();
+    fs::write(test_dir.join("lib/util.js"), "const firstName = lastName;\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .arg("stats")
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("5 identifier(s) in 2 file(s):"), "{}", stdout);
+    assert!(stdout.contains("  camel                    3   60.0%"), "{}", stdout);
+    assert!(stdout.contains("EXTENSION     camel     snake"), "{}", stdout);
+    assert!(stdout.contains(".js               2         0"), "{}", stdout);
+
+    let output = Command::new(get_binary_path())
+        .args(["--output", "json", "stats"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stats["files_scanned"], 2);
+    assert_eq!(stats["total"]["snake"], 2);
+    assert_eq!(stats["by_extension"][".py"]["camel"], 1);
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_default_command_steps() {
+    let test_dir = std::env::temp_dir().join("refmt_test_default_steps");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join("xdg")).unwrap();
+    let project = test_dir.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("Notes.md"), "Done ✅  \n").unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(get_binary_path())
+            .env("XDG_CONFIG_HOME", test_dir.join("xdg"))
+            .arg(&project)
+            .args(args)
+            .output()
+            .expect("Failed to execute refmt")
+    };
+
+    // Only the listed steps run
+    let output = run(&["--steps", "clean"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(project.join("Notes.md")).unwrap(), "Done ✅\n");
+    assert!(!run(&["--steps", "indent"]).status.success());
+
+    // Without --steps, those of refmt.toml
+    fs::write(project.join("refmt.toml"), "steps = [\"emojis\", \"rename\"]\n").unwrap();
+    let output = run(&[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(project.join("notes.md")).unwrap(), "Done [x]\n");
+
+    // Converting needs formats from refmt.toml
+    fs::write(project.join("app.py"), "userName = 1\n").unwrap();
+    let output = run(&["--steps", "convert"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("convert.from and convert.to"));
+    fs::write(
+        project.join("refmt.toml"),
+        "[convert]\nfrom = [\"camel\"]\nto = \"snake\"\n",
+    )
+    .unwrap();
+    let output = run(&["--steps", "convert"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Identifiers converted: 1 file(s)"));
+    assert_eq!(fs::read_to_string(project.join("app.py")).unwrap(), "user_name = 1\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_default_command_diff() {
+    let test_dir = std::env::temp_dir().join("refmt_test_default_diff");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(test_dir.join("notes.md"), "Done ✅ \nok\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .env("XDG_CONFIG_HOME", test_dir.join("xdg"))
+        .arg(&test_dir)
+        .args(["--dry-run", "--diff", "--no-pager"])
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+
+    // The emoji and whitespace changes to the line show as one hunk
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("+++ ").count(), 1, "{}", stdout);
+    assert!(stdout.contains("-Done ✅ \n+Done [x]\n"), "{}", stdout);
+    assert_eq!(fs::read_to_string(test_dir.join("notes.md")).unwrap(), "Done ✅ \nok\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_presets() {
+    let test_dir = std::env::temp_dir().join("refmt_test_presets");
+    let _ = fs::remove_dir_all(&test_dir);
+    let project = test_dir.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("User Model.py"), "userName = 1  # ✅  \n").unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(get_binary_path())
+            .env("XDG_CONFIG_HOME", test_dir.join("xdg"))
+            .arg(&project)
+            .args(args)
+            .output()
+            .expect("Failed to execute refmt")
+    };
+
+    let output = run(&["--preset", "code-hygiene"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        fs::read_to_string(project.join("User Model.py")).unwrap(),
+        "userName = 1  # ✅\n"
+    );
+    assert!(!run(&["--preset", "tidy"]).status.success());
+    assert!(!run(&["--preset", "code-hygiene", "--steps", "clean"]).status.success());
+
+    // From refmt.toml, whose settings override the preset's
+    fs::write(
+        project.join("refmt.toml"),
+        "preset = \"snake-everything\"\n[rename]\nspaces = \"hyphen\"\n",
+    )
+    .unwrap();
+    let output = run(&[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        fs::read_to_string(project.join("user-model.py")).unwrap(),
+        "user_name = 1  # ✅\n"
+    );
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_default_command_step_extensions() {
+    let test_dir = std::env::temp_dir().join("refmt_test_step_extensions");
+    let _ = fs::remove_dir_all(&test_dir);
+    let project = test_dir.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(
+        project.join("refmt.toml"),
+        "[extensions]\nemojis = [\".md\"]\nclean = [\".rs\"]\nrename = [\".txt\"]\n",
+    )
+    .unwrap();
+    fs::write(project.join("Guide.md"), "Done ✅  \n").unwrap();
+    fs::write(project.join("Main.rs"), "// ✅  \n").unwrap();
+    fs::write(project.join("Notes.txt"), "notes\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .env("XDG_CONFIG_HOME", test_dir.join("xdg"))
+        .arg(&project)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // Each step only touches its own extensions
+    assert_eq!(fs::read_to_string(project.join("Guide.md")).unwrap(), "Done [x]  \n");
+    assert_eq!(fs::read_to_string(project.join("Main.rs")).unwrap(), "// ✅\n");
+    assert!(project.join("notes.txt").exists());
+    let names: Vec<String> = fs::read_dir(&project)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    assert!(names.contains(&"Guide.md".to_string()), "{:?}", names);
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_default_command_dry_run_report() {
+    let test_dir = std::env::temp_dir().join("refmt_test_dry_run_report");
+    let _ = fs::remove_dir_all(&test_dir);
+    let project = test_dir.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("Notes.md"), "Done ✅  \n").unwrap();
+    fs::write(project.join("plain.txt"), "text  \n").unwrap();
+    fs::write(project.join("clean.txt"), "clean\n").unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(get_binary_path())
+            .env("XDG_CONFIG_HOME", test_dir.join("xdg"))
+            .arg(&project)
+            .arg("--dry-run")
+            .args(args)
+            .output()
+            .expect("Failed to execute refmt")
+    };
+
+    let output = run(&[]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let table: Vec<&str> = stdout.lines().skip_while(|l| !l.starts_with("FILE")).collect();
+    assert_eq!(table.len(), 3, "{}", stdout);
+    assert!(table[0].contains("RENAME") && table[0].ends_with("CLEAN"), "{}", stdout);
+    assert!(table[1].contains("Notes.md") && table[1].contains("notes.md"), "{}", stdout);
+    assert!(table[2].contains("plain.txt") && table[2].ends_with("1"), "{}", stdout);
+
+    let output = run(&["--output", "json"]);
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let report = summary["report"].as_array().unwrap();
+    assert_eq!(report.len(), 2);
+    assert_eq!(report[0]["renamed_to"], project.join("notes.md").display().to_string());
+    assert_eq!(report[0]["steps"][1]["step"], "emojis");
+    assert_eq!(report[1]["steps"][0]["changes"], 1);
+    assert_eq!(fs::read_to_string(project.join("Notes.md")).unwrap(), "Done ✅  \n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_clean_tabs_and_spaces() {
+    let test_dir = std::env::temp_dir().join("refmt_test_clean_tabs");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let file = test_dir.join("main.py");
+    fs::write(&file, "def f():\n\tif x:  \n\t\treturn 1\n").unwrap();
+
+    let clean = |args: &[&str]| {
+        Command::new(get_binary_path())
+            .arg("clean")
+            .args(args)
+            .arg(&file)
+            .output()
+            .expect("Failed to execute refmt")
+    };
+
+    assert!(clean(&["--tabs-to-spaces", "2"]).status.success());
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "def f():\n  if x:\n    return 1\n"
+    );
+    assert!(clean(&["--spaces-to-tabs", "2"]).status.success());
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "def f():\n\tif x:\n\t\treturn 1\n"
+    );
+    assert!(!clean(&["--tabs-to-spaces", "2", "--spaces-to-tabs", "2"]).status.success());
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_clean_file_end() {
+    let test_dir = std::env::temp_dir().join("refmt_test_clean_file_end");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let unterminated = test_dir.join("unterminated.txt");
+    let padded = test_dir.join("padded.md");
+    fs::write(&unterminated, "last line").unwrap();
+    fs::write(&padded, "# Title\n\n\n\n").unwrap();
+
+    // Off by default
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--check"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success());
+
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--ensure-final-newline", "--trim-trailing-blank-lines"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(&unterminated).unwrap(), "last line\n");
+    assert_eq!(fs::read_to_string(&padded).unwrap(), "# Title\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_clean_markdown_breaks() {
+    let test_dir = std::env::temp_dir().join("refmt_test_clean_markdown_breaks");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let doc = test_dir.join("doc.md");
+    let notes = test_dir.join("notes.txt");
+    fs::write(&doc, "Roses are red,   \nviolets blue. \n").unwrap();
+    fs::write(&notes, "Roses are red,  \nviolets blue.\n").unwrap();
+
+    // Hard breaks in Markdown are kept by default, as exactly two spaces
+    let output = Command::new(get_binary_path())
+        .arg("clean")
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(&doc).unwrap(), "Roses are red,  \nviolets blue.\n");
+    assert_eq!(fs::read_to_string(&notes).unwrap(), "Roses are red,\nviolets blue.\n");
+
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--markdown-breaks", "backslash"])
+        .arg(&doc)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(&doc).unwrap(), "Roses are red,\\\nviolets blue.\n");
+
+    fs::write(&doc, "Roses are red,  \nviolets blue.\n").unwrap();
+    let output = Command::new(get_binary_path())
+        .args(["clean", "--markdown-breaks", "remove"])
+        .arg(&doc)
+        .output()
+        .expect("Failed to execute refmt");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(&doc).unwrap(), "Roses are red,\nviolets blue.\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_dot_root() {
+    let test_dir = std::env::temp_dir().join("refmt_test_dot_root");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join("queries")).unwrap();
+    fs::create_dir_all(test_dir.join(".cache")).unwrap();
+
+    let sql_file = test_dir.join("queries/report.sql");
+    fs::write(&sql_file, "select 1;\n").unwrap();
+    let hidden_file = test_dir.join(".cache/report.sql");
+    fs::write(&hidden_file, "select 1;\n").unwrap();
+    let rust_file = test_dir.join("main.rs");
+    fs::write(&rust_file, "const N: u64 = 1000000;\n").unwrap();
+    let skipped_file = test_dir.join("skipped.rs");
+    fs::write(&skipped_file, "const N: u64 = 1000000;\n").unwrap();
+
+    // Walking "." must not treat the root itself as a hidden directory
+    let output = Command::new(get_binary_path())
+        .current_dir(&test_dir)
+        .args(["sql", "."])
+        .output()
+        .expect("Failed to execute refmt sql");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&sql_file).unwrap(), "SELECT 1;\n");
+    assert_eq!(fs::read_to_string(&hidden_file).unwrap(), "select 1;\n");
+
+    let output = Command::new(get_binary_path())
+        .current_dir(&test_dir)
+        .args(["--exclude", "skipped.rs", "numbers", "."])
+        .output()
+        .expect("Failed to execute refmt numbers");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&rust_file).unwrap(), "const N: u64 = 1_000_000;\n");
+    assert_eq!(fs::read_to_string(&skipped_file).unwrap(), "const N: u64 = 1000000;\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_daemon_socket_survives_bad_client() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let test_dir = std::env::temp_dir().join("refmt_test_daemon_socket");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let socket = test_dir.join("refmt.sock");
+
+    let mut child = Command::new(get_binary_path())
+        .arg("daemon")
+        .arg("--socket")
+        .arg(&socket)
+        .spawn()
+        .expect("Failed to execute refmt");
+
+    let connect = || {
+        for _ in 0..50 {
+            if let Ok(stream) = UnixStream::connect(&socket) {
+                return stream;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        panic!("Daemon did not start");
+    };
+
+    // A request that isn't UTF-8 fails this connection only
+    let mut stream = connect();
+    stream.write_all(b"\xff\xfe\n").unwrap();
+    let mut response = String::new();
+    let _ = BufReader::new(&stream).read_line(&mut response);
+    drop(stream);
+
+    let mut stream = connect();
+    stream
+        .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"shutdown\"}\n")
+        .unwrap();
+    let mut response = String::new();
+    BufReader::new(&stream).read_line(&mut response).unwrap();
+    assert!(response.contains("\"id\":1"));
+
+    assert!(child.wait().unwrap().success());
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_journal_keep() {
+    let test_dir = std::env::temp_dir().join("refmt_test_journal_keep");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(test_dir.join("refmt.toml"), "").unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(get_binary_path())
+            .args(args)
+            .arg(&test_dir)
+            .output()
+            .expect("Failed to execute refmt")
+    };
+
+    for spaces in ["  ", "   ", "    "] {
+        fs::write(test_dir.join("notes.txt"), format!("line{}\n", spaces)).unwrap();
+        assert!(run(&["--journal-keep", "2", "clean"]).status.success());
+    }
+
+    let output = run(&["undo", "--list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().count(), 2, "{}", stdout);
+    // Only the previous contents of the kept runs are left
+    let objects = fs::read_dir(test_dir.join(".refmt/journal/objects")).unwrap().count();
+    assert_eq!(objects, 2);
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_comments_diff_and_convert() {
+    let test_dir = std::env::temp_dir().join("refmt_test_comments");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let test_file = test_dir.join("main.c");
+    fs::write(&test_file, "/* setup */
+int x;
+").unwrap();
+
+    // Dry run with a diff leaves the file alone
+    let output = Command::new(get_binary_path())
+        .args(["comments", "--to", "line", "--dry-run", "--diff"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt comments");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("-/* setup */"));
+    assert!(stdout.contains("+// setup"));
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "/* setup */\nint x;\n");
+
+    let output = Command::new(get_binary_path())
+        .args(["comments", "--to", "line"])
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt comments");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "// setup\nint x;\n");
+
+    // Without --to or --banners there is nothing to do
+    let output = Command::new(get_binary_path())
+        .arg("comments")
+        .arg(&test_dir)
+        .output()
+        .expect("Failed to execute refmt comments");
+    assert!(!output.status.success());
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+fn run_daemon(requests: &str) -> Vec<serde_json::Value> {
+    run_daemon_in(std::path::Path::new("."), requests)
+}
+
+/// Runs the daemon from `dir`, where it loads its refmt.toml
+fn run_daemon_in(dir: &std::path::Path, requests: &str) -> Vec<serde_json::Value> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new(get_binary_path())
+        .arg("daemon")
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute refmt");
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(requests.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Daemon output should be JSON"))
+        .collect()
+}
+
+#[test]
+fn test_cli_daemon_transform() {
+    let requests = concat!(
+        r#"{"jsonrpc":"2.0","id":1,"method":"transform","params":{"content":"Done ✅   \n"}}"#,
+        "\n",
+        r#"{"jsonrpc":"2.0","id":2,"method":"transform","params":{"content":"myVar = 1\n","operations":["convert"],"from":"camel","to":"snake"}}"#,
+        "\n",
+        r#"{"jsonrpc":"2.0","id":3,"method":"transform","params":{"content":"one  \ntwo \n","path":"notes.md"}}"#,
+        "\n",
+        r#"{"jsonrpc":"2.0","id":4,"method":"shutdown"}"#,
+        "\n",
+    );
+
+    let responses = run_daemon(requests);
+    assert_eq!(responses.len(), 4);
+
+    assert_eq!(responses[0]["id"], 1);
+    assert_eq!(responses[0]["result"]["content"], "Done [x]\n");
+    assert_eq!(responses[0]["result"]["stats"]["emoji_changes"], 1);
+    assert_eq!(responses[0]["result"]["stats"]["whitespace_lines"], 1);
+
+    assert_eq!(responses[1]["result"]["content"], "my_var = 1\n");
+    assert_eq!(responses[1]["result"]["stats"]["converted"], true);
+
+    // A Markdown buffer keeps its hard line break
+    assert_eq!(responses[2]["result"]["content"], "one  \ntwo\n");
+
+    assert_eq!(responses[3]["id"], 4);
+}
+
+#[test]
+fn test_cli_daemon_process_and_plan_renames() {
+    let test_dir = std::env::temp_dir().join("refmt_test_cli_daemon");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    let test_file = test_dir.join("Notes.md");
+    fs::write(&test_file, "Task ✅  \n").unwrap();
+
+    let requests = format!(
+        "{}\n{}\n{}\n",
+        serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "process", "params": {"path": test_dir}}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "planRenames", "params": {"path": test_dir, "case": "lowercase"}}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 3, "method": "bogus"}),
+    );
+
+    let responses = run_daemon(&requests);
+    assert_eq!(responses.len(), 3);
+
+    assert_eq!(responses[0]["result"]["files_changed"], 1);
+    assert_eq!(fs::read_to_string(&test_file).unwrap(), "Task [x]\n");
+
+    let renames = responses[1]["result"]["renames"].as_array().unwrap();
+    assert_eq!(renames.len(), 1);
+    assert!(renames[0]["to"].as_str().unwrap().ends_with("notes.md"));
+    // Planning must not rename anything
+    assert!(test_file.exists());
+
+    assert_eq!(responses[2]["error"]["code"], -32601);
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_cli_daemon_config() {
+    let test_dir = std::env::temp_dir().join("refmt_test_cli_daemon_config");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(
+        test_dir.join("refmt.toml"),
+        "exclude = [\"vendor.md\"]\n\n[emojis.mappings]\n\"✅\" = \"(done)\"\n",
+    )
+    .unwrap();
+    fs::write(test_dir.join("notes.md"), "Task ✅\n").unwrap();
+    fs::write(test_dir.join("vendor.md"), "Task ✅  \n").unwrap();
+
+    let requests = format!(
+        "{}\n{}\n{}\n",
+        serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "process", "params": {"path": "."}}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "transform", "params": {"content": "x  \n", "path": "vendor.md"}}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 3, "method": "transform", "params": {"content": "✅\n", "path": "a.md"}}),
+    );
+    let responses = run_daemon_in(&test_dir, &requests);
+
+    assert_eq!(responses[0]["result"]["files_changed"], 1);
+    assert_eq!(fs::read_to_string(test_dir.join("notes.md")).unwrap(), "Task (done)\n");
+    assert_eq!(fs::read_to_string(test_dir.join("vendor.md")).unwrap(), "Task ✅  \n");
+    assert_eq!(responses[1]["result"]["changed"], false);
+    assert_eq!(responses[2]["result"]["content"], "(done)\n");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn test_cli_serve_transform() {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+
+    // Grab a free port, then hand it to the server
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    let mut child = Command::new(get_binary_path())
+        .args(["serve", "--port", &port.to_string()])
+        .spawn()
+        .expect("Failed to execute refmt");
+
+    let post = |target: &str, body: &str| {
+        let mut stream = None;
+        for _ in 0..50 {
+            if let Ok(s) = TcpStream::connect(("127.0.0.1", port)) {
+                stream = Some(s);
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        let mut stream = stream.expect("Server did not start");
+
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            target,
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let json = &response[response.find("\r\n\r\n").unwrap() + 4..];
+        let result: serde_json::Value = serde_json::from_str(json).unwrap();
+        (response[9..12].to_string(), result)
+    };
+
+    let (status, result) = post("/transform", "Done ✅  \n");
+    assert_eq!(status, "200");
+    assert_eq!(result["content"], "Done [x]\n");
+    assert_eq!(result["stats"]["whitespace_lines"], 1);
+
+    // Query values are percent-decoded
+    let (status, result) = post("/transform?operations=emojis%2Cclean", "Done ✅  \n");
+    assert_eq!(status, "200");
+    assert_eq!(result["content"], "Done [x]\n");
+
+    let (status, result) = post("/transform?operations=emojis%2", "Done\n");
+    assert_eq!(status, "400");
+    assert!(result["error"].as_str().unwrap().contains("percent-encoding"));
+
+    child.kill().unwrap();
+    let _ = child.wait();
+}
@@ -0,0 +1,215 @@
+//! Benchmarks for the content transformers and the combined file pipeline
+//!
+//! Corpora are generated deterministically so runs are comparable:
+//! - a camelCase source file (converter)
+//! - emoji-heavy markdown (emoji transformer)
+//! - source with trailing whitespace on many lines (whitespace cleaner)
+//! - a source tree on disk (combined processing, IO included)
+//!
+//! Run with `cargo bench -p refmt-core`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use refmt_core::{
+    CaseConverter, CaseFormat, CombinedOptions, CombinedProcessor, EmojiTransformer,
+    WhitespaceCleaner,
+};
+use std::fs;
+use std::path::PathBuf;
+
+/// Minimal deterministic generator so corpora don't depend on a RNG crate
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> usize {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 33) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.next() % items.len()]
+    }
+}
+
+const WORDS: &[&str] = &[
+    "user", "name", "value", "count", "index", "buffer", "request", "handler", "config", "item",
+];
+
+/// JavaScript-like source with camelCase identifiers on most lines
+fn camel_source(lines: usize) -> String {
+    let mut rng = Lcg(1);
+    let mut out = String::new();
+    for i in 0..lines {
+        let a = rng.pick(WORDS);
+        let b = rng.pick(WORDS);
+        let c = rng.pick(WORDS);
+        match i % 4 {
+            0 => out.push_str(&format!("function get{}{}(x) {{\n", capitalize(a), capitalize(b))),
+            1 => out.push_str(&format!("    let {}{} = x + {};\n", a, capitalize(c), i)),
+            2 => out.push_str("    // plain comment without identifiers\n"),
+            _ => out.push_str(&format!("    return {}{};\n}}\n", b, capitalize(a))),
+        }
+    }
+    out
+}
+
+/// Markdown task lists sprinkled with task and decorative emojis
+fn emoji_markdown(lines: usize) -> String {
+    let emojis = ["✅", "☐", "⚠", "🟢", "🔴", "🚀", "🎉", "📝", ""];
+    let mut rng = Lcg(2);
+    let mut out = String::from("# Status\n\n");
+    for _ in 0..lines {
+        out.push_str(&format!(
+            "- {} {} the {} {}\n",
+            rng.pick(&emojis),
+            rng.pick(WORDS),
+            rng.pick(WORDS),
+            rng.pick(&emojis),
+        ));
+    }
+    out
+}
+
+/// Source where roughly half the lines carry trailing spaces or tabs
+fn dirty_whitespace(lines: usize) -> String {
+    let trailing = ["", "", " ", "   ", "\t", " \t "];
+    let mut rng = Lcg(3);
+    let mut out = String::new();
+    for _ in 0..lines {
+        out.push_str(&format!(
+            "    {} = {}({}){}\n",
+            rng.pick(WORDS),
+            rng.pick(WORDS),
+            rng.pick(WORDS),
+            rng.pick(&trailing),
+        ));
+    }
+    out
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn converter(from: CaseFormat, to: CaseFormat) -> CaseConverter {
+    CaseConverter::new(
+        from,
+        to,
+        None,
+        true,
+        false,
+        String::new(),
+        String::new(),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+    .unwrap()
+}
+
+fn bench_converter(c: &mut Criterion) {
+    let mut group = c.benchmark_group("converter");
+    let camel_to_snake = converter(CaseFormat::CamelCase, CaseFormat::SnakeCase);
+    let snake_to_camel = converter(CaseFormat::SnakeCase, CaseFormat::CamelCase);
+
+    for lines in [1_000, 10_000] {
+        let source = camel_source(lines);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(BenchmarkId::new("camel_to_snake", lines), &source, |b, s| {
+            b.iter(|| camel_to_snake.convert_text(black_box(s)))
+        });
+        // No snake_case identifiers: measures the no-match path
+        group.bench_with_input(BenchmarkId::new("snake_to_camel_no_match", lines), &source, |b, s| {
+            b.iter(|| snake_to_camel.convert_text(black_box(s)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_emoji(c: &mut Criterion) {
+    let mut group = c.benchmark_group("emoji");
+    let transformer = EmojiTransformer::with_defaults();
+
+    for lines in [1_000, 10_000] {
+        let markdown = emoji_markdown(lines);
+        group.throughput(Throughput::Bytes(markdown.len() as u64));
+        group.bench_with_input(BenchmarkId::new("markdown", lines), &markdown, |b, s| {
+            b.iter(|| transformer.transform_str(black_box(s)))
+        });
+
+        let ascii = camel_source(lines);
+        group.bench_with_input(BenchmarkId::new("ascii_source", lines), &ascii, |b, s| {
+            b.iter(|| transformer.transform_str(black_box(s)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_whitespace(c: &mut Criterion) {
+    let mut group = c.benchmark_group("whitespace");
+    let cleaner = WhitespaceCleaner::with_defaults();
+
+    for lines in [1_000, 10_000] {
+        let dirty = dirty_whitespace(lines);
+        group.throughput(Throughput::Bytes(dirty.len() as u64));
+        group.bench_with_input(BenchmarkId::new("dirty", lines), &dirty, |b, s| {
+            b.iter(|| cleaner.clean_str(black_box(s)))
+        });
+
+        let clean = cleaner.clean_str(&dirty).0.into_owned();
+        group.bench_with_input(BenchmarkId::new("clean", lines), &clean, |b, s| {
+            b.iter(|| cleaner.clean_str(black_box(s)))
+        });
+    }
+    group.finish();
+}
+
+/// Writes an already-normalized tree so repeated runs exercise traversal, filtering
+/// and reads without rewriting anything
+fn clean_tree(files: usize) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("refmt_bench_tree_{}", files));
+    let _ = fs::remove_dir_all(&root);
+
+    let cleaner = WhitespaceCleaner::with_defaults();
+    let emoji = EmojiTransformer::with_defaults();
+    for i in 0..files {
+        let dir = root.join(format!("pkg{}", i % 16));
+        fs::create_dir_all(&dir).unwrap();
+        let content = if i % 3 == 0 {
+            emoji.transform_str(&emoji_markdown(100)).0.into_owned()
+        } else {
+            camel_source(100)
+        };
+        let (content, _) = cleaner.clean_str(&content);
+        let ext = if i % 3 == 0 { "md" } else { "js" };
+        fs::write(dir.join(format!("file{}.{}", i, ext)), content.as_bytes()).unwrap();
+    }
+    root
+}
+
+fn bench_combined(c: &mut Criterion) {
+    let mut group = c.benchmark_group("combined");
+    group.sample_size(20);
+
+    for files in [100, 1_000] {
+        let root = clean_tree(files);
+        let processor = CombinedProcessor::new(CombinedOptions::default());
+        group.throughput(Throughput::Elements(files as u64));
+        group.bench_with_input(BenchmarkId::new("clean_tree", files), &root, |b, root| {
+            b.iter(|| processor.process(black_box(root)).unwrap())
+        });
+        let _ = fs::remove_dir_all(&root);
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_converter, bench_emoji, bench_whitespace, bench_combined);
+criterion_main!(benches);
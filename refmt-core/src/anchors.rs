@@ -0,0 +1,273 @@
+//! Markdown anchor link updating
+//!
+//! Headings get GitHub-style anchors (`## Getting Started` -> `#getting-started`), so
+//! when a transform changes a heading, links to the old anchor break. Transformers
+//! record the anchors they changed in [`AnchorChanges`], which then rewrites the
+//! matching `#anchor` links, in the same document or in other files of the
+//! processed tree.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+use crate::markdown;
+use crate::report::{report, EventSink};
+use crate::vfs::Fs;
+
+/// Inline links and images: `](target#anchor)`
+static INLINE_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\]\(([^)\s#]*)#([^)\s]+)\)").unwrap());
+/// Reference definitions: `[id]: target#anchor`
+static REFERENCE_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^( {0,3}\[[^\]]+\]:[ \t]*)([^\s#]*)#(\S+)").unwrap());
+/// Links inside headings render as their text
+static HEADING_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").unwrap());
+
+/// Whether a file is Markdown
+pub fn is_markdown(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("md" | "markdown" | "qmd" | "mdx")
+    )
+}
+
+/// Computes the GitHub-style anchor of a heading
+///
+/// Text is lowercased, punctuation other than `-` and `_` is dropped and spaces
+/// become hyphens. Links render as their text.
+pub fn slugify(heading: &str) -> String {
+    let text = HEADING_LINK.replace_all(heading, "$1");
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Anchors of every heading in a document, with `-1`, `-2`, ... appended to repeats
+fn anchors(content: &str) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    markdown::headings(content)
+        .iter()
+        .map(|heading| {
+            let slug = slugify(heading);
+            let count = seen.entry(slug.clone()).or_insert(0);
+            let anchor = if *count == 0 {
+                slug
+            } else {
+                format!("{}-{}", slug, count)
+            };
+            *count += 1;
+            anchor
+        })
+        .collect()
+}
+
+/// Removes `.` and `..` components without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Heading anchors renamed by a transform, per file
+#[derive(Debug, Clone, Default)]
+pub struct AnchorChanges {
+    renames: BTreeMap<PathBuf, BTreeMap<String, String>>,
+}
+
+impl AnchorChanges {
+    /// Creates an empty set of changes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if no anchor changed
+    pub fn is_empty(&self) -> bool {
+        self.renames.is_empty()
+    }
+
+    /// Records the anchors that changed between two versions of a Markdown file
+    ///
+    /// Headings are paired by position; if the transform added or removed headings
+    /// nothing is recorded, since the pairing would be a guess.
+    pub fn record(&mut self, path: &Path, old_content: &str, new_content: &str) {
+        let old = anchors(old_content);
+        let new = anchors(new_content);
+        if old.len() != new.len() {
+            return;
+        }
+
+        let renames: BTreeMap<String, String> = old
+            .into_iter()
+            .zip(new)
+            .filter(|(old, new)| old != new)
+            .collect();
+        if !renames.is_empty() {
+            self.renames
+                .entry(normalize(path))
+                .or_default()
+                .extend(renames);
+        }
+    }
+
+    /// Adds the changes recorded in `other`
+    pub fn merge(&mut self, other: AnchorChanges) {
+        for (path, renames) in other.renames {
+            self.renames.entry(path).or_default().extend(renames);
+        }
+    }
+
+    /// Rewrites links to renamed anchors in the content of the Markdown file at `path`
+    ///
+    /// Returns the new content and the number of links updated.
+    pub fn rewrite_links<'a>(&self, path: &Path, content: &'a str) -> (Cow<'a, str>, usize) {
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let mut updated = 0;
+
+        let mut renamed = |target: &str, anchor: &str| -> Option<String> {
+            let file = if target.is_empty() {
+                normalize(path)
+            } else {
+                normalize(&dir.join(target))
+            };
+            let new = self.renames.get(&file)?.get(anchor)?;
+            updated += 1;
+            Some(new.clone())
+        };
+
+        let content_after_inline = INLINE_LINK.replace_all(content, |caps: &Captures| {
+            match renamed(&caps[1], &caps[2]) {
+                Some(anchor) => format!("]({}#{})", &caps[1], anchor),
+                None => caps[0].to_string(),
+            }
+        });
+        let rewritten = match REFERENCE_LINK.replace_all(&content_after_inline, |caps: &Captures| {
+            match renamed(&caps[2], &caps[3]) {
+                Some(anchor) => format!("{}{}#{}", &caps[1], &caps[2], anchor),
+                None => caps[0].to_string(),
+            }
+        }) {
+            Cow::Borrowed(_) => content_after_inline,
+            Cow::Owned(owned) => Cow::Owned(owned),
+        };
+
+        if updated == 0 {
+            return (Cow::Borrowed(content), 0);
+        }
+        (rewritten, updated)
+    }
+
+    /// Rewrites links to renamed anchors in the Markdown files among `files`, read
+    /// from and written to `fs`, sending each changed file to `events`
+    ///
+    /// Returns the number of links updated.
+    pub fn apply(
+        &self,
+        fs: &dyn Fs,
+        files: &[PathBuf],
+        dry_run: bool,
+        events: &EventSink,
+    ) -> crate::Result<usize> {
+        if self.is_empty() {
+            return Ok(0);
+        }
+
+        let mut total = 0;
+        for path in files.iter().filter(|p| is_markdown(p)) {
+            let content = String::from_utf8(fs.read(path)?)?;
+            let (rewritten, updated) = self.rewrite_links(path, &content);
+            if updated == 0 {
+                continue;
+            }
+
+            events.file_changed(path, updated, dry_run);
+            if dry_run {
+                report!("Would update {} anchor links in '{}'", updated, path.display());
+            } else {
+                fs.write(path, rewritten.as_bytes())?;
+                report!("Updated {} anchor links in '{}'", updated, path.display());
+            }
+            total += updated;
+        }
+
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Getting Started"), "getting-started");
+        // Emoji are dropped but the space after them still becomes a hyphen
+        assert_eq!(slugify("\u{1F680} Launch"), "-launch");
+        assert_eq!(slugify("What's `new` in v2.0?"), "whats-new-in-v20");
+        assert_eq!(slugify("See [the docs](http://x)"), "see-the-docs");
+        assert_eq!(slugify("snake_case Names"), "snake_case-names");
+    }
+
+    #[test]
+    fn test_duplicate_anchors() {
+        assert_eq!(anchors("# A\n## A\n# B\n# A\n"), vec!["a", "a-1", "b", "a-2"]);
+    }
+
+    #[test]
+    fn test_rewrite_links() {
+        let mut changes = AnchorChanges::new();
+        changes.record(
+            Path::new("docs/guide.md"),
+            "# Guide\n## myOptions\n",
+            "# Guide\n## my_options\n",
+        );
+        assert!(!changes.is_empty());
+
+        // Same document
+        let (result, updated) = changes.rewrite_links(
+            Path::new("docs/guide.md"),
+            "See [options](#myoptions) and [guide](#guide).\n",
+        );
+        assert_eq!(result, "See [options](#my_options) and [guide](#guide).\n");
+        assert_eq!(updated, 1);
+
+        // Another document, through a relative path and a reference definition
+        let (result, updated) = changes.rewrite_links(
+            Path::new("README.md"),
+            "[a](docs/guide.md#myoptions)\n[b]: ./docs/../docs/guide.md#myoptions\n[c](other.md#myoptions)\n",
+        );
+        assert_eq!(
+            result,
+            "[a](docs/guide.md#my_options)\n[b]: ./docs/../docs/guide.md#my_options\n[c](other.md#myoptions)\n"
+        );
+        assert_eq!(updated, 2);
+    }
+
+    #[test]
+    fn test_changed_heading_count_is_not_recorded() {
+        let mut changes = AnchorChanges::new();
+        changes.record(Path::new("a.md"), "# One\n# Two\n", "# One\n");
+        assert!(changes.is_empty());
+    }
+}
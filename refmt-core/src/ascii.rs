@@ -0,0 +1,354 @@
+//! Non-ASCII character report
+//!
+//! Finds every non-ASCII character in source files and classifies it, so users can
+//! decide which cleanup transforms (emojis, quotes, punctuation) are worth running.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// Kind of non-ASCII character
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CharCategory {
+    /// Emoji and pictographs
+    Emoji,
+    /// Latin letters with diacritics (é, ñ, ß)
+    Accented,
+    /// Letters from other scripts (Greek, Cyrillic, CJK, ...)
+    Letter,
+    /// Zero-width characters, non-breaking and other special spaces, BOMs, bidi controls
+    Invisible,
+    /// Typographic punctuation (curly quotes, dashes, ellipsis, fullwidth forms)
+    Punctuation,
+    /// Anything else (math, currency, arrows, box drawing, ...)
+    Symbol,
+}
+
+impl CharCategory {
+    /// Classifies a non-ASCII character
+    pub fn of(c: char) -> CharCategory {
+        if is_invisible(c) {
+            CharCategory::Invisible
+        } else if is_emoji(c) {
+            CharCategory::Emoji
+        } else if is_punctuation(c) {
+            CharCategory::Punctuation
+        } else if c.is_alphabetic() {
+            if matches!(c, '\u{00C0}'..='\u{024F}' | '\u{1E00}'..='\u{1EFF}') {
+                CharCategory::Accented
+            } else {
+                CharCategory::Letter
+            }
+        } else {
+            CharCategory::Symbol
+        }
+    }
+}
+
+impl fmt::Display for CharCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CharCategory::Emoji => "emoji",
+            CharCategory::Accented => "accented letter",
+            CharCategory::Letter => "letter",
+            CharCategory::Invisible => "invisible",
+            CharCategory::Punctuation => "punctuation",
+            CharCategory::Symbol => "symbol",
+        };
+        f.write_str(name)
+    }
+}
+
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0080}'..='\u{00A0}'     // C1 controls and no-break space
+            | '\u{00AD}'            // Soft hyphen
+            | '\u{2000}'..='\u{200F}' // Special spaces, zero-width characters, direction marks
+            | '\u{2028}'..='\u{202F}' // Separators, bidi embedding, narrow no-break space
+            | '\u{205F}'..='\u{206F}' // Word joiner, invisible operators, bidi isolates
+            | '\u{3000}'            // Ideographic space
+            | '\u{FE00}'..='\u{FE0F}' // Variation selectors
+            | '\u{FEFF}'            // Byte order mark
+            | '\u{E0000}'..='\u{E007F}' // Tags
+    )
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1F000}'..='\u{1F02F}'   // Mahjong tiles
+            | '\u{1F0CF}'           // Playing card
+            | '\u{1F18E}'
+            | '\u{1F191}'..='\u{1F19A}'
+            | '\u{1F1E6}'..='\u{1F1FF}' // Regional indicators (flags)
+            | '\u{1F300}'..='\u{1F64F}' // Pictographs and emoticons
+            | '\u{1F680}'..='\u{1F6FF}' // Transport and map symbols
+            | '\u{1F900}'..='\u{1FAFF}' // Supplemental symbols and pictographs
+            | '\u{2600}'..='\u{27BF}'   // Miscellaneous symbols and dingbats
+            | '\u{2B50}'
+            | '\u{2B55}'
+            | '\u{2B1B}'..='\u{2B1C}'
+            | '\u{231A}'..='\u{231B}'
+            | '\u{23E9}'..='\u{23F3}'
+    )
+}
+
+fn is_punctuation(c: char) -> bool {
+    matches!(
+        c,
+        '\u{2010}'..='\u{2027}'     // Dashes, quotes, bullets, ellipsis
+            | '\u{2030}'..='\u{205E}' // Primes, guillemets and other general punctuation
+            | '\u{00AB}' | '\u{00BB}' // Guillemets
+            | '\u{00A1}' | '\u{00BF}' // Inverted ! and ?
+            | '\u{3001}'..='\u{3003}' // Ideographic comma and full stop
+            | '\u{FF01}'..='\u{FF0F}' // Fullwidth punctuation
+            | '\u{FF1A}'..='\u{FF20}'
+            | '\u{FF3B}'..='\u{FF40}'
+            | '\u{FF5B}'..='\u{FF65}'
+    )
+}
+
+/// One non-ASCII character found in a file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NonAsciiChar {
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, counted in characters
+    pub column: usize,
+    /// The character
+    pub ch: char,
+    /// Its category
+    pub category: CharCategory,
+}
+
+impl NonAsciiChar {
+    /// Printable form of the character; invisible characters are shown escaped
+    pub fn display_char(&self) -> String {
+        match self.category {
+            CharCategory::Invisible => self.ch.escape_unicode().to_string(),
+            _ => self.ch.to_string(),
+        }
+    }
+}
+
+/// Non-ASCII characters found in one file
+#[derive(Debug, Clone, Serialize)]
+pub struct FileFindings {
+    pub path: PathBuf,
+    pub chars: Vec<NonAsciiChar>,
+}
+
+/// Options for the non-ASCII report
+#[derive(Debug, Clone)]
+pub struct AsciiOptions {
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+}
+
+impl Default for AsciiOptions {
+    fn default() -> Self {
+        AsciiOptions {
+            file_extensions: vec![
+                ".py", ".pyx", ".pxd", ".pxi",
+                ".c", ".h", ".cpp", ".hpp",
+                ".rs", ".go", ".java",
+                ".js", ".ts", ".jsx", ".tsx",
+                ".md", ".qmd", ".txt",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            fs: vfs::real(),
+        }
+    }
+}
+
+/// Scanner reporting non-ASCII characters in files
+pub struct AsciiChecker {
+    options: AsciiOptions,
+    walker: Walker,
+}
+
+impl AsciiChecker {
+    /// Creates a new checker with the given options
+    pub fn new(options: AsciiOptions) -> Self {
+        AsciiChecker {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a checker with default options
+    pub fn with_defaults() -> Self {
+        AsciiChecker {
+            options: AsciiOptions::default(),
+            walker: Walker::default(),
+        }
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        if !self.options.fs.is_file(path) {
+            return false;
+        }
+
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none()
+    }
+
+    /// Lists the non-ASCII characters in a string
+    pub fn check_str(&self, content: &str) -> Vec<NonAsciiChar> {
+        if content.is_ascii() {
+            return Vec::new();
+        }
+
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_ascii())
+            .flat_map(|(n, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|(_, c)| !c.is_ascii())
+                    .map(move |(col, ch)| NonAsciiChar {
+                        line: n + 1,
+                        column: col + 1,
+                        ch,
+                        category: CharCategory::of(ch),
+                    })
+            })
+            .collect()
+    }
+
+    /// Lists the non-ASCII characters in a file
+    ///
+    /// Files that aren't valid UTF-8 are skipped and report nothing.
+    pub fn check_file(&self, path: &Path) -> crate::Result<Vec<NonAsciiChar>> {
+        if !self.should_process(path) {
+            return Ok(Vec::new());
+        }
+
+        let bytes = self.options.fs.read(path)?;
+        match String::from_utf8(bytes) {
+            Ok(content) => Ok(self.check_str(&content)),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Scans a directory or file, returning the files that contain non-ASCII
+    /// characters in path order
+    pub fn process(&self, path: &Path) -> crate::Result<Vec<FileFindings>> {
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.check_file(file)
+        });
+
+        let mut findings = Vec::new();
+        for (file_path, result) in files.into_iter().zip(results) {
+            let chars = result?;
+            if !chars.is_empty() {
+                findings.push(FileFindings {
+                    path: file_path,
+                    chars,
+                });
+            }
+        }
+
+        Ok(findings)
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_categories() {
+        assert_eq!(CharCategory::of('é'), CharCategory::Accented);
+        assert_eq!(CharCategory::of('ß'), CharCategory::Accented);
+        assert_eq!(CharCategory::of('λ'), CharCategory::Letter);
+        assert_eq!(CharCategory::of('\u{1F680}'), CharCategory::Emoji);
+        assert_eq!(CharCategory::of('\u{2705}'), CharCategory::Emoji);
+        assert_eq!(CharCategory::of('\u{200B}'), CharCategory::Invisible);
+        assert_eq!(CharCategory::of('\u{00A0}'), CharCategory::Invisible);
+        assert_eq!(CharCategory::of('\u{FEFF}'), CharCategory::Invisible);
+        assert_eq!(CharCategory::of('\u{2014}'), CharCategory::Punctuation);
+        assert_eq!(CharCategory::of('\u{201C}'), CharCategory::Punctuation);
+        assert_eq!(CharCategory::of('€'), CharCategory::Symbol);
+    }
+
+    #[test]
+    fn test_check_str_positions() {
+        let checker = AsciiChecker::with_defaults();
+        let found = checker.check_str("plain\ncafé \u{1F680}\nx\u{200B}y\n");
+
+        let positions: Vec<_> = found.iter().map(|f| (f.line, f.column, f.category)).collect();
+        assert_eq!(
+            positions,
+            vec![
+                (2, 4, CharCategory::Accented),
+                (2, 6, CharCategory::Emoji),
+                (3, 2, CharCategory::Invisible),
+            ]
+        );
+        assert_eq!(found[2].display_char(), "\\u{200b}");
+
+        assert!(checker.check_str("all ascii\n").is_empty());
+    }
+
+    #[test]
+    fn test_process_skips_clean_files() {
+        let test_dir = std::env::temp_dir().join("refmt_ascii_process");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+
+        fs::write(test_dir.join("a.py"), "print('ok')\n").unwrap();
+        fs::write(test_dir.join("b.py"), "# naïve\n").unwrap();
+        fs::write(test_dir.join("c.xyz"), "naïve\n").unwrap();
+
+        let findings = AsciiChecker::with_defaults().process(&test_dir).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].path.ends_with("b.py"));
+        assert_eq!(findings[0].chars[0].ch, 'ï');
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+}
@@ -1,7 +1,20 @@
 //! Case format definitions and conversion logic
 
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::Context;
+
 /// Supported case formats for identifier conversion
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Ordered as listed in [`CaseFormat::ALL`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum CaseFormat {
     /// camelCase: firstName, lastName
     CamelCase,
@@ -15,18 +28,272 @@ pub enum CaseFormat {
     KebabCase,
     /// SCREAMING-KEBAB-CASE: FIRST-NAME, LAST-NAME
     ScreamingKebabCase,
+    /// Train-Case: First-Name, Last-Name
+    TrainCase,
+    /// Ada_Case: First_Name, Last_Name
+    AdaCase,
+    /// dot.case: first.name, config.value.name
+    DotCase,
+    /// path/case: first/name, config/value/name
+    PathCase,
+}
+
+/// Error for a case format name [`CaseFormat::from_str`] doesn't know
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unknown case format '{0}'")]
+pub struct ParseCaseFormatError(String);
+
+/// Words with a fixed spelling in the capitalized formats (`ID`, `DB`, `iOS`, ...),
+/// so `user_id` becomes `userID` rather than `userId`
+///
+/// The first word of camelCase stays lowercase (`idValue`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Abbreviations {
+    /// Spelling by lowercased word
+    words: HashMap<String, String>,
+}
+
+/// An abbreviations file: `abbreviations = ["ID", "DB", "iOS"]`, the same key as in
+/// `refmt.toml`
+#[derive(Deserialize)]
+struct AbbreviationsFile {
+    #[serde(default)]
+    abbreviations: Vec<String>,
+}
+
+impl Abbreviations {
+    /// Creates a dictionary from the spellings to use
+    pub fn new<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let words = words
+            .into_iter()
+            .map(Into::into)
+            .map(|word| (word.to_lowercase(), word))
+            .collect();
+        Abbreviations { words }
+    }
+
+    /// Loads the `abbreviations` list of a TOML file
+    pub fn load(path: &Path) -> crate::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read abbreviations file '{}'", path.display()))?;
+        let file: AbbreviationsFile = toml::from_str(&content)
+            .with_context(|| format!("Invalid abbreviations file '{}'", path.display()))?;
+        Ok(Abbreviations::new(file.abbreviations))
+    }
+
+    /// The spelling of `word`, if it's in the dictionary
+    pub fn get(&self, word: &str) -> Option<&str> {
+        self.words.get(&word.to_lowercase()).map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// How digits inside identifiers are split into words
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigitBoundary {
+    /// Digits belong to the word before them: `sha_256_hash` gives `sha256_hash`
+    Attach,
+    /// Runs of digits are words of their own: `sha256Hash` gives `sha_256_hash`
+    Separate,
+    /// A digit after a letter starts a new word: `v2beta` gives `v_2beta`
+    Split,
 }
 
+impl DigitBoundary {
+    /// Regroups words split by [`CaseFormat::split_words`]
+    pub fn apply(self, words: Vec<String>) -> Vec<String> {
+        let mut result: Vec<String> = Vec::with_capacity(words.len());
+        for word in words {
+            if self == DigitBoundary::Attach {
+                match result.last_mut() {
+                    Some(previous) if word.chars().all(|c| c.is_ascii_digit()) => {
+                        previous.push_str(&word)
+                    }
+                    _ => result.push(word),
+                }
+                continue;
+            }
+
+            let mut current = String::new();
+            let mut previous: Option<char> = None;
+            for c in word.chars() {
+                let boundary = previous.is_some_and(|p| match self {
+                    DigitBoundary::Separate => p.is_ascii_digit() != c.is_ascii_digit(),
+                    _ => !p.is_ascii_digit() && c.is_ascii_digit(),
+                });
+                if boundary {
+                    result.push(std::mem::take(&mut current));
+                }
+                current.push(c);
+                previous = Some(c);
+            }
+            if !current.is_empty() {
+                result.push(current);
+            }
+        }
+        result
+    }
+}
+
+/// Whole-string versions of each format's Unicode pattern, for [`CaseFormat::detect`]
+static WHOLE_PATTERNS: LazyLock<Vec<(CaseFormat, Regex)>> = LazyLock::new(|| {
+    CaseFormat::ALL
+        .iter()
+        .map(|&format| {
+            let pattern = format!("^(?:{})$", format.unicode_pattern());
+            (format, Regex::new(&pattern).unwrap())
+        })
+        .collect()
+});
+
 impl CaseFormat {
+    /// Every case format
+    pub const ALL: [CaseFormat; 10] = [
+        CaseFormat::CamelCase,
+        CaseFormat::PascalCase,
+        CaseFormat::SnakeCase,
+        CaseFormat::ScreamingSnakeCase,
+        CaseFormat::KebabCase,
+        CaseFormat::ScreamingKebabCase,
+        CaseFormat::TrainCase,
+        CaseFormat::AdaCase,
+        CaseFormat::DotCase,
+        CaseFormat::PathCase,
+    ];
+
+    /// The short name used by the CLI and configuration files (`camel`,
+    /// `screaming-snake`, ...)
+    pub fn name(&self) -> &'static str {
+        match self {
+            CaseFormat::CamelCase => "camel",
+            CaseFormat::PascalCase => "pascal",
+            CaseFormat::SnakeCase => "snake",
+            CaseFormat::ScreamingSnakeCase => "screaming-snake",
+            CaseFormat::KebabCase => "kebab",
+            CaseFormat::ScreamingKebabCase => "screaming-kebab",
+            CaseFormat::TrainCase => "train",
+            CaseFormat::AdaCase => "ada",
+            CaseFormat::DotCase => "dot",
+            CaseFormat::PathCase => "path",
+        }
+    }
+
+    /// `user_account_id` spelled in this format
+    pub fn example(&self) -> String {
+        let words = ["user", "account", "id"].map(String::from);
+        self.join_words(&words, "", "")
+    }
+
+    /// Why converting from this format to `to` can lose information, or `None` if it
+    /// can't
+    ///
+    /// camelCase and PascalCase split words at every uppercase letter, so acronyms
+    /// come apart; formats that spell every word alike don't record which words are
+    /// acronyms; and without separators, a word starting with a digit runs into the one
+    /// before it.
+    pub fn lossy_reason(&self, to: CaseFormat) -> Option<&'static str> {
+        let joined = matches!(to, CaseFormat::CamelCase | CaseFormat::PascalCase);
+        let capitalized = joined || matches!(to, CaseFormat::TrainCase | CaseFormat::AdaCase);
+        match self {
+            _ if *self == to => None,
+            CaseFormat::CamelCase | CaseFormat::PascalCase if !joined => {
+                Some("acronyms are split into single letters (userID -> user_i_d)")
+            }
+            CaseFormat::ScreamingSnakeCase | CaseFormat::ScreamingKebabCase if capitalized => {
+                Some("acronym casing is lost (HTTP_SERVER -> httpServer)")
+            }
+            CaseFormat::CamelCase
+            | CaseFormat::PascalCase
+            | CaseFormat::TrainCase
+            | CaseFormat::AdaCase => None,
+            _ if joined => {
+                Some("a word starting with a digit joins the one before (user_2fa -> user2fa)")
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts a single identifier from one format to another
+    ///
+    /// ```
+    /// use refmt_core::CaseFormat;
+    ///
+    /// let name = CaseFormat::convert(CaseFormat::CamelCase, CaseFormat::SnakeCase, "firstName");
+    /// assert_eq!(name, "first_name");
+    /// ```
+    pub fn convert(from: CaseFormat, to: CaseFormat, text: &str) -> String {
+        to.join_words(&from.split_words(text), "", "")
+    }
+
+    /// Detects the format of a whole identifier
+    ///
+    /// Returns `None` for text that matches no format, and for single words, which
+    /// fit several (`name` is both camelCase and snake_case). Non-ASCII letters are
+    /// recognized (`größeZahl` is camelCase).
+    pub fn detect(text: &str) -> Option<CaseFormat> {
+        WHOLE_PATTERNS
+            .iter()
+            .find(|(_, regex)| regex.is_match(text))
+            .map(|&(format, _)| format)
+    }
+
+    /// Whether a whole identifier is in this format
+    pub fn matches(&self, text: &str) -> bool {
+        WHOLE_PATTERNS
+            .iter()
+            .any(|(format, regex)| format == self && regex.is_match(text))
+    }
+
     /// Returns the regex pattern for identifying this case format
     pub fn pattern(&self) -> &str {
         match self {
-            CaseFormat::CamelCase => r"\b[a-z]+(?:[A-Z][a-z0-9]*)+\b",
+            CaseFormat::CamelCase => r"\b[a-z][a-z0-9]*(?:[A-Z][a-z0-9]*)+\b",
             CaseFormat::PascalCase => r"\b[A-Z][a-z0-9]+(?:[A-Z][a-z0-9]*)+\b",
-            CaseFormat::SnakeCase => r"\b[a-z]+(?:_[a-z0-9]+)+\b",
-            CaseFormat::ScreamingSnakeCase => r"\b[A-Z]+(?:_[A-Z0-9]+)+\b",
-            CaseFormat::KebabCase => r"\b[a-z]+(?:-[a-z0-9]+)+\b",
-            CaseFormat::ScreamingKebabCase => r"\b[A-Z]+(?:-[A-Z0-9]+)+\b",
+            CaseFormat::SnakeCase => r"\b[a-z][a-z0-9]*(?:_[a-z0-9]+)+\b",
+            CaseFormat::ScreamingSnakeCase => r"\b[A-Z][A-Z0-9]*(?:_[A-Z0-9]+)+\b",
+            CaseFormat::KebabCase => r"\b[a-z][a-z0-9]*(?:-[a-z0-9]+)+\b",
+            CaseFormat::ScreamingKebabCase => r"\b[A-Z][A-Z0-9]*(?:-[A-Z0-9]+)+\b",
+            CaseFormat::TrainCase => r"\b[A-Z][a-z0-9]+(?:-[A-Z][a-z0-9]*)+\b",
+            CaseFormat::AdaCase => r"\b[A-Z][a-z0-9]+(?:_[A-Z][a-z0-9]*)+\b",
+            CaseFormat::DotCase => r"\b[a-z]+(?:\.[a-z0-9]+)+\b",
+            CaseFormat::PathCase => r"\b[a-z]+(?:/[a-z0-9]+)+\b",
+        }
+    }
+
+    /// [`pattern()`](Self::pattern) with any lowercase or uppercase letter in place of
+    /// `a-z` and `A-Z`, for identifiers such as `größeZahl` or `naïveValue`
+    pub fn unicode_pattern(&self) -> String {
+        self.pattern()
+            .replace("a-z", r"\p{Ll}")
+            .replace("A-Z", r"\p{Lu}")
+    }
+
+    /// Cheap byte-level check run before the regex scan.
+    ///
+    /// Returns `false` only when `text` cannot contain a match of [`pattern()`](Self::pattern):
+    /// separator-based formats need their separator byte, and camel/Pascal case need
+    /// an ASCII uppercase letter.
+    pub fn may_match(&self, text: &str) -> bool {
+        let bytes = text.as_bytes();
+        match self {
+            CaseFormat::SnakeCase | CaseFormat::ScreamingSnakeCase | CaseFormat::AdaCase => {
+                memchr::memchr(b'_', bytes).is_some()
+            }
+            CaseFormat::KebabCase | CaseFormat::ScreamingKebabCase | CaseFormat::TrainCase => {
+                memchr::memchr(b'-', bytes).is_some()
+            }
+            CaseFormat::DotCase => memchr::memchr(b'.', bytes).is_some(),
+            CaseFormat::PathCase => memchr::memchr(b'/', bytes).is_some(),
+            CaseFormat::CamelCase | CaseFormat::PascalCase => {
+                bytes.iter().any(u8::is_ascii_uppercase)
+            }
         }
     }
 
@@ -52,64 +319,173 @@ impl CaseFormat {
 
                 words
             }
-            CaseFormat::SnakeCase | CaseFormat::ScreamingSnakeCase => {
+            CaseFormat::SnakeCase | CaseFormat::ScreamingSnakeCase | CaseFormat::AdaCase => {
                 // Split on underscores
                 text.split('_')
                     .filter(|s| !s.is_empty())
                     .map(|s| s.to_lowercase())
                     .collect()
             }
-            CaseFormat::KebabCase | CaseFormat::ScreamingKebabCase => {
+            CaseFormat::KebabCase | CaseFormat::ScreamingKebabCase | CaseFormat::TrainCase => {
                 // Split on hyphens
                 text.split('-')
                     .filter(|s| !s.is_empty())
                     .map(|s| s.to_lowercase())
                     .collect()
             }
+            CaseFormat::DotCase | CaseFormat::PathCase => {
+                let separator = if *self == CaseFormat::DotCase { '.' } else { '/' };
+                text.split(separator)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_lowercase())
+                    .collect()
+            }
         }
     }
 
     /// Joins words into this case format with optional prefix and suffix
     pub fn join_words(&self, words: &[String], prefix: &str, suffix: &str) -> String {
+        self.join_words_with(words, prefix, suffix, &Abbreviations::default())
+    }
+
+    /// Like [`join_words`](Self::join_words), spelling the words found in
+    /// `abbreviations` as given when this format capitalizes them
+    ///
+    /// ```
+    /// use refmt_core::{Abbreviations, CaseFormat};
+    ///
+    /// let abbreviations = Abbreviations::new(["ID", "DB"]);
+    /// let words = CaseFormat::SnakeCase.split_words("user_db_id");
+    /// assert_eq!(CaseFormat::CamelCase.join_words_with(&words, "", "", &abbreviations), "userDBID");
+    /// ```
+    pub fn join_words_with(
+        &self,
+        words: &[String],
+        prefix: &str,
+        suffix: &str,
+        abbreviations: &Abbreviations,
+    ) -> String {
         if words.is_empty() {
             return String::new();
         }
 
+        let capitalize = |word: &String| match abbreviations.get(word) {
+            Some(spelling) => spelling.to_string(),
+            None => capitalize(word),
+        };
         let result = match self {
             CaseFormat::CamelCase => {
                 let first = words[0].to_lowercase();
-                let rest: String = words[1..]
-                    .iter()
-                    .map(|w| {
-                        let mut chars = w.chars();
-                        match chars.next() {
-                            None => String::new(),
-                            Some(first) => first.to_uppercase().chain(chars).collect(),
-                        }
-                    })
-                    .collect();
+                let rest: String = words[1..].iter().map(capitalize).collect();
                 format!("{}{}", first, rest)
             }
-            CaseFormat::PascalCase => words
-                .iter()
-                .map(|w| {
-                    let mut chars = w.chars();
-                    match chars.next() {
-                        None => String::new(),
-                        Some(first) => first.to_uppercase().chain(chars).collect(),
-                    }
-                })
-                .collect::<String>(),
+            CaseFormat::PascalCase => words.iter().map(capitalize).collect::<String>(),
             CaseFormat::SnakeCase => words.iter().map(|w| w.to_lowercase()).collect::<Vec<_>>().join("_"),
             CaseFormat::ScreamingSnakeCase => words.iter().map(|w| w.to_uppercase()).collect::<Vec<_>>().join("_"),
             CaseFormat::KebabCase => words.iter().map(|w| w.to_lowercase()).collect::<Vec<_>>().join("-"),
             CaseFormat::ScreamingKebabCase => words.iter().map(|w| w.to_uppercase()).collect::<Vec<_>>().join("-"),
+            CaseFormat::TrainCase => words.iter().map(capitalize).collect::<Vec<_>>().join("-"),
+            CaseFormat::AdaCase => words.iter().map(capitalize).collect::<Vec<_>>().join("_"),
+            CaseFormat::DotCase => words.iter().map(|w| w.to_lowercase()).collect::<Vec<_>>().join("."),
+            CaseFormat::PathCase => words.iter().map(|w| w.to_lowercase()).collect::<Vec<_>>().join("/"),
         };
 
         format!("{}{}{}", prefix, result, suffix)
     }
 }
 
+impl fmt::Display for CaseFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses a [`name`](CaseFormat::name), ignoring case, with `_` or `-` and an optional
+/// `case` suffix (`snake`, `SNAKE_CASE`, `screaming_snake`, `kebab-case`)
+impl FromStr for CaseFormat {
+    type Err = ParseCaseFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase().replace('_', "-");
+        let name = name.strip_suffix("-case").unwrap_or(&name);
+        CaseFormat::ALL
+            .into_iter()
+            .find(|format| format.name() == name)
+            .ok_or_else(|| ParseCaseFormatError(s.to_string()))
+    }
+}
+
+/// Serialized by [`name`](CaseFormat::name), so formats can be written in `refmt.toml`
+impl Serialize for CaseFormat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for CaseFormat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "clap")]
+impl clap::ValueEnum for CaseFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &CaseFormat::ALL
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        Some(clap::builder::PossibleValue::new(self.name()))
+    }
+}
+
+/// Uppercases the first character of a word
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        None => String::new(),
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+    }
+}
+
+/// Splits a file or directory name into lowercase words
+///
+/// Any non-alphanumeric character separates words, as do camelCase humps and the
+/// end of an acronym (`HTTPServer` gives `http`, `server`).
+pub(crate) fn identifier_words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+
+    for part in name.split(|c: char| !c.is_alphanumeric()).filter(|p| !p.is_empty()) {
+        let chars: Vec<char> = part.chars().collect();
+        let mut current = String::new();
+
+        for (i, &c) in chars.iter().enumerate() {
+            let prev = i.checked_sub(1).map(|p| chars[p]);
+            let next = chars.get(i + 1);
+            let boundary = match prev {
+                Some(p) if c.is_uppercase() => {
+                    p.is_lowercase()
+                        || p.is_ascii_digit()
+                        || (p.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+                }
+                _ => false,
+            };
+            if boundary && !current.is_empty() {
+                words.push(current.to_lowercase());
+                current.clear();
+            }
+            current.push(c);
+        }
+
+        if !current.is_empty() {
+            words.push(current.to_lowercase());
+        }
+    }
+
+    words
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -138,6 +514,159 @@ mod tests {
         assert_eq!(CaseFormat::SnakeCase.join_words(&words, "", ""), "first_name");
     }
 
+    #[test]
+    fn test_lossy_reason() {
+        use CaseFormat::{CamelCase, KebabCase, PascalCase, ScreamingSnakeCase, SnakeCase};
+
+        // The examples in the reasons
+        assert_eq!(CaseFormat::convert(CamelCase, SnakeCase, "userID"), "user_i_d");
+        assert_eq!(CaseFormat::convert(ScreamingSnakeCase, CamelCase, "HTTP_SERVER"), "httpServer");
+        let joined = CaseFormat::convert(SnakeCase, CamelCase, "user_2fa");
+        assert_eq!(CaseFormat::convert(CamelCase, SnakeCase, &joined), "user2fa");
+
+        assert!(CamelCase.lossy_reason(SnakeCase).unwrap().contains("acronyms"));
+        assert!(ScreamingSnakeCase.lossy_reason(PascalCase).unwrap().contains("casing"));
+        assert!(KebabCase.lossy_reason(CamelCase).unwrap().contains("digit"));
+        assert_eq!(CamelCase.lossy_reason(PascalCase), None);
+        assert_eq!(SnakeCase.lossy_reason(KebabCase), None);
+        assert_eq!(SnakeCase.lossy_reason(SnakeCase), None);
+        assert_eq!(KebabCase.example(), "user-account-id");
+    }
+
+    #[test]
+    fn test_may_match_prefilter() {
+        assert!(CaseFormat::SnakeCase.may_match("let first_name = 1;"));
+        assert!(!CaseFormat::SnakeCase.may_match("let firstname = 1;"));
+        assert!(CaseFormat::KebabCase.may_match("first-name"));
+        assert!(!CaseFormat::ScreamingKebabCase.may_match("FIRST_NAME"));
+        assert!(CaseFormat::CamelCase.may_match("firstName"));
+        assert!(!CaseFormat::PascalCase.may_match("all lowercase text"));
+    }
+
+    #[test]
+    fn test_convert_and_detect() {
+        assert_eq!(
+            CaseFormat::convert(CaseFormat::SnakeCase, CaseFormat::PascalCase, "user_id"),
+            "UserId"
+        );
+        assert_eq!(CaseFormat::detect("firstName"), Some(CaseFormat::CamelCase));
+        assert_eq!(CaseFormat::detect("FirstName"), Some(CaseFormat::PascalCase));
+        assert_eq!(CaseFormat::detect("MAX_SIZE"), Some(CaseFormat::ScreamingSnakeCase));
+        assert_eq!(CaseFormat::detect("first-name"), Some(CaseFormat::KebabCase));
+        assert_eq!(CaseFormat::detect("First-Name"), Some(CaseFormat::TrainCase));
+        assert_eq!(CaseFormat::detect("First_Name"), Some(CaseFormat::AdaCase));
+        assert_eq!(CaseFormat::detect("FIRST-NAME"), Some(CaseFormat::ScreamingKebabCase));
+        assert_eq!(CaseFormat::detect("config.value.name"), Some(CaseFormat::DotCase));
+        assert_eq!(CaseFormat::detect("api/user/list"), Some(CaseFormat::PathCase));
+        assert_eq!(CaseFormat::detect("name"), None);
+        assert!(CaseFormat::SnakeCase.matches("max_size"));
+        assert!(!CaseFormat::SnakeCase.matches("max_size value"));
+        assert_eq!(CaseFormat::detect("first name"), None);
+
+        // Every format round-trips through every other one
+        for from in CaseFormat::ALL {
+            let original = CaseFormat::convert(CaseFormat::SnakeCase, from, "max_retry_count");
+            assert_eq!(CaseFormat::detect(&original), Some(from));
+            for to in CaseFormat::ALL {
+                let converted = CaseFormat::convert(from, to, &original);
+                assert_eq!(CaseFormat::detect(&converted), Some(to));
+                assert_eq!(CaseFormat::convert(to, from, &converted), original);
+            }
+        }
+    }
+
+    #[test]
+    fn test_train_and_ada_case() {
+        let words = vec!["first".to_string(), "name".to_string()];
+        assert_eq!(CaseFormat::TrainCase.join_words(&words, "", ""), "First-Name");
+        assert_eq!(CaseFormat::AdaCase.join_words(&words, "", ""), "First_Name");
+        assert_eq!(CaseFormat::TrainCase.split_words("Content-Type"), vec!["content", "type"]);
+        assert_eq!(CaseFormat::AdaCase.split_words("Put_Line"), vec!["put", "line"]);
+        assert!(CaseFormat::AdaCase.may_match("Put_Line"));
+        assert!(!CaseFormat::TrainCase.may_match("Put_Line"));
+
+        let converted = CaseFormat::convert(CaseFormat::AdaCase, CaseFormat::CamelCase, "Put_Line");
+        assert_eq!(converted, "putLine");
+    }
+
+    #[test]
+    fn test_dot_and_path_case() {
+        let words = CaseFormat::DotCase.split_words("config.value.name");
+        assert_eq!(words, vec!["config", "value", "name"]);
+        assert_eq!(CaseFormat::PathCase.join_words(&words, "", ""), "config/value/name");
+        assert!(CaseFormat::PathCase.may_match("/users/list"));
+        assert!(!CaseFormat::DotCase.may_match("users/list"));
+
+        let text = "route api/user/list";
+        let regex = Regex::new(CaseFormat::PathCase.pattern()).unwrap();
+        assert_eq!(regex.find(text).unwrap().as_str(), "api/user/list");
+    }
+
+    #[test]
+    fn test_unicode_identifiers() {
+        let regex = Regex::new(&CaseFormat::CamelCase.unicode_pattern()).unwrap();
+        assert_eq!(regex.find("let größeZahl = 1;").unwrap().as_str(), "größeZahl");
+        assert!(Regex::new(CaseFormat::CamelCase.pattern()).unwrap().find("größeZahl").is_none());
+
+        assert_eq!(CaseFormat::detect("naïveValue"), Some(CaseFormat::CamelCase));
+        assert_eq!(CaseFormat::detect("ÉTAT_INITIAL"), Some(CaseFormat::ScreamingSnakeCase));
+        assert_eq!(
+            CaseFormat::convert(CaseFormat::CamelCase, CaseFormat::SnakeCase, "größeZahl"),
+            "größe_zahl"
+        );
+        assert_eq!(
+            CaseFormat::convert(CaseFormat::SnakeCase, CaseFormat::PascalCase, "élan_vital"),
+            "ÉlanVital"
+        );
+    }
+
+    #[test]
+    fn test_digit_boundaries() {
+        let camel = CaseFormat::CamelCase.split_words("sha256Hash");
+        let snake = CaseFormat::SnakeCase.split_words("sha_256_hash");
+        for words in [camel, snake] {
+            let join = |boundary: DigitBoundary| {
+                CaseFormat::SnakeCase.join_words(&boundary.apply(words.clone()), "", "")
+            };
+            assert_eq!(join(DigitBoundary::Attach), "sha256_hash");
+            assert_eq!(join(DigitBoundary::Separate), "sha_256_hash");
+            assert_eq!(join(DigitBoundary::Split), "sha_256_hash");
+        }
+
+        let words = CaseFormat::CamelCase.split_words("v2betaName");
+        assert_eq!(DigitBoundary::Split.apply(words.clone()), vec!["v", "2beta", "name"]);
+        assert_eq!(DigitBoundary::Separate.apply(words), vec!["v", "2", "beta", "name"]);
+        assert_eq!(CaseFormat::detect("user2Name"), Some(CaseFormat::CamelCase));
+        assert_eq!(CaseFormat::detect("utf8_decode"), Some(CaseFormat::SnakeCase));
+    }
+
+    #[test]
+    fn test_names() {
+        for format in CaseFormat::ALL {
+            assert_eq!(format.to_string().parse::<CaseFormat>(), Ok(format));
+        }
+        assert_eq!("SCREAMING_SNAKE".parse(), Ok(CaseFormat::ScreamingSnakeCase));
+        assert_eq!("kebab-case".parse(), Ok(CaseFormat::KebabCase));
+        assert_eq!("Train_Case".parse(), Ok(CaseFormat::TrainCase));
+        assert!("hungarian".parse::<CaseFormat>().is_err());
+    }
+
+    #[test]
+    fn test_abbreviations() {
+        let abbreviations = Abbreviations::new(["ID", "DB", "iOS"]);
+        let join = |format: CaseFormat, text: &str| {
+            let words = CaseFormat::SnakeCase.split_words(text);
+            format.join_words_with(&words, "", "", &abbreviations)
+        };
+        assert_eq!(join(CaseFormat::CamelCase, "user_id"), "userID");
+        assert_eq!(join(CaseFormat::CamelCase, "id_value"), "idValue");
+        assert_eq!(join(CaseFormat::PascalCase, "ios_db_id"), "iOSDBID");
+        assert_eq!(join(CaseFormat::TrainCase, "user_id"), "User-ID");
+        // Formats that don't capitalize ignore the dictionary
+        assert_eq!(join(CaseFormat::KebabCase, "user_id"), "user-id");
+        assert_eq!(join(CaseFormat::ScreamingSnakeCase, "ios_id"), "IOS_ID");
+    }
+
     #[test]
     fn test_with_prefix_suffix() {
         let words = vec!["first".to_string(), "name".to_string()];
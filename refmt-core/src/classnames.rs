@@ -0,0 +1,518 @@
+//! CSS class and id name conversion across stylesheets and markup
+//!
+//! Class and id names are collected from the selectors of the stylesheets in the
+//! processed tree (including `<style>` blocks in HTML), converted to the target case
+//! and then renamed everywhere they are used: in selectors and in the `class=`,
+//! `className=` and `id=` attributes of HTML and JSX files. Names that no stylesheet
+//! defines (framework utility classes, for example) are left alone, so selectors and
+//! markup always stay in sync.
+//!
+//! BEM-style names (`card__title--active`) and names starting with `-` or `_` are
+//! never converted, since their separators carry meaning.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+
+use regex::{Captures, Regex};
+
+use crate::case::{identifier_words, CaseFormat};
+use crate::error::Error;
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::report::{report, EventSink};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// `class="..."`, `className='...'` and `id="..."` attributes
+static ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(\s(?:class|className|id)\s*=\s*)(?:"([^"]*)"|'([^']*)')"#).unwrap()
+});
+/// `<style>` blocks in HTML
+static STYLE_BLOCK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)(<style\b[^>]*>)(.*?)(</style>)").unwrap());
+static TOKEN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\S+").unwrap());
+
+/// Old -> new class and id names
+pub type ClassNameMapping = BTreeMap<String, String>;
+
+/// Options for class name conversion
+#[derive(Debug, Clone)]
+pub struct ClassNameOptions {
+    /// Case format to convert names to
+    pub target: CaseFormat,
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for ClassNameOptions {
+    fn default() -> Self {
+        ClassNameOptions {
+            target: CaseFormat::KebabCase,
+            file_extensions: [".css", ".html", ".htm", ".jsx", ".tsx"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// Statistics from class name conversion
+#[derive(Debug, Default)]
+pub struct ClassNameStats {
+    /// Number of files changed
+    pub files_changed: usize,
+    /// Number of distinct names renamed
+    pub names_renamed: usize,
+    /// Number of selector and attribute references updated
+    pub references_updated: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Language {
+    Stylesheet,
+    Html,
+    Jsx,
+}
+
+impl Language {
+    fn from_path(path: &Path) -> Option<Language> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "css" | "scss" | "less" => Some(Language::Stylesheet),
+            "html" | "htm" | "vue" | "svelte" => Some(Language::Html),
+            "jsx" | "tsx" | "js" | "ts" => Some(Language::Jsx),
+            _ => None,
+        }
+    }
+}
+
+/// Converts CSS class and id names consistently across stylesheets and markup
+pub struct ClassNameConverter {
+    options: ClassNameOptions,
+    walker: Walker,
+}
+
+impl ClassNameConverter {
+    /// Creates a new class name converter with the given options
+    pub fn new(options: ClassNameOptions) -> Self {
+        ClassNameConverter {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a converter with default options (kebab-case)
+    pub fn with_defaults() -> Self {
+        ClassNameConverter {
+            options: ClassNameOptions::default(),
+            walker: Walker::default(),
+        }
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        if !self.options.fs.is_file(path) {
+            return false;
+        }
+
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none()
+            && Language::from_path(path).is_some()
+    }
+
+    /// Class and id names defined by the selectors of a stylesheet
+    pub fn stylesheet_names(content: &str) -> BTreeSet<String> {
+        selector_names(content)
+            .into_iter()
+            .map(|range| content[range].to_string())
+            .collect()
+    }
+
+    /// Class and id names defined in the `<style>` blocks of an HTML document
+    pub fn style_block_names(content: &str) -> BTreeSet<String> {
+        STYLE_BLOCK
+            .captures_iter(content)
+            .flat_map(|caps| Self::stylesheet_names(&caps[2]))
+            .collect()
+    }
+
+    /// New name for `name`, or `None` if it is kept as is
+    fn rename(&self, name: &str) -> Option<String> {
+        if name.starts_with(['-', '_']) || name.contains("__") || name.contains("--") {
+            return None;
+        }
+        let words = identifier_words(name);
+        if words.is_empty() {
+            return None;
+        }
+        let new_name = self.options.target.join_words(&words, "", "");
+        (new_name != name).then_some(new_name)
+    }
+
+    /// Builds the old -> new mapping for a set of defined names
+    ///
+    /// Fails if a name would be renamed to another defined name, or two names would
+    /// get the same new name.
+    pub fn mapping(&self, names: &BTreeSet<String>) -> crate::Result<ClassNameMapping> {
+        let mut mapping = ClassNameMapping::new();
+        let mut targets: BTreeMap<String, String> = BTreeMap::new();
+
+        for name in names {
+            let Some(new_name) = self.rename(name) else {
+                continue;
+            };
+            if names.contains(&new_name) {
+                return Err(Error::Conflict(format!(
+                    "Renaming '{}' to '{}' would collide with an existing class or id",
+                    name, new_name
+                )));
+            }
+            if let Some(other) = targets.insert(new_name.clone(), name.clone()) {
+                return Err(Error::Conflict(format!(
+                    "Both '{}' and '{}' would be renamed to '{}'",
+                    other, name, new_name
+                )));
+            }
+            mapping.insert(name.clone(), new_name);
+        }
+
+        Ok(mapping)
+    }
+
+    /// Renames the class and id names in the selectors of a stylesheet
+    ///
+    /// Returns the new content and the number of references updated.
+    pub fn convert_stylesheet<'a>(
+        &self,
+        content: &'a str,
+        mapping: &ClassNameMapping,
+    ) -> (Cow<'a, str>, usize) {
+        let mut out = String::new();
+        let mut copied = 0;
+        let mut updated = 0;
+
+        for range in selector_names(content) {
+            if let Some(new_name) = mapping.get(&content[range.clone()]) {
+                out.push_str(&content[copied..range.start]);
+                out.push_str(new_name);
+                copied = range.end;
+                updated += 1;
+            }
+        }
+
+        if updated == 0 {
+            return (Cow::Borrowed(content), 0);
+        }
+        out.push_str(&content[copied..]);
+        (Cow::Owned(out), updated)
+    }
+
+    /// Renames the names in the `class=`, `className=` and `id=` attributes of HTML
+    /// or JSX markup, and in the selectors of HTML `<style>` blocks
+    ///
+    /// Returns the new content and the number of references updated.
+    pub fn convert_markup<'a>(
+        &self,
+        content: &'a str,
+        mapping: &ClassNameMapping,
+    ) -> (Cow<'a, str>, usize) {
+        let mut updated = 0;
+
+        let styled = STYLE_BLOCK.replace_all(content, |caps: &Captures| {
+            let (css, count) = self.convert_stylesheet(&caps[2], mapping);
+            updated += count;
+            format!("{}{}{}", &caps[1], css, &caps[3])
+        });
+        let converted = ATTRIBUTE
+            .replace_all(&styled, |caps: &Captures| {
+                let (quote, value) = match caps.get(2) {
+                    Some(value) => ('"', value.as_str()),
+                    None => ('\'', caps.get(3).map_or("", |m| m.as_str())),
+                };
+                let value =
+                    TOKEN.replace_all(value, |token: &Captures| match mapping.get(&token[0]) {
+                        Some(new_name) => {
+                            updated += 1;
+                            new_name.clone()
+                        }
+                        None => token[0].to_string(),
+                    });
+                format!("{}{}{}{}", &caps[1], quote, value, quote)
+            })
+            .into_owned();
+
+        if updated == 0 {
+            return (Cow::Borrowed(content), 0);
+        }
+        (Cow::Owned(converted), updated)
+    }
+
+    /// Names defined by a file: selectors of stylesheets and HTML `<style>` blocks
+    fn defined_names(&self, path: &Path) -> crate::Result<BTreeSet<String>> {
+        Ok(match Language::from_path(path) {
+            Some(Language::Stylesheet) => {
+                Self::stylesheet_names(&vfs::read_to_string(self.options.fs.as_ref(), path)?)
+            }
+            Some(Language::Html) => {
+                Self::style_block_names(&vfs::read_to_string(self.options.fs.as_ref(), path)?)
+            }
+            _ => BTreeSet::new(),
+        })
+    }
+
+    /// Converts a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path, mapping: &ClassNameMapping) -> crate::Result<usize> {
+        let Some(language) = Language::from_path(path) else {
+            return Ok(0);
+        };
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        let (converted, updated) = match language {
+            Language::Stylesheet => self.convert_stylesheet(&content, mapping),
+            Language::Html | Language::Jsx => self.convert_markup(&content, mapping),
+        };
+
+        if let Cow::Owned(converted) = converted {
+            if !self.options.dry_run {
+                self.options.fs.write(path, converted.as_bytes())?;
+            }
+        }
+        Ok(updated)
+    }
+
+    fn report(&self, path: &Path, updated: usize) {
+        if updated == 0 {
+            return;
+        }
+
+        self.options.events.file_changed(path, updated, self.options.dry_run);
+        if self.options.dry_run {
+            report!(
+                "Would update {} class names in '{}'",
+                updated,
+                path.display()
+            );
+        } else {
+            report!("Updated {} class names in '{}'", updated, path.display());
+        }
+    }
+
+    /// Processes a directory or file
+    ///
+    /// All stylesheets are read first to build the mapping, so a failure (such as a
+    /// collision) leaves every file untouched.
+    pub fn process(&self, path: &Path) -> crate::Result<ClassNameStats> {
+        let mut stats = ClassNameStats::default();
+
+        let files: Vec<_> = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        }
+        .into_iter()
+        .filter(|file| self.should_process(file))
+        .collect();
+
+        let mut names = BTreeSet::new();
+        let fs = self.options.fs.as_ref();
+        for result in parallel::map_files(fs, &files, |file| self.defined_names(file)) {
+            names.extend(result?);
+        }
+        let mapping = self.mapping(&names)?;
+        if mapping.is_empty() {
+            return Ok(stats);
+        }
+        stats.names_renamed = mapping.len();
+
+        let results = parallel::map_files(fs, &files, |file| self.apply_to_file(file, &mapping));
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let updated = result?;
+            self.report(file_path, updated);
+            if updated > 0 {
+                stats.files_changed += 1;
+                stats.references_updated += updated;
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+/// Byte ranges of the class and id names in the selectors of a stylesheet
+///
+/// Text before a `{` is a selector unless it starts an at-rule; text before a `;`
+/// or `}` is a declaration, so hex colors and numbers are never mistaken for names.
+fn selector_names(content: &str) -> Vec<Range<usize>> {
+    let bytes = content.as_bytes();
+    let mut names = Vec::new();
+    let mut candidates = Vec::new();
+    let mut at_rule: Option<bool> = None;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = content[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + 2 + end + 2);
+                continue;
+            }
+            quote @ (b'"' | b'\'') => {
+                at_rule.get_or_insert(false);
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'{' => {
+                if at_rule != Some(true) {
+                    names.append(&mut candidates);
+                }
+                candidates.clear();
+                at_rule = None;
+            }
+            b';' | b'}' => {
+                candidates.clear();
+                at_rule = None;
+            }
+            b'.' | b'#' => {
+                at_rule.get_or_insert(false);
+                let start = i + 1;
+                let mut end = start;
+                while end < bytes.len()
+                    && (bytes[end].is_ascii_alphanumeric() || matches!(bytes[end], b'-' | b'_'))
+                {
+                    end += 1;
+                }
+                let starts_like_name = bytes
+                    .get(start)
+                    .is_some_and(|b| b.is_ascii_alphabetic() || matches!(b, b'-' | b'_'));
+                if starts_like_name {
+                    candidates.push(start..end);
+                }
+                i = end;
+                continue;
+            }
+            b'@' => {
+                at_rule.get_or_insert(true);
+            }
+            b if !b.is_ascii_whitespace() => {
+                at_rule.get_or_insert(false);
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn converter(target: CaseFormat) -> ClassNameConverter {
+        let options = ClassNameOptions {
+            target,
+            ..ClassNameOptions::default()
+        };
+        ClassNameConverter::new(options)
+    }
+
+    #[test]
+    fn test_stylesheet_names() {
+        let css = concat!(
+            "/* .commented { } */\n",
+            ".nav-bar > a.navLink:hover, #main-content { color: #fff; margin: 0.5em; }\n",
+            "@media (min-width: 40em) { .card__title--active { top: 1px } }\n",
+            "[data-x=\".quoted\"] { }\n",
+        );
+        let names: Vec<_> = ClassNameConverter::stylesheet_names(css)
+            .into_iter()
+            .collect();
+        assert_eq!(
+            names,
+            vec!["card__title--active", "main-content", "nav-bar", "navLink"]
+        );
+    }
+
+    #[test]
+    fn test_convert_stylesheet_and_markup() {
+        let converter = converter(CaseFormat::CamelCase);
+        let names = ClassNameConverter::stylesheet_names(".nav-bar, #main-content, .btn { }");
+        let mapping = converter.mapping(&names).unwrap();
+        assert_eq!(mapping.len(), 2);
+
+        let (css, updated) = converter.convert_stylesheet(".nav-bar .btn { color: red }", &mapping);
+        assert_eq!(css, ".navBar .btn { color: red }");
+        assert_eq!(updated, 1);
+
+        let html = concat!(
+            "<style>.nav-bar { }</style>\n",
+            "<div class=\"nav-bar col-md-6\" id='main-content' data-class=\"nav-bar\"></div>\n",
+        );
+        let (result, updated) = converter.convert_markup(html, &mapping);
+        assert_eq!(
+            result,
+            concat!(
+                "<style>.navBar { }</style>\n",
+                "<div class=\"navBar col-md-6\" id='mainContent' data-class=\"nav-bar\"></div>\n",
+            )
+        );
+        assert_eq!(updated, 3);
+
+        let jsx = "<nav className=\"nav-bar\">{label}</nav>";
+        let (result, _) = converter.convert_markup(jsx, &mapping);
+        assert_eq!(result, "<nav className=\"navBar\">{label}</nav>");
+    }
+
+    #[test]
+    fn test_mapping_collisions() {
+        let converter = converter(CaseFormat::KebabCase);
+        let names: BTreeSet<String> = ["navBar", "nav-bar"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(converter.mapping(&names).is_err());
+
+        let names: BTreeSet<String> = ["navBar", "nav_bar"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(converter.mapping(&names).is_err());
+    }
+}
@@ -1,37 +1,179 @@
 //! Combined processing for multiple transformations in a single pass
+//!
+//! The pipeline is an ordered list of [`CombinedStep`]s, each with its own options; by
+//! default files are renamed to lowercase, then emojis are transformed and trailing
+//! whitespace is cleaned.
 
-use std::fs;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::sync::Arc;
 
+use serde::Serialize;
+
+use crate::diff::unified_diff;
+use crate::filter::{read_text, ExtensionSet, FilterOptions};
+use crate::report::{report, EventSink, FailedFile, FailedFiles, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
 use crate::{
-    CaseTransform, EmojiOptions, EmojiTransformer, FileRenamer, RenameOptions, WhitespaceCleaner,
-    WhitespaceOptions,
+    Abbreviations, CaseConverter, CaseFormat, CaseTransform, ContentTransform, EmojiOptions,
+    EmojiTransformer, FileRenamer, RenameOptions, WhitespaceCleaner, WhitespaceOptions,
 };
 
+/// Options for the case conversion step
+#[derive(Debug, Clone)]
+pub struct ConvertStepOptions {
+    /// Formats to convert from
+    pub from: Vec<CaseFormat>,
+    /// Format to convert to
+    pub to: CaseFormat,
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Identifiers never converted
+    pub excluded_words: Vec<String>,
+    /// Words spelled as given in capitalized formats
+    pub abbreviations: Abbreviations,
+    /// Treat non-ASCII letters as letters
+    pub unicode: bool,
+}
+
+impl ConvertStepOptions {
+    /// Converts from `from` to `to` in the default extensions
+    pub fn new(from: Vec<CaseFormat>, to: CaseFormat) -> Self {
+        ConvertStepOptions {
+            from,
+            to,
+            file_extensions: ExtensionSet::Convert.defaults(),
+            excluded_words: Vec::new(),
+            abbreviations: Abbreviations::default(),
+            unicode: false,
+        }
+    }
+}
+
+/// One step of the combined pipeline, with its options
+///
+/// The pipeline's `recursive`, `dry_run`, `exclude` and `skip_dirs` replace those of
+/// each step.
+#[derive(Debug, Clone)]
+pub enum CombinedStep {
+    /// Rename files
+    Rename(RenameOptions),
+    /// Replace or remove emojis
+    Emojis(EmojiOptions),
+    /// Clean trailing whitespace
+    Clean(WhitespaceOptions),
+    /// Convert identifiers between case formats
+    Convert(ConvertStepOptions),
+}
+
+impl CombinedStep {
+    /// The name of the step in `--steps` and `refmt.toml`
+    pub fn name(&self) -> &'static str {
+        match self {
+            CombinedStep::Rename(_) => "rename",
+            CombinedStep::Emojis(_) => "emojis",
+            CombinedStep::Clean(_) => "clean",
+            CombinedStep::Convert(_) => "convert",
+        }
+    }
+
+    /// Renaming files to lowercase
+    pub fn lowercase_rename() -> Self {
+        CombinedStep::Rename(RenameOptions {
+            case_transform: CaseTransform::Lowercase,
+            ..RenameOptions::default()
+        })
+    }
+
+    /// The default pipeline: lowercase names, transform emojis, clean whitespace
+    pub fn defaults() -> Vec<CombinedStep> {
+        vec![
+            CombinedStep::lowercase_rename(),
+            CombinedStep::Emojis(EmojiOptions::default()),
+            CombinedStep::Clean(WhitespaceOptions::default()),
+        ]
+    }
+}
+
 /// Options for combined processing
 #[derive(Debug, Clone)]
 pub struct CombinedOptions {
     /// Process directories recursively
     pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
     /// Dry run mode (don't modify files)
     pub dry_run: bool,
+    /// Leave files whose name or path matches one of these globs alone
+    pub exclude: Vec<glob::Pattern>,
+    /// Directories whose content isn't transformed, by name
+    pub skip_dirs: Vec<String>,
+    /// The transformations to apply, in order
+    pub steps: Vec<CombinedStep>,
+    /// Report a unified diff of each file, over all the steps
+    pub show_diff: bool,
+    /// Color the diffs with ANSI escapes
+    pub color_diff: bool,
+    /// Filesystem the files are read from and written to, by every step
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed, by every step
+    pub events: EventSink,
 }
 
 impl Default for CombinedOptions {
     fn default() -> Self {
         CombinedOptions {
             recursive: true,
+            walk: WalkOptions::default(),
             dry_run: false,
+            exclude: Vec::new(),
+            skip_dirs: FilterOptions::default().skip_dirs,
+            steps: CombinedStep::defaults(),
+            show_diff: false,
+            color_diff: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
         }
     }
 }
 
+/// What one step changes in a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StepChange {
+    /// The step, as named in `--steps`
+    pub step: &'static str,
+    /// Emojis, lines or identifiers changed; 1 for a rename
+    pub changes: usize,
+}
+
+/// The steps that change one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileReport {
+    /// The file, as found
+    pub path: PathBuf,
+    /// Its new path, if a rename step renames it
+    pub renamed_to: Option<PathBuf>,
+    /// The steps that change it, in pipeline order
+    pub steps: Vec<StepChange>,
+}
+
+impl FileReport {
+    /// The changes `step` makes to the file, if it changes anything
+    pub fn changes(&self, step: &str) -> Option<usize> {
+        self.steps
+            .iter()
+            .find(|change| change.step == step)
+            .map(|change| change.changes)
+    }
+}
+
 /// Statistics from combined processing
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq, Eq)]
 pub struct CombinedStats {
     /// Number of files renamed
     pub files_renamed: usize,
+    /// Number of files whose content changed (by any step)
+    pub files_modified: usize,
     /// Number of files with emoji transformations
     pub files_emoji_transformed: usize,
     /// Number of emoji changes
@@ -40,41 +182,166 @@ pub struct CombinedStats {
     pub files_whitespace_cleaned: usize,
     /// Number of lines with whitespace cleaned
     pub whitespace_lines_cleaned: usize,
+    /// Number of files with identifiers converted
+    pub files_converted: usize,
+    /// Number of identifiers converted
+    pub identifiers_converted: usize,
+    /// The files some step changes, sorted by path
+    pub files: Vec<FileReport>,
+}
+
+impl CombinedStats {
+    /// Counts the changes a content step made to one file
+    fn record(&mut self, stage: &Stage, changes: usize) {
+        let (files, total) = match stage {
+            Stage::Rename(_) => return,
+            Stage::Emojis(_) => (&mut self.files_emoji_transformed, &mut self.emoji_changes),
+            Stage::Clean(_) => (
+                &mut self.files_whitespace_cleaned,
+                &mut self.whitespace_lines_cleaned,
+            ),
+            Stage::Convert(_) => (&mut self.files_converted, &mut self.identifiers_converted),
+        };
+        *files += 1;
+        *total += changes;
+    }
+}
+
+/// A step with its transformer built
+enum Stage {
+    Rename(FileRenamer),
+    Emojis(EmojiTransformer),
+    Clean(WhitespaceCleaner),
+    Convert(Box<CaseConverter>),
+}
+
+impl Stage {
+    fn name(&self) -> &'static str {
+        match self {
+            Stage::Rename(_) => "rename",
+            Stage::Emojis(_) => "emojis",
+            Stage::Clean(_) => "clean",
+            Stage::Convert(_) => "convert",
+        }
+    }
+
+    fn content_transform(&self) -> Option<&dyn ContentTransform> {
+        match self {
+            Stage::Rename(_) => None,
+            Stage::Emojis(transformer) => Some(transformer),
+            Stage::Clean(cleaner) => Some(cleaner),
+            Stage::Convert(converter) => Some(converter.as_ref()),
+        }
+    }
+
+    /// Whether the content step processes the file at `path`, found under `root`
+    fn accepts(&self, path: &Path, root: &Path) -> bool {
+        match self {
+            Stage::Rename(_) => false,
+            Stage::Emojis(transformer) => transformer.skip_reason(path).is_none(),
+            Stage::Clean(cleaner) => cleaner.skip_reason(path).is_none(),
+            // Only directories below the root are skipped, as with `convert`
+            Stage::Convert(converter) => converter.skip_reason(path, root).is_none(),
+        }
+    }
+
+    /// Reports the changes the content step made to the file at `path`
+    fn report(&self, path: &Path, changes: usize) {
+        let outcome = FileOutcome::changed(changes);
+        match self {
+            Stage::Rename(_) => {}
+            Stage::Emojis(transformer) => transformer.report(path, outcome, None),
+            Stage::Clean(cleaner) => cleaner.report(path, outcome, None),
+            Stage::Convert(converter) => converter.report(path, outcome, None),
+        }
+    }
 }
 
 /// Combined processor that applies multiple transformations in a single pass
+///
+/// The transformers are built once (compiling their regexes) and reused for every file.
 pub struct CombinedProcessor {
     options: CombinedOptions,
-    rename_options: RenameOptions,
-    emoji_options: EmojiOptions,
-    whitespace_options: WhitespaceOptions,
+    stages: Vec<Stage>,
+    walker: Walker,
+    failed: FailedFiles,
 }
 
 impl CombinedProcessor {
     /// Creates a new combined processor with the given options
     pub fn new(options: CombinedOptions) -> Self {
-        // Configure rename options for lowercase transformation
-        let mut rename_options = RenameOptions::default();
-        rename_options.case_transform = CaseTransform::Lowercase;
-        rename_options.recursive = options.recursive;
-        rename_options.dry_run = options.dry_run;
-
-        // Configure emoji options with defaults
-        let mut emoji_options = EmojiOptions::default();
-        emoji_options.recursive = options.recursive;
-        emoji_options.dry_run = options.dry_run;
-
-        // Configure whitespace options with defaults
-        let mut whitespace_options = WhitespaceOptions::default();
-        whitespace_options.recursive = options.recursive;
-        whitespace_options.dry_run = options.dry_run;
+        let stages = options
+            .steps
+            .iter()
+            .map(|step| match step.clone() {
+                CombinedStep::Rename(mut rename) => {
+                    rename.recursive = options.recursive;
+                    rename.walk = options.walk;
+                    rename.dry_run = options.dry_run;
+                    rename.filter.exclude = options.exclude.clone();
+                    rename.filter.skip_dirs = options.skip_dirs.clone();
+                    rename.fs = options.fs.clone();
+                    rename.events = options.events.clone();
+                    Stage::Rename(FileRenamer::new(rename))
+                }
+                CombinedStep::Emojis(mut emoji) => {
+                    emoji.recursive = options.recursive;
+                    emoji.walk = options.walk;
+                    emoji.dry_run = options.dry_run;
+                    emoji.filter.exclude = options.exclude.clone();
+                    emoji.filter.skip_dirs = options.skip_dirs.clone();
+                    emoji.fs = options.fs.clone();
+                    emoji.events = options.events.clone();
+                    Stage::Emojis(EmojiTransformer::new(emoji))
+                }
+                CombinedStep::Clean(mut whitespace) => {
+                    whitespace.recursive = options.recursive;
+                    whitespace.walk = options.walk;
+                    whitespace.dry_run = options.dry_run;
+                    whitespace.filter.exclude = options.exclude.clone();
+                    whitespace.filter.skip_dirs = options.skip_dirs.clone();
+                    whitespace.fs = options.fs.clone();
+                    whitespace.events = options.events.clone();
+                    Stage::Clean(WhitespaceCleaner::new(whitespace))
+                }
+                CombinedStep::Convert(convert) => {
+                    Stage::Convert(Box::new(Self::converter(&options, convert)))
+                }
+            })
+            .collect();
 
         CombinedProcessor {
             options,
-            rename_options,
-            emoji_options,
-            whitespace_options,
+            stages,
+            walker: Walker::default(),
+            failed: FailedFiles::default(),
+        }
+    }
+
+    fn converter(options: &CombinedOptions, convert: ConvertStepOptions) -> CaseConverter {
+        // Without globs or word filters, only the built-in patterns are compiled
+        let filter = FilterOptions {
+            exclude: options.exclude.clone(),
+            skip_dirs: options.skip_dirs.clone(),
+            ..FilterOptions::default()
+        };
+
+        let from = convert.from.first().copied().unwrap_or(convert.to);
+        let mut builder = CaseConverter::builder(from, convert.to)
+            .extensions(convert.file_extensions)
+            .recursive(options.recursive)
+            .walk(options.walk)
+            .dry_run(options.dry_run)
+            .excluded_words(convert.excluded_words)
+            .abbreviations(convert.abbreviations)
+            .unicode(convert.unicode)
+            .filter(filter)
+            .fs(options.fs.clone())
+            .events(options.events.clone());
+        if !convert.from.is_empty() {
+            builder = builder.source_formats(convert.from);
         }
+        builder.build().expect("case format patterns are valid")
     }
 
     /// Creates a processor with default options
@@ -82,87 +349,180 @@ impl CombinedProcessor {
         CombinedProcessor::new(CombinedOptions::default())
     }
 
+    /// The names of the steps, in order
+    pub fn step_names(&self) -> Vec<&'static str> {
+        self.options.steps.iter().map(CombinedStep::name).collect()
+    }
+
+    /// The content transformers, in the order they are applied
+    pub fn transforms(&self) -> Vec<&dyn ContentTransform> {
+        self.stages
+            .iter()
+            .filter_map(Stage::content_transform)
+            .collect()
+    }
+
     /// Processes a directory or file with all transformations
     pub fn process(&self, path: &Path) -> crate::Result<CombinedStats> {
         let mut stats = CombinedStats::default();
 
-        if path.is_file() {
-            self.process_single_file(path, &mut stats)?;
-        } else if path.is_dir() {
-            if self.options.recursive {
-                // Collect all files first to avoid iterator invalidation during renames
-                let mut files: Vec<PathBuf> = WalkDir::new(path)
-                    .into_iter()
-                    .filter_map(|e| e.ok())
-                    .filter(|e| e.file_type().is_file())
-                    .map(|e| e.path().to_path_buf())
-                    .collect();
-
-                // Sort by depth (deepest first) to avoid parent directory rename issues
-                files.sort_by(|a, b| b.components().count().cmp(&a.components().count()));
-
-                for file_path in files {
-                    self.process_single_file(&file_path, &mut stats)?;
-                }
-            } else {
-                let mut files: Vec<PathBuf> = fs::read_dir(path)?
-                    .filter_map(|e| e.ok())
-                    .map(|e| e.path())
-                    .filter(|p| p.is_file())
-                    .collect();
-
-                // Sort for consistent processing
-                files.sort();
-
-                for file_path in files {
-                    self.process_single_file(&file_path, &mut stats)?;
+        if self.options.fs.is_file(path) {
+            self.process_single_file(path, path, &mut stats, true)?;
+        } else if self.options.fs.is_dir(path) {
+            // Collect all files first to avoid issues with renaming while iterating
+            let mut files = self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?;
+
+            // Sort by depth (deepest first) to avoid parent directory rename issues
+            files.sort_by_key(|p| std::cmp::Reverse(p.components().count()));
+
+            for file_path in files {
+                if let Err(e) = self.process_single_file(&file_path, path, &mut stats, true) {
+                    self.failed.record(&file_path, e, self.options.walk.errors, &self.options.events)?;
                 }
             }
+            stats.files.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+
+        Ok(stats)
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+
+    /// Returns the files that couldn't be processed since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_failed_files(&self) -> Vec<FailedFile> {
+        self.failed.take()
+    }
+
+    /// Computes the statistics [`process`](Self::process) would produce, without
+    /// writing or printing anything
+    pub fn plan(&self, path: &Path) -> crate::Result<CombinedStats> {
+        let mut stats = CombinedStats::default();
+
+        if self.options.fs.is_file(path) {
+            self.process_single_file(path, path, &mut stats, false)?;
+        } else if self.options.fs.is_dir(path) {
+            for file_path in self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )? {
+                self.process_single_file(&file_path, path, &mut stats, false)?;
+            }
+            stats.files.sort_by(|a, b| a.path.cmp(&b.path));
         }
 
         Ok(stats)
     }
 
     /// Processes a single file with all transformations
-    fn process_single_file(&self, path: &Path, stats: &mut CombinedStats) -> crate::Result<()> {
-        // Step 1: Rename file (lowercase)
-        let renamer = FileRenamer::new(self.rename_options.clone());
-        let renamed = renamer.rename_file(path)?;
-        if renamed {
-            stats.files_renamed += 1;
+    ///
+    /// The content is read once, before the first content step that accepts the file,
+    /// and written once, after the last step. Unless `apply` is set, nothing is
+    /// renamed, written or reported; only `stats` are counted.
+    fn process_single_file(
+        &self,
+        path: &Path,
+        root: &Path,
+        stats: &mut CombinedStats,
+        apply: bool,
+    ) -> crate::Result<()> {
+        // Where the file is, and the path the steps see; they differ once a rename
+        // step has run without renaming
+        let mut location: PathBuf = path.to_path_buf();
+        let mut current: PathBuf = path.to_path_buf();
+        let mut original: Option<String> = None;
+        let mut binary = false;
+        let mut transformed: Option<String> = None;
+        let mut changes: Vec<(&Stage, usize)> = Vec::new();
+
+        for stage in &self.stages {
+            let Some(transform) = stage.content_transform() else {
+                if let Stage::Rename(renamer) = stage {
+                    let Some(new_path) = renamer.target_path(&current)? else {
+                        continue;
+                    };
+                    if apply {
+                        if renamer.rename_to(&current, &new_path)?.is_none() {
+                            continue;
+                        }
+                        if !self.options.dry_run {
+                            location = new_path.clone();
+                        }
+                    }
+                    stats.files_renamed += 1;
+                    changes.push((stage, 1));
+                    current = new_path;
+                }
+                continue;
+            };
+            if binary || !stage.accepts(&current, root) {
+                continue;
+            }
+            if original.is_none() {
+                original = read_text(self.options.fs.as_ref(), &location)?;
+                binary = original.is_none();
+            }
+            let Some(content) = original.as_deref() else {
+                continue;
+            };
+
+            // Each step sees the output of the ones before it
+            let input = transformed.as_deref().unwrap_or(content);
+            let (output, count) = transform.transform(&current, input);
+            if count > 0 {
+                transformed = Some(output.into_owned());
+                changes.push((stage, count));
+            }
         }
 
-        // Determine the current path (may have been renamed)
-        let current_path = if renamed && !self.options.dry_run {
-            // Calculate the new path after renaming
-            let file_name = path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .ok_or_else(|| anyhow::anyhow!("Invalid filename"))?;
-
-            let lowercase_name = file_name.to_lowercase();
-            let parent = path
-                .parent()
-                .ok_or_else(|| anyhow::anyhow!("No parent directory"))?;
-            parent.join(lowercase_name)
-        } else {
-            path.to_path_buf()
-        };
+        if !changes.is_empty() {
+            stats.files.push(FileReport {
+                path: path.to_path_buf(),
+                renamed_to: (current != path).then(|| current.clone()),
+                steps: changes
+                    .iter()
+                    .map(|&(stage, changes)| StepChange {
+                        step: stage.name(),
+                        changes,
+                    })
+                    .collect(),
+            });
+        }
 
-        // Step 2: Transform emojis
-        let emoji_transformer = EmojiTransformer::new(self.emoji_options.clone());
-        let emoji_changes = emoji_transformer.transform_file(&current_path)?;
-        if emoji_changes > 0 {
-            stats.files_emoji_transformed += 1;
-            stats.emoji_changes += emoji_changes;
+        let (Some(content), Some(transformed)) = (original, transformed) else {
+            return Ok(());
+        };
+        stats.files_modified += 1;
+        for &(stage, count) in &changes {
+            stats.record(stage, count);
+        }
+        if !apply {
+            return Ok(());
         }
 
-        // Step 3: Clean whitespace
-        let whitespace_cleaner = WhitespaceCleaner::new(self.whitespace_options.clone());
-        let lines_cleaned = whitespace_cleaner.clean_file(&current_path)?;
-        if lines_cleaned > 0 {
-            stats.files_whitespace_cleaned += 1;
-            stats.whitespace_lines_cleaned += lines_cleaned;
+        if !self.options.dry_run {
+            self.options.fs.write(&location, transformed.as_bytes())?;
+        }
+        for (stage, count) in changes {
+            stage.report(&location, count);
+        }
+        // One diff for all the steps
+        if self.options.show_diff {
+            report!(
+                "{}",
+                unified_diff(&location, &content, &transformed, self.options.color_diff).trim_end()
+            );
         }
 
         Ok(())
@@ -172,6 +532,7 @@ impl CombinedProcessor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::FileAction;
     use std::fs;
 
     #[test]
@@ -216,8 +577,10 @@ mod tests {
         let original_content = "Line 1   \nTask ✅\n";
         fs::write(&test_file, original_content).unwrap();
 
-        let mut options = CombinedOptions::default();
-        options.dry_run = true;
+        let options = CombinedOptions {
+            dry_run: true,
+            ..CombinedOptions::default()
+        };
 
         let processor = CombinedProcessor::new(options);
         let _stats = processor.process(&test_file).unwrap();
@@ -230,6 +593,39 @@ mod tests {
         fs::remove_dir_all(&test_dir).unwrap();
     }
 
+    #[test]
+    fn test_plan_matches_process() {
+        let test_dir = std::env::temp_dir().join("refmt_combined_plan");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+
+        fs::write(test_dir.join("Notes.md"), "Done ✅  \n").unwrap();
+        fs::write(test_dir.join("clean.txt"), "nothing to do\n").unwrap();
+        fs::write(test_dir.join("spaces.txt"), "trailing \n").unwrap();
+        fs::write(test_dir.join("Upper.txt"), "fine\n").unwrap();
+
+        let processor = CombinedProcessor::with_defaults();
+        let plan = processor.plan(&test_dir).unwrap();
+        assert_eq!(plan.files_renamed, 2);
+        assert_eq!(plan.files_modified, 2);
+        let steps: Vec<Vec<&str>> = plan
+            .files
+            .iter()
+            .map(|file| file.steps.iter().map(|change| change.step).collect())
+            .collect();
+        assert_eq!(
+            steps,
+            [vec!["rename", "emojis", "clean"], vec!["rename"], vec!["clean"]]
+        );
+        assert_eq!(plan.files[0].renamed_to, Some(test_dir.join("notes.md")));
+        assert!(test_dir.join("Notes.md").exists());
+
+        let stats = processor.process(&test_dir).unwrap();
+        assert_eq!(plan, stats);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
     #[test]
     fn test_combined_recursive() {
         let test_dir = std::env::temp_dir().join("refmt_combined_recursive");
@@ -275,8 +671,10 @@ mod tests {
         fs::write(&file1, "Text   \n").unwrap();
         fs::write(&file2, "More   \n").unwrap();
 
-        let mut options = CombinedOptions::default();
-        options.recursive = false;
+        let options = CombinedOptions {
+            recursive: false,
+            ..CombinedOptions::default()
+        };
 
         let processor = CombinedProcessor::new(options);
         let stats = processor.process(&test_dir).unwrap();
@@ -294,4 +692,72 @@ mod tests {
 
         fs::remove_dir_all(&test_dir).unwrap();
     }
+
+    #[test]
+    fn test_configured_steps() {
+        let test_dir = std::env::temp_dir().join("refmt_combined_steps");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+
+        let source = test_dir.join("Main.py");
+        fs::write(&source, "myValue = 1  # ✅  \n").unwrap();
+
+        let options = CombinedOptions {
+            steps: vec![
+                CombinedStep::Clean(WhitespaceOptions::default()),
+                CombinedStep::Convert(ConvertStepOptions::new(
+                    vec![CaseFormat::CamelCase],
+                    CaseFormat::SnakeCase,
+                )),
+            ],
+            ..CombinedOptions::default()
+        };
+        let processor = CombinedProcessor::new(options);
+        assert_eq!(processor.step_names(), ["clean", "convert"]);
+        assert_eq!(processor.transforms().len(), 2);
+
+        let plan = processor.plan(&test_dir).unwrap();
+        let stats = processor.process(&test_dir).unwrap();
+        assert_eq!(plan, stats);
+
+        // Neither renamed nor rid of its emoji
+        assert_eq!(stats.files_renamed, 0);
+        assert_eq!(stats.files_emoji_transformed, 0);
+        assert_eq!(stats.files_whitespace_cleaned, 1);
+        assert_eq!(stats.files_converted, 1);
+        assert_eq!(stats.files_modified, 1);
+        assert_eq!(stats.files.len(), 1);
+        assert_eq!(stats.files[0].path, source);
+        assert_eq!(stats.files[0].renamed_to, None);
+        assert_eq!(stats.files[0].changes("clean"), Some(1));
+        assert_eq!(stats.files[0].changes("convert"), Some(1));
+        assert_eq!(fs::read_to_string(&source).unwrap(), "my_value = 1  # ✅\n");
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_events_from_every_step() {
+        let fs = Arc::new(crate::vfs::MemoryFs::new());
+        fs.add_file("events/Notes.txt", "Done ✅  \n");
+
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let options = CombinedOptions {
+            fs: fs.clone(),
+            events: {
+                let events = events.clone();
+                EventSink::new(move |event| events.lock().unwrap().push(event.action))
+            },
+            ..CombinedOptions::default()
+        };
+        CombinedProcessor::new(options)
+            .process(Path::new("events"))
+            .unwrap();
+
+        // The rename, the emoji and the whitespace step each report the file
+        let events = events.lock().unwrap();
+        let count = |action| events.iter().filter(|&&a| a == action).count();
+        assert_eq!(count(FileAction::Renamed), 1);
+        assert_eq!(count(FileAction::Changed), 2);
+    }
 }
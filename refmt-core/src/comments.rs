@@ -0,0 +1,554 @@
+//! Comment style conversion
+//!
+//! Converts between `/* */` blocks and `//` lines in C-family languages, and
+//! normalizes banner rules and decorated headings in `#`-comment languages.
+//! Only comments that occupy whole lines are touched; doc comments (`/** */`,
+//! `/*! */`, `///`, `//!`) are left alone since tooling depends on their form.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::diff::unified_diff;
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::report::{report, EventSink, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// Comment style for C-family languages
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommentStyle {
+    /// `// comment` lines
+    Line,
+    /// `/* comment */` blocks
+    Block,
+}
+
+/// Options for comment conversion
+#[derive(Debug, Clone)]
+pub struct CommentOptions {
+    /// Style to convert C-family comments to (`None` leaves them alone)
+    pub target: Option<CommentStyle>,
+    /// Normalize `#` banner rules and decorated headings
+    pub normalize_banners: bool,
+    /// Total width of normalized banner rules
+    pub banner_width: usize,
+    /// Print a unified diff of each change
+    pub show_diff: bool,
+    /// Color the diffs with ANSI escapes
+    pub color_diff: bool,
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for CommentOptions {
+    fn default() -> Self {
+        CommentOptions {
+            target: None,
+            normalize_banners: false,
+            banner_width: 79,
+            show_diff: false,
+            color_diff: false,
+            file_extensions: vec![
+                ".c", ".h", ".cpp", ".hpp", ".cc",
+                ".js", ".ts", ".jsx", ".tsx",
+                ".java", ".go", ".rs", ".cs", ".kt", ".swift",
+                ".py", ".sh", ".rb", ".pl", ".yaml", ".yml", ".toml",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// Comment syntax family of a file
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Family {
+    /// `//` and `/* */`
+    CStyle,
+    /// `#`
+    Hash,
+}
+
+fn family(path: &Path) -> Option<Family> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+        "c" | "h" | "cpp" | "hpp" | "cc" | "cxx" | "js" | "ts" | "jsx" | "tsx" | "java" | "go"
+        | "rs" | "cs" | "kt" | "swift" | "scala" | "dart" => Some(Family::CStyle),
+        "py" | "pyx" | "sh" | "bash" | "rb" | "pl" | "r" | "yaml" | "yml" | "toml" => {
+            Some(Family::Hash)
+        }
+        _ => None,
+    }
+}
+
+/// Characters that make up banner rules and heading decorations
+const BANNER_CHARS: &[char] = &['#', '=', '-', '*', '~', '_'];
+
+/// Comment style converter
+pub struct CommentConverter {
+    options: CommentOptions,
+    walker: Walker,
+}
+
+impl CommentConverter {
+    /// Creates a new comment converter with the given options
+    pub fn new(options: CommentOptions) -> Self {
+        CommentConverter {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        self.options.fs.is_file(path) && self.accepts(path)
+    }
+
+    /// Checks the path alone against the filters, without touching the filesystem
+    pub fn accepts(&self, path: &Path) -> bool {
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none() && family(path).is_some()
+    }
+
+    /// Converts comments in `content` for the language of `path`
+    ///
+    /// Returns the new content and the number of comments changed, or `None` if
+    /// nothing changes.
+    pub fn convert_str(&self, content: &str, path: &Path) -> Option<(String, usize)> {
+        let (converted, changes) = match family(path)? {
+            Family::CStyle => match self.options.target? {
+                CommentStyle::Line => blocks_to_lines(content),
+                CommentStyle::Block => lines_to_blocks(content),
+            },
+            Family::Hash if self.options.normalize_banners => {
+                normalize_banners(content, self.options.banner_width)
+            }
+            Family::Hash => return None,
+        };
+
+        if changes == 0 || converted == content {
+            None
+        } else {
+            Some((converted, changes))
+        }
+    }
+
+    /// Converts comments in a single file
+    pub fn convert_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        let (outcome, diff) = self.apply_to_file(path)?;
+        self.report(path, outcome, diff.as_deref());
+        Ok(outcome)
+    }
+
+    /// Converts a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path) -> crate::Result<(FileOutcome, Option<String>)> {
+        if !self.should_process(path) {
+            return Ok((FileOutcome::unchanged(), None));
+        }
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        let (converted, changes) = match self.convert_str(&content, path) {
+            Some(result) => result,
+            None => return Ok((FileOutcome::unchanged(), None)),
+        };
+
+        let diff = self
+            .options
+            .show_diff
+            .then(|| unified_diff(path, &content, &converted, self.options.color_diff));
+
+        if !self.options.dry_run {
+            self.options.fs.write(path, converted.as_bytes())?;
+        }
+
+        Ok((FileOutcome::changed(changes), diff))
+    }
+
+    fn report(&self, path: &Path, outcome: FileOutcome, diff: Option<&str>) {
+        if !outcome.changed {
+            return;
+        }
+
+        self.options.events.file_changed(path, outcome.changes, self.options.dry_run);
+        if self.options.dry_run {
+            report!(
+                "Would convert {} comments in '{}'",
+                outcome.changes,
+                path.display()
+            );
+        } else {
+            report!("Converted {} comments in '{}'", outcome.changes, path.display());
+        }
+
+        if let Some(diff) = diff {
+            report!("{}", diff.trim_end());
+        }
+    }
+
+    /// Processes a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<(usize, usize)> {
+        let mut total_files = 0;
+        let mut total_changes = 0;
+
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.apply_to_file(file)
+        });
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let (outcome, diff) = result?;
+            self.report(file_path, outcome, diff.as_deref());
+            if outcome.changed {
+                total_files += 1;
+                total_changes += outcome.changes;
+            }
+        }
+
+        Ok((total_files, total_changes))
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+/// Splits a line into its body and line ending
+fn split_eol(line: &str) -> (&str, &str) {
+    if let Some(body) = line.strip_suffix("\r\n") {
+        (body, "\r\n")
+    } else if let Some(body) = line.strip_suffix('\n') {
+        (body, "\n")
+    } else {
+        (line, "")
+    }
+}
+
+/// Splits a line body into its indentation and the rest
+fn split_indent(body: &str) -> (&str, &str) {
+    let trimmed = body.trim_start();
+    (&body[..body.len() - trimmed.len()], trimmed)
+}
+
+/// Converts whole-line `/* */` comments to `//` lines
+fn blocks_to_lines(content: &str) -> (String, usize) {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut out = String::with_capacity(content.len());
+    let mut changes = 0;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let (body, _) = split_eol(lines[i]);
+        let (indent, trimmed) = split_indent(body);
+
+        let is_block_start =
+            trimmed.starts_with("/*") && !trimmed.starts_with("/**") && !trimmed.starts_with("/*!");
+        if let Some((end, texts)) = is_block_start.then(|| block_comment(&lines, i)).flatten() {
+            let (_, eol) = split_eol(lines[end]);
+            let line_eol = if eol.is_empty() { "\n" } else { eol };
+            for (n, text) in texts.iter().enumerate() {
+                out.push_str(indent);
+                out.push_str("//");
+                if !text.is_empty() {
+                    out.push(' ');
+                    out.push_str(text);
+                }
+                out.push_str(if n + 1 == texts.len() { eol } else { line_eol });
+            }
+            changes += 1;
+            i = end + 1;
+            continue;
+        }
+
+        out.push_str(lines[i]);
+        i += 1;
+    }
+
+    (out, changes)
+}
+
+/// Reads the block comment starting on line `start`
+///
+/// Returns the index of its last line and its text lines with `*` decoration removed,
+/// or `None` if the comment does not end a line.
+fn block_comment(lines: &[&str], start: usize) -> Option<(usize, Vec<String>)> {
+    let mut pieces = Vec::new();
+
+    for (offset, line) in lines[start..].iter().enumerate() {
+        let (body, _) = split_eol(line);
+        let body = if offset == 0 {
+            &body.trim_start()[2..]
+        } else {
+            body
+        };
+
+        match body.find("*/") {
+            Some(close) => {
+                // Code after the comment: leave it alone
+                if !body[close + 2..].trim().is_empty() {
+                    return None;
+                }
+                pieces.push(&body[..close]);
+
+                let mut texts: Vec<String> = pieces
+                    .iter()
+                    .map(|piece| {
+                        let piece = piece.trim();
+                        let piece = piece.strip_prefix('*').map(str::trim_start).unwrap_or(piece);
+                        piece.trim_end().to_string()
+                    })
+                    .collect();
+                // Drop the empty lines left by `/*` and `*/` on their own lines
+                while texts.first().is_some_and(String::is_empty) {
+                    texts.remove(0);
+                }
+                while texts.last().is_some_and(String::is_empty) {
+                    texts.pop();
+                }
+
+                return (!texts.is_empty()).then_some((start + offset, texts));
+            }
+            None => pieces.push(body),
+        }
+    }
+
+    None
+}
+
+/// Converts runs of `//` lines to `/* */` blocks
+fn lines_to_blocks(content: &str) -> (String, usize) {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut out = String::with_capacity(content.len());
+    let mut changes = 0;
+    let mut i = 0;
+
+    let comment_text = |line: &str| -> Option<(String, String)> {
+        let (body, _) = split_eol(line);
+        let (indent, trimmed) = split_indent(body);
+        if !trimmed.starts_with("//") || trimmed.starts_with("///") || trimmed.starts_with("//!") {
+            return None;
+        }
+        // A `*/` inside the text would end the block early
+        if trimmed.contains("*/") {
+            return None;
+        }
+        let text = &trimmed[2..];
+        let text = text.strip_prefix(' ').unwrap_or(text);
+        Some((indent.to_string(), text.trim_end().to_string()))
+    };
+
+    while i < lines.len() {
+        let (indent, first_text) = match comment_text(lines[i]) {
+            Some(comment) => comment,
+            None => {
+                out.push_str(lines[i]);
+                i += 1;
+                continue;
+            }
+        };
+
+        let mut texts = vec![first_text];
+        let mut end = i;
+        while let Some((next_indent, text)) = lines.get(end + 1).and_then(|l| comment_text(l)) {
+            if next_indent != indent {
+                break;
+            }
+            texts.push(text);
+            end += 1;
+        }
+
+        let (_, first_eol) = split_eol(lines[i]);
+        let (_, last_eol) = split_eol(lines[end]);
+        let eol = if first_eol.is_empty() { "\n" } else { first_eol };
+
+        if texts.len() == 1 {
+            out.push_str(&format!("{}/* {} */{}", indent, texts[0], last_eol));
+        } else {
+            out.push_str(&format!("{}/*{}", indent, eol));
+            for text in &texts {
+                if text.is_empty() {
+                    out.push_str(&format!("{} *{}", indent, eol));
+                } else {
+                    out.push_str(&format!("{} * {}{}", indent, text, eol));
+                }
+            }
+            out.push_str(&format!("{} */{}", indent, last_eol));
+        }
+
+        changes += 1;
+        i = end + 1;
+    }
+
+    (out, changes)
+}
+
+/// Normalizes `#` banner rules to `# ----` of a fixed width and decorated
+/// headings (`## Title ##`, `# === Title ===`) to `# Title`
+fn normalize_banners(content: &str, width: usize) -> (String, usize) {
+    let mut out = String::with_capacity(content.len());
+    let mut changes = 0;
+
+    for (n, line) in content.split_inclusive('\n').enumerate() {
+        let (body, eol) = split_eol(line);
+        let (indent, trimmed) = split_indent(body);
+
+        let normalized = if trimmed.starts_with('#') && !(n == 0 && trimmed.starts_with("#!")) {
+            normalize_banner_line(indent, trimmed, width)
+        } else {
+            None
+        };
+
+        match normalized {
+            Some(normalized) if normalized != body => {
+                out.push_str(&normalized);
+                out.push_str(eol);
+                changes += 1;
+            }
+            _ => out.push_str(line),
+        }
+    }
+
+    (out, changes)
+}
+
+fn normalize_banner_line(indent: &str, trimmed: &str, width: usize) -> Option<String> {
+    let hashes = trimmed.len() - trimmed.trim_start_matches('#').len();
+    let text = trimmed[hashes..].trim();
+
+    // Rule lines: `########`, `# ----------`, `# ==========`
+    let is_rule = if text.is_empty() {
+        hashes >= 4
+    } else {
+        text.chars().count() >= 3 && text.chars().all(|c| BANNER_CHARS.contains(&c))
+    };
+    if is_rule {
+        let dashes = width.saturating_sub(indent.len() + 2).max(3);
+        return Some(format!("{}# {}", indent, "-".repeat(dashes)));
+    }
+
+    // Emacs modelines (`# -*- coding: utf-8 -*-`) look decorated but must stay intact
+    if text.starts_with("-*-") {
+        return None;
+    }
+
+    let inner = text.trim_start_matches(BANNER_CHARS).trim_start();
+    let leading = text.len() - text.trim_start_matches(BANNER_CHARS).len();
+    let inner_trimmed = inner.trim_end_matches(BANNER_CHARS).trim_end();
+    let trailing = inner.len() - inner.trim_end_matches(BANNER_CHARS).len();
+
+    // Headings: `## Title`, `### Title ###`, `# === Title ===`
+    let decorated = hashes >= 2 || (leading >= 2 && trailing >= 2);
+    if decorated && !inner_trimmed.is_empty() {
+        return Some(format!("{}# {}", indent, inner_trimmed));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_converter(target: Option<CommentStyle>, normalize_banners: bool) -> CommentConverter {
+        let options = CommentOptions {
+            target,
+            normalize_banners,
+            banner_width: 20,
+            ..CommentOptions::default()
+        };
+        CommentConverter::new(options)
+    }
+
+    #[test]
+    fn test_blocks_to_lines() {
+        let converter = make_converter(Some(CommentStyle::Line), false);
+        let content = "/* single */\nint x;\n    /*\n     * multi\n     *\n     * line\n     */\n    call();\n";
+        let (result, changes) = converter.convert_str(content, Path::new("a.c")).unwrap();
+        assert_eq!(
+            result,
+            "// single\nint x;\n    // multi\n    //\n    // line\n    call();\n"
+        );
+        assert_eq!(changes, 2);
+    }
+
+    #[test]
+    fn test_blocks_with_code_or_docs_untouched() {
+        let converter = make_converter(Some(CommentStyle::Line), false);
+        let content = "/** Doc comment */\n/* note */ int x;\nint y; /* trailing */\n";
+        assert_eq!(converter.convert_str(content, Path::new("a.c")), None);
+    }
+
+    #[test]
+    fn test_lines_to_blocks() {
+        let converter = make_converter(Some(CommentStyle::Block), false);
+        let content = "// one\nint x;\n  // first\n  //\n  // second\n  y();\n/// doc\n";
+        let (result, changes) = converter.convert_str(content, Path::new("a.js")).unwrap();
+        assert_eq!(
+            result,
+            "/* one */\nint x;\n  /*\n   * first\n   *\n   * second\n   */\n  y();\n/// doc\n"
+        );
+        assert_eq!(changes, 2);
+
+        // Round trip back to line comments
+        let (back, _) = make_converter(Some(CommentStyle::Line), false)
+            .convert_str(&result, Path::new("a.js"))
+            .unwrap();
+        assert_eq!(back, content);
+    }
+
+    #[test]
+    fn test_normalize_banners() {
+        let converter = make_converter(None, true);
+        let content = "#!/bin/sh\n# -*- coding: utf-8 -*-\n##########\n## Setup ##\n# === Build ===\n# - item\nx=1\n";
+        let (result, changes) = converter.convert_str(content, Path::new("a.sh")).unwrap();
+        assert_eq!(
+            result,
+            "#!/bin/sh\n# -*- coding: utf-8 -*-\n# ------------------\n# Setup\n# Build\n# - item\nx=1\n"
+        );
+        assert_eq!(changes, 3);
+
+        // Already normalized
+        assert_eq!(converter.convert_str(&result, Path::new("a.sh")), None);
+    }
+
+    #[test]
+    fn test_family_options() {
+        // Banner normalization doesn't touch C files, and no target leaves them alone
+        let converter = make_converter(None, true);
+        assert_eq!(converter.convert_str("/* x */\n", Path::new("a.c")), None);
+        assert_eq!(converter.convert_str("x\n", Path::new("a.txt")), None);
+    }
+}
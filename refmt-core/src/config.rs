@@ -0,0 +1,502 @@
+//! Project configuration (`refmt.toml`)
+//!
+//! The configuration file is discovered by walking up from the processing target,
+//! so a `refmt.toml` at the repository root applies to every path below it. Settings
+//! for all of a user's projects go in [`user_config_file`]
+//! (`~/.config/refmt/config.toml`), which a project's file overrides key by key.
+//! Named profiles (`[profile.docs]`) hold settings that override the others only when
+//! selected (see [`Config::with_profile`]). Built-in [`PRESETS`] give `refmt <path>`
+//! a ready-made pipeline. Command-line flags override all of them.
+//!
+//! ```toml
+//! require_clean_git = true
+//! abbreviations = ["ID", "URL"]
+//! exclude = ["**/generated/**"]
+//! steps = ["convert", "clean"]
+//!
+//! [extensions]
+//! clean = [".rs", ".toml", ".md"]
+//! emojis = [".md"]
+//! rename = [".txt"]
+//!
+//! [convert]
+//! from = ["camel"]
+//! to = "snake"
+//! exclude_words = ["iOS"]
+//!
+//! [emojis]
+//! remove_other = false
+//! mappings = { "🚀" = "[launch]" }
+//!
+//! [rename]
+//! case = "lowercase"
+//! spaces = "underscore"
+//!
+//! [profile.docs]
+//! steps = ["emojis", "clean"]
+//! extensions = { clean = [".md"], emojis = [".md"] }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::case::{Abbreviations, CaseFormat};
+use crate::error::{Context, Error};
+use crate::filter::ExtensionSet;
+use crate::rename::{CaseTransform, SpaceReplace};
+
+/// Name of the configuration file
+pub const CONFIG_FILE_NAME: &str = "refmt.toml";
+
+/// Steps a configured pipeline may run
+pub const STEPS: [&str; 4] = ["rename", "clean", "emojis", "convert"];
+
+/// Built-in pipelines for `refmt <path>`: each name with the steps it runs and the
+/// settings they need, as TOML
+pub const PRESETS: [(&str, &str); 3] = [
+    (
+        "markdown-cleanup",
+        "steps = [\"emojis\", \"clean\"]\n\
+         [extensions]\nemojis = [\".md\", \".markdown\"]\nclean = [\".md\", \".markdown\"]\n",
+    ),
+    ("code-hygiene", "steps = [\"clean\"]\n"),
+    (
+        "snake-everything",
+        "steps = [\"convert\", \"rename\"]\n\
+         [convert]\nfrom = [\"camel\", \"pascal\"]\nto = \"snake\"\n\
+         [rename]\ncase = \"lowercase\"\nspaces = \"underscore\"\n",
+    ),
+];
+
+/// Settings read from `refmt.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Refuse to modify files while the git repository has uncommitted changes
+    pub require_clean_git: bool,
+    /// Runs changing at least this many files ask for confirmation first (50 when unset)
+    pub confirm_threshold: Option<usize>,
+    /// Words `convert` spells as given in capitalized formats (see [`Abbreviations`])
+    pub abbreviations: Vec<String>,
+    /// Globs of files to leave out, on top of `--exclude`
+    pub exclude: Vec<String>,
+    /// Transformations `refmt <path>` and `watch` run, in order (see [`STEPS`])
+    pub steps: Option<Vec<String>>,
+    /// The [preset](PRESETS) `refmt <path>` runs when `--preset` isn't given
+    pub preset: Option<String>,
+    /// Default extension sets, replacing the built-in ones per subcommand
+    pub extensions: ExtensionsConfig,
+    /// Defaults for `convert`
+    pub convert: ConvertConfig,
+    /// Defaults for `emojis`
+    pub emojis: EmojiConfig,
+    /// Defaults for `rename_files`
+    pub rename: RenameConfig,
+    /// Named sets of the settings above, selected with `--profile`
+    pub profile: BTreeMap<String, toml::Table>,
+    /// The table this was read from, which profiles are applied to
+    #[serde(skip)]
+    table: toml::Table,
+}
+
+/// The `[extensions]` table; an unset entry keeps the built-in defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExtensionsConfig {
+    /// Extensions for `convert`
+    pub convert: Option<Vec<String>>,
+    /// Extensions for `clean`
+    pub clean: Option<Vec<String>>,
+    /// Extensions for `emojis`
+    pub emojis: Option<Vec<String>>,
+    /// Extensions of the files `rename_files` renames; all files when unset
+    pub rename: Option<Vec<String>>,
+}
+
+/// The `[convert]` table
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConvertConfig {
+    /// Formats to convert from when `--from` isn't given
+    pub from: Vec<CaseFormat>,
+    /// Format to convert to when `--to` isn't given
+    pub to: Option<CaseFormat>,
+    /// Words never converted, on top of `--exclude-words`
+    pub exclude_words: Vec<String>,
+    /// Treat non-ASCII letters as letters, like `--unicode`
+    pub unicode: bool,
+}
+
+/// The `[emojis]` table
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmojiConfig {
+    /// Whether task emojis are replaced with text
+    pub replace_task: Option<bool>,
+    /// Whether other emojis are removed
+    pub remove_other: Option<bool>,
+    /// Replacements applied before the built-in ones
+    pub mappings: BTreeMap<String, String>,
+}
+
+/// The `[rename]` table, used when `rename_files` gets no case or separator flag
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RenameConfig {
+    /// Case of the new names
+    pub case: Option<CaseTransform>,
+    /// What spaces become
+    pub spaces: Option<SpaceReplace>,
+}
+
+impl ExtensionsConfig {
+    /// The configured extensions for `set`, if any
+    pub fn get(&self, set: ExtensionSet) -> Option<&[String]> {
+        match set {
+            ExtensionSet::Convert => self.convert.as_deref(),
+            ExtensionSet::Emojis => self.emojis.as_deref(),
+            ExtensionSet::Whitespace => self.clean.as_deref(),
+        }
+    }
+}
+
+impl Config {
+    /// Parses configuration from TOML source
+    ///
+    /// Extensions may be written with or without the leading dot (`md` or `.md`).
+    pub fn parse(content: &str) -> crate::Result<Self> {
+        Config::from_table(toml::from_str(content)?)
+    }
+
+    fn from_table(table: toml::Table) -> crate::Result<Self> {
+        let mut config: Config = toml::Value::Table(table.clone()).try_into()?;
+        config.table = table;
+        for extensions in [
+            &mut config.extensions.convert,
+            &mut config.extensions.clean,
+            &mut config.extensions.emojis,
+            &mut config.extensions.rename,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            for ext in extensions.iter_mut() {
+                if !ext.starts_with('.') {
+                    ext.insert(0, '.');
+                }
+            }
+        }
+
+        if let Some(step) = config.steps.iter().flatten().find(|s| !STEPS.contains(&s.as_str())) {
+            return Err(Error::Invalid(format!(
+                "Unknown step '{}'; expected one of {}",
+                step,
+                STEPS.join(", ")
+            )));
+        }
+        if let Some(preset) = &config.preset {
+            preset_settings(preset)?;
+        }
+        config.exclude_patterns()?;
+
+        for (name, profile) in &config.profile {
+            let invalid = || format!("Invalid profile '{}'", name);
+            if profile.contains_key("profile") {
+                return Err(Error::Invalid("profiles can't define profiles".to_string()))
+                    .with_context(invalid);
+            }
+            Config::from_table(profile.clone()).with_context(invalid)?;
+        }
+        Ok(config)
+    }
+
+    /// Applies the named [`profile`](Self::profile): its settings replace the others,
+    /// with tables merged key by key
+    pub fn with_profile(&self, name: &str) -> crate::Result<Self> {
+        let Some(profile) = self.profile.get(name) else {
+            let names: Vec<&str> = self.profile.keys().map(String::as_str).collect();
+            return Err(Error::Invalid(if names.is_empty() {
+                format!("Unknown profile '{}'; no profiles are configured", name)
+            } else {
+                format!("Unknown profile '{}'; expected one of {}", name, names.join(", "))
+            }));
+        };
+        let mut table = self.table.clone();
+        table.remove("profile");
+        merge(&mut table, profile.clone());
+        Config::from_table(table)
+    }
+
+    /// Applies the named [preset](PRESETS): its steps replace [`steps`](Self::steps),
+    /// and its other settings apply where this configuration doesn't set them
+    pub fn with_preset(&self, name: &str) -> crate::Result<Self> {
+        let mut table: toml::Table = toml::from_str(preset_settings(name)?)?;
+        let mut own = self.table.clone();
+        own.remove("preset");
+        own.remove("steps");
+        merge(&mut table, own);
+        Config::from_table(table)
+    }
+
+    /// Loads a configuration file
+    pub fn load(path: &Path) -> crate::Result<Self> {
+        Config::load_all(&[path.to_path_buf()])
+    }
+
+    /// Loads configuration files in turn, each overriding the keys set by the previous
+    /// ones (tables are merged, other values replaced)
+    pub fn load_all(paths: &[PathBuf]) -> crate::Result<Self> {
+        let mut merged = toml::Table::new();
+        for path in paths {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read config file '{}'", path.display()))?;
+            let invalid = || format!("Invalid config file '{}'", path.display());
+            let table: toml::Table = toml::from_str(&content).with_context(invalid)?;
+            Config::from_table(table.clone()).with_context(invalid)?;
+            merge(&mut merged, table);
+        }
+        Config::from_table(merged)
+    }
+
+    /// Loads the configuration that applies to `path`: the [`user_config_file`], then
+    /// the project's `refmt.toml` (see [`find_config`])
+    pub fn load_for(path: &Path) -> crate::Result<Self> {
+        Config::load_all(&config_files(path))
+    }
+
+    /// The [`exclude`](Self::exclude) globs
+    pub fn exclude_patterns(&self) -> crate::Result<Vec<glob::Pattern>> {
+        self.exclude
+            .iter()
+            .map(|pattern| glob::Pattern::new(pattern).map_err(Error::from))
+            .collect()
+    }
+
+    /// The [`emojis.mappings`](EmojiConfig::mappings), as emoji options take them
+    pub fn emoji_mappings(&self) -> Vec<(String, String)> {
+        self.emojis
+            .mappings
+            .iter()
+            .map(|(from, to)| (from.clone(), to.clone()))
+            .collect()
+    }
+
+    /// The configured abbreviations dictionary
+    pub fn abbreviations(&self) -> Abbreviations {
+        Abbreviations::new(self.abbreviations.iter().cloned())
+    }
+
+    /// The default extensions for `set`: the configured ones, or the built-in table
+    pub fn extensions(&self, set: ExtensionSet) -> Vec<String> {
+        match self.extensions.get(set) {
+            Some(extensions) => extensions.to_vec(),
+            None => set.defaults(),
+        }
+    }
+}
+
+/// The settings of the named [preset](PRESETS)
+fn preset_settings(name: &str) -> crate::Result<&'static str> {
+    match PRESETS.iter().find(|(preset, _)| *preset == name) {
+        Some((_, settings)) => Ok(settings),
+        None => Err(Error::Invalid(format!(
+            "Unknown preset '{}'; expected one of {}",
+            name,
+            PRESETS.map(|(preset, _)| preset).join(", ")
+        ))),
+    }
+}
+
+/// Recursively merges `over` into `base`; `over` wins except where both have a table
+fn merge(base: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(over)) => merge(base, over),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// The user configuration file, `$XDG_CONFIG_HOME/refmt/config.toml` or
+/// `~/.config/refmt/config.toml`, if it exists
+pub fn user_config_file() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .or_else(|| std::env::var_os("USERPROFILE"))
+                .map(|home| PathBuf::from(home).join(".config"))
+        })?;
+    Some(config_home.join("refmt").join("config.toml")).filter(|file| file.is_file())
+}
+
+/// The configuration files that apply to `path`, lowest precedence first
+pub fn config_files(path: &Path) -> Vec<PathBuf> {
+    user_config_file().into_iter().chain(find_config(path)).collect()
+}
+
+/// Finds the `refmt.toml` that applies to `path`, searching it (or its directory,
+/// for a file) and then each parent directory
+pub fn find_config(path: &Path) -> Option<PathBuf> {
+    let path = path.canonicalize().ok()?;
+    let start = if path.is_file() {
+        path.parent()?
+    } else {
+        &path
+    };
+    start
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_extensions() {
+        let config = Config::parse("[extensions]\nclean = [\"rs\", \".md\"]\n").unwrap();
+        assert_eq!(
+            config.extensions(ExtensionSet::Whitespace),
+            vec![".rs".to_string(), ".md".to_string()]
+        );
+        assert_eq!(
+            config.extensions(ExtensionSet::Emojis),
+            ExtensionSet::Emojis.defaults()
+        );
+
+        assert_eq!(
+            Config::parse("[extensions]\nrename = [\"txt\"]\n").unwrap().extensions.rename,
+            Some(vec![".txt".to_string()])
+        );
+        assert!(Config::parse("[extensions]\nindent = []\n").is_err());
+        assert!(Config::parse("").unwrap().extensions.clean.is_none());
+        assert!(Config::parse("require_clean_git = true\n").unwrap().require_clean_git);
+        assert_eq!(Config::parse("confirm_threshold = 10\n").unwrap().confirm_threshold, Some(10));
+    }
+
+    #[test]
+    fn test_parse_sections() {
+        let config = Config::parse(
+            "exclude = [\"vendor/**\"]\nsteps = [\"convert\", \"clean\"]\n\
+             [convert]\nfrom = [\"camel\"]\nto = \"snake\"\n\
+             [emojis]\nremove_other = false\nmappings = { \"🚀\" = \"[launch]\" }\n\
+             [rename]\ncase = \"lowercase\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.exclude_patterns().unwrap().len(), 1);
+        assert_eq!(config.convert.from, vec![CaseFormat::CamelCase]);
+        assert_eq!(config.convert.to, Some(CaseFormat::SnakeCase));
+        assert_eq!(config.emojis.remove_other, Some(false));
+        assert_eq!(config.emojis.replace_task, None);
+        assert_eq!(
+            config.emoji_mappings(),
+            vec![("🚀".to_string(), "[launch]".to_string())]
+        );
+        assert_eq!(config.rename.case, Some(CaseTransform::Lowercase));
+
+        assert!(Config::parse("steps = [\"indent\"]\n").is_err());
+        assert!(Config::parse("exclude = [\"[\"]\n").is_err());
+        assert!(Config::parse("[convert]\nto = \"shouting\"\n").is_err());
+    }
+
+    #[test]
+    fn test_profiles() {
+        let config = Config::parse(
+            "abbreviations = [\"ID\"]\n\
+             [extensions]\nclean = [\".rs\"]\nemojis = [\".rs\"]\n\
+             [profile.docs]\nsteps = [\"emojis\"]\n\
+             [profile.docs.extensions]\nclean = [\"md\"]\n",
+        )
+        .unwrap();
+        assert!(config.steps.is_none());
+
+        let docs = config.with_profile("docs").unwrap();
+        assert_eq!(docs.steps, Some(vec!["emojis".to_string()]));
+        assert_eq!(docs.extensions.clean, Some(vec![".md".to_string()]));
+        assert_eq!(docs.extensions.emojis, Some(vec![".rs".to_string()]));
+        assert_eq!(docs.abbreviations, vec!["ID".to_string()]);
+        assert!(docs.profile.is_empty());
+
+        let error = config.with_profile("cpp").unwrap_err().to_string();
+        assert!(error.contains("expected one of docs"), "{}", error);
+        assert!(Config::parse("[profile.docs]\nsteps = [\"indent\"]\n").is_err());
+        assert!(Config::parse("[profile.docs.profile.cpp]\n").is_err());
+    }
+
+    #[test]
+    fn test_presets() {
+        for (name, _) in PRESETS {
+            assert!(Config::default().with_preset(name).unwrap().steps.is_some());
+        }
+
+        let config =
+            Config::parse("steps = [\"clean\"]\nabbreviations = [\"ID\"]\n[convert]\nto = \"kebab\"\n")
+                .unwrap();
+        let snake = config.with_preset("snake-everything").unwrap();
+        assert_eq!(
+            snake.steps,
+            Some(vec!["convert".to_string(), "rename".to_string()])
+        );
+        assert_eq!(snake.convert.from, vec![CaseFormat::CamelCase, CaseFormat::PascalCase]);
+        assert_eq!(snake.convert.to, Some(CaseFormat::KebabCase));
+        assert_eq!(snake.rename.spaces, Some(SpaceReplace::Underscore));
+        assert_eq!(snake.abbreviations, vec!["ID".to_string()]);
+
+        let error = config.with_preset("tidy").unwrap_err().to_string();
+        assert!(error.contains("expected one of markdown-cleanup"), "{}", error);
+        assert!(Config::parse("preset = \"tidy\"\n").is_err());
+        assert_eq!(
+            Config::parse("preset = \"code-hygiene\"\n").unwrap().preset.as_deref(),
+            Some("code-hygiene")
+        );
+    }
+
+    #[test]
+    fn test_load_all() {
+        let root = std::env::temp_dir().join("refmt_config_load_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let user = root.join("user.toml");
+        let project = root.join("project.toml");
+        fs::write(
+            &user,
+            "abbreviations = [\"ID\"]\n[emojis]\nremove_other = false\nreplace_task = false\n",
+        )
+        .unwrap();
+        fs::write(&project, "[emojis]\nreplace_task = true\n").unwrap();
+
+        let config = Config::load_all(&[user, project.clone()]).unwrap();
+        assert_eq!(config.abbreviations, vec!["ID".to_string()]);
+        assert_eq!(config.emojis.remove_other, Some(false));
+        assert_eq!(config.emojis.replace_task, Some(true));
+
+        fs::write(&project, "[emojis]\nunknown = 1\n").unwrap();
+        let error = Config::load(&project).unwrap_err().to_string();
+        assert!(error.contains("project.toml"), "{}", error);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_find_config() {
+        let root = std::env::temp_dir().join("refmt_config_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::write(root.join("src/nested/lib.rs"), "").unwrap();
+        assert_eq!(find_config(&root.join("src/nested/lib.rs")), None);
+
+        fs::write(root.join(CONFIG_FILE_NAME), "").unwrap();
+        let found = find_config(&root.join("src/nested/lib.rs")).unwrap();
+        assert_eq!(found, root.canonicalize().unwrap().join(CONFIG_FILE_NAME));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,10 +1,64 @@
 //! Case converter implementation for file processing
 
-use crate::case::CaseFormat;
+use crate::anchors::{self, AnchorChanges};
+use crate::case::{Abbreviations, CaseFormat, DigitBoundary};
+#[cfg(feature = "declared-only")]
+use crate::declarations::{self, SourceLanguage};
+use crate::diff::unified_diff;
+use crate::error::Error;
+use crate::filter::{read_text, ExtensionSet, FilterOptions, SkipReason};
+use crate::keys::{self, KeyFormat};
+use crate::parallel;
+use crate::progress::Progress;
+use crate::report::{self, report, EventSink, FailedFile, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions};
+use log::{error, warn};
 use regex::Regex;
-use std::fs;
-use std::path::Path;
-use walkdir::WalkDir;
+use std::borrow::Cow;
+use std::collections::HashSet;
+#[cfg(feature = "declared-only")]
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// What [`CaseConverter::process_directory`] did, in path order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConversionReport {
+    /// Files found under the path, including skipped and failed ones
+    pub files_scanned: usize,
+    /// Files that were converted (or would be, in dry run mode) with their number of
+    /// converted identifiers
+    pub converted: Vec<(PathBuf, usize)>,
+    /// Files left out by a filter
+    pub skipped: Vec<(PathBuf, SkipReason)>,
+    /// Files that couldn't be read or written
+    pub failed: Vec<FailedFile>,
+    /// Entries of the tree that couldn't be read, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub unreadable: Vec<TraversalError>,
+}
+
+impl ConversionReport {
+    /// Number of files converted
+    pub fn files_changed(&self) -> usize {
+        self.converted.len()
+    }
+
+    /// Number of identifiers converted across all files
+    pub fn identifiers_converted(&self) -> usize {
+        self.converted.iter().map(|(_, count)| count).sum()
+    }
+
+    fn record(&mut self, path: &Path, outcome: FileOutcome) {
+        self.files_scanned += 1;
+        match outcome.skipped {
+            Some(reason) => self.skipped.push((path.to_path_buf(), reason)),
+            None if outcome.changed => self.converted.push((path.to_path_buf(), outcome.changes)),
+            None => {}
+        }
+    }
+}
 
 /// Main converter for transforming case formats in files
 pub struct CaseConverter {
@@ -12,6 +66,7 @@ pub struct CaseConverter {
     to_format: CaseFormat,
     file_extensions: Vec<String>,
     recursive: bool,
+    walk: WalkOptions,
     dry_run: bool,
     prefix: String,
     suffix: String,
@@ -21,13 +76,31 @@ pub struct CaseConverter {
     replace_prefix_to: Option<String>,
     replace_suffix_from: Option<String>,
     replace_suffix_to: Option<String>,
-    glob_pattern: Option<glob::Pattern>,
+    glob_patterns: Vec<glob::Pattern>,
     word_filter: Option<Regex>,
+    word_exclude_filter: Option<Regex>,
+    excluded_words: HashSet<String>,
+    source_formats: Vec<CaseFormat>,
     source_pattern: Regex,
+    unicode: bool,
+    digit_boundary: Option<DigitBoundary>,
+    abbreviations: Abbreviations,
+    structured_keys: bool,
+    #[cfg(feature = "declared-only")]
+    declared_only: bool,
+    update_anchors: bool,
+    show_diff: bool,
+    color_diff: bool,
+    filter: FilterOptions,
+    progress: Progress,
+    fs: Arc<dyn Fs>,
+    events: EventSink,
 }
 
 impl CaseConverter {
     /// Creates a new case converter
+    // The positional form predates the builder, which is the readable way to set these
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         from_format: CaseFormat,
         to_format: CaseFormat,
@@ -45,19 +118,12 @@ impl CaseConverter {
         glob_pattern: Option<String>,
         word_filter: Option<String>,
     ) -> crate::Result<Self> {
-        let file_extensions = file_extensions.unwrap_or_else(|| {
-            vec![
-                ".c", ".h", ".py", ".md", ".js", ".ts", ".java", ".cpp", ".hpp",
-            ]
-            .iter()
-            .map(|s| s.to_string())
-            .collect()
-        });
+        let file_extensions = file_extensions.unwrap_or_else(|| ExtensionSet::Convert.defaults());
 
         let source_pattern = Regex::new(from_format.pattern())?;
-        let glob_pattern = match glob_pattern {
-            Some(pattern) => Some(glob::Pattern::new(&pattern)?),
-            None => None,
+        let glob_patterns = match glob_pattern {
+            Some(pattern) => vec![glob::Pattern::new(&pattern)?],
+            None => Vec::new(),
         };
         let word_filter = match word_filter {
             Some(pattern) => Some(Regex::new(&pattern)?),
@@ -69,6 +135,7 @@ impl CaseConverter {
             to_format,
             file_extensions,
             recursive,
+            walk: WalkOptions::default(),
             dry_run,
             prefix,
             suffix,
@@ -78,14 +145,211 @@ impl CaseConverter {
             replace_prefix_to,
             replace_suffix_from,
             replace_suffix_to,
-            glob_pattern,
+            glob_patterns,
             word_filter,
+            word_exclude_filter: None,
+            excluded_words: HashSet::new(),
+            source_formats: vec![from_format],
             source_pattern,
+            unicode: false,
+            digit_boundary: None,
+            abbreviations: Abbreviations::default(),
+            structured_keys: false,
+            #[cfg(feature = "declared-only")]
+            declared_only: false,
+            update_anchors: true,
+            show_diff: false,
+            color_diff: false,
+            filter: FilterOptions::default(),
+            progress: Progress::default(),
+            fs: vfs::real(),
+            events: EventSink::default(),
         })
     }
 
+    /// Starts a [`CaseConverterBuilder`], for setting options by name rather than
+    /// through the arguments of [`new`](Self::new)
+    pub fn builder(from_format: CaseFormat, to_format: CaseFormat) -> CaseConverterBuilder {
+        CaseConverterBuilder::new(from_format, to_format)
+    }
+
+    /// Sets whether Markdown links to headings whose anchors change are rewritten
+    /// (enabled by default)
+    pub fn set_update_anchors(&mut self, update_anchors: bool) {
+        self.update_anchors = update_anchors;
+    }
+
+    /// Sets whether a unified diff of each change is printed
+    pub fn set_show_diff(&mut self, show_diff: bool) {
+        self.show_diff = show_diff;
+    }
+
+    /// Sets whether printed diffs are colored with ANSI escapes
+    pub fn set_color_diff(&mut self, color_diff: bool) {
+        self.color_diff = color_diff;
+    }
+
+    /// Sets the formats converted to the target, replacing the one given to
+    /// [`new`](Self::new)
+    ///
+    /// All formats are matched in a single scan; each identifier is converted from the
+    /// first of them it fits as a whole.
+    pub fn set_source_formats(&mut self, formats: Vec<CaseFormat>) -> crate::Result<()> {
+        if formats.is_empty() {
+            return Err(Error::Invalid("At least one source format is required".to_string()));
+        }
+        self.source_formats = formats;
+        self.source_pattern = self.build_source_pattern()?;
+        Ok(())
+    }
+
+    /// Sets whether identifiers with non-ASCII letters (`größeZahl`, `naïveValue`) are
+    /// matched, using each format's [`unicode_pattern`](CaseFormat::unicode_pattern)
+    pub fn set_unicode(&mut self, unicode: bool) {
+        self.unicode = unicode;
+        self.source_pattern = self
+            .build_source_pattern()
+            .expect("case format patterns are valid");
+    }
+
+    /// Sets how digits split identifiers into words; `None` keeps the words as the
+    /// source format splits them (`sha_256` stays three words, `sha256Hash` two)
+    pub fn set_digit_boundary(&mut self, digit_boundary: Option<DigitBoundary>) {
+        self.digit_boundary = digit_boundary;
+    }
+
+    /// Sets the words with a fixed spelling in the target format (`userID` rather
+    /// than `userId`)
+    pub fn set_abbreviations(&mut self, abbreviations: Abbreviations) {
+        self.abbreviations = abbreviations;
+    }
+
+    /// One regex matching any of the source formats
+    fn build_source_pattern(&self) -> crate::Result<Regex> {
+        let pattern = self
+            .source_formats
+            .iter()
+            .map(|format| {
+                if self.unicode {
+                    format!("(?:{})", format.unicode_pattern())
+                } else {
+                    format!("(?:{})", format.pattern())
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("|");
+        Ok(Regex::new(&pattern)?)
+    }
+
+    /// Sets whether identifiers in any format are converted, instead of just those in
+    /// the source format
+    ///
+    /// dot.case and path/case are left out, as they also match file names, member
+    /// accesses and paths.
+    pub fn set_auto_detect(&mut self, auto_detect: bool) {
+        let formats = if auto_detect {
+            CaseFormat::ALL
+                .into_iter()
+                .filter(|&format| {
+                    format != self.to_format
+                        && !matches!(format, CaseFormat::DotCase | CaseFormat::PathCase)
+                })
+                .collect()
+        } else {
+            vec![self.from_format]
+        };
+        self.set_source_formats(formats)
+            .expect("case format patterns are valid");
+    }
+
+    /// Sets whether only the mapping keys of JSON, YAML and TOML files (by extension)
+    /// are converted, leaving their values, comments and layout alone
+    ///
+    /// Other files are converted as usual. A document whose keys can't all be renamed
+    /// in place fails rather than being re-serialized.
+    pub fn set_structured_keys(&mut self, structured_keys: bool) {
+        self.structured_keys = structured_keys;
+    }
+
+    /// Sets whether only the names a file declares (and their uses in that file) are
+    /// converted, leaving names from other code, such as `getElementById`, alone
+    ///
+    /// Files are parsed with tree-sitter and their declarations found with a locals
+    /// query per language (see [`SourceLanguage`]); files in other languages are left
+    /// unchanged. Uses are matched by name across the whole file rather than per
+    /// scope, and comments and strings are left alone. Requires the `declared-only`
+    /// feature.
+    #[cfg(feature = "declared-only")]
+    pub fn set_declared_only(&mut self, declared_only: bool) {
+        self.declared_only = declared_only;
+    }
+
+    /// Sets a regex of words that are never converted, applied after the word filter
+    /// (`^test.*` converts everything but test names)
+    pub fn set_word_exclude_filter(&mut self, pattern: Option<String>) -> crate::Result<()> {
+        self.word_exclude_filter = match pattern {
+            Some(pattern) => Some(Regex::new(&pattern)?),
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Sets identifiers that are never converted, such as `iPhone`, `macOS` or names
+    /// from external APIs
+    pub fn set_excluded_words<I, S>(&mut self, words: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.excluded_words = words.into_iter().map(Into::into).collect();
+    }
+
+    /// Sets the glob patterns files must match, replacing the one given to
+    /// [`new`](Self::new); a file is processed if any of them matches
+    pub fn set_globs<I, S>(&mut self, patterns: I) -> crate::Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.glob_patterns = patterns
+            .into_iter()
+            .map(|pattern| glob::Pattern::new(pattern.as_ref()))
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    /// Sets the filters applied on top of the extension and glob filters
+    pub fn set_filter(&mut self, filter: FilterOptions) {
+        self.filter = filter;
+    }
+
+    /// Sets the receiver of progress events for directory runs
+    pub fn set_progress(&mut self, progress: Progress) {
+        self.progress = progress;
+    }
+
+    /// Sets the filesystem files are read from and written to
+    pub fn set_fs(&mut self, fs: Arc<dyn Fs>) {
+        self.fs = fs;
+    }
+
+    /// Sets the receiver of a [`FileEvent`](crate::FileEvent) for every changed or
+    /// failed file
+    pub fn set_events(&mut self, events: EventSink) {
+        self.events = events;
+    }
+
+    /// Sets the depth limit and symlink handling of recursive walks
+    pub fn set_walk(&mut self, walk: WalkOptions) {
+        self.walk = walk;
+    }
+
     /// Converts a single identifier
     fn convert(&self, name: &str) -> String {
+        if self.excluded_words.contains(name) {
+            return name.to_string();
+        }
+
         let mut processed_name = name.to_string();
 
         // Step 1: Strip prefix if specified
@@ -122,17 +386,37 @@ impl CaseConverter {
                 return name.to_string(); // Return original if filter doesn't match
             }
         }
+        if let Some(ref filter) = self.word_exclude_filter {
+            if filter.is_match(&processed_name) {
+                return name.to_string();
+            }
+        }
 
         // Step 6: Apply case conversion
-        let words = self.from_format.split_words(&processed_name);
+        let from_format = match self.source_formats.as_slice() {
+            [format] => *format,
+            formats => match formats.iter().find(|format| format.matches(&processed_name)) {
+                Some(format) => *format,
+                None => return name.to_string(),
+            },
+        };
+        let mut words = from_format.split_words(&processed_name);
+        if let Some(boundary) = self.digit_boundary {
+            words = boundary.apply(words);
+        }
 
         // Step 7: Add prefix/suffix (existing functionality)
-        self.to_format.join_words(&words, &self.prefix, &self.suffix)
+        self.to_format
+            .join_words_with(&words, &self.prefix, &self.suffix, &self.abbreviations)
     }
 
-    /// Checks if a file matches the glob pattern
+    /// Checks if a file matches any of the glob patterns
     fn matches_glob(&self, filepath: &Path, base_path: &Path) -> bool {
-        if let Some(ref pattern) = self.glob_pattern {
+        if self.glob_patterns.is_empty() {
+            return true;
+        }
+
+        self.glob_patterns.iter().any(|pattern| {
             // Match against the filename
             if let Some(filename) = filepath.file_name() {
                 if pattern.matches(filename.to_string_lossy().as_ref()) {
@@ -141,111 +425,633 @@ impl CaseConverter {
             }
 
             // Also try matching against the full relative path
-            if let Ok(rel_path) = filepath.strip_prefix(base_path) {
-                if pattern.matches_path(rel_path) {
-                    return true;
-                }
-            }
+            filepath
+                .strip_prefix(base_path)
+                .is_ok_and(|rel_path| pattern.matches_path(rel_path))
+        })
+    }
 
-            false
-        } else {
-            true
+    /// Checks if a file should be processed (extension, glob and filters)
+    pub fn should_process(&self, filepath: &Path, base_path: &Path) -> bool {
+        self.skip_reason(filepath, base_path).is_none()
+    }
+
+    /// Returns why a file would be left alone, or `None` if it is processed
+    pub fn skip_reason(&self, filepath: &Path, base_path: &Path) -> Option<SkipReason> {
+        if let Some(reason) = self.filter.extension_skip(filepath, &self.file_extensions) {
+            return Some(reason);
+        }
+        if !self.matches_glob(filepath, base_path) {
+            return Some(SkipReason::Glob);
         }
+        // Only directories below the base count, so `convert build/` still converts
+        let relative = filepath.strip_prefix(base_path).unwrap_or(filepath);
+        self.filter
+            .dir_skip(relative)
+            .or_else(|| self.filter.skip_reason(filepath))
     }
 
-    /// Processes a single file
-    pub fn process_file(&self, filepath: &Path, base_path: &Path) -> crate::Result<()> {
-        // Check file extension
-        let extension = filepath
-            .extension()
-            .and_then(|e| e.to_str())
-            .map(|e| format!(".{}", e));
-
-        if let Some(ext) = extension {
-            if !self.file_extensions.contains(&ext) {
-                return Ok(());
+    /// Converts one identifier, the way it would be converted in a file
+    ///
+    /// Returns it unchanged unless it is entirely in a source format.
+    ///
+    /// ```
+    /// use refmt_core::{CaseConverter, CaseFormat};
+    ///
+    /// let converter = CaseConverter::new(
+    ///     CaseFormat::CamelCase, CaseFormat::SnakeCase, None, false, false,
+    ///     String::new(), String::new(), None, None, None, None, None, None, None, None,
+    /// )?;
+    /// assert_eq!(converter.convert_identifier("getUserName"), "get_user_name");
+    /// assert_eq!(converter.convert_identifier("get user"), "get user");
+    /// # Ok::<(), refmt_core::Error>(())
+    /// ```
+    pub fn convert_identifier(&self, name: &str) -> String {
+        let in_source_format = (self.unicode || name.is_ascii())
+            && self.source_formats.iter().any(|format| format.matches(name));
+        if !in_source_format {
+            return name.to_string();
+        }
+        self.convert(name)
+    }
+
+    /// Converts all identifiers in the source format found in `content`
+    ///
+    /// Returns the input borrowed when no identifier changes.
+    pub fn convert_text<'a>(&self, content: &'a str) -> Cow<'a, str> {
+        self.convert_counted(content).0
+    }
+
+    /// Converts identifiers, also returning how many were changed
+    pub(crate) fn convert_counted<'a>(&self, content: &'a str) -> (Cow<'a, str>, usize) {
+        self.convert_where(content, |_| true)
+    }
+
+    /// Converts the content of the file at `path` in the configured mode: keys only,
+    /// declared names only, or every identifier
+    pub(crate) fn convert_content<'a>(
+        &self,
+        path: &Path,
+        content: &'a str,
+    ) -> crate::Result<(Cow<'a, str>, usize)> {
+        if self.structured_keys {
+            if let Some(format) = KeyFormat::from_path(path) {
+                return self.convert_keys(content, format);
             }
-        } else {
-            return Ok(());
         }
+        #[cfg(feature = "declared-only")]
+        if self.declared_only {
+            let Some(language) = SourceLanguage::from_path(path) else {
+                return Ok((Cow::Borrowed(content), 0));
+            };
+            let ranges = declarations::declared_references(content, language);
+            return Ok(self.convert_ranges(content, &ranges));
+        }
+        Ok(self.convert_counted(content))
+    }
 
-        // Check glob pattern
-        if !self.matches_glob(filepath, base_path) {
-            return Ok(());
+    /// Converts the identifiers at `ranges`, which are in order, leaving the rest of
+    /// `content` as it is
+    #[cfg(feature = "declared-only")]
+    fn convert_ranges<'a>(
+        &self,
+        content: &'a str,
+        ranges: &[Range<usize>],
+    ) -> (Cow<'a, str>, usize) {
+        let mut converted = String::new();
+        let mut changes = 0;
+        let mut end = 0;
+        for range in ranges {
+            let (identifier, count) = self.convert_counted(&content[range.clone()]);
+            if count > 0 {
+                converted.push_str(&content[end..range.start]);
+                converted.push_str(&identifier);
+                end = range.end;
+                changes += count;
+            }
+        }
+
+        if changes == 0 {
+            return (Cow::Borrowed(content), 0);
+        }
+        converted.push_str(&content[end..]);
+        (Cow::Owned(converted), changes)
+    }
+
+    /// Converts the identifiers accepted by `accept`
+    fn convert_where<'a>(
+        &self,
+        content: &'a str,
+        accept: impl Fn(&str) -> bool,
+    ) -> (Cow<'a, str>, usize) {
+        // Skip the regex scan when the source format cannot possibly match
+        // The byte-level check only knows ASCII letters
+        let non_ascii = self.unicode && !content.is_ascii();
+        if !non_ascii && !self.source_formats.iter().any(|format| format.may_match(content)) {
+            return (Cow::Borrowed(content), 0);
+        }
+
+        let mut changes = 0;
+        let converted = self
+            .source_pattern
+            .replace_all(content, |caps: &regex::Captures| {
+                if !accept(&caps[0]) {
+                    return caps[0].to_string();
+                }
+                let converted = self.convert(&caps[0]);
+                if converted != caps[0] {
+                    changes += 1;
+                }
+                converted
+            });
+
+        // Matches that convert to themselves still produce an owned copy
+        if changes == 0 {
+            return (Cow::Borrowed(content), 0);
+        }
+        (converted, changes)
+    }
+
+    /// Converts the identifiers in the keys of a structured document, keeping the
+    /// rest of it as written
+    pub fn convert_keys<'a>(
+        &self,
+        content: &'a str,
+        format: KeyFormat,
+    ) -> crate::Result<(Cow<'a, str>, usize)> {
+        keys::rename_in_place(content, format, &|key| self.convert_text(key).into_owned())
+    }
+
+    /// Processes a single file
+    pub fn process_file(&self, filepath: &Path, base_path: &Path) -> crate::Result<FileOutcome> {
+        let (outcome, diff) = self.apply_to_file(filepath, base_path, None)?;
+        self.report(filepath, outcome, diff.as_deref());
+        Ok(outcome)
+    }
+
+    /// Converts a file without reporting, so it can run on a worker thread.
+    ///
+    /// Changed heading anchors in Markdown files are added to `anchor_changes`.
+    fn apply_to_file(
+        &self,
+        filepath: &Path,
+        base_path: &Path,
+        anchor_changes: Option<&Mutex<AnchorChanges>>,
+    ) -> crate::Result<(FileOutcome, Option<String>)> {
+        if let Some(reason) = self.skip_reason(filepath, base_path) {
+            return Ok((FileOutcome::skipped(reason), None));
         }
 
         // Read file content
-        let content = fs::read_to_string(filepath)?;
-
-        // Replace all matches of the source pattern
-        let modified_content = self.source_pattern.replace_all(&content, |caps: &regex::Captures| {
-            self.convert(&caps[0])
-        });
-
-        if content != modified_content {
-            if self.dry_run {
-                println!("Would convert '{}'", filepath.display());
-            } else {
-                fs::write(filepath, modified_content.as_ref())?;
-                println!("Converted '{}'", filepath.display());
+        let Some(content) = read_text(self.fs.as_ref(), filepath)? else {
+            return Ok((FileOutcome::skipped(SkipReason::Binary), None));
+        };
+
+        // Replace the matches of the source pattern
+        let (modified_content, changes) = self.convert_content(filepath, &content)?;
+
+        if changes == 0 {
+            return Ok((FileOutcome::unchanged(), None));
+        }
+
+        if let Some(anchor_changes) = anchor_changes {
+            if anchors::is_markdown(filepath) {
+                anchor_changes
+                    .lock()
+                    .unwrap()
+                    .record(filepath, &content, &modified_content);
             }
-        } else if !self.dry_run {
-            println!("No changes needed in '{}'", filepath.display());
         }
 
-        Ok(())
+        let diff = self
+            .show_diff
+            .then(|| unified_diff(filepath, &content, &modified_content, self.color_diff));
+        if !self.dry_run {
+            self.fs.write(filepath, modified_content.as_bytes())?;
+        }
+
+        Ok((FileOutcome::changed(changes), diff))
     }
 
-    /// Processes a directory or file
-    pub fn process_directory(&self, directory_path: &Path) -> crate::Result<()> {
-        if !directory_path.exists() {
-            eprintln!("Path '{}' does not exist.", directory_path.display());
-            return Ok(());
+    pub(crate) fn report(&self, filepath: &Path, outcome: FileOutcome, diff: Option<&str>) {
+        match outcome.skipped {
+            Some(reason) if self.filter.reports(reason) => {
+                report!("Skipped {} '{}'", reason, filepath.display());
+            }
+            Some(_) => {}
+            None if outcome.changed => {
+                self.events.file_changed(filepath, outcome.changes, self.dry_run);
+                if self.dry_run {
+                    report!("Would convert '{}'", filepath.display());
+                } else {
+                    report!("Converted '{}'", filepath.display());
+                }
+                if let Some(diff) = diff {
+                    report!("{}", diff.trim_end());
+                }
+            }
+            None if !self.dry_run => {
+                report!("No changes needed in '{}'", filepath.display());
+            }
+            None => {}
+        }
+    }
+
+    /// Processes a directory or file and returns what was converted
+    pub fn process_directory(&self, directory_path: &Path) -> crate::Result<ConversionReport> {
+        let mut conversion = ConversionReport::default();
+        if !self.fs.exists(directory_path) {
+            warn!("Path '{}' does not exist.", directory_path.display());
+            return Ok(conversion);
         }
 
+        let anchor_changes = Mutex::new(AnchorChanges::new());
+        let record = self.update_anchors.then_some(&anchor_changes);
+
         // If it's a single file, process it directly
-        if directory_path.is_file() {
-            if let Some(parent) = directory_path.parent() {
-                self.process_file(directory_path, parent)?;
-            } else {
-                self.process_file(directory_path, Path::new("."))?;
-            }
-            return Ok(());
+        if self.fs.is_file(directory_path) {
+            let base_path = directory_path.parent().unwrap_or(Path::new("."));
+            let (outcome, diff) = self.apply_to_file(directory_path, base_path, record)?;
+            self.report(directory_path, outcome, diff.as_deref());
+            conversion.record(directory_path, outcome);
+
+            let files = [directory_path.to_path_buf()];
+            let anchor_changes = anchor_changes.into_inner().unwrap();
+            anchor_changes.apply(self.fs.as_ref(), &files, self.dry_run, &self.events)?;
+            return Ok(conversion);
         }
 
         // Otherwise, process directory
-        if !directory_path.is_dir() {
-            eprintln!("Path '{}' is not a directory or file.", directory_path.display());
-            return Ok(());
+        if !self.fs.is_dir(directory_path) {
+            warn!("Path '{}' is not a directory or file.", directory_path.display());
+            return Ok(conversion);
         }
 
-        if self.recursive {
-            for entry in WalkDir::new(directory_path).into_iter().filter_map(|e| e.ok()) {
-                if entry.file_type().is_file() {
-                    if let Err(e) = self.process_file(entry.path(), directory_path) {
-                        eprintln!("Error processing file '{}': {}", entry.path().display(), e);
-                    }
+        let walk = self.fs.walk(directory_path, self.recursive, self.walk)?;
+        let files = walk.files;
+        conversion.unreadable = walk.errors;
+        let results = parallel::map_files_with_progress(
+            self.fs.as_ref(),
+            &files,
+            &self.progress,
+            |path| self.should_process(path, directory_path),
+            |path| self.apply_to_file(path, directory_path, record),
+        );
+
+        // Report in path order once the parallel work is done
+        for (path, result) in files.iter().zip(results) {
+            match result {
+                Ok((outcome, diff)) => {
+                    self.report(path, outcome, diff.as_deref());
+                    conversion.record(path, outcome);
                 }
-            }
-        } else {
-            for entry in fs::read_dir(directory_path)? {
-                let entry = entry?;
-                let path = entry.path();
-                if path.is_file() {
-                    if let Err(e) = self.process_file(&path, directory_path) {
-                        eprintln!("Error processing file '{}': {}", path.display(), e);
+                Err(e) => {
+                    conversion.files_scanned += 1;
+                    match report::file_failed(path, e, self.walk.errors) {
+                        Ok(failed) => {
+                            self.events.file_failed(&failed);
+                            conversion.failed.push(failed);
+                        }
+                        Err(e) => {
+                            error!("Error processing file '{}': {}", path.display(), e);
+                            conversion.failed.push(FailedFile {
+                                path: path.clone(),
+                                message: format!("{:#}", e),
+                            });
+                        }
                     }
                 }
             }
         }
 
-        Ok(())
+        // Fix links to headings whose anchors changed
+        let anchor_changes = anchor_changes.into_inner().unwrap();
+        anchor_changes.apply(self.fs.as_ref(), &files, self.dry_run, &self.events)?;
+
+        Ok(conversion)
+    }
+}
+
+/// Builds a [`CaseConverter`] option by option, starting from the defaults of
+/// [`CaseConverter::new`]
+///
+/// ```
+/// use refmt_core::{Abbreviations, CaseConverter, CaseFormat};
+///
+/// let converter = CaseConverter::builder(CaseFormat::SnakeCase, CaseFormat::CamelCase)
+///     .abbreviations(Abbreviations::new(["ID", "URL"]))
+///     .build()
+///     .unwrap();
+/// assert_eq!(converter.convert_identifier("user_id"), "userID");
+/// ```
+#[derive(Clone)]
+pub struct CaseConverterBuilder {
+    from_format: CaseFormat,
+    to_format: CaseFormat,
+    file_extensions: Option<Vec<String>>,
+    recursive: bool,
+    walk: WalkOptions,
+    dry_run: bool,
+    prefix: String,
+    suffix: String,
+    strip_prefix: Option<String>,
+    strip_suffix: Option<String>,
+    replace_prefix: Option<(String, String)>,
+    replace_suffix: Option<(String, String)>,
+    globs: Vec<String>,
+    word_filter: Option<String>,
+    word_exclude_filter: Option<String>,
+    excluded_words: Vec<String>,
+    source_formats: Option<Vec<CaseFormat>>,
+    auto_detect: bool,
+    unicode: bool,
+    digit_boundary: Option<DigitBoundary>,
+    abbreviations: Abbreviations,
+    structured_keys: bool,
+    #[cfg(feature = "declared-only")]
+    declared_only: bool,
+    update_anchors: bool,
+    show_diff: bool,
+    color_diff: bool,
+    filter: FilterOptions,
+    progress: Progress,
+    fs: Arc<dyn Fs>,
+    events: EventSink,
+}
+
+impl CaseConverterBuilder {
+    /// Starts a converter from `from_format` to `to_format`
+    pub fn new(from_format: CaseFormat, to_format: CaseFormat) -> Self {
+        CaseConverterBuilder {
+            from_format,
+            to_format,
+            file_extensions: None,
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            prefix: String::new(),
+            suffix: String::new(),
+            strip_prefix: None,
+            strip_suffix: None,
+            replace_prefix: None,
+            replace_suffix: None,
+            globs: Vec::new(),
+            word_filter: None,
+            word_exclude_filter: None,
+            excluded_words: Vec::new(),
+            source_formats: None,
+            auto_detect: false,
+            unicode: false,
+            digit_boundary: None,
+            abbreviations: Abbreviations::default(),
+            structured_keys: false,
+            #[cfg(feature = "declared-only")]
+            declared_only: false,
+            update_anchors: true,
+            show_diff: false,
+            color_diff: false,
+            filter: FilterOptions::default(),
+            progress: Progress::default(),
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+
+    /// File extensions to process, replacing the `convert` defaults
+    pub fn extensions(mut self, extensions: Vec<String>) -> Self {
+        self.file_extensions = Some(extensions);
+        self
+    }
+
+    /// Whether directories are processed recursively (the default)
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// See [`CaseConverter::set_walk`]
+    pub fn walk(mut self, walk: WalkOptions) -> Self {
+        self.walk = walk;
+        self
+    }
+
+    /// Whether files are left unchanged
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Prefix added to converted identifiers
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Suffix added to converted identifiers
+    pub fn suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.suffix = suffix.into();
+        self
+    }
+
+    /// Prefix removed from identifiers before conversion
+    pub fn strip_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.strip_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Suffix removed from identifiers before conversion
+    pub fn strip_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.strip_suffix = Some(suffix.into());
+        self
+    }
+
+    /// Prefix `from` replaced by `to` before conversion
+    pub fn replace_prefix(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.replace_prefix = Some((from.into(), to.into()));
+        self
+    }
+
+    /// Suffix `from` replaced by `to` before conversion
+    pub fn replace_suffix(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.replace_suffix = Some((from.into(), to.into()));
+        self
+    }
+
+    /// Glob patterns files must match; see [`CaseConverter::set_globs`]
+    pub fn globs<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.globs = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Regex identifiers must match to be converted
+    pub fn word_filter(mut self, pattern: impl Into<String>) -> Self {
+        self.word_filter = Some(pattern.into());
+        self
+    }
+
+    /// See [`CaseConverter::set_word_exclude_filter`]
+    pub fn word_exclude_filter(mut self, pattern: impl Into<String>) -> Self {
+        self.word_exclude_filter = Some(pattern.into());
+        self
+    }
+
+    /// See [`CaseConverter::set_excluded_words`]
+    pub fn excluded_words<I, S>(mut self, words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.excluded_words = words.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// See [`CaseConverter::set_source_formats`]
+    pub fn source_formats(mut self, formats: Vec<CaseFormat>) -> Self {
+        self.source_formats = Some(formats);
+        self
+    }
+
+    /// See [`CaseConverter::set_auto_detect`]; takes precedence over
+    /// [`source_formats`](Self::source_formats)
+    pub fn auto_detect(mut self, auto_detect: bool) -> Self {
+        self.auto_detect = auto_detect;
+        self
+    }
+
+    /// See [`CaseConverter::set_unicode`]
+    pub fn unicode(mut self, unicode: bool) -> Self {
+        self.unicode = unicode;
+        self
+    }
+
+    /// See [`CaseConverter::set_digit_boundary`]
+    pub fn digit_boundary(mut self, digit_boundary: Option<DigitBoundary>) -> Self {
+        self.digit_boundary = digit_boundary;
+        self
+    }
+
+    /// See [`CaseConverter::set_abbreviations`]
+    pub fn abbreviations(mut self, abbreviations: Abbreviations) -> Self {
+        self.abbreviations = abbreviations;
+        self
+    }
+
+    /// See [`CaseConverter::set_structured_keys`]
+    pub fn structured_keys(mut self, structured_keys: bool) -> Self {
+        self.structured_keys = structured_keys;
+        self
+    }
+
+    /// See [`CaseConverter::set_declared_only`]
+    #[cfg(feature = "declared-only")]
+    pub fn declared_only(mut self, declared_only: bool) -> Self {
+        self.declared_only = declared_only;
+        self
+    }
+
+    /// See [`CaseConverter::set_update_anchors`]
+    pub fn update_anchors(mut self, update_anchors: bool) -> Self {
+        self.update_anchors = update_anchors;
+        self
+    }
+
+    /// See [`CaseConverter::set_show_diff`]
+    pub fn show_diff(mut self, show_diff: bool) -> Self {
+        self.show_diff = show_diff;
+        self
+    }
+
+    /// See [`CaseConverter::set_color_diff`]
+    pub fn color_diff(mut self, color_diff: bool) -> Self {
+        self.color_diff = color_diff;
+        self
+    }
+
+    /// See [`CaseConverter::set_filter`]
+    pub fn filter(mut self, filter: FilterOptions) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// See [`CaseConverter::set_progress`]
+    pub fn progress(mut self, progress: Progress) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// See [`CaseConverter::set_fs`]
+    pub fn fs(mut self, fs: Arc<dyn Fs>) -> Self {
+        self.fs = fs;
+        self
+    }
+
+    /// See [`CaseConverter::set_events`]
+    pub fn events(mut self, events: EventSink) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Compiles the converter, failing on an invalid regex or glob pattern or an empty
+    /// list of source formats
+    pub fn build(self) -> crate::Result<CaseConverter> {
+        let (replace_prefix_from, replace_prefix_to) = self.replace_prefix.unzip();
+        let (replace_suffix_from, replace_suffix_to) = self.replace_suffix.unzip();
+        let mut converter = CaseConverter::new(
+            self.from_format,
+            self.to_format,
+            self.file_extensions,
+            self.recursive,
+            self.dry_run,
+            self.prefix,
+            self.suffix,
+            self.strip_prefix,
+            self.strip_suffix,
+            replace_prefix_from,
+            replace_prefix_to,
+            replace_suffix_from,
+            replace_suffix_to,
+            None,
+            self.word_filter,
+        )?;
+        converter.set_globs(self.globs)?;
+        converter.set_word_exclude_filter(self.word_exclude_filter)?;
+        converter.set_excluded_words(self.excluded_words);
+        converter.set_unicode(self.unicode);
+        if self.auto_detect {
+            converter.set_auto_detect(true);
+        } else if let Some(formats) = self.source_formats {
+            converter.set_source_formats(formats)?;
+        }
+        converter.set_digit_boundary(self.digit_boundary);
+        converter.set_abbreviations(self.abbreviations);
+        converter.set_structured_keys(self.structured_keys);
+        #[cfg(feature = "declared-only")]
+        converter.set_declared_only(self.declared_only);
+        converter.set_update_anchors(self.update_anchors);
+        converter.set_show_diff(self.show_diff);
+        converter.set_color_diff(self.color_diff);
+        converter.set_filter(self.filter);
+        converter.set_progress(self.progress);
+        converter.set_fs(self.fs);
+        converter.set_events(self.events);
+        converter.set_walk(self.walk);
+        Ok(converter)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
+
+    /// A converter between two formats with default values for every other option
+    fn converter(from: CaseFormat, to: CaseFormat) -> CaseConverter {
+        CaseConverter::builder(from, to).build().unwrap()
+    }
 
     #[test]
     fn test_camel_to_snake() {
@@ -313,4 +1119,206 @@ mod tests {
         assert!(!pattern.is_match("firstname"));
         assert!(!pattern.is_match("FIRST_NAME")); // SCREAMING_SNAKE_CASE
     }
+
+    #[test]
+    fn test_convert_text_unchanged_is_borrowed() {
+        let converter = converter(CaseFormat::CamelCase, CaseFormat::SnakeCase);
+
+        assert!(matches!(converter.convert_text("no_camel here\n"), Cow::Borrowed(_)));
+        assert_eq!(converter.convert_text("let myValue = 1;"), "let my_value = 1;");
+
+        let test_dir = std::env::temp_dir().join("refmt_converter_outcome");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+        let changed = test_dir.join("changed.js");
+        let unchanged = test_dir.join("unchanged.js");
+        fs::write(&changed, "let myValue = otherValue;\n").unwrap();
+        fs::write(&unchanged, "let value = 1;\n").unwrap();
+
+        assert_eq!(
+            converter.process_file(&changed, &test_dir).unwrap(),
+            FileOutcome::changed(2)
+        );
+        assert_eq!(
+            converter.process_file(&unchanged, &test_dir).unwrap(),
+            FileOutcome::unchanged()
+        );
+        assert_eq!(fs::read_to_string(&changed).unwrap(), "let my_value = other_value;\n");
+
+        fs::write(&changed, "let myValue = otherValue;\n").unwrap();
+        fs::write(test_dir.join("notes.bin"), "myValue").unwrap();
+        let report = converter.process_directory(&test_dir).unwrap();
+        assert_eq!(report.files_scanned, 3);
+        assert_eq!(report.converted, [(changed.clone(), 2)]);
+        assert_eq!(report.identifiers_converted(), 2);
+        assert_eq!(report.skipped, [(test_dir.join("notes.bin"), SkipReason::Extension)]);
+        assert!(report.failed.is_empty());
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_auto_detect() {
+        let mut converter = converter(CaseFormat::CamelCase, CaseFormat::SnakeCase);
+        converter.set_auto_detect(true);
+
+        let text = "userName UserId MAX_SIZE retry-count Content-Type Put_Line already_snake";
+        assert_eq!(
+            converter.convert_text(text),
+            "user_name user_id max_size retry_count content_type put_line already_snake"
+        );
+        // dot.case and path/case are never guessed
+        assert!(matches!(converter.convert_text("config.json src/main"), Cow::Borrowed(_)));
+
+        converter.set_auto_detect(false);
+        assert_eq!(converter.convert_text("userName MAX_SIZE"), "user_name MAX_SIZE");
+    }
+
+    #[test]
+    fn test_source_formats() {
+        let mut converter = converter(CaseFormat::CamelCase, CaseFormat::SnakeCase);
+        converter
+            .set_source_formats(vec![CaseFormat::CamelCase, CaseFormat::PascalCase])
+            .unwrap();
+        assert_eq!(
+            converter.convert_text("userName UserId MAX_SIZE"),
+            "user_name user_id MAX_SIZE"
+        );
+        assert!(converter.set_source_formats(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_unicode() {
+        let mut converter = converter(CaseFormat::CamelCase, CaseFormat::SnakeCase);
+        assert_eq!(converter.convert_text("größeZahl userName"), "größeZahl user_name");
+        converter.set_unicode(true);
+        assert_eq!(converter.convert_text("größeZahl userName"), "größe_zahl user_name");
+        assert_eq!(converter.convert_text("naïveValue"), "naïve_value");
+    }
+
+    #[test]
+    fn test_digit_boundary() {
+        let mut converter = converter(CaseFormat::CamelCase, CaseFormat::SnakeCase);
+        assert_eq!(converter.convert_text("sha256Hash"), "sha256_hash");
+        converter.set_digit_boundary(Some(DigitBoundary::Separate));
+        assert_eq!(converter.convert_text("sha256Hash user2Name"), "sha_256_hash user_2_name");
+    }
+
+    #[test]
+    fn test_abbreviations() {
+        let mut converter = converter(CaseFormat::SnakeCase, CaseFormat::CamelCase);
+        assert_eq!(converter.convert_text("user_id db_url"), "userId dbUrl");
+        converter.set_abbreviations(Abbreviations::new(["ID", "URL"]));
+        assert_eq!(converter.convert_text("user_id db_url"), "userID dbURL");
+    }
+
+    #[test]
+    fn test_builder() {
+        let converter = CaseConverter::builder(CaseFormat::SnakeCase, CaseFormat::CamelCase)
+            .strip_prefix("m_")
+            .replace_suffix("_ptr", "_ref")
+            .prefix("the_")
+            .abbreviations(Abbreviations::new(["ID"]))
+            .build()
+            .unwrap();
+        assert_eq!(converter.convert_text("m_user_id"), "the_userID");
+        assert_eq!(converter.convert_text("user_ptr"), "the_userRef");
+
+        let converter = CaseConverter::builder(CaseFormat::CamelCase, CaseFormat::SnakeCase)
+            .auto_detect(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            converter.convert_text("userName MAX_SIZE"),
+            "user_name max_size"
+        );
+
+        let invalid = CaseConverter::builder(CaseFormat::CamelCase, CaseFormat::SnakeCase)
+            .source_formats(Vec::new())
+            .build();
+        assert!(invalid.is_err());
+        let invalid = CaseConverter::builder(CaseFormat::CamelCase, CaseFormat::SnakeCase)
+            .globs(["[a-"])
+            .build();
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "declared-only")]
+    fn test_declared_only() {
+        let mut converter = converter(CaseFormat::CamelCase, CaseFormat::SnakeCase);
+        converter.set_declared_only(true);
+
+        let content = "function showUser(userId) {\n  const userName = document.getElementById(userId);\n}\n";
+        let (result, changes) = converter
+            .convert_content(Path::new("app.js"), content)
+            .unwrap();
+        assert_eq!(
+            result,
+            "function show_user(user_id) {\n  const user_name = document.getElementById(user_id);\n}\n"
+        );
+        assert_eq!(changes, 4);
+
+        // Comments and strings aren't references
+        let content = "def loadUser(userId):\n    # userId may be None\n    return api.fetchById(userId, 'userId')\n";
+        let (result, _) = converter
+            .convert_content(Path::new("app.py"), content)
+            .unwrap();
+        assert_eq!(
+            result,
+            "def load_user(user_id):\n    # userId may be None\n    return api.fetchById(user_id, 'userId')\n"
+        );
+
+        // Declarations of unknown languages aren't recognized
+        let (result, _) = converter
+            .convert_content(Path::new("notes.md"), "userName")
+            .unwrap();
+        assert!(matches!(result, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_excluded_words() {
+        let mut converter = converter(CaseFormat::CamelCase, CaseFormat::SnakeCase);
+        converter.set_excluded_words(["iPhone", "getElementById"]);
+        assert_eq!(
+            converter.convert_text("iPhone getElementById userName"),
+            "iPhone getElementById user_name"
+        );
+    }
+
+    #[test]
+    fn test_word_exclude_filter() {
+        let mut converter = CaseConverter::builder(CaseFormat::CamelCase, CaseFormat::SnakeCase)
+            .word_filter("^get")
+            .word_exclude_filter("Id$")
+            .build()
+            .unwrap();
+        assert_eq!(
+            converter.convert_text("getName getUserId userName"),
+            "get_name getUserId userName"
+        );
+        assert!(converter.set_word_exclude_filter(Some("(".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_multiple_globs() {
+        let mut converter = CaseConverter::builder(CaseFormat::CamelCase, CaseFormat::SnakeCase)
+            .extensions(vec![".py".to_string(), ".md".to_string(), ".js".to_string()])
+            .globs(["*.py"])
+            .build()
+            .unwrap();
+        let base = Path::new("project");
+        assert!(converter.should_process(Path::new("project/src/app.py"), base));
+        assert!(!converter.should_process(Path::new("project/docs/guide.md"), base));
+
+        converter.set_globs(["*.py", "docs/*.md"]).unwrap();
+        assert!(converter.should_process(Path::new("project/src/app.py"), base));
+        assert!(converter.should_process(Path::new("project/docs/guide.md"), base));
+        assert!(!converter.should_process(Path::new("project/README.md"), base));
+        assert!(!converter.should_process(Path::new("project/src/app.js"), base));
+
+        converter.set_globs(Vec::<String>::new()).unwrap();
+        assert!(converter.should_process(Path::new("project/src/app.js"), base));
+        assert!(converter.set_globs(["[a-"]).is_err());
+    }
 }
@@ -0,0 +1,379 @@
+//! Names declared in source files
+//!
+//! Parses a file with tree-sitter and runs a locals query for its language: every
+//! `@local.definition` capture (a function, type, variable, parameter or field name) is
+//! a name the file declares. A conversion limited to those names, and the identifiers
+//! with the same text elsewhere in the file, leaves the names of the libraries it
+//! calls alone.
+//!
+//! Scopes are not resolved: a name declared anywhere in the file matches every
+//! identifier spelled the same, including a property of a library object that happens
+//! to share it. Comments, strings and macro bodies are not identifiers and are never
+//! matched.
+
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Language, Node, Parser, Query, QueryCursor, Tree};
+
+/// Languages whose declarations are recognized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceLanguage {
+    /// C and C++
+    C,
+    Go,
+    Java,
+    /// JavaScript and TypeScript
+    JavaScript,
+    Python,
+    Rust,
+}
+
+impl SourceLanguage {
+    /// Detects the language from a file's extension
+    pub fn from_path(path: &Path) -> Option<SourceLanguage> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => Some(SourceLanguage::C),
+            "go" => Some(SourceLanguage::Go),
+            "java" => Some(SourceLanguage::Java),
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" => Some(SourceLanguage::JavaScript),
+            "py" | "pyi" => Some(SourceLanguage::Python),
+            "rs" => Some(SourceLanguage::Rust),
+            _ => None,
+        }
+    }
+
+    /// The grammar files are parsed with; C is parsed as C++ and JavaScript as TSX,
+    /// the supersets of both
+    fn grammar(self) -> Language {
+        match self {
+            SourceLanguage::C => tree_sitter_cpp::LANGUAGE.into(),
+            SourceLanguage::Go => tree_sitter_go::LANGUAGE.into(),
+            SourceLanguage::Java => tree_sitter_java::LANGUAGE.into(),
+            SourceLanguage::JavaScript => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            SourceLanguage::Python => tree_sitter_python::LANGUAGE.into(),
+            SourceLanguage::Rust => tree_sitter_rust::LANGUAGE.into(),
+        }
+    }
+
+    fn locals(self) -> &'static Query {
+        match self {
+            SourceLanguage::C => &C,
+            SourceLanguage::Go => &GO,
+            SourceLanguage::Java => &JAVA,
+            SourceLanguage::JavaScript => &JAVASCRIPT,
+            SourceLanguage::Python => &PYTHON,
+            SourceLanguage::Rust => &RUST,
+        }
+    }
+}
+
+fn locals_query(language: SourceLanguage, source: &str) -> Query {
+    Query::new(&language.grammar(), source).expect("locals queries are valid")
+}
+
+static C: LazyLock<Query> = LazyLock::new(|| {
+    locals_query(
+        SourceLanguage::C,
+        r#"
+        (preproc_def name: (identifier) @local.definition)
+        (preproc_function_def name: (identifier) @local.definition)
+        (declaration declarator: (identifier) @local.definition)
+        (init_declarator declarator: (identifier) @local.definition)
+        (pointer_declarator declarator: [(identifier) (field_identifier)] @local.definition)
+        (reference_declarator [(identifier) (field_identifier)] @local.definition)
+        (array_declarator declarator: [(identifier) (field_identifier)] @local.definition)
+        (function_declarator declarator: [(identifier) (field_identifier)] @local.definition)
+        (parameter_declaration declarator: (identifier) @local.definition)
+        (optional_parameter_declaration declarator: (identifier) @local.definition)
+        (field_declaration declarator: (field_identifier) @local.definition)
+        (for_range_loop declarator: (identifier) @local.definition)
+        (type_definition declarator: (type_identifier) @local.definition)
+        (struct_specifier name: (type_identifier) @local.definition body: (_))
+        (class_specifier name: (type_identifier) @local.definition body: (_))
+        (union_specifier name: (type_identifier) @local.definition body: (_))
+        (enum_specifier name: (type_identifier) @local.definition body: (_))
+        (enumerator name: (identifier) @local.definition)
+        (namespace_definition name: (namespace_identifier) @local.definition)
+        "#,
+    )
+});
+
+static GO: LazyLock<Query> = LazyLock::new(|| {
+    locals_query(
+        SourceLanguage::Go,
+        r#"
+        (function_declaration name: (identifier) @local.definition)
+        (method_declaration name: (field_identifier) @local.definition)
+        (parameter_declaration name: (identifier) @local.definition)
+        (variadic_parameter_declaration name: (identifier) @local.definition)
+        (var_spec name: (identifier) @local.definition)
+        (const_spec name: (identifier) @local.definition)
+        (type_spec name: (type_identifier) @local.definition)
+        (short_var_declaration left: (expression_list (identifier) @local.definition))
+        (range_clause left: (expression_list (identifier) @local.definition))
+        (field_declaration name: (field_identifier) @local.definition)
+        (import_spec name: (package_identifier) @local.definition)
+        "#,
+    )
+});
+
+static JAVA: LazyLock<Query> = LazyLock::new(|| {
+    locals_query(
+        SourceLanguage::Java,
+        r#"
+        (class_declaration name: (identifier) @local.definition)
+        (interface_declaration name: (identifier) @local.definition)
+        (enum_declaration name: (identifier) @local.definition)
+        (record_declaration name: (identifier) @local.definition)
+        (annotation_type_declaration name: (identifier) @local.definition)
+        (enum_constant name: (identifier) @local.definition)
+        (method_declaration name: (identifier) @local.definition)
+        (variable_declarator name: (identifier) @local.definition)
+        (formal_parameter name: (identifier) @local.definition)
+        (catch_formal_parameter name: (identifier) @local.definition)
+        (enhanced_for_statement name: (identifier) @local.definition)
+        (resource name: (identifier) @local.definition)
+        (lambda_expression parameters: (identifier) @local.definition)
+        (inferred_parameters (identifier) @local.definition)
+        "#,
+    )
+});
+
+static JAVASCRIPT: LazyLock<Query> = LazyLock::new(|| {
+    locals_query(
+        SourceLanguage::JavaScript,
+        r#"
+        (variable_declarator name: (identifier) @local.definition)
+        (object_pattern (shorthand_property_identifier_pattern) @local.definition)
+        (pair_pattern value: (identifier) @local.definition)
+        (array_pattern (identifier) @local.definition)
+        (function_declaration name: (identifier) @local.definition)
+        (generator_function_declaration name: (identifier) @local.definition)
+        (function_expression name: (identifier) @local.definition)
+        (class_declaration name: (type_identifier) @local.definition)
+        (abstract_class_declaration name: (type_identifier) @local.definition)
+        (interface_declaration name: (type_identifier) @local.definition)
+        (type_alias_declaration name: (type_identifier) @local.definition)
+        (enum_declaration name: (identifier) @local.definition)
+        (internal_module name: (identifier) @local.definition)
+        (method_definition name: (property_identifier) @local.definition)
+        (public_field_definition name: (property_identifier) @local.definition)
+        (required_parameter pattern: (identifier) @local.definition)
+        (optional_parameter pattern: (identifier) @local.definition)
+        (rest_pattern (identifier) @local.definition)
+        (assignment_pattern left: (identifier) @local.definition)
+        (arrow_function parameter: (identifier) @local.definition)
+        (catch_clause parameter: (identifier) @local.definition)
+        (import_specifier alias: (identifier) @local.definition)
+        (namespace_import (identifier) @local.definition)
+        (assignment_expression
+          left: (member_expression object: (this) property: (property_identifier) @local.definition))
+        "#,
+    )
+});
+
+static PYTHON: LazyLock<Query> = LazyLock::new(|| {
+    locals_query(
+        SourceLanguage::Python,
+        r#"
+        (function_definition name: (identifier) @local.definition)
+        (class_definition name: (identifier) @local.definition)
+        (parameters (identifier) @local.definition)
+        (lambda_parameters (identifier) @local.definition)
+        (default_parameter name: (identifier) @local.definition)
+        (typed_parameter (identifier) @local.definition)
+        (typed_default_parameter name: (identifier) @local.definition)
+        (list_splat_pattern (identifier) @local.definition)
+        (dictionary_splat_pattern (identifier) @local.definition)
+        (assignment left: (identifier) @local.definition)
+        (assignment
+          left: (attribute object: (identifier) @_self attribute: (identifier) @local.definition)
+          (#eq? @_self "self"))
+        (pattern_list (identifier) @local.definition)
+        (tuple_pattern (identifier) @local.definition)
+        (for_statement left: (identifier) @local.definition)
+        (for_in_clause left: (identifier) @local.definition)
+        (aliased_import alias: (identifier) @local.definition)
+        (as_pattern_target (identifier) @local.definition)
+        "#,
+    )
+});
+
+static RUST: LazyLock<Query> = LazyLock::new(|| {
+    locals_query(
+        SourceLanguage::Rust,
+        r#"
+        (function_item name: (identifier) @local.definition)
+        (function_signature_item name: (identifier) @local.definition)
+        (struct_item name: (type_identifier) @local.definition)
+        (enum_item name: (type_identifier) @local.definition)
+        (union_item name: (type_identifier) @local.definition)
+        (trait_item name: (type_identifier) @local.definition)
+        (type_item name: (type_identifier) @local.definition)
+        (mod_item name: (identifier) @local.definition)
+        (const_item name: (identifier) @local.definition)
+        (static_item name: (identifier) @local.definition)
+        (enum_variant name: (identifier) @local.definition)
+        (field_declaration name: (field_identifier) @local.definition)
+        (let_declaration pattern: (identifier) @local.definition)
+        (parameter pattern: (identifier) @local.definition)
+        (closure_parameters (identifier) @local.definition)
+        (for_expression pattern: (identifier) @local.definition)
+        (tuple_pattern (identifier) @local.definition)
+        (mut_pattern (identifier) @local.definition)
+        (ref_pattern (identifier) @local.definition)
+        "#,
+    )
+});
+
+fn parse(content: &str, language: SourceLanguage) -> Option<Tree> {
+    let mut parser = Parser::new();
+    parser.set_language(&language.grammar()).ok()?;
+    parser.parse(content, None)
+}
+
+fn definitions(tree: &Tree, content: &str, language: SourceLanguage) -> HashSet<String> {
+    let query = language.locals();
+    let definition = query
+        .capture_index_for_name("local.definition")
+        .expect("locals queries capture definitions");
+
+    let mut names = HashSet::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), content.as_bytes());
+    while let Some(found) = matches.next() {
+        for capture in found.captures.iter().filter(|c| c.index == definition) {
+            names.insert(content[capture.node.byte_range()].to_string());
+        }
+    }
+    names
+}
+
+/// The names declared in `content`
+pub fn declared_names(content: &str, language: SourceLanguage) -> HashSet<String> {
+    match parse(content, language) {
+        Some(tree) => definitions(&tree, content, language),
+        None => HashSet::new(),
+    }
+}
+
+/// The byte ranges of the identifiers in `content` naming something it declares: the
+/// declarations themselves and every identifier spelled like one of them, in order
+pub fn declared_references(content: &str, language: SourceLanguage) -> Vec<Range<usize>> {
+    let Some(tree) = parse(content, language) else {
+        return Vec::new();
+    };
+    let names = definitions(&tree, content, language);
+
+    let mut ranges = Vec::new();
+    if !names.is_empty() {
+        collect_identifiers(tree.root_node(), content, &names, &mut ranges);
+    }
+    ranges
+}
+
+/// Appends the ranges of the identifier leaves under `node` whose text is in `names`
+fn collect_identifiers(
+    node: Node,
+    content: &str,
+    names: &HashSet<String>,
+    ranges: &mut Vec<Range<usize>>,
+) {
+    if node.child_count() == 0 {
+        // `identifier`, `type_identifier`, `property_identifier`, ...
+        let kind = node.kind();
+        if node.is_named() && (kind.ends_with("identifier") || kind.ends_with("identifier_pattern"))
+        {
+            let range = node.byte_range();
+            if names.contains(&content[range.clone()]) {
+                ranges.push(range);
+            }
+        }
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_identifiers(child, content, names, ranges);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(content: &str, language: SourceLanguage) -> Vec<String> {
+        let mut names: Vec<_> = declared_names(content, language).into_iter().collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_python() {
+        let content = "import lib as myLib\nclass UserStore:\n    def getUser(self, userId, maxAge=10):\n        self.lastUser = lib.findById(userId)\n        for itemName in items:\n            pass\n        return self.lastUser\n";
+        assert_eq!(
+            names(content, SourceLanguage::Python),
+            ["UserStore", "getUser", "itemName", "lastUser", "maxAge", "myLib", "self", "userId"]
+        );
+    }
+
+    #[test]
+    fn test_javascript() {
+        let content = "const userName = document.getElementById('x');\nfunction showUser(userId, ...restArgs) {\n  if (userId) {\n    return fetchUser(userId);\n  }\n}\nitems.map((itemName) => itemName.trim());\n";
+        assert_eq!(
+            names(content, SourceLanguage::JavaScript),
+            ["itemName", "restArgs", "showUser", "userId", "userName"]
+        );
+    }
+
+    #[test]
+    fn test_c_and_java() {
+        let content = "#define MAX_SIZE 10\nstatic int userCount = 0;\nvoid printUser(const char *userName, int userId) {\n    printf(userName);\n    return userCount;\n}\n";
+        assert_eq!(
+            names(content, SourceLanguage::C),
+            ["MAX_SIZE", "printUser", "userCount", "userId", "userName"]
+        );
+
+        let content = "public class UserService {\n    private List<String> userNames = new ArrayList<>();\n    public void addUser(String userName) {\n        userNames.add(userName);\n    }\n}\n";
+        assert_eq!(
+            names(content, SourceLanguage::Java),
+            ["UserService", "addUser", "userName", "userNames"]
+        );
+    }
+
+    #[test]
+    fn test_go_and_rust() {
+        let content = "func (s *Server) HandleRequest(reqCtx context.Context, userId int) {\n\tmaxAge := 10\n\tvar retryCount int\n}\n";
+        assert_eq!(
+            names(content, SourceLanguage::Go),
+            ["HandleRequest", "maxAge", "reqCtx", "retryCount", "s", "userId"]
+        );
+
+        let content = "struct Config {\n    pub maxAge: u32,\n}\nfn loadConfig(filePath: &Path) -> Config {\n    let mut retryCount = 0;\n    std::fs::read(filePath);\n}\n";
+        assert_eq!(
+            names(content, SourceLanguage::Rust),
+            ["Config", "filePath", "loadConfig", "maxAge", "retryCount"]
+        );
+    }
+
+    #[test]
+    fn test_references() {
+        // The comment and the string mention `userId` but don't reference it
+        let content = "// userId\nfunction show(userId) { log('userId', userId, api.getById); }\n";
+        let ranges = declared_references(content, SourceLanguage::JavaScript);
+        let found: Vec<_> = ranges.iter().map(|r| (r.start, &content[r.clone()])).collect();
+        assert_eq!(found, [(19, "show"), (24, "userId"), (48, "userId")]);
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(SourceLanguage::from_path(Path::new("a.hpp")), Some(SourceLanguage::C));
+        assert_eq!(SourceLanguage::from_path(Path::new("a.tsx")), Some(SourceLanguage::JavaScript));
+        assert_eq!(SourceLanguage::from_path(Path::new("README.md")), None);
+    }
+}
@@ -0,0 +1,77 @@
+//! Unified diff rendering for previews
+
+use similar::TextDiff;
+use std::path::Path;
+
+/// Number of unchanged context lines around each hunk
+const CONTEXT_LINES: usize = 3;
+
+/// Renders a unified diff between two versions of a file, colored with ANSI
+/// escapes like `git diff` on a terminal if `color` is set
+pub fn unified_diff(path: &Path, old: &str, new: &str, color: bool) -> String {
+    let name = path.display().to_string();
+    let diff = TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(CONTEXT_LINES)
+        .header(&format!("a/{}", name), &format!("b/{}", name))
+        .to_string();
+    if color {
+        colorize(&diff)
+    } else {
+        diff
+    }
+}
+
+/// Colors headers bold, hunk markers cyan, removals red and additions green
+fn colorize(diff: &str) -> String {
+    let mut colored = String::with_capacity(diff.len() + diff.len() / 4);
+    for line in diff.split_inclusive('\n') {
+        let text = line.trim_end_matches('\n');
+        let color = if text.starts_with("---") || text.starts_with("+++") {
+            "1"
+        } else if text.starts_with("@@") {
+            "36"
+        } else if text.starts_with('-') {
+            "31"
+        } else if text.starts_with('+') {
+            "32"
+        } else {
+            colored.push_str(line);
+            continue;
+        };
+        colored.push_str(&format!("\x1b[{}m{}\x1b[0m", color, text));
+        colored.push_str(&line[text.len()..]);
+    }
+    colored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let diff = unified_diff(Path::new("src/main.c"), "a\nb\nc\n", "a\nB\nc\n", false);
+        assert!(diff.starts_with("--- a/src/main.c\n+++ b/src/main.c\n"));
+        assert!(diff.contains("-b\n+B\n"));
+
+        assert_eq!(unified_diff(Path::new("x"), "same\n", "same\n", false), "");
+    }
+
+    #[test]
+    fn test_colored_diff() {
+        let diff = unified_diff(Path::new("x"), "old\n", "new\n", true);
+        assert!(diff.starts_with("\x1b[1m--- a/x\x1b[0m\n"));
+        assert!(diff.contains("\x1b[31m-old\x1b[0m\n\x1b[32m+new\x1b[0m\n"));
+    }
+
+    #[test]
+    fn test_colorize() {
+        let diff = "--- a/x\n+++ b/x\n@@ -1 +1 @@\n-old\n+new\n same\n";
+        assert_eq!(
+            colorize(diff),
+            "\x1b[1m--- a/x\x1b[0m\n\x1b[1m+++ b/x\x1b[0m\n\x1b[36m@@ -1 +1 @@\x1b[0m\n\
+             \x1b[31m-old\x1b[0m\n\x1b[32m+new\x1b[0m\n same\n"
+        );
+    }
+}
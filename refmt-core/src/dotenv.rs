@@ -0,0 +1,415 @@
+//! `.env` key normalization
+//!
+//! Uppercases dotenv keys to SCREAMING_SNAKE_CASE and removes the spaces around
+//! `=`, leaving values (including multi-line quoted ones), comments and `export`
+//! prefixes alone. Keys defined more than once are reported, since only one of the
+//! definitions takes effect.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::case::{identifier_words, CaseFormat};
+use crate::parallel;
+use crate::report::{report, EventSink};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// Options for dotenv normalization
+#[derive(Debug, Clone)]
+pub struct DotenvOptions {
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for DotenvOptions {
+    fn default() -> Self {
+        DotenvOptions {
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// A key defined again after its first definition
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateKey {
+    /// File containing the key
+    pub path: PathBuf,
+    /// Normalized key
+    pub key: String,
+    /// Line of the repeated definition (1-based)
+    pub line: usize,
+    /// Line of the first definition (1-based)
+    pub first_line: usize,
+}
+
+/// Statistics from dotenv normalization
+#[derive(Debug, Default)]
+pub struct DotenvStats {
+    /// Number of files changed
+    pub files_changed: usize,
+    /// Number of lines changed
+    pub lines_changed: usize,
+    /// Keys defined more than once
+    pub duplicates: Vec<DuplicateKey>,
+}
+
+/// One `KEY=value` line
+struct Assignment<'a> {
+    indent: &'a str,
+    export: bool,
+    key: &'a str,
+    value: &'a str,
+}
+
+/// Normalizes keys and spacing in dotenv files
+pub struct DotenvNormalizer {
+    options: DotenvOptions,
+    walker: Walker,
+}
+
+impl DotenvNormalizer {
+    /// Creates a new normalizer with the given options
+    pub fn new(options: DotenvOptions) -> Self {
+        DotenvNormalizer {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a normalizer with default options
+    pub fn with_defaults() -> Self {
+        DotenvNormalizer::new(DotenvOptions::default())
+    }
+
+    /// Checks if a file should be processed
+    ///
+    /// Dotenv files are recognized by name: `.env`, `.env.*` (`.env.local`,
+    /// `.env.example`, ...) and `*.env`. Hidden directories are skipped.
+    pub fn should_process(&self, path: &Path) -> bool {
+        self.options.fs.is_file(path) && self.accepts(path)
+    }
+
+    /// Checks the path alone against the filters, without touching the filesystem
+    pub fn accepts(&self, path: &Path) -> bool {
+        let Some(parent) = path.parent() else {
+            return false;
+        };
+        if parent.components().any(|c| {
+            c.as_os_str()
+                .to_str()
+                .map(|s| s.starts_with('.') && s != "." && s != "..")
+                .unwrap_or(false)
+        }) {
+            return false;
+        }
+
+        is_dotenv(path)
+    }
+
+    /// Normalizes the keys and `=` spacing of dotenv content, returning the new content
+    /// and the number of lines changed
+    ///
+    /// Returns the input borrowed when nothing changes.
+    pub fn normalize_str<'a>(&self, content: &'a str) -> (Cow<'a, str>, usize) {
+        let mut out = String::with_capacity(content.len());
+        let mut changes = 0;
+
+        for_each_line(content, |line, eol, assignment| {
+            match assignment.map(|a| render(&a)) {
+                Some(normalized) if normalized != line => {
+                    out.push_str(&normalized);
+                    changes += 1;
+                }
+                _ => out.push_str(line),
+            }
+            out.push_str(eol);
+        });
+
+        if changes == 0 {
+            return (Cow::Borrowed(content), 0);
+        }
+        (Cow::Owned(out), changes)
+    }
+
+    /// Finds keys defined more than once, comparing them after normalization
+    pub fn duplicates(&self, path: &Path, content: &str) -> Vec<DuplicateKey> {
+        let mut first_lines: HashMap<String, usize> = HashMap::new();
+        let mut duplicates = Vec::new();
+        let mut line_number = 0;
+
+        for_each_line(content, |_, _, assignment| {
+            line_number += 1;
+            let Some(assignment) = assignment else {
+                return;
+            };
+            let key = normalize_key(assignment.key);
+            match first_lines.get(&key) {
+                Some(&first_line) => duplicates.push(DuplicateKey {
+                    path: path.to_path_buf(),
+                    key,
+                    line: line_number,
+                    first_line,
+                }),
+                None => {
+                    first_lines.insert(key, line_number);
+                }
+            }
+        });
+
+        duplicates
+    }
+
+    /// Normalizes a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path) -> crate::Result<(usize, Vec<DuplicateKey>)> {
+        if !self.should_process(path) {
+            return Ok((0, Vec::new()));
+        }
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        let duplicates = self.duplicates(path, &content);
+        let (normalized, changes) = self.normalize_str(&content);
+
+        if let Cow::Owned(normalized) = normalized {
+            if !self.options.dry_run {
+                self.options.fs.write(path, normalized.as_bytes())?;
+            }
+        }
+
+        Ok((changes, duplicates))
+    }
+
+    fn report(&self, path: &Path, changes: usize, duplicates: &[DuplicateKey]) {
+        if changes > 0 {
+            self.options.events.file_changed(path, changes, self.options.dry_run);
+            if self.options.dry_run {
+                report!("Would normalize {} lines in '{}'", changes, path.display());
+            } else {
+                report!("Normalized {} lines in '{}'", changes, path.display());
+            }
+        }
+
+        for duplicate in duplicates {
+            report!(
+                "{}:{}: duplicate key '{}' (first defined on line {})",
+                path.display(),
+                duplicate.line,
+                duplicate.key,
+                duplicate.first_line
+            );
+        }
+    }
+
+    /// Processes a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<DotenvStats> {
+        let mut stats = DotenvStats::default();
+
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.apply_to_file(file)
+        });
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let (changes, duplicates) = result?;
+            self.report(file_path, changes, &duplicates);
+            if changes > 0 {
+                stats.files_changed += 1;
+                stats.lines_changed += changes;
+            }
+            stats.duplicates.extend(duplicates);
+        }
+
+        Ok(stats)
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+/// Whether a file name is a dotenv file
+fn is_dotenv(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    name == ".env" || name.starts_with(".env.") || (name.ends_with(".env") && name.len() > 4)
+}
+
+/// SCREAMING_SNAKE_CASE form of a key; keys without letters or digits are kept
+fn normalize_key(key: &str) -> String {
+    let words = identifier_words(key);
+    if words.is_empty() {
+        key.to_string()
+    } else {
+        CaseFormat::ScreamingSnakeCase.join_words(&words, "", "")
+    }
+}
+
+fn render(assignment: &Assignment) -> String {
+    format!(
+        "{}{}{}={}",
+        assignment.indent,
+        if assignment.export { "export " } else { "" },
+        normalize_key(assignment.key),
+        assignment.value
+    )
+}
+
+/// Calls `f` with each line (without its line ending), the line ending and the
+/// assignment on it, if any
+///
+/// Lines that continue a multi-line quoted value are never treated as assignments.
+fn for_each_line<'a>(
+    content: &'a str,
+    mut f: impl FnMut(&'a str, &'a str, Option<Assignment<'a>>),
+) {
+    let mut open_quote: Option<char> = None;
+
+    for raw in content.split_inclusive('\n') {
+        let line = raw.trim_end_matches(['\r', '\n']);
+        let eol = &raw[line.len()..];
+
+        if let Some(quote) = open_quote {
+            if closes(line, quote) {
+                open_quote = None;
+            }
+            f(line, eol, None);
+            continue;
+        }
+
+        let assignment = parse_assignment(line);
+        if let Some(assignment) = &assignment {
+            let mut chars = assignment.value.chars();
+            if let Some(quote @ ('"' | '\'' | '`')) = chars.next() {
+                if !closes(chars.as_str(), quote) {
+                    open_quote = Some(quote);
+                }
+            }
+        }
+        f(line, eol, assignment);
+    }
+}
+
+/// Whether `text` contains the closing `quote` (`\"` escapes inside double quotes)
+fn closes(text: &str, quote: char) -> bool {
+    let mut escaped = false;
+    for c in text.chars() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && quote == '"' {
+            escaped = true;
+        } else if c == quote {
+            return true;
+        }
+    }
+    false
+}
+
+fn parse_assignment(line: &str) -> Option<Assignment<'_>> {
+    let body = line.trim_start();
+    let indent = &line[..line.len() - body.len()];
+    if body.is_empty() || body.starts_with('#') {
+        return None;
+    }
+
+    let (export, body) = match body.strip_prefix("export") {
+        Some(rest) if rest.starts_with([' ', '\t']) => (true, rest.trim_start()),
+        _ => (false, body),
+    };
+
+    let (key, value) = body.split_once('=')?;
+    let key = key.trim_end();
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return None;
+    }
+
+    Some(Assignment {
+        indent,
+        export,
+        key,
+        value: value.trim_start(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        let content = concat!(
+            "# Database\n",
+            "db_host = localhost\r\n",
+            "export apiKey=abc=def\n",
+            "PRIVATE_KEY=\"-----BEGIN\n",
+            "not_a = key\n",
+            "-----END\"\n",
+            "SAME=value\n",
+        );
+        let (result, changes) = DotenvNormalizer::with_defaults().normalize_str(content);
+        assert_eq!(
+            result,
+            concat!(
+                "# Database\n",
+                "DB_HOST=localhost\r\n",
+                "export API_KEY=abc=def\n",
+                "PRIVATE_KEY=\"-----BEGIN\n",
+                "not_a = key\n",
+                "-----END\"\n",
+                "SAME=value\n",
+            )
+        );
+        assert_eq!(changes, 2);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let normalizer = DotenvNormalizer::with_defaults();
+        let content = "A=1\ndbHost=x\n\nDB_HOST=y\nA=2\n";
+        let duplicates = normalizer.duplicates(Path::new(".env"), content);
+        let found: Vec<_> = duplicates
+            .iter()
+            .map(|d| (d.key.as_str(), d.line, d.first_line))
+            .collect();
+        assert_eq!(found, vec![("DB_HOST", 4, 2), ("A", 5, 1)]);
+    }
+
+    #[test]
+    fn test_is_dotenv() {
+        assert!(is_dotenv(Path::new("app/.env")));
+        assert!(is_dotenv(Path::new(".env.local")));
+        assert!(is_dotenv(Path::new("prod.env")));
+        assert!(!is_dotenv(Path::new(".envrc")));
+        assert!(!is_dotenv(Path::new("env.py")));
+    }
+}
@@ -4,9 +4,18 @@
 //! with special handling for task completion emojis.
 
 use regex::Regex;
-use std::fs;
+use std::borrow::Cow;
 use std::path::Path;
-use walkdir::WalkDir;
+use std::sync::{Arc, Mutex};
+
+use crate::anchors::{self, AnchorChanges};
+use crate::diff::unified_diff;
+use crate::filter::{read_text, ExtensionSet, FilterOptions, SkipReason};
+use crate::progress::Progress;
+use crate::parallel;
+use crate::report::{report, EventSink, FailedFile, FailedFiles, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
 
 /// Options for emoji transformation
 #[derive(Debug, Clone)]
@@ -15,12 +24,30 @@ pub struct EmojiOptions {
     pub replace_task_emojis: bool,
     /// Remove all other emojis
     pub remove_other_emojis: bool,
+    /// Replacements applied before the built-in ones, such as `🚀` → `[launch]`
+    pub mappings: Vec<(String, String)>,
+    /// Rewrite Markdown links to headings whose anchors change
+    pub update_anchors: bool,
     /// File extensions to process
     pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
     /// Process directories recursively
     pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
     /// Dry run mode (don't modify files)
     pub dry_run: bool,
+    /// Print a unified diff of each change
+    pub show_diff: bool,
+    /// Color the diffs with ANSI escapes
+    pub color_diff: bool,
+    /// Receives progress events while files are processed
+    pub progress: Progress,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
 }
 
 impl Default for EmojiOptions {
@@ -28,17 +55,18 @@ impl Default for EmojiOptions {
         EmojiOptions {
             replace_task_emojis: true,
             remove_other_emojis: true,
-            file_extensions: vec![
-                ".md", ".txt", ".rst", ".org",
-                ".py", ".rs", ".go", ".java",
-                ".js", ".ts", ".jsx", ".tsx",
-                ".c", ".h", ".cpp", ".hpp",
-            ]
-            .iter()
-            .map(|s| s.to_string())
-            .collect(),
+            mappings: Vec::new(),
+            update_anchors: true,
+            file_extensions: ExtensionSet::Emojis.defaults(),
+            filter: FilterOptions::default(),
             recursive: true,
+            walk: WalkOptions::default(),
             dry_run: false,
+            show_diff: false,
+            color_diff: false,
+            progress: Progress::default(),
+            fs: vfs::real(),
+            events: EventSink::default(),
         }
     }
 }
@@ -48,6 +76,8 @@ pub struct EmojiTransformer {
     options: EmojiOptions,
     task_emoji_pattern: Regex,
     general_emoji_pattern: Regex,
+    walker: Walker,
+    failed: FailedFiles,
 }
 
 impl EmojiTransformer {
@@ -110,6 +140,8 @@ impl EmojiTransformer {
             options,
             task_emoji_pattern,
             general_emoji_pattern,
+            walker: Walker::default(),
+            failed: FailedFiles::default(),
         }
     }
 
@@ -119,39 +151,15 @@ impl EmojiTransformer {
     }
 
     /// Checks if a file should be processed
-    fn should_process(&self, path: &Path) -> bool {
-        if !path.is_file() {
-            return false;
-        }
-
-        // Skip hidden files and directories
-        if path.components().any(|c| {
-            c.as_os_str()
-                .to_str()
-                .map(|s| s.starts_with('.'))
-                .unwrap_or(false)
-        }) {
-            return false;
-        }
-
-        // Skip build directories
-        let skip_dirs = ["build", "__pycache__", ".git", "node_modules", "venv", ".venv", "target"];
-        if path.components().any(|c| {
-            c.as_os_str()
-                .to_str()
-                .map(|s| skip_dirs.contains(&s))
-                .unwrap_or(false)
-        }) {
-            return false;
-        }
+    pub fn should_process(&self, path: &Path) -> bool {
+        self.options.fs.is_file(path) && self.skip_reason(path).is_none()
+    }
 
-        // Check file extension
-        if let Some(ext) = path.extension() {
-            let ext_str = format!(".{}", ext.to_string_lossy());
-            self.options.file_extensions.contains(&ext_str)
-        } else {
-            false
-        }
+    /// Returns why a file would be left alone, or `None` if it is processed
+    pub fn skip_reason(&self, path: &Path) -> Option<SkipReason> {
+        self.options
+            .filter
+            .path_skip(path, &self.options.file_extensions)
     }
 
     /// Replace task emojis with text equivalents
@@ -187,58 +195,130 @@ impl EmojiTransformer {
         }
     }
 
-    /// Transform emojis in a single file
-    pub fn transform_file(&self, path: &Path) -> crate::Result<usize> {
-        if !self.should_process(path) {
-            return Ok(0);
+    /// Transforms emojis in a string, returning the new content and the number of changes
+    ///
+    /// Returns the input borrowed when there is nothing to change.
+    pub fn transform_str<'a>(&self, content: &'a str) -> (Cow<'a, str>, usize) {
+        // Every emoji we handle is non-ASCII, so pure-ASCII content needs no regex scan
+        let ascii_mappings = self.options.mappings.iter().any(|(from, _)| from.is_ascii());
+        if content.is_ascii() && !ascii_mappings {
+            return (Cow::Borrowed(content), 0);
         }
 
-        let content = fs::read_to_string(path)?;
-        let original_content = content.clone();
-
-        let mut modified_content = content;
+        let mut modified_content = Cow::Borrowed(content);
         let mut changes = 0;
 
+        for (from, to) in self.options.mappings.iter().filter(|(from, _)| !from.is_empty()) {
+            let found = modified_content.matches(from.as_str()).count();
+            if found > 0 {
+                changes += found;
+                modified_content = Cow::Owned(modified_content.replace(from.as_str(), to));
+            }
+        }
+
         // Replace task emojis with text alternatives
         if self.options.replace_task_emojis {
-            let before = modified_content.clone();
-            let replaced = self.task_emoji_pattern.replace_all(&modified_content, |caps: &regex::Captures| {
-                self.replace_task_emoji(&caps[0])
-            });
-
-            if replaced != before {
-                // Count the number of replacements made
-                let task_emojis_found = self.task_emoji_pattern.find_iter(&before).count();
+            let task_emojis_found = self.task_emoji_pattern.find_iter(&modified_content).count();
+            if task_emojis_found > 0 {
                 changes += task_emojis_found;
-                modified_content = replaced.to_string();
+                modified_content = Cow::Owned(
+                    self.task_emoji_pattern
+                        .replace_all(&modified_content, |caps: &regex::Captures| {
+                            self.replace_task_emoji(&caps[0])
+                        })
+                        .into_owned(),
+                );
             }
         }
 
         // Remove other emojis
         if self.options.remove_other_emojis {
-            let before = modified_content.clone();
-            let cleaned = self.general_emoji_pattern.replace_all(&modified_content, "");
-            if cleaned != before {
-                // Count the number of emojis removed
-                let emojis_found = self.general_emoji_pattern.find_iter(&before).count();
+            let emojis_found = self.general_emoji_pattern.find_iter(&modified_content).count();
+            if emojis_found > 0 {
                 changes += emojis_found;
-                modified_content = cleaned.to_string();
+                modified_content = Cow::Owned(
+                    self.general_emoji_pattern
+                        .replace_all(&modified_content, "")
+                        .into_owned(),
+                );
             }
         }
 
-        if modified_content != original_content {
-            if self.options.dry_run {
-                println!(
-                    "Would transform emojis in '{}'",
-                    path.display()
-                );
-            } else {
-                fs::write(path, modified_content)?;
-                println!("Transformed emojis in '{}'", path.display());
+        (modified_content, changes)
+    }
+
+    /// Transform emojis in a single file
+    pub fn transform_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        let (outcome, diff) = self.apply_to_file(path, None)?;
+        self.report(path, outcome, diff.as_deref());
+        Ok(outcome)
+    }
+
+    /// Transforms a file without reporting, so it can run on a worker thread
+    ///
+    /// Changed heading anchors in Markdown files are added to `anchor_changes`.
+    fn apply_to_file(
+        &self,
+        path: &Path,
+        anchor_changes: Option<&Mutex<AnchorChanges>>,
+    ) -> crate::Result<(FileOutcome, Option<String>)> {
+        if !self.options.fs.is_file(path) {
+            return Ok((FileOutcome::unchanged(), None));
+        }
+        if let Some(reason) = self.skip_reason(path) {
+            return Ok((FileOutcome::skipped(reason), None));
+        }
+
+        let Some(content) = read_text(self.options.fs.as_ref(), path)? else {
+            return Ok((FileOutcome::skipped(SkipReason::Binary), None));
+        };
+        let (modified_content, changes) = self.transform_str(&content);
+
+        match modified_content {
+            Cow::Owned(modified_content) => {
+                if let Some(anchor_changes) = anchor_changes {
+                    if anchors::is_markdown(path) {
+                        anchor_changes
+                            .lock()
+                            .unwrap()
+                            .record(path, &content, &modified_content);
+                    }
+                }
+                let diff = self.options.show_diff.then(|| {
+                    unified_diff(path, &content, &modified_content, self.options.color_diff)
+                });
+                if !self.options.dry_run {
+                    self.options.fs.write(path, modified_content.as_bytes())?;
+                }
+                Ok((FileOutcome::changed(changes.max(1)), diff))
             }
-            Ok(changes.max(1))
+            Cow::Borrowed(_) => Ok((FileOutcome::unchanged(), None)),
+        }
+    }
+
+    pub(crate) fn report(&self, path: &Path, outcome: FileOutcome, diff: Option<&str>) {
+        if let Some(reason) = outcome.skipped {
+            if self.options.filter.reports(reason) {
+                report!("Skipped {} '{}'", reason, path.display());
+            }
+            return;
+        }
+        if !outcome.changed {
+            return;
+        }
+
+        self.options.events.file_changed(path, outcome.changes, self.options.dry_run);
+        if self.options.dry_run {
+            report!(
+                "Would transform emojis in '{}'",
+                path.display()
+            );
         } else {
-            Ok(0)
+            report!("Transformed emojis in '{}'", path.display());
+        }
+
+        if let Some(diff) = diff {
+            report!("{}", diff.trim_end());
         }
     }
 
@@ -247,142 +327,171 @@ impl EmojiTransformer {
         let mut total_files = 0;
         let mut total_changes = 0;
 
-        if path.is_file() {
-            let changes = self.transform_file(path)?;
-            if changes > 0 {
-                total_files = 1;
-                total_changes = changes;
-            }
-        } else if path.is_dir() {
-            if self.options.recursive {
-                for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
-                    if entry.file_type().is_file() {
-                        let changes = self.transform_file(entry.path())?;
-                        if changes > 0 {
-                            total_files += 1;
-                            total_changes += changes;
-                        }
-                    }
-                }
-            } else {
-                for entry in fs::read_dir(path)? {
-                    let entry = entry?;
-                    let entry_path = entry.path();
-                    if entry_path.is_file() {
-                        let changes = self.transform_file(&entry_path)?;
-                        if changes > 0 {
-                            total_files += 1;
-                            total_changes += changes;
-                        }
-                    }
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let anchor_changes = Mutex::new(AnchorChanges::new());
+        let record = self.options.update_anchors.then_some(&anchor_changes);
+        let results = parallel::map_files_with_progress(
+            self.options.fs.as_ref(),
+            &files,
+            &self.options.progress,
+            |file| self.should_process(file),
+            |file| self.apply_to_file(file, record),
+        );
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let (outcome, diff) = match result {
+                Ok(result) => result,
+                Err(e) => {
+                    self.failed.record(file_path, e, self.options.walk.errors, &self.options.events)?;
+                    continue;
                 }
+            };
+            self.report(file_path, outcome, diff.as_deref());
+            if outcome.changed {
+                total_files += 1;
+                total_changes += outcome.changes;
             }
         }
 
+        // Fix links to headings whose anchors changed
+        let anchor_changes = anchor_changes.into_inner().unwrap();
+        anchor_changes.apply(
+            self.options.fs.as_ref(),
+            &files,
+            self.options.dry_run,
+            &self.options.events,
+        )?;
+
         Ok((total_files, total_changes))
     }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+
+    /// Returns the files that couldn't be processed since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_failed_files(&self) -> Vec<FailedFile> {
+        self.failed.take()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
+    use crate::vfs::MemoryFs;
+    use std::path::PathBuf;
+
+    /// Default options on the in-memory filesystem `fs`
+    fn options_on(fs: &Arc<MemoryFs>) -> EmojiOptions {
+        EmojiOptions {
+            fs: fs.clone(),
+            ..EmojiOptions::default()
+        }
+    }
 
     #[test]
     fn test_replace_task_emojis() {
-        let test_dir = std::env::temp_dir().join("refmt_emoji_test");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("emoji_test");
 
         let test_file = test_dir.join("test.md");
-        fs::write(&test_file, "- [x] Done task\n- [ ] Todo task\n- Task complete\n").unwrap();
+        fs.add_file(&test_file, "- [x] Done task\n- [ ] Todo task\n- Task complete\n");
 
         // Replace checkmarks with [x]
-        let content = fs::read_to_string(&test_file).unwrap();
+        let content = fs.read_to_string(&test_file).unwrap();
         let updated = content.replace("✅", "[x]");
-        fs::write(&test_file, updated).unwrap();
+        fs.add_file(&test_file, updated);
 
-        let transformer = EmojiTransformer::with_defaults();
+        let transformer = EmojiTransformer::new(options_on(&fs));
         let (_files, _) = transformer.process(&test_file).unwrap();
 
         // Should still be valid markdown
-        let content = fs::read_to_string(&test_file).unwrap();
+        let content = fs.read_to_string(&test_file).unwrap();
         assert!(content.contains("[x]") || content.contains("[ ]"));
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 
     #[test]
     fn test_checkmark_replacement() {
-        let test_dir = std::env::temp_dir().join("refmt_emoji_checkmark");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("emoji_checkmark");
 
         let test_file = test_dir.join("test.txt");
-        fs::write(&test_file, "Task done ✅\nTask pending ☐\n").unwrap();
+        fs.add_file(&test_file, "Task done ✅\nTask pending ☐\n");
 
-        let transformer = EmojiTransformer::with_defaults();
+        let transformer = EmojiTransformer::new(options_on(&fs));
         let (files, _) = transformer.process(&test_file).unwrap();
 
         if files > 0 {
-            let content = fs::read_to_string(&test_file).unwrap();
+            let content = fs.read_to_string(&test_file).unwrap();
             assert!(content.contains("[x]") || content.contains("[ ]"));
             assert!(!content.contains("✅"));
             assert!(!content.contains("☐"));
         }
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 
     #[test]
     fn test_dry_run_mode() {
-        let test_dir = std::env::temp_dir().join("refmt_emoji_dry");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("emoji_dry");
 
         let test_file = test_dir.join("test.txt");
         let original = "Task ✅ done";
-        fs::write(&test_file, original).unwrap();
+        fs.add_file(&test_file, original);
 
-        let mut opts = EmojiOptions::default();
+        let mut opts = options_on(&fs);
         opts.dry_run = true;
 
         let transformer = EmojiTransformer::new(opts);
         transformer.process(&test_file).unwrap();
 
         // File should be unchanged
-        let content = fs::read_to_string(&test_file).unwrap();
+        let content = fs.read_to_string(&test_file).unwrap();
         assert_eq!(content, original);
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 
     #[test]
     fn test_skip_hidden_files() {
-        let test_dir = std::env::temp_dir().join("refmt_emoji_hidden");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("emoji_hidden");
 
         let hidden_file = test_dir.join(".hidden.txt");
-        fs::write(&hidden_file, "Task ✅\n").unwrap();
+        fs.add_file(&hidden_file, "Task ✅\n");
 
-        let transformer = EmojiTransformer::with_defaults();
+        let transformer = EmojiTransformer::new(options_on(&fs));
         let (files, _) = transformer.process(&hidden_file).unwrap();
 
         // Hidden file should be skipped
         assert_eq!(files, 0);
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 
     #[test]
     fn test_extension_filtering() {
-        let test_dir = std::env::temp_dir().join("refmt_emoji_ext");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("emoji_ext");
 
         let md_file = test_dir.join("test.md");
         let xyz_file = test_dir.join("test.xyz");
 
-        fs::write(&md_file, "✅ Task\n").unwrap();
-        fs::write(&xyz_file, "✅ Task\n").unwrap();
+        fs.add_file(&md_file, "✅ Task\n");
+        fs.add_file(&xyz_file, "✅ Task\n");
 
-        let mut opts = EmojiOptions::default();
+        let mut opts = options_on(&fs);
         opts.file_extensions = vec![".md".to_string()];
 
         let transformer = EmojiTransformer::new(opts);
@@ -391,50 +500,66 @@ mod tests {
         // Only .md should be processed
         assert_eq!(files, 1);
 
-        let md_content = fs::read_to_string(&md_file).unwrap();
-        let xyz_content = fs::read_to_string(&xyz_file).unwrap();
+        let md_content = fs.read_to_string(&md_file).unwrap();
+        let xyz_content = fs.read_to_string(&xyz_file).unwrap();
 
         assert!(md_content.contains("[x]") || !md_content.contains("✅"));
         assert_eq!(xyz_content, "✅ Task\n"); // Unchanged
+    }
 
-        fs::remove_dir_all(&test_dir).unwrap();
+    #[test]
+    fn test_heading_anchor_links_updated() {
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("emoji_anchors");
+
+        let guide = test_dir.join("guide.md");
+        let readme = test_dir.join("README.md");
+        fs.add_file(&guide, "# Guide\n\n## \u{1F680} Launch\n\nSee [launch](#-launch).\n");
+        fs.add_file(&readme, "Read [the launch steps](guide.md#-launch).\n");
+
+        let transformer = EmojiTransformer::new(options_on(&fs));
+        transformer.process(&test_dir).unwrap();
+
+        let guide_content = fs.read_to_string(&guide).unwrap();
+        assert!(guide_content.contains("See [launch](#launch)."));
+        assert_eq!(
+            fs.read_to_string(&readme).unwrap(),
+            "Read [the launch steps](guide.md#launch).\n"
+        );
     }
 
     #[test]
     fn test_recursive_processing() {
-        let test_dir = std::env::temp_dir().join("refmt_emoji_recursive");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("emoji_recursive");
 
         let sub_dir = test_dir.join("subdir");
-        fs::create_dir_all(&sub_dir).unwrap();
 
         let file1 = test_dir.join("file1.md");
         let file2 = sub_dir.join("file2.md");
 
-        fs::write(&file1, "✅ Done\n").unwrap();
-        fs::write(&file2, "☐ Todo\n").unwrap();
+        fs.add_file(&file1, "✅ Done\n");
+        fs.add_file(&file2, "☐ Todo\n");
 
-        let transformer = EmojiTransformer::with_defaults();
+        let transformer = EmojiTransformer::new(options_on(&fs));
         let (files, _) = transformer.process(&test_dir).unwrap();
 
         assert_eq!(files, 2);
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 
     #[test]
     fn test_star_and_circle_replacement() {
-        let test_dir = std::env::temp_dir().join("refmt_emoji_star_circle");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("emoji_star_circle");
 
         let test_file = test_dir.join("test.md");
-        fs::write(&test_file, "⭐ Important task\n🟡 In progress\n🟢 Complete\n🔴 Blocked\n").unwrap();
+        fs.add_file(&test_file, "⭐ Important task\n🟡 In progress\n🟢 Complete\n🔴 Blocked\n");
 
-        let transformer = EmojiTransformer::with_defaults();
+        let transformer = EmojiTransformer::new(options_on(&fs));
         let (files, _) = transformer.process(&test_file).unwrap();
 
         if files > 0 {
-            let content = fs::read_to_string(&test_file).unwrap();
+            let content = fs.read_to_string(&test_file).unwrap();
             assert!(content.contains("[+]"), "Star emoji should be replaced with [+]");
             assert!(content.contains("[yellow]"), "Yellow circle should be replaced with [yellow]");
             assert!(content.contains("[green]"), "Green circle should be replaced with [green]");
@@ -444,28 +569,56 @@ mod tests {
             assert!(!content.contains("🟢"), "Green circle should be removed");
             assert!(!content.contains("🔴"), "Red circle should be removed");
         }
+    }
 
-        fs::remove_dir_all(&test_dir).unwrap();
+    #[test]
+    fn test_ascii_content_unchanged() {
+        let transformer = EmojiTransformer::with_defaults();
+        let (content, changes) = transformer.transform_str("plain ascii [x] text\n");
+        assert!(matches!(content, Cow::Borrowed("plain ascii [x] text\n")));
+        assert_eq!(changes, 0);
+
+        // Non-ASCII content without emojis is also returned borrowed
+        let (content, _) = transformer.transform_str("caf\u{e9}\n");
+        assert!(matches!(content, Cow::Borrowed(_)));
+
+        let (content, changes) = transformer.transform_str("caf\u{e9} \u{2705}\n");
+        assert_eq!(content, "caf\u{e9} [x]\n");
+        assert_eq!(changes, 1);
+    }
+
+    #[test]
+    fn test_mappings() {
+        let options = EmojiOptions {
+            mappings: vec![
+                ("🚀".to_string(), "[launch]".to_string()),
+                ("✅".to_string(), "DONE".to_string()),
+            ],
+            ..EmojiOptions::default()
+        };
+        let transformer = EmojiTransformer::new(options);
+
+        let (content, changes) = transformer.transform_str("🚀 Release ✅ 🎉\n");
+        assert_eq!(content, "[launch] Release DONE \n");
+        assert_eq!(changes, 3);
     }
 
     #[test]
     fn test_yellow_square_replacement() {
-        let test_dir = std::env::temp_dir().join("refmt_emoji_yellow_square");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("emoji_yellow_square");
 
         let test_file = test_dir.join("test.md");
-        fs::write(&test_file, "🟨 In progress task\n🟡 Another yellow\n").unwrap();
+        fs.add_file(&test_file, "🟨 In progress task\n🟡 Another yellow\n");
 
-        let transformer = EmojiTransformer::with_defaults();
+        let transformer = EmojiTransformer::new(options_on(&fs));
         let (files, _) = transformer.process(&test_file).unwrap();
 
         if files > 0 {
-            let content = fs::read_to_string(&test_file).unwrap();
+            let content = fs.read_to_string(&test_file).unwrap();
             assert!(content.contains("[yellow]"), "Yellow square should be replaced with [yellow]");
             assert!(!content.contains("🟨"), "Yellow square emoji should be removed");
             assert!(!content.contains("🟡"), "Yellow circle emoji should be removed");
         }
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 }
@@ -0,0 +1,528 @@
+//! Line ending conversion
+//!
+//! Files are converted byte-wise, so any ASCII-compatible encoding is handled.
+//! Binary files (a NUL byte near the start, as git detects them) are never touched,
+//! and `.gitattributes` files in and above the processed tree are honoured:
+//! paths marked `binary` or `-text` are skipped and `eol=lf`/`eol=crlf` overrides
+//! the requested line ending.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::report::{report, EventSink, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// Number of leading bytes inspected for NUL when detecting binary files
+const BINARY_SNIFF_LEN: usize = 8000;
+
+/// Line ending style
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Unix line endings (`\n`)
+    Lf,
+    /// Windows line endings (`\r\n`)
+    Crlf,
+}
+
+/// Options for line ending conversion
+#[derive(Debug, Clone)]
+pub struct EolOptions {
+    /// Line ending to convert to
+    pub target: LineEnding,
+    /// File extensions to process (empty processes every text file)
+    pub file_extensions: Vec<String>,
+    /// Honour `binary`, `-text` and `eol` attributes from `.gitattributes`
+    pub respect_gitattributes: bool,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for EolOptions {
+    fn default() -> Self {
+        EolOptions {
+            target: LineEnding::Lf,
+            file_extensions: Vec::new(),
+            respect_gitattributes: true,
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// Statistics from line ending conversion
+#[derive(Debug, Default)]
+pub struct EolStats {
+    /// Number of files converted
+    pub files_converted: usize,
+    /// Number of line endings rewritten
+    pub lines_converted: usize,
+    /// Number of files skipped because they look binary
+    pub binary_skipped: usize,
+    /// Number of files skipped because `.gitattributes` marks them binary or `-text`
+    pub attributes_skipped: usize,
+}
+
+/// What happened to a single file
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EolResult {
+    Filtered,
+    Binary,
+    NotText,
+    Converted(LineEnding, usize),
+    Unchanged,
+}
+
+/// Line ending converter
+pub struct EolConverter {
+    options: EolOptions,
+    walker: Walker,
+}
+
+impl EolConverter {
+    /// Creates a new converter with the given options
+    pub fn new(options: EolOptions) -> Self {
+        EolConverter {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a converter with default options (convert to LF)
+    pub fn with_defaults() -> Self {
+        EolConverter::new(EolOptions::default())
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        if !self.options.fs.is_file(path) {
+            return false;
+        }
+
+        // An empty extension list selects every file
+        let filter = &self.options.filter;
+        if self.options.file_extensions.is_empty() {
+            return filter
+                .hidden_skip(path)
+                .or_else(|| filter.dir_skip(path))
+                .or_else(|| filter.skip_reason(path))
+                .is_none();
+        }
+        filter.path_skip(path, &self.options.file_extensions).is_none()
+    }
+
+    /// Converts the line endings of `content`, returning `None` if nothing changes
+    ///
+    /// Also returns the number of line endings rewritten. Lone `\r` is left alone.
+    pub fn convert_bytes(content: &[u8], target: LineEnding) -> Option<(Vec<u8>, usize)> {
+        let mut converted = Vec::with_capacity(content.len() + content.len() / 32);
+        let mut count = 0;
+
+        match target {
+            LineEnding::Lf => {
+                let mut i = 0;
+                while i < content.len() {
+                    if content[i] == b'\r' && content.get(i + 1) == Some(&b'\n') {
+                        count += 1;
+                        i += 1;
+                        continue;
+                    }
+                    converted.push(content[i]);
+                    i += 1;
+                }
+            }
+            LineEnding::Crlf => {
+                for (i, &byte) in content.iter().enumerate() {
+                    if byte == b'\n' && (i == 0 || content[i - 1] != b'\r') {
+                        converted.push(b'\r');
+                        count += 1;
+                    }
+                    converted.push(byte);
+                }
+            }
+        }
+
+        if count == 0 {
+            None
+        } else {
+            Some((converted, count))
+        }
+    }
+
+    /// Converts a single file, ignoring `.gitattributes`
+    pub fn convert_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        let result = self.apply_to_file(path, &GitAttributes::default())?;
+        self.report(path, result);
+        Ok(match result {
+            EolResult::Converted(_, count) => FileOutcome::changed(count),
+            _ => FileOutcome::unchanged(),
+        })
+    }
+
+    /// Converts a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path, attributes: &GitAttributes) -> crate::Result<EolResult> {
+        if !self.should_process(path) {
+            return Ok(EolResult::Filtered);
+        }
+
+        let attrs = attributes.lookup(path);
+        if attrs.not_text {
+            return Ok(EolResult::NotText);
+        }
+        let target = attrs.eol.unwrap_or(self.options.target);
+
+        let content = self.options.fs.read(path)?;
+        if is_binary(&content) {
+            return Ok(EolResult::Binary);
+        }
+
+        match Self::convert_bytes(&content, target) {
+            Some((converted, count)) => {
+                if !self.options.dry_run {
+                    self.options.fs.write(path, &converted)?;
+                }
+                Ok(EolResult::Converted(target, count))
+            }
+            None => Ok(EolResult::Unchanged),
+        }
+    }
+
+    fn report(&self, path: &Path, result: EolResult) {
+        if let EolResult::Converted(target, count) = result {
+            let name = match target {
+                LineEnding::Lf => "LF",
+                LineEnding::Crlf => "CRLF",
+            };
+            self.options.events.file_changed(path, count, self.options.dry_run);
+            if self.options.dry_run {
+                report!(
+                    "Would convert {} line endings to {} in '{}'",
+                    count,
+                    name,
+                    path.display()
+                );
+            } else {
+                report!(
+                    "Converted {} line endings to {} in '{}'",
+                    count,
+                    name,
+                    path.display()
+                );
+            }
+        }
+    }
+
+    /// Processes a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<EolStats> {
+        let mut stats = EolStats::default();
+
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let attributes = if self.options.respect_gitattributes {
+            GitAttributes::load(self.options.fs.as_ref(), path, &files)
+        } else {
+            GitAttributes::default()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.apply_to_file(file, &attributes)
+        });
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let result = result?;
+            self.report(file_path, result);
+            match result {
+                EolResult::Converted(_, count) => {
+                    stats.files_converted += 1;
+                    stats.lines_converted += count;
+                }
+                EolResult::Binary => stats.binary_skipped += 1,
+                EolResult::NotText => stats.attributes_skipped += 1,
+                EolResult::Filtered | EolResult::Unchanged => {}
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+/// Detects binary content the way git does: a NUL byte near the start
+pub(crate) fn is_binary(content: &[u8]) -> bool {
+    memchr::memchr(0, &content[..content.len().min(BINARY_SNIFF_LEN)]).is_some()
+}
+
+/// Splits a leading byte order mark off `content`, returning it (or `""`) and the rest
+pub(crate) fn split_bom(content: &str) -> (&str, &str) {
+    match content.strip_prefix('\u{FEFF}') {
+        Some(rest) => (&content[..content.len() - rest.len()], rest),
+        None => ("", content),
+    }
+}
+
+/// The lines of `content`, each with its own line ending (`\n`, `\r\n`, or `""` for
+/// a last line without one), so rewriting them line by line keeps mixed endings
+pub(crate) fn lines_with_endings(content: &str) -> impl Iterator<Item = (&str, &str)> {
+    content.split_inclusive('\n').map(|line| {
+        let text = match line.strip_suffix('\n') {
+            Some(text) => text.strip_suffix('\r').unwrap_or(text),
+            None => line,
+        };
+        (text, &line[text.len()..])
+    })
+}
+
+/// Attributes relevant to line ending conversion
+#[derive(Debug, Default, Clone, Copy)]
+struct EolAttributes {
+    not_text: bool,
+    eol: Option<LineEnding>,
+}
+
+/// One `.gitattributes` line
+#[derive(Debug)]
+struct AttributeRule {
+    /// Directory containing the `.gitattributes` file
+    base: PathBuf,
+    pattern: glob::Pattern,
+    /// Patterns containing a slash match the path relative to `base`, others the file name
+    anchored: bool,
+    binary: Option<bool>,
+    text: Option<bool>,
+    eol: Option<LineEnding>,
+}
+
+/// `.gitattributes` rules, ordered from lowest to highest precedence
+#[derive(Debug, Default)]
+struct GitAttributes {
+    rules: Vec<AttributeRule>,
+}
+
+impl GitAttributes {
+    /// Loads `.gitattributes` from the ancestors of `root` (up to the repository root)
+    /// and from within the collected files
+    fn load(fs: &dyn Fs, root: &Path, files: &[PathBuf]) -> Self {
+        let mut sources = Vec::new();
+
+        let start = if fs.is_file(root) {
+            root.parent()
+        } else {
+            Some(root)
+        };
+        let mut dir = start.map(Path::to_path_buf);
+        while let Some(current) = dir {
+            let candidate = current.join(".gitattributes");
+            if fs.is_file(&candidate) {
+                sources.push(candidate);
+            }
+            if fs.exists(&current.join(".git")) {
+                break;
+            }
+            dir = current.parent().map(Path::to_path_buf);
+        }
+        // Outermost files have the lowest precedence
+        sources.reverse();
+
+        let mut nested: Vec<&PathBuf> = files
+            .iter()
+            .filter(|f| f.file_name().is_some_and(|n| n == ".gitattributes"))
+            .filter(|f| !sources.contains(f))
+            .collect();
+        nested.sort_by_key(|p| p.components().count());
+        sources.extend(nested.into_iter().cloned());
+
+        let mut attributes = GitAttributes::default();
+        for source in sources {
+            if let Ok(content) = vfs::read_to_string(fs, &source) {
+                let base = source.parent().unwrap_or(Path::new(".")).to_path_buf();
+                attributes.parse(&base, &content);
+            }
+        }
+        attributes
+    }
+
+    fn parse(&mut self, base: &Path, content: &str) {
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let pattern = match parts.next() {
+                Some(pattern) => pattern,
+                None => continue,
+            };
+            let anchored = pattern.trim_start_matches('/').contains('/') || pattern.starts_with('/');
+            let pattern = match glob::Pattern::new(pattern.trim_start_matches('/')) {
+                Ok(pattern) => pattern,
+                Err(_) => continue,
+            };
+
+            let mut rule = AttributeRule {
+                base: base.to_path_buf(),
+                pattern,
+                anchored,
+                binary: None,
+                text: None,
+                eol: None,
+            };
+            for attr in parts {
+                match attr {
+                    "binary" => rule.binary = Some(true),
+                    "-binary" => rule.binary = Some(false),
+                    "text" | "text=auto" => rule.text = Some(true),
+                    "-text" => rule.text = Some(false),
+                    "eol=lf" => rule.eol = Some(LineEnding::Lf),
+                    "eol=crlf" => rule.eol = Some(LineEnding::Crlf),
+                    _ => {}
+                }
+            }
+
+            if rule.binary.is_some() || rule.text.is_some() || rule.eol.is_some() {
+                self.rules.push(rule);
+            }
+        }
+    }
+
+    /// Resolves the attributes for a path; later rules override earlier ones
+    fn lookup(&self, path: &Path) -> EolAttributes {
+        let mut attrs = EolAttributes::default();
+
+        for rule in &self.rules {
+            let relative = match path.strip_prefix(&rule.base) {
+                Ok(relative) => relative,
+                Err(_) => continue,
+            };
+            let matches = if rule.anchored {
+                rule.pattern.matches_path(relative)
+            } else {
+                path.file_name()
+                    .map(|n| rule.pattern.matches(&n.to_string_lossy()))
+                    .unwrap_or(false)
+            };
+            if !matches {
+                continue;
+            }
+
+            if let Some(binary) = rule.binary {
+                attrs.not_text = binary;
+            }
+            if let Some(text) = rule.text {
+                attrs.not_text = !text;
+            }
+            if rule.eol.is_some() {
+                attrs.eol = rule.eol;
+            }
+        }
+
+        attrs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_convert_bytes() {
+        let (lf, count) = EolConverter::convert_bytes(b"a\r\nb\r\nc\n", LineEnding::Lf).unwrap();
+        assert_eq!(lf, b"a\nb\nc\n");
+        assert_eq!(count, 2);
+
+        let (crlf, count) = EolConverter::convert_bytes(b"a\nb\r\nc\n", LineEnding::Crlf).unwrap();
+        assert_eq!(crlf, b"a\r\nb\r\nc\r\n");
+        assert_eq!(count, 2);
+
+        // Already converted, and lone CR is left alone
+        assert_eq!(EolConverter::convert_bytes(b"a\nb\rc\n", LineEnding::Lf), None);
+        assert_eq!(EolConverter::convert_bytes(b"", LineEnding::Crlf), None);
+    }
+
+    #[test]
+    fn test_binary_files_skipped() {
+        let test_dir = std::env::temp_dir().join("refmt_eol_binary");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+
+        fs::write(test_dir.join("image.bin"), b"\x89PNG\r\n\x00\x00\r\n").unwrap();
+        fs::write(test_dir.join("text.txt"), b"one\r\ntwo\r\n").unwrap();
+
+        let stats = EolConverter::with_defaults().process(&test_dir).unwrap();
+        assert_eq!(stats.files_converted, 1);
+        assert_eq!(stats.lines_converted, 2);
+        assert_eq!(stats.binary_skipped, 1);
+
+        assert_eq!(fs::read(test_dir.join("image.bin")).unwrap(), b"\x89PNG\r\n\x00\x00\r\n");
+        assert_eq!(fs::read(test_dir.join("text.txt")).unwrap(), b"one\ntwo\n");
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_gitattributes() {
+        let test_dir = std::env::temp_dir().join("refmt_eol_gitattributes");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(test_dir.join(".git")).unwrap();
+        fs::create_dir_all(test_dir.join("scripts")).unwrap();
+
+        fs::write(
+            test_dir.join(".gitattributes"),
+            "*.bat eol=crlf\n*.dat -text\nscripts/*.sh eol=lf\n",
+        )
+        .unwrap();
+        fs::write(test_dir.join("run.bat"), "echo\n").unwrap();
+        fs::write(test_dir.join("data.dat"), "a\r\n").unwrap();
+        fs::write(test_dir.join("notes.txt"), "a\n").unwrap();
+        fs::write(test_dir.join("scripts/build.sh"), "a\r\n").unwrap();
+
+        let options = EolOptions {
+            target: LineEnding::Crlf,
+            ..EolOptions::default()
+        };
+        let stats = EolConverter::new(options).process(&test_dir).unwrap();
+
+        assert_eq!(stats.files_converted, 3);
+        assert_eq!(stats.attributes_skipped, 1);
+        assert_eq!(fs::read_to_string(test_dir.join("run.bat")).unwrap(), "echo\r\n");
+        assert_eq!(fs::read_to_string(test_dir.join("data.dat")).unwrap(), "a\r\n");
+        assert_eq!(fs::read_to_string(test_dir.join("notes.txt")).unwrap(), "a\r\n");
+        assert_eq!(fs::read_to_string(test_dir.join("scripts/build.sh")).unwrap(), "a\n");
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+}
@@ -0,0 +1,123 @@
+//! Errors returned by refmt-core
+//!
+//! Library users can match on the kind of failure, for instance to skip a rename whose
+//! target already exists but retry an IO error. Errors raised while working on a file
+//! are wrapped in [`Error::Context`] naming it; [`Error::root`] gets past the wrapping.
+
+use std::fmt::Display;
+use std::path::PathBuf;
+
+/// An error from one of the transformers
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Reading, writing or walking files failed
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A pattern isn't a valid regular expression
+    #[error("Invalid regular expression: {0}")]
+    Regex(#[from] regex::Error),
+    /// A pattern isn't a valid glob
+    #[error("Invalid glob pattern: {0}")]
+    Glob(#[from] glob::PatternError),
+    /// Content isn't valid UTF-8
+    #[error("{0}")]
+    Encoding(String),
+    /// A configuration, JSON, YAML or TOML document couldn't be parsed or written
+    #[error("{0}")]
+    Parse(String),
+    /// A rename target already exists
+    #[error("Target file already exists: '{}'", .0.display())]
+    TargetExists(PathBuf),
+    /// Two names would collide (renamed keys, fields, classes)
+    #[error("{0}")]
+    Conflict(String),
+    /// An option or argument is invalid
+    #[error("{0}")]
+    Invalid(String),
+    /// An external command (git) failed
+    #[error("{0}")]
+    Command(String),
+    /// An error with a description of what was being done
+    #[error("{context}: {error}")]
+    Context {
+        /// What was being done
+        context: String,
+        /// What went wrong
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// The error under any [`Error::Context`] wrapping
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Context { error, .. } => error.root(),
+            error => error,
+        }
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(error: std::string::FromUtf8Error) -> Self {
+        Error::Encoding(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Parse(error.to_string())
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(error: serde_yaml::Error) -> Self {
+        Error::Parse(error.to_string())
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(error: toml::de::Error) -> Self {
+        Error::Parse(error.to_string())
+    }
+}
+
+impl From<toml::ser::Error> for Error {
+    fn from(error: toml::ser::Error) -> Self {
+        Error::Parse(error.to_string())
+    }
+}
+
+/// Adds a description of what was being done to an error, like `anyhow::Context`
+pub(crate) trait Context<T> {
+    fn context<C: Display>(self, context: C) -> crate::Result<T>;
+
+    fn with_context<C: Display, F: FnOnce() -> C>(self, context: F) -> crate::Result<T>;
+}
+
+impl<T, E: Into<Error>> Context<T> for std::result::Result<T, E> {
+    fn context<C: Display>(self, context: C) -> crate::Result<T> {
+        self.with_context(|| context)
+    }
+
+    fn with_context<C: Display, F: FnOnce() -> C>(self, context: F) -> crate::Result<T> {
+        self.map_err(|error| Error::Context {
+            context: context().to_string(),
+            error: Box::new(error.into()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_keeps_root() {
+        let result: std::result::Result<(), _> =
+            Err(std::io::Error::new(std::io::ErrorKind::NotFound, "missing"));
+        let error = result.context("Failed to read 'a.txt'").unwrap_err();
+
+        assert_eq!(error.to_string(), "Failed to read 'a.txt': missing");
+        assert!(matches!(error.root(), Error::Io(e) if e.kind() == std::io::ErrorKind::NotFound));
+    }
+}
@@ -0,0 +1,400 @@
+//! File name and declared identifier consistency
+//!
+//! Many conventions tie a file's name to the main type it declares (`FooBar` lives in
+//! `foo_bar.py`, `FooBar.java` or `foo-bar.ts`). This check finds files whose single
+//! top-level class, interface or exported function doesn't match the file name under
+//! the project's file naming convention, and can fix either side: the file is renamed
+//! through [`FileRenamer`], or the identifier is renamed within the file.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+
+use regex::Regex;
+
+use crate::case::{identifier_words, CaseFormat};
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::rename::{FileRenamer, RenameOptions};
+use crate::report::{report, EventSink};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// `class Name` at the top level of a Python module
+static PY_CLASS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^class\s+([A-Za-z]\w*)").unwrap());
+/// Public top-level Java types
+static JAVA_TYPE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^public\s+(?:(?:abstract|final|sealed|non-sealed|static|strictfp)\s+)*(?:class|interface|enum|record|@interface)\s+(\w+)").unwrap()
+});
+/// Non-private top-level Kotlin types and objects
+static KOTLIN_TYPE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^(?:(?:public|internal|open|abstract|sealed|data|enum|annotation|value)\s+)*(?:class|interface|object)\s+(\w+)").unwrap()
+});
+/// Exported JavaScript/TypeScript classes, interfaces and functions
+static JS_EXPORT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^export\s+(?:default\s+)?(?:abstract\s+)?(?:class|interface|function)\s+(\w+)").unwrap()
+});
+
+/// Which side of a mismatch to fix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilenameFix {
+    /// Only report mismatches
+    None,
+    /// Rename the file after its identifier
+    RenameFile,
+    /// Rename the identifier, within the file, after the file name
+    RenameIdentifier,
+}
+
+/// Options for the file name consistency check
+#[derive(Debug, Clone)]
+pub struct FilenameOptions {
+    /// Case format of file names; `None` uses each language's convention (snake_case
+    /// for Python, PascalCase for Java and Kotlin, kebab-case for JavaScript/TypeScript)
+    pub file_format: Option<CaseFormat>,
+    /// What to fix when a mismatch is found
+    pub fix: FilenameFix,
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for FilenameOptions {
+    fn default() -> Self {
+        FilenameOptions {
+            file_format: None,
+            fix: FilenameFix::None,
+            file_extensions: [".py", ".java", ".kt", ".js", ".jsx", ".ts", ".tsx"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// A file whose name doesn't match the identifier it declares
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilenameMismatch {
+    /// The file
+    pub path: PathBuf,
+    /// The identifier declared in the file
+    pub identifier: String,
+    /// File name the identifier calls for, with extension
+    pub expected_file_name: String,
+    /// Identifier the file name calls for, or `None` if the names only differ in
+    /// case format
+    pub expected_identifier: Option<String>,
+    /// Whether [`process`](FilenameChecker::process) fixed it (or would have, in a
+    /// dry run)
+    pub fixed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Language {
+    Python,
+    Java,
+    Kotlin,
+    JavaScript,
+}
+
+impl Language {
+    fn from_path(path: &Path) -> Option<Language> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        let language = match ext.as_str() {
+            "py" => Language::Python,
+            "java" => Language::Java,
+            "kt" => Language::Kotlin,
+            "js" | "jsx" | "ts" | "tsx" => Language::JavaScript,
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    fn file_format(self) -> CaseFormat {
+        match self {
+            Language::Python => CaseFormat::SnakeCase,
+            Language::Java | Language::Kotlin => CaseFormat::PascalCase,
+            Language::JavaScript => CaseFormat::KebabCase,
+        }
+    }
+
+    fn declarations(self) -> &'static Regex {
+        match self {
+            Language::Python => &PY_CLASS,
+            Language::Java => &JAVA_TYPE,
+            Language::Kotlin => &KOTLIN_TYPE,
+            Language::JavaScript => &JS_EXPORT,
+        }
+    }
+}
+
+/// Checker for file names that don't match their declared identifier
+pub struct FilenameChecker {
+    options: FilenameOptions,
+    walker: Walker,
+}
+
+impl FilenameChecker {
+    /// Creates a new checker with the given options
+    pub fn new(options: FilenameOptions) -> Self {
+        FilenameChecker {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a checker with default options
+    pub fn with_defaults() -> Self {
+        FilenameChecker {
+            options: FilenameOptions::default(),
+            walker: Walker::default(),
+        }
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        if !self.options.fs.is_file(path) {
+            return false;
+        }
+
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none()
+            && Language::from_path(path).is_some()
+    }
+
+    /// Compares a file name with the identifiers declared in `content`
+    ///
+    /// Files declaring no identifier, or several of which none matches (modules of
+    /// helpers), aren't reported.
+    pub fn check_str(&self, path: &Path, content: &str) -> Option<FilenameMismatch> {
+        let language = Language::from_path(path)?;
+        let stem = path.file_stem()?.to_str()?;
+        let format = self.options.file_format.unwrap_or(language.file_format());
+
+        let identifiers: Vec<&str> = language
+            .declarations()
+            .captures_iter(content)
+            .filter_map(|caps| caps.get(1))
+            .map(|m| m.as_str())
+            .collect();
+
+        let expected_stem = |identifier: &str| format.join_words(&identifier_words(identifier), "", "");
+        if identifiers.iter().any(|identifier| expected_stem(identifier) == stem) {
+            return None;
+        }
+        let [identifier] = identifiers.as_slice() else {
+            return None;
+        };
+
+        let mut expected_file_name = expected_stem(identifier);
+        if let Some(ext) = path.extension() {
+            expected_file_name = format!("{}.{}", expected_file_name, ext.to_string_lossy());
+        }
+
+        let stem_words = identifier_words(stem);
+        let expected_identifier = if stem_words == identifier_words(identifier) {
+            None
+        } else {
+            let identifier_format = if identifier.starts_with(char::is_lowercase) {
+                CaseFormat::CamelCase
+            } else {
+                CaseFormat::PascalCase
+            };
+            Some(identifier_format.join_words(&stem_words, "", ""))
+        };
+
+        Some(FilenameMismatch {
+            path: path.to_path_buf(),
+            identifier: identifier.to_string(),
+            expected_file_name,
+            expected_identifier,
+            fixed: false,
+        })
+    }
+
+    /// Checks a single file
+    pub fn check_file(&self, path: &Path) -> crate::Result<Option<FilenameMismatch>> {
+        if !self.should_process(path) {
+            return Ok(None);
+        }
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        Ok(self.check_str(path, &content))
+    }
+
+    /// Applies the configured fix to a mismatch, returning whether anything changed
+    pub fn fix(&self, mismatch: &FilenameMismatch) -> crate::Result<bool> {
+        match self.options.fix {
+            FilenameFix::None => Ok(false),
+            FilenameFix::RenameFile => {
+                let new_path = mismatch.path.with_file_name(&mismatch.expected_file_name);
+                let mut options = RenameOptions::default();
+                options.dry_run = self.options.dry_run;
+                FileRenamer::new(options).rename_to(&mismatch.path, &new_path)?;
+                Ok(true)
+            }
+            FilenameFix::RenameIdentifier => {
+                let Some(ref new) = mismatch.expected_identifier else {
+                    return Ok(false);
+                };
+
+                let word = Regex::new(&format!(r"\b{}\b", regex::escape(&mismatch.identifier)))?;
+                let content = vfs::read_to_string(self.options.fs.as_ref(), &mismatch.path)?;
+                let renamed = word.replace_all(&content, new.as_str());
+
+                self.options.events.file_changed(&mismatch.path, 1, self.options.dry_run);
+                if self.options.dry_run {
+                    report!(
+                        "Would rename {} -> {} in '{}'",
+                        mismatch.identifier,
+                        new,
+                        mismatch.path.display()
+                    );
+                } else {
+                    self.options.fs.write(&mismatch.path, renamed.as_bytes())?;
+                    report!(
+                        "Renamed {} -> {} in '{}'",
+                        mismatch.identifier,
+                        new,
+                        mismatch.path.display()
+                    );
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    /// Checks a directory or file, returning the mismatches in path order
+    ///
+    /// With a fix configured, each mismatch is also fixed.
+    pub fn process(&self, path: &Path) -> crate::Result<Vec<FilenameMismatch>> {
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.check_file(file)
+        });
+
+        let mut mismatches = Vec::new();
+        for result in results {
+            if let Some(mismatch) = result? {
+                mismatches.push(mismatch);
+            }
+        }
+
+        // Fix once all files are read, since renames change the paths
+        for mismatch in &mut mismatches {
+            mismatch.fixed = self.fix(mismatch)?;
+        }
+
+        Ok(mismatches)
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn check(file: &str, content: &str) -> Option<FilenameMismatch> {
+        FilenameChecker::with_defaults().check_str(Path::new(file), content)
+    }
+
+    #[test]
+    fn test_matching_names() {
+        assert_eq!(check("foo_bar.py", "import os\n\nclass FooBar:\n    pass\n"), None);
+        assert_eq!(check("http_server.py", "class HTTPServer:\n    pass\n"), None);
+        assert_eq!(check("FooBar.java", "public final class FooBar {}\n"), None);
+        assert_eq!(check("parse-args.ts", "export function parseArgs() {}\n"), None);
+        // A module of several classes isn't tied to any of them
+        assert_eq!(check("models.py", "class User:\n    pass\nclass Group:\n    pass\n"), None);
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let mismatch = check("user_service.py", "class AccountService:\n    pass\n").unwrap();
+        assert_eq!(mismatch.identifier, "AccountService");
+        assert_eq!(mismatch.expected_file_name, "account_service.py");
+        assert_eq!(mismatch.expected_identifier.as_deref(), Some("UserService"));
+
+        // Same words in the wrong file name format: only the file can be fixed
+        let mismatch = check("FooBar.py", "class FooBar:\n    pass\n").unwrap();
+        assert_eq!(mismatch.expected_file_name, "foo_bar.py");
+        assert_eq!(mismatch.expected_identifier, None);
+
+        let options = FilenameOptions {
+            file_format: Some(CaseFormat::PascalCase),
+            ..FilenameOptions::default()
+        };
+        let checker = FilenameChecker::new(options);
+        assert_eq!(checker.check_str(Path::new("FooBar.py"), "class FooBar:\n    pass\n"), None);
+    }
+
+    #[test]
+    fn test_fix_file_and_identifier() {
+        let test_dir = std::env::temp_dir().join("refmt_filenames_fix");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+
+        fs::write(test_dir.join("Widget.py"), "class Widget:\n    pass\n").unwrap();
+        fs::write(test_dir.join("user_store.py"), "class UserRepo:\n    x = UserRepo\n").unwrap();
+
+        let options = FilenameOptions {
+            fix: FilenameFix::RenameFile,
+            ..FilenameOptions::default()
+        };
+        let mismatches = FilenameChecker::new(options).process(&test_dir).unwrap();
+        assert_eq!(mismatches.len(), 2);
+        assert!(mismatches.iter().all(|mismatch| mismatch.fixed));
+        assert!(test_dir.join("widget.py").exists());
+        assert!(test_dir.join("user_repo.py").exists());
+
+        fs::write(test_dir.join("user_store.py"), "class UserRepo:\n    x = UserRepo\n").unwrap();
+        let options = FilenameOptions {
+            fix: FilenameFix::RenameIdentifier,
+            ..FilenameOptions::default()
+        };
+        let mismatches = FilenameChecker::new(options)
+            .process(&test_dir.join("user_store.py"))
+            .unwrap();
+        assert!(mismatches[0].fixed);
+        assert_eq!(
+            fs::read_to_string(test_dir.join("user_store.py")).unwrap(),
+            "class UserStore:\n    x = UserStore\n"
+        );
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+}
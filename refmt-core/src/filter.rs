@@ -0,0 +1,373 @@
+//! File filters shared by the text transformers
+//!
+//! Each transformer selects files by extension; the filters here are applied on
+//! top of that, to leave out files that match the extensions but shouldn't be
+//! rewritten. Skipped files are reported with a [`SkipReason`]; only localization
+//! skips are reported by default, the rest when
+//! [`report_all_skips`](FilterOptions::report_all_skips) is set.
+
+use std::fmt;
+use std::path::{Component, Path};
+
+use crate::eol::is_binary;
+use crate::journal::STATE_DIR;
+use crate::vfs::Fs;
+
+/// Extensions of translation catalogs
+const LOCALIZATION_EXTENSIONS: &[&str] =
+    &["po", "pot", "xliff", "xlf", "arb", "strings", "stringsdict"];
+
+/// Directories whose data files hold translations (`locales/en.json`)
+const LOCALIZATION_DIRS: &[&str] = &["locales", "locale", "i18n", "l10n", "translations"];
+
+/// Data file extensions that count as translations inside [`LOCALIZATION_DIRS`]
+const LOCALIZATION_DATA_EXTENSIONS: &[&str] = &["json", "yaml", "yml", "properties"];
+
+/// Build output and dependency directories the text transformers don't descend into
+/// unless [`skip_dirs`](FilterOptions::skip_dirs) is changed
+pub const DEFAULT_SKIP_DIRS: &[&str] =
+    &["build", "__pycache__", "node_modules", "venv", ".venv", "target"];
+
+/// Transformers whose default extensions come from the shared table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtensionSet {
+    /// Case conversion (`convert`)
+    Convert,
+    /// Emoji transformation (`emojis`)
+    Emojis,
+    /// Whitespace cleaning and re-indentation (`clean`, `indent`)
+    Whitespace,
+}
+
+impl ExtensionSet {
+    /// The built-in extensions of this set
+    pub fn defaults(self) -> Vec<String> {
+        let extensions: &[&str] = match self {
+            ExtensionSet::Convert => &[
+                ".c", ".h", ".py", ".md", ".js", ".ts", ".java", ".cpp", ".hpp",
+            ],
+            ExtensionSet::Emojis => &[
+                ".md", ".txt", ".rst", ".org",
+                ".py", ".rs", ".go", ".java",
+                ".js", ".ts", ".jsx", ".tsx",
+                ".c", ".h", ".cpp", ".hpp",
+            ],
+            ExtensionSet::Whitespace => &[
+                ".py", ".pyx", ".pxd", ".pxi",
+                ".c", ".h", ".cpp", ".hpp",
+                ".rs", ".go", ".java",
+                ".js", ".ts", ".jsx", ".tsx",
+                ".md", ".qmd", ".txt",
+            ],
+        };
+        extensions.iter().map(|s| s.to_string()).collect()
+    }
+}
+
+/// Why a file was left alone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The file or one of its directories is hidden (`.git`, `.cache`)
+    Hidden,
+    /// Under a build or dependency directory (`target`, `node_modules`, ...)
+    SkipDir,
+    /// The extension isn't in the transformer's list
+    Extension,
+    /// The extension was removed with `exclude_extensions`
+    ExcludedExtension,
+    /// The path doesn't match the glob pattern
+    Glob,
+    /// The path matches an `exclude` glob
+    Excluded,
+    /// Makefiles can't be indented with spaces, nor YAML with tabs
+    IndentUnsafe,
+    /// The content looks binary (a NUL byte near the start)
+    Binary,
+    /// Translation or localization file, where text is user-facing
+    Localization,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Hidden => write!(f, "hidden file"),
+            SkipReason::SkipDir => write!(f, "file in skipped directory"),
+            SkipReason::Extension => write!(f, "file with unselected extension"),
+            SkipReason::ExcludedExtension => write!(f, "file with excluded extension"),
+            SkipReason::Glob => write!(f, "file not matching glob"),
+            SkipReason::Excluded => write!(f, "excluded file"),
+            SkipReason::IndentUnsafe => write!(f, "file whose indent style must not change"),
+            SkipReason::Binary => write!(f, "binary file"),
+            SkipReason::Localization => write!(f, "localization file"),
+        }
+    }
+}
+
+/// Filters applied on top of a transformer's extension list
+#[derive(Debug, Clone)]
+pub struct FilterOptions {
+    /// Skip translation files (`.po`, `.xliff`, `locales/*.json`, ...)
+    pub protect_localization: bool,
+    /// Extensions removed from the transformer's list (`.md`, or just `md`)
+    pub exclude_extensions: Vec<String>,
+    /// Report every skipped file with its reason, not just localization files
+    pub report_all_skips: bool,
+    /// Descend into hidden directories (`.github`, `.config`); `.git` stays skipped
+    pub include_hidden_dirs: bool,
+    /// Process hidden files (`.editorconfig`, `.env.example`)
+    pub include_hidden_files: bool,
+    /// Skip files whose name or path matches one of these globs (`**/migrations/**`)
+    pub exclude: Vec<glob::Pattern>,
+    /// Directories never descended into, by name; `.git` is always skipped
+    pub skip_dirs: Vec<String>,
+}
+
+impl Default for FilterOptions {
+    fn default() -> Self {
+        FilterOptions {
+            protect_localization: true,
+            exclude_extensions: Vec::new(),
+            report_all_skips: false,
+            include_hidden_dirs: false,
+            include_hidden_files: false,
+            exclude: Vec::new(),
+            skip_dirs: DEFAULT_SKIP_DIRS.iter().map(|dir| dir.to_string()).collect(),
+        }
+    }
+}
+
+impl FilterOptions {
+    /// Whether files with extension `ext` (`.md`) may be processed
+    ///
+    /// Excluded extensions are subtracted silently, as if they had never been in the
+    /// transformer's list.
+    pub fn allows_extension(&self, ext: &str) -> bool {
+        let ext = ext.trim_start_matches('.');
+        !self
+            .exclude_extensions
+            .iter()
+            .any(|excluded| excluded.trim_start_matches('.').eq_ignore_ascii_case(ext))
+    }
+
+    /// Checks `path`'s extension against the transformer's `extensions`
+    pub fn extension_skip(&self, path: &Path, extensions: &[String]) -> Option<SkipReason> {
+        let Some(ext) = path.extension() else {
+            return Some(SkipReason::Extension);
+        };
+        let ext = format!(".{}", ext.to_string_lossy());
+        if !extensions.contains(&ext) {
+            Some(SkipReason::Extension)
+        } else if !self.allows_extension(&ext) {
+            Some(SkipReason::ExcludedExtension)
+        } else {
+            None
+        }
+    }
+
+    /// Skips hidden files and files under hidden directories, unless included
+    pub fn hidden_skip(&self, path: &Path) -> Option<SkipReason> {
+        let hidden_file = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('.'));
+        let in_hidden_dir = path.parent().is_some_and(is_hidden);
+        if (hidden_file && !self.include_hidden_files)
+            || (in_hidden_dir && !self.include_hidden_dirs)
+        {
+            Some(SkipReason::Hidden)
+        } else {
+            None
+        }
+    }
+
+    /// Skips files under `.git`, refmt's own `.refmt` or one of the
+    /// [`skip_dirs`](Self::skip_dirs)
+    pub fn dir_skip(&self, path: &Path) -> Option<SkipReason> {
+        let parent = path.parent()?;
+        parent
+            .components()
+            .filter_map(|c| c.as_os_str().to_str())
+            .any(|name| {
+                name == ".git" || name == STATE_DIR || self.skip_dirs.iter().any(|dir| dir == name)
+            })
+            .then_some(SkipReason::SkipDir)
+    }
+
+    /// Returns why a transformer selecting `extensions` leaves `path` alone: it is
+    /// hidden, under a skipped directory, of another extension, or filtered out by
+    /// [`skip_reason`](Self::skip_reason)
+    pub fn path_skip(&self, path: &Path, extensions: &[String]) -> Option<SkipReason> {
+        self.hidden_skip(path)
+            .or_else(|| self.dir_skip(path))
+            .or_else(|| self.extension_skip(path, extensions))
+            .or_else(|| self.skip_reason(path))
+    }
+
+    /// Returns why `path` should be skipped, or `None` if it can be processed
+    pub fn skip_reason(&self, path: &Path) -> Option<SkipReason> {
+        if is_excluded(path, &self.exclude) {
+            return Some(SkipReason::Excluded);
+        }
+        if self.protect_localization && is_localization_file(path) {
+            return Some(SkipReason::Localization);
+        }
+        None
+    }
+
+    /// Whether a file skipped for `reason` should be reported
+    pub fn reports(&self, reason: SkipReason) -> bool {
+        self.report_all_skips || reason == SkipReason::Localization
+    }
+}
+
+/// Reads a file as text, or returns `None` if it looks binary
+pub(crate) fn read_text(fs: &dyn Fs, path: &Path) -> crate::Result<Option<String>> {
+    let bytes = fs.read(path)?;
+    if is_binary(&bytes) {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8(bytes)?))
+}
+
+/// Whether the file or one of its directories is hidden; `.` and `..` don't count
+pub(crate) fn is_hidden(path: &Path) -> bool {
+    path.components().any(|c| match c {
+        Component::Normal(name) => name.to_str().is_some_and(|s| s.starts_with('.')),
+        _ => false,
+    })
+}
+
+/// Whether the file name, or the path without a leading `./`, matches one of `patterns`
+pub(crate) fn is_excluded(path: &Path, patterns: &[glob::Pattern]) -> bool {
+    if patterns.is_empty() {
+        return false;
+    }
+    let name = path.file_name().map(|name| name.to_string_lossy());
+    let path = path.strip_prefix(".").unwrap_or(path);
+    patterns.iter().any(|pattern| {
+        pattern.matches_path(path) || name.as_deref().is_some_and(|name| pattern.matches(name))
+    })
+}
+
+/// Whether a file holds translations: a catalog (`.po`, `.xliff`, `.arb`, ...) or a
+/// data file under a `locales`, `i18n`, `l10n`, ... directory
+pub fn is_localization_file(path: &Path) -> bool {
+    let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+        return false;
+    };
+    let ext = ext.to_lowercase();
+    if LOCALIZATION_EXTENSIONS.contains(&ext.as_str()) {
+        return true;
+    }
+
+    LOCALIZATION_DATA_EXTENSIONS.contains(&ext.as_str())
+        && path.parent().is_some_and(|parent| {
+            parent.components().any(|c| {
+                c.as_os_str()
+                    .to_str()
+                    .is_some_and(|s| LOCALIZATION_DIRS.contains(&s.to_lowercase().as_str()))
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_localization_files() {
+        assert!(is_localization_file(Path::new("po/de.po")));
+        assert!(is_localization_file(Path::new("messages.XLIFF")));
+        assert!(is_localization_file(Path::new("src/locales/en.json")));
+        assert!(is_localization_file(Path::new("config/i18n/fr/app.yml")));
+        assert!(!is_localization_file(Path::new("src/locales/index.ts")));
+        assert!(!is_localization_file(Path::new("package.json")));
+
+        let mut filter = FilterOptions::default();
+        assert_eq!(
+            filter.skip_reason(Path::new("app/l10n/es.json")),
+            Some(SkipReason::Localization)
+        );
+        filter.protect_localization = false;
+        assert_eq!(filter.skip_reason(Path::new("app/l10n/es.json")), None);
+    }
+
+    #[test]
+    fn test_path_checks() {
+        assert!(is_hidden(Path::new("src/.cache/x.rs")));
+        assert!(!is_hidden(Path::new("./src/../lib.rs")));
+        let mut filter = FilterOptions {
+            exclude_extensions: vec![".md".to_string()],
+            ..FilterOptions::default()
+        };
+        let in_node_modules = Path::new("app/node_modules/pkg/index.js");
+        assert_eq!(filter.dir_skip(in_node_modules), Some(SkipReason::SkipDir));
+        assert_eq!(filter.dir_skip(Path::new("src/build.rs")), None);
+        filter.skip_dirs = vec!["vendor".to_string()];
+        assert_eq!(filter.dir_skip(in_node_modules), None);
+        assert_eq!(filter.dir_skip(Path::new("vendor/lib.c")), Some(SkipReason::SkipDir));
+        assert_eq!(filter.dir_skip(Path::new("repo/.git/config")), Some(SkipReason::SkipDir));
+
+        let extensions = [".md".to_string(), ".rs".to_string()];
+        assert_eq!(filter.extension_skip(Path::new("a.rs"), &extensions), None);
+        assert_eq!(
+            filter.extension_skip(Path::new("a.md"), &extensions),
+            Some(SkipReason::ExcludedExtension)
+        );
+        assert_eq!(
+            filter.extension_skip(Path::new("Makefile"), &extensions),
+            Some(SkipReason::Extension)
+        );
+        assert!(!filter.reports(SkipReason::Extension));
+        assert!(filter.reports(SkipReason::Localization));
+    }
+
+    #[test]
+    fn test_exclude_globs() {
+        let filter = FilterOptions {
+            exclude: vec![
+                glob::Pattern::new("**/migrations/**").unwrap(),
+                glob::Pattern::new("*.min.js").unwrap(),
+            ],
+            ..FilterOptions::default()
+        };
+        assert_eq!(
+            filter.skip_reason(Path::new("./app/migrations/0001_init.py")),
+            Some(SkipReason::Excluded)
+        );
+        assert_eq!(
+            filter.skip_reason(Path::new("static/vendor.min.js")),
+            Some(SkipReason::Excluded)
+        );
+        assert_eq!(filter.skip_reason(Path::new("app/models.py")), None);
+    }
+
+    #[test]
+    fn test_hidden_skip() {
+        let workflow = Path::new(".github/workflows/README.md");
+        let dotfile = Path::new("src/.editorconfig");
+        let mut filter = FilterOptions::default();
+        assert_eq!(filter.hidden_skip(workflow), Some(SkipReason::Hidden));
+        assert_eq!(filter.hidden_skip(dotfile), Some(SkipReason::Hidden));
+        assert_eq!(filter.hidden_skip(Path::new("./docs/a.md")), None);
+
+        filter.include_hidden_dirs = true;
+        assert_eq!(filter.hidden_skip(workflow), None);
+        assert_eq!(filter.hidden_skip(Path::new(".config/.env")), Some(SkipReason::Hidden));
+
+        filter.include_hidden_files = true;
+        assert_eq!(filter.hidden_skip(dotfile), None);
+        assert_eq!(filter.hidden_skip(Path::new(".config/.env")), None);
+    }
+
+    #[test]
+    fn test_exclude_extensions() {
+        let mut filter = FilterOptions::default();
+        assert!(filter.allows_extension(".md"));
+
+        filter.exclude_extensions = vec![".md".to_string(), "RST".to_string()];
+        assert!(!filter.allows_extension(".md"));
+        assert!(!filter.allows_extension(".rst"));
+        assert!(filter.allows_extension(".py"));
+    }
+}
@@ -0,0 +1,249 @@
+//! Line ranges modified according to git
+//!
+//! Asks `git diff --unified=0` which lines were added or changed, so transforms can be
+//! limited to them and leave the rest of a legacy file alone. [`uncommitted_changes`]
+//! lets callers refuse to rewrite a tree with work in progress, and [`hooks_dir`] finds
+//! where to install a git hook.
+
+use std::collections::HashMap;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::{Context, Error};
+
+/// A set of 1-based line numbers
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineRanges {
+    ranges: Vec<RangeInclusive<usize>>,
+}
+
+impl LineRanges {
+    /// Every line of a file
+    pub fn all() -> Self {
+        LineRanges {
+            ranges: vec![1..=usize::MAX],
+        }
+    }
+
+    /// Returns `true` if `line` (1-based) is in the set
+    pub fn contains(&self, line: usize) -> bool {
+        self.ranges.iter().any(|range| range.contains(&line))
+    }
+
+    fn push(&mut self, range: RangeInclusive<usize>) {
+        self.ranges.push(range);
+    }
+}
+
+impl FromIterator<RangeInclusive<usize>> for LineRanges {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<usize>>>(iter: I) -> Self {
+        LineRanges {
+            ranges: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Lines changed in a git working tree, per file
+#[derive(Debug, Clone, Default)]
+pub struct ChangedLines {
+    files: HashMap<PathBuf, LineRanges>,
+}
+
+impl ChangedLines {
+    /// Collects the lines changed under `path`
+    ///
+    /// Compares the working tree against `since` (default: the index), or the index
+    /// against `since` (default: `HEAD`) when `staged` is set. Untracked files count as
+    /// entirely changed unless `staged` is set.
+    pub fn from_git(path: &Path, since: Option<&str>, staged: bool) -> crate::Result<Self> {
+        let path = fs::canonicalize(path)
+            .with_context(|| format!("Cannot access '{}'", path.display()))?;
+        let dir = if path.is_dir() {
+            path.as_path()
+        } else {
+            path.parent().unwrap_or(Path::new("/"))
+        };
+
+        let root = git(dir, &["rev-parse", "--show-toplevel"])?;
+        let root = fs::canonicalize(root.trim())?;
+
+        let mut args = vec![
+            "-c", "core.quotePath=off", "diff", "--unified=0", "--no-color", "--no-ext-diff",
+        ];
+        if staged {
+            args.push("--cached");
+        }
+        if let Some(since) = since {
+            args.push(since);
+        }
+        let path_arg = path.to_string_lossy();
+        args.extend(["--", path_arg.as_ref()]);
+
+        let mut changed = ChangedLines::parse_diff(&git(&root, &args)?, &root);
+
+        if !staged {
+            let untracked = git(
+                &root,
+                &[
+                    "-c", "core.quotePath=off", "ls-files", "--others", "--exclude-standard",
+                    "--full-name", "--", path_arg.as_ref(),
+                ],
+            )?;
+            for file in untracked.lines().filter(|l| !l.is_empty()) {
+                changed.files.insert(root.join(file), LineRanges::all());
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Parses the output of `git diff --unified=0`, resolving file names against `root`
+    pub fn parse_diff(diff: &str, root: &Path) -> Self {
+        let mut files = HashMap::new();
+        let mut current: Option<&mut LineRanges> = None;
+
+        for line in diff.lines() {
+            if let Some(target) = line.strip_prefix("+++ ") {
+                current = target
+                    .strip_prefix("b/")
+                    .map(|name| files.entry(root.join(name)).or_insert_with(LineRanges::default));
+            } else if let Some(hunk) = line.strip_prefix("@@ ") {
+                if let (Some(ranges), Some(range)) = (current.as_deref_mut(), parse_hunk(hunk)) {
+                    ranges.push(range);
+                }
+            }
+        }
+
+        ChangedLines { files }
+    }
+
+    /// Changed lines of the file at `path`, or `None` if it is unchanged
+    ///
+    /// `path` is resolved to an absolute path first, so it may be relative to the
+    /// current directory.
+    pub fn get(&self, path: &Path) -> Option<&LineRanges> {
+        let path = fs::canonicalize(path).ok()?;
+        self.files.get(&path).filter(|ranges| !ranges.ranges.is_empty())
+    }
+}
+
+/// Lists the uncommitted changes in the repository containing `path`: modified,
+/// staged and untracked files, as `git status --porcelain` lines
+///
+/// Returns `None` when `path` isn't inside a git work tree.
+pub fn uncommitted_changes(path: &Path) -> crate::Result<Option<Vec<String>>> {
+    let path = fs::canonicalize(path)
+        .with_context(|| format!("Cannot access '{}'", path.display()))?;
+    let dir = if path.is_dir() {
+        path.as_path()
+    } else {
+        path.parent().unwrap_or(Path::new("/"))
+    };
+
+    let inside = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .output()
+        .context("Failed to run git")?;
+    if !inside.status.success() || String::from_utf8_lossy(&inside.stdout).trim() != "true" {
+        return Ok(None);
+    }
+
+    let status = git(dir, &["-c", "core.quotePath=off", "status", "--porcelain"])?;
+    Ok(Some(
+        status
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect(),
+    ))
+}
+
+/// The hooks directory of the repository containing `path`
+///
+/// Honors `core.hooksPath` and linked work trees, like git itself.
+pub fn hooks_dir(path: &Path) -> crate::Result<PathBuf> {
+    let dir = if path.is_dir() {
+        path
+    } else {
+        path.parent().unwrap_or(Path::new("."))
+    };
+    let hooks = git(dir, &["rev-parse", "--git-path", "hooks"])?;
+    Ok(dir.join(hooks.trim()))
+}
+
+/// New-file line range of a hunk header (`-12,3 +14,2 @@ ...`)
+///
+/// Returns `None` for hunks that only delete lines.
+fn parse_hunk(hunk: &str) -> Option<RangeInclusive<usize>> {
+    let new = hunk.split_whitespace().find_map(|part| part.strip_prefix('+'))?;
+    let (start, count) = match new.split_once(',') {
+        Some((start, count)) => (start.parse::<usize>().ok()?, count.parse::<usize>().ok()?),
+        None => (new.parse::<usize>().ok()?, 1),
+    };
+
+    if count == 0 {
+        None
+    } else {
+        Some(start..=start + count - 1)
+    }
+}
+
+/// Runs git in `dir`, returning its standard output
+fn git(dir: &Path, args: &[&str]) -> crate::Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+
+    if !output.status.success() {
+        return Err(Error::Command(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hunk() {
+        assert_eq!(parse_hunk("-1,2 +3,4 @@ fn main()"), Some(3..=6));
+        assert_eq!(parse_hunk("-7 +7 @@"), Some(7..=7));
+        assert_eq!(parse_hunk("-5,2 +4,0 @@"), None);
+    }
+
+    #[test]
+    fn test_parse_diff() {
+        let diff = concat!(
+            "diff --git a/src/a.py b/src/a.py\n",
+            "--- a/src/a.py\n",
+            "+++ b/src/a.py\n",
+            "@@ -2 +2 @@ def f():\n",
+            "-    x = 1\n",
+            "+    x = 2  \n",
+            "@@ -10,0 +11,3 @@\n",
+            "diff --git a/gone.py b/gone.py\n",
+            "--- a/gone.py\n",
+            "+++ /dev/null\n",
+            "@@ -1,3 +0,0 @@\n",
+        );
+        let changed = ChangedLines::parse_diff(diff, Path::new("/repo"));
+        let ranges = &changed.files[Path::new("/repo/src/a.py")];
+
+        assert!(ranges.contains(2));
+        assert!(!ranges.contains(3));
+        assert!(ranges.contains(11) && ranges.contains(13));
+        assert!(!ranges.contains(14));
+        assert_eq!(changed.files.len(), 1);
+    }
+}
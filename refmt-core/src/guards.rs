@@ -0,0 +1,377 @@
+//! Include guard renaming for C/C++ headers
+//!
+//! Include guards are usually derived from the header's file name, so they go stale
+//! when files are renamed or a project changes naming conventions. This transform
+//! derives the guard from the current file name in a chosen case format and rewrites
+//! the `#ifndef`/`#define`/`#endif` trio. Headers using `#pragma once`, or without a
+//! guard at the top of the file, are left alone.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use regex::Regex;
+
+use crate::case::{identifier_words, CaseFormat};
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::report::{report, EventSink};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// Options for include guard renaming
+#[derive(Debug, Clone)]
+pub struct GuardOptions {
+    /// Case format of the guard macro (kebab formats use `_` so the macro stays valid)
+    pub format: CaseFormat,
+    /// Prefix for every guard (e.g., the project name)
+    pub prefix: String,
+    /// Suffix for every guard (e.g., `_` or `_INCLUDED`)
+    pub suffix: String,
+    /// Include the header's directories, relative to the processed root, in the guard
+    pub path_components: bool,
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for GuardOptions {
+    fn default() -> Self {
+        GuardOptions {
+            format: CaseFormat::ScreamingSnakeCase,
+            prefix: String::new(),
+            suffix: String::new(),
+            path_components: false,
+            file_extensions: [".h", ".hpp", ".hh", ".hxx"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// A guard rename applied to one header
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuardChange {
+    pub old: String,
+    pub new: String,
+}
+
+/// Include guard rewriter for C/C++ headers
+pub struct GuardRenamer {
+    options: GuardOptions,
+    ifndef_pattern: Regex,
+    define_pattern: Regex,
+    endif_pattern: Regex,
+    walker: Walker,
+}
+
+impl GuardRenamer {
+    /// Creates a new guard renamer with the given options
+    pub fn new(options: GuardOptions) -> Self {
+        GuardRenamer {
+            options,
+            ifndef_pattern: Regex::new(r"^\s*#\s*ifndef\s+(\w+)\s*$").unwrap(),
+            define_pattern: Regex::new(r"^\s*#\s*define\s+(\w+)\s*$").unwrap(),
+            endif_pattern: Regex::new(r"^\s*#\s*endif\b").unwrap(),
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a renamer with default options
+    pub fn with_defaults() -> Self {
+        Self::new(GuardOptions::default())
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        if !self.options.fs.is_file(path) {
+            return false;
+        }
+
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none()
+    }
+
+    /// Derives the guard macro for a header
+    ///
+    /// `relative` is the header's path relative to the processed root; its directories
+    /// are only used when `path_components` is set.
+    pub fn guard_name(&self, relative: &Path) -> String {
+        let mut words = Vec::new();
+
+        if self.options.path_components {
+            if let Some(parent) = relative.parent() {
+                for dir in parent.components() {
+                    words.extend(identifier_words(&dir.as_os_str().to_string_lossy()));
+                }
+            }
+        }
+        if let Some(name) = relative.file_name() {
+            words.extend(identifier_words(&name.to_string_lossy()));
+        }
+
+        let guard = self
+            .options
+            .format
+            .join_words(&words, &self.options.prefix, &self.options.suffix);
+        guard.replace('-', "_")
+    }
+
+    /// Rewrites the include guard in `content` to `guard`
+    ///
+    /// Returns the new content and the old guard, or `None` if the file has no guard
+    /// or it is already correct.
+    pub fn rewrite_str(&self, content: &str, guard: &str) -> Option<(String, String)> {
+        let lines: Vec<&str> = content.split_inclusive('\n').collect();
+
+        let ifndef = first_code_line(&lines)?;
+        let old = self
+            .ifndef_pattern
+            .captures(lines[ifndef].trim_end())?
+            .get(1)?
+            .as_str()
+            .to_string();
+
+        let define = (ifndef + 1..lines.len()).find(|&i| !lines[i].trim().is_empty())?;
+        let defined = self.define_pattern.captures(lines[define].trim_end())?;
+        if defined.get(1)?.as_str() != old {
+            return None;
+        }
+
+        let endif = (0..lines.len()).rev().find(|&i| !lines[i].trim().is_empty())?;
+        if endif <= define || !self.endif_pattern.is_match(lines[endif]) {
+            return None;
+        }
+
+        if old == guard {
+            return None;
+        }
+
+        let word = Regex::new(&format!(r"\b{}\b", regex::escape(&old))).unwrap();
+        let mut out = String::with_capacity(content.len() + guard.len() * 3);
+        for (i, line) in lines.iter().enumerate() {
+            if i == ifndef || i == define || i == endif {
+                out.push_str(&word.replace(line, guard));
+            } else {
+                out.push_str(line);
+            }
+        }
+
+        Some((out, old))
+    }
+
+    /// Rewrites the include guard of a single header
+    ///
+    /// `root` is the directory being processed, used for `path_components`.
+    pub fn rename_file(&self, path: &Path, root: &Path) -> crate::Result<Option<GuardChange>> {
+        let change = self.apply_to_file(path, root)?;
+        self.report(path, change.as_ref());
+        Ok(change)
+    }
+
+    /// Rewrites a header without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path, root: &Path) -> crate::Result<Option<GuardChange>> {
+        if !self.should_process(path) {
+            return Ok(None);
+        }
+
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let guard = self.guard_name(relative);
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        let (rewritten, old) = match self.rewrite_str(&content, &guard) {
+            Some(result) => result,
+            None => return Ok(None),
+        };
+
+        if !self.options.dry_run {
+            self.options.fs.write(path, rewritten.as_bytes())?;
+        }
+
+        Ok(Some(GuardChange { old, new: guard }))
+    }
+
+    fn report(&self, path: &Path, change: Option<&GuardChange>) {
+        let Some(change) = change else {
+            return;
+        };
+
+        self.options.events.file_changed(path, 1, self.options.dry_run);
+        let verb = if self.options.dry_run {
+            "Would rename"
+        } else {
+            "Renamed"
+        };
+        report!(
+            "{} guard {} -> {} in '{}'",
+            verb,
+            change.old,
+            change.new,
+            path.display()
+        );
+    }
+
+    /// Processes a directory or file, returning the number of guards renamed
+    pub fn process(&self, path: &Path) -> crate::Result<usize> {
+        let (files, root) = if self.options.fs.is_file(path) {
+            let root = path.parent().unwrap_or(Path::new(""));
+            (vec![path.to_path_buf()], root)
+        } else if self.options.fs.is_dir(path) {
+            (
+                self.walker.walk(
+                    self.options.fs.as_ref(),
+                    path,
+                    self.options.recursive,
+                    self.options.walk,
+                )?,
+                path,
+            )
+        } else {
+            (Vec::new(), path)
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.apply_to_file(file, root)
+        });
+
+        // Report in path order once the parallel work is done
+        let mut renamed = 0;
+        for (file_path, result) in files.iter().zip(results) {
+            let change = result?;
+            self.report(file_path, change.as_ref());
+            if change.is_some() {
+                renamed += 1;
+            }
+        }
+
+        Ok(renamed)
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+/// Index of the first line that isn't blank or a comment
+fn first_code_line(lines: &[&str]) -> Option<usize> {
+    let mut in_block = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if in_block {
+            if trimmed.contains("*/") {
+                in_block = false;
+            }
+            continue;
+        }
+        if trimmed.is_empty() || trimmed.starts_with("//") {
+            continue;
+        }
+        if trimmed.starts_with("/*") {
+            in_block = !trimmed.contains("*/");
+            continue;
+        }
+        return Some(i);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_guard_name() {
+        let renamer = GuardRenamer::with_defaults();
+        assert_eq!(renamer.guard_name(Path::new("my_file.h")), "MY_FILE_H");
+        assert_eq!(renamer.guard_name(Path::new("HTTPServer.hpp")), "HTTP_SERVER_HPP");
+        assert_eq!(renamer.guard_name(Path::new("net/socket-io.h")), "SOCKET_IO_H");
+
+        let options = GuardOptions {
+            prefix: "ACME_".to_string(),
+            suffix: "_".to_string(),
+            path_components: true,
+            ..GuardOptions::default()
+        };
+        let renamer = GuardRenamer::new(options);
+        assert_eq!(renamer.guard_name(Path::new("net/socketIo.h")), "ACME_NET_SOCKET_IO_H_");
+
+        let options = GuardOptions {
+            format: CaseFormat::KebabCase,
+            ..GuardOptions::default()
+        };
+        let renamer = GuardRenamer::new(options);
+        assert_eq!(renamer.guard_name(Path::new("my_file.h")), "my_file_h");
+    }
+
+    #[test]
+    fn test_rewrite_trio() {
+        let renamer = GuardRenamer::with_defaults();
+        let content = "/* Copyright */\n#ifndef OLD_NAME_H\n#define OLD_NAME_H\n\nint f(void);\n\n#endif // OLD_NAME_H\n";
+        let (result, old) = renamer.rewrite_str(content, "NEW_NAME_H").unwrap();
+
+        assert_eq!(old, "OLD_NAME_H");
+        assert_eq!(
+            result,
+            "/* Copyright */\n#ifndef NEW_NAME_H\n#define NEW_NAME_H\n\nint f(void);\n\n#endif // NEW_NAME_H\n"
+        );
+
+        // Already correct
+        assert_eq!(renamer.rewrite_str(&result, "NEW_NAME_H"), None);
+    }
+
+    #[test]
+    fn test_no_guard_untouched() {
+        let renamer = GuardRenamer::with_defaults();
+        assert_eq!(renamer.rewrite_str("#pragma once\nint f(void);\n", "X_H"), None);
+        // `#ifndef` that isn't followed by a matching `#define` isn't a guard
+        assert_eq!(
+            renamer.rewrite_str("#ifndef DEBUG\n#define NDEBUG\n#endif\n", "X_H"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_process_renamed_header() {
+        let test_dir = std::env::temp_dir().join("refmt_guards_process");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(test_dir.join("util")).unwrap();
+
+        let header = test_dir.join("util").join("strBuf.h");
+        fs::write(&header, "#ifndef STRING_BUFFER_H\n#define STRING_BUFFER_H\n#endif\n").unwrap();
+
+        let options = GuardOptions {
+            path_components: true,
+            ..GuardOptions::default()
+        };
+        let renamed = GuardRenamer::new(options).process(&test_dir).unwrap();
+
+        assert_eq!(renamed, 1);
+        assert_eq!(
+            fs::read_to_string(&header).unwrap(),
+            "#ifndef UTIL_STR_BUF_H\n#define UTIL_STR_BUF_H\n#endif\n"
+        );
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+}
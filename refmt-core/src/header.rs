@@ -0,0 +1,721 @@
+//! License/copyright header management
+//!
+//! Headers are rendered from a template and written as a comment using the comment
+//! syntax of each file's extension. An existing header is the comment block at the
+//! top of the file (after any shebang) that mentions a copyright or an SPDX license
+//! identifier; it is updated in place, so a header is never inserted twice.
+//!
+//! [`HeaderAction::Spdx`] only enforces the `SPDX-License-Identifier:` line: a missing
+//! one is inserted at the top of the file, a different one is updated, and the rest of
+//! the header is left alone.
+
+use regex::Regex;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::eol::split_bom;
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::report::{report, EventSink, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// Default header template
+pub const DEFAULT_TEMPLATE: &str = "Copyright (c) {year} {holder}";
+
+const SPDX_TAG: &str = "SPDX-License-Identifier:";
+
+/// What to do with headers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HeaderAction {
+    /// Insert missing headers and update outdated ones
+    Apply,
+    /// Remove existing headers
+    Remove,
+    /// Ensure an `SPDX-License-Identifier` line for the configured license
+    Spdx,
+}
+
+/// Options for header management
+#[derive(Debug, Clone)]
+pub struct HeaderOptions {
+    /// Header text; `{year}`, `{holder}` and `{license}` are substituted
+    pub template: String,
+    /// Copyright holder substituted for `{holder}`
+    pub holder: String,
+    /// License identifier substituted for `{license}`
+    pub license: String,
+    /// Year substituted for `{year}` (an existing header's first year is kept as a range)
+    pub year: u64,
+    /// Insert/update or remove headers
+    pub action: HeaderAction,
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for HeaderOptions {
+    fn default() -> Self {
+        HeaderOptions {
+            template: DEFAULT_TEMPLATE.to_string(),
+            holder: String::new(),
+            license: String::new(),
+            year: current_year(),
+            action: HeaderAction::Apply,
+            file_extensions: vec![
+                ".rs", ".c", ".h", ".cpp", ".hpp",
+                ".go", ".java", ".js", ".ts", ".jsx", ".tsx",
+                ".py", ".sh", ".rb",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// Statistics from header processing
+#[derive(Debug, Default)]
+pub struct HeaderStats {
+    /// Number of files that received a header
+    pub inserted: usize,
+    /// Number of files whose header was updated
+    pub updated: usize,
+    /// Number of files whose header was removed
+    pub removed: usize,
+}
+
+impl HeaderStats {
+    /// Total number of files changed
+    pub fn total(&self) -> usize {
+        self.inserted + self.updated + self.removed
+    }
+}
+
+/// Comment syntax for a language
+#[derive(Debug, Clone, Copy)]
+struct CommentSyntax {
+    /// Line comment marker, preferred for rendering when present
+    line: Option<&'static str>,
+    /// Block comment delimiters and the prefix used for lines inside the block
+    block: Option<(&'static str, &'static str, &'static str)>,
+}
+
+const C_BLOCK: (&str, &str, &str) = ("/*", " * ", " */");
+const HTML_BLOCK: (&str, &str, &str) = ("<!--", "  ", "-->");
+
+/// Returns the comment syntax for a file extension (without the dot)
+fn comment_syntax(extension: &str) -> Option<CommentSyntax> {
+    let (line, block) = match extension.to_lowercase().as_str() {
+        "rs" | "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "go" | "java" | "js" | "jsx"
+        | "ts" | "tsx" | "kt" | "swift" | "cs" | "scala" | "dart" => (Some("//"), Some(C_BLOCK)),
+        "py" | "pyx" | "sh" | "bash" | "rb" | "pl" | "r" | "yaml" | "yml" | "toml" => {
+            (Some("#"), None)
+        }
+        "sql" | "lua" | "hs" => (Some("--"), None),
+        "css" | "scss" => (None, Some(C_BLOCK)),
+        "html" | "xml" | "svg" => (None, Some(HTML_BLOCK)),
+        _ => return None,
+    };
+    Some(CommentSyntax { line, block })
+}
+
+/// Kind of change made to a file
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HeaderChange {
+    Inserted,
+    Updated,
+    Removed,
+}
+
+/// Inserts, updates and removes license/copyright headers
+pub struct HeaderManager {
+    options: HeaderOptions,
+    year_pattern: Regex,
+    walker: Walker,
+}
+
+impl HeaderManager {
+    /// Creates a new header manager with the given options
+    pub fn new(options: HeaderOptions) -> Self {
+        HeaderManager {
+            options,
+            year_pattern: Regex::new(r"\b((?:19|20)\d{2})\b").unwrap(),
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a header manager with default options
+    pub fn with_defaults() -> Self {
+        HeaderManager::new(HeaderOptions::default())
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        if !self.options.fs.is_file(path) {
+            return false;
+        }
+
+        // Only extensions that are configured and have a known comment syntax
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none()
+            && path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|ext| comment_syntax(ext).is_some())
+    }
+
+    /// Applies the configured action to `content`, using the comment syntax for `extension`
+    ///
+    /// Returns `None` when the content is already as requested.
+    pub fn transform_str(&self, content: &str, extension: &str) -> Option<String> {
+        let syntax = comment_syntax(extension)?;
+        self.transform(content, syntax).map(|(modified, _)| modified)
+    }
+
+    fn transform(&self, content: &str, syntax: CommentSyntax) -> Option<(String, HeaderChange)> {
+        // A byte order mark stays in front of the header
+        let (bom, content) = split_bom(content);
+        let (modified, change) = self.transform_body(content, syntax)?;
+        Some((format!("{}{}", bom, modified), change))
+    }
+
+    /// Applies the configured action to `content`, which has no byte order mark
+    fn transform_body(&self, content: &str, syntax: CommentSyntax) -> Option<(String, HeaderChange)> {
+        let eol = if content.contains("\r\n") { "\r\n" } else { "\n" };
+        if self.options.action == HeaderAction::Spdx {
+            return self.apply_spdx(content, syntax, eol);
+        }
+        let existing = find_header(content, syntax);
+
+        match (self.options.action, existing) {
+            (HeaderAction::Apply, Some(range)) => {
+                let header = render_comment(&self.render_text(Some(&content[range.clone()])), syntax, eol);
+                if content[range.clone()] == header {
+                    return None;
+                }
+                let modified = format!("{}{}{}", &content[..range.start], header, &content[range.end..]);
+                Some((modified, HeaderChange::Updated))
+            }
+            (HeaderAction::Apply, None) => {
+                let header = render_comment(&self.render_text(None), syntax, eol);
+                let start = shebang_len(content);
+                let (shebang, body) = content.split_at(start);
+
+                let mut modified = String::with_capacity(content.len() + header.len() + 2);
+                modified.push_str(shebang);
+                if !shebang.is_empty() && !shebang.ends_with('\n') {
+                    modified.push_str(eol);
+                }
+                modified.push_str(&header);
+                // Separate the header from the code with a blank line
+                if !body.is_empty() && !body.starts_with('\n') && !body.starts_with("\r\n") {
+                    modified.push_str(eol);
+                }
+                modified.push_str(body);
+                Some((modified, HeaderChange::Inserted))
+            }
+            (HeaderAction::Remove, Some(range)) => {
+                // Also drop the blank line that separated the header from the code
+                let rest = &content[range.end..];
+                let rest = rest
+                    .strip_prefix("\r\n")
+                    .or_else(|| rest.strip_prefix('\n'))
+                    .unwrap_or(rest);
+                Some((format!("{}{}", &content[..range.start], rest), HeaderChange::Removed))
+            }
+            (HeaderAction::Remove, None) => None,
+            (HeaderAction::Spdx, _) => unreachable!("handled above"),
+        }
+    }
+
+    /// Inserts or updates the `SPDX-License-Identifier` line
+    fn apply_spdx(
+        &self,
+        content: &str,
+        syntax: CommentSyntax,
+        eol: &str,
+    ) -> Option<(String, HeaderChange)> {
+        let license = self.options.license.trim();
+
+        if let Some(range) = find_spdx(content, syntax) {
+            if &content[range.clone()] == license {
+                return None;
+            }
+            let modified = format!("{}{}{}", &content[..range.start], license, &content[range.end..]);
+            return Some((modified, HeaderChange::Updated));
+        }
+
+        let text = format!("{} {}", SPDX_TAG, license);
+        let line = match (syntax.line, syntax.block) {
+            (Some(marker), _) => format!("{} {}{}", marker, text, eol),
+            (None, Some((open, _, close))) => format!("{} {} {}{}", open, text, close.trim_start(), eol),
+            (None, None) => return None,
+        };
+
+        let (preamble, body) = content.split_at(preamble_len(content));
+        let mut modified = String::with_capacity(content.len() + line.len() + 2);
+        modified.push_str(preamble);
+        if !preamble.is_empty() && !preamble.ends_with('\n') {
+            modified.push_str(eol);
+        }
+        modified.push_str(&line);
+        // An existing header comment follows directly; code gets a blank line
+        let next = body.trim_start_matches([' ', '\t']);
+        if !body.is_empty() && !next.starts_with(['\r', '\n']) && !starts_with_comment(next, syntax) {
+            modified.push_str(eol);
+        }
+        modified.push_str(body);
+        Some((modified, HeaderChange::Inserted))
+    }
+
+    /// Renders the header template, keeping the first year of an existing header as a range
+    fn render_text(&self, existing: Option<&str>) -> String {
+        let current = self.options.year;
+        let first_year = existing
+            .and_then(|header| self.year_pattern.captures(header))
+            .and_then(|caps| caps[1].parse::<u64>().ok());
+
+        let year = match first_year {
+            Some(first) if first < current => format!("{}-{}", first, current),
+            _ => current.to_string(),
+        };
+
+        self.options
+            .template
+            .trim_end()
+            .replace("{year}", &year)
+            .replace("{holder}", &self.options.holder)
+            .replace("{license}", &self.options.license)
+    }
+
+    /// Applies the configured action to a single file
+    pub fn process_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        let change = self.apply_to_file(path)?;
+        self.report(path, change);
+        Ok(match change {
+            Some(_) => FileOutcome::changed(1),
+            None => FileOutcome::unchanged(),
+        })
+    }
+
+    /// Processes a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path) -> crate::Result<Option<HeaderChange>> {
+        if !self.should_process(path) {
+            return Ok(None);
+        }
+
+        let syntax = match path.extension().and_then(|e| e.to_str()).and_then(comment_syntax) {
+            Some(syntax) => syntax,
+            None => return Ok(None),
+        };
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        let (modified, change) = match self.transform(&content, syntax) {
+            Some(result) => result,
+            None => return Ok(None),
+        };
+
+        if !self.options.dry_run {
+            self.options.fs.write(path, modified.as_bytes())?;
+        }
+
+        Ok(Some(change))
+    }
+
+    fn report(&self, path: &Path, change: Option<HeaderChange>) {
+        let spdx = self.options.action == HeaderAction::Spdx;
+        let message = match (change, self.options.dry_run) {
+            (None, _) => return,
+            (Some(HeaderChange::Inserted), false) if spdx => "Added SPDX identifier to",
+            (Some(HeaderChange::Inserted), true) if spdx => "Would add SPDX identifier to",
+            (Some(HeaderChange::Updated), false) if spdx => "Updated SPDX identifier in",
+            (Some(HeaderChange::Updated), true) if spdx => "Would update SPDX identifier in",
+            (Some(HeaderChange::Inserted), false) => "Added header to",
+            (Some(HeaderChange::Inserted), true) => "Would add header to",
+            (Some(HeaderChange::Updated), false) => "Updated header in",
+            (Some(HeaderChange::Updated), true) => "Would update header in",
+            (Some(HeaderChange::Removed), false) => "Removed header from",
+            (Some(HeaderChange::Removed), true) => "Would remove header from",
+        };
+        self.options.events.file_changed(path, 1, self.options.dry_run);
+        report!("{} '{}'", message, path.display());
+    }
+
+    /// Processes a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<HeaderStats> {
+        let mut stats = HeaderStats::default();
+
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.apply_to_file(file)
+        });
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let change = result?;
+            self.report(file_path, change);
+            match change {
+                Some(HeaderChange::Inserted) => stats.inserted += 1,
+                Some(HeaderChange::Updated) => stats.updated += 1,
+                Some(HeaderChange::Removed) => stats.removed += 1,
+                None => {}
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+/// Length of the shebang line (including its newline), or 0
+fn shebang_len(content: &str) -> usize {
+    if !content.starts_with("#!") {
+        return 0;
+    }
+    content.find('\n').map(|i| i + 1).unwrap_or(content.len())
+}
+
+/// Length of the lines that must stay first: a shebang, then a Python encoding
+/// declaration or an XML declaration
+fn preamble_len(content: &str) -> usize {
+    let mut end = shebang_len(content);
+    if let Some(line) = content[end..].split_inclusive('\n').next() {
+        let is_coding = line.starts_with('#') && line.contains("coding") && line.contains([':', '=']);
+        if is_coding || line.starts_with("<?xml") {
+            end += line.len();
+        }
+    }
+    end
+}
+
+/// Whether `text` starts with a comment in the given syntax
+fn starts_with_comment(text: &str, syntax: CommentSyntax) -> bool {
+    syntax.line.is_some_and(|marker| text.starts_with(marker))
+        || syntax.block.is_some_and(|(open, _, _)| text.starts_with(open))
+}
+
+/// Locates the license expression of an `SPDX-License-Identifier` line among the
+/// comments at the top of `content`
+fn find_spdx(content: &str, syntax: CommentSyntax) -> Option<Range<usize>> {
+    let mut offset = preamble_len(content);
+    let mut in_block = false;
+
+    for line in content[offset..].split_inclusive('\n') {
+        let trimmed = line.trim();
+        if !in_block && !trimmed.is_empty() && !starts_with_comment(trimmed, syntax) {
+            return None;
+        }
+        if let Some((open, _, close)) = syntax.block {
+            if in_block || trimmed.starts_with(open) {
+                let after_open = if in_block { trimmed } else { &trimmed[open.len()..] };
+                in_block = !after_open.contains(close);
+            }
+        }
+
+        if let Some(pos) = line.find(SPDX_TAG) {
+            let value = &line[pos + SPDX_TAG.len()..];
+            let value = match syntax.block {
+                Some((_, _, close)) => value.trim_end().strip_suffix(close.trim_start()).unwrap_or(value),
+                None => value,
+            };
+            let start = offset + pos + SPDX_TAG.len() + (value.len() - value.trim_start().len());
+            return Some(start..start + value.trim().len());
+        }
+        offset += line.len();
+    }
+
+    None
+}
+
+/// Renders header text as a comment block ending with a line break
+fn render_comment(text: &str, syntax: CommentSyntax, eol: &str) -> String {
+    let mut out = String::new();
+
+    match (syntax.line, syntax.block) {
+        (Some(marker), _) => {
+            for line in text.lines() {
+                out.push_str(marker);
+                if !line.is_empty() {
+                    out.push(' ');
+                    out.push_str(line);
+                }
+                out.push_str(eol);
+            }
+        }
+        (None, Some((open, prefix, close))) => {
+            out.push_str(open);
+            out.push_str(eol);
+            for line in text.lines() {
+                out.push_str(format!("{}{}", prefix, line).trim_end());
+                out.push_str(eol);
+            }
+            out.push_str(close);
+            out.push_str(eol);
+        }
+        (None, None) => {}
+    }
+
+    out
+}
+
+/// Locates an existing header comment at the top of `content`
+fn find_header(content: &str, syntax: CommentSyntax) -> Option<Range<usize>> {
+    let start = shebang_len(content);
+    let first_line = content[start..].split_inclusive('\n').next()?.trim_start();
+
+    let mut end = start;
+    if let Some(marker) = syntax.line.filter(|m| first_line.starts_with(m)) {
+        for line in content[start..].split_inclusive('\n') {
+            let trimmed = line.trim_start();
+            // Doc comments (`///`, `//!`) belong to the code, not the header
+            let is_doc = marker == "//" && (trimmed.starts_with("///") || trimmed.starts_with("//!"));
+            if !trimmed.starts_with(marker) || is_doc {
+                break;
+            }
+            end += line.len();
+        }
+    } else if let Some((open, _, close)) = syntax.block.filter(|(open, _, _)| first_line.starts_with(open)) {
+        let close_at = content[start + open.len()..].find(close)? + start + open.len() + close.len();
+        end = content[close_at..]
+            .find('\n')
+            .map(|i| close_at + i + 1)
+            .unwrap_or(content.len());
+    }
+
+    if end == start {
+        return None;
+    }
+
+    let block = &content[start..end];
+    if block.to_lowercase().contains("copyright") || block.contains("SPDX-License-Identifier") {
+        Some(start..end)
+    } else {
+        None
+    }
+}
+
+/// Current calendar year (UTC)
+fn current_year() -> u64 {
+    use std::time::SystemTime;
+
+    let secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut year: u64 = 1970;
+    let mut remaining_days = secs / 86400;
+    loop {
+        let leap = (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400);
+        let days_in_year = if leap { 366 } else { 365 };
+        if remaining_days < days_in_year {
+            return year;
+        }
+        remaining_days -= days_in_year;
+        year += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn manager(action: HeaderAction) -> HeaderManager {
+        let options = HeaderOptions {
+            holder: "Acme Corp".to_string(),
+            year: 2025,
+            action,
+            ..HeaderOptions::default()
+        };
+        HeaderManager::new(options)
+    }
+
+    #[test]
+    fn test_insert_header() {
+        let manager = manager(HeaderAction::Apply);
+
+        let result = manager.transform_str("fn main() {}\n", "rs").unwrap();
+        assert_eq!(result, "// Copyright (c) 2025 Acme Corp\n\nfn main() {}\n");
+
+        // Already up to date
+        assert_eq!(manager.transform_str(&result, "rs"), None);
+    }
+
+    #[test]
+    fn test_insert_after_shebang() {
+        let manager = manager(HeaderAction::Apply);
+        let result = manager
+            .transform_str("#!/usr/bin/env python\nprint('hi')\n", "py")
+            .unwrap();
+        assert_eq!(
+            result,
+            "#!/usr/bin/env python\n# Copyright (c) 2025 Acme Corp\n\nprint('hi')\n"
+        );
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        let inserter = manager(HeaderAction::Apply);
+        let result = inserter.transform_str("\u{FEFF}fn main() {}\n", "rs").unwrap();
+        assert_eq!(result, "\u{FEFF}// Copyright (c) 2025 Acme Corp\n\nfn main() {}\n");
+        assert_eq!(inserter.transform_str(&result, "rs"), None);
+
+        let remover = manager(HeaderAction::Remove);
+        assert_eq!(remover.transform_str(&result, "rs").unwrap(), "\u{FEFF}fn main() {}\n");
+    }
+
+    #[test]
+    fn test_update_year_range() {
+        let manager = manager(HeaderAction::Apply);
+        let content = "// Copyright (c) 2019 Acme Corp\n\nfn main() {}\n";
+        let result = manager.transform_str(content, "rs").unwrap();
+        assert_eq!(result, "// Copyright (c) 2019-2025 Acme Corp\n\nfn main() {}\n");
+    }
+
+    #[test]
+    fn test_existing_block_header_is_replaced() {
+        let manager = manager(HeaderAction::Apply);
+        let content = "/*\n * Copyright 2020 Someone Else\n */\nint x;\n";
+        let result = manager.transform_str(content, "c").unwrap();
+        assert_eq!(result, "// Copyright (c) 2020-2025 Acme Corp\nint x;\n");
+    }
+
+    #[test]
+    fn test_doc_comments_are_not_header() {
+        let manager = manager(HeaderAction::Apply);
+        let content = "// Copyright (c) 2025 Acme Corp\n//! Crate docs\n";
+        assert_eq!(manager.transform_str(content, "rs"), None);
+
+        let result = manager.transform_str("//! Crate docs\n", "rs").unwrap();
+        assert_eq!(result, "// Copyright (c) 2025 Acme Corp\n\n//! Crate docs\n");
+    }
+
+    #[test]
+    fn test_block_syntax_and_template_variables() {
+        let options = HeaderOptions {
+            template: "Copyright {year} {holder}\n\nSPDX-License-Identifier: {license}".to_string(),
+            holder: "Acme".to_string(),
+            license: "MIT".to_string(),
+            year: 2025,
+            ..HeaderOptions::default()
+        };
+        let manager = HeaderManager::new(options);
+
+        let result = manager.transform_str("body { }\n", "css").unwrap();
+        assert_eq!(
+            result,
+            "/*\n * Copyright 2025 Acme\n *\n * SPDX-License-Identifier: MIT\n */\n\nbody { }\n"
+        );
+    }
+
+    #[test]
+    fn test_remove_header() {
+        let manager = manager(HeaderAction::Remove);
+        let content = "# Copyright (c) 2025 Acme Corp\n\nimport os\n";
+        assert_eq!(manager.transform_str(content, "py").unwrap(), "import os\n");
+
+        // Regular comments are left alone
+        assert_eq!(manager.transform_str("# just a comment\nimport os\n", "py"), None);
+    }
+
+    #[test]
+    fn test_spdx() {
+        let options = HeaderOptions {
+            license: "MIT".to_string(),
+            action: HeaderAction::Spdx,
+            ..HeaderOptions::default()
+        };
+        let manager = HeaderManager::new(options);
+
+        assert_eq!(
+            manager.transform_str("fn main() {}\n", "rs").unwrap(),
+            "// SPDX-License-Identifier: MIT\n\nfn main() {}\n"
+        );
+        // Above an existing header, after the shebang and encoding declaration
+        assert_eq!(
+            manager
+                .transform_str("#!/usr/bin/env python3\n# -*- coding: utf-8 -*-\n# Copyright Acme\nx = 1\n", "py")
+                .unwrap(),
+            "#!/usr/bin/env python3\n# -*- coding: utf-8 -*-\n# SPDX-License-Identifier: MIT\n# Copyright Acme\nx = 1\n"
+        );
+        assert_eq!(
+            manager.transform_str("body { }\n", "css").unwrap(),
+            "/* SPDX-License-Identifier: MIT */\n\nbody { }\n"
+        );
+
+        // Existing identifiers are updated in place
+        assert_eq!(
+            manager
+                .transform_str("/*\n * Copyright Acme\n * SPDX-License-Identifier: GPL-2.0 */\nint x;\n", "c")
+                .unwrap(),
+            "/*\n * Copyright Acme\n * SPDX-License-Identifier: MIT */\nint x;\n"
+        );
+        assert_eq!(manager.transform_str("// SPDX-License-Identifier: MIT\nfn f() {}\n", "rs"), None);
+
+        // Only the comments at the top count
+        assert_eq!(
+            manager.transform_str("x = 1\n# SPDX-License-Identifier: MIT\n", "py").unwrap(),
+            "# SPDX-License-Identifier: MIT\n\nx = 1\n# SPDX-License-Identifier: MIT\n"
+        );
+    }
+
+    #[test]
+    fn test_process_directory() {
+        let test_dir = std::env::temp_dir().join("refmt_header_process");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+
+        fs::write(test_dir.join("a.rs"), "fn a() {}\n").unwrap();
+        fs::write(test_dir.join("b.py"), "# Copyright (c) 2020 Acme Corp\nx = 1\n").unwrap();
+        fs::write(test_dir.join("notes.txt"), "no header here\n").unwrap();
+
+        let stats = manager(HeaderAction::Apply).process(&test_dir).unwrap();
+        assert_eq!(stats.inserted, 1);
+        assert_eq!(stats.updated, 1);
+        assert_eq!(stats.total(), 2);
+
+        assert_eq!(
+            fs::read_to_string(test_dir.join("b.py")).unwrap(),
+            "# Copyright (c) 2020-2025 Acme Corp\nx = 1\n"
+        );
+        assert_eq!(fs::read_to_string(test_dir.join("notes.txt")).unwrap(), "no header here\n");
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+//! `.refmtignore` files
+//!
+//! Paths listed in a `.refmtignore` (gitignore syntax) are left out of every directory
+//! walk, so vendored code and generated files can be excluded once per project
+//! instead of on each command line. Files are read from the walk root and its
+//! ancestors up to the repository root, and from directories inside the walked tree;
+//! deeper files and later lines take precedence.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the ignore file
+pub const IGNORE_FILE: &str = ".refmtignore";
+
+/// One `.refmtignore` line
+#[derive(Debug)]
+struct IgnoreRule {
+    /// Directory containing the `.refmtignore` file
+    base: PathBuf,
+    pattern: glob::Pattern,
+    /// Patterns containing a slash match the path relative to `base`, others any name
+    anchored: bool,
+    /// `dir/` only matches directories
+    directory_only: bool,
+    /// `!pattern` re-includes what an earlier line excluded
+    negated: bool,
+}
+
+impl IgnoreRule {
+    /// Whether the rule matches `path` or one of its directories
+    fn matches(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.base) else {
+            return false;
+        };
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::default()
+        };
+
+        // The file itself, then each of its directories
+        let mut candidate = Some(relative);
+        let mut is_directory = false;
+        while let Some(current) = candidate.filter(|c| !c.as_os_str().is_empty()) {
+            if is_directory || !self.directory_only {
+                let matches = if self.anchored {
+                    self.pattern.matches_path_with(current, options)
+                } else {
+                    current
+                        .file_name()
+                        .is_some_and(|n| self.pattern.matches_with(&n.to_string_lossy(), options))
+                };
+                if matches {
+                    return true;
+                }
+            }
+            candidate = current.parent();
+            is_directory = true;
+        }
+        false
+    }
+}
+
+/// `.refmtignore` rules, ordered from lowest to highest precedence
+#[derive(Debug, Default)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    /// Loads the `.refmtignore` files that apply to a walk of `root` which found `files`
+    pub fn load(root: &Path, files: &[PathBuf]) -> Self {
+        let mut sources = Vec::new();
+
+        let mut dir = Some(root.to_path_buf());
+        while let Some(current) = dir {
+            let candidate = current.join(IGNORE_FILE);
+            if candidate.is_file() {
+                sources.push(candidate);
+            }
+            if current.join(".git").exists() {
+                break;
+            }
+            dir = current.parent().map(Path::to_path_buf);
+        }
+        // Outermost files have the lowest precedence
+        sources.reverse();
+
+        let mut nested: Vec<&PathBuf> = files
+            .iter()
+            .filter(|f| f.file_name().is_some_and(|n| n == IGNORE_FILE))
+            .filter(|f| !sources.contains(f))
+            .collect();
+        nested.sort_by_key(|p| p.components().count());
+        sources.extend(nested.into_iter().cloned());
+
+        let mut rules = IgnoreRules::default();
+        for source in sources {
+            if let Ok(content) = fs::read_to_string(&source) {
+                let base = source.parent().unwrap_or(Path::new(".")).to_path_buf();
+                rules.parse(&base, &content);
+            }
+        }
+        rules
+    }
+
+    /// Adds the rules of an ignore file in `base`
+    pub fn parse(&mut self, base: &Path, content: &str) {
+        for line in content.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, pattern) = match line.strip_prefix('!') {
+                Some(pattern) => (true, pattern),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let directory_only = pattern.ends_with('/');
+            let pattern = pattern.trim_end_matches('/');
+            let anchored = pattern.contains('/');
+            let pattern = match glob::Pattern::new(pattern.trim_start_matches('/')) {
+                Ok(pattern) => pattern,
+                Err(_) => continue,
+            };
+
+            self.rules.push(IgnoreRule {
+                base: base.to_path_buf(),
+                pattern,
+                anchored,
+                directory_only,
+                negated,
+            });
+        }
+    }
+
+    /// Whether `path` is excluded; the last matching rule decides
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(path))
+            .is_some_and(|rule| !rule.negated)
+    }
+
+    /// Whether there are no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns() {
+        let mut rules = IgnoreRules::default();
+        rules.parse(
+            Path::new("repo"),
+            "# vendored\nthird_party/\n*.pb.go\n/generated.rs\ndocs/**/*.md\n!docs/keep/README.md\n",
+        );
+
+        assert!(rules.is_ignored(Path::new("repo/third_party/lib/a.c")));
+        assert!(rules.is_ignored(Path::new("repo/src/third_party/b.c")));
+        assert!(!rules.is_ignored(Path::new("repo/third_party")));
+        assert!(rules.is_ignored(Path::new("repo/api/v1/user.pb.go")));
+        assert!(rules.is_ignored(Path::new("repo/generated.rs")));
+        assert!(!rules.is_ignored(Path::new("repo/src/generated.rs")));
+        assert!(rules.is_ignored(Path::new("repo/docs/guide/intro.md")));
+        assert!(!rules.is_ignored(Path::new("repo/docs/keep/README.md")));
+        assert!(!rules.is_ignored(Path::new("other/third_party/a.c")));
+    }
+}
@@ -0,0 +1,531 @@
+//! Import and include block sorting
+//!
+//! Sorts runs of consecutive single-line imports (`#include`, `import`, `from ...
+//! import`, `use`) and separates them into groups, following simple per-language
+//! rules. A Rust `use` whose braces span several lines moves as one item. Runs end at
+//! blank lines, comments and anything else, so hand-made groups, conditional includes
+//! and other multi-line imports stay where they are.
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+
+use regex::Regex;
+
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::report::{report, EventSink, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// `#include <system.h>` or `#include "local.h"`
+static C_INCLUDE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^\s*#\s*include\s*([<"])([^>"]+)[>"]\s*(//.*|/\*.*\*/)?$"#).unwrap()
+});
+/// `import a, b as c`
+static PY_IMPORT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*import\s+([\w.]+)(\s+as\s+\w+)?(\s*,\s*[\w.]+(\s+as\s+\w+)?)*\s*(#.*)?$").unwrap()
+});
+/// `from module import names`, without parentheses or continuations
+static PY_FROM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*from\s+(\.*[\w.]*)\s+import\s+[^()\\;]+$").unwrap());
+/// `use path;` or `pub use path::{a, b};` on one line
+static RUST_USE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(pub(\([^)]*\))?\s+)?use\s+(::)?([\w:]+(::\{[^}]*\}|::\*)?(\s+as\s+\w+)?)\s*;\s*(//.*)?$").unwrap()
+});
+/// The first line of `use path::{` with its braces closed on a later line
+static RUST_USE_OPEN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(pub(\([^)]*\))?\s+)?use\s+(::)?([\w:]+)::\{[^}]*$").unwrap()
+});
+/// `};` closing a multi-line `use`
+static RUST_USE_CLOSE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\}\s*;\s*(//.*)?$").unwrap());
+/// `import [static] a.b.C;`
+static JAVA_IMPORT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*import\s+(static\s+)?([\w.]+(\.\*)?)\s*;\s*$").unwrap());
+/// `import ... from 'module';`
+static JS_IMPORT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^\s*import\s+.+\s+from\s+['"]([^'"]+)['"]\s*;?\s*(//.*)?$"#).unwrap()
+});
+
+/// Options for import sorting
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        ImportOptions {
+            file_extensions: [
+                ".c", ".h", ".cpp", ".hpp", ".cc", ".hh", ".cxx", ".hxx",
+                ".py", ".pyi", ".rs", ".java",
+                ".js", ".mjs", ".jsx", ".ts", ".tsx",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// Import syntax families
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Language {
+    C,
+    Python,
+    Rust,
+    Java,
+    JavaScript,
+}
+
+impl Language {
+    fn from_path(path: &Path) -> Option<Language> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        let language = match ext.as_str() {
+            "c" | "h" | "cpp" | "hpp" | "cc" | "hh" | "cxx" | "hxx" => Language::C,
+            "py" | "pyi" => Language::Python,
+            "rs" => Language::Rust,
+            "java" => Language::Java,
+            "js" | "mjs" | "jsx" | "ts" | "tsx" => Language::JavaScript,
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    /// Group and sort key of an import line, or `None` if the line isn't a sortable
+    /// import
+    ///
+    /// Groups, in output order:
+    /// - C/C++: `<system>` headers, then `"local"` ones
+    /// - Python: `__future__`, then absolute imports, then relative ones; plain
+    ///   `import` lines come before `from` lines within a group
+    /// - Rust: `std`/`core`/`alloc`, then other crates, then `crate`/`self`/`super`
+    /// - Java: static imports, then the rest
+    /// - JavaScript/TypeScript: packages, then relative paths
+    fn classify(self, line: &str) -> Option<(u8, String)> {
+        match self {
+            Language::C => {
+                let caps = C_INCLUDE.captures(line)?;
+                let group = if &caps[1] == "<" { 0 } else { 1 };
+                Some((group, caps[2].to_string()))
+            }
+            Language::Python => {
+                if let Some(caps) = PY_IMPORT.captures(line) {
+                    return Some((1, format!("0 {}", caps[1].to_lowercase())));
+                }
+                let caps = PY_FROM.captures(line)?;
+                let module = &caps[1];
+                let group = if module == "__future__" {
+                    0
+                } else if module.starts_with('.') {
+                    2
+                } else {
+                    1
+                };
+                Some((group, format!("1 {}", module.to_lowercase())))
+            }
+            Language::Rust => {
+                let caps = RUST_USE.captures(line)?;
+                let path = caps[4].to_string();
+                Some((rust_group(&path), path))
+            }
+            Language::Java => {
+                let caps = JAVA_IMPORT.captures(line)?;
+                let group = if caps.get(1).is_some() { 0 } else { 1 };
+                Some((group, caps[2].to_string()))
+            }
+            Language::JavaScript => {
+                let caps = JS_IMPORT.captures(line)?;
+                let source = caps[1].to_string();
+                let group = if source.starts_with('.') { 1 } else { 0 };
+                Some((group, source))
+            }
+        }
+    }
+}
+
+/// The group of a Rust `use` path: std, then other crates, then the crate itself
+fn rust_group(path: &str) -> u8 {
+    match path.split("::").next().unwrap_or("") {
+        "std" | "core" | "alloc" => 0,
+        "crate" | "self" | "super" => 2,
+        _ => 1,
+    }
+}
+
+/// The number of lines, group and key of a Rust `use` whose braces span several lines
+/// at the start of `lines`
+fn rust_use_block(lines: &[&str]) -> Option<(usize, u8, String)> {
+    let first = lines.first()?.trim_end_matches(['\r', '\n']);
+    let caps = RUST_USE_OPEN.captures(first)?;
+    let mut depth = 0usize;
+
+    for (n, line) in lines.iter().enumerate() {
+        let text = line.trim_end_matches(['\r', '\n']);
+        if n > 0 && text.trim().is_empty() {
+            return None;
+        }
+        depth += text.matches('{').count();
+        depth = depth.checked_sub(text.matches('}').count())?;
+        if depth == 0 {
+            if n == 0 || !RUST_USE_CLOSE.is_match(text) {
+                return None;
+            }
+            let path = caps[4].to_string();
+            return Some((n + 1, rust_group(&path), format!("{path}::{{")));
+        }
+    }
+
+    None
+}
+
+/// A sortable import, one line or several
+struct Import<'a> {
+    lines: Vec<&'a str>,
+    group: u8,
+    key: String,
+}
+
+/// Import sorter for source files
+pub struct ImportSorter {
+    options: ImportOptions,
+    walker: Walker,
+}
+
+impl ImportSorter {
+    /// Creates a new import sorter with the given options
+    pub fn new(options: ImportOptions) -> Self {
+        ImportSorter {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a sorter with default options
+    pub fn with_defaults() -> Self {
+        ImportSorter {
+            options: ImportOptions::default(),
+            walker: Walker::default(),
+        }
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        self.options.fs.is_file(path) && self.accepts(path)
+    }
+
+    /// Checks the path alone against the filters, without touching the filesystem
+    pub fn accepts(&self, path: &Path) -> bool {
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none()
+            && Language::from_path(path).is_some()
+    }
+
+    /// Sorts and groups the import runs in source code, returning the new content and
+    /// the number of runs changed
+    ///
+    /// The language is taken from `path`'s extension. Exact duplicate imports within a
+    /// run are dropped. Returns the input borrowed when nothing changes.
+    pub fn sort_str<'a>(&self, content: &'a str, path: &Path) -> (Cow<'a, str>, usize) {
+        let Some(language) = Language::from_path(path) else {
+            return (Cow::Borrowed(content), 0);
+        };
+
+        let lines: Vec<&str> = content.split_inclusive('\n').collect();
+        let mut out = String::with_capacity(content.len());
+        let mut changes = 0;
+        let mut i = 0;
+
+        while i < lines.len() {
+            // An import under a Rust attribute (`#[cfg(test)]`) has to stay put
+            let attributed = language == Language::Rust
+                && i > 0
+                && lines[i - 1].trim_start().starts_with("#[");
+            let run = if attributed {
+                Vec::new()
+            } else {
+                import_run(&lines[i..], language)
+            };
+            if run.len() < 2 {
+                out.push_str(lines[i]);
+                i += 1;
+                continue;
+            }
+
+            let len = run.iter().map(|import| import.lines.len()).sum::<usize>();
+            let original = &lines[i..i + len];
+            let sorted = render_run(run, original);
+            if sorted != original.concat() {
+                changes += 1;
+            }
+            out.push_str(&sorted);
+            i += original.len();
+        }
+
+        if changes == 0 {
+            return (Cow::Borrowed(content), 0);
+        }
+        (Cow::Owned(out), changes)
+    }
+
+    /// Sorts the imports of a single file
+    pub fn sort_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        let outcome = self.apply_to_file(path)?;
+        self.report(path, outcome);
+        Ok(outcome)
+    }
+
+    /// Sorts a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        if !self.should_process(path) {
+            return Ok(FileOutcome::unchanged());
+        }
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        let (sorted, changes) = self.sort_str(&content, path);
+
+        match sorted {
+            Cow::Borrowed(_) => Ok(FileOutcome::unchanged()),
+            Cow::Owned(sorted) => {
+                if !self.options.dry_run {
+                    self.options.fs.write(path, sorted.as_bytes())?;
+                }
+                Ok(FileOutcome::changed(changes))
+            }
+        }
+    }
+
+    fn report(&self, path: &Path, outcome: FileOutcome) {
+        if !outcome.changed {
+            return;
+        }
+
+        self.options.events.file_changed(path, outcome.changes, self.options.dry_run);
+        if self.options.dry_run {
+            report!(
+                "Would sort {} import blocks in '{}'",
+                outcome.changes,
+                path.display()
+            );
+        } else {
+            report!("Sorted {} import blocks in '{}'", outcome.changes, path.display());
+        }
+    }
+
+    /// Processes a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<(usize, usize)> {
+        let mut total_files = 0;
+        let mut total_changes = 0;
+
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.apply_to_file(file)
+        });
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let outcome = result?;
+            self.report(file_path, outcome);
+            if outcome.changed {
+                total_files += 1;
+                total_changes += outcome.changes;
+            }
+        }
+
+        Ok((total_files, total_changes))
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+/// The run of consecutive, equally indented imports at the start of `lines`
+fn import_run<'a>(lines: &[&'a str], language: Language) -> Vec<Import<'a>> {
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let mut run: Vec<Import<'a>> = Vec::new();
+    let mut n = 0;
+
+    while n < lines.len() {
+        let text = lines[n].trim_end_matches(['\r', '\n']);
+        let (len, group, key) = match language.classify(text) {
+            Some((group, key)) => (1, group, key),
+            None if language == Language::Rust => match rust_use_block(&lines[n..]) {
+                Some(block) => block,
+                None => break,
+            },
+            None => break,
+        };
+        if run.first().is_some_and(|first| indent(first.lines[0]) != indent(text)) {
+            break;
+        }
+        let item = lines[n..n + len]
+            .iter()
+            .map(|line| line.trim_end_matches(['\r', '\n']))
+            .collect();
+        run.push(Import { lines: item, group, key });
+        n += len;
+    }
+
+    run
+}
+
+/// Sorts a run and separates its groups with blank lines, keeping the original line
+/// endings
+fn render_run(mut run: Vec<Import<'_>>, original: &[&str]) -> String {
+    let eol = if original[0].ends_with("\r\n") { "\r\n" } else { "\n" };
+    let last = original[original.len() - 1];
+    let trailing_eol = if last.ends_with('\n') { eol } else { "" };
+
+    run.sort_by(|a, b| (a.group, &a.key, &a.lines).cmp(&(b.group, &b.key, &b.lines)));
+    run.dedup_by(|a, b| a.lines == b.lines);
+
+    let mut out = String::new();
+    for (n, import) in run.iter().enumerate() {
+        if n > 0 {
+            out.push_str(eol);
+            if import.group != run[n - 1].group {
+                out.push_str(eol);
+            }
+        }
+        out.push_str(&import.lines.join(eol));
+    }
+    out.push_str(trailing_eol);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort(content: &str, file: &str) -> String {
+        ImportSorter::with_defaults()
+            .sort_str(content, Path::new(file))
+            .0
+            .into_owned()
+    }
+
+    #[test]
+    fn test_sort_c_includes() {
+        let content = "#include \"util.h\"\n#include <stdio.h>\n#include <stdio.h>\n#include <errno.h>\n\nint x;\n";
+        assert_eq!(
+            sort(content, "main.c"),
+            "#include <errno.h>\n#include <stdio.h>\n\n#include \"util.h\"\n\nint x;\n"
+        );
+    }
+
+    #[test]
+    fn test_sort_python_imports() {
+        let content = "from .models import User\nimport sys\nfrom os import path\nimport Json as j\nfrom __future__ import annotations\n";
+        assert_eq!(
+            sort(content, "app.py"),
+            "from __future__ import annotations\n\nimport Json as j\nimport sys\nfrom os import path\n\nfrom .models import User\n"
+        );
+
+        // Parenthesized imports end the run
+        let content = "import sys\nimport os\nfrom a import (\n    b,\n)\n";
+        assert_eq!(
+            sort(content, "app.py"),
+            "import os\nimport sys\nfrom a import (\n    b,\n)\n"
+        );
+    }
+
+    #[test]
+    fn test_sort_rust_uses() {
+        let content = "use crate::report::FileOutcome;\nuse regex::Regex;\nuse std::fs;\npub use std::borrow::Cow;\n";
+        assert_eq!(
+            sort(content, "lib.rs"),
+            "pub use std::borrow::Cow;\nuse std::fs;\n\nuse regex::Regex;\n\nuse crate::report::FileOutcome;\n"
+        );
+
+        let content = "#[cfg(test)]\nuse tempfile::TempDir;\nuse std::fs;\nuse std::env;\n";
+        assert_eq!(
+            sort(content, "lib.rs"),
+            "#[cfg(test)]\nuse tempfile::TempDir;\nuse std::env;\nuse std::fs;\n"
+        );
+    }
+
+    #[test]
+    fn test_multiline_rust_use() {
+        let content = "use crate::walker::Walker;\nuse std::{\n    fs,\n    io::{self, Read},\n};\nuse regex::Regex;\n\nfn main() {}\n";
+        assert_eq!(
+            sort(content, "lib.rs"),
+            "use std::{\n    fs,\n    io::{self, Read},\n};\n\nuse regex::Regex;\n\nuse crate::walker::Walker;\n\nfn main() {}\n"
+        );
+
+        // CRLF endings are kept inside the item
+        let content = "use std::fs;\r\nuse crate::{\r\n    a,\r\n};\r\nuse alloc::vec::Vec;\r\n";
+        assert_eq!(
+            sort(content, "lib.rs"),
+            "use alloc::vec::Vec;\r\nuse std::fs;\r\n\r\nuse crate::{\r\n    a,\r\n};\r\n"
+        );
+
+        // An unclosed brace ends the run
+        let content = "use std::io;\nuse std::fs;\nuse std::{\n    env,\n\nfn main() {}\n";
+        assert_eq!(
+            sort(content, "lib.rs"),
+            "use std::fs;\nuse std::io;\nuse std::{\n    env,\n\nfn main() {}\n"
+        );
+    }
+
+    #[test]
+    fn test_java_and_javascript() {
+        let content = "import java.util.List;\nimport static org.junit.Assert.assertTrue;\nimport java.io.File;\n";
+        assert_eq!(
+            sort(content, "A.java"),
+            "import static org.junit.Assert.assertTrue;\n\nimport java.io.File;\nimport java.util.List;\n"
+        );
+
+        let content = "import { b } from './b';\nimport React from 'react';\nimport './styles.css';\n";
+        assert_eq!(
+            sort(content, "app.tsx"),
+            "import React from 'react';\n\nimport { b } from './b';\nimport './styles.css';\n"
+        );
+    }
+
+    #[test]
+    fn test_sorted_untouched() {
+        let sorter = ImportSorter::with_defaults();
+        let content = "#include <a.h>\n// local\n#include \"z.h\"\n#include \"b.h\"\n";
+        let (result, changes) = sorter.sort_str(content, Path::new("x.c"));
+        assert_eq!(result, "#include <a.h>\n// local\n#include \"b.h\"\n#include \"z.h\"\n");
+        assert_eq!(changes, 1);
+
+        let (result, changes) = sorter.sort_str(&result, Path::new("x.c"));
+        assert!(matches!(result, Cow::Borrowed(_)));
+        assert_eq!(changes, 0);
+    }
+}
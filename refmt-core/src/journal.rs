@@ -0,0 +1,486 @@
+//! Undo journal
+//!
+//! A [`RealFs`](crate::vfs::RealFs) given a [`Journal`] notes every file it rewrites or
+//! renames with the SHA-256 of its content before and after, and keeps the previous
+//! content under `.refmt/journal/objects`. [`Journal::finish`] saves the run as
+//! `.refmt/journal/<id>.json`, and [`undo`] puts the files back as long as nothing else
+//! has changed them since. Only files inside the project are recorded.
+//!
+//! Only the latest runs are kept ([`DEFAULT_KEEP`] unless the journal is told
+//! otherwise); saving a run deletes the older ones, and previous contents no remaining
+//! run refers to.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::CONFIG_FILE_NAME;
+use crate::error::{Context, Error};
+use crate::report::{report, EventSink};
+
+/// Directory holding refmt's own state, in the project root
+pub const STATE_DIR: &str = ".refmt";
+
+/// Runs kept in a journal unless it is told otherwise
+pub const DEFAULT_KEEP: usize = 20;
+
+/// One change made by a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    /// A file's content was replaced
+    Write {
+        path: PathBuf,
+        /// Hash of the previous content, `None` if the file was created
+        before: Option<String>,
+        after: String,
+    },
+    /// A file was renamed
+    Rename { from: PathBuf, to: PathBuf },
+}
+
+/// The changes of one run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// Milliseconds since the Unix epoch when the run started, as text
+    pub id: String,
+    /// The command line of the run
+    pub command: Vec<String>,
+    /// Changes in the order they were made; paths are relative to the project root
+    pub changes: Vec<Change>,
+}
+
+/// What [`undo`] did
+#[derive(Debug, Clone, Default)]
+pub struct UndoReport {
+    /// The entry that was undone
+    pub id: String,
+    /// Files whose content was restored
+    pub restored: usize,
+    /// Files renamed back
+    pub renamed: usize,
+    /// Files changed again since the run, left alone
+    pub skipped: Vec<PathBuf>,
+}
+
+/// The run being recorded
+#[derive(Debug)]
+struct Recording {
+    id: String,
+    changes: Vec<Change>,
+}
+
+impl Recording {
+    fn new() -> Self {
+        let id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis())
+            .to_string();
+        Recording {
+            id,
+            changes: Vec::new(),
+        }
+    }
+}
+
+/// Records the changes of a run in the journal of a project
+///
+/// Give it to the [`RealFs`](crate::vfs::RealFs) of the options whose changes should be
+/// undoable, then [`finish`](Journal::finish) it once the run is over.
+#[derive(Debug)]
+pub struct Journal {
+    root: PathBuf,
+    keep: usize,
+    recording: Mutex<Recording>,
+}
+
+/// The directory holding the journal of the project rooted at `root`
+pub fn journal_dir(root: &Path) -> PathBuf {
+    root.join(STATE_DIR).join("journal")
+}
+
+fn objects_dir(root: &Path) -> PathBuf {
+    journal_dir(root).join("objects")
+}
+
+/// The project root for `path`: the nearest directory with a `.refmt` directory,
+/// a `refmt.toml` or a `.git`, or else `path` itself (its directory for a file)
+pub fn find_root(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let start = if path.is_file() {
+        path.parent().unwrap_or(&path).to_path_buf()
+    } else {
+        path
+    };
+    start
+        .ancestors()
+        .find(|dir| {
+            dir.join(STATE_DIR).is_dir()
+                || dir.join(CONFIG_FILE_NAME).is_file()
+                || dir.join(".git").exists()
+        })
+        .unwrap_or(&start)
+        .to_path_buf()
+}
+
+impl Journal {
+    /// Starts recording a run into the journal of the project rooted at `root`,
+    /// keeping the latest [`DEFAULT_KEEP`] runs
+    pub fn new(root: &Path) -> Self {
+        Journal {
+            root: root.to_path_buf(),
+            keep: DEFAULT_KEEP,
+            recording: Mutex::new(Recording::new()),
+        }
+    }
+
+    /// Keeps the latest `keep` runs (at least the one being recorded) when a run is saved
+    pub fn keep(mut self, keep: usize) -> Self {
+        self.keep = keep.max(1);
+        self
+    }
+
+    /// The root of the project whose journal this is
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn recording(&self) -> MutexGuard<'_, Recording> {
+        self.recording.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Saves the changes recorded so far as one run, if there are any, prunes the
+    /// journal and starts recording the next run
+    pub fn finish(&self, command: Vec<String>) -> crate::Result<Option<Entry>> {
+        let recording = std::mem::replace(&mut *self.recording(), Recording::new());
+        if recording.changes.is_empty() {
+            return Ok(None);
+        }
+
+        let entry = Entry {
+            id: recording.id,
+            command,
+            changes: recording.changes,
+        };
+        let path = entry_path(&self.root, &entry.id);
+        let json = serde_json::to_string_pretty(&entry)?;
+        fs::write(&path, json + "\n")
+            .with_context(|| format!("Failed to write journal '{}'", path.display()))?;
+        prune(&self.root, self.keep)?;
+        Ok(Some(entry))
+    }
+
+    /// Notes that `path` is about to be rewritten; called before the write, so the
+    /// previous content is safe even if the process dies halfway
+    pub(crate) fn record_write(
+        &self,
+        path: &Path,
+        before: Option<&[u8]>,
+        after: &[u8],
+    ) -> io::Result<()> {
+        let Some(path) = relative(&self.root, path) else {
+            return Ok(());
+        };
+        let before = before.map(|content| (hash(content), content));
+        if let Some((hash, content)) = &before {
+            create_dirs(&self.root)?;
+            let object = objects_dir(&self.root).join(hash);
+            if !object.exists() {
+                fs::write(object, content)?;
+            }
+        }
+        self.recording().changes.push(Change::Write {
+            path,
+            before: before.map(|(hash, _)| hash),
+            after: hash(after),
+        });
+        Ok(())
+    }
+
+    /// Notes that `from` was renamed to `to`
+    pub(crate) fn record_rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (Some(from), Some(to)) = (relative(&self.root, from), relative(&self.root, to)) else {
+            return Ok(());
+        };
+        create_dirs(&self.root)?;
+        self.recording().changes.push(Change::Rename { from, to });
+        Ok(())
+    }
+}
+
+fn entry_path(root: &Path, id: &str) -> PathBuf {
+    journal_dir(root).join(format!("{}.json", id))
+}
+
+/// Deletes all but the latest `keep` runs of the project rooted at `root`, then the
+/// previous contents no remaining run refers to; returns the number of runs deleted
+pub fn prune(root: &Path, keep: usize) -> crate::Result<usize> {
+    let entries = entries(root)?;
+    let old = entries.len().saturating_sub(keep);
+    for entry in &entries[..old] {
+        fs::remove_file(entry_path(root, &entry.id))?;
+    }
+    remove_unused_objects(root, &entries[old..])?;
+    Ok(old)
+}
+
+/// Deletes the previous contents none of `entries` refers to
+fn remove_unused_objects(root: &Path, entries: &[Entry]) -> io::Result<()> {
+    let dir = objects_dir(root);
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    let used: HashSet<&str> = entries
+        .iter()
+        .flat_map(|entry| &entry.changes)
+        .filter_map(|change| match change {
+            Change::Write {
+                before: Some(hash), ..
+            } => Some(hash.as_str()),
+            _ => None,
+        })
+        .collect();
+    for file in fs::read_dir(&dir)? {
+        let path = file?.path();
+        let name = path.file_name().and_then(|n| n.to_str());
+        if name.is_some_and(|name| !used.contains(name)) {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+fn hash(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// `path` relative to `root`, if it is inside it
+fn relative(root: &Path, path: &Path) -> Option<PathBuf> {
+    let path = std::path::absolute(path).ok()?;
+    path.strip_prefix(root).ok().map(Path::to_path_buf)
+}
+
+/// Creates the journal directories, keeping `.refmt` out of git
+fn create_dirs(root: &Path) -> io::Result<()> {
+    let state = root.join(STATE_DIR);
+    if !state.exists() {
+        fs::create_dir_all(&state)?;
+        fs::write(state.join(".gitignore"), "*\n")?;
+    }
+    fs::create_dir_all(objects_dir(root))
+}
+
+/// The saved runs of the project rooted at `root`, oldest first
+pub fn entries(root: &Path) -> crate::Result<Vec<Entry>> {
+    let dir = journal_dir(root);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for file in fs::read_dir(&dir)? {
+        let path = file?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let content = fs::read_to_string(&path)?;
+            let entry: Entry = serde_json::from_str(&content)
+                .with_context(|| format!("Invalid journal '{}'", path.display()))?;
+            entries.push(entry);
+        }
+    }
+    // Ids are timestamps of the same length, so they sort numerically as text
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(entries)
+}
+
+/// Reverts a run of the project rooted at `root`, the latest unless `id` is given
+///
+/// Changes are reverted newest first. A file that has been changed again since is
+/// left alone and listed in [`UndoReport::skipped`]; the entry is only removed once
+/// everything was reverted, so the undo can be retried after fixing those files.
+/// Each restored or renamed file is sent to `events`.
+pub fn undo(
+    root: &Path,
+    id: Option<&str>,
+    dry_run: bool,
+    events: &EventSink,
+) -> crate::Result<UndoReport> {
+    let entries = entries(root)?;
+    let entry = match id {
+        Some(id) => entries.iter().find(|entry| entry.id == id).ok_or_else(|| {
+            Error::Invalid(format!("No journal entry '{}' in '{}'", id, root.display()))
+        })?,
+        None => entries
+            .last()
+            .ok_or_else(|| Error::Invalid(format!("Nothing to undo in '{}'", root.display())))?,
+    };
+
+    let mut report = UndoReport {
+        id: entry.id.clone(),
+        ..UndoReport::default()
+    };
+    let prefix = if dry_run { "Would restore" } else { "Restored" };
+    for change in entry.changes.iter().rev() {
+        match change {
+            Change::Write {
+                path,
+                before,
+                after,
+            } => {
+                let full = root.join(path);
+                let current = fs::read(&full).ok().map(|content| hash(&content));
+                if current == *before {
+                    continue;
+                }
+                if current.as_ref() != Some(after) {
+                    report.skipped.push(path.clone());
+                    continue;
+                }
+                if !dry_run {
+                    match before {
+                        Some(hash) => {
+                            let content = fs::read(objects_dir(root).join(hash)).with_context(
+                                || format!("Missing previous content of '{}'", path.display()),
+                            )?;
+                            fs::write(&full, content)?;
+                        }
+                        None => fs::remove_file(&full)?,
+                    }
+                }
+                events.file_changed(&full, 1, dry_run);
+                report!("{} '{}'", prefix, path.display());
+                report.restored += 1;
+            }
+            Change::Rename { from, to } => {
+                let (from_full, to_full) = (root.join(from), root.join(to));
+                if from_full.exists() && !to_full.exists() {
+                    continue;
+                }
+                if !to_full.exists() || from_full.exists() {
+                    report.skipped.push(to.clone());
+                    continue;
+                }
+                if !dry_run {
+                    fs::rename(&to_full, &from_full)?;
+                }
+                events.file_renamed(&to_full, &from_full, dry_run);
+                let verb = if dry_run { "Would rename" } else { "Renamed" };
+                report!("{} '{}' back to '{}'", verb, to.display(), from.display());
+                report.renamed += 1;
+            }
+        }
+    }
+
+    if !dry_run && report.skipped.is_empty() {
+        fs::remove_file(entry_path(root, &entry.id))?;
+        let remaining: Vec<Entry> = entries
+            .iter()
+            .filter(|e| e.id != entry.id)
+            .cloned()
+            .collect();
+        remove_unused_objects(root, &remaining)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_undo() {
+        let root = std::env::temp_dir().join("refmt_test_journal");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.txt"), "before").unwrap();
+        fs::write(root.join("b.txt"), "untouched").unwrap();
+
+        let journal = Journal::new(&root);
+        let (a, b, c) = (root.join("a.txt"), root.join("b.txt"), root.join("c.txt"));
+        journal.record_write(&a, Some(b"before"), b"after").unwrap();
+        fs::write(&a, "after").unwrap();
+        fs::rename(&b, &c).unwrap();
+        journal.record_rename(&b, &c).unwrap();
+        let entry = journal.finish(vec!["refmt".to_string()]).unwrap().unwrap();
+        assert_eq!(entry.changes.len(), 2);
+        assert!(journal.finish(Vec::new()).unwrap().is_none());
+        assert_eq!(fs::read_to_string(root.join(".refmt/.gitignore")).unwrap(), "*\n");
+
+        let dry = undo(&root, None, true, &EventSink::default()).unwrap();
+        assert_eq!((dry.restored, dry.renamed), (1, 1));
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "after");
+
+        let report = undo(&root, Some(&entry.id), false, &EventSink::default()).unwrap();
+        assert_eq!((report.restored, report.renamed), (1, 1));
+        assert!(report.skipped.is_empty());
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "before");
+        assert_eq!(fs::read_to_string(root.join("b.txt")).unwrap(), "untouched");
+        assert!(entries(&root).unwrap().is_empty());
+        assert_eq!(fs::read_dir(objects_dir(&root)).unwrap().count(), 0);
+        assert!(matches!(undo(&root, None, false, &EventSink::default()), Err(Error::Invalid(_))));
+
+        // A file edited after the run is left alone
+        journal.record_write(&a, Some(b"before"), b"after").unwrap();
+        fs::write(&a, "edited since").unwrap();
+        journal.finish(Vec::new()).unwrap();
+        let report = undo(&root, None, false, &EventSink::default()).unwrap();
+        assert_eq!(report.skipped, vec![PathBuf::from("a.txt")]);
+        assert_eq!(entries(&root).unwrap().len(), 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_prune() {
+        let root = std::env::temp_dir().join("refmt_test_journal_prune");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        let journal = Journal::new(&root).keep(2);
+        let a = root.join("a.txt");
+        for (i, before) in ["one", "two", "three"].iter().enumerate() {
+            let before = Some(before.as_bytes());
+            journal.record_write(&a, before, b"x").unwrap();
+            // Ids are millisecond timestamps; runs saved within one would collide
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            journal.finish(vec![i.to_string()]).unwrap();
+        }
+
+        let kept: Vec<_> = entries(&root)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.command)
+            .collect();
+        assert_eq!(kept, vec![vec!["1".to_string()], vec!["2".to_string()]]);
+        assert!(!objects_dir(&root).join(hash(b"one")).exists());
+        assert!(objects_dir(&root).join(hash(b"two")).exists());
+        assert!(objects_dir(&root).join(hash(b"three")).exists());
+
+        assert_eq!(prune(&root, 0).unwrap(), 2);
+        assert_eq!(fs::read_dir(objects_dir(&root)).unwrap().count(), 0);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_find_root() {
+        let root = std::env::temp_dir().join("refmt_test_journal_root");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::write(root.join(CONFIG_FILE_NAME), "").unwrap();
+        fs::write(root.join("src/nested/a.rs"), "").unwrap();
+
+        assert_eq!(find_root(&root.join("src/nested")), root);
+        assert_eq!(find_root(&root.join("src/nested/a.rs")), root);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,853 @@
+//! Structure-aware key case conversion for JSON, YAML and TOML
+//!
+//! Documents are parsed and only mapping keys are renamed, so values that happen to
+//! look like identifiers are never touched. Converted documents are re-serialized,
+//! which normalizes their formatting (and drops YAML/TOML comments); documents whose
+//! keys are already in the target case are left byte-for-byte alone.
+//!
+//! [`CaseConverter::set_structured_keys`](crate::CaseConverter::set_structured_keys)
+//! instead renames the keys where they are written, keeping the rest of the file.
+
+use std::borrow::Cow;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+
+use regex::Regex;
+
+use crate::case::{identifier_words, CaseFormat};
+use crate::eol::split_bom;
+use crate::error::{Context, Error};
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::report::{report, EventSink, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// Structured document format
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl KeyFormat {
+    /// Parses a format name (`json`, `yaml`/`yml`, `toml`)
+    pub fn from_name(name: &str) -> Option<KeyFormat> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(KeyFormat::Json),
+            "yaml" | "yml" => Some(KeyFormat::Yaml),
+            "toml" => Some(KeyFormat::Toml),
+            _ => None,
+        }
+    }
+
+    /// Detects the format from a file's extension
+    pub fn from_path(path: &Path) -> Option<KeyFormat> {
+        KeyFormat::from_name(path.extension()?.to_str()?)
+    }
+}
+
+/// Options for key case conversion
+#[derive(Debug, Clone)]
+pub struct KeyOptions {
+    /// Case format to convert keys to
+    pub target: CaseFormat,
+    /// Document format (`None` detects it from each file's extension)
+    pub format: Option<KeyFormat>,
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for KeyOptions {
+    fn default() -> Self {
+        KeyOptions {
+            target: CaseFormat::SnakeCase,
+            format: None,
+            file_extensions: [".json", ".yaml", ".yml", ".toml"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// Converts the keys of structured documents between case formats
+pub struct KeyConverter {
+    options: KeyOptions,
+    walker: Walker,
+}
+
+impl KeyConverter {
+    /// Creates a new key converter with the given options
+    pub fn new(options: KeyOptions) -> Self {
+        KeyConverter {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a converter with default options
+    pub fn with_defaults() -> Self {
+        KeyConverter {
+            options: KeyOptions::default(),
+            walker: Walker::default(),
+        }
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        if !self.options.fs.is_file(path) {
+            return false;
+        }
+
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none()
+            && self.format_for(path).is_some()
+    }
+
+    fn format_for(&self, path: &Path) -> Option<KeyFormat> {
+        self.options.format.or_else(|| KeyFormat::from_path(path))
+    }
+
+    /// Converts the keys of a document, returning the new content and the number of
+    /// keys renamed
+    ///
+    /// Returns the input borrowed when every key is already in the target case. Fails
+    /// if the document doesn't parse or two keys of a mapping would get the same name.
+    pub fn convert_str<'a>(
+        &self,
+        content: &'a str,
+        format: KeyFormat,
+    ) -> crate::Result<(Cow<'a, str>, usize)> {
+        let rename = |key: &str| self.rename(key);
+        let mut renamer = KeyRenamer::new(&rename);
+        let document = Document::parse(content, format)?.rename_keys(&mut renamer)?;
+        if renamer.renamed == 0 {
+            return Ok((Cow::Borrowed(content), 0));
+        }
+
+        let mut converted = document.to_string()?.trim_end().to_string();
+        converted.push('\n');
+        Ok((Cow::Owned(converted), renamer.renamed))
+    }
+
+    /// New name for `key`; keys without any letters or digits are kept
+    fn rename(&self, key: &str) -> String {
+        let words = identifier_words(key);
+        if words.is_empty() {
+            key.to_string()
+        } else {
+            self.options.target.join_words(&words, "", "")
+        }
+    }
+
+    /// Converts the keys of a single file
+    pub fn convert_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        let outcome = self.apply_to_file(path)?;
+        self.report(path, outcome);
+        Ok(outcome)
+    }
+
+    /// Converts a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        if !self.should_process(path) {
+            return Ok(FileOutcome::unchanged());
+        }
+        let Some(format) = self.format_for(path) else {
+            return Ok(FileOutcome::unchanged());
+        };
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        let (converted, renamed) = self
+            .convert_str(&content, format)
+            .with_context(|| format!("Failed to convert keys in '{}'", path.display()))?;
+
+        match converted {
+            Cow::Borrowed(_) => Ok(FileOutcome::unchanged()),
+            Cow::Owned(converted) => {
+                if !self.options.dry_run {
+                    self.options.fs.write(path, converted.as_bytes())?;
+                }
+                Ok(FileOutcome::changed(renamed))
+            }
+        }
+    }
+
+    fn report(&self, path: &Path, outcome: FileOutcome) {
+        if !outcome.changed {
+            return;
+        }
+
+        self.options.events.file_changed(path, outcome.changes, self.options.dry_run);
+        if self.options.dry_run {
+            report!("Would rename {} keys in '{}'", outcome.changes, path.display());
+        } else {
+            report!("Renamed {} keys in '{}'", outcome.changes, path.display());
+        }
+    }
+
+    /// Processes a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<(usize, usize)> {
+        let mut total_files = 0;
+        let mut total_keys = 0;
+
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.apply_to_file(file)
+        });
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let outcome = result?;
+            self.report(file_path, outcome);
+            if outcome.changed {
+                total_files += 1;
+                total_keys += outcome.changes;
+            }
+        }
+
+        Ok((total_files, total_keys))
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+/// A parsed structured document
+#[derive(Debug, PartialEq)]
+enum Document {
+    Json(serde_json::Value),
+    Yaml(serde_yaml::Value),
+    Toml(toml::Value),
+}
+
+impl Document {
+    fn parse(content: &str, format: KeyFormat) -> crate::Result<Self> {
+        // The parsers reject a byte order mark
+        let (_, content) = split_bom(content);
+        Ok(match format {
+            KeyFormat::Json => Document::Json(serde_json::from_str(content)?),
+            KeyFormat::Yaml => Document::Yaml(serde_yaml::from_str(content)?),
+            KeyFormat::Toml => Document::Toml(toml::from_str(content)?),
+        })
+    }
+
+    fn to_string(&self) -> crate::Result<String> {
+        Ok(match self {
+            Document::Json(value) => serde_json::to_string_pretty(value)?,
+            Document::Yaml(value) => serde_yaml::to_string(value)?,
+            Document::Toml(value) => toml::to_string(value)?,
+        })
+    }
+
+    fn rename_keys(self, renamer: &mut KeyRenamer) -> crate::Result<Self> {
+        Ok(match self {
+            Document::Json(value) => Document::Json(renamer.json(value)?),
+            Document::Yaml(value) => Document::Yaml(renamer.yaml(value)?),
+            Document::Toml(value) => Document::Toml(renamer.toml(value)?),
+        })
+    }
+}
+
+/// Renames the mapping keys of parsed documents, counting the keys it changes
+struct KeyRenamer<'r> {
+    rename: &'r dyn Fn(&str) -> String,
+    renamed: usize,
+}
+
+impl<'r> KeyRenamer<'r> {
+    fn new(rename: &'r dyn Fn(&str) -> String) -> Self {
+        KeyRenamer { rename, renamed: 0 }
+    }
+
+    /// Renames `key`, counting the change and failing if the name is already taken
+    fn key(&mut self, key: &str, taken: impl Fn(&str) -> bool) -> crate::Result<String> {
+        let new_key = (self.rename)(key);
+        if taken(&new_key) {
+            return Err(Error::Conflict(format!(
+                "Renaming key '{}' to '{}' would collide with another key",
+                key, new_key
+            )));
+        }
+        if new_key != key {
+            self.renamed += 1;
+        }
+        Ok(new_key)
+    }
+
+    fn json(&mut self, value: serde_json::Value) -> crate::Result<serde_json::Value> {
+        use serde_json::Value;
+
+        Ok(match value {
+            Value::Object(map) => {
+                let mut converted = serde_json::Map::new();
+                for (key, value) in map {
+                    let key = self.key(&key, |k| converted.contains_key(k))?;
+                    let value = self.json(value)?;
+                    converted.insert(key, value);
+                }
+                Value::Object(converted)
+            }
+            Value::Array(items) => Value::Array(
+                items
+                    .into_iter()
+                    .map(|item| self.json(item))
+                    .collect::<crate::Result<_>>()?,
+            ),
+            other => other,
+        })
+    }
+
+    fn yaml(&mut self, value: serde_yaml::Value) -> crate::Result<serde_yaml::Value> {
+        use serde_yaml::Value;
+
+        Ok(match value {
+            Value::Mapping(map) => {
+                let mut converted = serde_yaml::Mapping::new();
+                for (key, value) in map {
+                    // Only string keys have a case
+                    let key = match key {
+                        Value::String(key) => {
+                            Value::String(self.key(&key, |k| converted.contains_key(k))?)
+                        }
+                        other => other,
+                    };
+                    let value = self.yaml(value)?;
+                    converted.insert(key, value);
+                }
+                Value::Mapping(converted)
+            }
+            Value::Sequence(items) => Value::Sequence(
+                items
+                    .into_iter()
+                    .map(|item| self.yaml(item))
+                    .collect::<crate::Result<_>>()?,
+            ),
+            Value::Tagged(mut tagged) => {
+                tagged.value = self.yaml(tagged.value)?;
+                Value::Tagged(tagged)
+            }
+            other => other,
+        })
+    }
+
+    fn toml(&mut self, value: toml::Value) -> crate::Result<toml::Value> {
+        use toml::Value;
+
+        Ok(match value {
+            Value::Table(table) => {
+                let mut converted = toml::map::Map::new();
+                for (key, value) in table {
+                    let key = self.key(&key, |k| converted.contains_key(k))?;
+                    let value = self.toml(value)?;
+                    converted.insert(key, value);
+                }
+                Value::Table(converted)
+            }
+            Value::Array(items) => Value::Array(
+                items
+                    .into_iter()
+                    .map(|item| self.toml(item))
+                    .collect::<crate::Result<_>>()?,
+            ),
+            other => other,
+        })
+    }
+}
+
+/// Renames the keys of `content` in place, leaving values, comments and formatting
+/// byte-for-byte alone; also returns the number of keys renamed
+///
+/// Fails if the document doesn't parse, two keys of a mapping would collide, or a key
+/// that needs renaming can't be located in the source (such as a YAML flow mapping or
+/// a quoted key with escapes).
+pub(crate) fn rename_in_place<'a>(
+    content: &'a str,
+    format: KeyFormat,
+    rename: &dyn Fn(&str) -> String,
+) -> crate::Result<(Cow<'a, str>, usize)> {
+    let mut renamer = KeyRenamer::new(rename);
+    let expected = Document::parse(content, format)?.rename_keys(&mut renamer)?;
+    if renamer.renamed == 0 {
+        return Ok((Cow::Borrowed(content), 0));
+    }
+
+    let mut converted = String::with_capacity(content.len());
+    let mut last = 0;
+    for span in key_spans(content, format) {
+        let key = &content[span.clone()];
+        converted.push_str(&content[last..span.start]);
+        converted.push_str(&rename(key));
+        last = span.end;
+    }
+    converted.push_str(&content[last..]);
+
+    // Every key must have been found, and nothing else touched
+    if Document::parse(&converted, format).ok() != Some(expected) {
+        return Err(Error::Invalid(
+            "Some keys can't be renamed without re-serializing the document".to_string(),
+        ));
+    }
+    Ok((Cow::Owned(converted), renamer.renamed))
+}
+
+/// Byte ranges of the (unquoted) keys in `content`; keys written with escapes are
+/// left out
+fn key_spans(content: &str, format: KeyFormat) -> Vec<Range<usize>> {
+    match format {
+        KeyFormat::Json => json_key_spans(content),
+        KeyFormat::Yaml => yaml_key_spans(content),
+        KeyFormat::Toml => TomlScanner::new(content).key_spans(),
+    }
+}
+
+/// Strings followed by a colon
+fn json_key_spans(content: &str) -> Vec<Range<usize>> {
+    let bytes = content.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'"' {
+            i += 1;
+            continue;
+        }
+        let start = i + 1;
+        let mut end = start;
+        let mut escaped = false;
+        while end < bytes.len() && bytes[end] != b'"' {
+            if bytes[end] == b'\\' {
+                escaped = true;
+                end += 1;
+            }
+            end += 1;
+        }
+        let next = bytes[end.min(bytes.len())..]
+            .iter()
+            .skip(1)
+            .find(|b| !b.is_ascii_whitespace());
+        if next == Some(&b':') && !escaped {
+            spans.push(start..end);
+        }
+        i = end + 1;
+    }
+    spans
+}
+
+/// A block mapping key at the start of a line, possibly after sequence dashes
+static YAML_KEY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^([ \t]*(?:-[ \t]+)*)(?:"([^"\\]*)"|'([^']*)'|([A-Za-z_$][\w.$/-]*))[ \t]*:(?:[ \t]|$)"#)
+        .unwrap()
+});
+
+/// A block scalar indicator (`|`, `>-`, ...) ending a line
+static YAML_BLOCK_SCALAR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:[&!]\S*[ \t]+)*[|>][-+0-9]*[ \t]*(?:#.*)?$").unwrap());
+
+/// Block mapping keys, line by line, skipping block scalars and multi-line quoted
+/// scalars (flow mappings aren't scanned)
+fn yaml_key_spans(content: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    // Lines indented deeper than this belong to a block scalar
+    let mut block_indent: Option<usize> = None;
+    // Quote that closes a multi-line scalar
+    let mut open_quote: Option<char> = None;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        if let Some(quote) = open_quote {
+            if closing_quote(line, quote).is_some() {
+                open_quote = None;
+            }
+            continue;
+        }
+        let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+        if let Some(parent) = block_indent {
+            if line.trim().is_empty() || indent > parent {
+                continue;
+            }
+            block_indent = None;
+        }
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let (node_column, value) = match YAML_KEY.captures(line) {
+            Some(caps) => {
+                let key = caps.get(2).or(caps.get(3)).or(caps.get(4)).unwrap();
+                spans.push(start + key.start()..start + key.end());
+                (caps[1].len(), &line[caps[0].len()..])
+            }
+            None => {
+                let dashes = line.trim_start_matches([' ', '\t', '-']);
+                (indent, dashes)
+            }
+        };
+        let value = value.trim_start();
+        if YAML_BLOCK_SCALAR.is_match(value) {
+            block_indent = Some(node_column);
+        } else if let Some(quote) = value.chars().next().filter(|c| matches!(c, '"' | '\'')) {
+            if closing_quote(&value[1..], quote).is_none() {
+                open_quote = Some(quote);
+            }
+        }
+    }
+    spans
+}
+
+/// Position of the quote closing a YAML scalar in `text`
+fn closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if quote == '"' => {
+                chars.next();
+            }
+            // '' is an escaped quote in single-quoted scalars
+            '\'' if quote == '\'' && chars.peek().is_some_and(|&(_, n)| n == '\'') => {
+                chars.next();
+            }
+            c if c == quote => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Finds the keys of a TOML document: table headers, `key = value` lines and inline
+/// tables, skipping over strings and other values
+struct TomlScanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    spans: Vec<Range<usize>>,
+}
+
+impl<'a> TomlScanner<'a> {
+    fn new(content: &'a str) -> Self {
+        TomlScanner {
+            bytes: content.as_bytes(),
+            pos: 0,
+            spans: Vec::new(),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, text: &[u8]) -> bool {
+        self.bytes[self.pos..].starts_with(text)
+    }
+
+    /// Skips spaces, and also newlines and comments if `lines` is set
+    fn skip_blank(&mut self, lines: bool) {
+        while let Some(b) = self.peek() {
+            match b {
+                b' ' | b'\t' => self.pos += 1,
+                b'\r' | b'\n' if lines => self.pos += 1,
+                b'#' if lines => {
+                    while self.peek().is_some_and(|b| b != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn key_spans(mut self) -> Vec<Range<usize>> {
+        loop {
+            self.skip_blank(true);
+            match self.peek() {
+                None => break,
+                Some(b'[') => {
+                    while self.peek() == Some(b'[') {
+                        self.pos += 1;
+                    }
+                    self.key_path();
+                    while self.peek() == Some(b']') {
+                        self.pos += 1;
+                    }
+                }
+                Some(_) => {
+                    let before = self.pos;
+                    self.key_path();
+                    self.skip_blank(false);
+                    if self.peek() == Some(b'=') {
+                        self.pos += 1;
+                        self.value();
+                    } else if self.pos == before {
+                        // Not a key; the document parsed, so this shouldn't happen
+                        self.pos += 1;
+                    }
+                }
+            }
+        }
+        self.spans
+    }
+
+    /// A dotted key, recording each part
+    fn key_path(&mut self) {
+        loop {
+            self.skip_blank(false);
+            match self.peek() {
+                Some(quote @ (b'"' | b'\'')) => {
+                    let start = self.pos + 1;
+                    let escaped = self.string(quote);
+                    if !escaped {
+                        self.spans.push(start..self.pos - 1);
+                    }
+                }
+                _ => {
+                    let start = self.pos;
+                    while self
+                        .peek()
+                        .is_some_and(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+                    {
+                        self.pos += 1;
+                    }
+                    if self.pos > start {
+                        self.spans.push(start..self.pos);
+                    }
+                }
+            }
+            self.skip_blank(false);
+            if self.peek() != Some(b'.') {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Skips a string starting at the current position, returning whether it has escapes
+    fn string(&mut self, quote: u8) -> bool {
+        let multi_line = [quote; 3];
+        let delimiter: &[u8] = if self.starts_with(&multi_line) {
+            &multi_line
+        } else {
+            &multi_line[..1]
+        };
+        let basic = quote == b'"';
+        self.pos += delimiter.len();
+        let mut escaped = false;
+        while self.pos < self.bytes.len() && !self.starts_with(delimiter) {
+            if basic && self.peek() == Some(b'\\') {
+                escaped = true;
+                self.pos += 1;
+            }
+            self.pos += 1;
+        }
+        self.pos += delimiter.len();
+        // Up to two more quotes may end a multi-line string ("""a"""")
+        while delimiter.len() == 3 && self.peek() == Some(quote) {
+            self.pos += 1;
+        }
+        escaped
+    }
+
+    fn value(&mut self) {
+        self.skip_blank(false);
+        match self.peek() {
+            Some(quote @ (b'"' | b'\'')) => {
+                self.string(quote);
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                loop {
+                    self.skip_blank(true);
+                    match self.peek() {
+                        None => break,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        Some(b',') => self.pos += 1,
+                        Some(_) => {
+                            let before = self.pos;
+                            self.key_path();
+                            self.skip_blank(false);
+                            if self.peek() == Some(b'=') {
+                                self.pos += 1;
+                                self.value();
+                            } else if self.pos == before {
+                                self.pos += 1;
+                            }
+                        }
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                loop {
+                    self.skip_blank(true);
+                    match self.peek() {
+                        None => break,
+                        Some(b']') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        Some(b',') => self.pos += 1,
+                        Some(_) => self.value(),
+                    }
+                }
+            }
+            _ => {
+                // Numbers, booleans and dates run up to the next delimiter
+                while self
+                    .peek()
+                    .is_some_and(|b| !matches!(b, b',' | b']' | b'}' | b'\n' | b'#'))
+                {
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn converter(target: CaseFormat) -> KeyConverter {
+        let options = KeyOptions {
+            target,
+            ..KeyOptions::default()
+        };
+        KeyConverter::new(options)
+    }
+
+    #[test]
+    fn test_json_keys() {
+        let content = r#"{"userName": "camelCaseValue", "items": [{"itemId": 1}], "snake_key": null}"#;
+        let (result, renamed) = converter(CaseFormat::SnakeCase)
+            .convert_str(content, KeyFormat::Json)
+            .unwrap();
+        assert_eq!(
+            result,
+            "{\n  \"user_name\": \"camelCaseValue\",\n  \"items\": [\n    {\n      \"item_id\": 1\n    }\n  ],\n  \"snake_key\": null\n}\n"
+        );
+        assert_eq!(renamed, 2);
+    }
+
+    #[test]
+    fn test_yaml_keys() {
+        let content = "server_config:\n  max_connections: 10\n  hosts: [web_one]\n1: numeric\n";
+        let (result, renamed) = converter(CaseFormat::CamelCase)
+            .convert_str(content, KeyFormat::Yaml)
+            .unwrap();
+        assert_eq!(result, "serverConfig:\n  maxConnections: 10\n  hosts:\n  - web_one\n1: numeric\n");
+        assert_eq!(renamed, 2);
+    }
+
+    #[test]
+    fn test_toml_keys() {
+        let content = "[package-info]\nfirst-name = \"kebab-value\"\n";
+        let (result, renamed) = converter(CaseFormat::SnakeCase)
+            .convert_str(content, KeyFormat::Toml)
+            .unwrap();
+        assert_eq!(result, "[package_info]\nfirst_name = \"kebab-value\"\n");
+        assert_eq!(renamed, 2);
+    }
+
+    #[test]
+    fn test_unchanged_and_collisions() {
+        let converter = converter(CaseFormat::SnakeCase);
+        let content = "{ \"already_snake\": 1 }";
+        let (result, renamed) = converter.convert_str(content, KeyFormat::Json).unwrap();
+        assert!(matches!(result, Cow::Borrowed(_)));
+        assert_eq!(renamed, 0);
+
+        assert!(converter
+            .convert_str("{\"fooBar\": 1, \"foo_bar\": 2}", KeyFormat::Json)
+            .is_err());
+        assert!(converter.convert_str("{ not json", KeyFormat::Json).is_err());
+    }
+    fn snake(key: &str) -> String {
+        if CaseFormat::CamelCase.matches(key) {
+            CaseFormat::convert(CaseFormat::CamelCase, CaseFormat::SnakeCase, key)
+        } else {
+            key.to_string()
+        }
+    }
+
+    #[test]
+    fn test_rename_in_place_json() {
+        let content = "{\n    \"userName\": \"camelCaseValue\",\n    \"items\": [ {\"itemId\" : 1} ],\n    \"url\": \"http://x/fooBar\"\n}";
+        let (result, renamed) = rename_in_place(content, KeyFormat::Json, &snake).unwrap();
+        assert_eq!(
+            result,
+            "{\n    \"user_name\": \"camelCaseValue\",\n    \"items\": [ {\"item_id\" : 1} ],\n    \"url\": \"http://x/fooBar\"\n}"
+        );
+        assert_eq!(renamed, 2);
+
+        let (result, _) = rename_in_place("{\"a\": 1}", KeyFormat::Json, &snake).unwrap();
+        assert!(matches!(result, Cow::Borrowed(_)));
+
+        // A byte order mark and CRLF line endings are kept
+        let content = "\u{FEFF}{\r\n  \"userName\": 1\r\n}\r\n";
+        let (result, _) = rename_in_place(content, KeyFormat::Json, &snake).unwrap();
+        assert_eq!(result, "\u{FEFF}{\r\n  \"user_name\": 1\r\n}\r\n");
+    }
+
+    #[test]
+    fn test_rename_in_place_yaml() {
+        let content = "# serverConfig: comment\nserverConfig:  # trailing\n  maxConnections: 10\n  script: |\n    fooBar: not a key\n  hosts:\n    - hostName: web\n      'portNumber': 80\n  note: \"multi\n    lineKey: text\"\n";
+        let (result, renamed) = rename_in_place(content, KeyFormat::Yaml, &snake).unwrap();
+        assert_eq!(
+            result,
+            "# serverConfig: comment\nserver_config:  # trailing\n  max_connections: 10\n  script: |\n    fooBar: not a key\n  hosts:\n    - host_name: web\n      'port_number': 80\n  note: \"multi\n    lineKey: text\"\n"
+        );
+        assert_eq!(renamed, 4);
+
+        // Flow mappings aren't scanned, so the document is left alone
+        assert!(rename_in_place("server: {hostName: web}\n", KeyFormat::Yaml, &snake).is_err());
+    }
+
+    #[test]
+    fn test_rename_in_place_toml() {
+        let content = "# userName = 1\n[serverConfig]\nmaxConnections = 10 # fooBar = 2\n\"quotedKey\" = '''\nfooBar = not a key'''\npoint = { xValue = 1, yValue = [1, 2] }\n\n[[serverConfig.hostList]]\nhostName = \"webOne\"\n";
+        let (result, renamed) = rename_in_place(content, KeyFormat::Toml, &snake).unwrap();
+        assert_eq!(
+            result,
+            "# userName = 1\n[server_config]\nmax_connections = 10 # fooBar = 2\n\"quoted_key\" = '''\nfooBar = not a key'''\npoint = { x_value = 1, y_value = [1, 2] }\n\n[[server_config.host_list]]\nhost_name = \"webOne\"\n"
+        );
+        assert_eq!(renamed, 7);
+    }
+}
@@ -3,20 +3,97 @@
 //! This library provides the fundamental building blocks for transforming code,
 //! including case format conversion, pattern matching, and file processing.
 
+pub mod anchors;
+pub mod ascii;
 pub mod case;
+pub mod classnames;
 pub mod combined;
+pub mod comments;
+pub mod config;
 pub mod converter;
+#[cfg(feature = "declared-only")]
+pub mod declarations;
+pub mod diff;
+pub mod dotenv;
 pub mod emoji;
+pub mod eol;
+pub mod error;
+pub mod filenames;
+pub mod filter;
+pub mod git;
+pub mod guards;
+pub mod header;
+pub mod ignore;
+pub mod imports;
+pub mod journal;
+pub mod keys;
+mod markdown;
+pub mod memory;
+pub mod naming;
+pub mod numbers;
+mod parallel;
+pub mod progress;
+pub mod punctuation;
+pub mod quotes;
 pub mod rename;
+pub mod report;
+pub mod schema;
+pub mod shebang;
+pub mod sql;
+pub mod stats;
+pub mod strings;
+pub mod vfs;
+pub mod walker;
 pub mod whitespace;
 
 // Re-export commonly used types
-pub use case::CaseFormat;
-pub use combined::{CombinedOptions, CombinedProcessor, CombinedStats};
-pub use converter::CaseConverter;
+pub use anchors::AnchorChanges;
+pub use ascii::{AsciiChecker, AsciiOptions, CharCategory, FileFindings, NonAsciiChar};
+pub use case::{Abbreviations, CaseFormat, DigitBoundary, ParseCaseFormatError};
+pub use classnames::{
+    ClassNameConverter, ClassNameMapping, ClassNameOptions, ClassNameStats,
+};
+pub use combined::{
+    CombinedOptions, CombinedProcessor, CombinedStats, CombinedStep, ConvertStepOptions,
+    FileReport, StepChange,
+};
+pub use comments::{CommentConverter, CommentOptions, CommentStyle};
+pub use config::{Config, ExtensionsConfig};
+pub use converter::{CaseConverter, CaseConverterBuilder, ConversionReport};
+#[cfg(feature = "declared-only")]
+pub use declarations::SourceLanguage;
+pub use dotenv::{DotenvNormalizer, DotenvOptions, DotenvStats, DuplicateKey};
 pub use emoji::{EmojiOptions, EmojiTransformer};
-pub use rename::{CaseTransform, FileRenamer, RenameOptions, SpaceReplace, TimestampFormat};
-pub use whitespace::{WhitespaceCleaner, WhitespaceOptions};
+pub use eol::{EolConverter, EolOptions, EolStats, LineEnding};
+pub use error::Error;
+pub use filenames::{FilenameChecker, FilenameFix, FilenameMismatch, FilenameOptions};
+pub use filter::{ExtensionSet, FilterOptions, SkipReason, DEFAULT_SKIP_DIRS};
+pub use git::{ChangedLines, LineRanges};
+pub use guards::{GuardChange, GuardOptions, GuardRenamer};
+pub use header::{HeaderAction, HeaderManager, HeaderOptions, HeaderStats};
+pub use ignore::IgnoreRules;
+pub use imports::{ImportOptions, ImportSorter};
+pub use journal::{Change, Entry, UndoReport};
+pub use keys::{KeyConverter, KeyFormat, KeyOptions};
+pub use memory::{ContentTransform, MemoryStats};
+pub use naming::{ItemKind, NamingChecker, NamingLanguage, NamingOptions, NamingViolation};
+pub use numbers::{NumberFormatter, NumberOptions};
+pub use progress::{Progress, ProgressEvent};
+pub use punctuation::{PunctuationNormalizer, PunctuationOptions};
+pub use quotes::{QuoteOptions, QuoteStyle, QuoteTransformer};
+pub use rename::{
+    CaseTransform, ConflictResolution, FileRenamer, RenameOptions, RenamePlanEntry, SpaceReplace,
+    TimestampFormat,
+};
+pub use report::{EventSink, FailedFile, FileAction, FileEvent, FileOutcome};
+pub use schema::{FieldRename, FieldRenamer, SchemaFormat, SchemaOptions, SchemaStats};
+pub use shebang::{ShebangNormalizer, ShebangOptions, ShebangStats};
+pub use sql::{KeywordCase, SqlFormatter, SqlOptions};
+pub use stats::{CaseCounts, CaseScanner, CaseStats, StatsOptions};
+pub use strings::{StringQuote, StringQuoteConverter, StringQuoteOptions};
+pub use whitespace::{
+    HardBreaks, IndentOptions, IndentStyle, WhitespaceCleaner, WhitespaceOptions,
+};
 
 // Re-export Result type
-pub type Result<T> = anyhow::Result<T>;
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,287 @@
+//! Markdown structure helpers for prose-only transforms
+//!
+//! Typographic transforms must not touch code, so a document is split into prose
+//! and verbatim regions: YAML front matter, fenced code blocks, inline code spans,
+//! HTML tags and link destinations are copied through unchanged.
+
+use std::borrow::Cow;
+
+/// Applies `f` to each prose segment of a Markdown document
+///
+/// `f` receives the segment and the character written just before it (if any), so
+/// context-sensitive transforms such as quote curling see across code spans.
+/// Returns the input borrowed when no segment changes.
+pub(crate) fn map_prose<'a, F>(content: &'a str, mut f: F) -> Cow<'a, str>
+where
+    F: FnMut(&str, Option<char>) -> String,
+{
+    let mut out = String::with_capacity(content.len());
+    let mut blocks = BlockScanner::new(content);
+
+    for line in content.split_inclusive('\n') {
+        if blocks.is_verbatim(line) {
+            out.push_str(line);
+        } else {
+            map_inline(line, &mut out, &mut f);
+        }
+    }
+
+    if out == content {
+        Cow::Borrowed(content)
+    } else {
+        Cow::Owned(out)
+    }
+}
+
+/// Returns the text of each ATX heading (`# Title`) outside code blocks, in order
+pub(crate) fn headings(content: &str) -> Vec<String> {
+    let mut blocks = BlockScanner::new(content);
+    let mut found = Vec::new();
+
+    for line in content.split_inclusive('\n') {
+        if blocks.is_verbatim(line) {
+            continue;
+        }
+
+        if let Some(rest) = heading_text(line) {
+            // Closing sequences (`## Title ##`) aren't part of the heading
+            let text = rest.trim().trim_end_matches('#').trim_end();
+            found.push(text.to_string());
+        }
+    }
+
+    found
+}
+
+/// Returns what follows the `#` markers if `line` is an ATX heading
+fn heading_text(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    let level = trimmed.len() - trimmed.trim_start_matches('#').len();
+    let rest = &trimmed[level..];
+    ((1..=6).contains(&level) && (rest.is_empty() || rest.starts_with([' ', '\t']))).then_some(rest)
+}
+
+/// Returns the 1-based numbers of the lines ending in a hard line break, in order
+///
+/// A hard break is two or more trailing spaces after the text of a paragraph line
+/// that the paragraph continues past; headings, code blocks and front matter have
+/// none.
+pub(crate) fn hard_break_lines(content: &str) -> Vec<usize> {
+    let mut blocks = BlockScanner::new(content);
+    let mut lines = content.lines().enumerate().peekable();
+    let mut found = Vec::new();
+
+    while let Some((i, line)) = lines.next() {
+        if blocks.is_verbatim(line) {
+            continue;
+        }
+
+        let text = line.trim_end_matches(' ');
+        if line.len() - text.len() < 2 || text.trim().is_empty() || heading_text(text).is_some() {
+            continue;
+        }
+        let continued = lines.peek().is_some_and(|(_, next)| {
+            !next.trim().is_empty() && fence_marker(next.trim_end()).is_none()
+        });
+        if continued {
+            found.push(i + 1);
+        }
+    }
+
+    found
+}
+
+/// Tracks front matter and fenced code blocks line by line
+struct BlockScanner {
+    fence: Option<(char, usize)>,
+    in_front_matter: bool,
+    first_line: bool,
+}
+
+impl BlockScanner {
+    fn new(content: &str) -> Self {
+        BlockScanner {
+            fence: None,
+            in_front_matter: content.starts_with("---\n") || content.starts_with("---\r\n"),
+            first_line: true,
+        }
+    }
+
+    /// Whether `line` (the next line of the document) must be copied verbatim
+    fn is_verbatim(&mut self, line: &str) -> bool {
+        let first_line = std::mem::replace(&mut self.first_line, false);
+        let trimmed = line.trim_end();
+
+        if self.in_front_matter {
+            if !first_line && (trimmed == "---" || trimmed == "...") {
+                self.in_front_matter = false;
+            }
+            return true;
+        }
+
+        if let Some(marker) = fence_marker(trimmed) {
+            match self.fence {
+                None => self.fence = Some(marker),
+                Some((c, len)) if marker.0 == c && marker.1 >= len && is_bare_fence(trimmed) => {
+                    self.fence = None
+                }
+                _ => {}
+            }
+            return true;
+        }
+
+        self.fence.is_some()
+    }
+}
+
+/// Returns the fence character and length if `line` opens or closes a code fence
+fn fence_marker(line: &str) -> Option<(char, usize)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+
+    let rest = &line[indent..];
+    let c = rest.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = rest.len() - rest.trim_start_matches(c).len();
+    // A backtick fence's info string can't contain backticks (that's inline code)
+    let info_ok = c == '~' || !rest[len..].contains('`');
+    (len >= 3 && info_ok).then_some((c, len))
+}
+
+/// Closing fences carry no info string
+fn is_bare_fence(line: &str) -> bool {
+    let rest = line.trim_start();
+    rest.trim_start_matches(['`', '~']).trim().is_empty()
+}
+
+/// Splits one line into prose and verbatim spans, passing prose through `f`
+fn map_inline<F>(line: &str, out: &mut String, f: &mut F)
+where
+    F: FnMut(&str, Option<char>) -> String,
+{
+    let bytes = line.as_bytes();
+    let mut prose_start = 0;
+    let mut i = 0;
+
+    let mut flush = |out: &mut String, prose: &str| {
+        if !prose.is_empty() {
+            let prev = out.chars().next_back();
+            out.push_str(&f(prose, prev));
+        }
+    };
+
+    while i < bytes.len() {
+        let verbatim_end = match bytes[i] {
+            b'`' => {
+                let run = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+                match find_backtick_run(&line[i + run..], run) {
+                    Some(close) => Some(i + run + close + run),
+                    None => {
+                        i += run;
+                        continue;
+                    }
+                }
+            }
+            b'<' if bytes
+                .get(i + 1)
+                .is_some_and(|b| b.is_ascii_alphabetic() || *b == b'/' || *b == b'!') =>
+            {
+                line[i..].find('>').map(|close| i + close + 1)
+            }
+            b']' if bytes.get(i + 1) == Some(&b'(') => {
+                // Keep the `]` in the prose so the link text ends where it should
+                flush(out, &line[prose_start..=i]);
+                prose_start = i + 1;
+                line[i..].find(')').map(|close| i + close + 1)
+            }
+            _ => None,
+        };
+
+        match verbatim_end {
+            Some(end) => {
+                flush(out, &line[prose_start..i.max(prose_start)]);
+                let start = i.max(prose_start);
+                out.push_str(&line[start..end]);
+                i = end;
+                prose_start = end;
+            }
+            None => i += 1,
+        }
+    }
+
+    flush(out, &line[prose_start..]);
+}
+
+/// Finds a closing run of exactly `run` backticks, returning its offset
+fn find_backtick_run(text: &str, run: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'`' {
+            let len = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+            if len == run {
+                return Some(i);
+            }
+            i += len;
+        } else {
+            i += 1;
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upper(content: &str) -> String {
+        map_prose(content, |prose, _| prose.to_uppercase()).into_owned()
+    }
+
+    #[test]
+    fn test_code_is_verbatim() {
+        let content = "text `code` <a href=\"x\">link</a> [name](url)\n```rust\nlet x;\n```\nend\n";
+        assert_eq!(
+            upper(content),
+            "TEXT `code` <a href=\"x\">LINK</a> [NAME](url)\n```rust\nlet x;\n```\nEND\n"
+        );
+    }
+
+    #[test]
+    fn test_front_matter_and_fences() {
+        let content = "---\ntitle: x\n---\n~~~~\n~~~\nstill code\n~~~~\nprose\n";
+        assert_eq!(upper(content), "---\ntitle: x\n---\n~~~~\n~~~\nstill code\n~~~~\nPROSE\n");
+
+        // Unmatched backticks are prose
+        assert_eq!(upper("a ` b\n"), "A ` B\n");
+    }
+
+    #[test]
+    fn test_headings() {
+        let content = "# Title\n```\n# not a heading\n```\n## Sub ##\n#hashtag\n####### too deep\n";
+        assert_eq!(headings(content), vec!["Title", "Sub"]);
+    }
+
+    #[test]
+    fn test_hard_break_lines() {
+        let content =
+            "one  \ntwo   \nlast  \n\n# Title  \nthree \nfour\t  \nfive\n```\ncode  \nx\n```\n";
+        assert_eq!(hard_break_lines(content), vec![1, 2, 7]);
+        assert_eq!(hard_break_lines("a  \r\nb\r\n"), vec![1]);
+        assert!(hard_break_lines("a  \n```\nb\n```\n").is_empty());
+    }
+
+    #[test]
+    fn test_prev_char_spans_code() {
+        let mut prevs = Vec::new();
+        let result = map_prose("a`b`c\n", |prose, prev| {
+            prevs.push(prev);
+            prose.to_string()
+        });
+        assert!(matches!(result, Cow::Borrowed(_)));
+        assert_eq!(prevs, vec![None, Some('`')]);
+    }
+}
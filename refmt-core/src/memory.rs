@@ -0,0 +1,419 @@
+//! In-memory processing
+//!
+//! Runs transformers over a map of paths to contents instead of a directory tree, for
+//! fast tests and for servers that must not touch the disk. Paths are only used for
+//! filtering (extensions, globs, hidden and build directories), so they don't need to
+//! exist.
+//!
+//! [`unstable_files`] uses the same machinery to check that a pipeline is idempotent,
+//! and [`changed_files`] to preview which files a run would change.
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use std::path::PathBuf;
+//! use refmt_core::{memory, EmojiTransformer, WhitespaceCleaner};
+//!
+//! let files = HashMap::from([(PathBuf::from("notes.md"), "- ✅ done  \n".to_string())]);
+//! let (files, stats) = memory::process_files(
+//!     files,
+//!     &[&EmojiTransformer::with_defaults(), &WhitespaceCleaner::with_defaults()],
+//! );
+//! assert_eq!(files[&PathBuf::from("notes.md")], "- [x] done\n");
+//! assert_eq!(stats.files_modified, 1);
+//! ```
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::filter::read_text;
+use crate::vfs::Fs;
+use crate::walker::WalkOptions;
+use crate::{
+    CaseConverter, CommentConverter, DotenvNormalizer, EmojiTransformer, ImportSorter,
+    NumberFormatter, PunctuationNormalizer, QuoteTransformer, SqlFormatter,
+    StringQuoteConverter, WhitespaceCleaner,
+};
+
+/// A transformer that can rewrite file contents without the filesystem
+pub trait ContentTransform {
+    /// Whether a file at `path` would be processed, judged by the path alone
+    fn accepts(&self, path: &Path) -> bool;
+
+    /// Transforms the content of the file at `path`, returning the new content and
+    /// the number of changes
+    fn transform<'a>(&self, path: &Path, content: &'a str) -> (Cow<'a, str>, usize);
+}
+
+/// Totals of a [`process_files`] run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Files accepted by at least one transformer
+    pub files_processed: usize,
+    /// Files whose content changed
+    pub files_modified: usize,
+    /// Changes summed over all transformers, in each transformer's unit
+    pub changes: usize,
+}
+
+/// Runs `transforms` in order over every file in `files`
+///
+/// Returns the files with their new contents, and the totals.
+pub fn process_files(
+    mut files: HashMap<PathBuf, String>,
+    transforms: &[&dyn ContentTransform],
+) -> (HashMap<PathBuf, String>, MemoryStats) {
+    let mut stats = MemoryStats::default();
+
+    for (path, content) in files.iter_mut() {
+        let mut accepted = false;
+        let mut modified = false;
+        for transform in transforms.iter().filter(|t| t.accepts(path)) {
+            accepted = true;
+            let (transformed, changes) = transform.transform(path, content);
+            if let Cow::Owned(transformed) = transformed {
+                stats.changes += changes;
+                modified |= transformed != *content;
+                *content = transformed;
+            }
+        }
+        stats.files_processed += usize::from(accepted);
+        stats.files_modified += usize::from(modified);
+    }
+
+    (files, stats)
+}
+
+/// Files under `path` that `transforms` would still change after a first pass
+///
+/// Nothing is written: each file is run through `transforms` twice in memory, and
+/// kept when the second pass changes it again. An idempotent pipeline returns no
+/// files. Binary and non-UTF-8 files are left out.
+pub fn unstable_files(
+    fs: &dyn Fs,
+    path: &Path,
+    recursive: bool,
+    walk: WalkOptions,
+    transforms: &[&dyn ContentTransform],
+) -> crate::Result<Vec<PathBuf>> {
+    let mut unstable = Vec::new();
+    for_each_text_file(fs, path, recursive, walk, transforms, |file, accepted, content| {
+        let once = apply(accepted, &file, content);
+        let twice = apply(accepted, &file, once.clone());
+        if twice != once {
+            unstable.push(file);
+        }
+    })?;
+    Ok(unstable)
+}
+
+/// Files under `path` that `transforms` would change
+///
+/// Nothing is written. Binary and non-UTF-8 files are left out.
+pub fn changed_files(
+    fs: &dyn Fs,
+    path: &Path,
+    recursive: bool,
+    walk: WalkOptions,
+    transforms: &[&dyn ContentTransform],
+) -> crate::Result<Vec<PathBuf>> {
+    let mut changed = Vec::new();
+    for_each_text_file(fs, path, recursive, walk, transforms, |file, accepted, content| {
+        if apply(accepted, &file, content.clone()) != content {
+            changed.push(file);
+        }
+    })?;
+    Ok(changed)
+}
+
+/// Calls `f` with each text file under `path` that one of `transforms` accepts, the
+/// transforms accepting it and its content
+fn for_each_text_file(
+    fs: &dyn Fs,
+    path: &Path,
+    recursive: bool,
+    walk: WalkOptions,
+    transforms: &[&dyn ContentTransform],
+    mut f: impl FnMut(PathBuf, &[&dyn ContentTransform], String),
+) -> crate::Result<()> {
+    let files = if fs.is_file(path) {
+        vec![path.to_path_buf()]
+    } else {
+        fs.walk(path, recursive, walk)?.files
+    };
+
+    for file in files {
+        let accepted: Vec<_> = transforms.iter().copied().filter(|t| t.accepts(&file)).collect();
+        if accepted.is_empty() {
+            continue;
+        }
+        let Ok(Some(content)) = read_text(fs, &file) else {
+            continue;
+        };
+        f(file, &accepted, content);
+    }
+    Ok(())
+}
+
+/// Runs `transforms` in order over one file's content
+fn apply(transforms: &[&dyn ContentTransform], path: &Path, mut content: String) -> String {
+    for transform in transforms {
+        if let (Cow::Owned(transformed), _) = transform.transform(path, &content) {
+            content = transformed;
+        }
+    }
+    content
+}
+
+impl ContentTransform for WhitespaceCleaner {
+    fn accepts(&self, path: &Path) -> bool {
+        self.skip_reason(path).is_none()
+    }
+
+    fn transform<'a>(&self, path: &Path, content: &'a str) -> (Cow<'a, str>, usize) {
+        self.clean_path_str(path, content)
+    }
+}
+
+impl ContentTransform for EmojiTransformer {
+    fn accepts(&self, path: &Path) -> bool {
+        self.skip_reason(path).is_none()
+    }
+
+    fn transform<'a>(&self, _path: &Path, content: &'a str) -> (Cow<'a, str>, usize) {
+        self.transform_str(content)
+    }
+}
+
+/// Globs are matched against the file name and the path as given. In keys-only mode,
+/// documents whose keys can't be renamed in place are left unchanged.
+impl ContentTransform for CaseConverter {
+    fn accepts(&self, path: &Path) -> bool {
+        self.skip_reason(path, Path::new("")).is_none()
+    }
+
+    fn transform<'a>(&self, path: &Path, content: &'a str) -> (Cow<'a, str>, usize) {
+        self.convert_content(path, content)
+            .unwrap_or((Cow::Borrowed(content), 0))
+    }
+}
+
+impl ContentTransform for QuoteTransformer {
+    fn accepts(&self, path: &Path) -> bool {
+        QuoteTransformer::accepts(self, path)
+    }
+
+    fn transform<'a>(&self, _path: &Path, content: &'a str) -> (Cow<'a, str>, usize) {
+        self.transform_str(content)
+    }
+}
+
+impl ContentTransform for PunctuationNormalizer {
+    fn accepts(&self, path: &Path) -> bool {
+        PunctuationNormalizer::accepts(self, path)
+    }
+
+    fn transform<'a>(&self, _path: &Path, content: &'a str) -> (Cow<'a, str>, usize) {
+        self.normalize_str(content)
+    }
+}
+
+impl ContentTransform for SqlFormatter {
+    fn accepts(&self, path: &Path) -> bool {
+        SqlFormatter::accepts(self, path)
+    }
+
+    fn transform<'a>(&self, _path: &Path, content: &'a str) -> (Cow<'a, str>, usize) {
+        self.format_str(content)
+    }
+}
+
+impl ContentTransform for DotenvNormalizer {
+    fn accepts(&self, path: &Path) -> bool {
+        DotenvNormalizer::accepts(self, path)
+    }
+
+    fn transform<'a>(&self, _path: &Path, content: &'a str) -> (Cow<'a, str>, usize) {
+        self.normalize_str(content)
+    }
+}
+
+impl ContentTransform for NumberFormatter {
+    fn accepts(&self, path: &Path) -> bool {
+        NumberFormatter::accepts(self, path)
+    }
+
+    fn transform<'a>(&self, path: &Path, content: &'a str) -> (Cow<'a, str>, usize) {
+        self.format_str(content, path)
+    }
+}
+
+impl ContentTransform for StringQuoteConverter {
+    fn accepts(&self, path: &Path) -> bool {
+        StringQuoteConverter::accepts(self, path)
+    }
+
+    fn transform<'a>(&self, path: &Path, content: &'a str) -> (Cow<'a, str>, usize) {
+        self.convert_str(content, path)
+    }
+}
+
+impl ContentTransform for ImportSorter {
+    fn accepts(&self, path: &Path) -> bool {
+        ImportSorter::accepts(self, path)
+    }
+
+    fn transform<'a>(&self, path: &Path, content: &'a str) -> (Cow<'a, str>, usize) {
+        self.sort_str(content, path)
+    }
+}
+
+impl ContentTransform for CommentConverter {
+    fn accepts(&self, path: &Path) -> bool {
+        CommentConverter::accepts(self, path)
+    }
+
+    fn transform<'a>(&self, path: &Path, content: &'a str) -> (Cow<'a, str>, usize) {
+        match self.convert_str(content, path) {
+            Some((converted, changes)) => (Cow::Owned(converted), changes),
+            None => (Cow::Borrowed(content), 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+    use crate::CaseFormat;
+
+    #[test]
+    fn test_process_files_filters_and_chains() {
+        let files = HashMap::from([
+            (PathBuf::from("src/app.py"), "userName = 1  \n".to_string()),
+            (PathBuf::from("src/clean.py"), "x = 1\n".to_string()),
+            (PathBuf::from("node_modules/pkg/index.js"), "a  \n".to_string()),
+            (PathBuf::from("image.png"), "not text  ".to_string()),
+        ]);
+        let converter = CaseConverter::builder(CaseFormat::CamelCase, CaseFormat::SnakeCase)
+            .extensions(vec![".py".to_string()])
+            .recursive(false)
+            .build()
+            .unwrap();
+        let cleaner = WhitespaceCleaner::with_defaults();
+
+        let (files, stats) = process_files(files, &[&converter, &cleaner]);
+
+        assert_eq!(files[Path::new("src/app.py")], "user_name = 1\n");
+        assert_eq!(files[Path::new("node_modules/pkg/index.js")], "a  \n");
+        assert_eq!(files[Path::new("image.png")], "not text  ");
+        assert_eq!(
+            stats,
+            MemoryStats {
+                files_processed: 2,
+                files_modified: 1,
+                changes: 2,
+            }
+        );
+    }
+
+    /// Appends a mark on every pass, so it never settles
+    struct Drifting;
+
+    impl ContentTransform for Drifting {
+        fn accepts(&self, path: &Path) -> bool {
+            path.extension().is_some_and(|ext| ext == "txt")
+        }
+
+        fn transform<'a>(&self, _path: &Path, content: &'a str) -> (Cow<'a, str>, usize) {
+            (Cow::Owned(format!("{}!", content)), 1)
+        }
+    }
+
+    #[test]
+    fn test_unstable_files() {
+        let fs = MemoryFs::new();
+        let test_dir = Path::new("project");
+        fs.add_file(test_dir.join("a.txt"), "a  \n");
+        fs.add_file(test_dir.join("b.md"), "b  \n");
+
+        let cleaner = WhitespaceCleaner::with_defaults();
+        let unstable =
+            unstable_files(&fs, test_dir, true, WalkOptions::default(), &[&cleaner]).unwrap();
+        assert!(unstable.is_empty());
+        assert_eq!(
+            unstable_files(
+                &fs,
+                test_dir,
+                true,
+                WalkOptions::default(),
+                &[&cleaner, &Drifting]
+            )
+            .unwrap(),
+            vec![test_dir.join("a.txt")]
+        );
+        // Nothing was written
+        assert_eq!(fs.read_to_string(test_dir.join("a.txt")).unwrap(), "a  \n");
+    }
+
+    #[test]
+    fn test_changed_files() {
+        let fs = MemoryFs::new();
+        let test_dir = Path::new("project");
+        fs.add_file(test_dir.join("a.txt"), "a  \n");
+        fs.add_file(test_dir.join("b.txt"), "b\n");
+
+        let cleaner = WhitespaceCleaner::with_defaults();
+        let changed =
+            changed_files(&fs, test_dir, true, WalkOptions::default(), &[&cleaner]).unwrap();
+        assert_eq!(changed, vec![test_dir.join("a.txt")]);
+        assert_eq!(fs.read_to_string(test_dir.join("a.txt")).unwrap(), "a  \n");
+    }
+
+    /// Random text built from identifiers in every case, emojis and odd whitespace
+    fn random_documents(count: usize) -> Vec<String> {
+        const FRAGMENTS: &[&str] = &[
+            "userName", "UserName", "user_name", "USER_NAME", "user-name", "user",
+            "getHTTPResponse", "parse_URL", "x2y", "v1_2", "ID", "_private", "__init__",
+            " ", "  ", "\t", "\n", "\r\n", "   \n", "\t\n", "= ", "(", ")", ".", "::",
+            "\"", "'", "# ", "// ", "- ", "\u{2705}", "\u{2610}", "\u{1F680}", "\u{2B50}",
+            "\u{1F7E1}", "\u{2714}\u{FE0F}", "\u{1F44D}\u{1F3FD}",
+        ];
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = |n: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % n as u64) as usize
+        };
+        (0..count)
+            .map(|_| (0..next(40)).map(|_| FRAGMENTS[next(FRAGMENTS.len())]).collect())
+            .collect()
+    }
+
+    fn assert_fixed_point(transform: &dyn ContentTransform, path: &Path, name: &str) {
+        for document in random_documents(300) {
+            let once = apply(&[transform], path, document.clone());
+            let twice = apply(&[transform], path, once.clone());
+            assert_eq!(twice, once, "{} is not idempotent on {:?}", name, document);
+        }
+    }
+
+    #[test]
+    fn test_outputs_are_fixed_points() {
+        let path = Path::new("notes.md");
+        assert_fixed_point(&WhitespaceCleaner::with_defaults(), path, "clean");
+        assert_fixed_point(&EmojiTransformer::with_defaults(), path, "emojis");
+
+        let path = Path::new("main.py");
+        for from in CaseFormat::ALL {
+            for to in CaseFormat::ALL {
+                let converter = CaseConverter::builder(from, to)
+                    .recursive(false)
+                    .build()
+                    .unwrap();
+                assert_fixed_point(&converter, path, &format!("convert {:?} -> {:?}", from, to));
+            }
+        }
+    }
+}
@@ -0,0 +1,784 @@
+//! Language naming conventions
+//!
+//! Lints declarations against a language's naming conventions, recognizing each
+//! declaration from simple syntactic context (`fn name`, `struct Name`, `const NAME:`,
+//! `name: Type` in a parameter list or struct body), and can fix deviations by renaming
+//! every occurrence of the identifier in the code (not comments or strings) of the
+//! processed files. Rust is supported: snake_case functions, variables and fields,
+//! PascalCase types and SCREAMING_SNAKE_CASE constants and statics, with `#[allow(...)]`
+//! for the matching rustc lint respected.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::case::{identifier_words, CaseFormat};
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::report::{report, EventSink, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+static RUST_FN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bfn\s+([A-Za-z_]\w*)").unwrap());
+static RUST_TYPE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:struct|enum|trait|type|union)\s+([A-Za-z_]\w*)").unwrap());
+/// `const NAME:` and `static [mut] NAME:`, but not `'static` or `const fn`
+static RUST_CONST: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[^'\w])(?:const|static)\s+(?:mut\s+)?([A-Za-z_]\w*)\s*:").unwrap()
+});
+static RUST_LET: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\blet\s+(?:mut\s+)?([A-Za-z_]\w*)\s*[:=;]").unwrap());
+static RUST_FOR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bfor\s+(?:mut\s+)?([A-Za-z_]\w*)\s+in\b").unwrap());
+/// Declarations whose braces hold fields
+static RUST_STRUCT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:struct|union)\s+[A-Za-z_]\w*").unwrap());
+/// `#[allow(...)]` or `#![allow(...)]`
+static ALLOW: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^#(!?)\[allow\(([^)]*)\)\]").unwrap());
+
+/// Languages with built-in naming conventions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NamingLanguage {
+    Rust,
+}
+
+impl NamingLanguage {
+    /// Looks a language up by name (`rust`)
+    pub fn from_name(name: &str) -> Option<NamingLanguage> {
+        match name.to_lowercase().as_str() {
+            "rust" | "rs" => Some(NamingLanguage::Rust),
+            _ => None,
+        }
+    }
+
+    /// Extensions of the language's source files
+    pub fn file_extensions(self) -> Vec<String> {
+        match self {
+            NamingLanguage::Rust => vec![".rs".to_string()],
+        }
+    }
+}
+
+/// Kind of declaration, which decides the expected case format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemKind {
+    /// Functions and methods
+    Function,
+    /// `let` and `for` bindings, and function parameters
+    Variable,
+    /// Struct and union fields
+    Field,
+    /// Structs, enums, traits, unions and type aliases
+    Type,
+    /// Constants and statics
+    Constant,
+}
+
+impl ItemKind {
+    /// Case format the convention requires
+    pub fn expected_format(self) -> CaseFormat {
+        match self {
+            ItemKind::Function | ItemKind::Variable | ItemKind::Field => CaseFormat::SnakeCase,
+            ItemKind::Type => CaseFormat::PascalCase,
+            ItemKind::Constant => CaseFormat::ScreamingSnakeCase,
+        }
+    }
+
+    /// Name of the matching rustc lint, as used in `#[allow(...)]`
+    pub fn lint(self) -> &'static str {
+        match self {
+            ItemKind::Function | ItemKind::Variable | ItemKind::Field => "non_snake_case",
+            ItemKind::Type => "non_camel_case_types",
+            ItemKind::Constant => "non_upper_case_globals",
+        }
+    }
+
+    /// Whether `name` already follows the convention
+    fn accepts(self, name: &str) -> bool {
+        let name = name.trim_start_matches('_');
+        match self {
+            ItemKind::Function | ItemKind::Variable | ItemKind::Field => !name.chars().any(char::is_uppercase),
+            ItemKind::Type => {
+                name.chars().next().is_none_or(|c| !c.is_lowercase()) && !name.contains('_')
+            }
+            ItemKind::Constant => !name.chars().any(char::is_lowercase),
+        }
+    }
+}
+
+impl fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ItemKind::Function => "function",
+            ItemKind::Variable => "variable",
+            ItemKind::Field => "field",
+            ItemKind::Type => "type",
+            ItemKind::Constant => "constant",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A declaration that doesn't follow the naming convention
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NamingViolation {
+    pub path: PathBuf,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, counted in characters
+    pub column: usize,
+    pub kind: ItemKind,
+    pub name: String,
+    /// Name following the convention
+    pub expected: String,
+}
+
+/// Options for naming convention checks
+#[derive(Debug, Clone)]
+pub struct NamingOptions {
+    /// Language whose conventions apply
+    pub language: NamingLanguage,
+    /// Rename violating identifiers instead of only reporting them
+    pub fix: bool,
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for NamingOptions {
+    fn default() -> Self {
+        NamingOptions {
+            language: NamingLanguage::Rust,
+            fix: false,
+            file_extensions: NamingLanguage::Rust.file_extensions(),
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// Naming convention linter and fixer
+pub struct NamingChecker {
+    options: NamingOptions,
+    walker: Walker,
+}
+
+impl NamingChecker {
+    /// Creates a new checker with the given options
+    pub fn new(options: NamingOptions) -> Self {
+        NamingChecker {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a checker with default options
+    pub fn with_defaults() -> Self {
+        NamingChecker {
+            options: NamingOptions::default(),
+            walker: Walker::default(),
+        }
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        if !self.options.fs.is_file(path) {
+            return false;
+        }
+
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none()
+    }
+
+    /// Lists the declarations in `content` that break the naming convention
+    pub fn check_str(&self, path: &Path, content: &str) -> Vec<NamingViolation> {
+        let patterns: [(&Regex, ItemKind); 5] = match self.options.language {
+            NamingLanguage::Rust => [
+                (&RUST_FN, ItemKind::Function),
+                (&RUST_TYPE, ItemKind::Type),
+                (&RUST_CONST, ItemKind::Constant),
+                (&RUST_LET, ItemKind::Variable),
+                (&RUST_FOR, ItemKind::Variable),
+            ],
+        };
+
+        let mut violations = Vec::new();
+        let mut file_allows = String::new();
+        let mut item_allows = String::new();
+        let mut in_block_comment = false;
+        let mut signature = None;
+        let mut struct_body = None;
+
+        for (n, line) in content.lines().enumerate() {
+            let code = code_part(line, &mut in_block_comment);
+            let trimmed = code.trim();
+
+            if let Some(caps) = ALLOW.captures(trimmed) {
+                let allows = if caps[1].is_empty() {
+                    &mut item_allows
+                } else {
+                    &mut file_allows
+                };
+                allows.push_str(&caps[2]);
+                allows.push(',');
+                continue;
+            }
+            if trimmed.is_empty() || trimmed.starts_with("#[") {
+                continue;
+            }
+
+            let mut names = Vec::new();
+            for (pattern, kind) in patterns {
+                for caps in pattern.captures_iter(&code) {
+                    names.push((caps.get(1).unwrap().range(), kind));
+                }
+            }
+
+            // Parameters, in a signature continued from earlier lines or opened on this one
+            let mut params = signature_params(&code, 0, &mut signature);
+            for fn_match in RUST_FN.find_iter(&code) {
+                signature = Some(Signature::default());
+                params.extend(signature_params(&code, fn_match.end(), &mut signature));
+            }
+            names.extend(params.into_iter().map(|range| (range, ItemKind::Variable)));
+
+            // Fields, in a struct body continued from earlier lines or opened on this one
+            let mut fields = struct_fields(&code, 0, &mut struct_body);
+            for struct_match in RUST_STRUCT.find_iter(&code) {
+                struct_body = Some(StructBody::default());
+                fields.extend(struct_fields(&code, struct_match.end(), &mut struct_body));
+            }
+            names.extend(fields.into_iter().map(|range| (range, ItemKind::Field)));
+
+            for (range, kind) in names {
+                let name = &code[range.clone()];
+                if kind.accepts(name)
+                    || file_allows.contains(kind.lint())
+                    || item_allows.contains(kind.lint())
+                {
+                    continue;
+                }
+
+                violations.push(NamingViolation {
+                    path: path.to_path_buf(),
+                    line: n + 1,
+                    column: line[..range.start].chars().count() + 1,
+                    kind,
+                    name: name.to_string(),
+                    expected: conventional_name(name, kind),
+                });
+            }
+            item_allows.clear();
+        }
+
+        violations.sort_by_key(|v| (v.line, v.column));
+        violations
+    }
+
+    /// Lists naming violations in a single file
+    pub fn check_file(&self, path: &Path) -> crate::Result<Vec<NamingViolation>> {
+        if !self.should_process(path) {
+            return Ok(Vec::new());
+        }
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        Ok(self.check_str(path, &content))
+    }
+
+    /// Renames every whole-word occurrence of the `renames` keys in the code of a
+    /// string, leaving comments and string literals alone
+    ///
+    /// Returns the new content and the number of occurrences renamed.
+    pub fn rename_str(&self, content: &str, renames: &BTreeMap<String, String>) -> (String, usize) {
+        if renames.is_empty() {
+            return (content.to_string(), 0);
+        }
+
+        let alternatives: Vec<String> = renames.keys().map(|name| regex::escape(name)).collect();
+        let pattern = Regex::new(&format!(r"\b(?:{})\b", alternatives.join("|"))).unwrap();
+
+        let mut count = 0;
+        let mut renamed = String::with_capacity(content.len());
+        let mut in_block_comment = false;
+        for line in content.split_inclusive('\n') {
+            // The code has the byte offsets of the line, so matches map back onto it
+            let code = code_part(line, &mut in_block_comment);
+            let mut last = 0;
+            for found in pattern.find_iter(&code) {
+                renamed.push_str(&line[last..found.start()]);
+                renamed.push_str(&renames[found.as_str()]);
+                last = found.end();
+                count += 1;
+            }
+            renamed.push_str(&line[last..]);
+        }
+        (renamed, count)
+    }
+
+    fn fix_file(&self, path: &Path, renames: &BTreeMap<String, String>) -> crate::Result<FileOutcome> {
+        if !self.should_process(path) {
+            return Ok(FileOutcome::unchanged());
+        }
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        let (renamed, count) = self.rename_str(&content, renames);
+        if count == 0 {
+            return Ok(FileOutcome::unchanged());
+        }
+
+        if !self.options.dry_run {
+            self.options.fs.write(path, renamed.as_bytes())?;
+        }
+        Ok(FileOutcome::changed(count))
+    }
+
+    fn report(&self, path: &Path, outcome: FileOutcome) {
+        if !outcome.changed {
+            return;
+        }
+
+        self.options.events.file_changed(path, outcome.changes, self.options.dry_run);
+        if self.options.dry_run {
+            report!(
+                "Would rename {} identifiers in '{}'",
+                outcome.changes,
+                path.display()
+            );
+        } else {
+            report!("Renamed {} identifiers in '{}'", outcome.changes, path.display());
+        }
+    }
+
+    /// Checks a directory or file, returning the violations in path order
+    ///
+    /// With `fix` set, violating identifiers are then renamed in every processed file,
+    /// so uses in other modules follow their declaration.
+    pub fn process(&self, path: &Path) -> crate::Result<Vec<NamingViolation>> {
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.check_file(file)
+        });
+        let mut violations = Vec::new();
+        for result in results {
+            violations.extend(result?);
+        }
+
+        if !self.options.fix || violations.is_empty() {
+            return Ok(violations);
+        }
+
+        let renames: BTreeMap<String, String> = violations
+            .iter()
+            .map(|v| (v.name.clone(), v.expected.clone()))
+            .collect();
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.fix_file(file, &renames)
+        });
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            self.report(file_path, result?);
+        }
+
+        Ok(violations)
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+/// The name `name` should have as a `kind`, keeping leading underscores
+fn conventional_name(name: &str, kind: ItemKind) -> String {
+    let trimmed = name.trim_start_matches('_');
+    let underscores = &name[..name.len() - trimmed.len()];
+    kind.expected_format()
+        .join_words(&identifier_words(trimmed), underscores, "")
+}
+
+/// Where the parameter list of a function signature is, while it spans lines
+#[derive(Debug, Default)]
+struct Signature {
+    /// Open parentheses; the parameters are at depth 1
+    parens: usize,
+    /// Open angle brackets, of the generics or of a parameter's type
+    angles: usize,
+    /// Whether the next identifier at depth 1 starts a parameter
+    at_param: bool,
+}
+
+/// The byte ranges of the parameters declared in `code` from `from` on, in the open
+/// `signature`, which is closed (set to `None`) with its parameter list
+///
+/// Only plain `name: Type` and `mut name: Type` parameters are found; `self` and
+/// destructuring patterns are left alone.
+fn signature_params(
+    code: &str,
+    from: usize,
+    signature: &mut Option<Signature>,
+) -> Vec<Range<usize>> {
+    let mut params = Vec::new();
+    let Some(sig) = signature.as_mut() else {
+        return params;
+    };
+
+    let bytes = code.as_bytes();
+    let mut i = from;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c == b'_' || c.is_ascii_alphabetic() {
+            let end = i + bytes[i..]
+                .iter()
+                .take_while(|b| **b == b'_' || b.is_ascii_alphanumeric())
+                .count();
+            if sig.parens == 1 && sig.angles == 0 && sig.at_param && &code[i..end] != "mut" {
+                sig.at_param = false;
+                let rest = code[end..].trim_start();
+                if rest.starts_with(':') && !rest.starts_with("::") {
+                    params.push(i..end);
+                }
+            }
+            i = end;
+            continue;
+        }
+
+        match c {
+            // The arrow of a return type, or of an `Fn(A) -> B` bound
+            b'-' if bytes.get(i + 1) == Some(&b'>') => i += 1,
+            b'<' => sig.angles += 1,
+            b'>' => sig.angles = sig.angles.saturating_sub(1),
+            // Parentheses of a generic bound (`F: Fn(i32)`) don't open the parameters
+            b'(' if sig.parens > 0 || sig.angles == 0 => {
+                sig.parens += 1;
+                if sig.parens == 1 {
+                    sig.at_param = true;
+                }
+            }
+            b')' if sig.parens > 0 => {
+                sig.parens -= 1;
+                if sig.parens == 0 {
+                    *signature = None;
+                    return params;
+                }
+            }
+            b',' if sig.parens == 1 && sig.angles == 0 => sig.at_param = true,
+            b' ' | b'\t' => {}
+            _ if sig.parens == 1 && sig.angles == 0 => sig.at_param = false,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    params
+}
+
+/// Where the body of a struct or union is, while it spans lines
+#[derive(Debug, Default)]
+struct StructBody {
+    /// Whether the opening brace has been seen
+    open: bool,
+    /// Open parentheses, brackets, angle brackets and braces; the fields are at depth 0
+    depth: usize,
+    /// Whether the next identifier at depth 0 starts a field
+    at_field: bool,
+}
+
+/// The byte ranges of the fields declared in `code` from `from` on, in the open
+/// struct `body`, which is closed (set to `None`) with its closing brace, or at the `;`
+/// ending a unit or tuple struct
+///
+/// Only named `name: Type` fields are found, after an optional `pub` or `pub(...)`.
+fn struct_fields(code: &str, from: usize, body: &mut Option<StructBody>) -> Vec<Range<usize>> {
+    let mut fields = Vec::new();
+    let Some(state) = body.as_mut() else {
+        return fields;
+    };
+
+    let bytes = code.as_bytes();
+    let mut i = from;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c == b'_' || c.is_ascii_alphabetic() {
+            let end = i + bytes[i..]
+                .iter()
+                .take_while(|b| **b == b'_' || b.is_ascii_alphanumeric())
+                .count();
+            if state.open && state.depth == 0 && state.at_field && &code[i..end] != "pub" {
+                state.at_field = false;
+                let rest = code[end..].trim_start();
+                if rest.starts_with(':') && !rest.starts_with("::") {
+                    fields.push(i..end);
+                }
+            }
+            i = end;
+            continue;
+        }
+
+        match c {
+            b'-' if bytes.get(i + 1) == Some(&b'>') => i += 1,
+            b'{' if !state.open && state.depth == 0 => {
+                state.open = true;
+                state.at_field = true;
+            }
+            b'}' if state.open && state.depth == 0 => {
+                *body = None;
+                return fields;
+            }
+            b';' if !state.open && state.depth == 0 => {
+                *body = None;
+                return fields;
+            }
+            b'(' | b'[' | b'<' | b'{' => state.depth += 1,
+            b')' | b']' | b'>' | b'}' => state.depth = state.depth.saturating_sub(1),
+            b',' if state.depth == 0 => state.at_field = true,
+            // Attributes on a field, and whitespace, come before its name
+            b'#' | b' ' | b'\t' => {}
+            _ if state.depth == 0 => state.at_field = false,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    fields
+}
+
+/// The code on a line, with comments and string contents blanked so neither is
+/// mistaken for a declaration or renamed
+///
+/// Blanked characters become as many spaces as they take bytes, so byte offsets into
+/// the code are offsets into the line; a line comment ends the code.
+fn code_part(line: &str, in_block_comment: &mut bool) -> String {
+    let mut code = String::with_capacity(line.len());
+    let blank = |code: &mut String, c: char| code.extend(std::iter::repeat_n(' ', c.len_utf8()));
+    let mut chars = line.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if *in_block_comment {
+            blank(&mut code, c);
+            if c == '*' && chars.peek() == Some(&'/') {
+                chars.next();
+                code.push(' ');
+                *in_block_comment = false;
+            }
+            continue;
+        }
+        if in_string {
+            match c {
+                '\\' => {
+                    blank(&mut code, c);
+                    if let Some(escaped) = chars.next() {
+                        blank(&mut code, escaped);
+                    }
+                }
+                '"' => {
+                    in_string = false;
+                    code.push(c);
+                }
+                _ => blank(&mut code, c),
+            }
+            continue;
+        }
+        match c {
+            '/' if chars.peek() == Some(&'/') => break,
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                code.push_str("  ");
+                *in_block_comment = true;
+            }
+            '"' => {
+                in_string = true;
+                code.push(c);
+            }
+            _ => code.push(c),
+        }
+    }
+
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+
+    fn check(content: &str) -> Vec<(usize, ItemKind, String, String)> {
+        NamingChecker::with_defaults()
+            .check_str(Path::new("lib.rs"), content)
+            .into_iter()
+            .map(|v| (v.line, v.kind, v.name, v.expected))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_conventions() {
+        let content = concat!(
+            "const maxSize: usize = 10;\n",
+            "static _Counter: u32 = 0;\n",
+            "struct http_client;\n",
+            "pub fn parseArgs(input: &'static str) -> Option<()> {\n",
+            "    let userName = \"let badName = 1\";\n",
+            "    for Item in items {} // fn notAFunction\n",
+            "    None\n",
+            "}\n",
+            "const fn ok_fn() {}\n",
+            "struct HTTPServer;\n",
+        );
+        assert_eq!(
+            check(content),
+            vec![
+                (1, ItemKind::Constant, "maxSize".into(), "MAX_SIZE".into()),
+                (2, ItemKind::Constant, "_Counter".into(), "_COUNTER".into()),
+                (3, ItemKind::Type, "http_client".into(), "HttpClient".into()),
+                (4, ItemKind::Function, "parseArgs".into(), "parse_args".into()),
+                (5, ItemKind::Variable, "userName".into(), "user_name".into()),
+                (6, ItemKind::Variable, "Item".into(), "item".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_allow_attributes() {
+        let content = "#[allow(non_snake_case)]\nfn getX() {}\nfn getY() {}\n";
+        assert_eq!(check(content).len(), 1);
+
+        let content = "#![allow(non_camel_case_types)]\nstruct c_thing;\nfn getY() {}\n";
+        assert_eq!(check(content), vec![(3, ItemKind::Function, "getY".into(), "get_y".into())]);
+    }
+
+    #[test]
+    fn test_parameters() {
+        let content = concat!(
+            "fn apply<F: Fn(i32) -> i32>(someValue: i32, mut f: F, &self) {}\n",
+            "fn build(\n",
+            "    mut maxLen: usize,\n",
+            "    map: HashMap<String, std::path::PathBuf>,\n",
+            "    (a, B): (i32, i32),\n",
+            ") -> Option<usize> {\n",
+            "    let _ = |notParam: i32| notParam;\n",
+            "}\n",
+        );
+        assert_eq!(
+            check(content),
+            vec![
+                (1, ItemKind::Variable, "someValue".into(), "some_value".into()),
+                (3, ItemKind::Variable, "maxLen".into(), "max_len".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_struct_fields() {
+        let content = concat!(
+            "pub struct Config<T: Fn(u8) -> u8>\n",
+            "where\n",
+            "    T: Clone,\n",
+            "{\n",
+            "    pub maxSize: usize,\n",
+            "    #[serde(rename = \"X\")]\n",
+            "    pub(crate) userName: HashMap<String, Vec<T>>,\n",
+            "    #[allow(non_snake_case)]\n",
+            "    legacyName: bool,\n",
+            "    callback: fn(inputValue: u8) -> u8,\n",
+            "}\n",
+            "struct Point { x: i32, #[doc(hidden)] yPos: i32 }\n",
+            "struct Pair(i32, i32);\n",
+            "fn main() { let p = Point { x: 1, yPos: 2 }; }\n",
+        );
+        assert_eq!(
+            check(content),
+            vec![
+                (5, ItemKind::Field, "maxSize".into(), "max_size".into()),
+                (7, ItemKind::Field, "userName".into(), "user_name".into()),
+                (12, ItemKind::Field, "yPos".into(), "y_pos".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_violations_in_position_order() {
+        let content = "fn parseArgs(someValue: i32) { let userName = 1; }\nstruct s { aB: u8 }\n";
+        let found: Vec<(usize, usize, String)> = NamingChecker::with_defaults()
+            .check_str(Path::new("lib.rs"), content)
+            .into_iter()
+            .map(|v| (v.line, v.column, v.name))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (1, 4, "parseArgs".into()),
+                (1, 14, "someValue".into()),
+                (1, 36, "userName".into()),
+                (2, 8, "s".into()),
+                (2, 12, "aB".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fix_across_files() {
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("naming_fix");
+        fs.add_file(test_dir.join("lib.rs"), "pub fn parseArgs() {}\n");
+        fs.add_file(
+            test_dir.join("main.rs"),
+            concat!(
+                "// calls parseArgs\n",
+                "fn main() { lib::parseArgs(); println!(\"parseArgs {}\", \"\\\"parseArgs\"); }\n",
+            ),
+        );
+
+        let options = NamingOptions {
+            fs: fs.clone(),
+            fix: true,
+            ..NamingOptions::default()
+        };
+        let violations = NamingChecker::new(options).process(&test_dir).unwrap();
+
+        assert_eq!(violations.len(), 1);
+        assert_eq!(
+            fs.read_to_string(test_dir.join("main.rs")).unwrap(),
+            concat!(
+                "// calls parseArgs\n",
+                "fn main() { lib::parse_args(); println!(\"parseArgs {}\", \"\\\"parseArgs\"); }\n",
+            )
+        );
+        assert_eq!(fs.read_to_string(test_dir.join("lib.rs")).unwrap(), "pub fn parse_args() {}\n");
+    }
+}
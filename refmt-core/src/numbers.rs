@@ -0,0 +1,553 @@
+//! Digit separators for long numeric literals
+//!
+//! Inserts the language's digit separator into long literals (`1000000` ->
+//! `1_000_000` in Rust and Python, `1'000'000` in C++). Only languages that accept
+//! separators are touched, and strings, comments and literals that already contain a
+//! separator are left alone.
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::report::{report, EventSink, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// Options for numeric literal formatting
+#[derive(Debug, Clone)]
+pub struct NumberOptions {
+    /// Minimum number of digits before a literal (or its fraction) is grouped
+    pub min_digits: usize,
+    /// Also group hexadecimal, octal and binary literals, in fours
+    pub radix_literals: bool,
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for NumberOptions {
+    fn default() -> Self {
+        NumberOptions {
+            min_digits: 5,
+            radix_literals: false,
+            file_extensions: [
+                ".rs", ".py", ".pyi", ".java", ".kt", ".kts", ".swift", ".go", ".cs",
+                ".js", ".mjs", ".cjs", ".jsx", ".ts", ".tsx",
+                ".cpp", ".cc", ".cxx", ".hpp", ".hh", ".hxx",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// Languages with digit separators
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Language {
+    Rust,
+    Python,
+    Java,
+    Kotlin,
+    Swift,
+    Go,
+    CSharp,
+    JavaScript,
+    Cpp,
+}
+
+impl Language {
+    fn from_path(path: &Path) -> Option<Language> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        let language = match ext.as_str() {
+            "rs" => Language::Rust,
+            "py" | "pyi" => Language::Python,
+            "java" => Language::Java,
+            "kt" | "kts" => Language::Kotlin,
+            "swift" => Language::Swift,
+            "go" => Language::Go,
+            "cs" => Language::CSharp,
+            "js" | "mjs" | "cjs" | "jsx" | "ts" | "tsx" => Language::JavaScript,
+            "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => Language::Cpp,
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    fn separator(self) -> u8 {
+        match self {
+            Language::Cpp => b'\'',
+            _ => b'_',
+        }
+    }
+
+    fn line_comment(self) -> &'static [u8] {
+        match self {
+            Language::Python => b"#",
+            _ => b"//",
+        }
+    }
+
+    /// Whether `"""` opens a multi-line string
+    fn triple_quotes(self) -> bool {
+        matches!(
+            self,
+            Language::Python | Language::Java | Language::Kotlin | Language::Swift | Language::CSharp
+        )
+    }
+
+    /// Whether backticks delimit strings
+    fn backtick_strings(self) -> bool {
+        matches!(self, Language::Go | Language::JavaScript)
+    }
+}
+
+/// Inserts digit separators into long numeric literals
+pub struct NumberFormatter {
+    options: NumberOptions,
+    walker: Walker,
+}
+
+impl NumberFormatter {
+    /// Creates a new number formatter with the given options
+    pub fn new(options: NumberOptions) -> Self {
+        NumberFormatter {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a formatter with default options
+    pub fn with_defaults() -> Self {
+        NumberFormatter {
+            options: NumberOptions::default(),
+            walker: Walker::default(),
+        }
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        self.options.fs.is_file(path) && self.accepts(path)
+    }
+
+    /// Checks the path alone against the filters, without touching the filesystem
+    pub fn accepts(&self, path: &Path) -> bool {
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none()
+            && Language::from_path(path).is_some()
+    }
+
+    /// Groups the digits of long literals in source code, returning the new content
+    /// and the number of literals changed
+    ///
+    /// The language is taken from `path`'s extension; content in other languages is
+    /// returned borrowed and unchanged.
+    pub fn format_str<'a>(&self, content: &'a str, path: &Path) -> (Cow<'a, str>, usize) {
+        match Language::from_path(path) {
+            Some(language) => self.format_source(content, language),
+            None => (Cow::Borrowed(content), 0),
+        }
+    }
+
+    fn format_source<'a>(&self, content: &'a str, language: Language) -> (Cow<'a, str>, usize) {
+        let bytes = content.as_bytes();
+        let mut out = String::new();
+        let mut copied = 0;
+        let mut changes = 0;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let rest = &bytes[i..];
+            let c = bytes[i];
+
+            if rest.starts_with(language.line_comment()) {
+                i = find(bytes, i, b"\n").unwrap_or(bytes.len());
+            } else if language != Language::Python && rest.starts_with(b"/*") {
+                i = find(bytes, i + 2, b"*/").map_or(bytes.len(), |end| end + 2);
+            } else if language.triple_quotes() && (rest.starts_with(b"\"\"\"") || rest.starts_with(b"'''")) {
+                let delimiter = &rest[..3];
+                i = find(bytes, i + 3, delimiter).map_or(bytes.len(), |end| end + 3);
+            } else if c == b'"' || (c == b'`' && language.backtick_strings()) {
+                i = skip_string(bytes, i, c, language != Language::Go || c == b'"');
+            } else if c == b'\'' {
+                i = if language == Language::Rust && !is_char_literal(content, i) {
+                    i + 1 // Lifetime or label
+                } else {
+                    skip_string(bytes, i, c, true)
+                };
+            } else if is_ident_byte(c) && !c.is_ascii_digit() {
+                let start = i;
+                while i < bytes.len() && is_ident_byte(bytes[i]) {
+                    i += 1;
+                }
+                if language == Language::Rust && matches!(&bytes[start..i], b"r" | b"br" | b"cr") {
+                    if let Some(end) = skip_raw_string(bytes, i) {
+                        i = end;
+                    }
+                }
+            } else if c.is_ascii_digit() {
+                let start = i;
+                let (end, grouped) = self.scan_number(content, start, language);
+                i = end;
+
+                // `.5` and tuple fields (`x.0`) aren't literals on their own, but
+                // the end of a range (`0..100000`) is
+                let after_dot = start > 0
+                    && bytes[start - 1] == b'.'
+                    && !(start > 1 && bytes[start - 2] == b'.');
+                if let (Some(grouped), false) = (grouped, after_dot) {
+                    out.push_str(&content[copied..start]);
+                    out.push_str(&grouped);
+                    copied = end;
+                    changes += 1;
+                }
+            } else {
+                i += 1;
+            }
+        }
+
+        if changes == 0 {
+            return (Cow::Borrowed(content), 0);
+        }
+        out.push_str(&content[copied..]);
+        (Cow::Owned(out), changes)
+    }
+
+    /// Scans the literal starting at `start`, returning where it ends and its grouped
+    /// form if it needs separators
+    fn scan_number(&self, content: &str, start: usize, language: Language) -> (usize, Option<String>) {
+        let bytes = content.as_bytes();
+        let separator = language.separator();
+        let take = |mut i: usize, digit: fn(u8) -> bool| {
+            while i < bytes.len() && (digit(bytes[i]) || bytes[i] == separator) {
+                i += 1;
+            }
+            i
+        };
+        let suffix_end = |mut i: usize| {
+            while i < bytes.len() && is_ident_byte(bytes[i]) {
+                i += 1;
+            }
+            i
+        };
+
+        // Hexadecimal, octal and binary
+        let radix = match bytes.get(start..start + 2) {
+            Some(b"0x" | b"0X") => Some((|b: u8| b.is_ascii_hexdigit()) as fn(u8) -> bool),
+            Some(b"0o" | b"0O") => Some((|b: u8| matches!(b, b'0'..=b'7')) as fn(u8) -> bool),
+            Some(b"0b" | b"0B") => Some((|b: u8| matches!(b, b'0' | b'1')) as fn(u8) -> bool),
+            _ => None,
+        };
+        if let Some(digit) = radix {
+            let digits_end = take(start + 2, digit);
+            let end = suffix_end(digits_end);
+            let digits = &content[start + 2..digits_end];
+            if !self.options.radix_literals
+                || digits.len() < self.options.min_digits
+                || digits.bytes().any(|b| b == separator)
+            {
+                return (end, None);
+            }
+            let grouped = format!(
+                "{}{}{}",
+                &content[start..start + 2],
+                group_from_right(digits, 4, separator),
+                &content[digits_end..end]
+            );
+            return (end, Some(grouped));
+        }
+
+        let int_end = take(start, |b| b.is_ascii_digit());
+        let mut i = int_end;
+
+        let mut fraction = None;
+        if i + 1 < bytes.len() && bytes[i] == b'.' && bytes[i + 1].is_ascii_digit() {
+            let fraction_end = take(i + 1, |b| b.is_ascii_digit());
+            fraction = Some((i + 1, fraction_end));
+            i = fraction_end;
+        }
+
+        let exponent_start = i;
+        if i < bytes.len() && matches!(bytes[i], b'e' | b'E') {
+            let mut j = i + 1;
+            if j < bytes.len() && matches!(bytes[j], b'+' | b'-') {
+                j += 1;
+            }
+            if j < bytes.len() && bytes[j].is_ascii_digit() {
+                i = take(j, |b| b.is_ascii_digit());
+            }
+        }
+        let end = suffix_end(i);
+
+        let literal = &content[start..end];
+        let integer = &content[start..int_end];
+        // Leading zeros mean octal in C-family languages
+        if literal.bytes().any(|b| b == separator) || (integer.len() > 1 && integer.starts_with('0')) {
+            return (end, None);
+        }
+
+        let min = self.options.min_digits;
+        let group_integer = integer.len() >= min;
+        let group_fraction = fraction.is_some_and(|(s, e)| e - s >= min);
+        if !group_integer && !group_fraction {
+            return (end, None);
+        }
+
+        let mut grouped = if group_integer {
+            group_from_right(integer, 3, separator)
+        } else {
+            integer.to_string()
+        };
+        if let Some((s, e)) = fraction {
+            grouped.push('.');
+            if group_fraction {
+                grouped.push_str(&group_from_left(&content[s..e], 3, separator));
+            } else {
+                grouped.push_str(&content[s..e]);
+            }
+        }
+        grouped.push_str(&content[exponent_start..end]);
+
+        (end, Some(grouped))
+    }
+
+    /// Formats numeric literals in a single file
+    pub fn format_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        let outcome = self.apply_to_file(path)?;
+        self.report(path, outcome);
+        Ok(outcome)
+    }
+
+    /// Formats a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        if !self.should_process(path) {
+            return Ok(FileOutcome::unchanged());
+        }
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        let (formatted, changes) = self.format_str(&content, path);
+
+        match formatted {
+            Cow::Borrowed(_) => Ok(FileOutcome::unchanged()),
+            Cow::Owned(formatted) => {
+                if !self.options.dry_run {
+                    self.options.fs.write(path, formatted.as_bytes())?;
+                }
+                Ok(FileOutcome::changed(changes))
+            }
+        }
+    }
+
+    fn report(&self, path: &Path, outcome: FileOutcome) {
+        if !outcome.changed {
+            return;
+        }
+
+        self.options.events.file_changed(path, outcome.changes, self.options.dry_run);
+        if self.options.dry_run {
+            report!(
+                "Would format {} numeric literals in '{}'",
+                outcome.changes,
+                path.display()
+            );
+        } else {
+            report!(
+                "Formatted {} numeric literals in '{}'",
+                outcome.changes,
+                path.display()
+            );
+        }
+    }
+
+    /// Processes a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<(usize, usize)> {
+        let mut total_files = 0;
+        let mut total_changes = 0;
+
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.apply_to_file(file)
+        });
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let outcome = result?;
+            self.report(file_path, outcome);
+            if outcome.changed {
+                total_files += 1;
+                total_changes += outcome.changes;
+            }
+        }
+
+        Ok((total_files, total_changes))
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80
+}
+
+/// Position of the next `needle` at or after `from`
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|p| from + p)
+}
+
+/// Skips a quoted string starting at `start`, returning the position after it
+///
+/// Strings end at the closing quote or, for unterminated ones, at the end of the line.
+fn skip_string(bytes: &[u8], start: usize, quote: u8, escapes: bool) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if escapes => i += 2,
+            b if b == quote => return i + 1,
+            b'\n' if quote != b'`' => return i,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Skips a Rust raw string (`r#"..."#`) whose hashes start at `start`
+fn skip_raw_string(bytes: &[u8], start: usize) -> Option<usize> {
+    let hashes = bytes[start..].iter().take_while(|&&b| b == b'#').count();
+    if bytes.get(start + hashes) != Some(&b'"') {
+        return None;
+    }
+
+    let closing = format!("\"{}", "#".repeat(hashes));
+    let end = find(bytes, start + hashes + 1, closing.as_bytes())
+        .map_or(bytes.len(), |end| end + closing.len());
+    Some(end)
+}
+
+/// Whether the `'` at `start` opens a Rust char literal rather than a lifetime
+fn is_char_literal(content: &str, start: usize) -> bool {
+    let mut chars = content[start + 1..].chars();
+    match chars.next() {
+        Some('\\') => true,
+        Some(_) => chars.next() == Some('\''),
+        None => false,
+    }
+}
+
+/// Inserts `separator` every `size` digits, counting from the right
+fn group_from_right(digits: &str, size: usize, separator: u8) -> String {
+    let len = digits.len();
+    let mut grouped = String::with_capacity(len + len / size);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (len - i).is_multiple_of(size) {
+            grouped.push(separator as char);
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+/// Inserts `separator` every `size` digits, counting from the left
+fn group_from_left(digits: &str, size: usize, separator: u8) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / size);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && i.is_multiple_of(size) {
+            grouped.push(separator as char);
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(content: &str, file: &str) -> String {
+        NumberFormatter::with_defaults()
+            .format_str(content, Path::new(file))
+            .0
+            .into_owned()
+    }
+
+    #[test]
+    fn test_group_literals() {
+        assert_eq!(
+            format("let n = 1000000u64 + 12345 + 1234;\n", "main.rs"),
+            "let n = 1_000_000u64 + 12_345 + 1234;\n"
+        );
+        assert_eq!(
+            format("x = 3.14159265 * 1e10 + 100000.5\n", "calc.py"),
+            "x = 3.141_592_65 * 1e10 + 100_000.5\n"
+        );
+        assert_eq!(format("auto n = 1000000ULL;\n", "main.cpp"), "auto n = 1'000'000ULL;\n");
+        assert_eq!(format("for i in 0..100000 {}\n", "main.rs"), "for i in 0..100_000 {}\n");
+    }
+
+    #[test]
+    fn test_untouched() {
+        let content = concat!(
+            "let a = 10_00_000; // 1000000\n",
+            "let s = \"1000000\"; let r = r#\"1000000\"#;\n",
+            "let f: &'static str = x.0; let c = '1';\n",
+            "let v = var1000000 + 0o1234567 + 0xDEADBEEF;\n",
+        );
+        let (result, changes) = NumberFormatter::with_defaults().format_str(content, Path::new("lib.rs"));
+        assert!(matches!(result, Cow::Borrowed(_)));
+        assert_eq!(changes, 0);
+
+        // C has no digit separators before C23
+        assert_eq!(format("int n = 1000000;\n", "main.c"), "int n = 1000000;\n");
+        assert_eq!(format("x = '''1000000'''  # 1000000\n", "a.py"), "x = '''1000000'''  # 1000000\n");
+    }
+
+    #[test]
+    fn test_radix_literals() {
+        let options = NumberOptions {
+            radix_literals: true,
+            ..NumberOptions::default()
+        };
+        let formatter = NumberFormatter::new(options);
+
+        let (result, changes) =
+            formatter.format_str("const MASK: u32 = 0xDEADBEEF | 0b11110000u32;\n", Path::new("a.rs"));
+        assert_eq!(result, "const MASK: u32 = 0xDEAD_BEEF | 0b1111_0000u32;\n");
+        assert_eq!(changes, 2);
+    }
+}
@@ -0,0 +1,185 @@
+//! Batched per-file work on the rayon thread pool
+//!
+//! Scheduling one task per file lets task overhead dominate on trees with thousands
+//! of tiny files, so consecutive small files are grouped into batches while large
+//! files stay individual tasks. Batches are contiguous runs of the input, which keeps
+//! results in input order. Without the `parallel` feature files are processed in turn.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::progress::Progress;
+use crate::vfs::Fs;
+
+/// Files at least this large are scheduled as their own task
+const LARGE_FILE_BYTES: u64 = 256 * 1024;
+/// Target combined size of a batch of small files
+const BATCH_BYTES: u64 = 256 * 1024;
+/// Maximum number of files in one batch
+const MAX_BATCH_FILES: usize = 64;
+
+/// Applies `f` to every file, returning the results in the same order as `files`
+///
+/// The files are sized for batching through `fs`, the filesystem `f` reads them from.
+pub(crate) fn map_files<T, F>(fs: &dyn Fs, files: &[PathBuf], f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&Path) -> T + Sync,
+{
+    map_indexed(fs, files, |_, path| f(path))
+}
+
+/// Like [`map_files`], reporting progress over the files for which `counted` holds
+///
+/// The counted files are announced up front, so the total is known before any file
+/// is read; files filtered out are processed but not reported.
+pub(crate) fn map_files_with_progress<T, C, F>(
+    fs: &dyn Fs,
+    files: &[PathBuf],
+    progress: &Progress,
+    counted: C,
+    f: F,
+) -> Vec<T>
+where
+    T: Send,
+    C: Fn(&Path) -> bool,
+    F: Fn(&Path) -> T + Sync,
+{
+    let counted: Vec<bool> = files.iter().map(|p| counted(p)).collect();
+    progress.started(counted.iter().filter(|&&c| c).count());
+
+    map_indexed(fs, files, |i, path| {
+        let result = f(path);
+        if counted[i] {
+            progress.file_done(path);
+        }
+        result
+    })
+}
+
+/// Applies `f` to every file and its index in `files`, preserving order
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+fn map_indexed<T, F>(fs: &dyn Fs, files: &[PathBuf], f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize, &Path) -> T + Sync,
+{
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+
+        batch_ranges(&file_sizes(fs, files))
+            .into_par_iter()
+            .map(|range| range.map(|i| f(i, &files[i])).collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        files.iter().enumerate().map(|(i, p)| f(i, p)).collect()
+    }
+}
+
+/// The size of each file as `fs` sees it, or 0 if it can't be read
+///
+/// The files are stat'ed on the thread pool too, so sizing a large tree doesn't hold
+/// up the batches.
+#[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+fn file_sizes(fs: &dyn Fs, files: &[PathBuf]) -> Vec<u64> {
+    let size = |p: &PathBuf| fs.metadata(p).map_or(0, |m| m.len);
+
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+
+        files.par_iter().map(size).collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        files.iter().map(size).collect()
+    }
+}
+
+/// Splits files (given by size) into contiguous batches
+#[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+fn batch_ranges(sizes: &[u64]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut batch_bytes = 0;
+
+    for (i, &size) in sizes.iter().enumerate() {
+        if size >= LARGE_FILE_BYTES {
+            // Close the pending batch, then give the large file its own task
+            if start < i {
+                ranges.push(start..i);
+            }
+            ranges.push(i..i + 1);
+            start = i + 1;
+            batch_bytes = 0;
+            continue;
+        }
+
+        batch_bytes += size;
+        if batch_bytes >= BATCH_BYTES || i + 1 - start >= MAX_BATCH_FILES {
+            ranges.push(start..i + 1);
+            start = i + 1;
+            batch_bytes = 0;
+        }
+    }
+
+    if start < sizes.len() {
+        ranges.push(start..sizes.len());
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+
+    #[test]
+    fn test_batch_ranges() {
+        // Small files are grouped up to the file limit
+        let ranges = batch_ranges(&vec![10; MAX_BATCH_FILES + 1]);
+        assert_eq!(ranges, vec![0..MAX_BATCH_FILES, MAX_BATCH_FILES..MAX_BATCH_FILES + 1]);
+
+        // Large files split batches and stay on their own
+        let ranges = batch_ranges(&[10, 10, LARGE_FILE_BYTES, 10]);
+        assert_eq!(ranges, vec![0..2, 2..3, 3..4]);
+
+        // Batches close once they reach the byte target
+        let half = BATCH_BYTES / 2;
+        let ranges = batch_ranges(&[half, half, half]);
+        assert_eq!(ranges, vec![0..2, 2..3]);
+
+        assert!(batch_ranges(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_file_sizes() {
+        // Files that only exist in memory are sized by their contents
+        let fs = MemoryFs::new();
+        fs.add_file("small.txt", "abc");
+        fs.add_file("large.txt", "x".repeat(LARGE_FILE_BYTES as usize));
+        let files = [
+            PathBuf::from("small.txt"),
+            PathBuf::from("large.txt"),
+            PathBuf::from("missing.txt"),
+        ];
+        assert_eq!(file_sizes(&fs, &files), vec![3, LARGE_FILE_BYTES, 0]);
+    }
+
+    #[test]
+    fn test_map_files_preserves_order() {
+        let files: Vec<PathBuf> = (0..500).map(|i| PathBuf::from(format!("f{}", i))).collect();
+        let names = map_files(&MemoryFs::new(), &files, |p| p.to_string_lossy().into_owned());
+        let expected: Vec<String> = (0..500).map(|i| format!("f{}", i)).collect();
+        assert_eq!(names, expected);
+    }
+}
@@ -0,0 +1,92 @@
+//! Progress events for long-running transformations
+//!
+//! Transformers that support progress first count the files they will read, then
+//! report each one as it is done, so a front end can show a real progress bar with
+//! a rate and ETA. Events may arrive from worker threads.
+
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A step of a transformer run
+#[derive(Debug, Clone, Copy)]
+pub enum ProgressEvent<'a> {
+    /// Files were enumerated; `total` of them will be read
+    Started {
+        /// Number of files that pass the transformer's filters
+        total: usize,
+    },
+    /// One of the counted files was processed
+    FileDone {
+        /// The file just processed
+        path: &'a Path,
+    },
+}
+
+type Callback = dyn Fn(ProgressEvent<'_>) + Send + Sync;
+
+/// Receiver of [`ProgressEvent`]s; the default ignores them
+#[derive(Clone, Default)]
+pub struct Progress {
+    callback: Option<Arc<Callback>>,
+}
+
+impl Progress {
+    /// Creates a progress receiver calling `callback` for every event
+    pub fn new(callback: impl Fn(ProgressEvent<'_>) + Send + Sync + 'static) -> Self {
+        Progress {
+            callback: Some(Arc::new(callback)),
+        }
+    }
+
+    pub(crate) fn started(&self, total: usize) {
+        self.emit(ProgressEvent::Started { total });
+    }
+
+    pub(crate) fn file_done(&self, path: &Path) {
+        self.emit(ProgressEvent::FileDone { path });
+    }
+
+    fn emit(&self, event: ProgressEvent<'_>) {
+        if let Some(callback) = &self.callback {
+            callback(event);
+        }
+    }
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("enabled", &self.callback.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_events_reach_callback() {
+        let done = Arc::new(AtomicUsize::new(0));
+        let total = Arc::new(AtomicUsize::new(0));
+        let progress = {
+            let (done, total) = (done.clone(), total.clone());
+            Progress::new(move |event| match event {
+                ProgressEvent::Started { total: n } => total.store(n, Ordering::SeqCst),
+                ProgressEvent::FileDone { .. } => {
+                    done.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+        };
+
+        progress.started(2);
+        progress.file_done(Path::new("a.txt"));
+        progress.file_done(Path::new("b.txt"));
+        Progress::default().file_done(Path::new("ignored.txt"));
+
+        assert_eq!(total.load(Ordering::SeqCst), 2);
+        assert_eq!(done.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,278 @@
+//! Unicode punctuation normalization
+//!
+//! Replaces em/en dashes, ellipsis characters and fullwidth punctuation with ASCII
+//! equivalents in the prose of text and Markdown files. Content pasted from word
+//! processors is full of these, and they trip up linters, terminals and `grep`.
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::filter::FilterOptions;
+use crate::report::{report, EventSink, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+use crate::{markdown, parallel};
+
+/// Options for punctuation normalization
+#[derive(Debug, Clone)]
+pub struct PunctuationOptions {
+    /// Replacement for em dashes (`—`) and horizontal bars (`―`)
+    pub em_dash: String,
+    /// Replacement for en dashes (`–`), figure dashes and minus signs
+    pub en_dash: String,
+    /// Replacement for the ellipsis character (`…`)
+    pub ellipsis: String,
+    /// Replace fullwidth punctuation (`，！？：；（）`) with its ASCII form
+    pub fullwidth: bool,
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for PunctuationOptions {
+    fn default() -> Self {
+        PunctuationOptions {
+            em_dash: "--".to_string(),
+            en_dash: "-".to_string(),
+            ellipsis: "...".to_string(),
+            fullwidth: true,
+            file_extensions: [".md", ".markdown", ".qmd", ".txt", ".rst"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// Punctuation normalizer for text and Markdown files
+pub struct PunctuationNormalizer {
+    options: PunctuationOptions,
+    walker: Walker,
+}
+
+impl PunctuationNormalizer {
+    /// Creates a new punctuation normalizer with the given options
+    pub fn new(options: PunctuationOptions) -> Self {
+        PunctuationNormalizer {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a normalizer with default options
+    pub fn with_defaults() -> Self {
+        PunctuationNormalizer {
+            options: PunctuationOptions::default(),
+            walker: Walker::default(),
+        }
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        self.options.fs.is_file(path) && self.accepts(path)
+    }
+
+    /// Checks the path alone against the filters, without touching the filesystem
+    pub fn accepts(&self, path: &Path) -> bool {
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none()
+    }
+
+    /// Returns the ASCII replacement for a punctuation character, if it has one
+    fn replacement(&self, c: char) -> Option<Cow<'_, str>> {
+        let replacement = match c {
+            '\u{2014}' | '\u{2015}' => Cow::Borrowed(self.options.em_dash.as_str()),
+            '\u{2013}' | '\u{2012}' | '\u{2212}' => Cow::Borrowed(self.options.en_dash.as_str()),
+            '\u{2010}' | '\u{2011}' => Cow::Borrowed("-"),
+            '\u{2026}' => Cow::Borrowed(self.options.ellipsis.as_str()),
+            '\u{FF01}'..='\u{FF5E}' if self.options.fullwidth => {
+                let ascii = char::from_u32(c as u32 - 0xFEE0)?;
+                // Fullwidth letters and digits are text, not punctuation
+                if ascii.is_ascii_alphanumeric() {
+                    return None;
+                }
+                Cow::Owned(ascii.to_string())
+            }
+            '\u{3000}' if self.options.fullwidth => Cow::Borrowed(" "),
+            _ => return None,
+        };
+        Some(replacement)
+    }
+
+    /// Normalizes punctuation in a string, returning the new content and the number
+    /// of characters replaced
+    ///
+    /// Returns the input borrowed when nothing changes.
+    pub fn normalize_str<'a>(&self, content: &'a str) -> (Cow<'a, str>, usize) {
+        let mut changes = 0;
+
+        let result = markdown::map_prose(content, |prose, _| {
+            let mut out = String::with_capacity(prose.len());
+            for c in prose.chars() {
+                match self.replacement(c) {
+                    Some(replacement) => {
+                        out.push_str(&replacement);
+                        changes += 1;
+                    }
+                    None => out.push(c),
+                }
+            }
+            out
+        });
+
+        (result, changes)
+    }
+
+    /// Normalizes punctuation in a single file
+    pub fn normalize_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        let outcome = self.apply_to_file(path)?;
+        self.report(path, outcome);
+        Ok(outcome)
+    }
+
+    /// Normalizes a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        if !self.should_process(path) {
+            return Ok(FileOutcome::unchanged());
+        }
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        let (normalized, changes) = self.normalize_str(&content);
+
+        match normalized {
+            Cow::Borrowed(_) => Ok(FileOutcome::unchanged()),
+            Cow::Owned(normalized) => {
+                if !self.options.dry_run {
+                    self.options.fs.write(path, normalized.as_bytes())?;
+                }
+                Ok(FileOutcome::changed(changes))
+            }
+        }
+    }
+
+    fn report(&self, path: &Path, outcome: FileOutcome) {
+        if !outcome.changed {
+            return;
+        }
+
+        self.options.events.file_changed(path, outcome.changes, self.options.dry_run);
+        if self.options.dry_run {
+            report!(
+                "Would replace {} punctuation characters in '{}'",
+                outcome.changes,
+                path.display()
+            );
+        } else {
+            report!(
+                "Replaced {} punctuation characters in '{}'",
+                outcome.changes,
+                path.display()
+            );
+        }
+    }
+
+    /// Processes a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<(usize, usize)> {
+        let mut total_files = 0;
+        let mut total_changes = 0;
+
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.apply_to_file(file)
+        });
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let outcome = result?;
+            self.report(file_path, outcome);
+            if outcome.changed {
+                total_files += 1;
+                total_changes += outcome.changes;
+            }
+        }
+
+        Ok((total_files, total_changes))
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_dashes_and_ellipsis() {
+        let normalizer = PunctuationNormalizer::with_defaults();
+        let (result, changes) =
+            normalizer.normalize_str("Wait\u{2026} pages 3\u{2013}5 \u{2014} done\n");
+        assert_eq!(result, "Wait... pages 3-5 -- done\n");
+        assert_eq!(changes, 3);
+
+        let (result, _) = normalizer.normalize_str("plain ascii -- text\n");
+        assert!(matches!(result, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_fullwidth_punctuation() {
+        let normalizer = PunctuationNormalizer::with_defaults();
+        let (result, changes) =
+            normalizer.normalize_str("Hello\u{FF0C}world\u{FF01} \u{FF21}\u{FF11}\n");
+        // Fullwidth letters and digits are left alone
+        assert_eq!(result, "Hello,world! \u{FF21}\u{FF11}\n");
+        assert_eq!(changes, 2);
+
+        let options = PunctuationOptions {
+            fullwidth: false,
+            ..PunctuationOptions::default()
+        };
+        let normalizer = PunctuationNormalizer::new(options);
+        assert_eq!(normalizer.normalize_str("a\u{FF0C}b\n").1, 0);
+    }
+
+    #[test]
+    fn test_configurable_replacements() {
+        let options = PunctuationOptions {
+            em_dash: " - ".to_string(),
+            ellipsis: ".".to_string(),
+            ..PunctuationOptions::default()
+        };
+        let normalizer = PunctuationNormalizer::new(options);
+
+        let (result, _) = normalizer.normalize_str("a\u{2014}b\u{2026}\n`x\u{2014}y`\n");
+        assert_eq!(result, "a - b.\n`x\u{2014}y`\n");
+    }
+}
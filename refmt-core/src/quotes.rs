@@ -0,0 +1,321 @@
+//! Typographic quote normalization
+//!
+//! Converts curly quotes and apostrophes to straight ASCII quotes, or straight
+//! quotes to curly ones, in the prose of text and Markdown files. Code fences,
+//! inline code, HTML tags and link destinations are never touched.
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::filter::FilterOptions;
+use crate::report::{report, EventSink, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+use crate::{markdown, parallel};
+
+/// Quote style to convert to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuoteStyle {
+    /// `'` and `"`
+    Straight,
+    /// `‘ ’` and `“ ”`
+    Curly,
+}
+
+/// Options for quote normalization
+#[derive(Debug, Clone)]
+pub struct QuoteOptions {
+    /// Style to convert quotes to
+    pub target: QuoteStyle,
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for QuoteOptions {
+    fn default() -> Self {
+        QuoteOptions {
+            target: QuoteStyle::Straight,
+            file_extensions: [".md", ".markdown", ".qmd", ".txt", ".rst"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// Quote normalizer for text and Markdown files
+pub struct QuoteTransformer {
+    options: QuoteOptions,
+    walker: Walker,
+}
+
+impl QuoteTransformer {
+    /// Creates a new quote transformer with the given options
+    pub fn new(options: QuoteOptions) -> Self {
+        QuoteTransformer {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a transformer with default options
+    pub fn with_defaults() -> Self {
+        QuoteTransformer {
+            options: QuoteOptions::default(),
+            walker: Walker::default(),
+        }
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        self.options.fs.is_file(path) && self.accepts(path)
+    }
+
+    /// Checks the path alone against the filters, without touching the filesystem
+    pub fn accepts(&self, path: &Path) -> bool {
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none()
+    }
+
+    /// Normalizes quotes in a string, returning the new content and the number of
+    /// quotes changed
+    ///
+    /// Returns the input borrowed when nothing changes.
+    pub fn transform_str<'a>(&self, content: &'a str) -> (Cow<'a, str>, usize) {
+        let mut changes = 0;
+        let target = self.options.target;
+
+        let result = markdown::map_prose(content, |prose, prev| {
+            let (converted, n) = match target {
+                QuoteStyle::Straight => straighten(prose),
+                QuoteStyle::Curly => curl(prose, prev),
+            };
+            changes += n;
+            converted
+        });
+
+        (result, changes)
+    }
+
+    /// Normalizes quotes in a single file
+    pub fn transform_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        let outcome = self.apply_to_file(path)?;
+        self.report(path, outcome);
+        Ok(outcome)
+    }
+
+    /// Transforms a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        if !self.should_process(path) {
+            return Ok(FileOutcome::unchanged());
+        }
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        let (transformed, changes) = self.transform_str(&content);
+
+        match transformed {
+            Cow::Borrowed(_) => Ok(FileOutcome::unchanged()),
+            Cow::Owned(transformed) => {
+                if !self.options.dry_run {
+                    self.options.fs.write(path, transformed.as_bytes())?;
+                }
+                Ok(FileOutcome::changed(changes))
+            }
+        }
+    }
+
+    fn report(&self, path: &Path, outcome: FileOutcome) {
+        if !outcome.changed {
+            return;
+        }
+
+        self.options.events.file_changed(path, outcome.changes, self.options.dry_run);
+        if self.options.dry_run {
+            report!(
+                "Would normalize {} quotes in '{}'",
+                outcome.changes,
+                path.display()
+            );
+        } else {
+            report!("Normalized {} quotes in '{}'", outcome.changes, path.display());
+        }
+    }
+
+    /// Processes a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<(usize, usize)> {
+        let mut total_files = 0;
+        let mut total_changes = 0;
+
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.apply_to_file(file)
+        });
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let outcome = result?;
+            self.report(file_path, outcome);
+            if outcome.changed {
+                total_files += 1;
+                total_changes += outcome.changes;
+            }
+        }
+
+        Ok((total_files, total_changes))
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+/// Replaces curly quotes and apostrophes with their ASCII equivalents
+fn straighten(text: &str) -> (String, usize) {
+    let mut changes = 0;
+    let result = text
+        .chars()
+        .map(|c| match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' => {
+                changes += 1;
+                '\''
+            }
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' => {
+                changes += 1;
+                '"'
+            }
+            _ => c,
+        })
+        .collect();
+    (result, changes)
+}
+
+/// Whether a quote after `prev` opens a quotation
+fn opens_quote(prev: Option<char>) -> bool {
+    match prev {
+        None => true,
+        Some(p) => {
+            p.is_whitespace()
+                || matches!(
+                    p,
+                    '(' | '[' | '{' | '-' | '/' | '\u{2013}' | '\u{2014}' | '\u{201C}' | '\u{2018}'
+                )
+        }
+    }
+}
+
+/// Replaces straight quotes with curly ones, deciding opening versus closing
+/// from the preceding character
+fn curl(text: &str, mut prev: Option<char>) -> (String, usize) {
+    let mut changes = 0;
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let out = match c {
+            '"' => {
+                changes += 1;
+                if opens_quote(prev) {
+                    '\u{201C}'
+                } else {
+                    '\u{201D}'
+                }
+            }
+            '\'' => {
+                changes += 1;
+                // Decade abbreviations ('90s) take an apostrophe, not an opening quote
+                let decade = chars.peek().is_some_and(|n| n.is_ascii_digit());
+                if opens_quote(prev) && !decade {
+                    '\u{2018}'
+                } else {
+                    '\u{2019}'
+                }
+            }
+            _ => c,
+        };
+        result.push(out);
+        prev = Some(out);
+    }
+
+    (result, changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transformer(target: QuoteStyle) -> QuoteTransformer {
+        let options = QuoteOptions {
+            target,
+            ..QuoteOptions::default()
+        };
+        QuoteTransformer::new(options)
+    }
+
+    #[test]
+    fn test_straighten() {
+        let transformer = QuoteTransformer::with_defaults();
+        let (result, changes) =
+            transformer.transform_str("\u{201C}Don\u{2019}t,\u{201D} she said.\n");
+        assert_eq!(result, "\"Don't,\" she said.\n");
+        assert_eq!(changes, 3);
+
+        let (result, changes) = transformer.transform_str("plain \"ascii\"\n");
+        assert!(matches!(result, Cow::Borrowed(_)));
+        assert_eq!(changes, 0);
+    }
+
+    #[test]
+    fn test_curl() {
+        let transformer = transformer(QuoteStyle::Curly);
+        let (result, changes) =
+            transformer.transform_str("\"Don't,\" she said ('quietly') in the '90s.\n");
+        assert_eq!(
+            result,
+            "\u{201C}Don\u{2019}t,\u{201D} she said (\u{2018}quietly\u{2019}) in the \u{2019}90s.\n"
+        );
+        assert_eq!(changes, 6);
+    }
+
+    #[test]
+    fn test_code_untouched() {
+        let transformer = transformer(QuoteStyle::Curly);
+        let content = "Use `print(\"hi\")` or <a href=\"x\">it's</a>\n```py\nx = 'a'\n```\n";
+        let (result, _) = transformer.transform_str(content);
+        assert_eq!(
+            result,
+            "Use `print(\"hi\")` or <a href=\"x\">it\u{2019}s</a>\n```py\nx = 'a'\n```\n"
+        );
+    }
+}
@@ -1,11 +1,20 @@
 //! File renaming transformer
 
-use std::fs;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::filter::{FilterOptions, SkipReason};
+use crate::report::{report, EventSink, FailedFile, FailedFiles};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
 
 /// Case transformation options
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum CaseTransform {
     /// Convert to lowercase
     Lowercase,
@@ -18,7 +27,8 @@ pub enum CaseTransform {
 }
 
 /// Space replacement options
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SpaceReplace {
     /// Replace spaces with underscores
     Underscore,
@@ -39,6 +49,33 @@ pub enum TimestampFormat {
     None,
 }
 
+/// What to do when a rename target already exists
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictResolution {
+    /// Leave the file alone
+    Skip,
+    /// Replace the existing target
+    Overwrite,
+    /// Rename to the first free numbered variant (`report-2.txt`, `report-3.txt`, ...)
+    AutoNumber,
+    /// Rename to this path instead; a bare file name stays in the same directory
+    Rename(PathBuf),
+}
+
+type ConflictHandler = dyn Fn(&Path, &Path) -> ConflictResolution;
+
+/// A rename computed by [`FileRenamer::plan`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenamePlanEntry {
+    /// The file to rename
+    pub from: PathBuf,
+    /// Its new path
+    pub to: PathBuf,
+    /// `to` already exists or is the target of an earlier entry, so applying the entry
+    /// goes through the conflict handler
+    pub conflict: bool,
+}
+
 /// Options for file renaming
 #[derive(Debug, Clone)]
 pub struct RenameOptions {
@@ -58,8 +95,18 @@ pub struct RenameOptions {
     pub timestamp_format: TimestampFormat,
     /// Process directories recursively
     pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
     /// Dry run mode (don't rename files)
     pub dry_run: bool,
+    /// Hidden files, skipped directories and excluded globs left alone
+    pub filter: FilterOptions,
+    /// Only rename files with one of these extensions; all files when `None`
+    pub file_extensions: Option<Vec<String>>,
+    /// Filesystem the files are renamed on
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed or renamed
+    pub events: EventSink,
 }
 
 impl Default for RenameOptions {
@@ -73,7 +120,12 @@ impl Default for RenameOptions {
             remove_suffix: None,
             timestamp_format: TimestampFormat::None,
             recursive: true,
+            walk: WalkOptions::default(),
             dry_run: false,
+            filter: FilterOptions::default(),
+            file_extensions: None,
+            fs: vfs::real(),
+            events: EventSink::default(),
         }
     }
 }
@@ -81,36 +133,61 @@ impl Default for RenameOptions {
 /// File renamer for transforming file names
 pub struct FileRenamer {
     options: RenameOptions,
+    on_conflict: Option<Box<ConflictHandler>>,
+    walker: Walker,
+    failed: FailedFiles,
 }
 
 impl FileRenamer {
     /// Creates a new file renamer with the given options
     pub fn new(options: RenameOptions) -> Self {
-        FileRenamer { options }
+        FileRenamer {
+            options,
+            on_conflict: None,
+            walker: Walker::default(),
+            failed: FailedFiles::default(),
+        }
     }
 
     /// Creates a renamer with default options
     pub fn with_defaults() -> Self {
-        FileRenamer {
-            options: RenameOptions::default(),
-        }
+        FileRenamer::new(RenameOptions::default())
+    }
+
+    /// Resolves rename conflicts with `handler` instead of failing
+    ///
+    /// The handler gets the file and the existing target, and is asked again if it
+    /// picks another name that exists too.
+    pub fn set_conflict_handler(
+        &mut self,
+        handler: impl Fn(&Path, &Path) -> ConflictResolution + 'static,
+    ) {
+        self.on_conflict = Some(Box::new(handler));
     }
 
     /// Checks if a path should be processed
     fn should_process(&self, path: &Path) -> bool {
         // Only process files, not directories
-        if !path.is_file() {
-            return false;
+        self.options.fs.is_file(path) && self.skip_reason(path).is_none()
+    }
+
+    /// Returns why a file would be left alone, or `None` if it is renamed
+    ///
+    /// Hidden files, files under `.git`, hidden or skipped directories, unselected
+    /// extensions and filtered files keep their names.
+    pub fn skip_reason(&self, path: &Path) -> Option<SkipReason> {
+        let filter = &self.options.filter;
+        if let Some(reason) = filter.hidden_skip(path).or_else(|| filter.dir_skip(path)) {
+            return Some(reason);
         }
 
-        // Skip hidden files
-        if let Some(name) = path.file_name() {
-            if name.to_str().map(|s| s.starts_with('.')).unwrap_or(false) {
-                return false;
+        if let Some(extensions) = &self.options.file_extensions {
+            if let Some(reason) = filter.extension_skip(path, extensions) {
+                return Some(reason);
             }
         }
 
-        true
+        filter.skip_reason(path)
     }
 
     /// Detects the separator style used in a filename
@@ -143,13 +220,8 @@ impl FileRenamer {
         match self.options.timestamp_format {
             TimestampFormat::None => None,
             TimestampFormat::Long | TimestampFormat::Short => {
-                // Get file metadata
-                let metadata = fs::metadata(path).ok()?;
-
-                // Try to get creation time, fall back to modified time
-                let created = metadata.created()
-                    .or_else(|_| metadata.modified())
-                    .ok()?;
+                // Creation time, or modified time where it isn't recorded
+                let created = self.options.fs.metadata(path).ok()?.created?;
 
                 // Convert to duration since epoch
                 let duration = created.duration_since(SystemTime::UNIX_EPOCH).ok()?;
@@ -205,7 +277,7 @@ impl FileRenamer {
 
     /// Checks if a year is a leap year
     fn is_leap_year(year: u64) -> bool {
-        (year % 4 == 0 && year % 100 != 0) || (year % 400 == 0)
+        (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
     }
 
     /// Applies all transformations to a filename
@@ -230,11 +302,11 @@ impl FileRenamer {
         match self.options.space_replace {
             SpaceReplace::Underscore => {
                 // Replace all separators (spaces, hyphens) with underscores
-                result = result.replace(' ', "_").replace('-', "_");
+                result = result.replace([' ', '-'], "_");
             }
             SpaceReplace::Hyphen => {
                 // Replace all separators (spaces, underscores) with hyphens
-                result = result.replace(' ', "-").replace('_', "-");
+                result = result.replace([' ', '_'], "-");
             }
             SpaceReplace::None => {}
         }
@@ -281,16 +353,18 @@ impl FileRenamer {
         result
     }
 
-    /// Renames a single file
-    pub fn rename_file(&self, path: &Path) -> crate::Result<bool> {
+    /// Computes the path a file would be renamed to, without touching the filesystem.
+    ///
+    /// Returns `None` if the file is skipped or its name would not change.
+    pub fn target_path(&self, path: &Path) -> crate::Result<Option<PathBuf>> {
         if !self.should_process(path) {
-            return Ok(false);
+            return Ok(None);
         }
 
         let file_name = path
             .file_name()
             .and_then(|n| n.to_str())
-            .ok_or_else(|| anyhow::anyhow!("Invalid filename"))?;
+            .ok_or_else(|| Error::Encoding("Invalid filename".to_string()))?;
 
         // Split filename and extension
         let (name, extension) = if let Some(pos) = file_name.rfind('.') {
@@ -311,440 +385,646 @@ impl FileRenamer {
 
         // If name didn't change, nothing to do
         if new_name == file_name {
-            return Ok(false);
+            return Ok(None);
         }
 
         let parent = path
             .parent()
-            .ok_or_else(|| anyhow::anyhow!("No parent directory"))?;
-        let new_path = parent.join(&new_name);
-
-        // Check if target already exists (but allow case-only renames on case-insensitive filesystems)
-        if new_path.exists() {
-            // Check if this is the same file (case-insensitive filesystems)
-            // Use canonicalize to resolve to the actual path
-            let same_file = match (path.canonicalize(), new_path.canonicalize()) {
-                (Ok(p1), Ok(p2)) => p1 == p2,
-                _ => false,
+            .ok_or_else(|| Error::Invalid("No parent directory".to_string()))?;
+        Ok(Some(parent.join(&new_name)))
+    }
+
+    /// Renames a single file
+    pub fn rename_file(&self, path: &Path) -> crate::Result<bool> {
+        let new_path = match self.target_path(path)? {
+            Some(new_path) => new_path,
+            None => return Ok(false),
+        };
+
+        Ok(self.rename_to(path, &new_path)?.is_some())
+    }
+
+    /// Renames a file to an explicit new path, honouring dry run mode
+    ///
+    /// If `new_path` exists and isn't the same file (case-only renames on
+    /// case-insensitive filesystems are allowed), the conflict handler decides what
+    /// happens; without one, this fails. Returns the path the file was renamed to, or
+    /// `None` if the conflict was skipped.
+    pub fn rename_to(&self, path: &Path, new_path: &Path) -> crate::Result<Option<PathBuf>> {
+        let mut new_path = new_path.to_path_buf();
+        while self.conflicts(path, &new_path) {
+            let Some(handler) = &self.on_conflict else {
+                return Err(Error::TargetExists(new_path));
             };
 
-            if !same_file {
-                return Err(anyhow::anyhow!(
-                    "Target file already exists: '{}'",
-                    new_path.display()
-                ));
+            match handler(path, &new_path) {
+                ConflictResolution::Skip => {
+                    report!(
+                        "Skipped '{}': '{}' already exists",
+                        path.display(),
+                        new_path.display()
+                    );
+                    return Ok(None);
+                }
+                ConflictResolution::Overwrite => break,
+                ConflictResolution::AutoNumber => {
+                    new_path = self.numbered_path(path, &new_path);
+                }
+                ConflictResolution::Rename(name) => {
+                    let parent = new_path.parent().unwrap_or(Path::new(""));
+                    new_path = parent.join(name);
+                }
             }
         }
 
+        self.options.events.file_renamed(path, &new_path, self.options.dry_run);
         if self.options.dry_run {
-            println!(
+            report!(
                 "Would rename '{}' -> '{}'",
                 path.display(),
                 new_path.display()
             );
         } else {
-            fs::rename(path, &new_path)?;
-            println!("Renamed '{}' -> '{}'", path.display(), new_path.display());
+            self.options.fs.rename(path, &new_path)?;
+            report!("Renamed '{}' -> '{}'", path.display(), new_path.display());
         }
 
-        Ok(true)
+        Ok(Some(new_path))
     }
 
-    /// Processes a directory or file
-    pub fn process(&self, path: &Path) -> crate::Result<usize> {
-        let mut renamed_count = 0;
+    /// Whether renaming `path` to `new_path` would replace another file
+    fn conflicts(&self, path: &Path, new_path: &Path) -> bool {
+        // The same file under another case, on a case-insensitive filesystem, is fine
+        self.options.fs.exists(new_path) && !self.options.fs.same_file(path, new_path)
+    }
 
-        if path.is_file() {
-            if self.rename_file(path)? {
-                renamed_count = 1;
-            }
-        } else if path.is_dir() {
-            if self.options.recursive {
-                // Collect all files first to avoid issues with renaming while iterating
-                let mut files: Vec<PathBuf> = WalkDir::new(path)
-                    .into_iter()
-                    .filter_map(|e| e.ok())
-                    .filter(|e| e.file_type().is_file())
-                    .map(|e| e.path().to_path_buf())
-                    .collect();
-
-                // Sort by depth (deepest first) to avoid parent directory rename issues
-                files.sort_by(|a, b| b.components().count().cmp(&a.components().count()));
-
-                for file_path in files {
-                    if self.rename_file(&file_path)? {
-                        renamed_count += 1;
-                    }
-                }
-            } else {
-                let mut files: Vec<PathBuf> = fs::read_dir(path)?
-                    .filter_map(|e| e.ok())
-                    .map(|e| e.path())
-                    .filter(|p| p.is_file())
-                    .collect();
-
-                // Sort for consistent processing
-                files.sort();
-
-                for file_path in files {
-                    if self.rename_file(&file_path)? {
-                        renamed_count += 1;
-                    }
-                }
+    /// The first `name-N.ext` next to `new_path` (N from 2) that doesn't exist yet,
+    /// using the separator style of the name
+    fn numbered_path(&self, path: &Path, new_path: &Path) -> PathBuf {
+        let file_name = new_path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let (name, extension) = match file_name.rfind('.') {
+            Some(pos) if pos > 0 => (&file_name[..pos], &file_name[pos..]),
+            _ => (file_name.as_str(), ""),
+        };
+        let separator = Self::detect_separator(name);
+
+        (2..)
+            .map(|n| new_path.with_file_name(format!("{}{}{}{}", name, separator, n, extension)))
+            .find(|candidate| !self.conflicts(path, candidate))
+            .expect("an unused numbered name")
+    }
+
+    /// Computes every rename [`process`](Self::process) would make, without touching
+    /// the filesystem
+    ///
+    /// Entries are ordered deepest first, the order they are applied in. The plan can
+    /// be shown or edited and then passed to [`apply_plan`](Self::apply_plan).
+    pub fn plan(&self, path: &Path) -> crate::Result<Vec<RenamePlanEntry>> {
+        let mut files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        // Sort by depth (deepest first) to avoid parent directory rename issues
+        files.sort_by_key(|p| std::cmp::Reverse(p.components().count()));
+
+        let mut targets = HashSet::new();
+        let mut plan = Vec::new();
+        for from in files {
+            if let Some(to) = self.target_path(&from)? {
+                let conflict = self.conflicts(&from, &to) || !targets.insert(to.clone());
+                plan.push(RenamePlanEntry { from, to, conflict });
             }
         }
+        Ok(plan)
+    }
 
+    /// Applies a plan in order, honouring dry run mode and the conflict handler
+    ///
+    /// Returns the number of files renamed (or that would be, in dry run mode).
+    pub fn apply_plan(&self, plan: &[RenamePlanEntry]) -> crate::Result<usize> {
+        let mut renamed_count = 0;
+        for entry in plan {
+            match self.rename_to(&entry.from, &entry.to) {
+                Ok(Some(_)) => renamed_count += 1,
+                Ok(None) => {}
+                Err(e) => self.failed.record(&entry.from, e, self.options.walk.errors, &self.options.events)?,
+            }
+        }
         Ok(renamed_count)
     }
+
+    /// Processes a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<usize> {
+        // Collect all files first to avoid issues with renaming while iterating
+        let plan = self.plan(path)?;
+        self.apply_plan(&plan)
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+
+    /// Returns the files that couldn't be processed since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_failed_files(&self) -> Vec<FailedFile> {
+        self.failed.take()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
+    use crate::vfs::MemoryFs;
 
     #[test]
     fn test_lowercase_transform() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_lowercase");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_lowercase");
 
         let test_file = test_dir.join("TestFile.txt");
-        fs::write(&test_file, "content").unwrap();
+        fs.add_file(&test_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.case_transform = CaseTransform::Lowercase;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            case_transform: CaseTransform::Lowercase,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
 
         assert_eq!(count, 1);
         let new_file = test_dir.join("testfile.txt");
-        assert!(new_file.exists());
-        assert_eq!(fs::read_to_string(&new_file).unwrap(), "content");
+        assert!(fs.exists(&new_file));
+        assert_eq!(fs.read_to_string(&new_file).unwrap(), "content");
+    }
 
-        fs::remove_dir_all(&test_dir).unwrap();
+    #[test]
+    fn test_file_extensions() {
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_extensions");
+        fs.add_file(test_dir.join("Notes.txt"), "notes");
+        fs.add_file(test_dir.join("Main.rs"), "fn main() {}");
+        fs.add_file(test_dir.join("README"), "readme");
+
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            case_transform: CaseTransform::Lowercase,
+            file_extensions: Some(vec![".txt".to_string()]),
+            ..RenameOptions::default()
+        };
+
+        let renamer = FileRenamer::new(opts);
+        assert_eq!(renamer.process(&test_dir).unwrap(), 1);
+        assert!(fs.exists(&test_dir.join("notes.txt")));
+        assert!(fs.exists(&test_dir.join("Main.rs")));
+        assert!(fs.exists(&test_dir.join("README")));
     }
 
     #[test]
     fn test_uppercase_transform() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_uppercase");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_uppercase");
 
         let test_file = test_dir.join("testfile.txt");
-        fs::write(&test_file, "content").unwrap();
+        fs.add_file(&test_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.case_transform = CaseTransform::Uppercase;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            case_transform: CaseTransform::Uppercase,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
 
         assert_eq!(count, 1);
         let new_file = test_dir.join("TESTFILE.txt");
-        assert!(new_file.exists());
-        assert_eq!(fs::read_to_string(&new_file).unwrap(), "content");
-
-        fs::remove_dir_all(&test_dir).unwrap();
+        assert!(fs.exists(&new_file));
+        assert_eq!(fs.read_to_string(&new_file).unwrap(), "content");
     }
 
     #[test]
     fn test_capitalize_transform() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_capitalize");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_capitalize");
 
         let test_file = test_dir.join("testFile.txt");
-        fs::write(&test_file, "content").unwrap();
+        fs.add_file(&test_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.case_transform = CaseTransform::Capitalize;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            case_transform: CaseTransform::Capitalize,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
 
         assert_eq!(count, 1);
         let new_file = test_dir.join("Testfile.txt");
-        assert!(new_file.exists());
-        assert_eq!(fs::read_to_string(&new_file).unwrap(), "content");
-
-        fs::remove_dir_all(&test_dir).unwrap();
+        assert!(fs.exists(&new_file));
+        assert_eq!(fs.read_to_string(&new_file).unwrap(), "content");
     }
 
     #[test]
     fn test_separators_to_underscore() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_underscore");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_underscore");
 
         // Test space to underscore
         let test_file1 = test_dir.join("test file.txt");
-        fs::write(&test_file1, "content").unwrap();
+        fs.add_file(&test_file1, "content");
 
         // Test hyphen to underscore
         let test_file2 = test_dir.join("test-file2.txt");
-        fs::write(&test_file2, "content").unwrap();
+        fs.add_file(&test_file2, "content");
 
         // Test mixed separators to underscore
         let test_file3 = test_dir.join("test-file 3.txt");
-        fs::write(&test_file3, "content").unwrap();
+        fs.add_file(&test_file3, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.space_replace = SpaceReplace::Underscore;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            space_replace: SpaceReplace::Underscore,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_dir).unwrap();
 
         assert_eq!(count, 3);
-        assert!(test_dir.join("test_file.txt").exists());
-        assert!(test_dir.join("test_file2.txt").exists());
-        assert!(test_dir.join("test_file_3.txt").exists());
-
-        fs::remove_dir_all(&test_dir).unwrap();
+        assert!(fs.exists(&test_dir.join("test_file.txt")));
+        assert!(fs.exists(&test_dir.join("test_file2.txt")));
+        assert!(fs.exists(&test_dir.join("test_file_3.txt")));
     }
 
     #[test]
     fn test_separators_to_hyphen() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_hyphen");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_hyphen");
 
         // Test space to hyphen
         let test_file1 = test_dir.join("test file.txt");
-        fs::write(&test_file1, "content").unwrap();
+        fs.add_file(&test_file1, "content");
 
         // Test underscore to hyphen
         let test_file2 = test_dir.join("test_file2.txt");
-        fs::write(&test_file2, "content").unwrap();
+        fs.add_file(&test_file2, "content");
 
         // Test mixed separators to hyphen
         let test_file3 = test_dir.join("test_file 3.txt");
-        fs::write(&test_file3, "content").unwrap();
+        fs.add_file(&test_file3, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.space_replace = SpaceReplace::Hyphen;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            space_replace: SpaceReplace::Hyphen,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_dir).unwrap();
 
         assert_eq!(count, 3);
-        assert!(test_dir.join("test-file.txt").exists());
-        assert!(test_dir.join("test-file2.txt").exists());
-        assert!(test_dir.join("test-file-3.txt").exists());
-
-        fs::remove_dir_all(&test_dir).unwrap();
+        assert!(fs.exists(&test_dir.join("test-file.txt")));
+        assert!(fs.exists(&test_dir.join("test-file2.txt")));
+        assert!(fs.exists(&test_dir.join("test-file-3.txt")));
     }
 
     #[test]
     fn test_add_prefix() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_add_prefix");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_add_prefix");
 
         let test_file = test_dir.join("file.txt");
-        fs::write(&test_file, "content").unwrap();
+        fs.add_file(&test_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.add_prefix = Some("new_".to_string());
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            add_prefix: Some("new_".to_string()),
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
 
         assert_eq!(count, 1);
-        assert!(test_dir.join("new_file.txt").exists());
-        assert!(!test_file.exists());
-
-        fs::remove_dir_all(&test_dir).unwrap();
+        assert!(fs.exists(&test_dir.join("new_file.txt")));
+        assert!(!fs.exists(&test_file));
     }
 
     #[test]
     fn test_remove_prefix() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_rm_prefix");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_rm_prefix");
 
         let test_file = test_dir.join("old_file.txt");
-        fs::write(&test_file, "content").unwrap();
+        fs.add_file(&test_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.remove_prefix = Some("old_".to_string());
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            remove_prefix: Some("old_".to_string()),
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
 
         assert_eq!(count, 1);
-        assert!(test_dir.join("file.txt").exists());
-        assert!(!test_file.exists());
-
-        fs::remove_dir_all(&test_dir).unwrap();
+        assert!(fs.exists(&test_dir.join("file.txt")));
+        assert!(!fs.exists(&test_file));
     }
 
     #[test]
     fn test_add_suffix() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_add_suffix");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_add_suffix");
 
         let test_file = test_dir.join("file.txt");
-        fs::write(&test_file, "content").unwrap();
+        fs.add_file(&test_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.add_suffix = Some("_backup".to_string());
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            add_suffix: Some("_backup".to_string()),
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
 
         assert_eq!(count, 1);
-        assert!(test_dir.join("file_backup.txt").exists());
-        assert!(!test_file.exists());
-
-        fs::remove_dir_all(&test_dir).unwrap();
+        assert!(fs.exists(&test_dir.join("file_backup.txt")));
+        assert!(!fs.exists(&test_file));
     }
 
     #[test]
     fn test_remove_suffix() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_rm_suffix");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_rm_suffix");
 
         let test_file = test_dir.join("file_old.txt");
-        fs::write(&test_file, "content").unwrap();
+        fs.add_file(&test_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.remove_suffix = Some("_old".to_string());
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            remove_suffix: Some("_old".to_string()),
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
 
         assert_eq!(count, 1);
-        assert!(test_dir.join("file.txt").exists());
-        assert!(!test_file.exists());
-
-        fs::remove_dir_all(&test_dir).unwrap();
+        assert!(fs.exists(&test_dir.join("file.txt")));
+        assert!(!fs.exists(&test_file));
     }
 
     #[test]
     fn test_combined_transforms() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_combined");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_combined");
 
         let test_file = test_dir.join("old_Test File.txt");
-        fs::write(&test_file, "content").unwrap();
-
-        let mut opts = RenameOptions::default();
-        opts.remove_prefix = Some("old_".to_string());
-        opts.space_replace = SpaceReplace::Underscore;
-        opts.case_transform = CaseTransform::Lowercase;
-        opts.add_suffix = Some("_new".to_string());
+        fs.add_file(&test_file, "content");
+
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            remove_prefix: Some("old_".to_string()),
+            space_replace: SpaceReplace::Underscore,
+            case_transform: CaseTransform::Lowercase,
+            add_suffix: Some("_new".to_string()),
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
 
         assert_eq!(count, 1);
-        assert!(test_dir.join("test_file_new.txt").exists());
-        assert!(!test_file.exists());
-
-        fs::remove_dir_all(&test_dir).unwrap();
+        assert!(fs.exists(&test_dir.join("test_file_new.txt")));
+        assert!(!fs.exists(&test_file));
     }
 
     #[test]
     fn test_dry_run_mode() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_dry");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_dry");
 
         let test_file = test_dir.join("TestFile.txt");
         let original_content = "content";
-        fs::write(&test_file, original_content).unwrap();
+        fs.add_file(&test_file, original_content);
 
-        let mut opts = RenameOptions::default();
-        opts.case_transform = CaseTransform::Lowercase;
-        opts.dry_run = true;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            case_transform: CaseTransform::Lowercase,
+            dry_run: true,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
 
         assert_eq!(count, 1);
         // File should still exist and be unchanged in dry run
-        assert!(test_file.exists());
-        assert_eq!(fs::read_to_string(&test_file).unwrap(), original_content);
-
-        fs::remove_dir_all(&test_dir).unwrap();
+        assert!(fs.exists(&test_file));
+        assert_eq!(fs.read_to_string(&test_file).unwrap(), original_content);
     }
 
     #[test]
     fn test_skip_hidden_files() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_hidden");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_hidden");
 
         let hidden_file = test_dir.join(".hidden.txt");
-        fs::write(&hidden_file, "content").unwrap();
+        fs.add_file(&hidden_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.case_transform = CaseTransform::Uppercase;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            case_transform: CaseTransform::Uppercase,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&hidden_file).unwrap();
 
         // Hidden file should be skipped
         assert_eq!(count, 0);
-        assert!(hidden_file.exists());
-
-        fs::remove_dir_all(&test_dir).unwrap();
+        assert!(fs.exists(&hidden_file));
     }
 
     #[test]
     fn test_recursive_processing() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_recursive");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_recursive");
 
         let sub_dir = test_dir.join("subdir");
-        fs::create_dir_all(&sub_dir).unwrap();
 
         let file1 = test_dir.join("File1.txt");
         let file2 = sub_dir.join("File2.txt");
 
-        fs::write(&file1, "content1").unwrap();
-        fs::write(&file2, "content2").unwrap();
+        fs.add_file(&file1, "content1");
+        fs.add_file(&file2, "content2");
 
-        let mut opts = RenameOptions::default();
-        opts.case_transform = CaseTransform::Lowercase;
-        opts.recursive = true;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            case_transform: CaseTransform::Lowercase,
+            recursive: true,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_dir).unwrap();
 
         assert_eq!(count, 2);
-        assert!(test_dir.join("file1.txt").exists());
-        assert!(sub_dir.join("file2.txt").exists());
+        assert!(fs.exists(&test_dir.join("file1.txt")));
+        assert!(fs.exists(&sub_dir.join("file2.txt")));
+    }
 
-        fs::remove_dir_all(&test_dir).unwrap();
+    #[test]
+    fn test_plan_and_apply() {
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_plan");
+        fs.add_file(test_dir.join("Draft.md"), "draft");
+        fs.add_file(test_dir.join("README.md"), "readme");
+        fs.add_file(test_dir.join("readme.md"), "taken");
+        fs.add_file(test_dir.join("docs/Guide.md"), "guide");
+
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            case_transform: CaseTransform::Lowercase,
+            ..RenameOptions::default()
+        };
+        let renamer = FileRenamer::new(opts);
+
+        let mut plan = renamer.plan(&test_dir).unwrap();
+        let entry = |from: &str, to: &str, conflict| RenamePlanEntry {
+            from: test_dir.join(from),
+            to: test_dir.join(to),
+            conflict,
+        };
+        assert_eq!(
+            plan,
+            vec![
+                entry("docs/Guide.md", "docs/guide.md", false),
+                entry("Draft.md", "draft.md", false),
+                entry("README.md", "readme.md", true),
+            ]
+        );
+        // Nothing was renamed yet
+        assert!(fs.exists(&test_dir.join("Draft.md")));
+
+        // Plans can be edited before they are applied
+        plan.pop();
+        plan[1].to = test_dir.join("notes.md");
+        assert_eq!(renamer.apply_plan(&plan).unwrap(), 2);
+        assert_eq!(fs.read_to_string(test_dir.join("notes.md")).unwrap(), "draft");
+        assert_eq!(fs.read_to_string(test_dir.join("docs/guide.md")).unwrap(), "guide");
+        assert_eq!(fs.read_to_string(test_dir.join("README.md")).unwrap(), "readme");
+    }
+
+    #[test]
+    fn test_rename_conflicts() {
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_conflicts");
+        let write = |name: &str| fs.add_file(test_dir.join(name), name);
+
+        let options = RenameOptions {
+            fs: fs.clone(),
+            case_transform: CaseTransform::Lowercase,
+            ..RenameOptions::default()
+        };
+
+        // Without a handler, an existing target fails the file...
+        write("A.txt");
+        write("a.txt");
+        let renamer = FileRenamer::new(options.clone());
+        assert_eq!(renamer.process(&test_dir).unwrap(), 0);
+        let failed = renamer.take_failed_files();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].path, test_dir.join("A.txt"));
+
+        // ...or the run, under ErrorPolicy::Fail
+        let mut failing = options.clone();
+        failing.walk.errors = crate::walker::ErrorPolicy::Fail;
+        let error = FileRenamer::new(failing).process(&test_dir).unwrap_err();
+        assert!(matches!(error.root(), Error::TargetExists(target) if target.ends_with("a.txt")));
+
+        let mut renamer = FileRenamer::new(options.clone());
+        renamer.set_conflict_handler(|_, _| ConflictResolution::Skip);
+        assert_eq!(renamer.process(&test_dir).unwrap(), 0);
+        assert!(fs.exists(&test_dir.join("A.txt")));
+
+        let mut renamer = FileRenamer::new(options.clone());
+        renamer.set_conflict_handler(|_, _| ConflictResolution::AutoNumber);
+        write("a-2.txt");
+        assert_eq!(renamer.process(&test_dir).unwrap(), 1);
+        assert_eq!(fs.read_to_string(test_dir.join("a-3.txt")).unwrap(), "A.txt");
+
+        // A manual name that exists too is resolved again
+        let mut renamer = FileRenamer::new(options.clone());
+        renamer.set_conflict_handler(|_, target| {
+            if target.ends_with("b.txt") {
+                ConflictResolution::Rename(PathBuf::from("a.txt"))
+            } else {
+                ConflictResolution::Overwrite
+            }
+        });
+        write("B.txt");
+        write("b.txt");
+        assert_eq!(renamer.process(&test_dir).unwrap(), 1);
+        assert_eq!(fs.read_to_string(test_dir.join("a.txt")).unwrap(), "B.txt");
+        assert!(!fs.exists(&test_dir.join("B.txt")));
     }
 
     #[test]
     fn test_no_extension_file() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_no_ext");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_no_ext");
 
         let test_file = test_dir.join("TestFile");
-        fs::write(&test_file, "content").unwrap();
+        fs.add_file(&test_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.case_transform = CaseTransform::Lowercase;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            case_transform: CaseTransform::Lowercase,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
 
         assert_eq!(count, 1);
         let new_file = test_dir.join("testfile");
-        assert!(new_file.exists());
-        assert_eq!(fs::read_to_string(&new_file).unwrap(), "content");
-
-        fs::remove_dir_all(&test_dir).unwrap();
+        assert!(fs.exists(&new_file));
+        assert_eq!(fs.read_to_string(&new_file).unwrap(), "content");
     }
 
     #[test]
     fn test_timestamp_long_format() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_timestamp_long");
-        let _ = fs::remove_dir_all(&test_dir); // Clean up first
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_timestamp_long");
 
         let test_file = test_dir.join("document.txt");
-        fs::write(&test_file, "content").unwrap();
+        fs.add_file(&test_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.timestamp_format = TimestampFormat::Long;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            timestamp_format: TimestampFormat::Long,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
@@ -753,10 +1033,10 @@ mod tests {
 
         // Check that a file with timestamp prefix exists
         // The name should be like: YYYYMMDD-document.txt (hyphen as default)
-        let entries: Vec<_> = fs::read_dir(&test_dir).unwrap().collect();
+        let entries = fs.paths();
         assert_eq!(entries.len(), 1);
 
-        let renamed_file = entries[0].as_ref().unwrap().path();
+        let renamed_file = &entries[0];
         let file_name = renamed_file.file_name().unwrap().to_str().unwrap();
 
         // Verify format: should start with 8 digits followed by hyphen (default separator)
@@ -764,21 +1044,21 @@ mod tests {
         assert!(file_name.starts_with(|c: char| c.is_ascii_digit()), "Should start with digit");
         assert_eq!(&file_name[8..9], "-", "Should have hyphen after date (default separator)");
         assert!(file_name.ends_with("document.txt"), "Should end with original name");
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 
     #[test]
     fn test_timestamp_short_format() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_timestamp_short");
-        let _ = fs::remove_dir_all(&test_dir); // Clean up first
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_timestamp_short");
 
         let test_file = test_dir.join("notes.md");
-        fs::write(&test_file, "content").unwrap();
+        fs.add_file(&test_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.timestamp_format = TimestampFormat::Short;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            timestamp_format: TimestampFormat::Short,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
@@ -787,10 +1067,10 @@ mod tests {
 
         // Check that a file with timestamp prefix exists
         // The name should be like: YYMMDD-notes.md (hyphen as default)
-        let entries: Vec<_> = fs::read_dir(&test_dir).unwrap().collect();
+        let entries = fs.paths();
         assert_eq!(entries.len(), 1);
 
-        let renamed_file = entries[0].as_ref().unwrap().path();
+        let renamed_file = &entries[0];
         let file_name = renamed_file.file_name().unwrap().to_str().unwrap();
 
         // Verify format: should start with 6 digits followed by hyphen (default separator)
@@ -798,22 +1078,23 @@ mod tests {
         assert!(file_name.starts_with(|c: char| c.is_ascii_digit()), "Should start with digit");
         assert_eq!(&file_name[6..7], "-", "Should have hyphen after date (default separator)");
         assert!(file_name.ends_with("notes.md"), "Should end with original name");
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 
     #[test]
     fn test_timestamp_with_other_transforms() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_timestamp_combined");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_timestamp_combined");
 
         let test_file = test_dir.join("My Document.txt");
-        fs::write(&test_file, "content").unwrap();
-
-        let mut opts = RenameOptions::default();
-        opts.timestamp_format = TimestampFormat::Long;
-        opts.space_replace = SpaceReplace::Underscore;
-        opts.case_transform = CaseTransform::Lowercase;
+        fs.add_file(&test_file, "content");
+
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            timestamp_format: TimestampFormat::Long,
+            space_replace: SpaceReplace::Underscore,
+            case_transform: CaseTransform::Lowercase,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
@@ -821,10 +1102,10 @@ mod tests {
         assert_eq!(count, 1);
 
         // The file should be renamed with timestamp, spaces replaced, and lowercase
-        let entries: Vec<_> = fs::read_dir(&test_dir).unwrap().collect();
+        let entries = fs.paths();
         assert_eq!(entries.len(), 1);
 
-        let renamed_file = entries[0].as_ref().unwrap().path();
+        let renamed_file = &entries[0];
         let file_name = renamed_file.file_name().unwrap().to_str().unwrap();
 
         // Should have format: YYYYMMDD_my_document.txt
@@ -832,155 +1113,192 @@ mod tests {
         assert!(file_name.contains("my_document.txt"));
         assert!(!file_name.contains(" "));
         assert!(!file_name.contains("My"));
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 
     #[test]
     fn test_timestamp_separator_detection_hyphen() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_timestamp_hyphen");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_timestamp_hyphen");
 
         let test_file = test_dir.join("my-document-file.txt");
-        fs::write(&test_file, "content").unwrap();
+        fs.add_file(&test_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.timestamp_format = TimestampFormat::Long;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            timestamp_format: TimestampFormat::Long,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
 
         assert_eq!(count, 1);
 
-        let entries: Vec<_> = fs::read_dir(&test_dir).unwrap().collect();
+        let entries = fs.paths();
         assert_eq!(entries.len(), 1);
 
-        let renamed_file = entries[0].as_ref().unwrap().path();
+        let renamed_file = &entries[0];
         let file_name = renamed_file.file_name().unwrap().to_str().unwrap();
 
         // Should use hyphen as separator: YYYYMMDD-my-document-file.txt
         assert!(file_name.starts_with(|c: char| c.is_ascii_digit()));
         assert_eq!(&file_name[8..9], "-", "Timestamp should use hyphen separator");
         assert!(file_name.ends_with("my-document-file.txt"));
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 
     #[test]
     fn test_timestamp_separator_detection_underscore() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_timestamp_underscore");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_timestamp_underscore");
 
         let test_file = test_dir.join("my_document_file.txt");
-        fs::write(&test_file, "content").unwrap();
+        fs.add_file(&test_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.timestamp_format = TimestampFormat::Short;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            timestamp_format: TimestampFormat::Short,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
 
         assert_eq!(count, 1);
 
-        let entries: Vec<_> = fs::read_dir(&test_dir).unwrap().collect();
+        let entries = fs.paths();
         assert_eq!(entries.len(), 1);
 
-        let renamed_file = entries[0].as_ref().unwrap().path();
+        let renamed_file = &entries[0];
         let file_name = renamed_file.file_name().unwrap().to_str().unwrap();
 
         // Should use underscore as separator: YYMMDD_my_document_file.txt
         assert!(file_name.starts_with(|c: char| c.is_ascii_digit()));
         assert_eq!(&file_name[6..7], "_", "Timestamp should use underscore separator");
         assert!(file_name.ends_with("my_document_file.txt"));
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 
     #[test]
     fn test_timestamp_separator_detection_mixed() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_timestamp_mixed");
-        let _ = fs::remove_dir_all(&test_dir); // Clean up first
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_timestamp_mixed");
 
         // More hyphens than underscores (2 hyphens vs 1 underscore)
         let test_file1 = test_dir.join("my-document-file_v2.txt");
-        fs::write(&test_file1, "content").unwrap();
+        fs.add_file(&test_file1, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.timestamp_format = TimestampFormat::Long;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            timestamp_format: TimestampFormat::Long,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file1).unwrap();
 
         assert_eq!(count, 1);
 
-        let entries: Vec<_> = fs::read_dir(&test_dir).unwrap().collect();
+        let entries = fs.paths();
         assert_eq!(entries.len(), 1);
 
-        let renamed_file = entries[0].as_ref().unwrap().path();
+        let renamed_file = &entries[0];
         let file_name = renamed_file.file_name().unwrap().to_str().unwrap();
 
         // Should use hyphen (more hyphens than underscores)
         assert_eq!(&file_name[8..9], "-", "Should use hyphen for mixed with more hyphens");
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 
     #[test]
     fn test_timestamp_separator_detection_no_separator() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_timestamp_nosep");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_timestamp_nosep");
 
         let test_file = test_dir.join("mydocument.txt");
-        fs::write(&test_file, "content").unwrap();
+        fs.add_file(&test_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.timestamp_format = TimestampFormat::Long;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            timestamp_format: TimestampFormat::Long,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
 
         assert_eq!(count, 1);
 
-        let entries: Vec<_> = fs::read_dir(&test_dir).unwrap().collect();
+        let entries = fs.paths();
         assert_eq!(entries.len(), 1);
 
-        let renamed_file = entries[0].as_ref().unwrap().path();
+        let renamed_file = &entries[0];
         let file_name = renamed_file.file_name().unwrap().to_str().unwrap();
 
         // Should default to hyphen when no separators
         assert_eq!(&file_name[8..9], "-", "Should default to hyphen");
         assert!(file_name.ends_with("mydocument.txt"));
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 
     #[test]
     fn test_timestamp_separator_detection_spaces() {
-        let test_dir = std::env::temp_dir().join("refmt_rename_timestamp_spaces");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_timestamp_spaces");
 
         let test_file = test_dir.join("my document file.txt");
-        fs::write(&test_file, "content").unwrap();
+        fs.add_file(&test_file, "content");
 
-        let mut opts = RenameOptions::default();
-        opts.timestamp_format = TimestampFormat::Long;
+        let opts = RenameOptions {
+            fs: fs.clone(),
+            timestamp_format: TimestampFormat::Long,
+            ..RenameOptions::default()
+        };
 
         let renamer = FileRenamer::new(opts);
         let count = renamer.process(&test_file).unwrap();
 
         assert_eq!(count, 1);
 
-        let entries: Vec<_> = fs::read_dir(&test_dir).unwrap().collect();
+        let entries = fs.paths();
         assert_eq!(entries.len(), 1);
 
-        let renamed_file = entries[0].as_ref().unwrap().path();
+        let renamed_file = &entries[0];
         let file_name = renamed_file.file_name().unwrap().to_str().unwrap();
 
         // Should use hyphen for space-separated files
         assert_eq!(&file_name[8..9], "-", "Should use hyphen for space-separated files");
         assert!(file_name.ends_with("my document file.txt"));
+    }
 
-        fs::remove_dir_all(&test_dir).unwrap();
+    #[test]
+    fn test_skips_git_and_skip_dirs() {
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("rename_skip_dirs");
+        fs.add_file(test_dir.join(".git/HEAD"), "ref: refs/heads/main");
+        fs.add_file(test_dir.join(".github/CODEOWNERS"), "*");
+        fs.add_file(test_dir.join("node_modules/Pkg.js"), "");
+        fs.add_file(test_dir.join("vendor/Lib.c"), "");
+        fs.add_file(test_dir.join("Notes.txt"), "notes");
+
+        let renamer = FileRenamer::new(RenameOptions {
+            fs: fs.clone(),
+            case_transform: CaseTransform::Lowercase,
+            ..RenameOptions::default()
+        });
+        let plan = renamer.plan(&test_dir).unwrap();
+        let renamed: Vec<_> = plan.iter().map(|entry| entry.from.clone()).collect();
+        assert_eq!(renamed, vec![test_dir.join("vendor/Lib.c"), test_dir.join("Notes.txt")]);
+
+        let filter = FilterOptions {
+            skip_dirs: vec!["vendor".to_string()],
+            ..FilterOptions::default()
+        };
+        let renamer = FileRenamer::new(RenameOptions {
+            fs: fs.clone(),
+            case_transform: CaseTransform::Lowercase,
+            filter,
+            ..RenameOptions::default()
+        });
+        assert_eq!(renamer.process(&test_dir).unwrap(), 2);
+        assert!(fs.exists(&test_dir.join(".git/HEAD")));
+        assert!(fs.exists(&test_dir.join("node_modules/pkg.js")));
+        assert!(fs.exists(&test_dir.join("vendor/Lib.c")));
     }
 }
@@ -0,0 +1,228 @@
+//! Per-file results shared by the content transformers
+//!
+//! The transformers report each file they change ("Converted '…'", "Would rename
+//! …") as `info` records of the `log` crate under [`LOG_TARGET`]. Front ends show
+//! these as their normal output; without a logger nothing is printed.
+//!
+//! Files that can't be read or written, even after the retries of a
+//! [`RetryFs`](crate::vfs::RetryFs), are handled like unreadable
+//! directories: under the [`ErrorPolicy::Warn`] of the transformer's walk options they
+//! are kept by the transformer for its caller (`take_failed_files`) and the run goes
+//! on, otherwise the first one aborts it.
+//!
+//! Front ends that need more than text, such as JSON output, can also receive each
+//! change, rename and failure as a [`FileEvent`] through the [`EventSink`] in a
+//! transformer's options.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::filter::SkipReason;
+use crate::walker::ErrorPolicy;
+
+/// Log target of the per-file messages
+pub const LOG_TARGET: &str = "refmt::report";
+
+/// Logs a per-file message under [`LOG_TARGET`]
+macro_rules! report {
+    ($($arg:tt)*) => {
+        log::info!(target: $crate::report::LOG_TARGET, $($arg)*)
+    };
+}
+pub(crate) use report;
+
+/// A file that couldn't be processed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedFile {
+    /// The file
+    pub path: PathBuf,
+    /// What went wrong
+    pub message: String,
+}
+
+impl fmt::Display for FailedFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}': {}", self.path.display(), self.message)
+    }
+}
+
+/// What happened to a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileAction {
+    /// The content was modified (or would be, in dry run mode)
+    Changed,
+    /// The file was renamed (or would be)
+    Renamed,
+    /// The file couldn't be read or written
+    Failed,
+}
+
+/// A per-file result, the structured counterpart of a [`LOG_TARGET`] message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileEvent {
+    /// What happened
+    #[serde(rename = "event")]
+    pub action: FileAction,
+    /// The file
+    pub path: PathBuf,
+    /// The new name of a renamed file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<PathBuf>,
+    /// Number of individual changes in the file
+    pub changes: usize,
+    /// Whether the change was only previewed
+    pub dry_run: bool,
+    /// What went wrong, for a failed file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+type Callback = dyn Fn(&FileEvent) + Send + Sync;
+
+/// Receiver of [`FileEvent`]s, possibly from several threads at once; the default
+/// ignores them
+#[derive(Clone, Default)]
+pub struct EventSink {
+    callback: Option<Arc<Callback>>,
+}
+
+impl EventSink {
+    /// Creates a sink calling `callback` for every event
+    pub fn new(callback: impl Fn(&FileEvent) + Send + Sync + 'static) -> Self {
+        EventSink {
+            callback: Some(Arc::new(callback)),
+        }
+    }
+
+    /// Sends an event for `path`, changed by `changes` edits
+    pub(crate) fn file_changed(&self, path: &Path, changes: usize, dry_run: bool) {
+        self.emit(FileEvent {
+            action: FileAction::Changed,
+            path: path.to_path_buf(),
+            to: None,
+            changes,
+            dry_run,
+            message: None,
+        });
+    }
+
+    /// Sends an event for `from`, renamed to `to`
+    pub(crate) fn file_renamed(&self, from: &Path, to: &Path, dry_run: bool) {
+        self.emit(FileEvent {
+            action: FileAction::Renamed,
+            path: from.to_path_buf(),
+            to: Some(to.to_path_buf()),
+            changes: 1,
+            dry_run,
+            message: None,
+        });
+    }
+
+    /// Sends an event for a file that couldn't be processed
+    pub(crate) fn file_failed(&self, failed: &FailedFile) {
+        self.emit(FileEvent {
+            action: FileAction::Failed,
+            path: failed.path.clone(),
+            to: None,
+            changes: 0,
+            dry_run: false,
+            message: Some(failed.message.clone()),
+        });
+    }
+
+    fn emit(&self, event: FileEvent) {
+        if let Some(callback) = &self.callback {
+            callback(&event);
+        }
+    }
+}
+
+impl fmt::Debug for EventSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventSink")
+            .field("enabled", &self.callback.is_some())
+            .finish()
+    }
+}
+
+/// Returns a file that failed under [`ErrorPolicy::Warn`], for the caller to keep;
+/// otherwise returns the error
+pub(crate) fn file_failed(
+    path: &Path,
+    error: Error,
+    policy: ErrorPolicy,
+) -> crate::Result<FailedFile> {
+    if policy != ErrorPolicy::Warn {
+        return Err(error);
+    }
+    Ok(FailedFile {
+        path: path.to_path_buf(),
+        message: format!("{:#}", error),
+    })
+}
+
+/// The files a transformer couldn't process, kept until they are taken
+#[derive(Debug, Default)]
+pub(crate) struct FailedFiles(Mutex<Vec<FailedFile>>);
+
+impl FailedFiles {
+    /// Keeps a file that failed under [`ErrorPolicy::Warn`], sending it to `events`;
+    /// otherwise returns the error
+    pub(crate) fn record(
+        &self,
+        path: &Path,
+        error: Error,
+        policy: ErrorPolicy,
+        events: &EventSink,
+    ) -> crate::Result<()> {
+        let failed = file_failed(path, error, policy)?;
+        events.file_failed(&failed);
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).push(failed);
+        Ok(())
+    }
+
+    /// Returns the files kept since the last call
+    pub(crate) fn take(&self) -> Vec<FailedFile> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Outcome of transforming a single file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileOutcome {
+    /// Whether the content was modified (or would be, in dry run mode)
+    pub changed: bool,
+    /// Number of individual changes (emojis, lines or identifiers)
+    pub changes: usize,
+    /// Why the file was left out by a filter, if it was
+    pub skipped: Option<SkipReason>,
+}
+
+impl FileOutcome {
+    /// Outcome for a file that was left untouched
+    pub fn unchanged() -> Self {
+        FileOutcome::default()
+    }
+
+    /// Outcome for a file with `changes` modifications
+    pub fn changed(changes: usize) -> Self {
+        FileOutcome {
+            changed: true,
+            changes,
+            skipped: None,
+        }
+    }
+
+    /// Outcome for a file left out by a filter
+    pub fn skipped(reason: SkipReason) -> Self {
+        FileOutcome {
+            skipped: Some(reason),
+            ..FileOutcome::default()
+        }
+    }
+}
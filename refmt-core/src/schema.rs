@@ -0,0 +1,765 @@
+//! Field renaming for protobuf and JSON Schema files
+//!
+//! Renames message fields in `.proto` files and `properties` in JSON Schema
+//! documents to a case convention, and records every rename as a [`FieldRename`] so
+//! that code generated from the schemas can be updated to match.
+//!
+//! In protobuf only field names change: field numbers, types, enum values and
+//! options are kept, so the binary wire format is unaffected (the JSON mapping of
+//! a message is not, unless its fields set `json_name`). In JSON Schema the
+//! `required` and `dependentRequired` lists and local `$ref` pointers are updated
+//! along with the property names. JSON documents are re-serialized when changed.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::case::{identifier_words, CaseFormat};
+use crate::error::{Context, Error};
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::report::{report, EventSink};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// Schema file format
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SchemaFormat {
+    Proto,
+    JsonSchema,
+}
+
+impl SchemaFormat {
+    /// Detects the format from a file's extension
+    pub fn from_path(path: &Path) -> Option<SchemaFormat> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "proto" => Some(SchemaFormat::Proto),
+            "json" => Some(SchemaFormat::JsonSchema),
+            _ => None,
+        }
+    }
+}
+
+/// One renamed field
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldRename {
+    /// Schema file containing the field
+    pub file: PathBuf,
+    /// Message (`Outer.Inner`) or JSON pointer of the schema (`#/properties/address`)
+    /// the field belongs to
+    pub scope: String,
+    /// Field name before the rename
+    pub old: String,
+    /// Field name after the rename
+    pub new: String,
+}
+
+/// Options for schema field renaming
+#[derive(Debug, Clone)]
+pub struct SchemaOptions {
+    /// Case format to rename fields to
+    pub target: CaseFormat,
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for SchemaOptions {
+    fn default() -> Self {
+        SchemaOptions {
+            target: CaseFormat::SnakeCase,
+            file_extensions: vec![".proto".to_string(), ".json".to_string()],
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// Statistics from schema field renaming
+#[derive(Debug, Default)]
+pub struct SchemaStats {
+    /// Number of files changed
+    pub files_changed: usize,
+    /// Every field renamed, in file order
+    pub renames: Vec<FieldRename>,
+}
+
+impl SchemaStats {
+    /// The renames as a JSON array of `{file, scope, old, new}` objects
+    pub fn mapping_json(&self) -> crate::Result<String> {
+        let mut json = serde_json::to_string_pretty(&self.renames)?;
+        json.push('\n');
+        Ok(json)
+    }
+}
+
+/// Renames the fields of protobuf and JSON Schema files
+pub struct FieldRenamer {
+    options: SchemaOptions,
+    walker: Walker,
+}
+
+impl FieldRenamer {
+    /// Creates a new field renamer with the given options
+    pub fn new(options: SchemaOptions) -> Self {
+        FieldRenamer {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a renamer with default options (snake_case)
+    pub fn with_defaults() -> Self {
+        FieldRenamer {
+            options: SchemaOptions::default(),
+            walker: Walker::default(),
+        }
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        if !self.options.fs.is_file(path) {
+            return false;
+        }
+
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none()
+            && SchemaFormat::from_path(path).is_some()
+    }
+
+    /// Renames the fields of a schema, returning the new content and the renames
+    ///
+    /// The format is detected from `path`, which is also recorded in the renames.
+    /// JSON files that aren't JSON Schemas (no `$schema` key and no `.schema.json`
+    /// name) are returned unchanged. Fails if two fields of a message or schema would
+    /// get the same name.
+    pub fn rename_str<'a>(
+        &self,
+        path: &Path,
+        content: &'a str,
+    ) -> crate::Result<(Cow<'a, str>, Vec<FieldRename>)> {
+        match SchemaFormat::from_path(path) {
+            Some(SchemaFormat::Proto) => self.rename_proto(path, content),
+            Some(SchemaFormat::JsonSchema) => self.rename_json_schema(path, content),
+            None => Ok((Cow::Borrowed(content), Vec::new())),
+        }
+    }
+
+    /// New name for `name`, or `None` if it already matches the target case
+    fn rename(&self, name: &str) -> Option<String> {
+        let words = identifier_words(name);
+        if words.is_empty() {
+            return None;
+        }
+        let new_name = self.options.target.join_words(&words, "", "");
+        (new_name != name).then_some(new_name)
+    }
+
+    /// Renames for the fields of one scope, failing on collisions
+    fn rename_scope(&self, scope: &str, names: &[&str]) -> crate::Result<BTreeMap<String, String>> {
+        let existing: BTreeSet<&str> = names.iter().copied().collect();
+        let mut renames = BTreeMap::new();
+        let mut targets: BTreeMap<String, &str> = BTreeMap::new();
+
+        for &name in names {
+            let Some(new_name) = self.rename(name) else {
+                continue;
+            };
+            if existing.contains(new_name.as_str()) {
+                return Err(Error::Conflict(format!(
+                    "Renaming field '{}' to '{}' in '{}' would collide with an existing field",
+                    name, new_name, scope
+                )));
+            }
+            if let Some(other) = targets.insert(new_name.clone(), name) {
+                return Err(Error::Conflict(format!(
+                    "Fields '{}' and '{}' in '{}' would both be renamed to '{}'",
+                    other, name, scope, new_name
+                )));
+            }
+            renames.insert(name.to_string(), new_name);
+        }
+
+        Ok(renames)
+    }
+
+    fn rename_proto<'a>(
+        &self,
+        path: &Path,
+        content: &'a str,
+    ) -> crate::Result<(Cow<'a, str>, Vec<FieldRename>)> {
+        let fields = proto_fields(content);
+
+        let mut scopes: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (scope, range) in &fields {
+            scopes
+                .entry(scope)
+                .or_default()
+                .push(&content[range.clone()]);
+        }
+        let mut scope_renames = BTreeMap::new();
+        for (scope, names) in &scopes {
+            scope_renames.insert(*scope, self.rename_scope(scope, names)?);
+        }
+
+        let mut out = String::new();
+        let mut copied = 0;
+        let mut renames = Vec::new();
+        for (scope, range) in &fields {
+            let old = &content[range.clone()];
+            if let Some(new) = scope_renames[scope.as_str()].get(old) {
+                out.push_str(&content[copied..range.start]);
+                out.push_str(new);
+                copied = range.end;
+                renames.push(FieldRename {
+                    file: path.to_path_buf(),
+                    scope: scope.clone(),
+                    old: old.to_string(),
+                    new: new.clone(),
+                });
+            }
+        }
+
+        if renames.is_empty() {
+            return Ok((Cow::Borrowed(content), renames));
+        }
+        out.push_str(&content[copied..]);
+        Ok((Cow::Owned(out), renames))
+    }
+
+    fn rename_json_schema<'a>(
+        &self,
+        path: &Path,
+        content: &'a str,
+    ) -> crate::Result<(Cow<'a, str>, Vec<FieldRename>)> {
+        let named_schema = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.to_lowercase().ends_with(".schema.json"));
+        if !named_schema && !content.contains("\"$schema\"") {
+            return Ok((Cow::Borrowed(content), Vec::new()));
+        }
+
+        let mut schema: Value = serde_json::from_str(content)?;
+        if !named_schema && schema.get("$schema").is_none() {
+            return Ok((Cow::Borrowed(content), Vec::new()));
+        }
+
+        let mut walk = SchemaWalk {
+            renamer: self,
+            file: path,
+            renames: Vec::new(),
+            pointers: Vec::new(),
+        };
+        walk.visit(&mut schema, "#", "#")?;
+        if walk.renames.is_empty() {
+            return Ok((Cow::Borrowed(content), Vec::new()));
+        }
+
+        // Longest pointers first, so the most specific rename wins
+        walk.pointers
+            .sort_by_key(|(old, _)| std::cmp::Reverse(old.len()));
+        update_refs(&mut schema, &walk.pointers);
+
+        let mut converted = serde_json::to_string_pretty(&schema)?;
+        converted.push('\n');
+        Ok((Cow::Owned(converted), walk.renames))
+    }
+
+    /// Renames a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path) -> crate::Result<Vec<FieldRename>> {
+        if !self.should_process(path) {
+            return Ok(Vec::new());
+        }
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        let (converted, renames) = self
+            .rename_str(path, &content)
+            .with_context(|| format!("Failed to rename fields in '{}'", path.display()))?;
+
+        if let Cow::Owned(converted) = converted {
+            if !self.options.dry_run {
+                self.options.fs.write(path, converted.as_bytes())?;
+            }
+        }
+        Ok(renames)
+    }
+
+    fn report(&self, path: &Path, renames: &[FieldRename]) {
+        if renames.is_empty() {
+            return;
+        }
+
+        self.options.events.file_changed(path, renames.len(), self.options.dry_run);
+        if self.options.dry_run {
+            report!(
+                "Would rename {} fields in '{}'",
+                renames.len(),
+                path.display()
+            );
+        } else {
+            report!("Renamed {} fields in '{}'", renames.len(), path.display());
+        }
+    }
+
+    /// Processes a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<SchemaStats> {
+        let mut stats = SchemaStats::default();
+
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.apply_to_file(file)
+        });
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let renames = result?;
+            self.report(file_path, &renames);
+            if !renames.is_empty() {
+                stats.files_changed += 1;
+                stats.renames.extend(renames);
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+/// Keywords whose values are instance data rather than schemas
+const DATA_KEYWORDS: &[&str] = &["const", "default", "enum", "examples"];
+
+/// State of a JSON Schema rename pass
+struct SchemaWalk<'r> {
+    renamer: &'r FieldRenamer,
+    file: &'r Path,
+    renames: Vec<FieldRename>,
+    /// Old -> new JSON pointers of properties whose path changed
+    pointers: Vec<(String, String)>,
+}
+
+impl SchemaWalk<'_> {
+    fn visit(&mut self, value: &mut Value, old_ptr: &str, new_ptr: &str) -> crate::Result<()> {
+        match value {
+            Value::Object(map) => {
+                let mut renamed = BTreeMap::new();
+                if let Some(Value::Object(properties)) = map.get_mut("properties") {
+                    let names: Vec<&str> = properties.keys().map(String::as_str).collect();
+                    renamed = self.renamer.rename_scope(old_ptr, &names)?;
+
+                    let mut converted = serde_json::Map::new();
+                    for (name, mut schema) in std::mem::take(properties) {
+                        let new_name = renamed.get(&name).cloned().unwrap_or_else(|| name.clone());
+                        let old_child = format!("{}/properties/{}", old_ptr, escape_pointer(&name));
+                        let new_child =
+                            format!("{}/properties/{}", new_ptr, escape_pointer(&new_name));
+                        self.visit(&mut schema, &old_child, &new_child)?;
+                        if old_child != new_child {
+                            self.pointers.push((old_child, new_child));
+                        }
+                        if new_name != name {
+                            self.renames.push(FieldRename {
+                                file: self.file.to_path_buf(),
+                                scope: old_ptr.to_string(),
+                                old: name,
+                                new: new_name.clone(),
+                            });
+                        }
+                        converted.insert(new_name, schema);
+                    }
+                    *properties = converted;
+                }
+
+                if let Some(Value::Array(required)) = map.get_mut("required") {
+                    rename_strings(required, &renamed);
+                }
+                if let Some(Value::Object(dependent)) = map.get_mut("dependentRequired") {
+                    let mut converted = serde_json::Map::new();
+                    for (name, mut names) in std::mem::take(dependent) {
+                        if let Value::Array(names) = &mut names {
+                            rename_strings(names, &renamed);
+                        }
+                        converted.insert(renamed.get(&name).cloned().unwrap_or(name), names);
+                    }
+                    *dependent = converted;
+                }
+
+                for (key, child) in map.iter_mut() {
+                    if matches!(
+                        key.as_str(),
+                        "properties" | "required" | "dependentRequired"
+                    ) || DATA_KEYWORDS.contains(&key.as_str())
+                    {
+                        continue;
+                    }
+                    let segment = escape_pointer(key);
+                    self.visit(
+                        child,
+                        &format!("{}/{}", old_ptr, segment),
+                        &format!("{}/{}", new_ptr, segment),
+                    )?;
+                }
+            }
+            Value::Array(items) => {
+                for (i, item) in items.iter_mut().enumerate() {
+                    self.visit(
+                        item,
+                        &format!("{}/{}", old_ptr, i),
+                        &format!("{}/{}", new_ptr, i),
+                    )?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn rename_strings(values: &mut [Value], renamed: &BTreeMap<String, String>) {
+    for value in values {
+        if let Value::String(name) = value {
+            if let Some(new_name) = renamed.get(name.as_str()) {
+                *name = new_name.clone();
+            }
+        }
+    }
+}
+
+/// Escapes a JSON pointer segment (`~` -> `~0`, `/` -> `~1`)
+fn escape_pointer(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+/// Points local `$ref`s at the renamed properties
+///
+/// `pointers` must be sorted longest first.
+fn update_refs(value: &mut Value, pointers: &[(String, String)]) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                match child {
+                    Value::String(target) if key == "$ref" => {
+                        let new_target = pointers.iter().find_map(|(old, new)| {
+                            let rest = target.strip_prefix(old.as_str())?;
+                            (rest.is_empty() || rest.starts_with('/'))
+                                .then(|| format!("{}{}", new, rest))
+                        });
+                        if let Some(new_target) = new_target {
+                            *target = new_target;
+                        }
+                    }
+                    _ => update_refs(child, pointers),
+                }
+            }
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| update_refs(item, pointers)),
+        _ => {}
+    }
+}
+
+/// Kinds of `{ ... }` blocks in a `.proto` file
+enum ProtoScope {
+    /// `message Name` or `extend Name`; fields belong to `Name`
+    Fields(String),
+    /// `oneof name`; fields belong to the enclosing message
+    Oneof,
+    /// `enum`, `service`, option values, ...
+    Other,
+}
+
+/// Field name ranges in a `.proto` file, with the message they belong to
+///
+/// A field is an identifier directly followed by `= <number>` inside a message,
+/// oneof or extend block, after a type name, and not in an `option` statement or
+/// `[...]` field options.
+fn proto_fields(content: &str) -> Vec<(String, Range<usize>)> {
+    let tokens = proto_tokens(content);
+    let mut fields = Vec::new();
+    let mut scopes: Vec<ProtoScope> = Vec::new();
+    let mut statement = 0;
+    let mut brackets = 0usize;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            ProtoToken::Punct(b'{') => {
+                let keyword = match tokens.get(statement) {
+                    Some(ProtoToken::Ident(range)) => &content[range.clone()],
+                    _ => "",
+                };
+                let name = match tokens.get(statement + 1) {
+                    Some(ProtoToken::Ident(range)) => &content[range.clone()],
+                    _ => "",
+                };
+                scopes.push(match keyword {
+                    "message" => {
+                        let parent = scope_name(&scopes);
+                        if parent.is_empty() {
+                            ProtoScope::Fields(name.to_string())
+                        } else {
+                            ProtoScope::Fields(format!("{}.{}", parent, name))
+                        }
+                    }
+                    "extend" => ProtoScope::Fields(name.trim_start_matches('.').to_string()),
+                    "oneof" => ProtoScope::Oneof,
+                    _ => ProtoScope::Other,
+                });
+                statement = i + 1;
+            }
+            ProtoToken::Punct(b'}') => {
+                scopes.pop();
+                statement = i + 1;
+            }
+            ProtoToken::Punct(b';') => statement = i + 1,
+            ProtoToken::Punct(b'[') => brackets += 1,
+            ProtoToken::Punct(b']') => brackets = brackets.saturating_sub(1),
+            ProtoToken::Punct(b'=') if brackets == 0 && i >= 2 => {
+                let in_fields = matches!(
+                    scopes.last(),
+                    Some(ProtoScope::Fields(_) | ProtoScope::Oneof)
+                );
+                let is_option = matches!(
+                    tokens.get(statement),
+                    Some(ProtoToken::Ident(range)) if &content[range.clone()] == "option"
+                );
+                let after_type = matches!(
+                    tokens[i - 2],
+                    ProtoToken::Ident(_) | ProtoToken::Punct(b'>')
+                );
+                let numbered = matches!(tokens.get(i + 1), Some(ProtoToken::Number));
+                if let ProtoToken::Ident(range) = &tokens[i - 1] {
+                    if in_fields && !is_option && after_type && numbered {
+                        fields.push((scope_name(&scopes), range.clone()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fields
+}
+
+/// Name of the innermost message or extended type
+fn scope_name(scopes: &[ProtoScope]) -> String {
+    scopes
+        .iter()
+        .rev()
+        .find_map(|scope| match scope {
+            ProtoScope::Fields(name) => Some(name.clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+enum ProtoToken {
+    Ident(Range<usize>),
+    Number,
+    Punct(u8),
+    Str,
+}
+
+/// Splits a `.proto` file into tokens, dropping comments and whitespace
+fn proto_tokens(content: &str) -> Vec<ProtoToken> {
+    let bytes = content.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c.is_ascii_whitespace() {
+            i += 1;
+        } else if bytes[i..].starts_with(b"//") {
+            i = content[i..].find('\n').map_or(bytes.len(), |end| i + end);
+        } else if bytes[i..].starts_with(b"/*") {
+            i = content[i + 2..]
+                .find("*/")
+                .map_or(bytes.len(), |end| i + 2 + end + 2);
+        } else if c == b'"' || c == b'\'' {
+            i += 1;
+            while i < bytes.len() && bytes[i] != c {
+                i += if bytes[i] == b'\\' { 2 } else { 1 };
+            }
+            i += 1;
+            tokens.push(ProtoToken::Str);
+        } else if c.is_ascii_alphabetic() || c == b'_' || c == b'.' {
+            let start = i;
+            while i < bytes.len()
+                && (bytes[i].is_ascii_alphanumeric() || matches!(bytes[i], b'_' | b'.'))
+            {
+                i += 1;
+            }
+            tokens.push(ProtoToken::Ident(start..i));
+        } else if c.is_ascii_digit() || c == b'-' {
+            i += 1;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') {
+                i += 1;
+            }
+            tokens.push(ProtoToken::Number);
+        } else {
+            // Step over whole characters so slicing stays on UTF-8 boundaries
+            tokens.push(ProtoToken::Punct(c));
+            i += content[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn renamer(target: CaseFormat) -> FieldRenamer {
+        let options = SchemaOptions {
+            target,
+            ..SchemaOptions::default()
+        };
+        FieldRenamer::new(options)
+    }
+
+    #[test]
+    fn test_proto_fields() {
+        let content = concat!(
+            "syntax = \"proto3\";\n",
+            "option java_package = \"com.example\";\n",
+            "message User {\n",
+            "  string firstName = 1; // lastName = 9\n",
+            "  repeated int32 itemIds = 2 [deprecated = true];\n",
+            "  map<string, string> extraInfo = 3;\n",
+            "  oneof contactMethod { string emailAddress = 4; }\n",
+            "  message Address { string zipCode = 1; }\n",
+            "  enum Status { STATUS_UNKNOWN = 0; }\n",
+            "  reserved \"oldName\";\n",
+            "}\n",
+        );
+        let (result, renames) = renamer(CaseFormat::SnakeCase)
+            .rename_str(Path::new("user.proto"), content)
+            .unwrap();
+        assert_eq!(
+            result,
+            concat!(
+                "syntax = \"proto3\";\n",
+                "option java_package = \"com.example\";\n",
+                "message User {\n",
+                "  string first_name = 1; // lastName = 9\n",
+                "  repeated int32 item_ids = 2 [deprecated = true];\n",
+                "  map<string, string> extra_info = 3;\n",
+                "  oneof contactMethod { string email_address = 4; }\n",
+                "  message Address { string zip_code = 1; }\n",
+                "  enum Status { STATUS_UNKNOWN = 0; }\n",
+                "  reserved \"oldName\";\n",
+                "}\n",
+            )
+        );
+        let found: Vec<_> = renames
+            .iter()
+            .map(|r| (r.scope.as_str(), r.old.as_str(), r.new.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("User", "firstName", "first_name"),
+                ("User", "itemIds", "item_ids"),
+                ("User", "extraInfo", "extra_info"),
+                ("User", "emailAddress", "email_address"),
+                ("User.Address", "zipCode", "zip_code"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let content = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "properties": {
+    "first_name": { "type": "string", "default": { "keep_me": 1 } },
+    "home_address": { "$ref": "#/$defs/address" },
+    "work_address": { "$ref": "#/properties/home_address" }
+  },
+  "required": ["first_name", "other"],
+  "$defs": {
+    "address": { "properties": { "zip_code": { "type": "string" } } }
+  }
+}"##;
+        let (result, renames) = renamer(CaseFormat::CamelCase)
+            .rename_str(Path::new("user.json"), content)
+            .unwrap();
+        let value: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            value["properties"]["firstName"]["default"]["keep_me"],
+            Value::from(1)
+        );
+        assert_eq!(
+            value["properties"]["workAddress"]["$ref"],
+            "#/properties/homeAddress"
+        );
+        assert_eq!(value["required"], serde_json::json!(["firstName", "other"]));
+        assert!(value["$defs"]["address"]["properties"]["zipCode"].is_object());
+
+        let scopes: Vec<_> = renames
+            .iter()
+            .map(|r| (r.scope.as_str(), r.old.as_str()))
+            .collect();
+        assert_eq!(
+            scopes,
+            vec![
+                ("#", "first_name"),
+                ("#", "home_address"),
+                ("#", "work_address"),
+                ("#/$defs/address", "zip_code"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_non_schema_json_and_collisions() {
+        let renamer = renamer(CaseFormat::SnakeCase);
+        let content = "{\"properties\": {\"fooBar\": {}}}";
+        let (result, renames) = renamer
+            .rename_str(Path::new("package.json"), content)
+            .unwrap();
+        assert!(matches!(result, Cow::Borrowed(_)));
+        assert!(renames.is_empty());
+
+        let content = "message M { int32 fooBar = 1; int32 foo_bar = 2; }";
+        assert!(renamer.rename_str(Path::new("m.proto"), content).is_err());
+    }
+}
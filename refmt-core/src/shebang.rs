@@ -0,0 +1,401 @@
+//! Shebang normalization
+//!
+//! Rewrites `#!` lines to a canonical form (`#!/usr/bin/python` ->
+//! `#!/usr/bin/env python3`), removes a `\r` left on the shebang line by CRLF line
+//! endings (the kernel would look for `python3\r`), and makes the executable bit match:
+//! scripts with a shebang become executable, other text files lose the bit.
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::eol::is_binary;
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::report::{report, EventSink};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// Options for shebang normalization
+#[derive(Debug, Clone)]
+pub struct ShebangOptions {
+    /// Run interpreters through `/usr/bin/env` instead of an absolute path
+    pub use_env: bool,
+    /// Set or clear the executable bit to match the shebang (Unix only)
+    pub fix_permissions: bool,
+    /// File extensions to process (empty processes every text file)
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for ShebangOptions {
+    fn default() -> Self {
+        ShebangOptions {
+            use_env: true,
+            fix_permissions: true,
+            file_extensions: Vec::new(),
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// Statistics from shebang normalization
+#[derive(Debug, Default)]
+pub struct ShebangStats {
+    /// Number of files changed
+    pub files_changed: usize,
+    /// Number of shebang lines rewritten
+    pub shebangs_rewritten: usize,
+    /// Number of shebang lines that ended in CRLF
+    pub line_endings_fixed: usize,
+    /// Number of files whose executable bit was set or cleared
+    pub permissions_fixed: usize,
+}
+
+/// What changed in a single file
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ShebangFix {
+    /// Old and new shebang line
+    rewritten: Option<(String, String)>,
+    crlf: bool,
+    /// New state of the executable bit, if it changed
+    executable: Option<bool>,
+}
+
+impl ShebangFix {
+    fn is_empty(&self) -> bool {
+        self.rewritten.is_none() && !self.crlf && self.executable.is_none()
+    }
+}
+
+/// Shebang normalizer
+pub struct ShebangNormalizer {
+    options: ShebangOptions,
+    walker: Walker,
+}
+
+impl ShebangNormalizer {
+    /// Creates a new normalizer with the given options
+    pub fn new(options: ShebangOptions) -> Self {
+        ShebangNormalizer {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a normalizer with default options
+    pub fn with_defaults() -> Self {
+        ShebangNormalizer::new(ShebangOptions::default())
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        if !self.options.fs.is_file(path) {
+            return false;
+        }
+
+        // An empty extension list selects every file
+        let filter = &self.options.filter;
+        if self.options.file_extensions.is_empty() {
+            return filter
+                .hidden_skip(path)
+                .or_else(|| filter.dir_skip(path))
+                .or_else(|| filter.skip_reason(path))
+                .is_none();
+        }
+        filter.path_skip(path, &self.options.file_extensions).is_none()
+    }
+
+    /// Returns the canonical form of a shebang line (without its line ending)
+    ///
+    /// Whitespace is normalized, `env` is spelled `/usr/bin/env` and bare `python`
+    /// becomes `python3`. With `use_env`, absolute interpreter paths are replaced by
+    /// `/usr/bin/env <name>`, except `/bin/sh` and interpreters given arguments, since
+    /// `env` would receive the name and arguments as a single word.
+    pub fn canonical_shebang<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let Some(command) = line.strip_prefix("#!") else {
+            return Cow::Borrowed(line);
+        };
+        let mut words = command.split_whitespace();
+        let Some(interpreter) = words.next() else {
+            return Cow::Borrowed(line);
+        };
+        let args: Vec<&str> = words.collect();
+
+        let name = interpreter.rsplit('/').next().unwrap_or(interpreter);
+        let canonical = if name == "env" {
+            match args.split_first() {
+                Some((program, rest)) if !program.starts_with('-') => env_shebang(program, rest),
+                _ if args.is_empty() => "#!/usr/bin/env".to_string(),
+                _ => format!("#!/usr/bin/env {}", args.join(" ")),
+            }
+        } else if self.options.use_env && args.is_empty() && interpreter != "/bin/sh" {
+            env_shebang(name, &[])
+        } else {
+            let mut canonical = format!("#!{}", interpreter);
+            for arg in &args {
+                canonical.push(' ');
+                canonical.push_str(arg);
+            }
+            canonical
+        };
+
+        if canonical == line {
+            Cow::Borrowed(line)
+        } else {
+            Cow::Owned(canonical)
+        }
+    }
+
+    /// Works out the fixes for a file without applying them
+    fn plan(&self, path: &Path, content: &[u8]) -> crate::Result<(ShebangFix, Option<Vec<u8>>)> {
+        let mut fix = ShebangFix::default();
+        let mut new_content = None;
+        let has_shebang = content.starts_with(b"#!");
+
+        if has_shebang {
+            let line_end = memchr::memchr(b'\n', content).unwrap_or(content.len());
+            let line = &content[..line_end];
+            let (line, crlf) = match line.strip_suffix(b"\r") {
+                Some(line) => (line, true),
+                None => (line, false),
+            };
+
+            if let Ok(line) = std::str::from_utf8(line) {
+                let canonical = self.canonical_shebang(line);
+                if canonical != line || crlf {
+                    if canonical != line {
+                        fix.rewritten = Some((line.to_string(), canonical.to_string()));
+                    }
+                    fix.crlf = crlf;
+
+                    let mut rewritten = canonical.into_owned().into_bytes();
+                    rewritten.extend_from_slice(&content[line_end..]);
+                    new_content = Some(rewritten);
+                }
+            }
+        }
+
+        if self.options.fix_permissions {
+            let executable = is_executable(path)?;
+            if has_shebang && !executable {
+                fix.executable = Some(true);
+            } else if !has_shebang && executable && path.extension().is_some() {
+                // Extension-less executables without a shebang are left alone
+                fix.executable = Some(false);
+            }
+        }
+
+        Ok((fix, new_content))
+    }
+
+    /// Normalizes a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path) -> crate::Result<ShebangFix> {
+        if !self.should_process(path) {
+            return Ok(ShebangFix::default());
+        }
+
+        let content = self.options.fs.read(path)?;
+        if is_binary(&content) {
+            return Ok(ShebangFix::default());
+        }
+
+        let (fix, new_content) = self.plan(path, &content)?;
+        if !self.options.dry_run {
+            if let Some(new_content) = new_content {
+                self.options.fs.write(path, &new_content)?;
+            }
+            if let Some(executable) = fix.executable {
+                set_executable(path, executable)?;
+            }
+        }
+
+        Ok(fix)
+    }
+
+    fn report(&self, path: &Path, fix: &ShebangFix) {
+        let dry_run = self.options.dry_run;
+        let changes = [fix.rewritten.is_some(), fix.crlf, fix.executable.is_some()];
+        self.options.events.file_changed(path, changes.iter().filter(|&&c| c).count(), dry_run);
+        if let Some((old, new)) = &fix.rewritten {
+            let verb = if dry_run { "Would rewrite" } else { "Rewrote" };
+            report!("{} shebang in '{}': {} -> {}", verb, path.display(), old, new);
+        }
+        if fix.crlf {
+            let verb = if dry_run { "Would fix" } else { "Fixed" };
+            report!("{} CRLF after shebang in '{}'", verb, path.display());
+        }
+        match (fix.executable, dry_run) {
+            (Some(true), false) => report!("Made '{}' executable", path.display()),
+            (Some(true), true) => report!("Would make '{}' executable", path.display()),
+            (Some(false), false) => report!("Removed executable bit from '{}'", path.display()),
+            (Some(false), true) => report!("Would remove executable bit from '{}'", path.display()),
+            (None, _) => {}
+        }
+    }
+
+    /// Processes a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<ShebangStats> {
+        let mut stats = ShebangStats::default();
+
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.apply_to_file(file)
+        });
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let fix = result?;
+            if fix.is_empty() {
+                continue;
+            }
+            self.report(file_path, &fix);
+
+            stats.files_changed += 1;
+            stats.shebangs_rewritten += usize::from(fix.rewritten.is_some());
+            stats.line_endings_fixed += usize::from(fix.crlf);
+            stats.permissions_fixed += usize::from(fix.executable.is_some());
+        }
+
+        Ok(stats)
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+/// `#!/usr/bin/env <program> <args>`, with bare `python` pinned to `python3`
+fn env_shebang(program: &str, args: &[&str]) -> String {
+    let program = if program == "python" { "python3" } else { program };
+    let mut shebang = format!("#!/usr/bin/env {}", program);
+    for arg in args {
+        shebang.push(' ');
+        shebang.push_str(arg);
+    }
+    shebang
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> crate::Result<bool> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok(fs::metadata(path)?.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> crate::Result<bool> {
+    Ok(false)
+}
+
+/// Sets or clears the executable bits, adding them only where the file is readable
+#[cfg(unix)]
+fn set_executable(path: &Path, executable: bool) -> crate::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    let mode = permissions.mode();
+    let mode = if executable {
+        mode | ((mode & 0o444) >> 2)
+    } else {
+        mode & !0o111
+    };
+    permissions.set_mode(mode);
+    fs::set_permissions(path, permissions)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path, _executable: bool) -> crate::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_shebang() {
+        let normalizer = ShebangNormalizer::with_defaults();
+        let canonical = |line| normalizer.canonical_shebang(line).into_owned();
+
+        assert_eq!(canonical("#!/usr/bin/python"), "#!/usr/bin/env python3");
+        assert_eq!(canonical("#! /usr/local/bin/node"), "#!/usr/bin/env node");
+        assert_eq!(canonical("#!/bin/env  python"), "#!/usr/bin/env python3");
+        assert_eq!(canonical("#!/usr/bin/env -S deno run"), "#!/usr/bin/env -S deno run");
+        assert_eq!(canonical("#!/usr/bin/perl -w"), "#!/usr/bin/perl -w");
+        assert_eq!(canonical("#!/bin/sh"), "#!/bin/sh");
+        assert!(matches!(
+            normalizer.canonical_shebang("#!/usr/bin/env bash"),
+            Cow::Borrowed(_)
+        ));
+
+        let options = ShebangOptions {
+            use_env: false,
+            ..ShebangOptions::default()
+        };
+        let normalizer = ShebangNormalizer::new(options);
+        assert_eq!(normalizer.canonical_shebang("#!/usr/bin/python3"), "#!/usr/bin/python3");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fix_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let test_dir = std::env::temp_dir().join("refmt_shebang_test");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+
+        let script = test_dir.join("run.py");
+        fs::write(&script, "#!/usr/bin/python\r\nprint('hi')\r\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o644)).unwrap();
+        let module = test_dir.join("lib.py");
+        fs::write(&module, "x = 1\n").unwrap();
+        fs::set_permissions(&module, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let stats = ShebangNormalizer::with_defaults().process(&test_dir).unwrap();
+        assert_eq!(stats.files_changed, 2);
+        assert_eq!(stats.shebangs_rewritten, 1);
+        assert_eq!(stats.line_endings_fixed, 1);
+        assert_eq!(stats.permissions_fixed, 2);
+
+        assert_eq!(
+            fs::read_to_string(&script).unwrap(),
+            "#!/usr/bin/env python3\nprint('hi')\r\n"
+        );
+        assert_eq!(fs::metadata(&script).unwrap().permissions().mode() & 0o777, 0o755);
+        assert_eq!(fs::metadata(&module).unwrap().permissions().mode() & 0o777, 0o644);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+}
@@ -0,0 +1,394 @@
+//! SQL keyword case normalization
+//!
+//! Uppercases (or lowercases) SQL keywords in `.sql` files. String literals,
+//! quoted identifiers, dollar-quoted bodies and comments are skipped, as are words
+//! qualified with a dot (`t.order`), so only keywords used as keywords change.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::report::{report, EventSink, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// Reserved words recognized as keywords
+///
+/// Words that commonly double as column names (`name`, `type`, `date`, `user`, ...)
+/// are deliberately left out.
+static KEYWORDS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
+    "ADD ALL ALTER ANALYZE AND ANY AS ASC BEGIN BETWEEN BY CASCADE CASE CAST CHECK COLUMN
+     COMMIT CONSTRAINT CREATE CROSS DATABASE DECLARE DEFAULT DELETE DESC DISTINCT DROP
+     ELSE END EXCEPT EXISTS EXPLAIN FALSE FETCH FOR FOREIGN FROM FULL FUNCTION GRANT
+     GROUP HAVING IF ILIKE IN INDEX INNER INSERT INTERSECT INTO IS JOIN KEY LATERAL LEFT
+     LIKE LIMIT MERGE NATURAL NOT NULL OFFSET ON OR ORDER OUTER OVER PARTITION PRIMARY
+     PROCEDURE RECURSIVE REFERENCES RENAME REPLACE RESTRICT RETURNING RETURNS REVOKE
+     RIGHT ROLLBACK SCHEMA SELECT SEQUENCE SET SOME TABLE TEMPORARY THEN TO TRANSACTION
+     TRIGGER TRUE TRUNCATE UNION UNIQUE UPDATE USING VALUES VIEW WHEN WHERE WINDOW WITH"
+        .split_whitespace()
+        .collect()
+});
+
+/// Case to write keywords in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeywordCase {
+    /// `SELECT`
+    Upper,
+    /// `select`
+    Lower,
+}
+
+/// Options for SQL keyword normalization
+#[derive(Debug, Clone)]
+pub struct SqlOptions {
+    /// Case to write keywords in
+    pub keywords: KeywordCase,
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for SqlOptions {
+    fn default() -> Self {
+        SqlOptions {
+            keywords: KeywordCase::Upper,
+            file_extensions: vec![".sql".to_string()],
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// Normalizes the case of SQL keywords
+pub struct SqlFormatter {
+    options: SqlOptions,
+    walker: Walker,
+}
+
+impl SqlFormatter {
+    /// Creates a new SQL formatter with the given options
+    pub fn new(options: SqlOptions) -> Self {
+        SqlFormatter {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a formatter with default options (uppercase keywords)
+    pub fn with_defaults() -> Self {
+        SqlFormatter {
+            options: SqlOptions::default(),
+            walker: Walker::default(),
+        }
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        self.options.fs.is_file(path) && self.accepts(path)
+    }
+
+    /// Checks the path alone against the filters, without touching the filesystem
+    pub fn accepts(&self, path: &Path) -> bool {
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none()
+    }
+
+    /// Rewrites keywords in SQL source, returning the new content and the number of
+    /// keywords changed
+    ///
+    /// Returns the input borrowed when every keyword already has the requested case.
+    pub fn format_str<'a>(&self, content: &'a str) -> (Cow<'a, str>, usize) {
+        let bytes = content.as_bytes();
+        let mut out = String::new();
+        let mut copied = 0;
+        let mut changes = 0;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let rest = &bytes[i..];
+            let c = bytes[i];
+
+            if rest.starts_with(b"--") {
+                i = find(bytes, i, b"\n").unwrap_or(bytes.len());
+            } else if rest.starts_with(b"/*") {
+                i = find(bytes, i + 2, b"*/").map_or(bytes.len(), |end| end + 2);
+            } else if matches!(c, b'\'' | b'"' | b'`') {
+                i = skip_quoted(bytes, i, c);
+            } else if c == b'$' {
+                i = skip_dollar_quoted(content, i);
+            } else if c.is_ascii_alphabetic() || c == b'_' {
+                let start = i;
+                while i < bytes.len() && is_word_byte(bytes[i]) {
+                    i += 1;
+                }
+                let word = &content[start..i];
+
+                // `t.order` and `schema.table` are names, whatever they spell
+                let qualified =
+                    (start > 0 && bytes[start - 1] == b'.') || bytes.get(i) == Some(&b'.');
+                if qualified || !KEYWORDS.contains(word.to_ascii_uppercase().as_str()) {
+                    continue;
+                }
+
+                let cased = match self.options.keywords {
+                    KeywordCase::Upper => word.to_ascii_uppercase(),
+                    KeywordCase::Lower => word.to_ascii_lowercase(),
+                };
+                if cased != word {
+                    out.push_str(&content[copied..start]);
+                    out.push_str(&cased);
+                    copied = i;
+                    changes += 1;
+                }
+            } else if c.is_ascii_digit() || c >= 0x80 {
+                // Numbers and non-ASCII identifiers can't start a keyword
+                while i < bytes.len() && (is_word_byte(bytes[i]) || bytes[i] >= 0x80) {
+                    i += 1;
+                }
+            } else {
+                i += 1;
+            }
+        }
+
+        if changes == 0 {
+            return (Cow::Borrowed(content), 0);
+        }
+        out.push_str(&content[copied..]);
+        (Cow::Owned(out), changes)
+    }
+
+    /// Normalizes keywords in a single file
+    pub fn format_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        let outcome = self.apply_to_file(path)?;
+        self.report(path, outcome);
+        Ok(outcome)
+    }
+
+    /// Formats a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        if !self.should_process(path) {
+            return Ok(FileOutcome::unchanged());
+        }
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        let (formatted, changes) = self.format_str(&content);
+
+        match formatted {
+            Cow::Borrowed(_) => Ok(FileOutcome::unchanged()),
+            Cow::Owned(formatted) => {
+                if !self.options.dry_run {
+                    self.options.fs.write(path, formatted.as_bytes())?;
+                }
+                Ok(FileOutcome::changed(changes))
+            }
+        }
+    }
+
+    fn report(&self, path: &Path, outcome: FileOutcome) {
+        if !outcome.changed {
+            return;
+        }
+
+        self.options.events.file_changed(path, outcome.changes, self.options.dry_run);
+        if self.options.dry_run {
+            report!(
+                "Would recase {} keywords in '{}'",
+                outcome.changes,
+                path.display()
+            );
+        } else {
+            report!(
+                "Recased {} keywords in '{}'",
+                outcome.changes,
+                path.display()
+            );
+        }
+    }
+
+    /// Processes a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<(usize, usize)> {
+        let mut total_files = 0;
+        let mut total_changes = 0;
+
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.apply_to_file(file)
+        });
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let outcome = result?;
+            self.report(file_path, outcome);
+            if outcome.changed {
+                total_files += 1;
+                total_changes += outcome.changes;
+            }
+        }
+
+        Ok((total_files, total_changes))
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$'
+}
+
+/// Position of the next `needle` at or after `from`
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|p| from + p)
+}
+
+/// Skips a quoted string or identifier; a doubled quote (`'it''s'`) doesn't end it
+fn skip_quoted(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && quote == b'\'' {
+            i += 2;
+        } else if bytes[i] == quote {
+            if bytes.get(i + 1) == Some(&quote) {
+                i += 2;
+            } else {
+                return i + 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+/// Skips a PostgreSQL dollar-quoted string (`$$...$$`, `$body$...$body$`)
+///
+/// A `$` that doesn't open one (`$1` parameters) is skipped on its own.
+fn skip_dollar_quoted(content: &str, start: usize) -> usize {
+    let bytes = content.as_bytes();
+    let tag_len = bytes[start + 1..]
+        .iter()
+        .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+        .count();
+    let tag_end = start + 1 + tag_len;
+    if bytes.get(tag_end) != Some(&b'$') || bytes.get(start + 1).is_some_and(u8::is_ascii_digit) {
+        return start + 1;
+    }
+
+    let tag = &bytes[start..=tag_end];
+    find(bytes, tag_end + 1, tag).map_or(bytes.len(), |end| end + tag.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+
+    #[test]
+    fn test_uppercase_keywords() {
+        let content = concat!(
+            "select id, t.order, 'select from' as label -- select\n",
+            "from orders t left join \"group\" g on g.id = t.group_id\n",
+            "where name like 'a''b' and $1 is not null;\n",
+            "/* create table */\n",
+        );
+        let (result, changes) = SqlFormatter::with_defaults().format_str(content);
+        assert_eq!(
+            result,
+            concat!(
+                "SELECT id, t.order, 'select from' AS label -- select\n",
+                "FROM orders t LEFT JOIN \"group\" g ON g.id = t.group_id\n",
+                "WHERE name LIKE 'a''b' AND $1 IS NOT NULL;\n",
+                "/* create table */\n",
+            )
+        );
+        assert_eq!(changes, 12);
+    }
+
+    #[test]
+    fn test_lowercase_and_dollar_quotes() {
+        let options = SqlOptions {
+            keywords: KeywordCase::Lower,
+            ..SqlOptions::default()
+        };
+        let formatter = SqlFormatter::new(options);
+
+        let content = "CREATE FUNCTION f() RETURNS int AS $body$ SELECT 1 $body$;\n";
+        let (result, changes) = formatter.format_str(content);
+        assert_eq!(
+            result,
+            "create function f() returns int as $body$ SELECT 1 $body$;\n"
+        );
+        assert_eq!(changes, 4);
+    }
+
+    #[test]
+    fn test_unchanged_is_borrowed() {
+        let (result, changes) =
+            SqlFormatter::with_defaults().format_str("SELECT name FROM users;\n");
+        assert!(matches!(result, Cow::Borrowed(_)));
+        assert_eq!(changes, 0);
+    }
+
+    #[test]
+    fn test_accepts_dot_root() {
+        let mut formatter = SqlFormatter::with_defaults();
+        assert!(formatter.accepts(Path::new("./queries/report.sql")));
+        assert!(!formatter.accepts(Path::new("./.cache/report.sql")));
+        assert!(!formatter.accepts(Path::new("./target/report.sql")));
+
+        formatter.options.filter.exclude = vec![glob::Pattern::new("queries/**").unwrap()];
+        assert!(!formatter.accepts(Path::new("./queries/report.sql")));
+    }
+
+    #[test]
+    fn test_process_in_memory() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.add_file("db/schema.sql", "create table t (id int);\n");
+        fs.add_file("db/notes.txt", "create table\n");
+
+        let options = SqlOptions {
+            fs: fs.clone(),
+            ..SqlOptions::default()
+        };
+        let (files, changes) = SqlFormatter::new(options).process(Path::new("db")).unwrap();
+        assert_eq!((files, changes), (1, 2));
+        let schema = fs.read_to_string("db/schema.sql").unwrap();
+        assert_eq!(schema, "CREATE TABLE t (id int);\n");
+        assert_eq!(fs.read_to_string("db/notes.txt").unwrap(), "create table\n");
+    }
+}
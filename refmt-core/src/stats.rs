@@ -0,0 +1,259 @@
+//! Case style statistics
+//!
+//! Counts the identifiers of each case format in a tree, per file extension and per
+//! directory, without changing anything, so a team can see how far a migration from
+//! one style to another has come. Identifiers are found with the patterns `convert`
+//! matches ([`CaseFormat::pattern`]); single words fit no format and aren't counted,
+//! and each identifier counts under one format only. dot.case and path/case are left
+//! out unless asked for, as member accesses and paths match them too.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::case::CaseFormat;
+use crate::filter::{ExtensionSet, FilterOptions};
+use crate::parallel;
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// Number of identifiers of each format; formats without any are left out
+pub type CaseCounts = BTreeMap<CaseFormat, usize>;
+
+/// Options for the statistics scan
+#[derive(Debug, Clone)]
+pub struct StatsOptions {
+    /// File extensions to scan
+    pub file_extensions: Vec<String>,
+    /// Scan directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Hidden files, build directories, globs and localization files
+    pub filter: FilterOptions,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Case formats to count
+    pub formats: Vec<CaseFormat>,
+}
+
+impl StatsOptions {
+    /// Every format except dot.case and path/case, which also match `self.name` and
+    /// `src/lib`
+    pub fn default_formats() -> Vec<CaseFormat> {
+        CaseFormat::ALL
+            .into_iter()
+            .filter(|format| !matches!(format, CaseFormat::DotCase | CaseFormat::PathCase))
+            .collect()
+    }
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        StatsOptions {
+            file_extensions: ExtensionSet::Convert.defaults(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            filter: FilterOptions::default(),
+            fs: vfs::real(),
+            formats: StatsOptions::default_formats(),
+        }
+    }
+}
+
+/// Identifier counts of a scanned tree
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CaseStats {
+    /// Files scanned, including those without identifiers
+    pub files_scanned: usize,
+    /// Counts over every file
+    pub total: CaseCounts,
+    /// Counts per file extension (`.py`; files without one are under `""`)
+    pub by_extension: BTreeMap<String, CaseCounts>,
+    /// Counts per directory, for the files directly inside it
+    pub by_directory: BTreeMap<PathBuf, CaseCounts>,
+}
+
+impl CaseStats {
+    /// The share of `format` among all counted identifiers, from 0 to 1
+    pub fn share(&self, format: CaseFormat) -> f64 {
+        let total: usize = self.total.values().sum();
+        if total == 0 {
+            return 0.0;
+        }
+        self.total.get(&format).copied().unwrap_or(0) as f64 / total as f64
+    }
+}
+
+fn add_counts(into: &mut CaseCounts, counts: &CaseCounts) {
+    for (&format, &count) in counts {
+        *into.entry(format).or_insert(0) += count;
+    }
+}
+
+/// Read-only scanner counting identifiers by case format
+pub struct CaseScanner {
+    options: StatsOptions,
+    /// One group per format in `options.formats`, so each identifier matches once
+    pattern: Regex,
+    walker: Walker,
+}
+
+impl CaseScanner {
+    /// Creates a new scanner with the given options
+    pub fn new(options: StatsOptions) -> Self {
+        let pattern = options
+            .formats
+            .iter()
+            .map(|format| format!("({})", format.pattern()))
+            .collect::<Vec<_>>()
+            .join("|");
+        let pattern = Regex::new(&pattern).expect("case format patterns are valid");
+        CaseScanner {
+            options,
+            pattern,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Checks if a file should be scanned
+    pub fn should_process(&self, path: &Path) -> bool {
+        let filter = &self.options.filter;
+        self.options.fs.is_file(path)
+            && filter.hidden_skip(path).is_none()
+            && filter.dir_skip(path).is_none()
+            && filter
+                .extension_skip(path, &self.options.file_extensions)
+                .or_else(|| filter.skip_reason(path))
+                .is_none()
+    }
+
+    /// Counts the identifiers of each format in a string
+    pub fn count_str(&self, content: &str) -> CaseCounts {
+        let mut counts = CaseCounts::new();
+        if !self.options.formats.iter().any(|format| format.may_match(content)) {
+            return counts;
+        }
+        for caps in self.pattern.captures_iter(content) {
+            let Some(n) = (1..caps.len()).find(|&n| caps.get(n).is_some()) else {
+                continue;
+            };
+            *counts.entry(self.options.formats[n - 1]).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Counts the identifiers of each format in a file
+    ///
+    /// Files that aren't valid UTF-8 count nothing.
+    pub fn count_file(&self, path: &Path) -> crate::Result<CaseCounts> {
+        let bytes = self.options.fs.read(path)?;
+        match String::from_utf8(bytes) {
+            Ok(content) => Ok(self.count_str(&content)),
+            Err(_) => Ok(CaseCounts::new()),
+        }
+    }
+
+    /// Scans a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<CaseStats> {
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+        let files: Vec<PathBuf> = files.into_iter().filter(|f| self.should_process(f)).collect();
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.count_file(file)
+        });
+
+        let mut stats = CaseStats {
+            files_scanned: files.len(),
+            ..CaseStats::default()
+        };
+        for (file, result) in files.iter().zip(results) {
+            let counts = result?;
+            if counts.is_empty() {
+                continue;
+            }
+            let extension = file
+                .extension()
+                .map(|ext| format!(".{}", ext.to_string_lossy()))
+                .unwrap_or_default();
+            let directory = file.parent().unwrap_or(Path::new("")).to_path_buf();
+
+            add_counts(&mut stats.total, &counts);
+            add_counts(stats.by_extension.entry(extension).or_default(), &counts);
+            add_counts(stats.by_directory.entry(directory).or_default(), &counts);
+        }
+        Ok(stats)
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_count_str() {
+        let scanner = CaseScanner::new(StatsOptions::default());
+        let counts = scanner.count_str("user_name = getUserName(MAX_RETRIES, user_id)\nname = 1\n");
+        assert_eq!(counts.get(&CaseFormat::SnakeCase), Some(&2));
+        assert_eq!(counts.get(&CaseFormat::CamelCase), Some(&1));
+        assert_eq!(counts.get(&CaseFormat::ScreamingSnakeCase), Some(&1));
+        assert_eq!(counts.get(&CaseFormat::PascalCase), None);
+
+        // Member accesses and paths aren't dot.case or path/case by default
+        let counts = scanner.count_str("path = os.path.join(self.base_dir, 'src/lib')\n");
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts.get(&CaseFormat::SnakeCase), Some(&1));
+
+        // Once asked for, an identifier still counts under a single format
+        let scanner = CaseScanner::new(StatsOptions {
+            formats: CaseFormat::ALL.to_vec(),
+            ..StatsOptions::default()
+        });
+        let counts = scanner.count_str("key = config.value/name\n");
+        assert_eq!(counts.get(&CaseFormat::DotCase), Some(&1));
+        assert_eq!(counts.get(&CaseFormat::PathCase), None);
+    }
+
+    #[test]
+    fn test_process() {
+        let test_dir = std::env::temp_dir().join("refmt_stats_process");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(test_dir.join("src")).unwrap();
+        fs::write(test_dir.join("src/a.py"), "user_name = userId\n").unwrap();
+        fs::write(test_dir.join("src/b.js"), "const userName = firstName;\n").unwrap();
+        fs::write(test_dir.join("c.py"), "print('no identifiers')\n").unwrap();
+        fs::write(test_dir.join("d.xyz"), "user_name\n").unwrap();
+
+        let stats = CaseScanner::new(StatsOptions::default()).process(&test_dir).unwrap();
+        assert_eq!(stats.files_scanned, 3);
+        assert_eq!(stats.total.get(&CaseFormat::CamelCase), Some(&3));
+        assert_eq!(stats.total.get(&CaseFormat::SnakeCase), Some(&1));
+        assert_eq!(stats.by_extension[".py"].len(), 2);
+        assert_eq!(stats.by_extension[".js"].get(&CaseFormat::CamelCase), Some(&2));
+        assert_eq!(stats.by_directory.len(), 1);
+        assert_eq!(stats.share(CaseFormat::CamelCase), 0.75);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+}
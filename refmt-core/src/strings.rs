@@ -0,0 +1,558 @@
+//! String literal quote style
+//!
+//! Converts JavaScript/TypeScript and Python string literals between single and
+//! double quotes, re-escaping their contents. A literal keeps its quotes when
+//! switching would need more escapes than it has now (`"don't"` stays as is), and
+//! docstrings, template literals, raw strings and f-strings with quotes inside are
+//! left alone.
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::filter::FilterOptions;
+use crate::parallel;
+use crate::report::{report, EventSink, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+/// Quote character for string literals
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StringQuote {
+    /// `'text'`
+    Single,
+    /// `"text"`
+    Double,
+}
+
+impl StringQuote {
+    fn char(self) -> char {
+        match self {
+            StringQuote::Single => '\'',
+            StringQuote::Double => '"',
+        }
+    }
+}
+
+/// Options for string quote conversion
+#[derive(Debug, Clone)]
+pub struct StringQuoteOptions {
+    /// Quote character to convert literals to
+    pub target: StringQuote,
+    /// File extensions to process
+    pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
+    /// Process directories recursively
+    pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
+    /// Dry run mode (don't modify files)
+    pub dry_run: bool,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
+}
+
+impl Default for StringQuoteOptions {
+    fn default() -> Self {
+        StringQuoteOptions {
+            target: StringQuote::Double,
+            // JSX text isn't parsed, so an apostrophe in it would look like a string;
+            // .jsx and .tsx have to be asked for explicitly
+            file_extensions: [".py", ".pyi", ".js", ".mjs", ".cjs", ".ts", ".mts", ".cts"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            filter: FilterOptions::default(),
+            recursive: true,
+            walk: WalkOptions::default(),
+            dry_run: false,
+            fs: vfs::real(),
+            events: EventSink::default(),
+        }
+    }
+}
+
+/// Languages with interchangeable string quotes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Language {
+    Python,
+    JavaScript,
+}
+
+impl Language {
+    fn from_path(path: &Path) -> Option<Language> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "py" | "pyi" => Some(Language::Python),
+            "js" | "mjs" | "cjs" | "jsx" | "ts" | "mts" | "cts" | "tsx" => Some(Language::JavaScript),
+            _ => None,
+        }
+    }
+}
+
+/// Converts the quote style of string literals
+pub struct StringQuoteConverter {
+    options: StringQuoteOptions,
+    walker: Walker,
+}
+
+impl StringQuoteConverter {
+    /// Creates a new converter with the given options
+    pub fn new(options: StringQuoteOptions) -> Self {
+        StringQuoteConverter {
+            options,
+            walker: Walker::default(),
+        }
+    }
+
+    /// Creates a converter with default options
+    pub fn with_defaults() -> Self {
+        StringQuoteConverter {
+            options: StringQuoteOptions::default(),
+            walker: Walker::default(),
+        }
+    }
+
+    /// Checks if a file should be processed
+    pub fn should_process(&self, path: &Path) -> bool {
+        self.options.fs.is_file(path) && self.accepts(path)
+    }
+
+    /// Checks the path alone against the filters, without touching the filesystem
+    pub fn accepts(&self, path: &Path) -> bool {
+        self.options.filter.path_skip(path, &self.options.file_extensions).is_none()
+            && Language::from_path(path).is_some()
+    }
+
+    /// Converts string literals in source code, returning the new content and the
+    /// number of literals changed
+    ///
+    /// The language is taken from `path`'s extension; content in other languages is
+    /// returned borrowed and unchanged.
+    pub fn convert_str<'a>(&self, content: &'a str, path: &Path) -> (Cow<'a, str>, usize) {
+        match Language::from_path(path) {
+            Some(language) => self.convert_source(content, language),
+            None => (Cow::Borrowed(content), 0),
+        }
+    }
+
+    fn convert_source<'a>(&self, content: &'a str, language: Language) -> (Cow<'a, str>, usize) {
+        let bytes = content.as_bytes();
+        let mut out = String::new();
+        let mut copied = 0;
+        let mut changes = 0;
+        let mut i = 0;
+        // Whether a `/` here would start a regex literal rather than divide
+        let mut regex_allowed = true;
+
+        while i < bytes.len() {
+            let rest = &bytes[i..];
+            let c = bytes[i];
+
+            if language == Language::Python && c == b'#' {
+                i = find(bytes, i, b'\n');
+                continue;
+            }
+            if language == Language::JavaScript && rest.starts_with(b"//") {
+                i = find(bytes, i, b'\n');
+                continue;
+            }
+            if language == Language::JavaScript && rest.starts_with(b"/*") {
+                i = bytes[i + 2..]
+                    .windows(2)
+                    .position(|w| w == b"*/")
+                    .map_or(bytes.len(), |p| i + 2 + p + 2);
+                continue;
+            }
+            if language == Language::JavaScript && c == b'/' && regex_allowed {
+                i = skip_regex(bytes, i);
+                regex_allowed = false;
+                continue;
+            }
+            if language == Language::JavaScript && c == b'`' {
+                i = skip_template(bytes, i);
+                regex_allowed = false;
+                continue;
+            }
+
+            if c == b'\'' || c == b'"' {
+                let end = self.convert_literal(content, i, "", &mut out, &mut copied, &mut changes);
+                i = end;
+                regex_allowed = false;
+                continue;
+            }
+
+            if c.is_ascii_alphabetic() || c == b'_' || c == b'$' {
+                let start = i;
+                while i < bytes.len() && is_ident_byte(bytes[i]) {
+                    i += 1;
+                }
+                let word = &content[start..i];
+
+                if language == Language::Python
+                    && i < bytes.len()
+                    && (bytes[i] == b'\'' || bytes[i] == b'"')
+                    && is_python_prefix(word)
+                {
+                    i = self.convert_literal(content, i, word, &mut out, &mut copied, &mut changes);
+                    regex_allowed = false;
+                } else {
+                    regex_allowed = matches!(
+                        word,
+                        "return" | "typeof" | "case" | "in" | "of" | "yield" | "await" | "void" | "delete"
+                    );
+                }
+                continue;
+            }
+
+            if !c.is_ascii_whitespace() {
+                regex_allowed = !matches!(c, b')' | b']' | b'}') && !c.is_ascii_digit();
+            }
+            i += 1;
+        }
+
+        if changes == 0 {
+            return (Cow::Borrowed(content), 0);
+        }
+        out.push_str(&content[copied..]);
+        (Cow::Owned(out), changes)
+    }
+
+    /// Converts the literal whose opening quote is at `start`, with Python string
+    /// `prefix`, returning the position after it
+    fn convert_literal(
+        &self,
+        content: &str,
+        start: usize,
+        prefix: &str,
+        out: &mut String,
+        copied: &mut usize,
+        changes: &mut usize,
+    ) -> usize {
+        let bytes = content.as_bytes();
+        let quote = bytes[start];
+
+        // Docstrings and other triple-quoted strings
+        if bytes[start..].starts_with(&[quote; 3]) {
+            let delimiter = [quote; 3];
+            return bytes[start + 3..]
+                .windows(3)
+                .position(|w| w == delimiter)
+                .map_or(bytes.len(), |p| start + 3 + p + 3);
+        }
+
+        let Some(end) = string_end(bytes, start, quote) else {
+            // Unterminated: leave the rest of the line alone
+            return find(bytes, start, b'\n');
+        };
+
+        let target = self.options.target.char();
+        if quote as char == target {
+            return end;
+        }
+
+        let body = &content[start + 1..end - 1];
+        let prefix = prefix.to_lowercase();
+        let verbatim = prefix.contains('r') || prefix.contains('f');
+        if let Some(converted) = requote(body, quote as char, target, verbatim) {
+            out.push_str(&content[*copied..start]);
+            out.push(target);
+            out.push_str(&converted);
+            out.push(target);
+            *copied = end;
+            *changes += 1;
+        }
+
+        end
+    }
+
+    /// Converts string literals in a single file
+    pub fn convert_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        let outcome = self.apply_to_file(path)?;
+        self.report(path, outcome);
+        Ok(outcome)
+    }
+
+    /// Converts a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        if !self.should_process(path) {
+            return Ok(FileOutcome::unchanged());
+        }
+
+        let content = vfs::read_to_string(self.options.fs.as_ref(), path)?;
+        let (converted, changes) = self.convert_str(&content, path);
+
+        match converted {
+            Cow::Borrowed(_) => Ok(FileOutcome::unchanged()),
+            Cow::Owned(converted) => {
+                if !self.options.dry_run {
+                    self.options.fs.write(path, converted.as_bytes())?;
+                }
+                Ok(FileOutcome::changed(changes))
+            }
+        }
+    }
+
+    fn report(&self, path: &Path, outcome: FileOutcome) {
+        if !outcome.changed {
+            return;
+        }
+
+        self.options.events.file_changed(path, outcome.changes, self.options.dry_run);
+        if self.options.dry_run {
+            report!(
+                "Would convert {} string literals in '{}'",
+                outcome.changes,
+                path.display()
+            );
+        } else {
+            report!(
+                "Converted {} string literals in '{}'",
+                outcome.changes,
+                path.display()
+            );
+        }
+    }
+
+    /// Processes a directory or file
+    pub fn process(&self, path: &Path) -> crate::Result<(usize, usize)> {
+        let mut total_files = 0;
+        let mut total_changes = 0;
+
+        let files = if self.options.fs.is_file(path) {
+            vec![path.to_path_buf()]
+        } else if self.options.fs.is_dir(path) {
+            self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let results = parallel::map_files(self.options.fs.as_ref(), &files, |file| {
+            self.apply_to_file(file)
+        });
+
+        // Report in path order once the parallel work is done
+        for (file_path, result) in files.iter().zip(results) {
+            let outcome = result?;
+            self.report(file_path, outcome);
+            if outcome.changed {
+                total_files += 1;
+                total_changes += outcome.changes;
+            }
+        }
+
+        Ok((total_files, total_changes))
+    }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+}
+
+/// Re-escapes a literal body from `from` quotes to `to` quotes
+///
+/// Returns `None` if the new quotes would need more escapes than the old ones, or, for
+/// raw strings and f-strings (`verbatim`), if the body contains any quote at all.
+fn requote(body: &str, from: char, to: char, verbatim: bool) -> Option<String> {
+    if verbatim {
+        return if body.contains(['\'', '"']) {
+            None
+        } else {
+            Some(body.to_string())
+        };
+    }
+
+    let mut escaped_from = 0;
+    let mut bare_to = 0;
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if chars.next() == Some(from) {
+                escaped_from += 1;
+            }
+        } else if c == to {
+            bare_to += 1;
+        }
+    }
+    if bare_to > escaped_from {
+        return None;
+    }
+
+    let mut converted = String::with_capacity(body.len() + bare_to);
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(next) if next == from => converted.push(next),
+                Some(next) => {
+                    converted.push('\\');
+                    converted.push(next);
+                }
+                None => converted.push('\\'),
+            }
+        } else if c == to {
+            converted.push('\\');
+            converted.push(c);
+        } else {
+            converted.push(c);
+        }
+    }
+    Some(converted)
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$'
+}
+
+/// Whether `word` is a Python string prefix (`r`, `b`, `f`, `u`, `rb`, ...)
+fn is_python_prefix(word: &str) -> bool {
+    matches!(
+        word.to_lowercase().as_str(),
+        "r" | "u" | "b" | "f" | "br" | "rb" | "fr" | "rf"
+    )
+}
+
+/// Position of the next `needle` at or after `from`, or the end of input
+fn find(bytes: &[u8], from: usize, needle: u8) -> usize {
+    bytes[from..]
+        .iter()
+        .position(|&b| b == needle)
+        .map_or(bytes.len(), |p| from + p)
+}
+
+/// Position after the closing quote of the string starting at `start`, or `None` if it
+/// isn't closed on the same line
+fn string_end(bytes: &[u8], start: usize, quote: u8) -> Option<usize> {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'\n' => return None,
+            b if b == quote => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Skips a template literal, including strings nested in its `${...}` expressions
+fn skip_template(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    let mut depth = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'`' if depth == 0 => return i + 1,
+            b'$' if bytes.get(i + 1) == Some(&b'{') => {
+                depth += 1;
+                i += 2;
+            }
+            b'{' if depth > 0 => {
+                depth += 1;
+                i += 1;
+            }
+            b'}' if depth > 0 => {
+                depth -= 1;
+                i += 1;
+            }
+            quote @ (b'\'' | b'"') if depth > 0 => {
+                i = string_end(bytes, i, quote).unwrap_or(i + 1);
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Skips a regex literal, including `/` inside character classes
+fn skip_regex(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    let mut in_class = false;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'\n' => return i,
+            b'[' => {
+                in_class = true;
+                i += 1;
+            }
+            b']' => {
+                in_class = false;
+                i += 1;
+            }
+            b'/' if !in_class => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(content: &str, file: &str, target: StringQuote) -> String {
+        let options = StringQuoteOptions {
+            target,
+            ..StringQuoteOptions::default()
+        };
+        StringQuoteConverter::new(options)
+            .convert_str(content, Path::new(file))
+            .0
+            .into_owned()
+    }
+
+    #[test]
+    fn test_python_to_double() {
+        let content = concat!(
+            "def f():\n",
+            "    '''Docstring with 'quotes'.'''\n",
+            "    a = 'plain' + b'bytes' + u'text'\n",
+            "    b = 'it\\'s' + 'say \"hi\"'  # 'comment'\n",
+            "    c = r'\\d+' + f'{x}' + f'{d[\"k\"]}'\n",
+        );
+        assert_eq!(
+            convert(content, "a.py", StringQuote::Double),
+            concat!(
+                "def f():\n",
+                "    '''Docstring with 'quotes'.'''\n",
+                "    a = \"plain\" + b\"bytes\" + u\"text\"\n",
+                "    b = \"it's\" + 'say \"hi\"'  # 'comment'\n",
+                "    c = r\"\\d+\" + f\"{x}\" + f'{d[\"k\"]}'\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_javascript_to_single() {
+        let content = concat!(
+            "const a = \"x\" + \"don't\" + `t ${\"y\"}`; // \"c\"\n",
+            "const re = /\"[^\"]*\"/g, b = \"say \\\"hi\\\"\";\n",
+        );
+        assert_eq!(
+            convert(content, "a.ts", StringQuote::Single),
+            concat!(
+                "const a = 'x' + \"don't\" + `t ${\"y\"}`; // \"c\"\n",
+                "const re = /\"[^\"]*\"/g, b = 'say \"hi\"';\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_unchanged_is_borrowed() {
+        let converter = StringQuoteConverter::with_defaults();
+        let (result, changes) = converter.convert_str("x = \"a\" / 'it\"s'\n", Path::new("a.js"));
+        assert!(matches!(result, Cow::Borrowed(_)));
+        assert_eq!(changes, 0);
+    }
+}
@@ -0,0 +1,725 @@
+//! Filesystem abstraction
+//!
+//! Every transformer takes an [`Fs`] in its options (the
+//! [`CaseConverter`](crate::CaseConverter) through `set_fs`) and does all its reads,
+//! writes, renames and walks through it. [`RealFs`] is the default; [`MemoryFs`] keeps
+//! files in a map, so tests run without temp directories and can't interfere with each
+//! other.
+//!
+//! Reads, writes and renames that fail transiently (a file locked by an editor or
+//! virus scanner, a network share hiccup) can be retried by giving the options a
+//! [`RetryFs`] around the local filesystem, with the [`RetryPolicy`] to follow.
+//!
+//! [`RealFs`] rewrites files in place, so they keep their owner; it also restores
+//! their permissions and, if its [`PreservePolicy`] asks for it, their modification
+//! time, so build systems don't see rewritten files as newer than their outputs.
+//! Given a [`Journal`], it records its writes and renames for `refmt undo`.
+//!
+//! On Windows, [`RealFs`] passes paths longer than `MAX_PATH` or with a component
+//! ending in a dot or space in their `\\?\` extended-length form (see [`long_path`]).
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::journal::Journal;
+use crate::walker::{self, Walk, WalkOptions};
+
+/// What the transformers need to know about a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata {
+    /// A regular file
+    pub is_file: bool,
+    /// A directory
+    pub is_dir: bool,
+    /// Creation time, or the modification time where creation isn't recorded
+    pub created: Option<SystemTime>,
+    /// Size in bytes
+    pub len: u64,
+}
+
+/// File operations used by the transformers
+pub trait Fs: fmt::Debug + Send + Sync {
+    /// Reads a whole file
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Replaces a file's content, creating it if needed
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+
+    /// Renames a file, replacing `to` if it exists
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Looks up a path
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    /// Lists the files under `root`, sorted, like [`walker::collect_files`]
+    fn walk(&self, root: &Path, recursive: bool, options: WalkOptions) -> crate::Result<Walk>;
+
+    /// Whether two paths name the same file, e.g. differing only in case on a
+    /// case-insensitive filesystem
+    fn same_file(&self, a: &Path, b: &Path) -> bool {
+        a == b
+    }
+
+    /// Whether `path` is a regular file
+    fn is_file(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok_and(|m| m.is_file)
+    }
+
+    /// Whether `path` is a directory
+    fn is_dir(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok_and(|m| m.is_dir)
+    }
+
+    /// Whether `path` exists
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+}
+
+/// How often reads, writes and renames are attempted before giving up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total; 1 means no retries
+    pub attempts: u32,
+    /// Pause before the first retry, doubled before each further one
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            attempts: 1,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Runs `op` until it succeeds, fails with a permanent error, or runs out of
+    /// attempts
+    pub fn run<T>(&self, mut op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+        let mut backoff = self.backoff;
+        for _ in 1..self.attempts {
+            match op() {
+                Err(e) if is_transient(&e) => {
+                    thread::sleep(backoff);
+                    backoff = backoff.saturating_mul(2);
+                }
+                result => return result,
+            }
+        }
+        op()
+    }
+}
+
+/// Whether retrying may help; a missing file or invalid content won't change
+fn is_transient(error: &io::Error) -> bool {
+    !matches!(
+        error.kind(),
+        io::ErrorKind::NotFound
+            | io::ErrorKind::AlreadyExists
+            | io::ErrorKind::InvalidInput
+            | io::ErrorKind::InvalidData
+            | io::ErrorKind::Unsupported
+    )
+}
+
+/// File attributes [`RealFs`] restores after rewriting a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreservePolicy {
+    /// Restore the permission bits
+    pub permissions: bool,
+    /// Restore the modification time
+    pub mtime: bool,
+}
+
+impl Default for PreservePolicy {
+    fn default() -> Self {
+        PreservePolicy {
+            permissions: true,
+            mtime: false,
+        }
+    }
+}
+
+/// Replaces a file's content, then restores the attributes `policy` names
+fn write_preserving(path: &Path, contents: &[u8], policy: PreservePolicy) -> io::Result<()> {
+    let original = if policy.permissions || policy.mtime {
+        fs::metadata(path).ok()
+    } else {
+        None
+    };
+    fs::write(path, contents)?;
+
+    let Some(original) = original else {
+        return Ok(());
+    };
+    if policy.permissions && fs::metadata(path)?.permissions() != original.permissions() {
+        fs::set_permissions(path, original.permissions())?;
+    }
+    if policy.mtime {
+        fs::File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(original.modified()?)?;
+    }
+    Ok(())
+}
+
+/// Reads a whole file through `fs` as UTF-8 text, like [`fs::read_to_string`]
+pub(crate) fn read_to_string(fs: &dyn Fs, path: &Path) -> io::Result<String> {
+    String::from_utf8(fs.read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// The default [`Fs`] for options structs: the local filesystem, without retries
+pub fn real() -> Arc<dyn Fs> {
+    Arc::new(RealFs::default())
+}
+
+/// Retries the reads, writes and renames of another [`Fs`] on transient errors
+#[derive(Debug)]
+pub struct RetryFs {
+    inner: Arc<dyn Fs>,
+    policy: RetryPolicy,
+}
+
+impl RetryFs {
+    /// Wraps `inner`
+    pub fn new(inner: Arc<dyn Fs>, policy: RetryPolicy) -> Self {
+        RetryFs { inner, policy }
+    }
+}
+
+impl Fs for RetryFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.policy.run(|| self.inner.read(path))
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.policy.run(|| self.inner.write(path, contents))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.policy.run(|| self.inner.rename(from, to))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.inner.metadata(path)
+    }
+
+    fn walk(&self, root: &Path, recursive: bool, options: WalkOptions) -> crate::Result<Walk> {
+        self.inner.walk(root, recursive, options)
+    }
+
+    fn same_file(&self, a: &Path, b: &Path) -> bool {
+        self.inner.same_file(a, b)
+    }
+}
+
+/// Longest path the Win32 API accepts without the `\\?\` prefix, less the room it
+/// reserves for an 8.3 file name in directory paths
+const MAX_PATH: usize = 260 - 12;
+
+/// The path to hand to the OS for `path`
+///
+/// On Windows, paths of `MAX_PATH` characters or more, and paths with a component
+/// ending in a dot or space (which Win32 would strip), are made absolute and given the
+/// `\\?\` extended-length prefix. Elsewhere `path` is returned as is.
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    {
+        let Some(text) = path.to_str() else {
+            return Cow::Borrowed(path);
+        };
+        if !needs_verbatim(text) {
+            return Cow::Borrowed(path);
+        }
+        let absolute = if path.is_absolute() {
+            Cow::Borrowed(path)
+        } else {
+            match std::env::current_dir() {
+                Ok(dir) => Cow::Owned(dir.join(path)),
+                Err(_) => return Cow::Borrowed(path),
+            }
+        };
+        match absolute.to_str().and_then(verbatim) {
+            Some(verbatim) => Cow::Owned(PathBuf::from(verbatim)),
+            None => Cow::Borrowed(path),
+        }
+    }
+    #[cfg(not(windows))]
+    {
+        Cow::Borrowed(path)
+    }
+}
+
+/// Whether Win32 would reject or alter a path unless it is given in verbatim form
+#[cfg_attr(not(windows), allow(dead_code))]
+fn needs_verbatim(path: &str) -> bool {
+    if path.starts_with(r"\\?\") {
+        return false;
+    }
+    path.chars().count() >= MAX_PATH
+        || path
+            .split(['\\', '/'])
+            .any(|name| name != "." && name != ".." && name.ends_with(['.', ' ']))
+}
+
+/// The `\\?\` form of an absolute Windows path (`C:\dir`, `\\server\share\dir`)
+///
+/// Verbatim paths skip all normalization, so separators are made backslashes and
+/// `.` and `..` components are resolved here. Returns `None` for other paths.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn verbatim(path: &str) -> Option<String> {
+    let path = path.replace('/', "\\");
+    // The drive, or the server and share, can't be climbed out of
+    let (prefix, rest, root_names) = if let Some(unc) = path.strip_prefix(r"\\") {
+        if unc.starts_with(['?', '.']) {
+            return None;
+        }
+        (r"\\?\UNC\", unc, 2)
+    } else if path.get(1..3) == Some(":\\") {
+        (r"\\?\", path.as_str(), 1)
+    } else {
+        return None;
+    };
+
+    let mut names: Vec<&str> = Vec::new();
+    for name in rest.split('\\') {
+        match name {
+            "" | "." => {}
+            ".." => {
+                if names.len() > root_names {
+                    names.pop();
+                }
+            }
+            _ => names.push(name),
+        }
+    }
+    Some(format!("{}{}", prefix, names.join("\\")))
+}
+
+/// The local filesystem
+#[derive(Debug, Clone, Default)]
+pub struct RealFs {
+    /// File attributes restored after rewriting a file
+    pub preserve: PreservePolicy,
+    /// Where rewrites and renames are recorded for `refmt undo`, if anywhere
+    pub journal: Option<Arc<Journal>>,
+}
+
+impl RealFs {
+    /// The local filesystem, restoring the attributes `preserve` names on rewrites
+    pub fn new(preserve: PreservePolicy) -> Self {
+        RealFs {
+            preserve,
+            journal: None,
+        }
+    }
+
+    /// Records the rewrites and renames in `journal`
+    pub fn with_journal(mut self, journal: Arc<Journal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+}
+
+impl Fs for RealFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(long_path(path))
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let long = long_path(path);
+        if let Some(journal) = &self.journal {
+            journal.record_write(path, fs::read(&long).ok().as_deref(), contents)?;
+        }
+        write_preserving(&long, contents, self.preserve)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(long_path(from), long_path(to))?;
+        match &self.journal {
+            Some(journal) => journal.record_rename(from, to),
+            None => Ok(()),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let metadata = fs::metadata(long_path(path))?;
+        Ok(Metadata {
+            is_file: metadata.is_file(),
+            is_dir: metadata.is_dir(),
+            created: metadata.created().or_else(|_| metadata.modified()).ok(),
+            len: metadata.len(),
+        })
+    }
+
+    fn walk(&self, root: &Path, recursive: bool, options: WalkOptions) -> crate::Result<Walk> {
+        walker::collect_files(root, recursive, options)
+    }
+
+    fn same_file(&self, a: &Path, b: &Path) -> bool {
+        match (a.canonicalize(), b.canonicalize()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MemoryFile {
+    contents: Vec<u8>,
+    created: SystemTime,
+}
+
+/// Files held in memory; directories exist implicitly as ancestors of files
+#[derive(Debug, Default)]
+pub struct MemoryFs {
+    files: Mutex<BTreeMap<PathBuf, MemoryFile>>,
+}
+
+impl MemoryFs {
+    /// Creates an empty filesystem
+    pub fn new() -> Self {
+        MemoryFs::default()
+    }
+
+    /// Adds or replaces a file, created now
+    pub fn add_file(&self, path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) {
+        self.add_file_created(path, contents, SystemTime::now());
+    }
+
+    /// Adds or replaces a file with the given creation time
+    pub fn add_file_created(
+        &self,
+        path: impl Into<PathBuf>,
+        contents: impl Into<Vec<u8>>,
+        created: SystemTime,
+    ) {
+        let file = MemoryFile {
+            contents: contents.into(),
+            created,
+        };
+        self.lock().insert(path.into(), file);
+    }
+
+    /// The content of a file as text, if it exists and is UTF-8
+    pub fn read_to_string(&self, path: impl AsRef<Path>) -> Option<String> {
+        let files = self.lock();
+        let file = files.get(path.as_ref())?;
+        String::from_utf8(file.contents.clone()).ok()
+    }
+
+    /// All file paths, sorted
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.lock().keys().cloned().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<PathBuf, MemoryFile>> {
+        self.files.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("'{}' does not exist", path.display()),
+    )
+}
+
+impl Fs for MemoryFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let files = self.lock();
+        files
+            .get(path)
+            .map(|file| file.contents.clone())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let mut files = self.lock();
+        match files.get_mut(path) {
+            Some(file) => file.contents = contents.to_vec(),
+            None => {
+                let file = MemoryFile {
+                    contents: contents.to_vec(),
+                    created: SystemTime::now(),
+                };
+                files.insert(path.to_path_buf(), file);
+            }
+        }
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut files = self.lock();
+        let file = files.remove(from).ok_or_else(|| not_found(from))?;
+        files.insert(to.to_path_buf(), file);
+        Ok(())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let files = self.lock();
+        if let Some(file) = files.get(path) {
+            return Ok(Metadata {
+                is_file: true,
+                is_dir: false,
+                created: Some(file.created),
+                len: file.contents.len() as u64,
+            });
+        }
+        if files.keys().any(|file| file.starts_with(path)) {
+            return Ok(Metadata {
+                is_file: false,
+                is_dir: true,
+                created: None,
+                len: 0,
+            });
+        }
+        Err(not_found(path))
+    }
+
+    fn walk(&self, root: &Path, recursive: bool, options: WalkOptions) -> crate::Result<Walk> {
+        let files = self
+            .lock()
+            .keys()
+            .filter(|path| {
+                let Ok(relative) = path.strip_prefix(root) else {
+                    return false;
+                };
+                let depth = relative.components().count();
+                let max_depth = if recursive {
+                    options.max_depth
+                } else {
+                    Some(1)
+                };
+                depth > 0 && max_depth.is_none_or(|max| depth <= max)
+            })
+            .cloned()
+            .collect();
+        Ok(Walk {
+            files,
+            errors: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_fs() {
+        let fs = MemoryFs::new();
+        fs.add_file("root/a.txt", "a");
+        fs.add_file("root/sub/b.txt", "b");
+        fs.add_file("rootless.txt", "c");
+
+        assert!(fs.is_dir(Path::new("root/sub")));
+        assert!(fs.is_file(Path::new("root/a.txt")));
+        assert!(!fs.exists(Path::new("root/missing.txt")));
+        assert_eq!(
+            fs.walk(Path::new("root"), true, WalkOptions::default())
+                .unwrap()
+                .files,
+            vec![PathBuf::from("root/a.txt"), PathBuf::from("root/sub/b.txt")]
+        );
+        assert_eq!(
+            fs.walk(Path::new("root"), false, WalkOptions::default())
+                .unwrap()
+                .files,
+            vec![PathBuf::from("root/a.txt")]
+        );
+        let shallow = WalkOptions {
+            max_depth: Some(1),
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            fs.walk(Path::new("root"), true, shallow).unwrap().files,
+            vec![PathBuf::from("root/a.txt")]
+        );
+
+        fs.rename(Path::new("root/a.txt"), Path::new("root/c.txt")).unwrap();
+        fs.write(Path::new("root/c.txt"), b"changed").unwrap();
+        assert_eq!(fs.read_to_string("root/c.txt").unwrap(), "changed");
+        assert!(fs.read(Path::new("root/a.txt")).is_err());
+    }
+
+    /// Fails the first `failures` writes with a transient error
+    #[derive(Debug)]
+    struct Flaky {
+        inner: MemoryFs,
+        failures: Mutex<u32>,
+        calls: Mutex<u32>,
+    }
+
+    impl Flaky {
+        fn new(failures: u32) -> Arc<Self> {
+            Arc::new(Flaky {
+                inner: MemoryFs::new(),
+                failures: Mutex::new(failures),
+                calls: Mutex::new(0),
+            })
+        }
+
+        fn calls(&self) -> u32 {
+            *self.calls.lock().unwrap()
+        }
+    }
+
+    impl Fs for Flaky {
+        fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            *self.calls.lock().unwrap() += 1;
+            self.inner.read(path)
+        }
+
+        fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+            *self.calls.lock().unwrap() += 1;
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied, "locked"));
+            }
+            self.inner.write(path, contents)
+        }
+
+        fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+            self.inner.rename(from, to)
+        }
+
+        fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+            self.inner.metadata(path)
+        }
+
+        fn walk(&self, root: &Path, recursive: bool, options: WalkOptions) -> crate::Result<Walk> {
+            self.inner.walk(root, recursive, options)
+        }
+    }
+
+    #[test]
+    fn test_retry_fs() {
+        let policy = RetryPolicy {
+            attempts: 3,
+            backoff: Duration::from_millis(1),
+        };
+        let path = Path::new("a.txt");
+
+        let flaky = Flaky::new(2);
+        RetryFs::new(flaky.clone(), policy).write(path, b"a").unwrap();
+        assert_eq!(flaky.calls(), 3);
+        assert_eq!(flaky.inner.read_to_string(path).unwrap(), "a");
+
+        let flaky = Flaky::new(3);
+        let error = RetryFs::new(flaky.clone(), policy).write(path, b"a").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(flaky.calls(), 3);
+
+        // A missing file stays missing: no retries
+        let flaky = Flaky::new(0);
+        assert!(RetryFs::new(flaky.clone(), policy).read(path).is_err());
+        assert_eq!(flaky.calls(), 1);
+    }
+
+    #[test]
+    fn test_write_preserving() {
+        let test_dir = std::env::temp_dir().join("refmt_test_write_preserving");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+        let path = test_dir.join("a.txt");
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+
+        let touch = |path: &Path| {
+            fs::write(path, "before").unwrap();
+            fs::File::options().write(true).open(path).unwrap().set_modified(old).unwrap();
+        };
+
+        touch(&path);
+        let policy = PreservePolicy {
+            permissions: true,
+            mtime: true,
+        };
+        RealFs::new(policy).write(&path, b"after").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "after");
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), old);
+
+        touch(&path);
+        write_preserving(&path, b"after", PreservePolicy::default()).unwrap();
+        assert_ne!(fs::metadata(&path).unwrap().modified().unwrap(), old);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+            write_preserving(&path, b"again", PreservePolicy::default()).unwrap();
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o750);
+        }
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_verbatim_paths() {
+        assert!(!needs_verbatim(r"C:\src\main.rs"));
+        assert!(needs_verbatim(r"C:\src\notes.\a.md"));
+        assert!(needs_verbatim("src/trailing /a.md"));
+        assert!(!needs_verbatim(r".\..\a.md"));
+        assert!(needs_verbatim(&format!(r"C:\{}", "d".repeat(MAX_PATH))));
+        assert!(!needs_verbatim(&format!(r"\\?\C:\{}", "d".repeat(MAX_PATH))));
+
+        assert_eq!(verbatim(r"C:\src\.\old\..\main.rs").unwrap(), r"\\?\C:\src\main.rs");
+        assert_eq!(verbatim("C:/a/b./c").unwrap(), r"\\?\C:\a\b.\c");
+        assert_eq!(verbatim(r"C:\..\a").unwrap(), r"\\?\C:\a");
+        assert_eq!(
+            verbatim(r"\\server\share\..\dir").unwrap(),
+            r"\\?\UNC\server\share\dir"
+        );
+        assert_eq!(verbatim(r"\\?\C:\a"), None);
+        assert_eq!(verbatim("relative/a"), None);
+    }
+
+    #[test]
+    fn test_long_paths() {
+        let root = std::env::temp_dir().join("refmt_vfs_long_paths");
+        let _ = fs::remove_dir_all(&root);
+        // Deeper than MAX_PATH, ending in a name close to the 255-byte limit
+        let mut dir = root.clone();
+        for i in 0..8 {
+            dir.push(format!("{}{}", i, "d".repeat(40)));
+        }
+        fs::create_dir_all(long_path(&dir)).unwrap();
+        let name = format!("{}.txt", "Near".repeat(62));
+        let file = dir.join(&name);
+        assert!(file.as_os_str().len() > 300);
+
+        let real = RealFs::default();
+        real.write(&file, b"myValue\n").unwrap();
+        assert!(real.is_file(&file));
+
+        let converter =
+            crate::CaseConverter::builder(crate::CaseFormat::CamelCase, crate::CaseFormat::SnakeCase)
+                .extensions(vec![".txt".to_string()])
+                .recursive(false)
+                .build()
+                .unwrap();
+        converter.process_file(&file, &dir).unwrap();
+        assert_eq!(real.read(&file).unwrap(), b"my_value\n");
+
+        let options = crate::RenameOptions {
+            case_transform: crate::CaseTransform::Lowercase,
+            ..crate::RenameOptions::default()
+        };
+        let renamed = crate::FileRenamer::new(options).process(&dir).unwrap();
+        assert_eq!(renamed, 1);
+        assert_eq!(real.read(&dir.join(name.to_lowercase())).unwrap(), b"my_value\n");
+
+        fs::remove_dir_all(long_path(&root)).unwrap();
+    }
+}
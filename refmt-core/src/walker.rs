@@ -0,0 +1,384 @@
+//! Shared directory traversal for the file transformers
+//!
+//! With the `parallel` feature, recursive walks read directories concurrently on the
+//! rayon thread pool (via jwalk); otherwise walkdir is used. Either way the returned
+//! paths are sorted, so processing order and reports are identical between the two.
+//!
+//! Entries that can't be read (permission errors, broken entries, symlink loops) are
+//! handled by the [`ErrorPolicy`] of the walk: ignored, returned alongside the files,
+//! or fatal. That policy, how deep recursive walks go and whether they follow symbolic
+//! links are up to the [`WalkOptions`] each transformer's options carry; a
+//! transformer's [`Walker`] keeps the errors of its walks for its caller.
+//! Paths excluded by a [`.refmtignore`](crate::ignore) are never returned.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::ignore::IgnoreRules;
+use crate::vfs::Fs;
+
+/// What to do with entries that can't be read while walking a tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Skip them silently
+    Ignore,
+    /// Skip them, and return them with the files
+    #[default]
+    Warn,
+    /// Fail the walk
+    Fail,
+}
+
+/// A path that couldn't be read, so the tree below it wasn't processed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraversalError {
+    /// The unreadable entry, or the walk root if the entry is unknown
+    pub path: PathBuf,
+    /// What went wrong
+    pub message: String,
+}
+
+impl fmt::Display for TraversalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}': {}", self.path.display(), self.message)
+    }
+}
+
+/// Limits of recursive walks, and what to do with what they can't read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WalkOptions {
+    /// Deepest level descended to; files directly in the root are at depth 1
+    pub max_depth: Option<usize>,
+    /// Descend into symlinked directories and include symlinked files; loops are
+    /// reported as unreadable entries
+    pub follow_symlinks: bool,
+    /// What to do with unreadable entries, and with files that fail to process
+    pub errors: ErrorPolicy,
+}
+
+/// The files found by a walk
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Walk {
+    /// The regular files, sorted
+    pub files: Vec<PathBuf>,
+    /// The entries that couldn't be read, under [`ErrorPolicy::Warn`]
+    pub errors: Vec<TraversalError>,
+}
+
+/// Walks trees for a transformer, keeping the entries its walks couldn't read until
+/// they are taken
+#[derive(Debug, Default)]
+pub struct Walker {
+    errors: Mutex<Vec<TraversalError>>,
+}
+
+impl Walker {
+    /// Lists the files under `root` through `fs`, keeping the unreadable entries
+    pub(crate) fn walk(
+        &self,
+        fs: &dyn Fs,
+        root: &Path,
+        recursive: bool,
+        options: WalkOptions,
+    ) -> crate::Result<Vec<PathBuf>> {
+        let walk = fs.walk(root, recursive, options)?;
+        self.errors
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(walk.errors);
+        Ok(walk.files)
+    }
+
+    /// Returns the entries kept since the last call
+    pub fn take_errors(&self) -> Vec<TraversalError> {
+        std::mem::take(&mut *self.errors.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Collects the regular files under `root`
+///
+/// Hidden files are included; filtering them is left to each transformer. Files
+/// excluded by `.refmtignore` are left out, and entries that cannot be read are
+/// handled according to the [`ErrorPolicy`] of `options`, which also limit
+/// recursive walks.
+pub fn collect_files(root: &Path, recursive: bool, options: WalkOptions) -> crate::Result<Walk> {
+    let mut errors = Vec::new();
+    let mut files = if recursive {
+        walk_recursive(root, options, &mut errors)
+    } else {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(root)? {
+            match entry {
+                Ok(entry) if entry.path().is_file() => files.push(entry.path()),
+                Ok(_) => {}
+                Err(e) => errors.push(TraversalError {
+                    path: root.to_path_buf(),
+                    message: e.to_string(),
+                }),
+            }
+        }
+        files
+    };
+
+    match options.errors {
+        ErrorPolicy::Ignore => errors.clear(),
+        ErrorPolicy::Warn => {}
+        ErrorPolicy::Fail => {
+            if let Some(first) = errors.first() {
+                return Err(std::io::Error::other(format!(
+                    "Cannot read {} path(s) under '{}', first {}",
+                    errors.len(),
+                    root.display(),
+                    first
+                ))
+                .into());
+            }
+        }
+    }
+
+    files.sort();
+    Ok(Walk {
+        files: remove_ignored(root, files),
+        errors,
+    })
+}
+
+/// Drops the files excluded by the `.refmtignore` files that apply to `root`
+fn remove_ignored(root: &Path, files: Vec<PathBuf>) -> Vec<PathBuf> {
+    // Ignore files above a relative root can only be found from its absolute path
+    let Ok(absolute_root) = std::path::absolute(root) else {
+        return files;
+    };
+    let absolute = |file: &Path| absolute_root.join(file.strip_prefix(root).unwrap_or(file));
+    let absolute_files: Vec<PathBuf> = files.iter().map(|file| absolute(file)).collect();
+
+    let rules = IgnoreRules::load(&absolute_root, &absolute_files);
+    if rules.is_empty() {
+        return files;
+    }
+    files
+        .into_iter()
+        .zip(absolute_files)
+        .filter(|(_, absolute)| !rules.is_ignored(absolute))
+        .map(|(file, _)| file)
+        .collect()
+}
+
+#[cfg(feature = "parallel")]
+fn walk_recursive(
+    root: &Path,
+    options: WalkOptions,
+    errors: &mut Vec<TraversalError>,
+) -> Vec<PathBuf> {
+    let error = |e: &jwalk::Error, path: &Path| TraversalError {
+        path: e.path().unwrap_or(path).to_path_buf(),
+        message: e.io_error().map_or_else(|| e.to_string(), |io| io.to_string()),
+    };
+
+    let mut files = Vec::new();
+    let walk = jwalk::WalkDir::new(root)
+        .skip_hidden(false)
+        .follow_links(options.follow_symlinks)
+        .max_depth(options.max_depth.unwrap_or(usize::MAX));
+    for entry in walk {
+        match entry {
+            Ok(entry) if entry.file_type().is_file() => files.push(entry.path()),
+            // jwalk yields unreadable directories, with the error attached
+            Ok(entry) => {
+                if let Some(e) = &entry.read_children_error {
+                    errors.push(error(e, &entry.path()));
+                }
+            }
+            Err(e) => errors.push(error(&e, root)),
+        }
+    }
+    files
+}
+
+#[cfg(not(feature = "parallel"))]
+fn walk_recursive(
+    root: &Path,
+    options: WalkOptions,
+    errors: &mut Vec<TraversalError>,
+) -> Vec<PathBuf> {
+    let walk = walkdir::WalkDir::new(root)
+        .follow_links(options.follow_symlinks)
+        .max_depth(options.max_depth.unwrap_or(usize::MAX));
+    let mut files = Vec::new();
+    for entry in walk {
+        match entry {
+            Ok(entry) if entry.file_type().is_file() => files.push(entry.into_path()),
+            Ok(_) => {}
+            Err(e) => errors.push(TraversalError {
+                path: e.path().unwrap_or(root).to_path_buf(),
+                message: e.io_error().map_or_else(|| e.to_string(), |io| io.to_string()),
+            }),
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_files_sorted() {
+        let test_dir = std::env::temp_dir().join("refmt_walker_sorted");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(test_dir.join("b/nested")).unwrap();
+        fs::create_dir_all(test_dir.join("a")).unwrap();
+        fs::create_dir_all(test_dir.join(".hidden")).unwrap();
+
+        for file in ["z.txt", "b/nested/deep.txt", "b/one.txt", "a/two.txt", ".hidden/h.txt"] {
+            fs::write(test_dir.join(file), "x").unwrap();
+        }
+
+        let files = collect_files(&test_dir, true, WalkOptions::default())
+            .unwrap()
+            .files;
+        let relative: Vec<_> = files
+            .iter()
+            .map(|p| p.strip_prefix(&test_dir).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            relative,
+            [".hidden/h.txt", "a/two.txt", "b/nested/deep.txt", "b/one.txt", "z.txt"]
+                .iter()
+                .map(PathBuf::from)
+                .collect::<Vec<_>>()
+        );
+
+        let top_level = collect_files(&test_dir, false, WalkOptions::default())
+            .unwrap()
+            .files;
+        assert_eq!(top_level, vec![test_dir.join("z.txt")]);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_refmtignore() {
+        let test_dir = std::env::temp_dir().join("refmt_walker_ignore");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(test_dir.join("project/third_party")).unwrap();
+        fs::create_dir_all(test_dir.join("project/api")).unwrap();
+        fs::write(test_dir.join(".refmtignore"), "third_party/\n").unwrap();
+        fs::write(test_dir.join("project/api/.refmtignore"), "*.pb.go\n").unwrap();
+        for file in ["third_party/lib.c", "api/user.pb.go", "api/user.go", "main.c"] {
+            fs::write(test_dir.join("project").join(file), "x").unwrap();
+        }
+
+        // The root's parent holds the first ignore file, the tree the second
+        let options = WalkOptions::default();
+        let files = collect_files(&test_dir.join("project"), true, options)
+            .unwrap()
+            .files;
+        let relative: Vec<_> = files
+            .iter()
+            .map(|p| p.strip_prefix(&test_dir).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            relative,
+            ["project/api/.refmtignore", "project/api/user.go", "project/main.c"]
+                .iter()
+                .map(PathBuf::from)
+                .collect::<Vec<_>>()
+        );
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_depth_and_symlinks() {
+        let test_dir = std::env::temp_dir().join("refmt_walker_depth");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(test_dir.join("tree/a/b")).unwrap();
+        fs::create_dir_all(test_dir.join("outside")).unwrap();
+        fs::write(test_dir.join("tree/top.txt"), "x").unwrap();
+        fs::write(test_dir.join("tree/a/mid.txt"), "x").unwrap();
+        fs::write(test_dir.join("tree/a/b/deep.txt"), "x").unwrap();
+        fs::write(test_dir.join("outside/linked.txt"), "x").unwrap();
+        std::os::unix::fs::symlink(test_dir.join("outside"), test_dir.join("tree/link")).unwrap();
+        // A loop back to the root
+        std::os::unix::fs::symlink(test_dir.join("tree"), test_dir.join("tree/a/up")).unwrap();
+
+        let root = test_dir.join("tree");
+        let walk = |max_depth, follow_symlinks| {
+            let options = WalkOptions {
+                max_depth,
+                follow_symlinks,
+                errors: ErrorPolicy::Ignore,
+            };
+            collect_files(&root, true, options)
+                .unwrap()
+                .files
+                .iter()
+                .map(|p| p.strip_prefix(&root).unwrap().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(walk(None, false), ["a/b/deep.txt", "a/mid.txt", "top.txt"]);
+        assert_eq!(walk(Some(2), false), ["a/mid.txt", "top.txt"]);
+        assert_eq!(
+            walk(None, true),
+            ["a/b/deep.txt", "a/mid.txt", "link/linked.txt", "top.txt"]
+        );
+        let options = WalkOptions {
+            max_depth: None,
+            follow_symlinks: true,
+            errors: ErrorPolicy::Warn,
+        };
+        let warned = collect_files(&root, true, options).unwrap();
+        assert!(warned.files.contains(&root.join("top.txt")));
+        assert!(!warned.errors.is_empty());
+        let options = WalkOptions {
+            errors: ErrorPolicy::Fail,
+            ..options
+        };
+        let error = collect_files(&root, true, options).unwrap_err();
+        assert!(error.to_string().contains("up"), "{}", error);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let test_dir = std::env::temp_dir().join("refmt_walker_unreadable");
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(test_dir.join("locked")).unwrap();
+        fs::write(test_dir.join("locked/a.txt"), "x").unwrap();
+        fs::write(test_dir.join("b.txt"), "x").unwrap();
+        fs::set_permissions(test_dir.join("locked"), fs::Permissions::from_mode(0o000)).unwrap();
+
+        // Root can read the directory anyway
+        if fs::read_dir(test_dir.join("locked")).is_err() {
+            let walk = |errors| {
+                let options = WalkOptions {
+                    errors,
+                    ..WalkOptions::default()
+                };
+                collect_files(&test_dir, true, options)
+            };
+            let ignored = walk(ErrorPolicy::Ignore).unwrap();
+            assert_eq!(ignored.files, vec![test_dir.join("b.txt")]);
+            assert!(ignored.errors.is_empty());
+
+            let warned = walk(ErrorPolicy::Warn).unwrap();
+            assert_eq!(warned.files, vec![test_dir.join("b.txt")]);
+            assert_eq!(warned.errors[0].path, test_dir.join("locked"));
+
+            let error = walk(ErrorPolicy::Fail).unwrap_err();
+            assert!(error.to_string().contains("locked"), "{}", error);
+        }
+
+        fs::set_permissions(test_dir.join("locked"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+}
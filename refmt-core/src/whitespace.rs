@@ -1,38 +1,153 @@
 //! Whitespace cleaning transformer
+//!
+//! Removes trailing whitespace and, optionally, re-indents lines (tabs to spaces,
+//! spaces to tabs, or a different indentation width) and makes files end with exactly
+//! one newline. In Markdown files, trailing spaces that make a hard line break are
+//! kept (or turned into a backslash) unless told otherwise.
 
-use std::fs;
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::path::Path;
-use walkdir::WalkDir;
+use std::sync::Arc;
+
+use crate::anchors::is_markdown;
+use crate::diff::unified_diff;
+use crate::eol::{lines_with_endings, split_bom};
+use crate::filter::{read_text, ExtensionSet, FilterOptions, SkipReason};
+use crate::git::{ChangedLines, LineRanges};
+use crate::markdown::hard_break_lines;
+use crate::parallel;
+use crate::progress::Progress;
+use crate::report::{report, EventSink, FailedFile, FailedFiles, FileOutcome};
+use crate::vfs::{self, Fs};
+use crate::walker::{TraversalError, WalkOptions, Walker};
+
+thread_local! {
+    /// Output buffer reused across files cleaned on the same thread
+    static OUTPUT_BUFFER: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Character used for indentation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndentStyle {
+    Spaces,
+    Tabs,
+}
+
+/// Options for re-indenting lines
+#[derive(Debug, Clone)]
+pub struct IndentOptions {
+    /// Character to indent with
+    pub style: IndentStyle,
+    /// Spaces per indentation level in the output
+    pub width: usize,
+    /// Spaces per indentation level in the input (`None` detects it per file)
+    pub source_width: Option<usize>,
+}
+
+impl Default for IndentOptions {
+    fn default() -> Self {
+        IndentOptions {
+            style: IndentStyle::Spaces,
+            width: 4,
+            source_width: None,
+        }
+    }
+}
+
+impl IndentOptions {
+    /// Rewrites each tab of the indentation as `width` spaces, like `expand -i`
+    pub fn expand_tabs(width: usize) -> Self {
+        IndentOptions {
+            style: IndentStyle::Spaces,
+            width: width.max(1),
+            source_width: Some(width.max(1)),
+        }
+    }
+
+    /// Rewrites each `width` spaces of the indentation as a tab, like `unexpand`;
+    /// narrower runs are kept as spaces
+    pub fn unexpand(width: usize) -> Self {
+        IndentOptions {
+            style: IndentStyle::Tabs,
+            width: width.max(1),
+            source_width: Some(width.max(1)),
+        }
+    }
+}
+
+/// What becomes of a Markdown hard line break: two or more trailing spaces ending a
+/// line the paragraph continues past
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HardBreaks {
+    /// Keep the break as exactly two spaces
+    #[default]
+    Keep,
+    /// Write the break as a trailing backslash
+    Backslash,
+    /// Trim the spaces like any other trailing whitespace
+    Remove,
+}
 
 /// Options for whitespace cleaning
 #[derive(Debug, Clone)]
 pub struct WhitespaceOptions {
     /// Remove trailing whitespace from lines
     pub remove_trailing: bool,
+    /// Re-indent lines (`None` leaves indentation alone)
+    pub indent: Option<IndentOptions>,
+    /// End a file whose last line has no line ending with one, like those of its other
+    /// lines (`\n` if it has none)
+    pub ensure_final_newline: bool,
+    /// Remove the blank lines at the end of a file
+    pub trim_trailing_blank_lines: bool,
+    /// What hard line breaks become in Markdown files (`.md`, `.qmd`, ...) when
+    /// trailing whitespace is removed
+    pub hard_breaks: HardBreaks,
+    /// Only touch these lines (`None` cleans every line); files without changed lines
+    /// are skipped
+    pub changed_lines: Option<ChangedLines>,
     /// File extensions to process
     pub file_extensions: Vec<String>,
+    /// Files to leave out even if their extension matches
+    pub filter: FilterOptions,
     /// Process directories recursively
     pub recursive: bool,
+    /// Depth limit and symlink handling of recursive walks
+    pub walk: WalkOptions,
     /// Dry run mode (don't modify files)
     pub dry_run: bool,
+    /// Print a unified diff of each change
+    pub show_diff: bool,
+    /// Color the diffs with ANSI escapes
+    pub color_diff: bool,
+    /// Receives progress events while a directory is processed
+    pub progress: Progress,
+    /// Filesystem the files are read from and written to
+    pub fs: Arc<dyn Fs>,
+    /// Receives a [`FileEvent`](crate::FileEvent) for every file changed
+    pub events: EventSink,
 }
 
 impl Default for WhitespaceOptions {
     fn default() -> Self {
         WhitespaceOptions {
             remove_trailing: true,
-            file_extensions: vec![
-                ".py", ".pyx", ".pxd", ".pxi",
-                ".c", ".h", ".cpp", ".hpp",
-                ".rs", ".go", ".java",
-                ".js", ".ts", ".jsx", ".tsx",
-                ".md", ".qmd", ".txt",
-            ]
-            .iter()
-            .map(|s| s.to_string())
-            .collect(),
+            indent: None,
+            ensure_final_newline: false,
+            trim_trailing_blank_lines: false,
+            hard_breaks: HardBreaks::Keep,
+            changed_lines: None,
+            file_extensions: ExtensionSet::Whitespace.defaults(),
+            filter: FilterOptions::default(),
             recursive: true,
+            walk: WalkOptions::default(),
             dry_run: false,
+            show_diff: false,
+            color_diff: false,
+            progress: Progress::default(),
+            fs: vfs::real(),
+            events: EventSink::default(),
         }
     }
 }
@@ -40,101 +155,338 @@ impl Default for WhitespaceOptions {
 /// Whitespace cleaner for removing trailing whitespace from files
 pub struct WhitespaceCleaner {
     options: WhitespaceOptions,
+    walker: Walker,
+    failed: FailedFiles,
 }
 
 impl WhitespaceCleaner {
     /// Creates a new whitespace cleaner with the given options
     pub fn new(options: WhitespaceOptions) -> Self {
-        WhitespaceCleaner { options }
+        WhitespaceCleaner {
+            options,
+            walker: Walker::default(),
+            failed: FailedFiles::default(),
+        }
     }
 
     /// Creates a cleaner with default options
     pub fn with_defaults() -> Self {
         WhitespaceCleaner {
             options: WhitespaceOptions::default(),
+            walker: Walker::default(),
+            failed: FailedFiles::default(),
         }
     }
 
     /// Checks if a file should be processed
-    fn should_process(&self, path: &Path) -> bool {
-        if !path.is_file() {
-            return false;
-        }
-
-        // Skip hidden files and directories
-        if path.components().any(|c| {
-            c.as_os_str()
-                .to_str()
-                .map(|s| s.starts_with('.'))
-                .unwrap_or(false)
-        }) {
-            return false;
-        }
-
-        // Skip build directories
-        let skip_dirs = ["build", "__pycache__", ".git", "node_modules", "venv", ".venv", "target"];
-        if path.components().any(|c| {
-            c.as_os_str()
-                .to_str()
-                .map(|s| skip_dirs.contains(&s))
-                .unwrap_or(false)
-        }) {
-            return false;
-        }
-
-        // Check file extension
-        if let Some(ext) = path.extension() {
-            let ext_str = format!(".{}", ext.to_string_lossy());
-            self.options.file_extensions.contains(&ext_str)
+    pub fn should_process(&self, path: &Path) -> bool {
+        self.options.fs.is_file(path) && self.skip_reason(path).is_none()
+    }
+
+    /// Returns why a file would be left alone, or `None` if it is processed
+    ///
+    /// Hidden files, build directories, unselected extensions and filtered files are
+    /// skipped, as are files whose indentation can't take the requested style.
+    pub fn skip_reason(&self, path: &Path) -> Option<SkipReason> {
+        let filter = &self.options.filter;
+        if let Some(reason) = filter.hidden_skip(path) {
+            return Some(reason);
+        }
+        if let Some(reason) = filter.dir_skip(path) {
+            return Some(reason);
+        }
+
+        if let Some(indent) = &self.options.indent {
+            if !indent_allowed(path, indent.style) {
+                return Some(SkipReason::IndentUnsafe);
+            }
+        }
+
+        filter
+            .extension_skip(path, &self.options.file_extensions)
+            .or_else(|| filter.skip_reason(path))
+    }
+
+    /// Whether the hard line breaks of `path` are left out of trailing whitespace
+    fn keeps_breaks(&self, path: &Path) -> bool {
+        self.options.hard_breaks != HardBreaks::Remove && is_markdown(path)
+    }
+
+    /// Applies the configured cleanups to line `number` (1-based)
+    ///
+    /// Lines outside `only` are still fed to the re-indenter, so bracket tracking
+    /// stays in step, but are returned unchanged. Lines listed in `breaks` end in a
+    /// Markdown hard line break.
+    fn clean_line<'a>(
+        &self,
+        number: usize,
+        line: &'a str,
+        reindenter: &mut Option<Reindenter>,
+        only: Option<&LineRanges>,
+        breaks: &[usize],
+    ) -> Cow<'a, str> {
+        let selected = only.is_none_or(|lines| lines.contains(number));
+        let trimmed = if self.options.remove_trailing && selected {
+            let text = line.trim_end();
+            if breaks.binary_search(&number).is_ok() {
+                self.hard_break(line, text)
+            } else {
+                Cow::Borrowed(text)
+            }
         } else {
-            false
+            Cow::Borrowed(line)
+        };
+
+        let cleaned = match (reindenter, trimmed) {
+            (Some(reindenter), Cow::Borrowed(trimmed)) => reindenter.line(trimmed),
+            (Some(reindenter), Cow::Owned(trimmed)) => {
+                Cow::Owned(reindenter.line(&trimmed).into_owned())
+            }
+            (None, trimmed) => trimmed,
+        };
+        if selected {
+            cleaned
+        } else {
+            Cow::Borrowed(line)
         }
     }
 
-    /// Removes trailing whitespace from a single file
-    pub fn clean_file(&self, path: &Path) -> crate::Result<usize> {
-        if !self.should_process(path) {
-            return Ok(0);
+    /// Ends `text`, the trimmed `line`, with its hard line break as configured
+    fn hard_break<'a>(&self, line: &'a str, text: &'a str) -> Cow<'a, str> {
+        match self.options.hard_breaks {
+            HardBreaks::Keep if &line[text.len()..] == "  " => Cow::Borrowed(line),
+            HardBreaks::Keep => Cow::Owned(format!("{}  ", text)),
+            HardBreaks::Backslash => Cow::Owned(format!("{}\\", text)),
+            HardBreaks::Remove => Cow::Borrowed(text),
+        }
+    }
+
+    /// The number of lines kept when trailing blank lines are trimmed: up to the last
+    /// one with text
+    fn kept_lines(&self, content: &str) -> Option<usize> {
+        if !self.options.trim_trailing_blank_lines {
+            return None;
         }
+        let last = lines_with_endings(content)
+            .enumerate()
+            .filter(|(_, (line, _))| !line.trim_end().is_empty())
+            .last();
+        Some(last.map_or(0, |(i, _)| i + 1))
+    }
 
-        let content = fs::read_to_string(path)?;
-        let lines: Vec<&str> = content.lines().collect();
-        let mut cleaned_lines = Vec::new();
-        let mut modified_count = 0;
+    /// The line ending added to a last line without one
+    fn final_newline(content: &str) -> &'static str {
+        match lines_with_endings(content).map(|(_, ending)| ending).next() {
+            Some("\r\n") => "\r\n",
+            _ => "\n",
+        }
+    }
 
-        for line in &lines {
-            if self.options.remove_trailing {
-                let cleaned = line.trim_end();
-                if cleaned != *line {
-                    modified_count += 1;
-                }
-                cleaned_lines.push(cleaned);
-            } else {
-                cleaned_lines.push(*line);
+    /// Counts the lines that would change, without allocating unless re-indenting or
+    /// rewriting hard line breaks
+    ///
+    /// Each trimmed blank line counts as a change, as does an added final newline.
+    /// With `markdown` set, hard line breaks are kept as configured.
+    fn count_changes(&self, content: &str, only: Option<&LineRanges>, markdown: bool) -> usize {
+        if !self.options.remove_trailing
+            && self.options.indent.is_none()
+            && !self.options.ensure_final_newline
+            && !self.options.trim_trailing_blank_lines
+        {
+            return 0;
+        }
+
+        let (_, content) = split_bom(content);
+        let kept = self.kept_lines(content);
+        let breaks = self.hard_breaks(content, markdown);
+        let mut reindenter = Reindenter::for_content(&self.options, content);
+        let mut changes = 0;
+        let mut unterminated = false;
+        for (i, (line, ending)) in lines_with_endings(content).enumerate() {
+            if kept.is_some_and(|kept| i >= kept) {
+                changes += 1;
+                continue;
+            }
+            let cleaned = self.clean_line(i + 1, line, &mut reindenter, only, &breaks);
+            if cleaned != line {
+                changes += 1;
             }
+            unterminated = ending.is_empty() && !cleaned.is_empty();
         }
+        if self.options.ensure_final_newline && unterminated {
+            changes += 1;
+        }
+        changes
+    }
 
-        // Check if file ends with newline
-        let ends_with_newline = content.ends_with('\n');
+    /// Writes the cleaned `content` into `out`, replacing its contents
+    fn clean_into(
+        &self,
+        content: &str,
+        only: Option<&LineRanges>,
+        markdown: bool,
+        out: &mut String,
+    ) {
+        out.clear();
+        out.reserve(content.len());
 
-        if modified_count > 0 {
-            if self.options.dry_run {
-                println!(
-                    "Would clean {} lines in '{}'",
-                    modified_count,
-                    path.display()
-                );
+        // Keep the byte order mark and each line's ending as they are
+        let (bom, content) = split_bom(content);
+        out.push_str(bom);
+        let kept = self.kept_lines(content);
+        let breaks = self.hard_breaks(content, markdown);
+        let mut reindenter = Reindenter::for_content(&self.options, content);
+        for (i, (line, ending)) in lines_with_endings(content).enumerate() {
+            if kept.is_some_and(|kept| i >= kept) {
+                break;
+            }
+            let cleaned = self.clean_line(i + 1, line, &mut reindenter, only, &breaks);
+            out.push_str(&cleaned);
+            if ending.is_empty() && !cleaned.is_empty() && self.options.ensure_final_newline {
+                out.push_str(Self::final_newline(content));
             } else {
-                let mut cleaned_content = cleaned_lines.join("\n");
-                if ends_with_newline {
-                    cleaned_content.push('\n');
+                out.push_str(ending);
+            }
+        }
+    }
+
+    /// The hard line breaks of `content`, if it is Markdown
+    fn hard_breaks(&self, content: &str, markdown: bool) -> Vec<usize> {
+        if markdown && self.options.remove_trailing {
+            hard_break_lines(content)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Removes trailing whitespace (and re-indents, if configured) in a string,
+    /// returning the new content and the number of lines that were modified
+    ///
+    /// The content is treated as plain text; see [`clean_path_str`](Self::clean_path_str)
+    /// for Markdown. Returns the input borrowed when no line changes.
+    pub fn clean_str<'a>(&self, content: &'a str) -> (Cow<'a, str>, usize) {
+        self.clean_lines_str(content, None)
+    }
+
+    /// Like [`clean_str`](Self::clean_str), but only touches the lines in `only`
+    pub fn clean_lines_str<'a>(
+        &self,
+        content: &'a str,
+        only: Option<&LineRanges>,
+    ) -> (Cow<'a, str>, usize) {
+        self.clean_text(content, only, false)
+    }
+
+    /// Like [`clean_str`](Self::clean_str), for the content of the file at `path`: the
+    /// hard line breaks of a Markdown file are kept as configured
+    pub fn clean_path_str<'a>(&self, path: &Path, content: &'a str) -> (Cow<'a, str>, usize) {
+        self.clean_text(content, None, self.keeps_breaks(path))
+    }
+
+    fn clean_text<'a>(
+        &self,
+        content: &'a str,
+        only: Option<&LineRanges>,
+        markdown: bool,
+    ) -> (Cow<'a, str>, usize) {
+        let modified_count = self.count_changes(content, only, markdown);
+        if modified_count == 0 {
+            return (Cow::Borrowed(content), 0);
+        }
+
+        let mut cleaned_content = String::new();
+        self.clean_into(content, only, markdown, &mut cleaned_content);
+        (Cow::Owned(cleaned_content), modified_count)
+    }
+
+    /// Removes trailing whitespace from a single file
+    ///
+    /// Files without trailing whitespace are detected by a scan that allocates nothing
+    /// beyond the read buffer; modified files are built in a per-thread output buffer
+    /// that is reused across files.
+    pub fn clean_file(&self, path: &Path) -> crate::Result<FileOutcome> {
+        let (outcome, diff) = self.apply_to_file(path)?;
+        self.report(path, outcome, diff.as_deref());
+        Ok(outcome)
+    }
+
+    /// Cleans a file without reporting, so it can run on a worker thread
+    fn apply_to_file(&self, path: &Path) -> crate::Result<(FileOutcome, Option<String>)> {
+        if !self.options.fs.is_file(path) {
+            return Ok((FileOutcome::unchanged(), None));
+        }
+        if let Some(reason) = self.skip_reason(path) {
+            return Ok((FileOutcome::skipped(reason), None));
+        }
+
+        let only = match &self.options.changed_lines {
+            Some(changed_lines) => match changed_lines.get(path) {
+                Some(lines) => Some(lines),
+                None => return Ok((FileOutcome::unchanged(), None)),
+            },
+            None => None,
+        };
+
+        let Some(content) = read_text(self.options.fs.as_ref(), path)? else {
+            return Ok((FileOutcome::skipped(SkipReason::Binary), None));
+        };
+        let markdown = self.keeps_breaks(path);
+        let modified_count = self.count_changes(&content, only, markdown);
+        if modified_count == 0 {
+            return Ok((FileOutcome::unchanged(), None));
+        }
+
+        let mut diff = None;
+        if !self.options.dry_run || self.options.show_diff {
+            OUTPUT_BUFFER.with(|buffer| {
+                let mut buffer = buffer.borrow_mut();
+                self.clean_into(&content, only, markdown, &mut buffer);
+                if self.options.show_diff {
+                    diff = Some(unified_diff(path, &content, &buffer, self.options.color_diff));
+                }
+                if self.options.dry_run {
+                    Ok(())
+                } else {
+                    self.options.fs.write(path, buffer.as_bytes())
                 }
-                fs::write(path, cleaned_content)?;
-                println!("Cleaned {} lines in '{}'", modified_count, path.display());
+            })?;
+        }
+
+        Ok((FileOutcome::changed(modified_count), diff))
+    }
+
+    pub(crate) fn report(&self, path: &Path, outcome: FileOutcome, diff: Option<&str>) {
+        if let Some(reason) = outcome.skipped {
+            if self.options.filter.reports(reason) {
+                report!("Skipped {} '{}'", reason, path.display());
             }
+            return;
+        }
+        if !outcome.changed {
+            return;
         }
 
-        Ok(modified_count)
+        let (would, did) = if self.options.indent.is_some() {
+            ("re-indent", "Re-indented")
+        } else {
+            ("clean", "Cleaned")
+        };
+
+        self.options.events.file_changed(path, outcome.changes, self.options.dry_run);
+        if self.options.dry_run {
+            report!(
+                "Would {} {} lines in '{}'",
+                would,
+                outcome.changes,
+                path.display()
+            );
+        } else {
+            report!("{} {} lines in '{}'", did, outcome.changes, path.display());
+        }
+
+        if let Some(diff) = diff {
+            report!("{}", diff.trim_end());
+        }
     }
 
     /// Processes a directory or file
@@ -142,136 +494,334 @@ impl WhitespaceCleaner {
         let mut total_files = 0;
         let mut total_lines = 0;
 
-        if path.is_file() {
-            let lines = self.clean_file(path)?;
-            if lines > 0 {
+        if self.options.fs.is_file(path) {
+            let outcome = self.clean_file(path)?;
+            if outcome.changed {
                 total_files = 1;
-                total_lines = lines;
+                total_lines = outcome.changes;
             }
-        } else if path.is_dir() {
-            if self.options.recursive {
-                for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
-                    if entry.file_type().is_file() {
-                        let lines = self.clean_file(entry.path())?;
-                        if lines > 0 {
-                            total_files += 1;
-                            total_lines += lines;
-                        }
-                    }
-                }
-            } else {
-                for entry in fs::read_dir(path)? {
-                    let entry = entry?;
-                    let entry_path = entry.path();
-                    if entry_path.is_file() {
-                        let lines = self.clean_file(&entry_path)?;
-                        if lines > 0 {
-                            total_files += 1;
-                            total_lines += lines;
-                        }
+        } else if self.options.fs.is_dir(path) {
+            let files = self.walker.walk(
+                self.options.fs.as_ref(),
+                path,
+                self.options.recursive,
+                self.options.walk,
+            )?;
+            let results = parallel::map_files_with_progress(
+                self.options.fs.as_ref(),
+                &files,
+                &self.options.progress,
+                |file| self.should_process(file),
+                |file| self.apply_to_file(file),
+            );
+
+            // Report in path order once the parallel work is done
+            for (file_path, result) in files.iter().zip(results) {
+                let (outcome, diff) = match result {
+                    Ok(result) => result,
+                    Err(e) => {
+                        self.failed.record(file_path, e, self.options.walk.errors, &self.options.events)?;
+                        continue;
                     }
+                };
+                self.report(file_path, outcome, diff.as_deref());
+                if outcome.changed {
+                    total_files += 1;
+                    total_lines += outcome.changes;
                 }
             }
         }
 
         Ok((total_files, total_lines))
     }
+
+    /// Returns the entries its walks couldn't read since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_walk_errors(&self) -> Vec<TraversalError> {
+        self.walker.take_errors()
+    }
+
+    /// Returns the files that couldn't be processed since the last call, under
+    /// [`ErrorPolicy::Warn`](crate::walker::ErrorPolicy::Warn)
+    pub fn take_failed_files(&self) -> Vec<FailedFile> {
+        self.failed.take()
+    }
+}
+
+/// Whether re-indenting with `style` is safe for the file's language
+///
+/// Makefile recipes must start with a tab and YAML forbids tabs in indentation.
+fn indent_allowed(path: &Path, style: IndentStyle) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let is_makefile = name == "makefile" || name == "gnumakefile" || name.ends_with(".mk");
+    let is_yaml = name.ends_with(".yml") || name.ends_with(".yaml");
+
+    match style {
+        IndentStyle::Spaces => !is_makefile,
+        IndentStyle::Tabs => !is_yaml,
+    }
+}
+
+/// Detects the indentation width of space-indented content
+///
+/// Uses the most common increase in indentation between consecutive lines, ignoring
+/// single-space steps (usually alignment). Returns `None` if there are no such steps.
+fn detect_indent_width(content: &str) -> Option<usize> {
+    let mut counts = [0usize; 9];
+    let mut previous = 0;
+
+    for line in content.lines() {
+        let body = line.trim_start_matches([' ', '\t']);
+        if body.is_empty() {
+            continue;
+        }
+        let leading = &line[..line.len() - body.len()];
+        if leading.contains('\t') {
+            continue;
+        }
+
+        let width = leading.len();
+        if width > previous && width - previous < counts.len() {
+            counts[width - previous] += 1;
+        }
+        previous = width;
+    }
+
+    (2..counts.len())
+        .filter(|&w| counts[w] > 0)
+        .max_by_key(|&w| (counts[w], std::cmp::Reverse(w)))
+}
+
+/// Re-indentation state carried from line to line within one file
+///
+/// Whole indentation levels are converted. Lines inside open `(`/`[` brackets or
+/// after a `\` continuation keep their offset from the line that started the
+/// continuation, so arguments aligned to an opening bracket stay aligned; columns past
+/// the last level (` * ` in block comments) are kept as spaces.
+struct Reindenter<'o> {
+    indent: &'o IndentOptions,
+    /// Spaces per indentation level in the input
+    source_width: usize,
+    /// Open brackets carried over from previous lines
+    depth: usize,
+    /// Whether the previous line ended in a `\`
+    escaped: bool,
+    /// Original column of the line that started the current continuation
+    anchor: usize,
+}
+
+impl<'o> Reindenter<'o> {
+    fn for_content(options: &'o WhitespaceOptions, content: &str) -> Option<Self> {
+        let indent = options.indent.as_ref()?;
+        let source_width = indent
+            .source_width
+            .or_else(|| detect_indent_width(content))
+            .unwrap_or(indent.width)
+            .max(1);
+
+        Some(Reindenter {
+            indent,
+            source_width,
+            depth: 0,
+            escaped: false,
+            anchor: 0,
+        })
+    }
+
+    /// Column reached by leading whitespace, expanding tabs to the source width
+    fn columns(&self, leading: &str) -> usize {
+        leading.chars().fold(0, |columns, c| {
+            if c == '\t' {
+                (columns / self.source_width + 1) * self.source_width
+            } else {
+                columns + 1
+            }
+        })
+    }
+
+    /// New leading whitespace for a line indented to `columns` in the input
+    fn leading_for(&self, columns: usize) -> String {
+        let levels = columns / self.source_width;
+        let align = columns % self.source_width;
+
+        match self.indent.style {
+            IndentStyle::Spaces => " ".repeat(levels * self.indent.width + align),
+            IndentStyle::Tabs => format!("{}{}", "\t".repeat(levels), " ".repeat(align)),
+        }
+    }
+
+    fn line<'a>(&mut self, line: &'a str) -> Cow<'a, str> {
+        let body = line.trim_start_matches([' ', '\t']);
+        if body.is_empty() {
+            return Cow::Borrowed(line);
+        }
+        let leading = &line[..line.len() - body.len()];
+        let columns = self.columns(leading);
+
+        let continuation = self.depth > 0 || self.escaped;
+        let new_leading = if continuation && columns > self.anchor {
+            format!("{}{}", self.leading_for(self.anchor), " ".repeat(columns - self.anchor))
+        } else {
+            self.leading_for(columns)
+        };
+
+        if !continuation {
+            self.anchor = columns;
+        }
+        self.depth = bracket_depth(body, self.depth);
+        self.escaped = body.ends_with('\\');
+
+        if new_leading == leading {
+            Cow::Borrowed(line)
+        } else {
+            Cow::Owned(format!("{}{}", new_leading, body))
+        }
+    }
+}
+
+/// Updates the count of open `(`/`[` brackets with one line of code
+///
+/// Brackets inside double-quoted strings and after `//` or `#` comments are ignored.
+fn bracket_depth(code: &str, mut depth: usize) -> usize {
+    let mut in_string = false;
+    let mut chars = code.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_string => {
+                chars.next();
+            }
+            '"' => in_string = !in_string,
+            '/' if !in_string && chars.peek() == Some(&'/') => break,
+            '#' if !in_string => break,
+            '(' | '[' if !in_string => depth += 1,
+            ')' | ']' if !in_string => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    depth
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
+    use crate::vfs::MemoryFs;
+    use std::path::PathBuf;
+
+    /// Default options on the in-memory filesystem `fs`
+    fn options_on(fs: &Arc<MemoryFs>) -> WhitespaceOptions {
+        WhitespaceOptions {
+            fs: fs.clone(),
+            ..WhitespaceOptions::default()
+        }
+    }
 
     #[test]
     fn test_remove_trailing_whitespace() {
-        let test_dir = std::env::temp_dir().join("refmt_whitespace_test");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("whitespace_test");
 
         let test_file = test_dir.join("test.txt");
-        fs::write(&test_file, "line1   \nline2\t\nline3\n").unwrap();
+        fs.add_file(&test_file, "line1   \nline2\t\nline3\n");
 
-        let cleaner = WhitespaceCleaner::with_defaults();
+        let cleaner = WhitespaceCleaner::new(options_on(&fs));
         let (files, lines) = cleaner.process(&test_file).unwrap();
 
         assert_eq!(files, 1);
         assert_eq!(lines, 2); // line1 and line2 had trailing whitespace
 
-        let content = fs::read_to_string(&test_file).unwrap();
+        let content = fs.read_to_string(&test_file).unwrap();
         assert_eq!(content, "line1\nline2\nline3\n");
+    }
 
-        fs::remove_dir_all(&test_dir).unwrap();
+    #[test]
+    fn test_clean_only_selected_lines() {
+        let cleaner = WhitespaceCleaner::with_defaults();
+        let only: LineRanges = [2..=3].into_iter().collect();
+
+        let (result, lines) = cleaner.clean_lines_str("a  \nb  \nc\t\nd \n", Some(&only));
+        assert_eq!(result, "a  \nb\nc\nd \n");
+        assert_eq!(lines, 2);
     }
 
     #[test]
     fn test_preserve_line_endings() {
-        let test_dir = std::env::temp_dir().join("refmt_whitespace_endings");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("whitespace_endings");
 
         let test_file = test_dir.join("test.txt");
-        fs::write(&test_file, "line1  \nline2\n").unwrap();
+        fs.add_file(&test_file, "line1  \nline2\n");
 
-        let cleaner = WhitespaceCleaner::with_defaults();
+        let cleaner = WhitespaceCleaner::new(options_on(&fs));
         cleaner.process(&test_file).unwrap();
 
-        let content = fs::read_to_string(&test_file).unwrap();
+        let content = fs.read_to_string(&test_file).unwrap();
         assert!(content.ends_with('\n'));
         assert_eq!(content, "line1\nline2\n");
 
-        fs::remove_dir_all(&test_dir).unwrap();
+        // CRLF, mixed endings and a byte order mark are kept
+        fs.add_file(&test_file, "\u{FEFF}line1  \r\nline2\t\r\nline3\nlast \r");
+        assert_eq!(cleaner.process(&test_file).unwrap(), (1, 3));
+        assert_eq!(
+            fs.read_to_string(&test_file).unwrap(),
+            "\u{FEFF}line1\r\nline2\r\nline3\nlast"
+        );
+        let (result, lines) = cleaner.clean_str("a\r\nb\r\n");
+        assert!(matches!(result, Cow::Borrowed(_)));
+        assert_eq!(lines, 0);
     }
 
     #[test]
     fn test_dry_run_mode() {
-        let test_dir = std::env::temp_dir().join("refmt_whitespace_dry");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("whitespace_dry");
 
         let test_file = test_dir.join("test.txt");
         let original = "line1   \nline2\n";
-        fs::write(&test_file, original).unwrap();
+        fs.add_file(&test_file, original);
 
-        let mut opts = WhitespaceOptions::default();
+        let mut opts = options_on(&fs);
         opts.dry_run = true;
 
         let cleaner = WhitespaceCleaner::new(opts);
         cleaner.process(&test_file).unwrap();
 
         // File should be unchanged
-        let content = fs::read_to_string(&test_file).unwrap();
+        let content = fs.read_to_string(&test_file).unwrap();
         assert_eq!(content, original);
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 
     #[test]
     fn test_skip_hidden_files() {
-        let test_dir = std::env::temp_dir().join("refmt_whitespace_hidden");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("whitespace_hidden");
 
         let hidden_file = test_dir.join(".hidden.txt");
-        fs::write(&hidden_file, "line1   \n").unwrap();
+        fs.add_file(&hidden_file, "line1   \n");
 
-        let cleaner = WhitespaceCleaner::with_defaults();
+        let cleaner = WhitespaceCleaner::new(options_on(&fs));
         let (files, _) = cleaner.process(&hidden_file).unwrap();
 
         // Hidden file should be skipped
         assert_eq!(files, 0);
-
-        fs::remove_dir_all(&test_dir).unwrap();
     }
 
     #[test]
     fn test_file_extension_filtering() {
-        let test_dir = std::env::temp_dir().join("refmt_whitespace_ext");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("whitespace_ext");
 
         let txt_file = test_dir.join("test.txt");
         let other_file = test_dir.join("test.xyz");
 
-        fs::write(&txt_file, "line1   \n").unwrap();
-        fs::write(&other_file, "line1   \n").unwrap();
+        fs.add_file(&txt_file, "line1   \n");
+        fs.add_file(&other_file, "line1   \n");
 
-        let mut opts = WhitespaceOptions::default();
+        let mut opts = options_on(&fs);
         opts.file_extensions = vec![".txt".to_string()];
 
         let cleaner = WhitespaceCleaner::new(opts);
@@ -280,35 +830,237 @@ mod tests {
         // Only .txt should be processed
         assert_eq!(files, 1);
 
-        let txt_content = fs::read_to_string(&txt_file).unwrap();
-        let other_content = fs::read_to_string(&other_file).unwrap();
+        let txt_content = fs.read_to_string(&txt_file).unwrap();
+        let other_content = fs.read_to_string(&other_file).unwrap();
 
         assert_eq!(txt_content, "line1\n");
         assert_eq!(other_content, "line1   \n"); // Unchanged
+    }
+
+    #[test]
+    fn test_buffer_reuse_across_files() {
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("whitespace_buffer");
+
+        // A long file followed by a short one: the reused buffer must not leak content
+        let long_file = test_dir.join("a_long.txt");
+        let short_file = test_dir.join("b_short.txt");
+        let clean_file = test_dir.join("c_clean.txt");
+        fs.add_file(&long_file, "first line   \nsecond line\t\nthird line\n");
+        fs.add_file(&short_file, "x \n");
+        fs.add_file(&clean_file, "already clean\n");
+
+        let cleaner = WhitespaceCleaner::new(options_on(&fs));
+        assert_eq!(cleaner.clean_file(&long_file).unwrap(), FileOutcome::changed(2));
+        assert_eq!(cleaner.clean_file(&short_file).unwrap(), FileOutcome::changed(1));
+        assert_eq!(cleaner.clean_file(&clean_file).unwrap(), FileOutcome::unchanged());
+
+        assert_eq!(
+            fs.read_to_string(&long_file).unwrap(),
+            "first line\nsecond line\nthird line\n"
+        );
+        assert_eq!(fs.read_to_string(&short_file).unwrap(), "x\n");
+        assert_eq!(fs.read_to_string(&clean_file).unwrap(), "already clean\n");
+    }
+
+    fn reindenter(style: IndentStyle, width: usize) -> WhitespaceCleaner {
+        let opts = WhitespaceOptions {
+            remove_trailing: false,
+            indent: Some(IndentOptions {
+                style,
+                width,
+                source_width: None,
+            }),
+            ..WhitespaceOptions::default()
+        };
+        WhitespaceCleaner::new(opts)
+    }
+
+    #[test]
+    fn test_reindent_width_change() {
+        let cleaner = reindenter(IndentStyle::Spaces, 4);
+        let content = "def f(a,\n      b):\n  if a:\n    return b\n";
+        let (result, changed) = cleaner.clean_str(content);
+
+        // The argument stays aligned with the opening bracket
+        assert_eq!(result, "def f(a,\n      b):\n    if a:\n        return b\n");
+        assert_eq!(changed, 2);
+
+        // Hanging indents inside brackets keep their offset too
+        let content = "x = f(\n      a,\n      b)\ny = 1\n";
+        let (result, _) = cleaner.clean_str(content);
+        assert!(matches!(result, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_reindent_tabs_and_spaces() {
+        let to_spaces = reindenter(IndentStyle::Spaces, 4);
+        let (result, _) = to_spaces.clean_str("fn f() {\n\tif x {\n\t\ty();\n\t}\n}\n");
+        assert_eq!(result, "fn f() {\n    if x {\n        y();\n    }\n}\n");
+
+        let to_tabs = reindenter(IndentStyle::Tabs, 4);
+        let (back, _) = to_tabs.clean_str(&result);
+        assert_eq!(back, "fn f() {\n\tif x {\n\t\ty();\n\t}\n}\n");
+
+        // Block comment decoration stays aligned
+        let (comment, _) = to_tabs.clean_str("    /*\n     * text\n     */\n");
+        assert_eq!(comment, "\t/*\n\t * text\n\t */\n");
 
-        fs::remove_dir_all(&test_dir).unwrap();
+        // Already indented as requested
+        assert!(matches!(to_tabs.clean_str(&back).0, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_expand_tabs_and_unexpand() {
+        let options = WhitespaceOptions {
+            indent: Some(IndentOptions::expand_tabs(2)),
+            ..WhitespaceOptions::default()
+        };
+        let (result, changed) = WhitespaceCleaner::new(options).clean_str("a\n\tb \n\t\tc\n");
+        assert_eq!(result, "a\n  b\n    c\n");
+        assert_eq!(changed, 2);
+
+        // The width is given, not detected, and odd columns stay spaces
+        let options = WhitespaceOptions {
+            indent: Some(IndentOptions::unexpand(2)),
+            ..WhitespaceOptions::default()
+        };
+        let (result, _) = WhitespaceCleaner::new(options).clean_str("a\n    b\n     c\n");
+        assert_eq!(result, "a\n\t\tb\n\t\t c\n");
+    }
+
+    #[test]
+    fn test_file_end() {
+        let options = WhitespaceOptions {
+            ensure_final_newline: true,
+            trim_trailing_blank_lines: true,
+            ..WhitespaceOptions::default()
+        };
+        let cleaner = WhitespaceCleaner::new(options);
+
+        assert_eq!(cleaner.clean_str("a\nb"), (Cow::Owned("a\nb\n".to_string()), 1));
+        assert_eq!(
+            cleaner.clean_str("a\r\nb \r\n\r\n  \r\n\t"),
+            (Cow::Owned("a\r\nb\r\n".to_string()), 4)
+        );
+        assert_eq!(cleaner.clean_str("\n \n").0, "");
+        assert!(matches!(cleaner.clean_str("a\n").0, Cow::Borrowed(_)));
+        assert!(matches!(cleaner.clean_str("").0, Cow::Borrowed(_)));
+
+        // Each option applies on its own
+        let options = WhitespaceOptions {
+            ensure_final_newline: true,
+            ..WhitespaceOptions::default()
+        };
+        assert_eq!(WhitespaceCleaner::new(options).clean_str("a\n\nb").0, "a\n\nb\n");
+        let options = WhitespaceOptions {
+            trim_trailing_blank_lines: true,
+            ..WhitespaceOptions::default()
+        };
+        assert_eq!(WhitespaceCleaner::new(options).clean_str("a\n\nb\n\n").0, "a\n\nb\n");
+        assert_eq!(WhitespaceCleaner::with_defaults().clean_str("a\n\n").1, 0);
+    }
+
+    #[test]
+    fn test_markdown_hard_breaks() {
+        let fs = Arc::new(MemoryFs::new());
+        let content = "one  \ntwo   \nlast  \n\n# Title  \n```\ncode  \n```\n";
+        fs.add_file("notes.md", content);
+        fs.add_file("notes.txt", content);
+
+        let cleaner = WhitespaceCleaner::new(options_on(&fs));
+        assert_eq!(cleaner.process(Path::new("notes.md")).unwrap(), (1, 4));
+        assert_eq!(
+            fs.read_to_string(Path::new("notes.md")).unwrap(),
+            "one  \ntwo  \nlast\n\n# Title\n```\ncode\n```\n"
+        );
+        cleaner.process(Path::new("notes.txt")).unwrap();
+        assert_eq!(
+            fs.read_to_string(Path::new("notes.txt")).unwrap(),
+            "one\ntwo\nlast\n\n# Title\n```\ncode\n```\n"
+        );
+
+        let options = WhitespaceOptions {
+            hard_breaks: HardBreaks::Backslash,
+            ..WhitespaceOptions::default()
+        };
+        let cleaner = WhitespaceCleaner::new(options);
+        let path = Path::new("notes.qmd");
+        assert_eq!(
+            cleaner.clean_path_str(path, "a  \r\nb\r\n").0,
+            "a\\\r\nb\r\n"
+        );
+        assert_eq!(cleaner.clean_str("a  \nb\n").0, "a\nb\n");
+
+        let options = WhitespaceOptions {
+            hard_breaks: HardBreaks::Remove,
+            ..WhitespaceOptions::default()
+        };
+        let cleaner = WhitespaceCleaner::new(options);
+        assert_eq!(
+            cleaner.clean_path_str(path, "a  \nb\n"),
+            (Cow::Owned("a\nb\n".to_string()), 1)
+        );
+    }
+
+    #[test]
+    fn test_indent_allowed() {
+        assert!(!indent_allowed(Path::new("Makefile"), IndentStyle::Spaces));
+        assert!(indent_allowed(Path::new("Makefile"), IndentStyle::Tabs));
+        assert!(!indent_allowed(Path::new("ci.yml"), IndentStyle::Tabs));
+        assert!(indent_allowed(Path::new("main.py"), IndentStyle::Spaces));
     }
 
     #[test]
     fn test_recursive_processing() {
-        let test_dir = std::env::temp_dir().join("refmt_whitespace_recursive");
-        fs::create_dir_all(&test_dir).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("whitespace_recursive");
 
         let sub_dir = test_dir.join("subdir");
-        fs::create_dir_all(&sub_dir).unwrap();
 
         let file1 = test_dir.join("file1.txt");
         let file2 = sub_dir.join("file2.txt");
 
-        fs::write(&file1, "line1   \n").unwrap();
-        fs::write(&file2, "line2\t\n").unwrap();
+        fs.add_file(&file1, "line1   \n");
+        fs.add_file(&file2, "line2\t\n");
 
-        let cleaner = WhitespaceCleaner::with_defaults();
+        let cleaner = WhitespaceCleaner::new(options_on(&fs));
         let (files, lines) = cleaner.process(&test_dir).unwrap();
 
         assert_eq!(files, 2);
         assert_eq!(lines, 2);
+    }
+
+    #[test]
+    fn test_progress_counts_matching_files() {
+        use crate::progress::ProgressEvent;
+        use std::sync::{Arc, Mutex};
+
+        let fs = Arc::new(MemoryFs::new());
+        let test_dir = PathBuf::from("whitespace_progress");
+        fs.add_file(test_dir.join("a.txt"), "a  \n");
+        fs.add_file(test_dir.join("b.txt"), "b\n");
+        fs.add_file(test_dir.join("image.png"), "png");
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut options = options_on(&fs);
+        options.progress = {
+            let events = events.clone();
+            Progress::new(move |event| {
+                let event = match event {
+                    ProgressEvent::Started { total } => format!("started {}", total),
+                    ProgressEvent::FileDone { path } => {
+                        format!("done {}", path.file_name().unwrap().to_string_lossy())
+                    }
+                };
+                events.lock().unwrap().push(event);
+            })
+        };
+        WhitespaceCleaner::new(options).process(&test_dir).unwrap();
 
-        fs::remove_dir_all(&test_dir).unwrap();
+        let mut events = events.lock().unwrap().clone();
+        assert_eq!(events.remove(0), "started 2");
+        events.sort();
+        assert_eq!(events, ["done a.txt", "done b.txt"]);
     }
 }
@@ -236,7 +236,7 @@ fn test_library_word_filter() {
 #[test]
 fn test_library_all_case_formats() {
     // Test conversion between all major formats
-    let test_cases = vec![
+    let test_cases = [
         (CaseFormat::CamelCase, CaseFormat::SnakeCase, "firstName", "first_name"),
         (CaseFormat::SnakeCase, CaseFormat::CamelCase, "first_name", "firstName"),
         (CaseFormat::PascalCase, CaseFormat::KebabCase, "FirstName", "first-name"),